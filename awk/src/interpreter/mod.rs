@@ -206,7 +206,7 @@ fn builtin_match(stack: &mut Stack, global_env: &mut GlobalEnv) -> Result<(f64,
     let string = stack
         .pop_scalar_value()?
         .scalar_to_string(&global_env.convfmt)?;
-    let mut locations = ere.match_locations(string.try_into()?);
+    let mut locations = ere.match_locations(&string);
     let start;
     let len;
     if let Some(first_match) = locations.next() {
@@ -256,7 +256,7 @@ fn gsub(
     repl_parts.push(current_repl_part);
 
     let mut num_replacements = 0;
-    for m in ere.match_locations(AwkString::from(in_str).try_into()?) {
+    for m in ere.match_locations(in_str) {
         result.push_str(&in_str[last_match_end..m.start]);
         let replaced_string = &in_str[m.start..m.end];
         result.push_str(&repl_parts[0]);
@@ -467,7 +467,7 @@ fn split_record<S: FnMut(usize, AwkString) -> Result<(), String>>(
         FieldSeparator::Ere(re) => {
             let mut split_start = 0;
             let mut index = 0;
-            for separator_range in re.match_locations(record.clone().try_into()?) {
+            for separator_range in re.match_locations(&record) {
                 store_result(index, string(&record[split_start..separator_range.start]))?;
                 split_start = separator_range.end;
                 index += 1;
@@ -486,7 +486,7 @@ impl TryFrom<AwkString> for FieldSeparator {
         } else if value.len() == 1 {
             Ok(FieldSeparator::Char(*value.as_bytes().first().unwrap()))
         } else {
-            let ere = Regex::new(value.try_into()?)?;
+            let ere = Regex::new(&value)?;
             Ok(FieldSeparator::Ere(Rc::from(ere)))
         }
     }
@@ -550,7 +550,7 @@ enum FieldsState {
 }
 
 struct Record {
-    record: RefCell<CString>,
+    record: RefCell<AwkString>,
     fields: Vec<AwkValueRef>,
     last_field: RefCell<usize>,
 }
@@ -575,7 +575,7 @@ impl Record {
             }
         }
         *self.fields[0].get_mut() = AwkValue::field_ref(record.clone(), 0);
-        *self.record.get_mut() = record.try_into()?;
+        *self.record.get_mut() = record;
         *self.last_field.get_mut() = last_field;
         Ok(())
     }
@@ -620,7 +620,7 @@ impl Record {
         // mark it as a numeric string if appropriate
         let record_str = maybe_numeric_string(new_record);
         *self.fields[0].get() = AwkValue::field_ref(record_str.clone(), 0);
-        *self.record.borrow_mut() = record_str.try_into()?;
+        *self.record.borrow_mut() = record_str;
         *self.last_field.borrow_mut() = last_field;
         Ok(())
     }
@@ -640,7 +640,7 @@ impl Record {
         })
         .expect("error splitting record");
         *self.fields[0].get() = AwkValue::field_ref(record_str.clone(), 0);
-        *self.record.borrow_mut() = record_str.try_into()?;
+        *self.record.borrow_mut() = record_str;
         *self.last_field.borrow_mut() = last_field;
         Ok(())
     }
@@ -660,7 +660,7 @@ impl Default for Record {
             })
             .collect();
         Self {
-            record: CString::default().into(),
+            record: AwkString::default().into(),
             fields,
             last_field: 0.into(),
         }
@@ -830,7 +830,7 @@ impl AwkValue {
         }
     }
 
-    fn from_ere(ere: Rc<Regex>, record: &CString) -> Self {
+    fn from_ere(ere: Rc<Regex>, record: &str) -> Self {
         let matches_record = ere.matches(record);
         Self {
             value: AwkValueVariant::Regex {
@@ -1350,7 +1350,7 @@ impl Interpreter {
                     let string = stack
                         .pop_scalar_value()?
                         .scalar_to_string(&global_env.convfmt)?;
-                    let result = ere.matches(&string.try_into()?);
+                    let result = ere.matches(&string);
                     stack.push_value(bool_to_f64(result))?;
                 }
                 OpCode::Concat => {
@@ -3062,9 +3062,7 @@ mod tests {
         ];
         let constant = vec![
             Constant::from("hello"),
-            Constant::Regex(Rc::new(
-                Regex::new(CString::new("e").unwrap()).expect("failed to compile regex"),
-            )),
+            Constant::Regex(Rc::new(Regex::new("e").expect("failed to compile regex"))),
         ];
         assert_eq!(interpret_expr(instructions, constant), AwkValue::from(1.0));
     }
@@ -7,36 +7,11 @@
 // SPDX-License-Identifier: MIT
 //
 
-use std::ffi::CString;
-use std::ptr;
-
-fn regex_compilation_result(
-    status_integer: libc::c_int,
-    regex: &libc::regex_t,
-) -> Result<(), String> {
-    if status_integer != 0 {
-        let mut error_buffer = vec![b'\0'; 128];
-        unsafe {
-            libc::regerror(
-                status_integer,
-                ptr::from_ref(regex),
-                error_buffer.as_mut_ptr() as *mut libc::c_char,
-                128,
-            )
-        };
-        let error = CString::from_vec_with_nul(error_buffer)
-            .expect("error message returned from `libc::regerror` is an invalid CString");
-        Err(error
-            .into_string()
-            .expect("error message from `libc::regerror' contains invalid utf-8"))
-    } else {
-        Ok(())
-    }
-}
+use plib::regex::{Matches, RegexFlags};
 
 pub struct Regex {
-    raw_regex: libc::regex_t,
-    regex_string: CString,
+    regex: plib::regex::Regex,
+    regex_string: String,
 }
 
 #[cfg_attr(test, derive(Debug))]
@@ -46,89 +21,45 @@ pub struct RegexMatch {
     pub end: usize,
 }
 
-pub struct MatchIter<'re> {
-    string: CString,
-    next_start: usize,
-    regex: &'re Regex,
+pub struct MatchIter<'re, 's> {
+    matches: Matches<'re, 's>,
 }
 
-impl Iterator for MatchIter<'_> {
+impl Iterator for MatchIter<'_, '_> {
     type Item = RegexMatch;
     fn next(&mut self) -> Option<Self::Item> {
-        if self.next_start >= self.string.as_bytes().len() {
-            return None;
-        }
-        let mut match_range = libc::regmatch_t {
-            rm_so: -1,
-            rm_eo: -1,
-        };
-        let exec_status = unsafe {
-            libc::regexec(
-                ptr::from_ref(&self.regex.raw_regex),
-                self.string.as_ptr().add(self.next_start),
-                1,
-                ptr::from_mut(&mut match_range),
-                0,
-            )
-        };
-        if exec_status == libc::REG_NOMATCH {
-            return None;
-        }
-        let result = RegexMatch {
-            start: self.next_start + match_range.rm_so as usize,
-            end: self.next_start + match_range.rm_eo as usize,
-        };
-        self.next_start += match_range.rm_eo as usize;
-        Some(result)
+        self.matches.next().map(|range| RegexMatch {
+            start: range.start,
+            end: range.end,
+        })
     }
 }
 
 impl Regex {
-    pub fn new(regex: CString) -> Result<Self, String> {
-        let mut raw = unsafe { std::mem::zeroed::<libc::regex_t>() };
-        let compilation_status =
-            unsafe { libc::regcomp(ptr::from_mut(&mut raw), regex.as_ptr(), libc::REG_EXTENDED) };
-        regex_compilation_result(compilation_status, &raw)?;
+    pub fn new(regex: &str) -> Result<Self, String> {
+        let compiled = plib::regex::Regex::new(regex.as_bytes(), RegexFlags::ere())
+            .map_err(|e| e.to_string())?;
         Ok(Self {
-            raw_regex: raw,
-            regex_string: regex,
+            regex: compiled,
+            regex_string: regex.to_string(),
         })
     }
 
-    pub fn match_locations(&self, string: CString) -> MatchIter {
+    pub fn match_locations<'re, 's>(&'re self, string: &'s str) -> MatchIter<'re, 's> {
         MatchIter {
-            next_start: 0,
-            regex: self,
-            string,
+            matches: self.regex.find_iter(string.as_bytes()),
         }
     }
 
-    pub fn matches(&self, string: &CString) -> bool {
-        let exec_status = unsafe {
-            libc::regexec(
-                ptr::from_ref(&self.raw_regex),
-                string.as_ptr(),
-                0,
-                ptr::null_mut(),
-                0,
-            )
-        };
-        exec_status != libc::REG_NOMATCH
-    }
-}
-
-impl Drop for Regex {
-    fn drop(&mut self) {
-        unsafe {
-            libc::regfree(ptr::from_mut(&mut self.raw_regex));
-        }
+    pub fn matches(&self, string: &str) -> bool {
+        self.regex.is_match(string.as_bytes())
     }
 }
 
 #[cfg(test)]
 impl core::fmt::Debug for Regex {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
-        writeln!(f, "/{}/", self.regex_string.to_str().unwrap())
+        writeln!(f, "/{}/", self.regex_string)
     }
 }
 
@@ -141,7 +72,7 @@ impl PartialEq for Regex {
 /// utility function for writing tests
 #[cfg(test)]
 pub fn regex_from_str(re: &str) -> Regex {
-    Regex::new(CString::new(re).unwrap()).expect("error compiling ere")
+    Regex::new(re).expect("error compiling ere")
 }
 
 #[cfg(test)]
@@ -149,27 +80,32 @@ mod tests {
     use super::*;
 
     #[test]
-    #[cfg_attr(miri, ignore)]
     fn test_create_regex() {
         regex_from_str("test");
     }
 
     #[test]
-    #[cfg_attr(miri, ignore)]
     fn test_regex_matches() {
         let ere = regex_from_str("ab*c");
-        assert!(ere.matches(&CString::new("abbbbc").unwrap()));
+        assert!(ere.matches("abbbbc"));
     }
 
     #[test]
-    #[cfg_attr(miri, ignore)]
     fn test_regex_match_locations() {
         let ere = regex_from_str("match");
-        let mut iter = ere.match_locations(CString::new("match 12345 match2 matchmatch").unwrap());
+        let mut iter = ere.match_locations("match 12345 match2 matchmatch");
         assert_eq!(iter.next(), Some(RegexMatch { start: 0, end: 5 }));
         assert_eq!(iter.next(), Some(RegexMatch { start: 12, end: 17 }));
         assert_eq!(iter.next(), Some(RegexMatch { start: 19, end: 24 }));
         assert_eq!(iter.next(), Some(RegexMatch { start: 24, end: 29 }));
         assert_eq!(iter.next(), None);
     }
+
+    #[test]
+    fn test_regex_match_locations_anchored() {
+        let ere = regex_from_str("^a");
+        let mut iter = ere.match_locations("aaa");
+        assert_eq!(iter.next(), Some(RegexMatch { start: 0, end: 1 }));
+        assert_eq!(iter.next(), None);
+    }
 }
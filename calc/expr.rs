@@ -8,7 +8,7 @@
 //
 
 use gettextrs::{bind_textdomain_codeset, setlocale, textdomain, LocaleCategory};
-use regex::Regex;
+use plib::regex::{MatchFlags, Regex, RegexFlags};
 
 #[derive(Clone, Debug, PartialEq)]
 enum Token {
@@ -238,35 +238,34 @@ fn logop(lhs: &Token, rhs: &Token, is_and: bool) -> Token {
     }
 }
 
-// regex match operation
+// regex match operation; the BRE is anchored at the first character
 fn matchop(lhs: &Token, rhs: &Token) -> Result<Token, &'static str> {
     let lhs = token_to_string(lhs)?;
     let rhs = token_to_string(rhs)?;
 
-    let re = match Regex::new(&rhs) {
+    let re = match Regex::new(rhs.as_bytes(), RegexFlags::bre()) {
         Ok(re_res) => re_res,
         Err(_) => {
             return Err("invalid regex");
         }
     };
 
-    match re.captures(&lhs) {
-        // no regex match: zero
-        None => Ok(Token::Integer(0)),
-
-        // regex matched
-        Some(caps) => {
-            let cap1 = caps.get(1);
-
-            // if regex subexpression #1 matched, return as string
-            if let Some(mtch) = cap1 {
-                Ok(Token::Str(String::from(mtch.as_str())))
-
-            // otherwise, return length of overall match as int
-            } else {
-                Ok(Token::Integer(caps.get(0).unwrap().len() as i64))
-            }
-        }
+    // leftmost match must begin at the start of the string
+    let caps = re
+        .exec(lhs.as_bytes(), MatchFlags::default())
+        .filter(|caps| caps[0].as_ref().is_some_and(|m| m.start == 0));
+
+    if re.subexpression_count() > 0 {
+        // if regex subexpression #1 matched, return as string, otherwise null
+        let cap1 = caps.and_then(|caps| caps[1].clone());
+        Ok(Token::Str(match cap1 {
+            Some(range) => String::from(&lhs[range]),
+            None => String::new(),
+        }))
+    } else {
+        // return number of characters matched as int, zero if no match
+        let len = caps.map_or(0, |caps| lhs[caps[0].clone().unwrap()].chars().count());
+        Ok(Token::Integer(len as i64))
     }
 }
 
@@ -8,12 +8,13 @@
 //
 
 use std::collections::HashSet;
+use std::os::unix::ffi::OsStrExt;
 use std::os::unix::fs::{FileTypeExt, MetadataExt, PermissionsExt};
 use std::path::PathBuf;
 use std::{env, fs};
 
 use gettextrs::{bind_textdomain_codeset, setlocale, textdomain, LocaleCategory};
-use regex::Regex;
+use plib::regex::{Regex, RegexFlags};
 use walkdir::{DirEntry, WalkDir};
 
 #[derive(Clone)]
@@ -194,7 +195,7 @@ fn parse_expression(tokens: &mut Vec<&str>) -> Vec<Expr> {
     stack
 }
 
-/// Converts a shell pattern to a regular expression.
+/// Converts a shell pattern to an anchored basic regular expression.
 ///
 /// # Arguments
 ///
@@ -204,18 +205,53 @@ fn parse_expression(tokens: &mut Vec<&str>) -> Vec<Expr> {
 ///
 /// * A `Regex` object representing the converted pattern.
 fn pattern_to_regex(pattern: &str) -> Regex {
-    let mut regex_pattern = pattern.replace('?', ".");
-    regex_pattern = regex_pattern.replace('*', ".*");
-
-    let bracket_regex = Regex::new(r"\[(?:[^\]]+)\]").unwrap();
-    regex_pattern = bracket_regex
-        .replace_all(&regex_pattern, |caps: &regex::Captures| {
-            let bracket_content = &caps[0][1..caps[0].len() - 1];
-            format!("[{}]", bracket_content)
-        })
-        .to_string();
+    let mut regex_pattern = String::from("^");
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '?' => regex_pattern.push('.'),
+            '*' => regex_pattern.push_str(".*"),
+            '[' => {
+                // copy the bracket expression, if it is terminated
+                let rest: String = chars.clone().collect();
+                let body_start = usize::from(rest.starts_with('!') || rest.starts_with('^'));
+                let close = rest
+                    .char_indices()
+                    .skip(body_start + 1)
+                    .find(|&(_, c)| c == ']')
+                    .map(|(i, _)| i);
+                match close {
+                    Some(close) => {
+                        regex_pattern.push('[');
+                        if body_start == 1 {
+                            regex_pattern.push('^');
+                        }
+                        regex_pattern.push_str(&rest[body_start..=close]);
+                        for _ in 0..rest[..=close].chars().count() {
+                            chars.next();
+                        }
+                    }
+                    None => regex_pattern.push_str("\\["),
+                }
+            }
+            '\\' => {
+                if let Some(next) = chars.next() {
+                    if ".[\\*^$".contains(next) {
+                        regex_pattern.push('\\');
+                    }
+                    regex_pattern.push(next);
+                }
+            }
+            '.' | '^' | '$' => {
+                regex_pattern.push('\\');
+                regex_pattern.push(c);
+            }
+            _ => regex_pattern.push(c),
+        }
+    }
+    regex_pattern.push('$');
 
-    Regex::new(&format!("^{}$", regex_pattern)).unwrap()
+    Regex::new(regex_pattern.as_bytes(), RegexFlags::bre().no_sub(true)).unwrap()
 }
 
 /// Executes a command based on the list of expressions and returns the matching file paths.
@@ -275,14 +311,14 @@ fn evaluate_expression(
                 }
                 Expr::Name(name) => {
                     let regex = pattern_to_regex(name);
-                    if !regex.is_match(&file.file_name().to_string_lossy()) {
+                    if !regex.is_match(file.file_name().as_bytes()) {
                         c_files.remove(file.path());
                     }
                 }
                 Expr::Path(path) => {
                     let regex = pattern_to_regex(path);
 
-                    if !regex.is_match(&file.path().to_string_lossy()) && !first {
+                    if !regex.is_match(file.path().as_os_str().as_bytes()) && !first {
                         c_files.remove(file.path());
                     }
                 }
@@ -0,0 +1,69 @@
+//
+// Copyright (c) 2024 Hemi Labs, Inc.
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+//! Run-time formatting for the `gettext!` family of macros, whose format
+//! string is only known after translation.
+
+use std::fmt::{Display, Write};
+
+/// Substitute `args` into `fmt`: `{}` takes the next argument, `{N}` the
+/// argument with index N, and `{{` and `}}` stand for braces.  Anything
+/// else, including a placeholder without an argument, is copied as is.
+pub fn format(fmt: &str, args: &[&dyn Display]) -> String {
+    let mut out = String::with_capacity(fmt.len());
+    let mut next_arg = 0;
+    let mut rest = fmt;
+    while let Some(pos) = rest.find(['{', '}']) {
+        out.push_str(&rest[..pos]);
+        let tail = &rest[pos..];
+        if tail.starts_with("{{") || tail.starts_with("}}") {
+            out.push_str(&tail[..1]);
+            rest = &tail[2..];
+            continue;
+        }
+        let placeholder = tail
+            .strip_prefix('{')
+            .and_then(|t| t.find('}').map(|end| &t[..end]))
+            .filter(|index| index.bytes().all(|b| b.is_ascii_digit()));
+        let Some(index) = placeholder else {
+            out.push_str(&tail[..1]);
+            rest = &tail[1..];
+            continue;
+        };
+        let arg = if index.is_empty() {
+            next_arg += 1;
+            args.get(next_arg - 1)
+        } else {
+            index.parse::<usize>().ok().and_then(|i| args.get(i))
+        };
+        match arg {
+            Some(arg) => {
+                let _ = write!(out, "{arg}");
+            }
+            None => out.push_str(&tail[..index.len() + 2]),
+        }
+        rest = &tail[index.len() + 2..];
+    }
+    out.push_str(rest);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format() {
+        assert_eq!(format("{} of {}", &[&1, &"two"]), "1 of two");
+        assert_eq!(format("{1} {0}", &[&"a", &"b"]), "b a");
+        assert_eq!(format("{{{}}}", &[&7]), "{7}");
+        assert_eq!(format("{} {} {x}", &[&1]), "1 {} {x}");
+        assert_eq!(format("no args", &[]), "no args");
+    }
+}
@@ -1,59 +1,500 @@
-pub fn setlocale<T: Into<Vec<u8>>>(_category: LocaleCategory, locale: T) -> Option<Vec<u8>> {
-    Some(locale.into())
+//
+// Copyright (c) 2024 Hemi Labs, Inc.
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+//! A small, dependency-free implementation of the `gettext` message
+//! translation API.
+//!
+//! Translations are read from GNU `.mo` catalogs at
+//! `DIR/LANG/LC_MESSAGES/DOMAIN.mo`, where `DIR` is the directory bound
+//! to the domain with [`bindtextdomain`] and `LANG` is taken from the
+//! `LANGUAGE` list or the `LC_MESSAGES` locale chosen by [`setlocale`].
+//! A locale name such as `de_DE.UTF-8` also tries `de_DE.utf8`, `de_DE`
+//! and `de`.
+
+pub mod formatter;
+pub mod mo;
+pub mod plural;
+
+use std::collections::HashMap;
+use std::env;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard, OnceLock};
+
+use mo::Catalog;
+
+/// The directory searched for catalogs of a domain that was not bound.
+pub const DEFAULT_LOCALE_DIR: &str = "/usr/share/locale";
+
+const DEFAULT_DOMAIN: &str = "messages";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum LocaleCategory {
+    LcCType,
+    LcNumeric,
+    LcTime,
+    LcCollate,
+    LcMonetary,
+    LcMessages,
+    LcAll,
+    LcPaper,
+    LcName,
+    LcAddress,
+    LcTelephone,
+    LcMeasurement,
+    LcIdentification,
+}
+
+impl LocaleCategory {
+    const ALL: [LocaleCategory; 12] = [
+        LocaleCategory::LcCType,
+        LocaleCategory::LcNumeric,
+        LocaleCategory::LcTime,
+        LocaleCategory::LcCollate,
+        LocaleCategory::LcMonetary,
+        LocaleCategory::LcMessages,
+        LocaleCategory::LcPaper,
+        LocaleCategory::LcName,
+        LocaleCategory::LcAddress,
+        LocaleCategory::LcTelephone,
+        LocaleCategory::LcMeasurement,
+        LocaleCategory::LcIdentification,
+    ];
+
+    /// The name of the category's environment variable.
+    pub fn name(self) -> &'static str {
+        match self {
+            LocaleCategory::LcCType => "LC_CTYPE",
+            LocaleCategory::LcNumeric => "LC_NUMERIC",
+            LocaleCategory::LcTime => "LC_TIME",
+            LocaleCategory::LcCollate => "LC_COLLATE",
+            LocaleCategory::LcMonetary => "LC_MONETARY",
+            LocaleCategory::LcMessages => "LC_MESSAGES",
+            LocaleCategory::LcAll => "LC_ALL",
+            LocaleCategory::LcPaper => "LC_PAPER",
+            LocaleCategory::LcName => "LC_NAME",
+            LocaleCategory::LcAddress => "LC_ADDRESS",
+            LocaleCategory::LcTelephone => "LC_TELEPHONE",
+            LocaleCategory::LcMeasurement => "LC_MEASUREMENT",
+            LocaleCategory::LcIdentification => "LC_IDENTIFICATION",
+        }
+    }
+}
+
+#[derive(Default)]
+struct State {
+    domain: Option<String>,
+    dirs: HashMap<String, PathBuf>,
+    codesets: HashMap<String, String>,
+    locales: HashMap<LocaleCategory, String>,
+    /// Loaded catalogs by path; `None` records a missing or bad file.
+    catalogs: HashMap<PathBuf, Option<Catalog>>,
+}
+
+fn state() -> MutexGuard<'static, State> {
+    static STATE: OnceLock<Mutex<State>> = OnceLock::new();
+    STATE
+        .get_or_init(Mutex::default)
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+}
+
+fn lossy(bytes: impl Into<Vec<u8>>) -> String {
+    String::from_utf8_lossy(&bytes.into()).into_owned()
+}
+
+fn non_empty_var(name: &str) -> Option<String> {
+    env::var(name).ok().filter(|v| !v.is_empty())
+}
+
+/// The locale named by the environment for `category`.
+fn env_locale(category: LocaleCategory) -> String {
+    non_empty_var("LC_ALL")
+        .or_else(|| non_empty_var(category.name()))
+        .or_else(|| non_empty_var("LANG"))
+        .unwrap_or_else(|| String::from("C"))
 }
 
+/// Set the locale of `category`, or of every category with `LcAll`.  An
+/// empty name selects the locale from the environment.  Returns the
+/// resulting locale name.
+pub fn setlocale<T: Into<Vec<u8>>>(category: LocaleCategory, locale: T) -> Option<Vec<u8>> {
+    let locale = lossy(locale);
+    let mut state = state();
+    let categories: &[LocaleCategory] = if category == LocaleCategory::LcAll {
+        &LocaleCategory::ALL
+    } else {
+        std::slice::from_ref(&category)
+    };
+    for &cat in categories {
+        let name = if locale.is_empty() {
+            env_locale(cat)
+        } else {
+            locale.clone()
+        };
+        state.locales.insert(cat, name);
+    }
+    let result = state
+        .locales
+        .get(&categories[0])
+        .cloned()
+        .unwrap_or_default();
+    Some(result.into_bytes())
+}
+
+/// Record the codeset translations of `domainname` are returned in.
+/// Translations are always returned as UTF-8 strings.
 pub fn bind_textdomain_codeset<T, U>(
-    _domainname: T,
-    _codeset: U,
+    domainname: T,
+    codeset: U,
 ) -> Result<Option<String>, std::io::Error>
 where
     T: Into<Vec<u8>>,
     U: Into<String>,
 {
-    Ok(None)
+    let codeset = codeset.into();
+    state().codesets.insert(lossy(domainname), codeset.clone());
+    Ok(Some(codeset))
 }
 
-pub fn bindtextdomain<T, U>(
-    _domainname: T,
-    dirname: U,
-) -> Result<std::path::PathBuf, std::io::Error>
+/// Set the directory the catalogs of `domainname` are read from.
+pub fn bindtextdomain<T, U>(domainname: T, dirname: U) -> Result<std::path::PathBuf, std::io::Error>
 where
     T: Into<Vec<u8>>,
     U: Into<std::path::PathBuf>,
 {
-    Ok(dirname.into())
+    let domainname = lossy(domainname);
+    if domainname.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "empty domain name",
+        ));
+    }
+    let dirname = dirname.into();
+    state().dirs.insert(domainname, dirname.clone());
+    Ok(dirname)
 }
 
+/// Set the default domain; an empty name restores `messages`.
 pub fn textdomain<T: Into<Vec<u8>>>(domainname: T) -> Result<Vec<u8>, std::io::Error> {
-    Ok(domainname.into())
+    let domainname = lossy(domainname);
+    let mut state = state();
+    state.domain = (!domainname.is_empty()).then_some(domainname);
+    Ok(state
+        .domain
+        .as_deref()
+        .unwrap_or(DEFAULT_DOMAIN)
+        .as_bytes()
+        .to_vec())
 }
 
+/// The names to try for the locale `name`, most specific first: for
+/// `de_DE.UTF-8@euro`, the name itself, then `de_DE.utf8@euro`,
+/// `de_DE@euro`, `de@euro` and the same without the modifier.
+fn locale_variants(name: &str) -> Vec<String> {
+    let (base, modifier) = match name.split_once('@') {
+        Some((base, modifier)) => (base, Some(modifier)),
+        None => (name, None),
+    };
+    let (base, codeset) = match base.split_once('.') {
+        Some((base, codeset)) => (base, Some(codeset)),
+        None => (base, None),
+    };
+    let language = base.split_once('_').map_or(base, |(language, _)| language);
+
+    let mut names = Vec::new();
+    if let Some(codeset) = codeset {
+        let normalized: String = codeset
+            .chars()
+            .filter(char::is_ascii_alphanumeric)
+            .map(|c| c.to_ascii_lowercase())
+            .collect();
+        names.push(format!("{base}.{codeset}"));
+        if normalized != codeset {
+            names.push(format!("{base}.{normalized}"));
+        }
+    }
+    names.push(base.to_string());
+    if language != base {
+        names.push(language.to_string());
+    }
+    match modifier {
+        Some(modifier) => names
+            .iter()
+            .map(|n| format!("{n}@{modifier}"))
+            .chain(names.iter().cloned())
+            .collect(),
+        None => names,
+    }
+}
+
+/// The languages to look up translations for, in order of preference.
+fn languages(state: &State, category: LocaleCategory) -> Vec<String> {
+    let locale = match state.locales.get(&category) {
+        Some(locale) => locale.as_str(),
+        None => return Vec::new(),
+    };
+    if locale == "C" || locale == "POSIX" {
+        return Vec::new();
+    }
+    match non_empty_var("LANGUAGE") {
+        Some(list) => list
+            .split(':')
+            .filter(|l| !l.is_empty())
+            .map(String::from)
+            .collect(),
+        None => vec![locale.to_string()],
+    }
+}
+
+fn load<'a>(state: &'a mut State, path: &Path) -> Option<&'a Catalog> {
+    state
+        .catalogs
+        .entry(path.to_path_buf())
+        .or_insert_with(|| Catalog::open(path).ok())
+        .as_ref()
+}
+
+/// Look up a message in the catalogs of `domain` for each language until
+/// one has it, passing the catalog to `lookup`.
+fn translate<F>(domain: Option<&str>, category: LocaleCategory, lookup: F) -> Option<String>
+where
+    F: Fn(&Catalog) -> Option<String>,
+{
+    let mut state = state();
+    let domain = domain
+        .map(String::from)
+        .or_else(|| state.domain.clone())
+        .unwrap_or_else(|| String::from(DEFAULT_DOMAIN));
+    let dir = state
+        .dirs
+        .get(&domain)
+        .cloned()
+        .unwrap_or_else(|| PathBuf::from(DEFAULT_LOCALE_DIR));
+    let category_dir = match category {
+        LocaleCategory::LcAll => LocaleCategory::LcMessages.name(),
+        _ => category.name(),
+    };
+    for language in languages(&state, category) {
+        let catalog = locale_variants(&language).into_iter().find_map(|name| {
+            let path = dir
+                .join(name)
+                .join(category_dir)
+                .join(format!("{domain}.mo"));
+            load(&mut state, &path).map(|_| path)
+        });
+        if let Some(path) = catalog {
+            if let Some(translation) = load(&mut state, &path).and_then(&lookup) {
+                return Some(translation);
+            }
+        }
+    }
+    None
+}
+
+/// Translate `msgid` in the default domain.
 pub fn gettext<T: Into<String>>(msgid: T) -> String {
-    msgid.into()
+    dcgettext(None::<String>, msgid, LocaleCategory::LcMessages)
+}
+
+/// Translate `msgid` in `domainname`.
+pub fn dgettext<T: Into<String>, U: Into<String>>(domainname: T, msgid: U) -> String {
+    dcgettext(Some(domainname), msgid, LocaleCategory::LcMessages)
 }
 
+/// Translate `msgid` in `domainname`, or the default domain, for the
+/// locale of `category`.
+pub fn dcgettext<T: Into<String>, U: Into<String>>(
+    domainname: Option<T>,
+    msgid: U,
+    category: LocaleCategory,
+) -> String {
+    let domainname = domainname.map(Into::into);
+    let msgid = msgid.into();
+    translate(domainname.as_deref(), category, |c| c.get(None, &msgid)).unwrap_or(msgid)
+}
+
+/// Translate `msgid` with the plural form for `n` in the default domain.
+pub fn ngettext<T: Into<String>, U: Into<String>>(msgid: T, msgid_plural: U, n: u32) -> String {
+    dcngettext(
+        None::<String>,
+        msgid,
+        msgid_plural,
+        n,
+        LocaleCategory::LcMessages,
+    )
+}
+
+/// Translate `msgid` with the plural form for `n` in `domainname`.
+pub fn dngettext<T, U, V>(domainname: T, msgid: U, msgid_plural: V, n: u32) -> String
+where
+    T: Into<String>,
+    U: Into<String>,
+    V: Into<String>,
+{
+    dcngettext(
+        Some(domainname),
+        msgid,
+        msgid_plural,
+        n,
+        LocaleCategory::LcMessages,
+    )
+}
+
+/// Translate `msgid` with the plural form for `n` in `domainname`, or
+/// the default domain, for the locale of `category`.  Without a
+/// translation, `msgid` is returned if `n` is 1 and `msgid_plural`
+/// otherwise.
+pub fn dcngettext<T, U, V>(
+    domainname: Option<T>,
+    msgid: U,
+    msgid_plural: V,
+    n: u32,
+    category: LocaleCategory,
+) -> String
+where
+    T: Into<String>,
+    U: Into<String>,
+    V: Into<String>,
+{
+    let domainname = domainname.map(Into::into);
+    let msgid = msgid.into();
+    translate(domainname.as_deref(), category, |c| {
+        c.get_plural(None, &msgid, u64::from(n))
+    })
+    .unwrap_or_else(|| if n == 1 { msgid } else { msgid_plural.into() })
+}
+
+/// Translate `msgid` in the context `msgctxt` in the default domain.
+pub fn pgettext<T: Into<String>, U: Into<String>>(msgctxt: T, msgid: U) -> String {
+    let msgctxt = msgctxt.into();
+    let msgid = msgid.into();
+    translate(None, LocaleCategory::LcMessages, |c| {
+        c.get(Some(&msgctxt), &msgid)
+    })
+    .unwrap_or(msgid)
+}
+
+/// Translate `msgid` in the context `msgctxt` with the plural form for
+/// `n` in the default domain.
+pub fn npgettext<T, U, V>(msgctxt: T, msgid: U, msgid_plural: V, n: u32) -> String
+where
+    T: Into<String>,
+    U: Into<String>,
+    V: Into<String>,
+{
+    let msgctxt = msgctxt.into();
+    let msgid = msgid.into();
+    translate(None, LocaleCategory::LcMessages, |c| {
+        c.get_plural(Some(&msgctxt), &msgid, u64::from(n))
+    })
+    .unwrap_or_else(|| if n == 1 { msgid } else { msgid_plural.into() })
+}
+
+/// Translate a format string and substitute its `{}` placeholders, as
+/// described in [`formatter::format`].
 #[macro_export]
 macro_rules! gettext {
     ($fmt:expr) => {
-        format!($fmt)
+        $crate::gettext($fmt)
     };
-    ($fmt:expr, $($arg:tt)*) => {
-        format!($fmt, $($arg)*)
+    ($fmt:expr, $($arg:expr),+ $(,)?) => {
+        $crate::formatter::format(
+            &$crate::gettext($fmt),
+            &[$(&$arg as &dyn ::std::fmt::Display),+],
+        )
     };
 }
 
-pub enum LocaleCategory {
-    LcCType,
-    LcNumeric,
-    LcTime,
-    LcCollate,
-    LcMonetary,
-    LcMessages,
-    LcAll,
-    LcPaper,
-    LcName,
-    LcAddress,
-    LcTelephone,
-    LcMeasurement,
-    LcIdentification,
+/// The plural counterpart of [`gettext!`].
+#[macro_export]
+macro_rules! ngettext {
+    ($fmt:expr, $fmt_plural:expr, $n:expr $(, $arg:expr)* $(,)?) => {
+        $crate::formatter::format(
+            &$crate::ngettext($fmt, $fmt_plural, $n),
+            &[$(&$arg as &dyn ::std::fmt::Display),*],
+        )
+    };
+}
+
+/// The context counterpart of [`gettext!`].
+#[macro_export]
+macro_rules! pgettext {
+    ($ctxt:expr, $fmt:expr $(, $arg:expr)* $(,)?) => {
+        $crate::formatter::format(
+            &$crate::pgettext($ctxt, $fmt),
+            &[$(&$arg as &dyn ::std::fmt::Display),*],
+        )
+    };
+}
+
+/// The context counterpart of [`ngettext!`].
+#[macro_export]
+macro_rules! npgettext {
+    ($ctxt:expr, $fmt:expr, $fmt_plural:expr, $n:expr $(, $arg:expr)* $(,)?) => {
+        $crate::formatter::format(
+            &$crate::npgettext($ctxt, $fmt, $fmt_plural, $n),
+            &[$(&$arg as &dyn ::std::fmt::Display),*],
+        )
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_locale_variants() {
+        assert_eq!(
+            locale_variants("de_DE.UTF-8"),
+            vec!["de_DE.UTF-8", "de_DE.utf8", "de_DE", "de"]
+        );
+        assert_eq!(locale_variants("de"), vec!["de"]);
+        assert_eq!(
+            locale_variants("sr_RS@latin"),
+            vec!["sr_RS@latin", "sr@latin", "sr_RS", "sr"]
+        );
+    }
+
+    // The only test touching the global state and the environment, so it
+    // cannot race with another.
+    #[test]
+    fn test_translation() {
+        let dir = env::temp_dir().join(format!("gettext-rs-test-{}", std::process::id()));
+        let messages = dir.join("de_DE").join("LC_MESSAGES");
+        std::fs::create_dir_all(&messages).unwrap();
+        let data = mo::tests::build(mo::tests::ENTRIES, 7, false);
+        std::fs::write(messages.join("test-domain.mo"), data).unwrap();
+
+        env::remove_var("LANGUAGE");
+        env::remove_var("LC_ALL");
+        env::set_var("LC_MESSAGES", "de_DE.UTF-8");
+        setlocale(LocaleCategory::LcAll, "");
+        textdomain("test-domain").unwrap();
+        bindtextdomain("test-domain", dir.clone()).unwrap();
+
+        assert_eq!(gettext("hello"), "hallo");
+        assert_eq!(gettext("untranslated"), "untranslated");
+        assert_eq!(pgettext("menu", "Open"), "Öffnen (Menü)");
+        assert_eq!(ngettext("%d file", "%d files", 2), "%d Dateien (zwei)");
+        assert_eq!(ngettext("one", "many", 1), "one");
+        assert_eq!(ngettext("one", "many", 3), "many");
+        assert_eq!(gettext!("{} {}", "hello", 1), "hello 1");
+        assert_eq!(dgettext("other-domain", "hello"), "hello");
+
+        setlocale(LocaleCategory::LcMessages, "C");
+        assert_eq!(gettext("hello"), "hello");
+
+        setlocale(LocaleCategory::LcMessages, "fr_FR");
+        env::set_var("LANGUAGE", "fr:de_DE");
+        assert_eq!(gettext("hello"), "hallo");
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
 }
@@ -0,0 +1,411 @@
+//
+// Copyright (c) 2024 Hemi Labs, Inc.
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+//! GNU `.mo` message catalogs.
+//!
+//! A catalog starts with a header of 32-bit words in either byte order:
+//! the magic number, the format revision, the number of strings, the
+//! offsets of the original and translated string tables, and the size and
+//! offset of a hash table.  Each string table entry is a length and an
+//! offset; strings are NUL-terminated.  A message with a context has the
+//! original string `context\x04msgid`, and a plural message has
+//! `msgid\0msgid_plural` with its translations separated by NULs.
+
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::plural::PluralForms;
+
+/// The magic number of a catalog, as read in the catalog's byte order.
+pub const MAGIC: u32 = 0x950412de;
+
+/// Separator between the context and the msgid of an original string.
+pub const CONTEXT_SEPARATOR: char = '\x04';
+
+/// Size of the header written by `msgfmt`, up to the hash table offset.
+pub const HEADER_SIZE: u32 = 28;
+
+/// The hash function of the catalog hash table.
+pub fn hash_string(s: &[u8]) -> u32 {
+    let mut hval: u32 = 0;
+    for &b in s {
+        hval = (hval << 4).wrapping_add(u32::from(b));
+        let g = hval & (0xf << 28);
+        if g != 0 {
+            hval ^= g >> 24;
+            hval ^= g;
+        }
+    }
+    hval
+}
+
+/// Character sets a catalog's translations can be converted from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Charset {
+    Utf8,
+    Latin1,
+}
+
+/// A loaded message catalog.
+#[derive(Debug)]
+pub struct Catalog {
+    data: Vec<u8>,
+    big_endian: bool,
+    count: usize,
+    orig_table: usize,
+    trans_table: usize,
+    hash_size: usize,
+    hash_table: usize,
+    charset: Charset,
+    plural: PluralForms,
+}
+
+fn invalid(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg.to_string())
+}
+
+impl Catalog {
+    /// Read the catalog file `path`.
+    pub fn open(path: &Path) -> io::Result<Catalog> {
+        Catalog::parse(fs::read(path)?)
+    }
+
+    /// Parse a catalog, checking that its tables and strings lie within
+    /// the data.
+    pub fn parse(data: Vec<u8>) -> io::Result<Catalog> {
+        if data.len() < HEADER_SIZE as usize {
+            return Err(invalid("truncated message catalog"));
+        }
+        let magic = [data[0], data[1], data[2], data[3]];
+        let big_endian = if u32::from_le_bytes(magic) == MAGIC {
+            false
+        } else if u32::from_be_bytes(magic) == MAGIC {
+            true
+        } else {
+            return Err(invalid("not a message catalog"));
+        };
+        let mut catalog = Catalog {
+            data,
+            big_endian,
+            count: 0,
+            orig_table: 0,
+            trans_table: 0,
+            hash_size: 0,
+            hash_table: 0,
+            charset: Charset::Utf8,
+            plural: PluralForms::default(),
+        };
+        if catalog.word(4) >> 16 > 1 {
+            return Err(invalid("unsupported message catalog revision"));
+        }
+        catalog.count = catalog.word(8) as usize;
+        catalog.orig_table = catalog.word(12) as usize;
+        catalog.trans_table = catalog.word(16) as usize;
+        catalog.hash_size = catalog.word(20) as usize;
+        catalog.hash_table = catalog.word(24) as usize;
+
+        let len = catalog.data.len();
+        let table_end = |offset: usize, entries: usize, size: usize| {
+            entries
+                .checked_mul(size)
+                .and_then(|n| n.checked_add(offset))
+                .is_some_and(|end| end <= len)
+        };
+        if !table_end(catalog.orig_table, catalog.count, 8)
+            || !table_end(catalog.trans_table, catalog.count, 8)
+            || (catalog.hash_size > 0 && !table_end(catalog.hash_table, catalog.hash_size, 4))
+        {
+            return Err(invalid("message catalog table out of range"));
+        }
+        for i in 0..catalog.count {
+            for table in [catalog.orig_table, catalog.trans_table] {
+                let (length, offset) = catalog.entry(table, i);
+                if offset.checked_add(length).map_or(true, |end| end >= len)
+                    || catalog.data[offset + length] != 0
+                {
+                    return Err(invalid("message catalog string out of range"));
+                }
+            }
+        }
+
+        if let Some(header) = catalog.translation(b"") {
+            let header = String::from_utf8_lossy(header).into_owned();
+            catalog.read_header(&header);
+        }
+        Ok(catalog)
+    }
+
+    fn word(&self, offset: usize) -> u32 {
+        let bytes = [
+            self.data[offset],
+            self.data[offset + 1],
+            self.data[offset + 2],
+            self.data[offset + 3],
+        ];
+        if self.big_endian {
+            u32::from_be_bytes(bytes)
+        } else {
+            u32::from_le_bytes(bytes)
+        }
+    }
+
+    /// The length and offset of string `index` of a string table.
+    fn entry(&self, table: usize, index: usize) -> (usize, usize) {
+        let at = table + index * 8;
+        (self.word(at) as usize, self.word(at + 4) as usize)
+    }
+
+    fn string(&self, table: usize, index: usize) -> &[u8] {
+        let (length, offset) = self.entry(table, index);
+        &self.data[offset..offset + length]
+    }
+
+    /// The original string `index` up to its first NUL: the msgid, with
+    /// the plural msgid left out.
+    fn key(&self, index: usize) -> &[u8] {
+        let orig = self.string(self.orig_table, index);
+        match orig.iter().position(|&b| b == 0) {
+            Some(end) => &orig[..end],
+            None => orig,
+        }
+    }
+
+    fn read_header(&mut self, header: &str) {
+        for line in header.lines() {
+            let Some((name, value)) = line.split_once(':') else {
+                continue;
+            };
+            match name.trim() {
+                "Content-Type" => {
+                    let charset = value
+                        .split(';')
+                        .filter_map(|p| p.trim().strip_prefix("charset="))
+                        .next()
+                        .unwrap_or("UTF-8")
+                        .to_ascii_uppercase();
+                    if matches!(charset.as_str(), "ISO-8859-1" | "ISO8859-1" | "LATIN1") {
+                        self.charset = Charset::Latin1;
+                    }
+                }
+                "Plural-Forms" => {
+                    if let Some(plural) = PluralForms::parse(value) {
+                        self.plural = plural;
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// The index of the message whose msgid is `key`, found through the
+    /// hash table if the catalog has one and by binary search otherwise.
+    fn find(&self, key: &[u8]) -> Option<usize> {
+        if self.hash_size > 2 {
+            let hval = hash_string(key) as usize;
+            let size = self.hash_size;
+            let incr = 1 + hval % (size - 2);
+            let mut idx = hval % size;
+            for _ in 0..size {
+                let n = self.word(self.hash_table + idx * 4) as usize;
+                if n == 0 {
+                    return None;
+                }
+                if n <= self.count && self.key(n - 1) == key {
+                    return Some(n - 1);
+                }
+                idx = if idx >= size - incr {
+                    idx - (size - incr)
+                } else {
+                    idx + incr
+                };
+            }
+            return None;
+        }
+        let (mut lo, mut hi) = (0, self.count);
+        while lo < hi {
+            let mid = (lo + hi) / 2;
+            match self.key(mid).cmp(key) {
+                std::cmp::Ordering::Less => lo = mid + 1,
+                std::cmp::Ordering::Greater => hi = mid,
+                std::cmp::Ordering::Equal => return Some(mid),
+            }
+        }
+        None
+    }
+
+    fn translation(&self, key: &[u8]) -> Option<&[u8]> {
+        self.find(key).map(|i| self.string(self.trans_table, i))
+    }
+
+    fn decode(&self, bytes: &[u8]) -> String {
+        match self.charset {
+            Charset::Utf8 => String::from_utf8_lossy(bytes).into_owned(),
+            Charset::Latin1 => bytes.iter().map(|&b| char::from(b)).collect(),
+        }
+    }
+
+    fn lookup_key(context: Option<&str>, msgid: &str) -> Vec<u8> {
+        match context {
+            Some(context) => format!("{context}{CONTEXT_SEPARATOR}{msgid}").into_bytes(),
+            None => msgid.as_bytes().to_vec(),
+        }
+    }
+
+    /// The translation of `msgid` in `context`, if the catalog has one.
+    pub fn get(&self, context: Option<&str>, msgid: &str) -> Option<String> {
+        let key = Catalog::lookup_key(context, msgid);
+        let translation = self.translation(&key)?;
+        let first = translation.split(|&b| b == 0).next().unwrap_or_default();
+        Some(self.decode(first))
+    }
+
+    /// The translation of the plural message `msgid` for the count `n`,
+    /// if the catalog has one.
+    pub fn get_plural(&self, context: Option<&str>, msgid: &str, n: u64) -> Option<String> {
+        let key = Catalog::lookup_key(context, msgid);
+        let translation = self.translation(&key)?;
+        let index = self.plural.index(n);
+        let form = translation.split(|&b| b == 0).nth(index)?;
+        Some(self.decode(form))
+    }
+
+    /// The number of messages, including the header entry.
+    pub fn len(&self) -> usize {
+        self.count
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    /// The plural rule from the catalog header.
+    pub fn plural_forms(&self) -> &PluralForms {
+        &self.plural
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// Build a catalog from (original, translation) pairs, which must be
+    /// sorted, with a hash table of `hash_size` entries.
+    pub(crate) fn build(entries: &[(&[u8], &[u8])], hash_size: u32, big_endian: bool) -> Vec<u8> {
+        let word = |w: u32| {
+            if big_endian {
+                w.to_be_bytes()
+            } else {
+                w.to_le_bytes()
+            }
+        };
+        let n = entries.len() as u32;
+        let orig_table = HEADER_SIZE;
+        let trans_table = orig_table + 8 * n;
+        let hash_table = trans_table + 8 * n;
+        let mut strings_at = hash_table + 4 * hash_size;
+
+        let mut out = Vec::new();
+        for w in [MAGIC, 0, n, orig_table, trans_table, hash_size, hash_table] {
+            out.extend_from_slice(&word(w));
+        }
+        let mut strings = Vec::new();
+        for column in 0..2 {
+            for entry in entries {
+                let s = if column == 0 { entry.0 } else { entry.1 };
+                out.extend_from_slice(&word(s.len() as u32));
+                out.extend_from_slice(&word(strings_at));
+                strings.extend_from_slice(s);
+                strings.push(0);
+                strings_at += s.len() as u32 + 1;
+            }
+        }
+        let mut hash = vec![0u32; hash_size as usize];
+        for (i, entry) in entries.iter().enumerate().filter(|_| hash_size > 0) {
+            let key = entry.0.split(|&b| b == 0).next().unwrap();
+            let hval = hash_string(key);
+            let mut idx = hval % hash_size;
+            let incr = 1 + hval % (hash_size - 2);
+            while hash[idx as usize] != 0 {
+                idx = (idx + incr) % hash_size;
+            }
+            hash[idx as usize] = i as u32 + 1;
+        }
+        for h in hash {
+            out.extend_from_slice(&word(h));
+        }
+        out.extend_from_slice(&strings);
+        out
+    }
+
+    pub(crate) const ENTRIES: &[(&[u8], &[u8])] = &[
+        (
+            b"",
+            b"Content-Type: text/plain; charset=UTF-8\n\
+              Plural-Forms: nplurals=3; plural=n==1 ? 0 : n==2 ? 1 : 2;\n",
+        ),
+        (
+            b"%d file\0%d files",
+            b"%d Datei\0%d Dateien (zwei)\0%d Dateien",
+        ),
+        (b"menu\x04Open", b"\xc3\x96ffnen (Men\xc3\xbc)"),
+        (b"Open", b"\xc3\x96ffnen"),
+        (b"hello", b"hallo"),
+    ];
+
+    #[test]
+    fn test_hash_string() {
+        assert_eq!(hash_string(b""), 0);
+        assert_eq!(hash_string(b"a"), 0x61);
+        assert_eq!(hash_string(b"hello"), 0x6ec32f);
+        assert_eq!(hash_string(b"a long message id"), 0xd8b3934);
+    }
+
+    #[test]
+    fn test_lookup() {
+        // Sorted for the binary search used without a hash table.
+        let mut sorted = ENTRIES.to_vec();
+        sorted.sort();
+        for (entries, hash_size, big_endian) in [
+            (ENTRIES, 7, false),
+            (ENTRIES, 11, true),
+            (&sorted[..], 0, false),
+        ] {
+            let catalog = Catalog::parse(build(entries, hash_size, big_endian)).unwrap();
+            assert_eq!(catalog.len(), 5);
+            assert_eq!(catalog.get(None, "hello").as_deref(), Some("hallo"));
+            assert_eq!(catalog.get(None, "Open").as_deref(), Some("Öffnen"));
+            assert_eq!(
+                catalog.get(Some("menu"), "Open").as_deref(),
+                Some("Öffnen (Menü)")
+            );
+            assert_eq!(catalog.get(None, "missing"), None);
+            assert_eq!(catalog.get(Some("other"), "Open"), None);
+            let plural = |n| catalog.get_plural(None, "%d file", n);
+            assert_eq!(plural(1).as_deref(), Some("%d Datei"));
+            assert_eq!(plural(2).as_deref(), Some("%d Dateien (zwei)"));
+            assert_eq!(plural(7).as_deref(), Some("%d Dateien"));
+        }
+    }
+
+    #[test]
+    fn test_latin1_and_errors() {
+        let entries: &[(&[u8], &[u8])] = &[
+            (b"", b"Content-Type: text/plain; charset=ISO-8859-1\n"),
+            (b"yes", b"s\xed"),
+        ];
+        let catalog = Catalog::parse(build(entries, 5, false)).unwrap();
+        assert_eq!(catalog.get(None, "yes").as_deref(), Some("sí"));
+
+        assert!(Catalog::parse(b"not a catalog at all, really!!".to_vec()).is_err());
+        let mut truncated = build(ENTRIES, 7, false);
+        truncated.truncate(truncated.len() - 3);
+        assert!(Catalog::parse(truncated).is_err());
+    }
+}
@@ -0,0 +1,306 @@
+//
+// Copyright (c) 2024 Hemi Labs, Inc.
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+//! The `plural=` expression of a catalog's `Plural-Forms` header: a C
+//! expression in the variable `n`, with the operators `?:`, `||`, `&&`,
+//! `==`, `!=`, `<`, `>`, `<=`, `>=`, `+`, `-`, `*`, `/`, `%` and `!`.
+
+/// A binary operator.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Op {
+    Or,
+    And,
+    Eq,
+    Ne,
+    Lt,
+    Gt,
+    Le,
+    Ge,
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+}
+
+impl Op {
+    fn precedence(self) -> u8 {
+        match self {
+            Op::Or => 1,
+            Op::And => 2,
+            Op::Eq | Op::Ne => 3,
+            Op::Lt | Op::Gt | Op::Le | Op::Ge => 4,
+            Op::Add | Op::Sub => 5,
+            Op::Mul | Op::Div | Op::Rem => 6,
+        }
+    }
+
+    fn apply(self, a: u64, b: u64) -> u64 {
+        match self {
+            Op::Or => u64::from(a != 0 || b != 0),
+            Op::And => u64::from(a != 0 && b != 0),
+            Op::Eq => u64::from(a == b),
+            Op::Ne => u64::from(a != b),
+            Op::Lt => u64::from(a < b),
+            Op::Gt => u64::from(a > b),
+            Op::Le => u64::from(a <= b),
+            Op::Ge => u64::from(a >= b),
+            Op::Add => a.wrapping_add(b),
+            Op::Sub => a.wrapping_sub(b),
+            Op::Mul => a.wrapping_mul(b),
+            Op::Div => a.checked_div(b).unwrap_or(0),
+            Op::Rem => a.checked_rem(b).unwrap_or(0),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Token {
+    Num(u64),
+    N,
+    Op(Op),
+    Not,
+    Question,
+    Colon,
+    Open,
+    Close,
+}
+
+fn tokenize(s: &str) -> Option<Vec<Token>> {
+    let bytes = s.as_bytes();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        let c = bytes[i];
+        let next = bytes.get(i + 1).copied();
+        let (token, len) = match c {
+            b' ' | b'\t' | b'\n' => {
+                i += 1;
+                continue;
+            }
+            b'0'..=b'9' => {
+                let end = bytes[i..]
+                    .iter()
+                    .position(|b| !b.is_ascii_digit())
+                    .map_or(bytes.len(), |p| i + p);
+                (Token::Num(s[i..end].parse().ok()?), end - i)
+            }
+            b'n' => (Token::N, 1),
+            b'|' if next == Some(b'|') => (Token::Op(Op::Or), 2),
+            b'&' if next == Some(b'&') => (Token::Op(Op::And), 2),
+            b'=' if next == Some(b'=') => (Token::Op(Op::Eq), 2),
+            b'!' if next == Some(b'=') => (Token::Op(Op::Ne), 2),
+            b'<' if next == Some(b'=') => (Token::Op(Op::Le), 2),
+            b'>' if next == Some(b'=') => (Token::Op(Op::Ge), 2),
+            b'<' => (Token::Op(Op::Lt), 1),
+            b'>' => (Token::Op(Op::Gt), 1),
+            b'+' => (Token::Op(Op::Add), 1),
+            b'-' => (Token::Op(Op::Sub), 1),
+            b'*' => (Token::Op(Op::Mul), 1),
+            b'/' => (Token::Op(Op::Div), 1),
+            b'%' => (Token::Op(Op::Rem), 1),
+            b'!' => (Token::Not, 1),
+            b'?' => (Token::Question, 1),
+            b':' => (Token::Colon, 1),
+            b'(' => (Token::Open, 1),
+            b')' => (Token::Close, 1),
+            _ => return None,
+        };
+        tokens.push(token);
+        i += len;
+    }
+    Some(tokens)
+}
+
+/// A parsed plural expression.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Expr {
+    Num(u64),
+    N,
+    Not(Box<Expr>),
+    Binary(Op, Box<Expr>, Box<Expr>),
+    Cond(Box<Expr>, Box<Expr>, Box<Expr>),
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<Token> {
+        self.tokens.get(self.pos).copied()
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.peek();
+        self.pos += 1;
+        token
+    }
+
+    fn expect(&mut self, token: Token) -> Option<()> {
+        (self.next()? == token).then_some(())
+    }
+
+    fn conditional(&mut self) -> Option<Expr> {
+        let cond = self.binary(1)?;
+        if self.peek() != Some(Token::Question) {
+            return Some(cond);
+        }
+        self.pos += 1;
+        let then = self.conditional()?;
+        self.expect(Token::Colon)?;
+        let otherwise = self.conditional()?;
+        Some(Expr::Cond(
+            Box::new(cond),
+            Box::new(then),
+            Box::new(otherwise),
+        ))
+    }
+
+    fn binary(&mut self, min_precedence: u8) -> Option<Expr> {
+        let mut lhs = self.unary()?;
+        while let Some(Token::Op(op)) = self.peek() {
+            if op.precedence() < min_precedence {
+                break;
+            }
+            self.pos += 1;
+            let rhs = self.binary(op.precedence() + 1)?;
+            lhs = Expr::Binary(op, Box::new(lhs), Box::new(rhs));
+        }
+        Some(lhs)
+    }
+
+    fn unary(&mut self) -> Option<Expr> {
+        match self.next()? {
+            Token::Not => Some(Expr::Not(Box::new(self.unary()?))),
+            Token::Num(value) => Some(Expr::Num(value)),
+            Token::N => Some(Expr::N),
+            Token::Open => {
+                let expr = self.conditional()?;
+                self.expect(Token::Close)?;
+                Some(expr)
+            }
+            _ => None,
+        }
+    }
+}
+
+impl Expr {
+    /// Parse a plural expression, or `None` if it is malformed.
+    pub fn parse(s: &str) -> Option<Expr> {
+        let mut parser = Parser {
+            tokens: tokenize(s)?,
+            pos: 0,
+        };
+        let expr = parser.conditional()?;
+        (parser.pos == parser.tokens.len()).then_some(expr)
+    }
+
+    /// Evaluate the expression for `n`; division by zero yields 0.
+    pub fn eval(&self, n: u64) -> u64 {
+        match self {
+            Expr::Num(value) => *value,
+            Expr::N => n,
+            Expr::Not(e) => u64::from(e.eval(n) == 0),
+            Expr::Binary(op, a, b) => op.apply(a.eval(n), b.eval(n)),
+            Expr::Cond(c, a, b) => {
+                if c.eval(n) != 0 {
+                    a.eval(n)
+                } else {
+                    b.eval(n)
+                }
+            }
+        }
+    }
+}
+
+/// The plural rule of a catalog.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PluralForms {
+    pub nplurals: usize,
+    pub expr: Expr,
+}
+
+impl Default for PluralForms {
+    /// The Germanic rule used when a catalog does not give one.
+    fn default() -> Self {
+        PluralForms {
+            nplurals: 2,
+            expr: Expr::Binary(Op::Ne, Box::new(Expr::N), Box::new(Expr::Num(1))),
+        }
+    }
+}
+
+impl PluralForms {
+    /// Parse the value of a `Plural-Forms` header,
+    /// `nplurals=N; plural=EXPR;`.
+    pub fn parse(value: &str) -> Option<PluralForms> {
+        let mut nplurals = None;
+        let mut expr = None;
+        for part in value.split(';') {
+            let Some((name, value)) = part.split_once('=') else {
+                continue;
+            };
+            match name.trim() {
+                "nplurals" => nplurals = value.trim().parse::<usize>().ok(),
+                "plural" => expr = Expr::parse(value),
+                _ => {}
+            }
+        }
+        Some(PluralForms {
+            nplurals: nplurals.filter(|&n| n > 0)?,
+            expr: expr?,
+        })
+    }
+
+    /// The index of the plural form for `n`.
+    pub fn index(&self, n: u64) -> usize {
+        usize::try_from(self.expr.eval(n)).unwrap_or(usize::MAX)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expressions() {
+        let e = Expr::parse("n != 1").unwrap();
+        assert_eq!((e.eval(0), e.eval(1), e.eval(2)), (1, 0, 1));
+
+        let e = Expr::parse(
+            "(n%10==1 && n%100!=11 ? 0 : n%10>=2 && n%10<=4 && (n%100<10 || n%100>=20) ? 1 : 2)",
+        )
+        .unwrap();
+        let forms: Vec<u64> = [1, 2, 5, 11, 21, 22, 25, 112]
+            .iter()
+            .map(|&n| e.eval(n))
+            .collect();
+        assert_eq!(forms, vec![0, 1, 2, 2, 0, 1, 2, 2]);
+
+        let e = Expr::parse("!(n - 1) + 2 * 3 % 4 / 1").unwrap();
+        assert_eq!((e.eval(1), e.eval(3)), (3, 2));
+        assert_eq!(Expr::parse("n / 0").unwrap().eval(5), 0);
+
+        assert_eq!(Expr::parse("n +"), None);
+        assert_eq!(Expr::parse("(n"), None);
+        assert_eq!(Expr::parse("n ? 1"), None);
+        assert_eq!(Expr::parse("x"), None);
+    }
+
+    #[test]
+    fn test_plural_forms() {
+        let forms = PluralForms::parse(" nplurals=3; plural=n==1 ? 0 : n==2 ? 1 : 2;").unwrap();
+        assert_eq!(forms.nplurals, 3);
+        assert_eq!((forms.index(1), forms.index(2), forms.index(9)), (0, 1, 2));
+        assert_eq!(PluralForms::parse("nplurals=2;"), None);
+        assert_eq!(PluralForms::default().index(1), 0);
+    }
+}
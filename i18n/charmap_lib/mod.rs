@@ -0,0 +1,308 @@
+//
+// Copyright (c) 2024 Jeff Garzik
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+//! Reader for POSIX charmap files (XBD 6.4), shared by `iconv` and
+//! `localedef`.  Charmaps compressed with gzip, as installed by glibc,
+//! are read transparently.
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read};
+use std::path::{Path, PathBuf};
+
+use flate2::read::GzDecoder;
+use plib::locale::charmap_dirs;
+
+#[derive(Debug)]
+pub struct CharmapHeader {
+    pub code_set_name: Option<String>,
+    pub mb_cur_max: usize,
+    pub mb_cur_min: usize,
+    pub escape_char: char,
+    pub comment_char: char,
+}
+
+impl Default for CharmapHeader {
+    fn default() -> Self {
+        CharmapHeader {
+            code_set_name: None,
+            mb_cur_max: 1,
+            mb_cur_min: 1,
+            escape_char: '\\',
+            comment_char: '#',
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct CharmapEntry {
+    pub symbolic_name: String,
+    pub encoding: Vec<u8>,
+    pub _comments: Option<String>,
+}
+
+#[derive(Debug)]
+pub struct Charmap {
+    pub header: CharmapHeader,
+    pub entries: HashMap<String, CharmapEntry>,
+    pub width_entries: HashMap<String, usize>,
+    pub width_default: usize,
+}
+
+fn invalid(msg: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg.into())
+}
+
+pub fn parse_encoding(encoding: &str, escape_char: char) -> io::Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+    let mut chars = encoding.chars().peekable();
+    let bad = || invalid(format!("invalid encoding '{encoding}'"));
+
+    while let Some(&c) = chars.peek() {
+        if c == escape_char {
+            chars.next(); // consume escape char
+            match chars.next() {
+                Some('d') => {
+                    let num: String = chars.by_ref().take(3).collect();
+                    bytes.push(num.parse::<u8>().map_err(|_| bad())?);
+                }
+                Some('x') => {
+                    let num: String = chars.by_ref().take(2).collect();
+                    bytes.push(u8::from_str_radix(&num, 16).map_err(|_| bad())?);
+                }
+                Some(c) if c.is_digit(8) => {
+                    let num: String = std::iter::once(c).chain(chars.by_ref().take(2)).collect();
+                    bytes.push(u8::from_str_radix(&num, 8).map_err(|_| bad())?);
+                }
+                _ => return Err(bad()),
+            }
+        } else {
+            chars.next(); // consume char
+        }
+    }
+
+    Ok(bytes)
+}
+
+/// Split a symbolic name into a prefix and the number ending it.
+fn split_name(name: &str, radix: u32) -> Option<(&str, u32, usize)> {
+    let prefix = name.trim_end_matches(|c: char| c.is_digit(radix));
+    let digits = &name[prefix.len()..];
+    let value = u32::from_str_radix(digits, radix).ok()?;
+    Some((prefix, value, digits.len()))
+}
+
+/// The symbolic names of the range `first` to `last`, inclusive.  The
+/// names end in hexadecimal numbers for a `..` range and for `<Uxxxx>`
+/// names (glibc style), and in decimal numbers otherwise (XBD 6.4).
+pub fn expand_range(first: &str, last: &str, ellipsis: &str) -> io::Result<Vec<String>> {
+    let bad = || invalid(format!("invalid range <{first}>{ellipsis}<{last}>"));
+    let unicode = first.len() > 1
+        && first.starts_with('U')
+        && first[1..].chars().all(|c| c.is_ascii_hexdigit());
+    let radix = if ellipsis == ".." || unicode { 16 } else { 10 };
+    let (prefix, lo, width) = split_name(first, radix).ok_or_else(bad)?;
+    let (last_prefix, hi, _) = split_name(last, radix).ok_or_else(bad)?;
+    if prefix != last_prefix || hi < lo {
+        return Err(bad());
+    }
+    Ok((lo..=hi)
+        .map(|n| match radix {
+            16 => format!("{prefix}{n:0width$X}"),
+            _ => format!("{prefix}{n:0width$}"),
+        })
+        .collect())
+}
+
+/// Add `n` to an encoding taken as a big-endian number, as the
+/// encodings of a range are consecutive.
+fn add_to_encoding(encoding: &[u8], n: usize) -> Vec<u8> {
+    let mut out = encoding.to_vec();
+    let mut carry = n;
+    for byte in out.iter_mut().rev() {
+        if carry == 0 {
+            break;
+        }
+        let sum = *byte as usize + carry;
+        *byte = sum as u8;
+        carry = sum >> 8;
+    }
+    out
+}
+
+/// The names defined by the first field of a charmap line: a single
+/// `<name>` or a range `<first>...<last>`.
+fn line_names(field: &str) -> io::Result<Vec<String>> {
+    let trim = |s: &str| s.trim_matches(|c| c == '<' || c == '>').to_string();
+    let range = match field.split_once("...") {
+        Some(range) => Some((range, "...")),
+        None => field.split_once("..").map(|range| (range, "..")),
+    };
+    match range {
+        Some(((first, last), ellipsis)) => expand_range(&trim(first), &trim(last), ellipsis),
+        None => Ok(vec![trim(field)]),
+    }
+}
+
+fn header_value<'a>(line: &'a str, keyword: &str) -> io::Result<&'a str> {
+    line.split_whitespace()
+        .nth(1)
+        .ok_or_else(|| invalid(format!("missing value for {keyword}")))
+}
+
+fn header_char(line: &str, keyword: &str) -> io::Result<char> {
+    header_value(line, keyword)?
+        .chars()
+        .next()
+        .ok_or_else(|| invalid(format!("missing value for {keyword}")))
+}
+
+fn header_number(line: &str, keyword: &str) -> io::Result<usize> {
+    header_value(line, keyword)?
+        .parse()
+        .map_err(|_| invalid(format!("invalid value for {keyword}")))
+}
+
+fn read_charmap(reader: impl BufRead) -> io::Result<Charmap> {
+    let mut charmap = Charmap {
+        header: CharmapHeader::default(),
+        entries: HashMap::new(),
+        width_entries: HashMap::new(),
+        width_default: 1,
+    };
+
+    let mut in_charmap_section = false;
+    let mut in_width_section = false;
+
+    for line in reader.lines() {
+        let line = line?;
+        let trimmed = line.trim();
+
+        if trimmed.is_empty() || trimmed.starts_with(charmap.header.comment_char) {
+            continue;
+        }
+
+        if !in_charmap_section && !in_width_section {
+            if trimmed.starts_with("<code_set_name>") {
+                charmap.header.code_set_name =
+                    Some(header_value(trimmed, "code_set_name")?.to_string());
+            } else if trimmed.starts_with("<mb_cur_max>") {
+                charmap.header.mb_cur_max = header_number(trimmed, "mb_cur_max")?;
+            } else if trimmed.starts_with("<mb_cur_min>") {
+                charmap.header.mb_cur_min = header_number(trimmed, "mb_cur_min")?;
+            } else if trimmed.starts_with("<escape_char>") {
+                charmap.header.escape_char = header_char(trimmed, "escape_char")?;
+            } else if trimmed.starts_with("<comment_char>") {
+                charmap.header.comment_char = header_char(trimmed, "comment_char")?;
+            } else if trimmed == "CHARMAP" {
+                in_charmap_section = true;
+            } else if trimmed == "WIDTH" {
+                in_width_section = true;
+            } else if trimmed.starts_with("WIDTH_DEFAULT") {
+                charmap.width_default = header_number(trimmed, "WIDTH_DEFAULT")?;
+            }
+        } else if in_charmap_section {
+            if trimmed == "END CHARMAP" {
+                in_charmap_section = false;
+                continue;
+            }
+            let mut fields = trimmed.split_whitespace();
+            let (Some(names), Some(encoding)) = (fields.next(), fields.next()) else {
+                continue;
+            };
+            let names = line_names(names)?;
+            let encoding = parse_encoding(encoding, charmap.header.escape_char)?;
+            let comments = Some(fields.collect::<Vec<_>>().join(" ")).filter(|c| !c.is_empty());
+            for (i, symbolic_name) in names.into_iter().enumerate() {
+                charmap.entries.insert(
+                    symbolic_name.clone(),
+                    CharmapEntry {
+                        symbolic_name,
+                        encoding: add_to_encoding(&encoding, i),
+                        _comments: comments.clone(),
+                    },
+                );
+            }
+        } else if trimmed == "END WIDTH" {
+            in_width_section = false;
+        } else {
+            let parts: Vec<&str> = trimmed.split_whitespace().collect();
+            if parts.len() >= 2 {
+                let width = parts[1]
+                    .parse()
+                    .map_err(|_| invalid(format!("invalid width '{}'", parts[1])))?;
+                for symbolic_name in line_names(parts[0])? {
+                    charmap.width_entries.insert(symbolic_name, width);
+                }
+            }
+        }
+    }
+
+    Ok(charmap)
+}
+
+/// Parse the charmap file at `path`, which may be gzip-compressed.
+pub fn parse_charmap(path: &Path) -> io::Result<Charmap> {
+    let file = File::open(path)?;
+    let reader: Box<dyn Read> = if path.extension().is_some_and(|ext| ext == "gz") {
+        Box::new(GzDecoder::new(file))
+    } else {
+        Box::new(file)
+    };
+    read_charmap(BufReader::new(reader))
+        .map_err(|e| io::Error::new(e.kind(), format!("{}: {}", path.display(), e)))
+}
+
+/// Path of the charmap `name`: a name containing a slash is used as a
+/// path as-is, any other is looked up in the charmap directories, with
+/// or without a `.gz` suffix.
+pub fn find_charmap(name: &str) -> Option<PathBuf> {
+    if name.contains('/') {
+        let path = PathBuf::from(name);
+        return path.is_file().then_some(path);
+    }
+    charmap_dirs()
+        .into_iter()
+        .flat_map(|dir| [dir.join(name), dir.join(format!("{name}.gz"))])
+        .find(|path| path.is_file())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_encoding() {
+        assert_eq!(
+            parse_encoding("/xe3/x90/x80", '/').unwrap(),
+            b"\xe3\x90\x80"
+        );
+        assert_eq!(parse_encoding("\\d065\\101", '\\').unwrap(), b"AA");
+        assert!(parse_encoding("/q1", '/').is_err());
+    }
+
+    #[test]
+    fn test_read_charmap() {
+        let text = "<code_set_name> TEST\n<comment_char> %\n<escape_char> /\n\
+                    <mb_cur_max> 3\n% a comment\nCHARMAP\n\
+                    <U0041>..<U0043> /x41 LATIN CAPITAL LETTERS\n\
+                    <j0101>...<j0103>\t/xe3/x90/xff\n\
+                    <space> /x20\nEND CHARMAP\n\
+                    WIDTH\n<j0101>...<j0102> 2\nEND WIDTH\n";
+        let charmap = read_charmap(text.as_bytes()).unwrap();
+        assert_eq!(charmap.header.code_set_name.as_deref(), Some("TEST"));
+        assert_eq!(charmap.header.mb_cur_max, 3);
+        assert_eq!(charmap.entries.len(), 7);
+        assert_eq!(charmap.entries["U0043"].encoding, b"C");
+        assert_eq!(charmap.entries["j0103"].encoding, b"\xe3\x91\x01");
+        assert_eq!(charmap.entries["space"].encoding, b" ");
+        assert_eq!(charmap.width_entries["j0102"], 2);
+        assert!(!charmap.width_entries.contains_key("j0103"));
+    }
+}
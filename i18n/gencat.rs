@@ -1,10 +1,12 @@
-use byteorder::{BigEndian, ByteOrder, LittleEndian, NativeEndian, WriteBytesExt};
+use byteorder::{BigEndian, WriteBytesExt};
+#[cfg(not(target_os = "macos"))]
+use byteorder::{LittleEndian, NativeEndian};
 use clap::Parser;
 use gettextrs::{bind_textdomain_codeset, gettext, setlocale, textdomain, LocaleCategory};
 use plib::io::input_stream;
+use plib::nlcat::Catalog;
 use std::{
     cell::RefCell,
-    collections::BTreeMap,
     fmt::Display,
     fs::File,
     io::{self, Cursor, Read, Seek, Write},
@@ -15,6 +17,7 @@ use std::{
 
 const NL_SETMAX: u32 = 255; //max set number(the limits.h defines it and is mentioned in POSIX specification)
 const NL_SETD: u32 = 1; // the default set number for the messages that are not in any set
+#[cfg(not(target_os = "macos"))]
 const GLIBC_MAGIC: u32 = 0x960408de;
 
 #[cfg(target_os = "macos")]
@@ -33,8 +36,17 @@ struct Args {
     #[arg(help = gettext("A pathname of the formatted message catalog"))]
     catfile: PathBuf,
 
-    #[arg(help = gettext("A pathname of a message text source file"))]
-    msgfile: PathBuf,
+    #[arg(
+        required_unless_present = "dump",
+        help = gettext("A pathname of a message text source file")
+    )]
+    msgfile: Option<PathBuf>,
+
+    #[arg(
+        long,
+        help = gettext("Print the catalog file as message text source instead of generating it")
+    )]
+    dump: bool,
 }
 
 /// In memory representation of a message
@@ -255,6 +267,49 @@ impl Display for ParseError {
 
 impl std::error::Error for ParseError {}
 
+/// Replace the escape sequences of a message text: `\n`, `\t`, `\v`,
+/// `\b`, `\r`, `\f`, `\\` and octal `\ddd`; a backslash before any
+/// other character, such as the quote character, stands for that
+/// character.
+fn unescape(msg: &str) -> String {
+    let mut bytes = Vec::with_capacity(msg.len());
+    let mut chars = msg.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            let mut buf = [0; 4];
+            bytes.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+            continue;
+        }
+        match chars.next() {
+            Some('n') => bytes.push(b'\n'),
+            Some('t') => bytes.push(b'\t'),
+            Some('v') => bytes.push(0x0b),
+            Some('b') => bytes.push(0x08),
+            Some('r') => bytes.push(b'\r'),
+            Some('f') => bytes.push(0x0c),
+            Some(d @ '0'..='7') => {
+                let mut value = d.to_digit(8).unwrap();
+                for _ in 0..2 {
+                    match chars.peek().and_then(|c| c.to_digit(8)) {
+                        Some(digit) => {
+                            value = value * 8 + digit;
+                            chars.next();
+                        }
+                        None => break,
+                    }
+                }
+                bytes.push(value as u8);
+            }
+            Some(other) => {
+                let mut buf = [0; 4];
+                bytes.extend_from_slice(other.encode_utf8(&mut buf).as_bytes());
+            }
+            None => bytes.push(b'\\'),
+        }
+    }
+    String::from_utf8_lossy(&bytes).to_string()
+}
+
 /// For set if it's $set NUMBER #COMMENT
 impl MessageCatalog {
     pub fn new(
@@ -410,7 +465,7 @@ impl MessageCatalog {
                 let msg_id = parts[0].parse::<usize>()?;
                 let msg = parts[1].to_string();
                 let msg = if let Some(q) = quote_char {
-                    if msg.starts_with(q) && msg.ends_with(q) {
+                    if msg.len() > 1 && msg.starts_with(q) && msg.ends_with(q) {
                         msg[1..msg.len() - 1].to_string()
                     } else {
                         msg
@@ -418,6 +473,7 @@ impl MessageCatalog {
                 } else {
                     msg
                 };
+                let msg = unescape(&msg);
 
                 catalog.add_msg(current_set.as_ref().unwrap(), msg_id, msg);
             }
@@ -552,73 +608,23 @@ impl MessageCatalog {
         }
     }
 
-    /// Read the **GNU based** binary catalog file and build [MessageCatalog]
-    #[allow(clippy::needless_range_loop)]
+    /// Read a binary catalog file in either layout and build [MessageCatalog]
     pub fn read_catfile<T: Read>(
         mut input: T,
     ) -> Result<MessageCatalog, Box<dyn std::error::Error>> {
-        let mut catalog = MessageCatalog::new(false);
-        let mut set_msg: BTreeMap<u32, BTreeMap<u32, String>> = BTreeMap::new();
-
         let mut buf: Vec<u8> = Vec::new();
         input.read_to_end(&mut buf)?;
-        let mut ptr = 0;
-
-        let header = NativeEndian::read_u32(&buf[ptr..(ptr + 4)]);
-        ptr += 4;
-
-        if header != GLIBC_MAGIC {
-            panic!("DOESNT MATCH");
-        }
-
-        let plane_size = NativeEndian::read_u32(&buf[ptr..(ptr + 4)]);
-        ptr += 4;
-
-        let plane_depth = NativeEndian::read_u32(&buf[ptr..(ptr + 4)]);
-        ptr += 4;
-
-        // little-endian array
-        let array_size = (plane_size * plane_depth * 3) as usize;
-        let mut le_array = vec![0u32; array_size];
-        for i in 0..array_size {
-            le_array[i] = LittleEndian::read_u32(&buf[ptr..(ptr + 4)]);
-            ptr += 4;
-        }
-
-        let mut be_array = vec![0u32; array_size];
-        for i in 0..array_size {
-            be_array[i] = BigEndian::read_u32(&buf[ptr..(ptr + 4)]);
-            ptr += 4;
-        }
-
-        // TODO: probably compare be_array and le_array as they should be the same
-
-        let string_pool = &buf[ptr..];
-
-        // Process the array to reconstruct sets and messages
-        for i in (0..array_size).step_by(3) {
-            if le_array[i] != 0 {
-                // as we already had incremented the set_id by 1 while inserting
-                // we will be now reducing it by 1 for now
-                let set_id = le_array[i] - 1;
-                let msg_id = le_array[i + 1];
-                let string_offset = le_array[i + 2] as usize;
-
-                let msg_end = string_pool[string_offset..]
-                    .iter()
-                    .position(|&c| c == 0)
-                    .unwrap_or(string_pool.len() - string_offset)
-                    + string_offset;
-
-                let msg = String::from_utf8_lossy(&string_pool[string_offset..msg_end]).to_string();
-                set_msg.entry(set_id).or_default().insert(msg_id, msg);
-            }
-        }
+        let parsed = Catalog::parse(&buf)?;
 
-        for (set_id, messages) in set_msg.iter() {
-            let set = catalog.add_set(*set_id, String::new());
-            for (msg_id, msg) in messages.iter() {
-                catalog.add_msg(&set, *msg_id as usize, msg.clone());
+        let mut catalog = MessageCatalog::new(false);
+        for (set_id, messages) in parsed.sets() {
+            let set = catalog.add_set(set_id, String::new());
+            for (msg_id, msg) in messages {
+                catalog.add_msg(
+                    &set,
+                    *msg_id as usize,
+                    String::from_utf8_lossy(msg).to_string(),
+                );
             }
         }
 
@@ -780,6 +786,27 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     let args = Args::parse();
 
+    if args.dump {
+        let catalog = if args.catfile.as_os_str() == "-" {
+            let mut buf = Vec::new();
+            io::stdin().read_to_end(&mut buf)?;
+            Catalog::parse(&buf)
+        } else {
+            Catalog::open(&args.catfile)
+        };
+        match catalog {
+            Ok(catalog) => {
+                catalog.write_msg(&mut io::stdout().lock())?;
+                return Ok(());
+            }
+            Err(err) => {
+                eprintln!("gencat: {}: {}", args.catfile.display(), err);
+                std::process::exit(1);
+            }
+        }
+    }
+    let msgfile = args.msgfile.expect("message file");
+
     let mut exit_code = 0;
 
     // the parsed catalog file, if it exists(which surely doesn't if we provide "-" a stdout)
@@ -792,7 +819,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         catfile_catalog = Some(MessageCatalog::read_catfile(catfile_catalog_file)?);
     }
 
-    match MessageCatalog::parse(&args.msgfile, catfile_catalog) {
+    match MessageCatalog::parse(&msgfile, catfile_catalog) {
         Ok(catalog) => {
             let mut buffer = Cursor::new(Vec::new());
             catalog.write_catfile(&mut buffer)?;
@@ -0,0 +1,130 @@
+//
+// Copyright (c) 2024 Hemi Labs, Inc.
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+//! gettext - retrieve text string from messages object
+
+mod gettext_lib;
+
+use std::io::{self, Write};
+
+use clap::Parser;
+use gettext_lib::{expand_escapes, text_domain};
+use gettextrs::{
+    bind_textdomain_codeset, dcgettext, gettext, setlocale, textdomain, LocaleCategory,
+};
+
+/// gettext - retrieve text string from messages object
+#[derive(Parser)]
+#[command(version, about = gettext("gettext - retrieve text string from messages object"))]
+struct Args {
+    #[arg(
+        short = 'd',
+        long = "domain",
+        value_name = "TEXTDOMAIN",
+        help = gettext("Retrieve the translations from this text domain")
+    )]
+    domain: Option<String>,
+
+    #[arg(
+        short = 'e',
+        overrides_with = "no_escapes",
+        help = gettext("Expand escape sequences in the message identifiers")
+    )]
+    escapes: bool,
+
+    #[arg(
+        short = 'E',
+        overrides_with = "escapes",
+        help = gettext("Do not expand escape sequences (the default)")
+    )]
+    no_escapes: bool,
+
+    #[arg(short = 'n', help = gettext("With -s, do not write a trailing newline"))]
+    no_newline: bool,
+
+    #[arg(
+        short = 's',
+        help = gettext("Translate each argument and write them separated by spaces, like echo")
+    )]
+    echo: bool,
+
+    #[arg(help = gettext("[TEXTDOMAIN] MSGID, or MSGID... with -s"))]
+    args: Vec<String>,
+}
+
+/// Translate `msgid` in `domain`, expanding escapes first if asked.
+/// Returns the translation and whether a `\c` escape ended the output.
+fn translate(domain: Option<&str>, msgid: &str, escapes: bool) -> (Vec<u8>, bool) {
+    let (msgid, stop) = if escapes {
+        expand_escapes(msgid)
+    } else {
+        (msgid.as_bytes().to_vec(), false)
+    };
+    // The empty msgid would retrieve the catalog header, and escapes may
+    // leave a msgid that is not text, which no catalog translates.
+    let translation = match (domain, String::from_utf8(msgid)) {
+        (Some(domain), Ok(msgid)) if !msgid.is_empty() => {
+            dcgettext(Some(domain), msgid, LocaleCategory::LcMessages).into_bytes()
+        }
+        (_, Ok(msgid)) => msgid.into_bytes(),
+        (_, Err(err)) => err.into_bytes(),
+    };
+    (translation, stop)
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    setlocale(LocaleCategory::LcAll, "");
+    textdomain("posixutils-rs")?;
+    bind_textdomain_codeset("posixutils-rs", "UTF-8")?;
+
+    let args = Args::parse();
+
+    let mut output = Vec::new();
+    let mut newline = false;
+    if args.echo {
+        let domain = text_domain(args.domain);
+        let mut stopped = false;
+        for (i, msgid) in args.args.iter().enumerate() {
+            if i > 0 {
+                output.push(b' ');
+            }
+            let (translation, stop) = translate(domain.as_deref(), msgid, args.escapes);
+            output.extend_from_slice(&translation);
+            if stop {
+                stopped = true;
+                break;
+            }
+        }
+        newline = !args.no_newline && !stopped;
+    } else {
+        let (domain, msgid) = match &args.args[..] {
+            [msgid] => (args.domain, msgid),
+            [domain, msgid] => (Some(domain.clone()), msgid),
+            [] => {
+                eprintln!("gettext: {}", gettext("missing arguments"));
+                std::process::exit(1);
+            }
+            _ => {
+                eprintln!("gettext: {}", gettext("too many arguments"));
+                std::process::exit(1);
+            }
+        };
+        let domain = text_domain(domain);
+        output = translate(domain.as_deref(), msgid, args.escapes).0;
+    }
+
+    let mut stdout = io::stdout().lock();
+    stdout.write_all(&output)?;
+    if newline {
+        stdout.write_all(b"\n")?;
+    }
+    stdout.flush()?;
+
+    std::process::exit(0)
+}
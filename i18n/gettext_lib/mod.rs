@@ -0,0 +1,101 @@
+//
+// Copyright (c) 2024 Hemi Labs, Inc.
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+//! Code shared by the `gettext` and `ngettext` utilities.
+
+use std::env;
+
+use gettextrs::bindtextdomain;
+
+/// The text domain to translate in: the `-d` option, else `$TEXTDOMAIN`.
+/// The domain's catalogs are read from `$TEXTDOMAINDIR` if it is set.
+pub fn text_domain(option: Option<String>) -> Option<String> {
+    let domain = option
+        .or_else(|| env::var("TEXTDOMAIN").ok())
+        .filter(|d| !d.is_empty())?;
+    if let Some(dir) = env::var_os("TEXTDOMAINDIR").filter(|d| !d.is_empty()) {
+        bindtextdomain(domain.as_str(), dir).ok()?;
+    }
+    Some(domain)
+}
+
+/// Expand the escape sequences of the `-e` option: `\a`, `\b`, `\f`,
+/// `\n`, `\r`, `\t`, `\v`, `\\` and `\0` followed by up to three octal
+/// digits giving the value of a byte, as far as they fit in one.  Returns
+/// the expanded bytes and whether a `\c` ended them.
+pub fn expand_escapes(s: &str) -> (Vec<u8>, bool) {
+    let mut out = Vec::with_capacity(s.len());
+    let mut bytes = s.bytes().peekable();
+    while let Some(b) = bytes.next() {
+        if b != b'\\' {
+            out.push(b);
+            continue;
+        }
+        match bytes.next() {
+            Some(b'a') => out.push(b'\x07'),
+            Some(b'b') => out.push(b'\x08'),
+            Some(b'c') => return (out, true),
+            Some(b'f') => out.push(b'\x0c'),
+            Some(b'n') => out.push(b'\n'),
+            Some(b'r') => out.push(b'\r'),
+            Some(b't') => out.push(b'\t'),
+            Some(b'v') => out.push(b'\x0b'),
+            Some(b'\\') => out.push(b'\\'),
+            Some(b'0') => {
+                let mut value: u8 = 0;
+                for _ in 0..3 {
+                    let next = bytes
+                        .peek()
+                        .filter(|b| (b'0'..=b'7').contains(b))
+                        .and_then(|b| value.checked_mul(8)?.checked_add(b - b'0'));
+                    match next {
+                        Some(next) => {
+                            value = next;
+                            bytes.next();
+                        }
+                        None => break,
+                    }
+                }
+                out.push(value);
+            }
+            Some(b) => out.extend_from_slice(&[b'\\', b]),
+            None => out.push(b'\\'),
+        }
+    }
+    (out, false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expand_escapes() {
+        assert_eq!(
+            expand_escapes(r"a\tb\\c\n\0101\q"),
+            (b"a\tb\\c\nA\\q".to_vec(), false)
+        );
+        assert_eq!(expand_escapes(r"stop\chere"), (b"stop".to_vec(), true));
+        assert_eq!(
+            expand_escapes("trailing\\"),
+            (b"trailing\\".to_vec(), false)
+        );
+        assert_eq!(expand_escapes("é"), ("é".as_bytes().to_vec(), false));
+    }
+
+    #[test]
+    fn test_expand_octal_escapes() {
+        // a single byte, not the UTF-8 encoding of U+00FF
+        assert_eq!(expand_escapes(r"\0377"), (vec![0xff], false));
+        assert_eq!(expand_escapes(r"\0200x"), (vec![0x80, b'x'], false));
+        // only the digits that fit in a byte
+        assert_eq!(expand_escapes(r"\0400"), (b" 0".to_vec(), false));
+        assert_eq!(expand_escapes(r"\0"), (vec![0], false));
+    }
+}
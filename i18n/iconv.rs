@@ -7,27 +7,31 @@
 // SPDX-License-Identifier: MIT
 //
 
+use charmap_lib::{find_charmap, parse_charmap, Charmap};
 use clap::Parser;
 use gettextrs::{bind_textdomain_codeset, gettext, setlocale, textdomain, LocaleCategory};
 use iconv_lib::{
-    ascii,
+    ascii, big5, code_pages, euc_jp, euc_kr,
+    gb18030::{self, GBVariant},
+    iso_2022_jp,
+    shift_jis::{self, ShiftJISVariant},
+    single_byte::{self, CodePage},
+    translit::{self, Transliterator},
     utf_16::{self, UTF16Variant},
     utf_32::{self, UTF32Variant},
     utf_8,
 };
 use plib::io::input_stream;
 use std::{
-    collections::HashMap,
     env,
-    fs::File,
-    io::{self, BufRead, BufReader, Read, Write},
+    io::{self, Read, Write},
     path::{Path, PathBuf},
     process::exit,
-    str::FromStr,
 };
 use strum::IntoEnumIterator;
-use strum_macros::{Display, EnumIter, EnumString};
+use strum_macros::{Display, EnumIter};
 
+mod charmap_lib;
 mod iconv_lib;
 
 #[derive(Parser)]
@@ -148,7 +152,7 @@ impl<R: Read> IntoIterator for CircularBuffer<R> {
 }
 
 #[allow(non_camel_case_types, clippy::upper_case_acronyms)]
-#[derive(EnumString, EnumIter, Debug, PartialEq, Display)]
+#[derive(EnumIter, Debug, PartialEq, Display)]
 #[strum(serialize_all = "SCREAMING-KEBAB-CASE")]
 enum Encodings {
     ASCII,
@@ -159,184 +163,210 @@ enum Encodings {
     UTF_32,
     UTF_32LE,
     UTF_32BE,
+    ISO_8859_1,
+    ISO_8859_2,
+    ISO_8859_3,
+    ISO_8859_4,
+    ISO_8859_5,
+    ISO_8859_6,
+    ISO_8859_7,
+    ISO_8859_8,
+    ISO_8859_9,
+    ISO_8859_10,
+    ISO_8859_11,
+    ISO_8859_13,
+    ISO_8859_14,
+    ISO_8859_15,
+    ISO_8859_16,
+    KOI8_R,
+    KOI8_U,
+    CP437,
+    CP850,
+    WINDOWS_1250,
+    WINDOWS_1251,
+    WINDOWS_1252,
+    WINDOWS_1253,
+    WINDOWS_1254,
+    WINDOWS_1255,
+    WINDOWS_1256,
+    WINDOWS_1257,
+    WINDOWS_1258,
+    #[strum(to_string = "SHIFT_JIS")]
+    SHIFT_JIS,
+    CP932,
+    EUC_JP,
+    ISO_2022_JP,
+    GBK,
+    GB18030,
+    BIG5,
+    EUC_KR,
+}
+
+/// Uppercase `name` and drop the separators, so that e.g. `latin1`,
+/// `ISO_8859-1` and `iso88591` compare equal to their canonical names.
+fn normalize_name(name: &str) -> String {
+    name.chars()
+        .filter(|c| !matches!(c, '-' | '_'))
+        .map(|c| c.to_ascii_uppercase())
+        .collect()
 }
 
 impl Encodings {
-    fn parse(encoding: &str) -> Self {
-        let cleaned_encoding = encoding.trim_matches('"');
-        match Encodings::from_str(cleaned_encoding) {
-            Ok(encoding) => encoding,
-            Err(_) => {
-                eprintln!("Error: Unknown encoding: {}", cleaned_encoding);
-                exit(1);
-            }
-        }
+    fn parse(encoding: &str) -> Option<Self> {
+        let name = normalize_name(encoding);
+        Encodings::iter().find(|encoding| {
+            normalize_name(&encoding.to_string()) == name
+                || encoding
+                    .aliases()
+                    .iter()
+                    .any(|alias| normalize_name(alias) == name)
+        })
     }
-}
 
-fn list_encodings() {
-    for encoding in Encodings::iter() {
-        println!("{:?}", encoding);
+    /// Other names the encoding is commonly known by.
+    fn aliases(&self) -> &'static [&'static str] {
+        match self {
+            Encodings::ASCII => &["US-ASCII", "ANSI_X3.4-1968", "646"],
+            Encodings::UTF_8 => &[],
+            Encodings::UTF_16 => &[],
+            Encodings::UTF_16LE => &[],
+            Encodings::UTF_16BE => &[],
+            Encodings::UTF_32 => &[],
+            Encodings::UTF_32LE => &[],
+            Encodings::UTF_32BE => &[],
+            Encodings::ISO_8859_1 => &["LATIN1", "L1", "IBM819", "CP819"],
+            Encodings::ISO_8859_2 => &["LATIN2", "L2"],
+            Encodings::ISO_8859_3 => &["LATIN3", "L3"],
+            Encodings::ISO_8859_4 => &["LATIN4", "L4"],
+            Encodings::ISO_8859_5 => &["CYRILLIC"],
+            Encodings::ISO_8859_6 => &["ARABIC"],
+            Encodings::ISO_8859_7 => &["GREEK", "GREEK8"],
+            Encodings::ISO_8859_8 => &["HEBREW"],
+            Encodings::ISO_8859_9 => &["LATIN5", "L5"],
+            Encodings::ISO_8859_10 => &["LATIN6", "L6"],
+            Encodings::ISO_8859_11 => &[],
+            Encodings::ISO_8859_13 => &["LATIN7", "L7"],
+            Encodings::ISO_8859_14 => &["LATIN8", "L8"],
+            Encodings::ISO_8859_15 => &["LATIN-9", "L9"],
+            Encodings::ISO_8859_16 => &["LATIN10", "L10"],
+            Encodings::KOI8_R => &[],
+            Encodings::KOI8_U => &[],
+            Encodings::CP437 => &["IBM437", "437"],
+            Encodings::CP850 => &["IBM850", "850"],
+            Encodings::WINDOWS_1250 => &["CP1250", "MS-EE"],
+            Encodings::WINDOWS_1251 => &["CP1251", "MS-CYRL"],
+            Encodings::WINDOWS_1252 => &["CP1252", "MS-ANSI"],
+            Encodings::WINDOWS_1253 => &["CP1253", "MS-GREEK"],
+            Encodings::WINDOWS_1254 => &["CP1254", "MS-TURK"],
+            Encodings::WINDOWS_1255 => &["CP1255", "MS-HEBR"],
+            Encodings::WINDOWS_1256 => &["CP1256", "MS-ARAB"],
+            Encodings::WINDOWS_1257 => &["CP1257", "WINBALTRIM"],
+            Encodings::WINDOWS_1258 => &["CP1258"],
+            Encodings::SHIFT_JIS => &["SJIS", "MS_KANJI", "CSSHIFTJIS"],
+            Encodings::CP932 => &["WINDOWS-31J", "MS932"],
+            Encodings::EUC_JP => &["UJIS"],
+            Encodings::ISO_2022_JP => &["CSISO2022JP"],
+            Encodings::GBK => &["CP936", "MS936", "WINDOWS-936"],
+            Encodings::GB18030 => &[],
+            Encodings::BIG5 => &["CN-BIG5", "CSBIG5"],
+            Encodings::EUC_KR => &["CSEUCKR"],
+        }
     }
-}
 
-#[derive(Debug, Default)]
-struct CharmapHeader {
-    code_set_name: Option<String>,
-    mb_cur_max: usize,
-    mb_cur_min: usize,
-    escape_char: char,
-    comment_char: char,
-}
+    /// The table of a single-byte encoding.
+    fn code_page(&self) -> Option<&'static CodePage> {
+        let table = match self {
+            Encodings::ISO_8859_1 => &code_pages::ISO_8859_1,
+            Encodings::ISO_8859_2 => &code_pages::ISO_8859_2,
+            Encodings::ISO_8859_3 => &code_pages::ISO_8859_3,
+            Encodings::ISO_8859_4 => &code_pages::ISO_8859_4,
+            Encodings::ISO_8859_5 => &code_pages::ISO_8859_5,
+            Encodings::ISO_8859_6 => &code_pages::ISO_8859_6,
+            Encodings::ISO_8859_7 => &code_pages::ISO_8859_7,
+            Encodings::ISO_8859_8 => &code_pages::ISO_8859_8,
+            Encodings::ISO_8859_9 => &code_pages::ISO_8859_9,
+            Encodings::ISO_8859_10 => &code_pages::ISO_8859_10,
+            Encodings::ISO_8859_11 => &code_pages::ISO_8859_11,
+            Encodings::ISO_8859_13 => &code_pages::ISO_8859_13,
+            Encodings::ISO_8859_14 => &code_pages::ISO_8859_14,
+            Encodings::ISO_8859_15 => &code_pages::ISO_8859_15,
+            Encodings::ISO_8859_16 => &code_pages::ISO_8859_16,
+            Encodings::KOI8_R => &code_pages::KOI8_R,
+            Encodings::KOI8_U => &code_pages::KOI8_U,
+            Encodings::CP437 => &code_pages::CP437,
+            Encodings::CP850 => &code_pages::CP850,
+            Encodings::WINDOWS_1250 => &code_pages::WINDOWS_1250,
+            Encodings::WINDOWS_1251 => &code_pages::WINDOWS_1251,
+            Encodings::WINDOWS_1252 => &code_pages::WINDOWS_1252,
+            Encodings::WINDOWS_1253 => &code_pages::WINDOWS_1253,
+            Encodings::WINDOWS_1254 => &code_pages::WINDOWS_1254,
+            Encodings::WINDOWS_1255 => &code_pages::WINDOWS_1255,
+            Encodings::WINDOWS_1256 => &code_pages::WINDOWS_1256,
+            Encodings::WINDOWS_1257 => &code_pages::WINDOWS_1257,
+            Encodings::WINDOWS_1258 => &code_pages::WINDOWS_1258,
+            _ => return None,
+        };
+        Some(table)
+    }
 
-#[derive(Debug)]
-struct CharmapEntry {
-    symbolic_name: String,
-    encoding: Vec<u8>,
-    _comments: Option<String>,
+    /// A test for whether the encoding can represent a code point.
+    fn encodable(&self) -> Box<dyn FnMut(u32) -> bool> {
+        match self {
+            Encodings::ASCII => Box::new(|code_point| code_point < 0x80),
+            Encodings::UTF_8
+            | Encodings::UTF_16
+            | Encodings::UTF_16LE
+            | Encodings::UTF_16BE
+            | Encodings::UTF_32
+            | Encodings::UTF_32LE
+            | Encodings::UTF_32BE => Box::new(|code_point| char::from_u32(code_point).is_some()),
+            Encodings::SHIFT_JIS => shift_jis::encodable(ShiftJISVariant::ShiftJIS),
+            Encodings::CP932 => shift_jis::encodable(ShiftJISVariant::CP932),
+            Encodings::EUC_JP => euc_jp::encodable(),
+            Encodings::ISO_2022_JP => iso_2022_jp::encodable(),
+            Encodings::GBK => gb18030::encodable(GBVariant::GBK),
+            Encodings::GB18030 => gb18030::encodable(GBVariant::GB18030),
+            Encodings::BIG5 => big5::encodable(),
+            Encodings::EUC_KR => euc_kr::encodable(),
+            single => single_byte::encodable(single.code_page().expect("single-byte encoding")),
+        }
+    }
 }
 
-#[derive(Debug)]
-struct Charmap {
-    header: CharmapHeader,
-    entries: HashMap<String, CharmapEntry>,
-    width_entries: HashMap<String, usize>,
-    width_default: usize,
+/// The suffixes of a target codeset name.
+#[derive(Debug, Default)]
+struct Suffixes {
+    /// `//TRANSLIT`: replace what the target cannot represent with a
+    /// transliteration
+    translit: bool,
+    /// `//IGNORE`: omit what the target cannot represent
+    ignore: bool,
 }
 
-fn parse_encoding(
-    encoding: &str,
-    escape_char: char,
-) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
-    let mut bytes = Vec::new();
-    let mut chars = encoding.chars().peekable();
-
-    while let Some(&c) = chars.peek() {
-        if c == escape_char {
-            chars.next(); // consume escape char
-            match chars.next() {
-                Some('d') => {
-                    let num: String = chars.by_ref().take(3).collect();
-                    bytes.push(num.parse::<u8>()?);
-                }
-                Some('x') => {
-                    let num: String = chars.by_ref().take(2).collect();
-                    bytes.push(u8::from_str_radix(&num, 16)?);
-                }
-                Some(c) if c.is_digit(8) => {
-                    let num: String = std::iter::once(c).chain(chars.by_ref().take(2)).collect();
-                    bytes.push(u8::from_str_radix(&num, 8)?);
-                }
-                _ => return Err("Invalid encoding format".into()),
-            }
-        } else {
-            chars.next(); // consume char
+/// Split the `//TRANSLIT` and `//IGNORE` suffixes off `codeset`, in any
+/// order and case.
+fn split_suffixes(codeset: &str) -> (&str, Suffixes) {
+    let mut codeset = codeset.trim_matches('"');
+    let mut suffixes = Suffixes::default();
+    while let Some((rest, suffix)) = codeset.rsplit_once("//") {
+        if suffix.eq_ignore_ascii_case("TRANSLIT") {
+            suffixes.translit = true;
+        } else if suffix.eq_ignore_ascii_case("IGNORE") {
+            suffixes.ignore = true;
+        } else if !suffix.is_empty() {
+            break;
         }
+        codeset = rest;
     }
-
-    Ok(bytes)
+    (codeset, suffixes)
 }
 
-fn parse_charmap(path: &Path) -> Result<Charmap, Box<dyn std::error::Error>> {
-    let file = File::open(path)?;
-    let reader = BufReader::new(file);
-    let mut charmap = Charmap {
-        header: CharmapHeader::default(),
-        entries: HashMap::new(),
-        width_entries: HashMap::new(),
-        width_default: 1,
-    };
-
-    let mut in_charmap_section = false;
-    let mut in_width_section = false;
-
-    for line in reader.lines() {
-        let line = line?;
-        let trimmed = line.trim();
-
-        if trimmed.is_empty() || trimmed.starts_with(charmap.header.comment_char) {
-            continue;
-        }
-
-        if !in_charmap_section && !in_width_section {
-            if trimmed.starts_with("<code_set_name>") {
-                charmap.header.code_set_name =
-                    Some(trimmed.split_whitespace().nth(1).unwrap().to_string());
-            } else if trimmed.starts_with("<mb_cur_max>") {
-                charmap.header.mb_cur_max = trimmed.split_whitespace().nth(1).unwrap().parse()?;
-            } else if trimmed.starts_with("<mb_cur_min>") {
-                charmap.header.mb_cur_min = trimmed.split_whitespace().nth(1).unwrap().parse()?;
-            } else if trimmed.starts_with("<escape_char>") {
-                charmap.header.escape_char = trimmed
-                    .split_whitespace()
-                    .nth(1)
-                    .unwrap()
-                    .chars()
-                    .next()
-                    .expect("Escape char is missing in charmap file")
-            } else if trimmed.starts_with("<comment_char>") {
-                charmap.header.comment_char = trimmed
-                    .split_whitespace()
-                    .nth(1)
-                    .unwrap()
-                    .chars()
-                    .next()
-                    .expect("Comment char is missing in charmap file")
-            } else if trimmed == "CHARMAP" {
-                in_charmap_section = true;
-            }
-        } else if in_charmap_section {
-            if trimmed == "END CHARMAP" {
-                in_charmap_section = false;
-            } else {
-                let mut parts = Vec::new();
-                let mut remaining = trimmed;
-
-                for _ in 0..3 {
-                    if remaining.is_empty() {
-                        break;
-                    }
-                    let part = remaining
-                        .trim_start()
-                        .split_once(' ')
-                        .map_or((remaining, ""), |(a, b)| (a, b));
-                    parts.push(part.0);
-                    remaining = part.1;
-                }
-
-                if parts.len() >= 2 {
-                    let symbolic_name = parts[0].trim_matches(|c| c == '<' || c == '>').to_string();
-                    let encoding = parse_encoding(parts[1], charmap.header.escape_char)?;
-                    let _comments = parts.get(2).map(|&s| s.to_string());
-                    charmap.entries.insert(
-                        symbolic_name.clone(),
-                        CharmapEntry {
-                            symbolic_name,
-                            encoding,
-                            _comments,
-                        },
-                    );
-                }
-            }
-        } else if in_width_section {
-            if trimmed == "END WIDTH" {
-                in_width_section = false;
-            } else {
-                let parts: Vec<&str> = trimmed.split_whitespace().collect();
-                if parts.len() >= 2 {
-                    let symbolic_name = parts[0].trim_matches(|c| c == '<' || c == '>').to_string();
-                    let width = parts[1].parse()?;
-                    charmap.width_entries.insert(symbolic_name, width);
-                }
-            }
-        } else if trimmed == "WIDTH" {
-            in_width_section = true;
-        } else if trimmed.starts_with("WIDTH_DEFAULT") {
-            charmap.width_default = trimmed.split_whitespace().nth(1).unwrap().parse()?;
-        }
+fn list_encodings() {
+    for encoding in Encodings::iter() {
+        let mut names = vec![encoding.to_string()];
+        names.extend(encoding.aliases().iter().map(|alias| alias.to_string()));
+        println!("{}", names.join(" "));
     }
-
-    Ok(charmap)
 }
 
 #[derive(Debug)]
@@ -345,11 +375,23 @@ enum CodesetType {
     Charmap(Charmap),
 }
 
+/// The codeset named `codeset`: a charmap file if the name contains a
+/// slash, else a built-in encoding, else a charmap of the charmap
+/// directories.
 fn parse_codeset(codeset: &str) -> Result<CodesetType, Box<dyn std::error::Error>> {
     if codeset.contains('/') {
-        Ok(CodesetType::Charmap(parse_charmap(Path::new(codeset))?))
-    } else {
-        Ok(CodesetType::Encoding(Encodings::parse(codeset)))
+        return Ok(CodesetType::Charmap(parse_charmap(Path::new(codeset))?));
+    }
+    let codeset = codeset.trim_matches('"');
+    if let Some(encoding) = Encodings::parse(codeset) {
+        return Ok(CodesetType::Encoding(encoding));
+    }
+    match find_charmap(codeset) {
+        Some(path) => Ok(CodesetType::Charmap(parse_charmap(&path)?)),
+        None => {
+            eprintln!("Error: Unknown encoding: {}", codeset);
+            exit(1);
+        }
     }
 }
 
@@ -359,6 +401,7 @@ fn encoding_conversion(
     input: CircularBuffer<Box<dyn Read>>,
     omit_invalid: bool,
     supress_error: bool,
+    suffixes: &Suffixes,
 ) {
     let iter = input.into_iter();
     let ucs4 = match from {
@@ -382,7 +425,32 @@ fn encoding_conversion(
             utf_32::to_ucs4(iter, omit_invalid, supress_error, UTF32Variant::UTF32BE)
         }
         Encodings::ASCII => ascii::to_ucs4(iter, omit_invalid, supress_error),
+        Encodings::SHIFT_JIS => {
+            shift_jis::to_ucs4(iter, omit_invalid, supress_error, ShiftJISVariant::ShiftJIS)
+        }
+        Encodings::CP932 => {
+            shift_jis::to_ucs4(iter, omit_invalid, supress_error, ShiftJISVariant::CP932)
+        }
+        Encodings::EUC_JP => euc_jp::to_ucs4(iter, omit_invalid, supress_error),
+        Encodings::ISO_2022_JP => iso_2022_jp::to_ucs4(iter, omit_invalid, supress_error),
+        Encodings::GBK => gb18030::to_ucs4(iter, omit_invalid, supress_error, GBVariant::GBK),
+        Encodings::GB18030 => {
+            gb18030::to_ucs4(iter, omit_invalid, supress_error, GBVariant::GB18030)
+        }
+        Encodings::BIG5 => big5::to_ucs4(iter, omit_invalid, supress_error),
+        Encodings::EUC_KR => euc_kr::to_ucs4(iter, omit_invalid, supress_error),
+        single => {
+            let table = single.code_page().expect("single-byte encoding");
+            single_byte::to_ucs4(iter, table, omit_invalid, supress_error)
+        }
+    };
+
+    let ucs4 = if suffixes.translit {
+        translit::transliterate(ucs4, Transliterator::new(), to.encodable())
+    } else {
+        ucs4
     };
+    let omit_invalid = omit_invalid || suffixes.ignore;
 
     let expected = match to {
         Encodings::UTF_8 => utf_8::from_ucs4(ucs4, omit_invalid, supress_error),
@@ -405,6 +473,24 @@ fn encoding_conversion(
             utf_32::from_ucs4(ucs4, omit_invalid, supress_error, UTF32Variant::UTF32BE)
         }
         Encodings::ASCII => ascii::from_ucs4(ucs4, omit_invalid, supress_error),
+        Encodings::SHIFT_JIS => {
+            shift_jis::from_ucs4(ucs4, omit_invalid, supress_error, ShiftJISVariant::ShiftJIS)
+        }
+        Encodings::CP932 => {
+            shift_jis::from_ucs4(ucs4, omit_invalid, supress_error, ShiftJISVariant::CP932)
+        }
+        Encodings::EUC_JP => euc_jp::from_ucs4(ucs4, omit_invalid, supress_error),
+        Encodings::ISO_2022_JP => iso_2022_jp::from_ucs4(ucs4, omit_invalid, supress_error),
+        Encodings::GBK => gb18030::from_ucs4(ucs4, omit_invalid, supress_error, GBVariant::GBK),
+        Encodings::GB18030 => {
+            gb18030::from_ucs4(ucs4, omit_invalid, supress_error, GBVariant::GB18030)
+        }
+        Encodings::BIG5 => big5::from_ucs4(ucs4, omit_invalid, supress_error),
+        Encodings::EUC_KR => euc_kr::from_ucs4(ucs4, omit_invalid, supress_error),
+        single => {
+            let table = single.code_page().expect("single-byte encoding");
+            single_byte::from_ucs4(ucs4, table, omit_invalid, supress_error)
+        }
     };
 
     expected.for_each(|byte| {
@@ -413,28 +499,64 @@ fn encoding_conversion(
     });
 }
 
+/// The `<Uxxxx>` symbolic name of `c`.
+fn unicode_name(c: char) -> String {
+    if (c as u32) > 0xffff {
+        format!("U{:08X}", c as u32)
+    } else {
+        format!("U{:04X}", c as u32)
+    }
+}
+
+/// The encoding in `to` of the transliteration of the character named
+/// `name`, which has to be a `<Uxxxx>` name for its code point to be
+/// known.
+fn charmap_translit(translit: &Transliterator, name: &str, to: &Charmap) -> Option<Vec<u8>> {
+    let c = name
+        .strip_prefix('U')
+        .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+        .and_then(char::from_u32)?;
+    let replacement =
+        translit.replacement(c, &mut |r| to.entries.contains_key(&unicode_name(r)))?;
+    Some(
+        replacement
+            .chars()
+            .flat_map(|r| to.entries[&unicode_name(r)].encoding.iter().copied())
+            .collect(),
+    )
+}
+
 fn charmap_conversion(
     from: &Charmap,
     to: &Charmap,
     input: CircularBuffer<Box<dyn Read>>,
     omit_invalid: bool,
     suppress_error: bool,
+    suffixes: &Suffixes,
 ) {
     let mut buffer = Vec::new();
     let stdout = io::stdout();
     let mut stdout = stdout.lock();
+    let translit = suffixes.translit.then(Transliterator::new);
 
     for byte in input {
         buffer.push(byte);
         let mut found = false;
         for entry in from.entries.values() {
             if buffer.starts_with(&entry.encoding) {
-                if let Some(to_entry) = to
+                let encoding = match to
                     .entries
                     .values()
                     .find(|e| e.symbolic_name == entry.symbolic_name)
                 {
-                    if let Err(e) = stdout.write_all(&to_entry.encoding) {
+                    Some(to_entry) => Some(to_entry.encoding.clone()),
+                    None => translit
+                        .as_ref()
+                        .and_then(|translit| charmap_translit(translit, &entry.symbolic_name, to))
+                        .or_else(|| suffixes.ignore.then(Vec::new)),
+                };
+                if let Some(encoding) = encoding {
+                    if let Err(e) = stdout.write_all(&encoding) {
                         eprintln!("Error writing to stdout: {}", e);
                     }
                     if let Err(e) = stdout.flush() {
@@ -511,8 +633,11 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             })
     });
 
-    let from_codeset = parse_codeset(&from_codeset)?;
-    let to_codeset = parse_codeset(&to_codeset)?;
+    // suffixes only matter for the target
+    let (from_codeset, _) = split_suffixes(&from_codeset);
+    let (to_codeset, suffixes) = split_suffixes(&to_codeset);
+    let from_codeset = parse_codeset(from_codeset)?;
+    let to_codeset = parse_codeset(to_codeset)?;
 
     let inputs: Vec<Box<dyn Read>> = match args.files {
         Some(files) => files
@@ -526,10 +651,24 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         let buf = CircularBuffer::new(input);
         match (&from_codeset, &to_codeset) {
             (CodesetType::Encoding(from), CodesetType::Encoding(to)) => {
-                encoding_conversion(from, to, buf, args.omit_invalid, args.suppress_messages);
+                encoding_conversion(
+                    from,
+                    to,
+                    buf,
+                    args.omit_invalid,
+                    args.suppress_messages,
+                    &suffixes,
+                );
             }
             (CodesetType::Charmap(from), CodesetType::Charmap(to)) => {
-                charmap_conversion(from, to, buf, args.omit_invalid, args.suppress_messages);
+                charmap_conversion(
+                    from,
+                    to,
+                    buf,
+                    args.omit_invalid,
+                    args.suppress_messages,
+                    &suffixes,
+                );
             }
             _ => {
                 eprintln!(
@@ -0,0 +1,96 @@
+//
+// Copyright (c) 2024 Jeff Garzik
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+//! Big5: ASCII, and two-byte characters with leads 0xa1 to 0xf9.
+
+use std::collections::HashMap;
+
+use super::big5_tables::BIG5;
+use super::multi_byte::{self, reverse_table, Bytes, Decoded, Decoder, Encoder};
+use super::single_byte::UNDEFINED;
+
+fn is_lead(byte: u8) -> bool {
+    (0xa1..=0xf9).contains(&byte)
+}
+
+fn is_trail(byte: u8) -> bool {
+    matches!(byte, 0x40..=0x7e | 0xa1..=0xfe)
+}
+
+struct Big5Decoder;
+
+impl Decoder for Big5Decoder {
+    fn decode<I: Iterator<Item = u8>>(&mut self, bytes: &mut Bytes<I>) -> Decoded {
+        let Some(byte) = bytes.next() else {
+            return Decoded::End;
+        };
+        match byte {
+            0x00..=0x80 => Decoded::Char(byte as u32),
+            lead if is_lead(lead) => {
+                let Some(trail) = bytes.next_in(is_trail) else {
+                    return Decoded::Invalid;
+                };
+                let trail_offset = if trail < 0x7f { 0x40 } else { 0x62 };
+                let pointer = (lead - 0xa1) as usize * 157 + (trail - trail_offset) as usize;
+                match BIG5[pointer] {
+                    UNDEFINED => Decoded::Invalid,
+                    code_point => Decoded::Char(code_point as u32),
+                }
+            }
+            _ => Decoded::Invalid,
+        }
+    }
+}
+
+struct Big5Encoder {
+    reverse: HashMap<u32, usize>,
+}
+
+impl Encoder for Big5Encoder {
+    fn encode(&mut self, code_point: u32, out: &mut Vec<u8>) -> bool {
+        if code_point <= 0x80 {
+            out.push(code_point as u8);
+        } else if let Some(&pointer) = self.reverse.get(&code_point) {
+            let (lead, trail) = (pointer / 157, pointer % 157);
+            let trail_offset = if trail < 0x3f { 0x40 } else { 0x62 };
+            out.extend([lead as u8 + 0xa1, (trail + trail_offset) as u8]);
+        } else {
+            return false;
+        }
+        true
+    }
+}
+
+// Convert Big5 to UCS-4
+pub fn to_ucs4<I: Iterator<Item = u8> + 'static>(
+    input: I,
+    omit_invalid: bool,
+    suppress_error: bool,
+) -> Box<dyn Iterator<Item = u32>> {
+    multi_byte::to_ucs4(input, Big5Decoder, omit_invalid, suppress_error)
+}
+
+fn encoder() -> Big5Encoder {
+    Big5Encoder {
+        reverse: reverse_table(&BIG5, |_| false),
+    }
+}
+
+pub fn encodable() -> Box<dyn FnMut(u32) -> bool> {
+    multi_byte::encodable(encoder())
+}
+
+// Convert UCS-4 to Big5
+pub fn from_ucs4<I: Iterator<Item = u32> + 'static>(
+    input: I,
+    omit_invalid: bool,
+    suppress_error: bool,
+) -> Box<dyn Iterator<Item = u8>> {
+    multi_byte::from_ucs4(input, encoder(), omit_invalid, suppress_error)
+}
@@ -0,0 +1,126 @@
+//
+// Copyright (c) 2024 Jeff Garzik
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+//! EUC-JP: ASCII and the C1 controls, JIS X 0208 in two bytes, half-width
+//! katakana behind SS2 and JIS X 0212 behind SS3.
+
+use std::collections::HashMap;
+
+use super::jis_tables::{JIS_X0208, JIS_X0212};
+use super::multi_byte::{self, reverse_table, Bytes, Decoded, Decoder, Encoder};
+use super::single_byte::UNDEFINED;
+
+const SS2: u8 = 0x8e;
+const SS3: u8 = 0x8f;
+
+fn is_gr(byte: u8) -> bool {
+    (0xa1..=0xfe).contains(&byte)
+}
+
+/// Look up the character at row and cell bytes `first` and `second` of
+/// a 94x94 set.
+fn lookup(table: &[u16], first: u8, second: u8) -> Decoded {
+    let code_point = table[(first - 0xa1) as usize * 94 + (second - 0xa1) as usize];
+    if code_point == UNDEFINED {
+        Decoded::Invalid
+    } else {
+        Decoded::Char(code_point as u32)
+    }
+}
+
+struct EucJPDecoder;
+
+impl Decoder for EucJPDecoder {
+    fn decode<I: Iterator<Item = u8>>(&mut self, bytes: &mut Bytes<I>) -> Decoded {
+        let Some(byte) = bytes.next() else {
+            return Decoded::End;
+        };
+        match byte {
+            SS2 => match bytes.next_in(|b| (0xa1..=0xdf).contains(&b)) {
+                Some(kana) => Decoded::Char(0xff61 + (kana - 0xa1) as u32),
+                None => Decoded::Invalid,
+            },
+            SS3 => {
+                let Some(first) = bytes.next_in(is_gr) else {
+                    return Decoded::Invalid;
+                };
+                match bytes.next_in(is_gr) {
+                    Some(second) => lookup(&JIS_X0212, first, second),
+                    None => Decoded::Invalid,
+                }
+            }
+            0x00..=0x9f => Decoded::Char(byte as u32),
+            first if is_gr(first) => match bytes.next_in(is_gr) {
+                Some(second) => lookup(&JIS_X0208, first, second),
+                None => Decoded::Invalid,
+            },
+            _ => Decoded::Invalid,
+        }
+    }
+}
+
+struct EucJPEncoder {
+    jis_x0208: HashMap<u32, usize>,
+    jis_x0212: HashMap<u32, usize>,
+}
+
+fn row_cell(pointer: usize) -> [u8; 2] {
+    [(pointer / 94) as u8 + 0xa1, (pointer % 94) as u8 + 0xa1]
+}
+
+impl Encoder for EucJPEncoder {
+    fn encode(&mut self, code_point: u32, out: &mut Vec<u8>) -> bool {
+        match code_point {
+            0x00..=0x9f if code_point != SS2 as u32 && code_point != SS3 as u32 => {
+                out.push(code_point as u8)
+            }
+            0xff61..=0xff9f => out.extend([SS2, (code_point - 0xff61) as u8 + 0xa1]),
+            _ => {
+                if let Some(&pointer) = self.jis_x0208.get(&code_point) {
+                    out.extend(row_cell(pointer));
+                } else if let Some(&pointer) = self.jis_x0212.get(&code_point) {
+                    out.push(SS3);
+                    out.extend(row_cell(pointer));
+                } else {
+                    return false;
+                }
+            }
+        }
+        true
+    }
+}
+
+// Convert EUC-JP to UCS-4
+pub fn to_ucs4<I: Iterator<Item = u8> + 'static>(
+    input: I,
+    omit_invalid: bool,
+    suppress_error: bool,
+) -> Box<dyn Iterator<Item = u32>> {
+    multi_byte::to_ucs4(input, EucJPDecoder, omit_invalid, suppress_error)
+}
+
+fn encoder() -> EucJPEncoder {
+    EucJPEncoder {
+        jis_x0208: reverse_table(&JIS_X0208, |_| false),
+        jis_x0212: reverse_table(&JIS_X0212, |_| false),
+    }
+}
+
+pub fn encodable() -> Box<dyn FnMut(u32) -> bool> {
+    multi_byte::encodable(encoder())
+}
+
+// Convert UCS-4 to EUC-JP
+pub fn from_ucs4<I: Iterator<Item = u32> + 'static>(
+    input: I,
+    omit_invalid: bool,
+    suppress_error: bool,
+) -> Box<dyn Iterator<Item = u8>> {
+    multi_byte::from_ucs4(input, encoder(), omit_invalid, suppress_error)
+}
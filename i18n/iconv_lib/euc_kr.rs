@@ -0,0 +1,88 @@
+//
+// Copyright (c) 2024 Jeff Garzik
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+//! EUC-KR: ASCII and the C1 controls, and KS X 1001 in two bytes.
+
+use std::collections::HashMap;
+
+use super::ksc_tables::KS_X1001;
+use super::multi_byte::{self, reverse_table, Bytes, Decoded, Decoder, Encoder};
+use super::single_byte::UNDEFINED;
+
+fn is_gr(byte: u8) -> bool {
+    (0xa1..=0xfe).contains(&byte)
+}
+
+struct EucKRDecoder;
+
+impl Decoder for EucKRDecoder {
+    fn decode<I: Iterator<Item = u8>>(&mut self, bytes: &mut Bytes<I>) -> Decoded {
+        let Some(byte) = bytes.next() else {
+            return Decoded::End;
+        };
+        match byte {
+            0x00..=0x9f => Decoded::Char(byte as u32),
+            first if is_gr(first) => {
+                let Some(second) = bytes.next_in(is_gr) else {
+                    return Decoded::Invalid;
+                };
+                match KS_X1001[(first - 0xa1) as usize * 94 + (second - 0xa1) as usize] {
+                    UNDEFINED => Decoded::Invalid,
+                    code_point => Decoded::Char(code_point as u32),
+                }
+            }
+            _ => Decoded::Invalid,
+        }
+    }
+}
+
+struct EucKREncoder {
+    reverse: HashMap<u32, usize>,
+}
+
+impl Encoder for EucKREncoder {
+    fn encode(&mut self, code_point: u32, out: &mut Vec<u8>) -> bool {
+        if code_point <= 0x9f {
+            out.push(code_point as u8);
+        } else if let Some(&pointer) = self.reverse.get(&code_point) {
+            out.extend([(pointer / 94) as u8 + 0xa1, (pointer % 94) as u8 + 0xa1]);
+        } else {
+            return false;
+        }
+        true
+    }
+}
+
+// Convert EUC-KR to UCS-4
+pub fn to_ucs4<I: Iterator<Item = u8> + 'static>(
+    input: I,
+    omit_invalid: bool,
+    suppress_error: bool,
+) -> Box<dyn Iterator<Item = u32>> {
+    multi_byte::to_ucs4(input, EucKRDecoder, omit_invalid, suppress_error)
+}
+
+fn encoder() -> EucKREncoder {
+    EucKREncoder {
+        reverse: reverse_table(&KS_X1001, |_| false),
+    }
+}
+
+pub fn encodable() -> Box<dyn FnMut(u32) -> bool> {
+    multi_byte::encodable(encoder())
+}
+
+// Convert UCS-4 to EUC-KR
+pub fn from_ucs4<I: Iterator<Item = u32> + 'static>(
+    input: I,
+    omit_invalid: bool,
+    suppress_error: bool,
+) -> Box<dyn Iterator<Item = u8>> {
+    multi_byte::from_ucs4(input, encoder(), omit_invalid, suppress_error)
+}
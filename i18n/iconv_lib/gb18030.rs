@@ -0,0 +1,173 @@
+//
+// Copyright (c) 2024 Jeff Garzik
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+//! GBK and GB18030.  GBK is ASCII, the euro sign at 0x80 and the
+//! two-byte area; GB18030 extends it with four-byte sequences that cover
+//! the rest of Unicode, and has the euro sign in two bytes only.
+
+use std::collections::HashMap;
+
+use super::gb_tables::{GB18030_RANGES, GB18030_TWO_BYTE};
+use super::multi_byte::{self, reverse_table, Bytes, Decoded, Decoder, Encoder};
+
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GBVariant {
+    GBK,
+    GB18030,
+}
+
+/// The pointer of the four-byte sequence for U+10000; the rest of the
+/// supplementary planes follow it in order.
+const SUPPLEMENTARY_POINTER: u32 = 189000;
+
+fn is_lead(byte: u8) -> bool {
+    (0x81..=0xfe).contains(&byte)
+}
+
+fn is_trail(byte: u8) -> bool {
+    matches!(byte, 0x40..=0x7e | 0x80..=0xfe)
+}
+
+fn is_digit(byte: u8) -> bool {
+    byte.is_ascii_digit()
+}
+
+fn four_byte_code_point(pointer: u32) -> Option<u32> {
+    if pointer >= SUPPLEMENTARY_POINTER {
+        let code_point = 0x10000 + pointer - SUPPLEMENTARY_POINTER;
+        return (code_point <= 0x10ffff).then_some(code_point);
+    }
+    let index = GB18030_RANGES.partition_point(|&(first, _, _)| first <= pointer);
+    let (first, last, code_point) = GB18030_RANGES[index.checked_sub(1)?];
+    (pointer <= last).then_some(code_point + pointer - first)
+}
+
+fn four_byte_pointer(code_point: u32) -> Option<u32> {
+    if code_point >= 0x10000 {
+        return Some(SUPPLEMENTARY_POINTER + code_point - 0x10000);
+    }
+    GB18030_RANGES
+        .iter()
+        .find(|&&(first, last, start)| (start..=start + last - first).contains(&code_point))
+        .map(|&(first, _, start)| first + code_point - start)
+}
+
+struct GBDecoder {
+    variant: GBVariant,
+}
+
+impl GBDecoder {
+    /// Decode the rest of a four-byte sequence, after the first two.
+    fn four_byte<I: Iterator<Item = u8>>(bytes: &mut Bytes<I>, first: u8, second: u8) -> Decoded {
+        let Some(third) = bytes.next_in(is_lead) else {
+            return Decoded::Invalid;
+        };
+        let Some(fourth) = bytes.next_in(is_digit) else {
+            return Decoded::Invalid;
+        };
+        let pointer = (((first - 0x81) as u32 * 10 + (second - 0x30) as u32) * 126
+            + (third - 0x81) as u32)
+            * 10
+            + (fourth - 0x30) as u32;
+        match four_byte_code_point(pointer) {
+            Some(code_point) => Decoded::Char(code_point),
+            None => Decoded::Invalid,
+        }
+    }
+}
+
+impl Decoder for GBDecoder {
+    fn decode<I: Iterator<Item = u8>>(&mut self, bytes: &mut Bytes<I>) -> Decoded {
+        let Some(byte) = bytes.next() else {
+            return Decoded::End;
+        };
+        match byte {
+            0x00..=0x7f => Decoded::Char(byte as u32),
+            0x80 if self.variant == GBVariant::GBK => Decoded::Char(0x20ac),
+            lead if is_lead(lead) => {
+                let gb18030 = self.variant == GBVariant::GB18030;
+                let Some(second) = bytes.next_in(|b| is_trail(b) || (gb18030 && is_digit(b)))
+                else {
+                    return Decoded::Invalid;
+                };
+                if is_digit(second) {
+                    return Self::four_byte(bytes, lead, second);
+                }
+                let trail_offset = if second < 0x7f { 0x40 } else { 0x41 };
+                let pointer = (lead - 0x81) as usize * 190 + (second - trail_offset) as usize;
+                Decoded::Char(GB18030_TWO_BYTE[pointer])
+            }
+            _ => Decoded::Invalid,
+        }
+    }
+}
+
+struct GBEncoder {
+    variant: GBVariant,
+    reverse: HashMap<u32, usize>,
+}
+
+impl Encoder for GBEncoder {
+    fn encode(&mut self, code_point: u32, out: &mut Vec<u8>) -> bool {
+        if code_point < 0x80 {
+            out.push(code_point as u8);
+        } else if code_point == 0x20ac && self.variant == GBVariant::GBK {
+            out.push(0x80);
+        } else if let Some(&pointer) = self.reverse.get(&code_point) {
+            let (lead, trail) = (pointer / 190, pointer % 190);
+            let trail_offset = if trail < 0x3f { 0x40 } else { 0x41 };
+            out.extend([lead as u8 + 0x81, (trail + trail_offset) as u8]);
+        } else if self.variant == GBVariant::GBK {
+            return false;
+        } else if let Some(pointer) = four_byte_pointer(code_point) {
+            out.extend([
+                (pointer / 12600) as u8 + 0x81,
+                (pointer / 1260 % 10) as u8 + 0x30,
+                (pointer / 10 % 126) as u8 + 0x81,
+                (pointer % 10) as u8 + 0x30,
+            ]);
+        } else {
+            return false;
+        }
+        true
+    }
+}
+
+// Convert GBK or GB18030 to UCS-4
+pub fn to_ucs4<I: Iterator<Item = u8> + 'static>(
+    input: I,
+    omit_invalid: bool,
+    suppress_error: bool,
+    variant: GBVariant,
+) -> Box<dyn Iterator<Item = u32>> {
+    let decoder = GBDecoder { variant };
+    multi_byte::to_ucs4(input, decoder, omit_invalid, suppress_error)
+}
+
+fn encoder(variant: GBVariant) -> GBEncoder {
+    GBEncoder {
+        variant,
+        reverse: reverse_table(&GB18030_TWO_BYTE, |_| false),
+    }
+}
+
+pub fn encodable(variant: GBVariant) -> Box<dyn FnMut(u32) -> bool> {
+    multi_byte::encodable(encoder(variant))
+}
+
+// Convert UCS-4 to GBK or GB18030
+pub fn from_ucs4<I: Iterator<Item = u32> + 'static>(
+    input: I,
+    omit_invalid: bool,
+    suppress_error: bool,
+    variant: GBVariant,
+) -> Box<dyn Iterator<Item = u8>> {
+    multi_byte::from_ucs4(input, encoder(variant), omit_invalid, suppress_error)
+}
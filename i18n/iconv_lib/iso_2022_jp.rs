@@ -0,0 +1,188 @@
+//
+// Copyright (c) 2024 Jeff Garzik
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+//! ISO-2022-JP (RFC 1468): a 7-bit encoding that switches between
+//! ASCII, JIS X 0201 Roman and JIS X 0208 with escape sequences.  The
+//! encoder switches only when needed and returns to ASCII at the end
+//! of every line and of the output.
+
+use std::collections::HashMap;
+
+use super::jis_tables::JIS_X0208;
+use super::multi_byte::{self, reverse_table, Bytes, Decoded, Decoder, Encoder};
+use super::single_byte::UNDEFINED;
+
+const ESC: u8 = 0x1b;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Charset {
+    Ascii,
+    Roman,
+    JisX0208,
+}
+
+impl Charset {
+    fn designation(self) -> &'static [u8] {
+        match self {
+            Charset::Ascii => b"\x1b(B",
+            Charset::Roman => b"\x1b(J",
+            Charset::JisX0208 => b"\x1b$B",
+        }
+    }
+}
+
+fn is_graphic(byte: u8) -> bool {
+    (0x21..=0x7e).contains(&byte)
+}
+
+struct Iso2022JPDecoder {
+    charset: Charset,
+}
+
+impl Iso2022JPDecoder {
+    /// Read the rest of an escape sequence and switch to the charset it
+    /// designates.
+    fn escape<I: Iterator<Item = u8>>(&mut self, bytes: &mut Bytes<I>) -> bool {
+        let charset = match bytes.next_in(|b| b == b'(' || b == b'$') {
+            Some(b'(') => match bytes.next_in(|b| b == b'B' || b == b'J') {
+                Some(b'B') => Charset::Ascii,
+                Some(_) => Charset::Roman,
+                None => return false,
+            },
+            Some(_) => match bytes.next_in(|b| b == b'@' || b == b'B') {
+                Some(_) => Charset::JisX0208,
+                None => return false,
+            },
+            None => return false,
+        };
+        self.charset = charset;
+        true
+    }
+}
+
+impl Decoder for Iso2022JPDecoder {
+    fn decode<I: Iterator<Item = u8>>(&mut self, bytes: &mut Bytes<I>) -> Decoded {
+        loop {
+            let Some(byte) = bytes.next() else {
+                return Decoded::End;
+            };
+            let code_point = match (self.charset, byte) {
+                (_, ESC) => {
+                    if self.escape(bytes) {
+                        continue;
+                    }
+                    return Decoded::Invalid;
+                }
+                (Charset::Roman, 0x5c) => 0xa5,
+                (Charset::Roman, 0x7e) => 0x203e,
+                (Charset::JisX0208, first) if is_graphic(first) => {
+                    let Some(second) = bytes.next_in(is_graphic) else {
+                        return Decoded::Invalid;
+                    };
+                    let pointer = (first - 0x21) as usize * 94 + (second - 0x21) as usize;
+                    JIS_X0208[pointer] as u32
+                }
+                (_, 0x00..=0x7f) => byte as u32,
+                _ => return Decoded::Invalid,
+            };
+            return if code_point == UNDEFINED as u32 {
+                Decoded::Invalid
+            } else {
+                Decoded::Char(code_point)
+            };
+        }
+    }
+}
+
+struct Iso2022JPEncoder {
+    charset: Charset,
+    reverse: HashMap<u32, usize>,
+}
+
+impl Iso2022JPEncoder {
+    fn switch(&mut self, charset: Charset, out: &mut Vec<u8>) {
+        if self.charset != charset {
+            out.extend(charset.designation());
+            self.charset = charset;
+        }
+    }
+}
+
+impl Encoder for Iso2022JPEncoder {
+    fn encode(&mut self, code_point: u32, out: &mut Vec<u8>) -> bool {
+        match code_point {
+            // an escape would change the charset behind the decoder's back
+            0x1b => return false,
+            // lines end in ASCII, and JIS X 0201 Roman has other
+            // characters at 0x5c and 0x7e
+            0x0a | 0x0d | 0x5c | 0x7e => {
+                self.switch(Charset::Ascii, out);
+                out.push(code_point as u8);
+            }
+            0x00..=0x7f => {
+                if self.charset == Charset::JisX0208 {
+                    self.switch(Charset::Ascii, out);
+                }
+                out.push(code_point as u8);
+            }
+            0xa5 => {
+                self.switch(Charset::Roman, out);
+                out.push(0x5c);
+            }
+            0x203e => {
+                self.switch(Charset::Roman, out);
+                out.push(0x7e);
+            }
+            _ => match self.reverse.get(&code_point) {
+                Some(&pointer) => {
+                    self.switch(Charset::JisX0208, out);
+                    out.extend([(pointer / 94) as u8 + 0x21, (pointer % 94) as u8 + 0x21]);
+                }
+                None => return false,
+            },
+        }
+        true
+    }
+
+    fn finish(&mut self, out: &mut Vec<u8>) {
+        self.switch(Charset::Ascii, out);
+    }
+}
+
+// Convert ISO-2022-JP to UCS-4
+pub fn to_ucs4<I: Iterator<Item = u8> + 'static>(
+    input: I,
+    omit_invalid: bool,
+    suppress_error: bool,
+) -> Box<dyn Iterator<Item = u32>> {
+    let decoder = Iso2022JPDecoder {
+        charset: Charset::Ascii,
+    };
+    multi_byte::to_ucs4(input, decoder, omit_invalid, suppress_error)
+}
+
+fn encoder() -> Iso2022JPEncoder {
+    Iso2022JPEncoder {
+        charset: Charset::Ascii,
+        reverse: reverse_table(&JIS_X0208, |_| false),
+    }
+}
+
+pub fn encodable() -> Box<dyn FnMut(u32) -> bool> {
+    multi_byte::encodable(encoder())
+}
+
+// Convert UCS-4 to ISO-2022-JP
+pub fn from_ucs4<I: Iterator<Item = u32> + 'static>(
+    input: I,
+    omit_invalid: bool,
+    suppress_error: bool,
+) -> Box<dyn Iterator<Item = u8>> {
+    multi_byte::from_ucs4(input, encoder(), omit_invalid, suppress_error)
+}
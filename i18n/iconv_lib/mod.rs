@@ -1,4 +1,18 @@
 pub mod ascii;
+pub mod big5;
+mod big5_tables;
+pub mod code_pages;
+pub mod euc_jp;
+pub mod euc_kr;
+pub mod gb18030;
+mod gb_tables;
+pub mod iso_2022_jp;
+mod jis_tables;
+mod ksc_tables;
+pub mod multi_byte;
+pub mod shift_jis;
+pub mod single_byte;
+pub mod translit;
 pub mod utf_16;
 pub mod utf_32;
 pub mod utf_8;
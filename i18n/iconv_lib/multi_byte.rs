@@ -0,0 +1,172 @@
+//
+// Copyright (c) 2024 Jeff Garzik
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+//! The driver shared by the multi-byte and stateful codecs.  A codec
+//! only decodes or encodes one character at a time; the driver keeps
+//! track of positions and deals with invalid input.  As the input is
+//! pulled a byte at a time, a character split between two reads of the
+//! input is decoded like any other.
+
+use std::{
+    collections::{HashMap, VecDeque},
+    iter::{self, Peekable},
+    process::exit,
+};
+
+use super::single_byte::UNDEFINED;
+
+/// The outcome of decoding one character.
+pub enum Decoded {
+    Char(u32),
+    Invalid,
+    End,
+}
+
+/// The input of a decoder, which may look at the byte after the ones it
+/// has taken.  A decoder leaves a byte that cannot continue the current
+/// character for the next one.
+pub struct Bytes<I: Iterator<Item = u8>> {
+    input: Peekable<I>,
+    position: usize,
+}
+
+impl<I: Iterator<Item = u8>> Bytes<I> {
+    pub fn next(&mut self) -> Option<u8> {
+        let byte = self.input.next();
+        if byte.is_some() {
+            self.position += 1;
+        }
+        byte
+    }
+
+    pub fn peek(&mut self) -> Option<u8> {
+        self.input.peek().copied()
+    }
+
+    /// Take the next byte if it is in `range`.
+    pub fn next_in(&mut self, range: impl Fn(u8) -> bool) -> Option<u8> {
+        match self.peek() {
+            Some(byte) if range(byte) => self.next(),
+            _ => None,
+        }
+    }
+}
+
+pub trait Decoder {
+    fn decode<I: Iterator<Item = u8>>(&mut self, bytes: &mut Bytes<I>) -> Decoded;
+}
+
+pub trait Encoder {
+    /// Append the encoding of `code_point` to `out`, or return false if
+    /// it has none.
+    fn encode(&mut self, code_point: u32, out: &mut Vec<u8>) -> bool;
+
+    /// Append what ends the output, such as a return to the initial
+    /// shift state.
+    fn finish(&mut self, _out: &mut Vec<u8>) {}
+}
+
+/// The reverse of a table indexed by pointer, skipping the pointers for
+/// which `skip` is true.  The first pointer of a code point wins.
+pub fn reverse_table<T: Copy + Into<u32>>(
+    table: &[T],
+    skip: impl Fn(usize) -> bool,
+) -> HashMap<u32, usize> {
+    let mut reverse = HashMap::with_capacity(table.len());
+    for (pointer, &code_point) in table.iter().enumerate() {
+        let code_point = code_point.into();
+        if code_point != UNDEFINED as u32 && !skip(pointer) {
+            reverse.entry(code_point).or_insert(pointer);
+        }
+    }
+    reverse
+}
+
+/// A test for whether `encoder` can encode a code point.
+pub fn encodable<E: Encoder + 'static>(mut encoder: E) -> Box<dyn FnMut(u32) -> bool> {
+    let mut out = Vec::new();
+    Box::new(move |code_point| {
+        out.clear();
+        encoder.encode(code_point, &mut out)
+    })
+}
+
+pub fn to_ucs4<I: Iterator<Item = u8> + 'static, D: Decoder + 'static>(
+    input: I,
+    mut decoder: D,
+    omit_invalid: bool,
+    suppress_error: bool,
+) -> Box<dyn Iterator<Item = u32>> {
+    let mut bytes = Bytes {
+        input: input.peekable(),
+        position: 0,
+    };
+
+    let iter = iter::from_fn(move || loop {
+        let start = bytes.position;
+        match decoder.decode(&mut bytes) {
+            Decoded::Char(code_point) => return Some(code_point),
+            Decoded::End => return None,
+            Decoded::Invalid if omit_invalid => continue,
+            Decoded::Invalid => {
+                if !suppress_error {
+                    eprintln!("Error: Invalid input position {}", start);
+                    exit(1)
+                }
+                return None;
+            }
+        }
+    });
+
+    Box::new(iter)
+}
+
+pub fn from_ucs4<I: Iterator<Item = u32> + 'static, E: Encoder + 'static>(
+    mut input: I,
+    mut encoder: E,
+    omit_invalid: bool,
+    suppress_error: bool,
+) -> Box<dyn Iterator<Item = u8>> {
+    let mut pending = VecDeque::new();
+    let mut position = 0;
+    let mut done = false;
+
+    let iter = iter::from_fn(move || loop {
+        if let Some(byte) = pending.pop_front() {
+            return Some(byte);
+        }
+        if done {
+            return None;
+        }
+        let mut out = Vec::new();
+        match input.next() {
+            Some(code_point) => {
+                position += 1;
+                if !encoder.encode(code_point, &mut out) {
+                    if omit_invalid {
+                        continue;
+                    }
+                    if !suppress_error {
+                        eprintln!("Error: Invalid input position {}", position - 1);
+                        exit(1)
+                    }
+                    encoder.finish(&mut out);
+                    done = true;
+                }
+            }
+            None => {
+                encoder.finish(&mut out);
+                done = true;
+            }
+        }
+        pending.extend(out);
+    });
+
+    Box::new(iter)
+}
@@ -0,0 +1,149 @@
+//
+// Copyright (c) 2024 Jeff Garzik
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+//! Shift_JIS, and its Microsoft variant CP932.  Shift_JIS proper takes
+//! its single bytes from JIS X 0201, where 0x5c is the yen sign and 0x7e
+//! the overline; CP932 keeps ASCII there, and adds the NEC and IBM
+//! extensions and user-defined characters to JIS X 0208.
+
+use std::collections::HashMap;
+
+use super::jis_tables::{CP932, JIS_X0208};
+use super::multi_byte::{self, reverse_table, Bytes, Decoded, Decoder, Encoder};
+use super::single_byte::UNDEFINED;
+
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShiftJISVariant {
+    ShiftJIS,
+    CP932,
+}
+
+/// The NEC-selected IBM extensions, which CP932 also has at 0xfa40 and
+/// up; those are preferred when encoding.
+const NEC_SELECTED_IBM: std::ops::RangeInclusive<usize> = 8272..=8835;
+
+fn is_lead(byte: u8) -> bool {
+    matches!(byte, 0x81..=0x9f | 0xe0..=0xfc)
+}
+
+fn is_trail(byte: u8) -> bool {
+    matches!(byte, 0x40..=0x7e | 0x80..=0xfc)
+}
+
+fn pointer(lead: u8, trail: u8) -> usize {
+    let lead_offset = if lead < 0xa0 { 0x81 } else { 0xc1 };
+    let trail_offset = if trail < 0x7f { 0x40 } else { 0x41 };
+    (lead - lead_offset) as usize * 188 + (trail - trail_offset) as usize
+}
+
+fn bytes(pointer: usize) -> [u8; 2] {
+    let (lead, trail) = (pointer / 188, pointer % 188);
+    let lead_offset = if lead < 0x1f { 0x81 } else { 0xc1 };
+    let trail_offset = if trail < 0x3f { 0x40 } else { 0x41 };
+    [(lead + lead_offset) as u8, (trail + trail_offset) as u8]
+}
+
+fn table(variant: ShiftJISVariant) -> &'static [u16] {
+    match variant {
+        ShiftJISVariant::ShiftJIS => &JIS_X0208,
+        ShiftJISVariant::CP932 => &CP932,
+    }
+}
+
+struct ShiftJISDecoder {
+    variant: ShiftJISVariant,
+}
+
+impl Decoder for ShiftJISDecoder {
+    fn decode<I: Iterator<Item = u8>>(&mut self, bytes: &mut Bytes<I>) -> Decoded {
+        let Some(byte) = bytes.next() else {
+            return Decoded::End;
+        };
+        let code_point = match (self.variant, byte) {
+            (ShiftJISVariant::ShiftJIS, 0x5c) => 0xa5,
+            (ShiftJISVariant::ShiftJIS, 0x7e) => 0x203e,
+            (_, 0x00..=0x7f) | (ShiftJISVariant::CP932, 0x80) => byte as u32,
+            (_, 0xa1..=0xdf) => 0xff61 + (byte - 0xa1) as u32,
+            (_, lead) if is_lead(lead) => {
+                let Some(trail) = bytes.next_in(is_trail) else {
+                    return Decoded::Invalid;
+                };
+                let table = table(self.variant);
+                match table.get(pointer(lead, trail)) {
+                    Some(&code_point) => code_point as u32,
+                    None => return Decoded::Invalid,
+                }
+            }
+            _ => return Decoded::Invalid,
+        };
+        if code_point == UNDEFINED as u32 {
+            Decoded::Invalid
+        } else {
+            Decoded::Char(code_point)
+        }
+    }
+}
+
+struct ShiftJISEncoder {
+    variant: ShiftJISVariant,
+    reverse: HashMap<u32, usize>,
+}
+
+impl Encoder for ShiftJISEncoder {
+    fn encode(&mut self, code_point: u32, out: &mut Vec<u8>) -> bool {
+        let shift_jis = self.variant == ShiftJISVariant::ShiftJIS;
+        match code_point {
+            0xa5 if shift_jis => out.push(0x5c),
+            0x203e if shift_jis => out.push(0x7e),
+            0x5c | 0x7e if shift_jis => return false,
+            0x00..=0x7f => out.push(code_point as u8),
+            0x80 if !shift_jis => out.push(0x80),
+            0xff61..=0xff9f => out.push((code_point - 0xff61) as u8 + 0xa1),
+            _ => match self.reverse.get(&code_point) {
+                Some(&pointer) => out.extend(bytes(pointer)),
+                None => return false,
+            },
+        }
+        true
+    }
+}
+
+// Convert Shift_JIS to UCS-4
+pub fn to_ucs4<I: Iterator<Item = u8> + 'static>(
+    input: I,
+    omit_invalid: bool,
+    suppress_error: bool,
+    variant: ShiftJISVariant,
+) -> Box<dyn Iterator<Item = u32>> {
+    let decoder = ShiftJISDecoder { variant };
+    multi_byte::to_ucs4(input, decoder, omit_invalid, suppress_error)
+}
+
+fn encoder(variant: ShiftJISVariant) -> ShiftJISEncoder {
+    let skip = |pointer| variant == ShiftJISVariant::CP932 && NEC_SELECTED_IBM.contains(&pointer);
+    ShiftJISEncoder {
+        variant,
+        reverse: reverse_table(table(variant), skip),
+    }
+}
+
+pub fn encodable(variant: ShiftJISVariant) -> Box<dyn FnMut(u32) -> bool> {
+    multi_byte::encodable(encoder(variant))
+}
+
+// Convert UCS-4 to Shift_JIS
+pub fn from_ucs4<I: Iterator<Item = u32> + 'static>(
+    input: I,
+    omit_invalid: bool,
+    suppress_error: bool,
+    variant: ShiftJISVariant,
+) -> Box<dyn Iterator<Item = u8>> {
+    multi_byte::from_ucs4(input, encoder(variant), omit_invalid, suppress_error)
+}
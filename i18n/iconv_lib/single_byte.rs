@@ -0,0 +1,100 @@
+//
+// Copyright (c) 2024 Jeff Garzik
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+//! Table-driven codecs for single-byte code pages whose lower half is
+//! ASCII, such as the ISO-8859 parts, KOI8 and the Windows code pages.
+
+use std::{collections::HashMap, iter, process::exit};
+
+/// The code points of the bytes 0x80 to 0xff.
+pub type CodePage = [u16; 128];
+
+/// Marks a byte that the code page leaves unassigned.
+pub const UNDEFINED: u16 = 0xffff;
+
+// Convert a single-byte code page to UCS-4
+pub fn to_ucs4<I: Iterator<Item = u8> + 'static>(
+    mut input: I,
+    table: &'static CodePage,
+    omit_invalid: bool,
+    suppress_error: bool,
+) -> Box<dyn Iterator<Item = u32>> {
+    let mut position = 0;
+
+    let iter = iter::from_fn(move || {
+        for byte in input.by_ref() {
+            position += 1;
+            let code_point = match byte {
+                0..=127 => byte as u32,
+                _ => table[byte as usize - 128] as u32,
+            };
+            if code_point != UNDEFINED as u32 {
+                return Some(code_point);
+            } else if omit_invalid {
+                continue;
+            } else if !suppress_error {
+                eprintln!("Error: Invalid input position {}", position - 1);
+                exit(1);
+            } else {
+                return None;
+            }
+        }
+        None
+    });
+
+    Box::new(iter)
+}
+
+/// The byte of each code point of the upper half of `table`.
+fn reverse(table: &CodePage) -> HashMap<u32, u8> {
+    table
+        .iter()
+        .enumerate()
+        .filter(|(_, &code_point)| code_point != UNDEFINED)
+        .map(|(i, &code_point)| (code_point as u32, i as u8 + 128))
+        .collect()
+}
+
+/// A test for whether a code point is in the code page.
+pub fn encodable(table: &'static CodePage) -> Box<dyn FnMut(u32) -> bool> {
+    let reverse = reverse(table);
+    Box::new(move |code_point| code_point <= 127 || reverse.contains_key(&code_point))
+}
+
+// Convert UCS-4 to a single-byte code page
+pub fn from_ucs4<I: Iterator<Item = u32> + 'static>(
+    mut input: I,
+    table: &'static CodePage,
+    omit_invalid: bool,
+    suppress_error: bool,
+) -> Box<dyn Iterator<Item = u8>> {
+    let reverse = reverse(table);
+    let mut position = 0;
+
+    let iter = iter::from_fn(move || {
+        for code_point in input.by_ref() {
+            position += 1;
+            if code_point <= 127 {
+                return Some(code_point as u8);
+            } else if let Some(&byte) = reverse.get(&code_point) {
+                return Some(byte);
+            } else if omit_invalid {
+                continue;
+            } else {
+                if !suppress_error {
+                    eprintln!("Error: Invalid input position {}", position - 1);
+                    exit(1)
+                }
+                return None;
+            }
+        }
+        None
+    });
+    Box::new(iter)
+}
@@ -0,0 +1,77 @@
+//
+// Copyright (c) 2024 Jeff Garzik
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+//! Transliteration for the `//TRANSLIT` suffix.  A character the target
+//! cannot represent is replaced by the first of its transliterations the
+//! target can represent in full.  The transliterations are looked up in
+//! LC_CTYPE of the current locale first, then in the built-in table, a
+//! `translit_start` section in locale definition syntax.
+
+use std::collections::VecDeque;
+use std::iter;
+
+use plib::locale::{self, Ctype, LocaleSource, SourceLoader};
+
+const TABLE: &str = include_str!("translit_table");
+
+pub struct Transliterator {
+    locale: &'static Ctype,
+    table: Ctype,
+}
+
+impl Transliterator {
+    pub fn new() -> Self {
+        let source = LocaleSource::parse(TABLE).expect("valid transliteration table");
+        let mut loader = SourceLoader::with_dirs(Vec::new());
+        let table = Ctype::from_source(&source, &mut loader).expect("valid transliteration table");
+        Transliterator {
+            locale: locale::ctype(),
+            table,
+        }
+    }
+
+    /// The replacement for `c`, for a target that can represent the
+    /// characters for which `encodable` is true.
+    pub fn replacement(&self, c: char, encodable: &mut dyn FnMut(char) -> bool) -> Option<&str> {
+        let candidates = self.locale.translit(c).iter().chain(self.table.translit(c));
+        let defaults = [self.locale.default_missing(), self.table.default_missing()];
+        candidates
+            .map(String::as_str)
+            .chain(defaults.into_iter().flatten())
+            .find(|candidate| candidate.chars().all(&mut *encodable))
+    }
+}
+
+// Transliterate the characters of UCS-4 input that `encodable` rejects
+pub fn transliterate<I: Iterator<Item = u32> + 'static>(
+    mut input: I,
+    translit: Transliterator,
+    mut encodable: Box<dyn FnMut(u32) -> bool>,
+) -> Box<dyn Iterator<Item = u32>> {
+    let mut pending = VecDeque::new();
+
+    let iter = iter::from_fn(move || loop {
+        if let Some(code_point) = pending.pop_front() {
+            return Some(code_point);
+        }
+        let code_point = input.next()?;
+        if encodable(code_point) {
+            return Some(code_point);
+        }
+        let replacement = char::from_u32(code_point)
+            .and_then(|c| translit.replacement(c, &mut |r| encodable(r as u32)));
+        match replacement {
+            Some(replacement) => pending.extend(replacement.chars().map(|c| c as u32)),
+            // left for the encoder to report
+            None => return Some(code_point),
+        }
+    });
+
+    Box::new(iter)
+}
@@ -0,0 +1,176 @@
+//
+// Copyright (c) 2024 Hemi Labs, Inc.
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+//! locale - get locale-specific information
+
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::io::{self, Write};
+
+use clap::Parser;
+use gettextrs::{bind_textdomain_codeset, gettext, setlocale, textdomain, LocaleCategory};
+use plib::locale::{
+    available_locales, category_keywords, charmap_dirs, find_keyword, locale_name, Category,
+    Keyword, LocaleInfo,
+};
+
+/// locale - get locale-specific information
+#[derive(Parser)]
+#[command(version, about = gettext("locale - get locale-specific information"))]
+struct Args {
+    #[arg(
+        short = 'a',
+        conflicts_with_all = ["charmaps", "names"],
+        help = gettext("Write the names of all available public locales")
+    )]
+    all_locales: bool,
+
+    #[arg(
+        short = 'm',
+        conflicts_with = "names",
+        help = gettext("Write the names of the available charmaps")
+    )]
+    charmaps: bool,
+
+    #[arg(short = 'c', help = gettext("Write the names of the selected locale categories"))]
+    category_names: bool,
+
+    #[arg(short = 'k', help = gettext("Write the names and values of the selected keywords"))]
+    keyword_names: bool,
+
+    #[arg(help = gettext("Keyword or locale category names"))]
+    names: Vec<String>,
+}
+
+fn env_nonempty(name: &str) -> Option<String> {
+    env::var(name).ok().filter(|v| !v.is_empty())
+}
+
+/// Write the locale of each category as selected by the environment.
+/// Values implied by `LC_ALL` or `LANG` are quoted.
+fn write_environment(out: &mut impl Write) -> io::Result<()> {
+    let lc_all = env_nonempty("LC_ALL");
+    writeln!(out, "LANG={}", env_nonempty("LANG").unwrap_or_default())?;
+    for category in Category::ALL {
+        let name = category.name();
+        match env_nonempty(name) {
+            Some(value) if lc_all.is_none() => writeln!(out, "{name}={value}")?,
+            _ => writeln!(out, "{name}=\"{}\"", locale_name(category))?,
+        }
+    }
+    writeln!(out, "LC_ALL={}", lc_all.unwrap_or_default())
+}
+
+/// The charmap names found in the charmap directories, without any
+/// compression suffix.
+fn charmap_names() -> Vec<String> {
+    let mut names = Vec::new();
+    for dir in charmap_dirs() {
+        let Ok(entries) = fs::read_dir(dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            let name = name.strip_suffix(".gz").unwrap_or(&name);
+            names.push(name.to_string());
+        }
+    }
+    names.sort();
+    names.dedup();
+    names
+}
+
+/// Reports keyword values, loading each category once.
+struct Reporter {
+    category_names: bool,
+    keyword_names: bool,
+    infos: HashMap<&'static str, LocaleInfo>,
+}
+
+impl Reporter {
+    fn info(&mut self, category: Category) -> &LocaleInfo {
+        self.infos
+            .entry(category.name())
+            .or_insert_with(|| LocaleInfo::for_locale(category, &locale_name(category)))
+    }
+
+    fn write_keyword(&mut self, out: &mut impl Write, keyword: &Keyword) -> io::Result<()> {
+        let keyword_names = self.keyword_names;
+        let Some(value) = self.info(keyword.category).get(keyword.name) else {
+            return Ok(());
+        };
+        if keyword_names {
+            write!(out, "{}=", keyword.name)?;
+            out.write_all(&value.quoted())?;
+        } else {
+            out.write_all(&value.plain())?;
+        }
+        writeln!(out)
+    }
+
+    /// Write the keywords named by `name`, a keyword or a category.
+    /// Returns false if it names neither.
+    fn write_name(&mut self, out: &mut impl Write, name: &str) -> io::Result<bool> {
+        if let Some(category) = Category::from_name(name) {
+            if self.category_names {
+                writeln!(out, "{name}")?;
+            }
+            for keyword in category_keywords(category) {
+                self.write_keyword(out, keyword)?;
+            }
+        } else if let Some(keyword) = find_keyword(name) {
+            if self.category_names {
+                writeln!(out, "{}", keyword.category.name())?;
+            }
+            self.write_keyword(out, keyword)?;
+        } else {
+            return Ok(false);
+        }
+        Ok(true)
+    }
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    setlocale(LocaleCategory::LcAll, "");
+    textdomain("posixutils-rs")?;
+    bind_textdomain_codeset("posixutils-rs", "UTF-8")?;
+
+    let args = Args::parse();
+    let mut exit_code = 0;
+    let mut out = io::stdout().lock();
+
+    if args.all_locales {
+        for name in available_locales() {
+            writeln!(out, "{name}")?;
+        }
+    } else if args.charmaps {
+        for name in charmap_names() {
+            writeln!(out, "{name}")?;
+        }
+    } else if args.names.is_empty() {
+        write_environment(&mut out)?;
+    } else {
+        let mut reporter = Reporter {
+            category_names: args.category_names,
+            keyword_names: args.keyword_names,
+            infos: HashMap::new(),
+        };
+        for name in &args.names {
+            if !reporter.write_name(&mut out, name)? {
+                out.flush()?;
+                eprintln!("locale: {name}: {}", gettext("unknown keyword or category"));
+                exit_code = 1;
+            }
+        }
+    }
+    out.flush()?;
+
+    std::process::exit(exit_code)
+}
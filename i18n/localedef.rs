@@ -0,0 +1,526 @@
+//
+// Copyright (c) 2024 Hemi Labs, Inc.
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+//! localedef - define locale environment
+//!
+//! The locale is compiled into a directory that `locale` and the other
+//! utilities read when it is found through `LOCPATH`.  Characters are
+//! stored as Unicode code points: the charmap decides which symbolic
+//! names are defined and which characters the locale's strings may use.
+
+mod charmap_lib;
+
+use std::collections::HashSet;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+use charmap_lib::{expand_range, find_charmap, parse_charmap, Charmap};
+use clap::Parser;
+use gettextrs::{bind_textdomain_codeset, gettext, setlocale, textdomain, LocaleCategory};
+use plib::locale::{
+    compiled_path, str_parts_text, symbol_char, write_category, Category, Collation, Ctype, Line,
+    LocaleInfo, LocaleSource, SourceCategory, SourceLoader, StrPart, Token, PORTABLE_CHARSET_NAMES,
+};
+
+/// Maximum nesting of `copy` and `include` directives.
+const MAX_DEPTH: usize = 16;
+
+/// Exit status when warnings were issued and the locale was created.
+const EXIT_WARNINGS: i32 = 1;
+/// Exit status when a charmap is not available.
+const EXIT_UNSUPPORTED: i32 = 2;
+/// Exit status when no locale was created because of warnings or errors.
+const EXIT_FAILED: i32 = 4;
+
+/// localedef - define locale environment
+#[derive(Parser)]
+#[command(version, about = gettext("localedef - define locale environment"))]
+struct Args {
+    #[arg(
+        short = 'c',
+        help = gettext("Create permanent output even if warning messages have been issued")
+    )]
+    force: bool,
+
+    #[arg(
+        short = 'f',
+        value_name = "CHARMAP",
+        help = gettext("Map symbolic character names using this charmap")
+    )]
+    charmap: Option<String>,
+
+    #[arg(
+        short = 'i',
+        value_name = "INPUTFILE",
+        help = gettext("Read the locale definition from this file instead of standard input")
+    )]
+    input: Option<PathBuf>,
+
+    #[arg(
+        short = 'u',
+        value_name = "CODE_SET_NAME",
+        help = gettext("Map characters given by their ISO/IEC 10646 position to this codeset")
+    )]
+    code_set: Option<String>,
+
+    #[arg(help = gettext("Name of the locale to create, or the path of its directory"))]
+    name: String,
+}
+
+/// A line of a category with `copy` and `include` resolved, and the
+/// name of the file it comes from.
+struct SourceLine {
+    origin: Rc<str>,
+    line: Line,
+}
+
+/// Whether `name` gives a character by its ISO/IEC 10646 position.
+fn is_unicode_name(name: &str) -> bool {
+    name.strip_prefix('U').is_some_and(|hex| {
+        (hex.len() == 4 || hex.len() == 8) && hex.chars().all(|c| c.is_ascii_hexdigit())
+    })
+}
+
+/// The name a charmap in glibc style gives the character `c`.
+fn unicode_name(c: char) -> String {
+    if (c as u32) > 0xffff {
+        format!("U{:08X}", c as u32)
+    } else {
+        format!("U{:04X}", c as u32)
+    }
+}
+
+fn is_portable(c: char) -> bool {
+    PORTABLE_CHARSET_NAMES.iter().any(|&(_, p)| p == c)
+}
+
+fn line_error(origin: &str, line: &Line, msg: impl std::fmt::Display) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("{origin}: line {}: {msg}", line.lineno),
+    )
+}
+
+struct Compiler {
+    loader: SourceLoader,
+    /// The charmap of `-f`.
+    charmap: Option<Charmap>,
+    /// The charmap of the `-u` codeset, for `<Uxxxx>` names.
+    unicode: Option<Charmap>,
+    /// Names of collating symbols and elements, which are not characters.
+    collating_names: HashSet<String>,
+    warnings: usize,
+}
+
+impl Compiler {
+    fn warn(&mut self, origin: &str, line: &Line, msg: impl std::fmt::Display) {
+        eprintln!("localedef: {origin}: line {}: {msg}", line.lineno);
+        self.warnings += 1;
+    }
+
+    /// Append the lines of `category` to `out`, replacing `copy`
+    /// directives, and `include` in LC_CTYPE transliterations, by the
+    /// lines they name.
+    fn flatten(
+        &mut self,
+        category: &SourceCategory,
+        origin: &Rc<str>,
+        depth: usize,
+        out: &mut Vec<SourceLine>,
+    ) -> io::Result<()> {
+        let mut in_translit = false;
+        for line in &category.lines {
+            let included = match (line.keyword(), line.operands()) {
+                (Some("copy"), [Token::Str(name)]) => Some(str_parts_text(name)),
+                (Some("copy"), _) => return Err(line_error(origin, line, "bad copy directive")),
+                (Some("include"), [Token::Str(name), ..]) if in_translit => {
+                    Some(str_parts_text(name))
+                }
+                (Some("translit_start"), _) => {
+                    in_translit = true;
+                    None
+                }
+                (Some("translit_end"), _) => {
+                    in_translit = false;
+                    None
+                }
+                _ => None,
+            };
+            let Some(name) = included else {
+                out.push(SourceLine {
+                    origin: origin.clone(),
+                    line: line.clone(),
+                });
+                continue;
+            };
+
+            if depth >= MAX_DEPTH {
+                return Err(line_error(origin, line, "copy nested too deeply"));
+            }
+            let source = self
+                .loader
+                .load(&name)
+                .map_err(|e| line_error(origin, line, e))?;
+            let copied = source.category(&category.name).ok_or_else(|| {
+                line_error(
+                    origin,
+                    line,
+                    format!("'{name}' has no {} category", category.name),
+                )
+            })?;
+            let copied_origin: Rc<str> = Rc::from(name.as_str());
+            // An included transliteration table is a whole LC_CTYPE
+            // category with its own translit_start section.
+            let marker = |keyword: &str| SourceLine {
+                origin: origin.clone(),
+                line: Line {
+                    lineno: line.lineno,
+                    tokens: vec![Token::Word(keyword.to_string())],
+                },
+            };
+            if in_translit {
+                out.push(marker("translit_end"));
+            }
+            self.flatten(copied, &copied_origin, depth + 1, out)?;
+            if in_translit {
+                out.push(marker("translit_start"));
+            }
+        }
+        Ok(())
+    }
+
+    /// Record the collating symbols and elements defined by LC_COLLATE.
+    fn collect_collating_names(&mut self, lines: &[SourceLine]) {
+        for SourceLine { line, .. } in lines {
+            match (line.keyword(), line.operands()) {
+                (
+                    Some("collating-symbol"),
+                    [Token::Symbol(first), Token::Word(dots), Token::Symbol(last)],
+                ) => {
+                    if let Ok(names) = expand_range(first, last, dots) {
+                        self.collating_names.extend(names);
+                    }
+                }
+                (
+                    Some(
+                        "collating-symbol" | "collating-element" | "script" | "symbol-equivalence",
+                    ),
+                    [Token::Symbol(name), ..],
+                ) => {
+                    self.collating_names.insert(name.clone());
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// The charmap that defines the symbolic name `name`.
+    fn charmap_for(&self, name: &str) -> Option<&Charmap> {
+        if is_unicode_name(name) {
+            self.unicode.as_ref().or(self.charmap.as_ref())
+        } else {
+            self.charmap.as_ref()
+        }
+    }
+
+    /// The character named `name`, and whether the charmap defines it.
+    fn resolve(&self, name: &str) -> Result<(char, bool), String> {
+        let charmap = self.charmap_for(name);
+        if let Some(entry) = charmap.and_then(|map| map.entries.get(name)) {
+            let utf8 = charmap
+                .and_then(|map| map.header.code_set_name.as_deref())
+                .is_some_and(|codeset| codeset.eq_ignore_ascii_case("UTF-8"));
+            let decoded = match &entry.encoding[..] {
+                [b] if b.is_ascii() => Some(*b as char),
+                bytes if utf8 => std::str::from_utf8(bytes)
+                    .ok()
+                    .and_then(|s| s.chars().next()),
+                _ => None,
+            };
+            return symbol_char(&entry.symbolic_name)
+                .or(decoded)
+                .map(|c| (c, true))
+                .ok_or_else(|| gettext("cannot be mapped to a Unicode character"));
+        }
+        let c = symbol_char(name).ok_or_else(|| gettext("undefined symbolic name"))?;
+        Ok((c, self.in_charmap(c, charmap)))
+    }
+
+    fn in_charmap(&self, c: char, charmap: Option<&Charmap>) -> bool {
+        match charmap {
+            Some(map) => is_portable(c) || map.entries.contains_key(&unicode_name(c)),
+            None => true,
+        }
+    }
+
+    /// Replace the symbolic names of a line by `<Uxxxx>` names, warning
+    /// about undefined names.  Characters outside the charmap are
+    /// warned about when `strict`, as the category's strings must be
+    /// representable in the codeset; LC_CTYPE and LC_COLLATE may
+    /// describe characters the codeset lacks.
+    fn normalize(&mut self, origin: &str, line: &mut Line, strict: bool) {
+        let mut problems = Vec::new();
+        let mut symbol = |name: &str| -> Option<String> {
+            if self.collating_names.contains(name) {
+                return None;
+            }
+            match self.resolve(name) {
+                Ok((c, defined)) => {
+                    if strict && !defined {
+                        problems.push(format!("<{name}>: {}", gettext("not defined in charmap")));
+                    }
+                    Some(unicode_name(c))
+                }
+                Err(msg) => {
+                    problems.push(format!("<{name}>: {msg}"));
+                    None
+                }
+            }
+        };
+        let mut literals = Vec::new();
+        for token in line.tokens.iter_mut() {
+            match token {
+                Token::Symbol(name) => {
+                    if let Some(normalized) = symbol(name) {
+                        *name = normalized;
+                    }
+                }
+                Token::Str(parts) => {
+                    for part in parts.iter_mut() {
+                        match part {
+                            StrPart::Symbol(name) => {
+                                let resolved = symbol(name);
+                                if let Some(c) = resolved.as_deref().and_then(symbol_char) {
+                                    *part = StrPart::Char(c);
+                                }
+                            }
+                            StrPart::Char(c) => literals.push(*c),
+                        }
+                    }
+                }
+                Token::Word(_) | Token::Punct(_) => {}
+            }
+        }
+        if strict {
+            let charmap = self.charmap.as_ref().or(self.unicode.as_ref());
+            for c in literals {
+                if !self.in_charmap(c, charmap) {
+                    problems.push(format!("'{c}': {}", gettext("not defined in charmap")));
+                }
+            }
+        }
+        for problem in problems {
+            self.warn(origin, line, problem);
+        }
+    }
+
+    /// The name of the codeset the locale is defined for, if a charmap
+    /// was given.
+    fn code_set_name(&self, args: &Args) -> Option<String> {
+        let (charmap, name) = match (&self.charmap, &self.unicode) {
+            (Some(map), _) => (map, args.charmap.as_deref()?),
+            (None, Some(map)) => (map, args.code_set.as_deref()?),
+            (None, None) => return None,
+        };
+        let fallback = name.rsplit('/').next().unwrap_or(name);
+        let fallback = fallback.strip_suffix(".gz").unwrap_or(fallback);
+        Some(
+            charmap
+                .header
+                .code_set_name
+                .clone()
+                .unwrap_or_else(|| fallback.to_string()),
+        )
+    }
+
+    /// Resolve and check the categories of `source`, returning the
+    /// compiled categories.
+    fn compile(&mut self, source: &LocaleSource, origin: &str) -> io::Result<LocaleSource> {
+        let origin: Rc<str> = Rc::from(origin);
+        let mut flattened = Vec::new();
+        for category in &source.categories {
+            if Category::from_name(&category.name).is_none() {
+                continue;
+            }
+            let mut lines = Vec::new();
+            self.flatten(category, &origin, 0, &mut lines)?;
+            flattened.push((category.name.clone(), lines));
+        }
+        for (name, lines) in &flattened {
+            if name == "LC_COLLATE" {
+                self.collect_collating_names(lines);
+            }
+        }
+
+        let mut compiled = LocaleSource::default();
+        for (name, lines) in flattened {
+            let strict = !matches!(name.as_str(), "LC_CTYPE" | "LC_COLLATE");
+            let mut category = SourceCategory {
+                name,
+                lines: Vec::new(),
+            };
+            for SourceLine { origin, mut line } in lines {
+                self.normalize(&origin, &mut line, strict);
+                category.lines.push(line);
+            }
+            compiled.categories.push(category);
+        }
+        Ok(compiled)
+    }
+}
+
+/// Check that the compiled categories are well-formed, the way the
+/// utilities will read them.
+fn check(compiled: &LocaleSource) -> io::Result<()> {
+    let mut loader = SourceLoader::with_dirs(Vec::new());
+    for section in &compiled.categories {
+        let context = |e: io::Error| io::Error::new(e.kind(), format!("{}: {e}", section.name));
+        match Category::from_name(&section.name) {
+            Some(Category::Ctype) => Ctype::from_source(compiled, &mut loader).map(drop),
+            Some(Category::Collate) => Collation::from_source(compiled, &mut loader).map(drop),
+            Some(category) => LocaleInfo::from_source(category, compiled, &mut loader).map(drop),
+            None => Ok(()),
+        }
+        .map_err(context)?;
+    }
+    Ok(())
+}
+
+/// Write the compiled categories into the locale directory `dir`,
+/// removing those of an earlier definition that this one lacks.
+fn write_locale(compiled: &LocaleSource, dir: &Path) -> io::Result<()> {
+    fs::create_dir_all(dir)?;
+    for category in Category::ALL {
+        let path = dir.join(category.name());
+        match compiled.category(category.name()) {
+            Some(section) => {
+                let mut out = BufWriter::new(File::create(&path)?);
+                write_category(&mut out, section)?;
+                out.flush()?;
+            }
+            None => match fs::remove_file(&path) {
+                Err(e) if e.kind() != io::ErrorKind::NotFound && path.is_file() => return Err(e),
+                _ => {}
+            },
+        }
+    }
+    Ok(())
+}
+
+/// Load the charmap `name`, reporting failures.
+fn load_charmap(name: &str) -> Result<Charmap, i32> {
+    let Some(path) = find_charmap(name) else {
+        eprintln!("localedef: {name}: {}", gettext("charmap not found"));
+        return Err(EXIT_UNSUPPORTED);
+    };
+    parse_charmap(&path).map_err(|e| {
+        eprintln!("localedef: {e}");
+        EXIT_FAILED
+    })
+}
+
+fn localedef(args: &Args) -> Result<i32, i32> {
+    let charmap = args.charmap.as_deref().map(load_charmap).transpose()?;
+    let unicode = args.code_set.as_deref().map(load_charmap).transpose()?;
+
+    let loader = SourceLoader::default();
+    let (origin, text) = match &args.input {
+        // An input file that does not exist is looked up by name among
+        // the locale sources, as for `copy`.
+        Some(path) => {
+            let path = match path.to_str() {
+                Some(name) if !path.exists() => loader.find(name).unwrap_or(path.clone()),
+                _ => path.clone(),
+            };
+            (path.display().to_string(), fs::read(&path))
+        }
+        None => {
+            let mut text = Vec::new();
+            let result = io::stdin().lock().read_to_end(&mut text).map(|_| text);
+            (String::from("-"), result)
+        }
+    };
+    let text = text.map_err(|e| {
+        eprintln!("localedef: {origin}: {e}");
+        EXIT_FAILED
+    })?;
+    let source = LocaleSource::parse(&String::from_utf8_lossy(&text)).map_err(|e| {
+        eprintln!("localedef: {origin}: {e}");
+        EXIT_FAILED
+    })?;
+
+    let mut compiler = Compiler {
+        loader,
+        charmap,
+        unicode,
+        collating_names: HashSet::new(),
+        warnings: 0,
+    };
+    let fail = |e: io::Error| {
+        eprintln!("localedef: {e}");
+        EXIT_FAILED
+    };
+    let mut compiled = compiler.compile(&source, &origin).map_err(fail)?;
+
+    // Record the codeset for the `charmap` keyword of `locale`.
+    if let (Some(codeset), Some(ctype)) = (
+        compiler.code_set_name(args),
+        compiled
+            .categories
+            .iter_mut()
+            .find(|c| c.name == Category::Ctype.name()),
+    ) {
+        let line = Line {
+            lineno: 0,
+            tokens: vec![
+                Token::Word(String::from("charmap")),
+                Token::Str(codeset.chars().map(StrPart::Char).collect()),
+            ],
+        };
+        ctype.lines.insert(0, line);
+    }
+
+    check(&compiled).map_err(|e| {
+        eprintln!("localedef: {origin}: {e}");
+        EXIT_FAILED
+    })?;
+
+    if compiler.warnings > 0 && !args.force {
+        eprintln!(
+            "localedef: {}",
+            gettext("no output created because of warnings")
+        );
+        return Err(EXIT_FAILED);
+    }
+
+    let dir = compiled_path(&args.name);
+    write_locale(&compiled, &dir).map_err(|e| {
+        eprintln!("localedef: {}: {e}", dir.display());
+        EXIT_FAILED
+    })?;
+
+    Ok(if compiler.warnings > 0 {
+        EXIT_WARNINGS
+    } else {
+        0
+    })
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    setlocale(LocaleCategory::LcAll, "");
+    textdomain("posixutils-rs")?;
+    bind_textdomain_codeset("posixutils-rs", "UTF-8")?;
+
+    let args = Args::parse();
+    let exit_code = localedef(&args).unwrap_or_else(|code| code);
+
+    std::process::exit(exit_code)
+}
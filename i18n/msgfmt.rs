@@ -0,0 +1,477 @@
+//
+// Copyright (c) 2024 Hemi Labs, Inc.
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+//! msgfmt - create message object files
+//!
+//! The portable object files are compiled into GNU `.mo` catalogs in
+//! little-endian byte order, with a hash table for the lookup.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+
+use clap::Parser;
+use gettextrs::mo::{hash_string, HEADER_SIZE, MAGIC};
+use gettextrs::plural::PluralForms;
+use gettextrs::{bind_textdomain_codeset, gettext, setlocale, textdomain, LocaleCategory};
+use plib::po::{self, format, header_field, Message};
+
+const DEFAULT_DOMAIN: &str = "messages";
+
+/// create message object files
+#[derive(Parser)]
+#[command(version, about = gettext("msgfmt - create message object files"))]
+struct Args {
+    #[arg(
+        short = 'c',
+        long = "check",
+        help = gettext("Check the format strings, the header entry and the domain names")
+    )]
+    check: bool,
+
+    #[arg(
+        short = 'D',
+        long = "directory",
+        help = gettext("Add a directory to the search list for input files")
+    )]
+    directories: Vec<PathBuf>,
+
+    #[arg(
+        short = 'f',
+        long = "use-fuzzy",
+        help = gettext("Use fuzzy entries in the output")
+    )]
+    use_fuzzy: bool,
+
+    #[arg(
+        short = 'o',
+        long = "output-file",
+        help = gettext("Write all messages to this file instead of DOMAIN.mo")
+    )]
+    output_file: Option<PathBuf>,
+
+    #[arg(long, help = gettext("Print statistics about the translations"))]
+    statistics: bool,
+
+    #[arg(
+        short = 'v',
+        long,
+        help = gettext("Verbose mode, print statistics about the translations")
+    )]
+    verbose: bool,
+
+    #[arg(required = true, help = gettext("Portable object files to compile, or - for standard input"))]
+    files: Vec<PathBuf>,
+}
+
+/// The messages of one output catalog by key, with the names of the
+/// files they came from.
+#[derive(Default)]
+struct Catalog {
+    messages: BTreeMap<Vec<u8>, (String, Message)>,
+}
+
+#[derive(Default)]
+struct Statistics {
+    translated: usize,
+    fuzzy: usize,
+    untranslated: usize,
+}
+
+struct Compiler {
+    args: Args,
+    catalogs: BTreeMap<String, Catalog>,
+    stats: Statistics,
+    errors: usize,
+}
+
+impl Compiler {
+    fn error(&mut self, name: &str, line: usize, msg: &str) {
+        eprintln!("msgfmt: {name}:{line}: {msg}");
+        self.errors += 1;
+    }
+
+    /// Find an input file, looking in the `-D` directories for a
+    /// relative name that does not exist.
+    fn resolve(&self, path: &Path) -> PathBuf {
+        if path.is_absolute() || path.exists() {
+            return path.to_path_buf();
+        }
+        self.args
+            .directories
+            .iter()
+            .map(|dir| dir.join(path))
+            .find(|p| p.exists())
+            .unwrap_or_else(|| path.to_path_buf())
+    }
+
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        if path.as_os_str() == "-" {
+            let mut data = Vec::new();
+            io::stdin().lock().read_to_end(&mut data)?;
+            Ok(data)
+        } else {
+            fs::read(self.resolve(path))
+        }
+    }
+
+    fn add_file(&mut self, path: &Path) {
+        let name = path.display().to_string();
+        let data = match self.read(path) {
+            Ok(data) => data,
+            Err(e) => {
+                eprintln!("msgfmt: {name}: {e}");
+                self.errors += 1;
+                return;
+            }
+        };
+        let messages = match po::parse(&data) {
+            Ok(messages) => messages,
+            Err(e) => {
+                self.error(&name, e.line, &gettext(e.message));
+                return;
+            }
+        };
+        for message in messages.into_iter().filter(|m| !m.obsolete) {
+            self.add_message(&name, message);
+        }
+    }
+
+    fn add_message(&mut self, name: &str, message: Message) {
+        let header = message.is_header();
+        if !header {
+            if !message.is_translated() {
+                self.stats.untranslated += 1;
+                return;
+            }
+            if message.is_fuzzy() && !self.args.use_fuzzy {
+                self.stats.fuzzy += 1;
+                return;
+            }
+            self.stats.translated += 1;
+        } else if !message.is_translated() {
+            return;
+        }
+
+        let domain = match (&self.args.output_file, &message.domain) {
+            (None, Some(domain)) => domain.clone(),
+            _ => String::from(DEFAULT_DOMAIN),
+        };
+        if self.args.check && domain.contains('/') {
+            self.error(
+                name,
+                message.line,
+                &gettext("domain name is not suitable as a file name"),
+            );
+        }
+        let key = message.key();
+        let catalog = self.catalogs.entry(domain).or_default();
+        if let Some((first_name, first)) = catalog.messages.get(&key) {
+            let first_location = format!("{first_name}:{}", first.line);
+            self.error(name, message.line, &gettext("duplicate message definition"));
+            eprintln!(
+                "msgfmt: {first_location}: {}",
+                gettext("...this is the location of the first definition")
+            );
+            return;
+        }
+        catalog.messages.insert(key, (name.to_string(), message));
+    }
+
+    /// The `-c` checks of the messages of a catalog.
+    fn check(&mut self, domain: &str) {
+        let catalog = &self.catalogs[domain];
+        let mut problems = Vec::new();
+
+        let header_entry = catalog.messages.get(&b""[..]);
+        let header = header_entry.map(|(_, m)| String::from_utf8_lossy(&m.strs[0]).into_owned());
+        let plural_forms = header
+            .as_deref()
+            .and_then(|h| header_field(h, "Plural-Forms"));
+        let has_plurals = catalog
+            .messages
+            .values()
+            .any(|(_, m)| m.id_plural.is_some());
+        let (header_name, header_line) = match (header_entry, catalog.messages.values().next()) {
+            (Some((name, m)), _) => (name.as_str(), m.line),
+            (None, Some((name, _))) => (name.as_str(), 1),
+            (None, None) => return,
+        };
+        let nplurals = match plural_forms {
+            Some(value) => match PluralForms::parse(value) {
+                Some(forms) => {
+                    let max = (0..1000).map(|n| forms.index(n)).max().unwrap_or(0);
+                    if max >= forms.nplurals {
+                        problems.push((
+                            header_name,
+                            header_line,
+                            gettext!(
+                                "plural expression can produce values as large as {}, but nplurals = {}",
+                                max,
+                                forms.nplurals
+                            ),
+                        ));
+                    }
+                    Some(forms.nplurals)
+                }
+                None => {
+                    problems.push((
+                        header_name,
+                        header_line,
+                        gettext("invalid plural expression"),
+                    ));
+                    None
+                }
+            },
+            None if has_plurals => {
+                problems.push((
+                    header_name,
+                    header_line,
+                    gettext("message catalog has plural form translations, but lacks a header entry with \"Plural-Forms: nplurals=INTEGER; plural=EXPRESSION;\""),
+                ));
+                None
+            }
+            None => None,
+        };
+
+        for (name, message) in catalog.messages.values().filter(|(_, m)| !m.is_header()) {
+            let (name, line) = (name.as_str(), message.line);
+            if let (Some(nplurals), Some(_)) = (nplurals, &message.id_plural) {
+                if message.strs.len() != nplurals {
+                    problems.push((
+                        name,
+                        line,
+                        gettext!(
+                            "nplurals = {} but plural message has {} forms",
+                            nplurals,
+                            message.strs.len()
+                        ),
+                    ));
+                }
+            }
+            for (i, msgstr) in message.strs.iter().enumerate() {
+                let msgid = match (&message.id_plural, i) {
+                    (Some(plural), 1..) => plural,
+                    _ => &message.id,
+                };
+                for (edge, first) in [("begin", true), ("end", false)] {
+                    let has_newline = |s: &[u8]| {
+                        let b = if first { s.first() } else { s.last() };
+                        b == Some(&b'\n')
+                    };
+                    if has_newline(msgid) != has_newline(msgstr) {
+                        problems.push((
+                            name,
+                            line,
+                            gettext!(
+                                "'msgid' and 'msgstr' entries do not both {} with '\\n'",
+                                edge
+                            ),
+                        ));
+                    }
+                }
+                if message.has_flag("c-format") {
+                    let allow_missing = message.id_plural.is_some();
+                    if let Err(e) = format::check(msgid, msgstr, allow_missing) {
+                        problems.push((name, line, gettext(e)));
+                    }
+                }
+            }
+        }
+
+        let problems: Vec<(String, usize, String)> = problems
+            .into_iter()
+            .map(|(name, line, problem)| (name.to_string(), line, problem))
+            .collect();
+        for (name, line, problem) in problems {
+            self.error(&name, line, &problem);
+        }
+    }
+
+    fn print_statistics(&self) {
+        let Statistics {
+            translated,
+            fuzzy,
+            untranslated,
+        } = self.stats;
+        let count = |n: usize| u32::try_from(n).unwrap_or(u32::MAX);
+        let mut line = gettextrs::ngettext!(
+            "{} translated message",
+            "{} translated messages",
+            count(translated),
+            translated
+        );
+        if fuzzy > 0 {
+            line.push_str(&gettextrs::ngettext!(
+                ", {} fuzzy translation",
+                ", {} fuzzy translations",
+                count(fuzzy),
+                fuzzy
+            ));
+        }
+        if untranslated > 0 {
+            line.push_str(&gettextrs::ngettext!(
+                ", {} untranslated message",
+                ", {} untranslated messages",
+                count(untranslated),
+                untranslated
+            ));
+        }
+        eprintln!("{line}.");
+    }
+}
+
+fn is_prime(n: usize) -> bool {
+    n >= 2 && (2..).take_while(|d| d * d <= n).all(|d| n % d != 0)
+}
+
+/// The smallest odd prime not less than `n`, the hash table size used by
+/// GNU `msgfmt`.
+fn next_prime(n: usize) -> usize {
+    let mut candidate = n | 1;
+    while !is_prime(candidate) {
+        candidate += 2;
+    }
+    candidate
+}
+
+/// Encode the messages as a little-endian `.mo` catalog.
+fn write_catalog(messages: &BTreeMap<Vec<u8>, (String, Message)>) -> Vec<u8> {
+    let n = messages.len();
+    let hash_size = next_prime(n * 4 / 3).max(3);
+    let orig_table = HEADER_SIZE as usize;
+    let trans_table = orig_table + 8 * n;
+    let hash_table = trans_table + 8 * n;
+    let strings_start = hash_table + 4 * hash_size;
+
+    let mut originals = Vec::new();
+    let mut translations = Vec::new();
+    for (key, (_, message)) in messages {
+        let mut orig = key.clone();
+        if let Some(plural) = &message.id_plural {
+            orig.push(0);
+            orig.extend_from_slice(plural);
+        }
+        originals.push(orig);
+        translations.push(message.strs.join(&0));
+    }
+
+    let mut hash = vec![0u32; hash_size];
+    for (i, key) in messages.keys().enumerate() {
+        let hval = hash_string(key) as usize;
+        let incr = 1 + hval % (hash_size - 2);
+        let mut idx = hval % hash_size;
+        while hash[idx] != 0 {
+            idx += incr;
+            if idx >= hash_size {
+                idx -= hash_size;
+            }
+        }
+        hash[idx] = i as u32 + 1;
+    }
+
+    let mut out = Vec::new();
+    let word = |out: &mut Vec<u8>, w: usize| out.extend_from_slice(&(w as u32).to_le_bytes());
+    for w in [
+        MAGIC as usize,
+        0,
+        n,
+        orig_table,
+        trans_table,
+        hash_size,
+        hash_table,
+    ] {
+        word(&mut out, w);
+    }
+    let mut offset = strings_start;
+    for s in originals.iter().chain(translations.iter()) {
+        word(&mut out, s.len());
+        word(&mut out, offset);
+        offset += s.len() + 1;
+    }
+    for h in hash {
+        word(&mut out, h as usize);
+    }
+    for s in originals.iter().chain(translations.iter()) {
+        out.extend_from_slice(s);
+        out.push(0);
+    }
+    out
+}
+
+fn write_output(path: &Path, data: &[u8]) -> io::Result<()> {
+    if path.as_os_str() == "-" {
+        io::stdout().lock().write_all(data)
+    } else {
+        fs::write(path, data)
+    }
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    setlocale(LocaleCategory::LcAll, "");
+    textdomain("posixutils-rs")?;
+    bind_textdomain_codeset("posixutils-rs", "UTF-8")?;
+
+    let args = Args::parse();
+    let files = args.files.clone();
+    let mut compiler = Compiler {
+        args,
+        catalogs: BTreeMap::new(),
+        stats: Statistics::default(),
+        errors: 0,
+    };
+
+    for path in &files {
+        compiler.add_file(path);
+    }
+    if compiler.args.check {
+        let domains: Vec<String> = compiler.catalogs.keys().cloned().collect();
+        for domain in domains {
+            compiler.check(&domain);
+        }
+    }
+
+    if compiler.errors > 0 {
+        let errors = compiler.errors;
+        eprintln!(
+            "msgfmt: {}",
+            gettextrs::ngettext!(
+                "found {} fatal error",
+                "found {} fatal errors",
+                u32::try_from(errors).unwrap_or(u32::MAX),
+                errors
+            )
+        );
+        std::process::exit(1);
+    }
+
+    if compiler.catalogs.is_empty() {
+        compiler
+            .catalogs
+            .insert(String::from(DEFAULT_DOMAIN), Catalog::default());
+    }
+    let mut exit_code = 0;
+    for (domain, catalog) in &compiler.catalogs {
+        let path = match &compiler.args.output_file {
+            Some(path) => path.clone(),
+            None => PathBuf::from(format!("{domain}.mo")),
+        };
+        if let Err(e) = write_output(&path, &write_catalog(&catalog.messages)) {
+            eprintln!("msgfmt: {}: {e}", path.display());
+            exit_code = 1;
+        }
+    }
+
+    if compiler.args.statistics || compiler.args.verbose {
+        compiler.print_statistics();
+    }
+
+    std::process::exit(exit_code)
+}
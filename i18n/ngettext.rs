@@ -0,0 +1,116 @@
+//
+// Copyright (c) 2024 Hemi Labs, Inc.
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+//! ngettext - retrieve text string from messages object with plural form
+
+mod gettext_lib;
+
+use std::io::{self, Write};
+
+use clap::Parser;
+use gettext_lib::{expand_escapes, text_domain};
+use gettextrs::{
+    bind_textdomain_codeset, dcngettext, gettext, setlocale, textdomain, LocaleCategory,
+};
+
+/// ngettext - retrieve text string from messages object with plural form
+#[derive(Parser)]
+#[command(
+    version,
+    about = gettext("ngettext - retrieve text string from messages object with plural form")
+)]
+struct Args {
+    #[arg(
+        short = 'd',
+        long = "domain",
+        value_name = "TEXTDOMAIN",
+        help = gettext("Retrieve the translations from this text domain")
+    )]
+    domain: Option<String>,
+
+    #[arg(
+        short = 'e',
+        overrides_with = "no_escapes",
+        help = gettext("Expand escape sequences in the message identifiers")
+    )]
+    escapes: bool,
+
+    #[arg(
+        short = 'E',
+        overrides_with = "escapes",
+        help = gettext("Do not expand escape sequences (the default)")
+    )]
+    no_escapes: bool,
+
+    #[arg(help = gettext("[TEXTDOMAIN] MSGID MSGID-PLURAL COUNT"))]
+    args: Vec<String>,
+}
+
+/// The count as passed to the plural expression.  Counts beyond the
+/// range of `ngettext` keep their last six digits, which is enough for
+/// the plural rules of every language to pick the same form.
+fn parse_count(s: &str) -> Option<u32> {
+    let n: u64 = s.parse().ok()?;
+    Some(u32::try_from(n).unwrap_or((n % 1_000_000 + 1_000_000) as u32))
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    setlocale(LocaleCategory::LcAll, "");
+    textdomain("posixutils-rs")?;
+    bind_textdomain_codeset("posixutils-rs", "UTF-8")?;
+
+    let args = Args::parse();
+
+    let (domain, msgid, msgid_plural, count) = match &args.args[..] {
+        [msgid, msgid_plural, count] => (args.domain, msgid, msgid_plural, count),
+        [domain, msgid, msgid_plural, count] => (Some(domain.clone()), msgid, msgid_plural, count),
+        [..] if args.args.len() < 3 => {
+            eprintln!("ngettext: {}", gettext("missing arguments"));
+            std::process::exit(1);
+        }
+        _ => {
+            eprintln!("ngettext: {}", gettext("too many arguments"));
+            std::process::exit(1);
+        }
+    };
+    let Some(n) = parse_count(count) else {
+        eprintln!("ngettext: {}: {count}", gettext("invalid count"));
+        std::process::exit(1);
+    };
+
+    let (msgid, msgid_plural) = if args.escapes {
+        (expand_escapes(msgid).0, expand_escapes(msgid_plural).0)
+    } else {
+        (msgid.as_bytes().to_vec(), msgid_plural.as_bytes().to_vec())
+    };
+    // The empty msgid would retrieve the catalog header, and escapes may
+    // leave msgids that are not text, which no catalog translates.
+    let translation = match (
+        text_domain(domain),
+        String::from_utf8(msgid.clone()),
+        String::from_utf8(msgid_plural.clone()),
+    ) {
+        (Some(domain), Ok(msgid), Ok(msgid_plural)) if !msgid.is_empty() => dcngettext(
+            Some(domain),
+            msgid,
+            msgid_plural,
+            n,
+            LocaleCategory::LcMessages,
+        )
+        .into_bytes(),
+        _ if n == 1 => msgid,
+        _ => msgid_plural,
+    };
+
+    let mut stdout = io::stdout().lock();
+    stdout.write_all(&translation)?;
+    stdout.flush()?;
+
+    std::process::exit(0)
+}
@@ -7,7 +7,7 @@
 // SPDX-License-Identifier: MIT
 //
 
-use plib::testing::{run_test_u8, TestPlanU8};
+use plib::testing::{run_test_base, run_test_u8, TestPlanU8};
 use std::env;
 use std::path::PathBuf;
 use std::{fs::File, io::Read};
@@ -127,3 +127,67 @@ fn gencat_sets_and_messagess_with_quote_unset() {
         Vec::new(),
     );
 }
+
+fn read_fixture(name: &str) -> Vec<u8> {
+    let cargo_manifest_dir = PathBuf::from(env::var("CARGO_MANIFEST_DIR").unwrap());
+    let mut data: Vec<u8> = Vec::new();
+    File::open(cargo_manifest_dir.join("tests/gencat").join(name))
+        .unwrap()
+        .read_to_end(&mut data)
+        .unwrap();
+    data
+}
+
+#[test]
+fn gencat_dump_both_layouts() {
+    let cargo_manifest_dir = PathBuf::from(env::var("CARGO_MANIFEST_DIR").unwrap());
+    let expected_output = "$quote \"\n$set 1\n1 \"This is first message\"\n\
+                           2 \"This is second message\"\n$set 2\n\
+                           1 \"This is third message\"\n2 \"This is fourth message\"\n";
+
+    for cat_file in [
+        "sets_and_messages_gnu_catfile.cat",
+        "sets_and_messages_osx_catfile.cat",
+    ] {
+        let cat_file = cargo_manifest_dir.join("tests/gencat").join(cat_file);
+        gencat_test(
+            &["--dump", cat_file.to_str().unwrap()],
+            expected_output.as_bytes().to_vec(),
+            Vec::new(),
+        );
+    }
+}
+
+#[test]
+fn gencat_dump_round_trip() {
+    let cargo_manifest_dir = PathBuf::from(env::var("CARGO_MANIFEST_DIR").unwrap());
+    let msg_file = cargo_manifest_dir.join("tests/gencat/escapes.msg");
+
+    let output = run_test_base(
+        "gencat",
+        &vec![String::from("-"), msg_file.to_str().unwrap().to_string()],
+        b"",
+    );
+    assert!(output.status.success());
+
+    run_test_u8(TestPlanU8 {
+        cmd: String::from("gencat"),
+        args: vec![String::from("--dump"), String::from("-")],
+        stdin_data: output.stdout,
+        expected_out: read_fixture("escapes_dump.msg"),
+        expected_err: Vec::new(),
+        expected_exit_code: 0,
+    });
+}
+
+#[test]
+fn gencat_dump_invalid_catalog() {
+    run_test_u8(TestPlanU8 {
+        cmd: String::from("gencat"),
+        args: vec![String::from("--dump"), String::from("-")],
+        stdin_data: b"not a catalog".to_vec(),
+        expected_out: Vec::new(),
+        expected_err: b"gencat: -: not a message catalog\n".to_vec(),
+        expected_exit_code: 1,
+    });
+}
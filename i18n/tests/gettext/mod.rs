@@ -0,0 +1,144 @@
+//
+// Copyright (c) 2024 Hemi Labs, Inc.
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::process::{Command, Output, Stdio};
+
+use plib::testing::{run_test, TestPlan};
+use tempfile::TempDir;
+
+const PO: &str = r#"msgid ""
+msgstr ""
+"Content-Type: text/plain; charset=UTF-8\n"
+"Plural-Forms: nplurals=3; plural=n==1 ? 0 : n%10>=2 && n%10<=4 && (n%100<10 || n%100>=20) ? 1 : 2;\n"
+
+msgid "Hello"
+msgstr "Cześć"
+
+msgid "Good bye"
+msgstr "Do widzenia"
+
+msgid "tab\there"
+msgstr "tab\ttutaj"
+
+msgid "%d file"
+msgid_plural "%d files"
+msgstr[0] "%d plik"
+msgstr[1] "%d pliki"
+msgstr[2] "%d plików"
+"#;
+
+/// Compile the test catalog into `DIR/pl/LC_MESSAGES/test.mo`.
+pub fn install_catalog(dir: &Path) {
+    let messages = dir.join("pl").join("LC_MESSAGES");
+    fs::create_dir_all(&messages).unwrap();
+    run_test(TestPlan {
+        cmd: String::from("msgfmt"),
+        args: vec![
+            String::from("-o"),
+            messages.join("test.mo").to_str().unwrap().to_string(),
+            String::from("-"),
+        ],
+        stdin_data: String::from(PO),
+        expected_out: String::new(),
+        expected_err: String::new(),
+        expected_exit_code: 0,
+    });
+}
+
+/// Run `cmd` in the Polish locale with the catalogs of `dir` bound to
+/// the `test` domain through the environment.
+pub fn run_localized(cmd: &str, args: &[&str], dir: &Path, domain_env: bool) -> Output {
+    let relpath = if cfg!(debug_assertions) {
+        format!("target/debug/{cmd}")
+    } else {
+        format!("target/release/{cmd}")
+    };
+    let test_bin_path = env::current_dir().unwrap().parent().unwrap().join(relpath);
+    let mut command = Command::new(test_bin_path);
+    command
+        .args(args)
+        .env_remove("LANGUAGE")
+        .env("LC_ALL", "pl_PL.UTF-8")
+        .env("TEXTDOMAINDIR", dir)
+        .stdin(Stdio::null());
+    if domain_env {
+        command.env("TEXTDOMAIN", "test");
+    } else {
+        command.env_remove("TEXTDOMAIN");
+    }
+    command.output().unwrap()
+}
+
+fn gettext_test(args: &[&str], dir: &Path, domain_env: bool, expected_out: &str) {
+    let output = run_localized("gettext", args, dir, domain_env);
+    assert_eq!(String::from_utf8_lossy(&output.stdout), expected_out);
+    assert_eq!(String::from_utf8_lossy(&output.stderr), "");
+    assert_eq!(output.status.code(), Some(0));
+}
+
+#[test]
+fn test_gettext_domain() {
+    let dir = TempDir::new().unwrap();
+    install_catalog(dir.path());
+
+    gettext_test(&["-d", "test", "Hello"], dir.path(), false, "Cześć");
+    gettext_test(&["test", "Hello"], dir.path(), false, "Cześć");
+    gettext_test(&["Hello"], dir.path(), true, "Cześć");
+    // Without a domain, or without a translation, the msgid is written.
+    gettext_test(&["Hello"], dir.path(), false, "Hello");
+    gettext_test(&["Untranslated"], dir.path(), true, "Untranslated");
+    gettext_test(&["-d", "other", "Hello"], dir.path(), true, "Hello");
+    // The empty msgid does not retrieve the catalog header.
+    gettext_test(&[""], dir.path(), true, "");
+}
+
+#[test]
+fn test_gettext_escapes() {
+    let dir = TempDir::new().unwrap();
+    install_catalog(dir.path());
+
+    gettext_test(&["-e", r"tab\there"], dir.path(), true, "tab\ttutaj");
+    gettext_test(&["-e", "-E", r"tab\there"], dir.path(), true, r"tab\there");
+    gettext_test(&["-e", r"a\nb\c ignored"], dir.path(), true, "a\nb");
+
+    // octal escapes write single bytes
+    let output = run_localized("gettext", &["-e", r"\0377\0400"], dir.path(), true);
+    assert_eq!(output.stdout, b"\xff 0");
+    assert_eq!(output.status.code(), Some(0));
+}
+
+#[test]
+fn test_gettext_echo() {
+    let dir = TempDir::new().unwrap();
+    install_catalog(dir.path());
+
+    gettext_test(
+        &["-s", "Hello", "Good bye", "world"],
+        dir.path(),
+        true,
+        "Cześć Do widzenia world\n",
+    );
+    gettext_test(&["-s", "-n", "Hello"], dir.path(), true, "Cześć");
+    gettext_test(&["-s", "-e", r"one\c", "two"], dir.path(), true, "one");
+}
+
+#[test]
+fn test_gettext_usage() {
+    run_test(TestPlan {
+        cmd: String::from("gettext"),
+        args: vec![String::from("a"), String::from("b"), String::from("c")],
+        stdin_data: String::new(),
+        expected_out: String::new(),
+        expected_err: String::from("gettext: too many arguments\n"),
+        expected_exit_code: 1,
+    });
+}
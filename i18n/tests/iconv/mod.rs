@@ -8,9 +8,12 @@
 //
 
 #![allow(non_snake_case)]
-use plib::testing::{run_test_u8, TestPlanU8};
+use plib::testing::{run_test_base, run_test_u8, TestPlanU8};
 use std::env;
+use std::fs;
+use std::io::Write;
 use std::path::PathBuf;
+use std::process::{Command, Stdio};
 use std::{fs::File, io::Read};
 
 fn iconv_test(args: &[&str], input: Vec<u8>, expected_output: Vec<u8>, expected_error: Vec<u8>) {
@@ -1083,3 +1086,321 @@ fn iconv_UTF16BE_to_UTF8_conversion_without_c_flag() {
         Vec::new(),
     );
 }
+
+#[test]
+fn iconv_WINDOWS1252_to_UTF8_conversion() {
+    let input = b"\x80 caf\xe9 \x93quoted\x94".to_vec();
+    let expected_output = "€ café “quoted”".as_bytes().to_vec();
+
+    iconv_test(
+        &["-f", "WINDOWS-1252", "-t", "UTF-8"],
+        input.clone(),
+        expected_output.clone(),
+        Vec::new(),
+    );
+
+    iconv_test(
+        &["-f", "UTF-8", "-t", "CP1252"],
+        expected_output,
+        input,
+        Vec::new(),
+    );
+}
+
+#[test]
+fn iconv_single_byte_code_pages() {
+    iconv_test(
+        &["-f", "UTF-8", "-t", "KOI8-R"],
+        "Привет".as_bytes().to_vec(),
+        b"\xf0\xd2\xc9\xd7\xc5\xd4".to_vec(),
+        Vec::new(),
+    );
+
+    // 0xa4 is the currency sign in Latin-1 and the euro sign in Latin-9
+    iconv_test(
+        &["-f", "latin1", "-t", "utf8"],
+        b"\xa4".to_vec(),
+        "¤".as_bytes().to_vec(),
+        Vec::new(),
+    );
+    iconv_test(
+        &["-f", "iso_8859-15", "-t", "UTF-8"],
+        b"\xa4".to_vec(),
+        "€".as_bytes().to_vec(),
+        Vec::new(),
+    );
+
+    iconv_test(
+        &["-f", "IBM437", "-t", "UTF-8"],
+        b"\xc9\xcd\xbb".to_vec(),
+        "╔═╗".as_bytes().to_vec(),
+        Vec::new(),
+    );
+}
+
+#[test]
+fn iconv_single_byte_unmapped_with_c_flag() {
+    // 0x81 is not assigned in Windows-1252, and Cyrillic is not in Latin-1
+    iconv_test(
+        &["-c", "-f", "WINDOWS-1252", "-t", "UTF-8"],
+        b"a\x81b".to_vec(),
+        b"ab".to_vec(),
+        Vec::new(),
+    );
+    iconv_test(
+        &["-c", "-f", "UTF-8", "-t", "LATIN1"],
+        "añбc".as_bytes().to_vec(),
+        b"a\xf1c".to_vec(),
+        Vec::new(),
+    );
+}
+
+#[test]
+fn iconv_translit_suffix() {
+    iconv_test(
+        &["-f", "UTF-8", "-t", "ASCII//TRANSLIT"],
+        "Ärger “quoted” – ﬁne €5 中".as_bytes().to_vec(),
+        b"Arger \"quoted\" - fine EUR5 ?".to_vec(),
+        Vec::new(),
+    );
+
+    // only what the target lacks is transliterated
+    iconv_test(
+        &["-f", "UTF-8", "-t", "LATIN1//TRANSLIT"],
+        "café €".as_bytes().to_vec(),
+        b"caf\xe9 EUR".to_vec(),
+        Vec::new(),
+    );
+
+    iconv_test(
+        &["-f", "UTF-8", "-t", "koi8-r//translit//ignore"],
+        "Straße ✓".as_bytes().to_vec(),
+        b"Strasse ?".to_vec(),
+        Vec::new(),
+    );
+}
+
+#[test]
+fn iconv_ignore_suffix() {
+    iconv_test(
+        &["-f", "UTF-8", "-t", "ASCII//IGNORE"],
+        "Ärger € 中x".as_bytes().to_vec(),
+        b"rger  x".to_vec(),
+        Vec::new(),
+    );
+}
+
+#[test]
+fn iconv_charmap_translit_suffix() {
+    let cargo_manifest_dir = PathBuf::from(env::var("CARGO_MANIFEST_DIR").unwrap());
+    let latin1 = cargo_manifest_dir.join("tests/iconv/test_charmap_latin1");
+    let ascii = cargo_manifest_dir.join("tests/iconv/test_charmap_ascii");
+    let latin1 = latin1.to_str().unwrap();
+
+    iconv_test(
+        &[
+            "-f",
+            latin1,
+            "-t",
+            &format!("{}//TRANSLIT", ascii.display()),
+        ],
+        b"\xc4rger caf\xe9 \xbd\n".to_vec(),
+        b"Arger cafe 1/2\n".to_vec(),
+        Vec::new(),
+    );
+
+    iconv_test(
+        &["-f", latin1, "-t", &format!("{}//IGNORE", ascii.display())],
+        b"\xc4rger caf\xe9\n".to_vec(),
+        b"rger caf\n".to_vec(),
+        Vec::new(),
+    );
+}
+
+#[test]
+fn iconv_charmap_by_name() {
+    let cargo_manifest_dir = PathBuf::from(env::var("CARGO_MANIFEST_DIR").unwrap());
+    let dir = tempfile::tempdir().unwrap();
+    let charmaps = dir.path().join("charmaps");
+    fs::create_dir(&charmaps).unwrap();
+    fs::copy(
+        cargo_manifest_dir.join("tests/iconv/test_charmap_latin1"),
+        charmaps.join("TEST-LATIN1"),
+    )
+    .unwrap();
+    fs::copy(
+        cargo_manifest_dir.join("tests/iconv/test_charmap_ascii"),
+        charmaps.join("TEST-ASCII"),
+    )
+    .unwrap();
+
+    // charmaps that are not built-in encodings are found in I18NPATH
+    let relpath = if cfg!(debug_assertions) {
+        "target/debug/iconv"
+    } else {
+        "target/release/iconv"
+    };
+    let mut child = Command::new(env::current_dir().unwrap().parent().unwrap().join(relpath))
+        .args(["-f", "TEST-LATIN1", "-t", "TEST-ASCII//TRANSLIT"])
+        .env("I18NPATH", dir.path())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(b"\xc4rger caf\xe9\n")
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert_eq!(String::from_utf8_lossy(&output.stderr), "");
+    assert_eq!(output.stdout, b"Arger cafe\n");
+    assert!(output.status.success());
+}
+
+#[test]
+fn iconv_list_codesets() {
+    let output = run_test_base("iconv", &vec![String::from("-l")], b"");
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert!(lines.contains(&"UTF-8"));
+    assert!(lines.contains(&"ISO-8859-1 LATIN1 L1 IBM819 CP819"));
+    assert!(lines.contains(&"KOI8-U"));
+    assert!(lines.contains(&"CP850 IBM850 850"));
+    assert!(lines.contains(&"WINDOWS-1251 CP1251 MS-CYRL"));
+    assert!(lines.contains(&"SHIFT_JIS SJIS MS_KANJI CSSHIFTJIS"));
+    assert!(lines.contains(&"GB18030"));
+}
+
+#[test]
+fn iconv_japanese_conversion() {
+    iconv_test(
+        &["-f", "UTF-8", "-t", "SHIFT_JIS"],
+        "日本語 ¥".as_bytes().to_vec(),
+        b"\x93\xfa\x96\x7b\x8c\xea \x5c".to_vec(),
+        Vec::new(),
+    );
+
+    // CP932 has ASCII at 0x5c, and the NEC extensions
+    iconv_test(
+        &["-f", "CP932", "-t", "UTF-8"],
+        b"\x5c\x81\x60\x87\x40".to_vec(),
+        "\\～①".as_bytes().to_vec(),
+        Vec::new(),
+    );
+
+    // half-width katakana behind SS2, JIS X 0212 behind SS3
+    iconv_test(
+        &["-f", "EUC-JP", "-t", "UTF-8"],
+        b"\x8e\xb1\x8f\xb0\xa1\xc6\xfc".to_vec(),
+        "ｱ丂日".as_bytes().to_vec(),
+        Vec::new(),
+    );
+}
+
+#[test]
+fn iconv_ISO2022JP_conversion() {
+    let utf8 = "¥ a 日本\nb".as_bytes().to_vec();
+    let iso_2022_jp = b"\x1b(J\x5c a \x1b$BF|K\\\x1b(B\nb".to_vec();
+
+    iconv_test(
+        &["-f", "UTF-8", "-t", "ISO-2022-JP"],
+        utf8.clone(),
+        iso_2022_jp.clone(),
+        Vec::new(),
+    );
+    iconv_test(
+        &["-f", "ISO-2022-JP", "-t", "UTF-8"],
+        iso_2022_jp,
+        utf8,
+        Vec::new(),
+    );
+
+    // the output returns to ASCII at the end
+    iconv_test(
+        &["-f", "UTF-8", "-t", "ISO-2022-JP"],
+        "日".as_bytes().to_vec(),
+        b"\x1b$BF|\x1b(B".to_vec(),
+        Vec::new(),
+    );
+}
+
+#[test]
+fn iconv_chinese_conversion() {
+    iconv_test(
+        &["-f", "UTF-8", "-t", "GBK"],
+        "中文€".as_bytes().to_vec(),
+        b"\xd6\xd0\xce\xc4\x80".to_vec(),
+        Vec::new(),
+    );
+
+    // GB18030 has four-byte sequences for the rest of Unicode
+    let utf8 = "é\u{80}😀€".as_bytes().to_vec();
+    let gb18030 = b"\xa8\xa6\x81\x30\x81\x30\x94\x39\xfc\x36\xa2\xe3".to_vec();
+    iconv_test(
+        &["-f", "UTF-8", "-t", "GB18030"],
+        utf8.clone(),
+        gb18030.clone(),
+        Vec::new(),
+    );
+    iconv_test(&["-f", "GB18030", "-t", "UTF-8"], gb18030, utf8, Vec::new());
+
+    iconv_test(
+        &["-f", "BIG5", "-t", "UTF-8"],
+        b"\xa4\xa4\xa4\xe5".to_vec(),
+        "中文".as_bytes().to_vec(),
+        Vec::new(),
+    );
+}
+
+#[test]
+fn iconv_korean_conversion() {
+    iconv_test(
+        &["-f", "UTF-8", "-t", "EUC-KR"],
+        "한국어".as_bytes().to_vec(),
+        b"\xc7\xd1\xb1\xb9\xbe\xee".to_vec(),
+        Vec::new(),
+    );
+}
+
+#[test]
+fn iconv_multi_byte_across_buffer_boundary() {
+    // "日" starts at the last byte of the first buffer, and an invalid
+    // lead byte is followed by a newline in the second
+    let mut input = vec![b'a'; 9999];
+    input.extend(b"\x93\xfa");
+    input.extend(vec![b'b'; 100]);
+    input.extend(b"\x81\n");
+
+    let mut expected_output = vec![b'a'; 9999];
+    expected_output.extend("日".as_bytes());
+    expected_output.extend(vec![b'b'; 100]);
+    expected_output.push(b'\n');
+
+    iconv_test(
+        &["-c", "-f", "SHIFT_JIS", "-t", "UTF-8"],
+        input.clone(),
+        expected_output,
+        Vec::new(),
+    );
+
+    expected_output = vec![b'a'; 9999];
+    expected_output.extend("日".as_bytes());
+    expected_output.extend(vec![b'b'; 100]);
+    run_test_u8(TestPlanU8 {
+        cmd: String::from("iconv"),
+        args: vec![
+            String::from("-f"),
+            String::from("SHIFT_JIS"),
+            String::from("-t"),
+            String::from("UTF-8"),
+        ],
+        stdin_data: input,
+        expected_out: expected_output,
+        expected_err: b"Error: Invalid input position 10101\n".to_vec(),
+        expected_exit_code: 1,
+    });
+}
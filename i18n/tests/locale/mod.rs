@@ -0,0 +1,172 @@
+//
+// Copyright (c) 2024 Hemi Labs, Inc.
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::process::{Command, Output, Stdio};
+
+use tempfile::TempDir;
+
+const SOURCE: &str = r#"comment_char %
+escape_char /
+LC_NUMERIC
+decimal_point "<U002C>"
+thousands_sep "."
+grouping 3;3
+END LC_NUMERIC
+
+LC_TIME
+abday "So";"Mo";"Di";"Mi";"Do";"Fr";"Sa"
+d_t_fmt "%a %d %b %Y %T"
+END LC_TIME
+
+LC_MESSAGES
+yesexpr "^[jJyY]"
+noexpr "^[nN]"
+END LC_MESSAGES
+"#;
+
+/// Run `locale` with only the given locale variables set, reading
+/// locale sources from `i18n_dir`.
+fn run_locale(args: &[&str], vars: &[(&str, &str)], i18n_dir: Option<&Path>) -> Output {
+    let relpath = if cfg!(debug_assertions) {
+        "target/debug/locale"
+    } else {
+        "target/release/locale"
+    };
+    let test_bin_path = env::current_dir().unwrap().parent().unwrap().join(relpath);
+    let mut command = Command::new(test_bin_path);
+    command.args(args).stdin(Stdio::null());
+    for (name, _) in env::vars() {
+        if name == "LANG" || name.starts_with("LC_") || name == "LOCPATH" || name == "I18NPATH" {
+            command.env_remove(name);
+        }
+    }
+    command.envs(vars.iter().copied());
+    if let Some(dir) = i18n_dir {
+        command.env("I18NPATH", dir).env("LOCPATH", dir);
+    }
+    command.output().unwrap()
+}
+
+fn locale_test(args: &[&str], vars: &[(&str, &str)], i18n_dir: Option<&Path>, expected: &str) {
+    let output = run_locale(args, vars, i18n_dir);
+    assert_eq!(String::from_utf8_lossy(&output.stdout), expected);
+    assert_eq!(String::from_utf8_lossy(&output.stderr), "");
+    assert_eq!(output.status.code(), Some(0));
+}
+
+#[test]
+fn test_locale_environment() {
+    locale_test(
+        &[],
+        &[("LANG", "de_DE.UTF-8"), ("LC_TIME", "C")],
+        None,
+        "LANG=de_DE.UTF-8\n\
+         LC_CTYPE=\"de_DE.UTF-8\"\n\
+         LC_COLLATE=\"de_DE.UTF-8\"\n\
+         LC_MESSAGES=\"de_DE.UTF-8\"\n\
+         LC_MONETARY=\"de_DE.UTF-8\"\n\
+         LC_NUMERIC=\"de_DE.UTF-8\"\n\
+         LC_TIME=C\n\
+         LC_ALL=\n",
+    );
+    // LC_ALL overrides the category variables, whose values are implied.
+    locale_test(
+        &[],
+        &[("LC_ALL", "POSIX"), ("LC_TIME", "C")],
+        None,
+        "LANG=\n\
+         LC_CTYPE=\"POSIX\"\n\
+         LC_COLLATE=\"POSIX\"\n\
+         LC_MESSAGES=\"POSIX\"\n\
+         LC_MONETARY=\"POSIX\"\n\
+         LC_NUMERIC=\"POSIX\"\n\
+         LC_TIME=\"POSIX\"\n\
+         LC_ALL=POSIX\n",
+    );
+}
+
+#[test]
+fn test_locale_posix_keywords() {
+    let vars = [("LC_ALL", "C")];
+    locale_test(&["decimal_point", "yesexpr"], &vars, None, ".\n^[yY]\n");
+    locale_test(
+        &["-k", "LC_NUMERIC"],
+        &vars,
+        None,
+        "decimal_point=\".\"\nthousands_sep=\"\"\ngrouping=-1\n",
+    );
+    locale_test(
+        &["-ck", "charmap", "am_pm", "int_frac_digits", "era"],
+        &vars,
+        None,
+        "LC_CTYPE\ncharmap=\"ANSI_X3.4-1968\"\n\
+         LC_TIME\nam_pm=\"AM;PM\"\n\
+         LC_MONETARY\nint_frac_digits=-1\n\
+         LC_TIME\nera=\n",
+    );
+}
+
+#[test]
+fn test_locale_source_keywords() {
+    let dir = TempDir::new().unwrap();
+    fs::create_dir(dir.path().join("locales")).unwrap();
+    fs::write(dir.path().join("locales").join("xx_XX"), SOURCE).unwrap();
+
+    let vars = [("LANG", "xx_XX.UTF-8"), ("LC_MESSAGES", "C")];
+    locale_test(
+        &[
+            "-k",
+            "decimal_point",
+            "grouping",
+            "abday",
+            "d_fmt",
+            "yesexpr",
+        ],
+        &vars,
+        Some(dir.path()),
+        "decimal_point=\",\"\n\
+         grouping=3;3\n\
+         abday=\"So;Mo;Di;Mi;Do;Fr;Sa\"\n\
+         d_fmt=\"%m/%d/%y\"\n\
+         yesexpr=\"^[yY]\"\n",
+    );
+    locale_test(
+        &["charmap", "d_t_fmt"],
+        &vars,
+        Some(dir.path()),
+        "UTF-8\n%a %d %b %Y %T\n",
+    );
+}
+
+#[test]
+fn test_locale_unknown_name() {
+    let output = run_locale(
+        &["decimal_point", "no_such_keyword"],
+        &[("LC_ALL", "C")],
+        None,
+    );
+    assert_eq!(String::from_utf8_lossy(&output.stdout), ".\n");
+    assert_eq!(
+        String::from_utf8_lossy(&output.stderr),
+        "locale: no_such_keyword: unknown keyword or category\n"
+    );
+    assert_eq!(output.status.code(), Some(1));
+}
+
+#[test]
+fn test_locale_available() {
+    let dir = TempDir::new().unwrap();
+    fs::create_dir_all(dir.path().join("xx_XX.utf8")).unwrap();
+    fs::write(dir.path().join("xx_XX.utf8").join("LC_CTYPE"), "").unwrap();
+    fs::create_dir_all(dir.path().join("not_a_locale")).unwrap();
+    locale_test(&["-a"], &[], Some(dir.path()), "C\nPOSIX\nxx_XX.utf8\n");
+}
@@ -0,0 +1,265 @@
+//
+// Copyright (c) 2024 Hemi Labs, Inc.
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+use std::cmp::Ordering;
+use std::env;
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Output, Stdio};
+
+use plib::locale::{load_compiled, Collation, Ctype, SourceLoader};
+use tempfile::TempDir;
+
+const CHARMAP: &str = r#"<code_set_name> TEST-8
+<comment_char> %
+<escape_char> /
+<mb_cur_max> 1
+% ASCII and two more letters
+CHARMAP
+<U0000>..<U007F> /x00 ASCII
+<U00C4> /xc4 LATIN CAPITAL LETTER A WITH DIAERESIS
+<U00E4> /xe4 LATIN SMALL LETTER A WITH DIAERESIS
+END CHARMAP
+"#;
+
+const SOURCE: &str = r#"comment_char %
+escape_char /
+% A test locale: German-like, with "ch" sorting as one letter.
+LC_CTYPE
+upper <U0041>..<U005A>;<U00C4>
+lower <U0061>..<U007A>;<U00E4>
+alpha <U0041>..<U005A>;<U0061>..<U007A>;<U00C4>;<U00E4>
+toupper (<U0061>,<U0041>);(<U00E4>,<U00C4>)
+tolower (<U0041>,<U0061>);(<U00C4>,<U00E4>)
+END LC_CTYPE
+
+LC_COLLATE
+collating-element <ch> from "<U0063><U0068>"
+order_start forward
+<U0061>
+<U00E4>
+<U0062>
+<U0063>
+<U0064>
+<ch>
+<U0065>
+UNDEFINED
+order_end
+END LC_COLLATE
+
+LC_NUMERIC
+decimal_point "<U002C>"
+thousands_sep "."
+grouping 3;3
+END LC_NUMERIC
+
+LC_MONETARY
+int_curr_symbol "XTS "
+currency_symbol "<U00E4>"
+mon_decimal_point ","
+frac_digits 2
+END LC_MONETARY
+
+LC_TIME
+copy "base_TIME"
+END LC_TIME
+
+LC_MESSAGES
+yesexpr "^[jJ]"
+noexpr "^[nN]"
+END LC_MESSAGES
+"#;
+
+const BASE: &str = r#"LC_TIME
+abday "So";"Mo";"Di";"Mi";"Do";"Fr";"Sa"
+d_fmt "%d.%m.%Y"
+END LC_TIME
+"#;
+
+/// Set up a directory holding the test charmap in `charmaps/` and the
+/// locale sources in `locales/`.
+fn setup() -> TempDir {
+    let dir = TempDir::new().unwrap();
+    fs::create_dir(dir.path().join("charmaps")).unwrap();
+    fs::create_dir(dir.path().join("locales")).unwrap();
+    fs::write(dir.path().join("charmaps/TEST-8"), CHARMAP).unwrap();
+    fs::write(dir.path().join("locales/base_TIME"), BASE).unwrap();
+    fs::write(dir.path().join("locales/xx_XX"), SOURCE).unwrap();
+    dir
+}
+
+/// Run a utility with `dir` as both `I18NPATH` and `LOCPATH`, and only
+/// the given locale variables set.
+fn run(cmd: &str, args: &[&str], stdin: &str, dir: &Path, vars: &[(&str, &str)]) -> Output {
+    let relpath = if cfg!(debug_assertions) {
+        format!("target/debug/{cmd}")
+    } else {
+        format!("target/release/{cmd}")
+    };
+    let test_bin_path = env::current_dir().unwrap().parent().unwrap().join(relpath);
+    let mut command = Command::new(test_bin_path);
+    command
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    for (name, _) in env::vars() {
+        if name == "LANG" || name.starts_with("LC_") {
+            command.env_remove(name);
+        }
+    }
+    command
+        .env("I18NPATH", dir)
+        .env("LOCPATH", dir)
+        .envs(vars.iter().copied());
+    let mut child = command.spawn().unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(stdin.as_bytes())
+        .unwrap();
+    child.wait_with_output().unwrap()
+}
+
+fn localedef(args: &[&str], stdin: &str, dir: &Path) -> Output {
+    run("localedef", args, stdin, dir, &[])
+}
+
+#[test]
+fn test_localedef_compile() {
+    let dir = setup();
+    // An input that is not a file is looked up among the sources.
+    let output = localedef(&["-f", "TEST-8", "-i", "xx_XX", "xx_XX"], "", dir.path());
+    assert_eq!(String::from_utf8_lossy(&output.stderr), "");
+    assert_eq!(output.status.code(), Some(0));
+
+    // The compiled locale no longer needs the sources it copied from.
+    fs::remove_dir_all(dir.path().join("locales")).unwrap();
+
+    let output = run(
+        "locale",
+        &[
+            "-k",
+            "charmap",
+            "decimal_point",
+            "currency_symbol",
+            "abday",
+            "d_fmt",
+            "yesexpr",
+        ],
+        "",
+        dir.path(),
+        &[("LC_ALL", "xx_XX")],
+    );
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "charmap=\"TEST-8\"\n\
+         decimal_point=\",\"\n\
+         currency_symbol=\"ä\"\n\
+         abday=\"So;Mo;Di;Mi;Do;Fr;Sa\"\n\
+         d_fmt=\"%d.%m.%Y\"\n\
+         yesexpr=\"^[jJ]\"\n"
+    );
+    assert_eq!(output.status.code(), Some(0));
+
+    let output = run("locale", &["-a"], "", dir.path(), &[]);
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "C\nPOSIX\nxx_XX\n");
+}
+
+#[test]
+fn test_localedef_ctype_collate() {
+    let dir = setup();
+    let locale_dir = dir.path().join("xx_XX");
+    let output = localedef(
+        &["-f", "TEST-8", locale_dir.to_str().unwrap()],
+        SOURCE,
+        dir.path(),
+    );
+    assert_eq!(String::from_utf8_lossy(&output.stderr), "");
+    assert_eq!(output.status.code(), Some(0));
+
+    let source = load_compiled(&locale_dir).unwrap();
+    let mut loader = SourceLoader::with_dirs(Vec::new());
+    let ctype = Ctype::from_source(&source, &mut loader).unwrap();
+    assert!(ctype.iswctype('ä', "lower"));
+    assert_eq!(ctype.towupper('ä'), 'Ä');
+
+    let collation = Collation::from_source(&source, &mut loader).unwrap();
+    let cmp = |a: &str, b: &str| collation.strcoll(a.as_bytes(), b.as_bytes());
+    assert_eq!(cmp("ä", "b"), Ordering::Less);
+    assert_eq!(cmp("ch", "d"), Ordering::Greater);
+    assert_eq!(cmp("ch", "e"), Ordering::Less);
+}
+
+#[test]
+fn test_localedef_warnings() {
+    let dir = setup();
+    let source = "LC_MONETARY\n\
+                  currency_symbol \"<U20AC>\"\n\
+                  mon_decimal_point \"<no-such-char>\"\n\
+                  END LC_MONETARY\n";
+
+    // Without -c, warnings leave no output.
+    let output = localedef(&["-f", "TEST-8", "xx_XX"], source, dir.path());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stderr),
+        "localedef: -: line 2: <U20AC>: not defined in charmap\n\
+         localedef: -: line 3: <no-such-char>: undefined symbolic name\n\
+         localedef: no output created because of warnings\n"
+    );
+    assert_eq!(output.status.code(), Some(4));
+    assert!(!dir.path().join("xx_XX").exists());
+
+    // With -c the locale is created anyway.
+    let output = localedef(&["-c", "-f", "TEST-8", "xx_XX"], source, dir.path());
+    assert_eq!(output.status.code(), Some(1));
+    assert!(dir.path().join("xx_XX/LC_MONETARY").is_file());
+
+    // The same characters are fine in a Unicode locale.
+    let source = "LC_MONETARY\ncurrency_symbol \"<U20AC>\"\nEND LC_MONETARY\n";
+    let output = localedef(&["yy_YY"], source, dir.path());
+    assert_eq!(String::from_utf8_lossy(&output.stderr), "");
+    assert_eq!(output.status.code(), Some(0));
+    let output = run(
+        "locale",
+        &["currency_symbol"],
+        "",
+        dir.path(),
+        &[("LC_ALL", "yy_YY")],
+    );
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "€\n");
+}
+
+#[test]
+fn test_localedef_errors() {
+    let dir = setup();
+    let output = localedef(&["-f", "NO-SUCH-CHARMAP", "xx_XX"], "", dir.path());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stderr),
+        "localedef: NO-SUCH-CHARMAP: charmap not found\n"
+    );
+    assert_eq!(output.status.code(), Some(2));
+
+    let output = localedef(
+        &["xx_XX"],
+        "LC_TIME\ncopy \"missing\"\nEND LC_TIME\n",
+        dir.path(),
+    );
+    assert_eq!(
+        String::from_utf8_lossy(&output.stderr),
+        "localedef: -: line 2: cannot find locale definition 'missing'\n"
+    );
+    assert_eq!(output.status.code(), Some(4));
+
+    let output = localedef(&["xx_XX"], "LC_NUMERIC\ngrouping x\n", dir.path());
+    assert_eq!(output.status.code(), Some(4));
+    assert!(!dir.path().join("xx_XX").exists());
+}
@@ -0,0 +1,148 @@
+//
+// Copyright (c) 2024 Hemi Labs, Inc.
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+use gettextrs::mo::Catalog;
+use plib::testing::{run_test, TestPlan};
+use tempfile::TempDir;
+
+const PO: &str = r#"# German translations.
+msgid ""
+msgstr ""
+"Content-Type: text/plain; charset=UTF-8\n"
+"Plural-Forms: nplurals=2; plural=n != 1;\n"
+
+#, c-format
+msgid "%s: no such file"
+msgstr "%s: Datei nicht gefunden"
+
+msgctxt "menu"
+msgid "Open"
+msgstr "Öffnen"
+
+#, c-format
+msgid "%d file"
+msgid_plural "%d files"
+msgstr[0] "%d Datei"
+msgstr[1] "%d Dateien"
+
+#, fuzzy
+msgid "Close"
+msgstr "Schließen"
+
+msgid "Quit"
+msgstr ""
+
+#~ msgid "Old"
+#~ msgstr "Alt"
+"#;
+
+fn msgfmt_test(args: &[&str], po: &str, expected_err: &str, expected_exit_code: i32) {
+    run_test(TestPlan {
+        cmd: String::from("msgfmt"),
+        args: args.iter().map(|s| String::from(*s)).collect(),
+        stdin_data: String::from(po),
+        expected_out: String::new(),
+        expected_err: String::from(expected_err),
+        expected_exit_code,
+    });
+}
+
+#[test]
+fn test_msgfmt_catalog() {
+    let dir = TempDir::new().unwrap();
+    let mo = dir.path().join("de.mo");
+    msgfmt_test(
+        &["--statistics", "-o", mo.to_str().unwrap(), "-"],
+        PO,
+        "3 translated messages, 1 fuzzy translation, 1 untranslated message.\n",
+        0,
+    );
+
+    let catalog = Catalog::open(&mo).unwrap();
+    assert_eq!(catalog.len(), 4);
+    assert_eq!(
+        catalog.get(None, "%s: no such file").as_deref(),
+        Some("%s: Datei nicht gefunden")
+    );
+    assert_eq!(catalog.get(Some("menu"), "Open").as_deref(), Some("Öffnen"));
+    assert_eq!(catalog.get(None, "Open"), None);
+    assert_eq!(
+        catalog.get_plural(None, "%d file", 1).as_deref(),
+        Some("%d Datei")
+    );
+    assert_eq!(
+        catalog.get_plural(None, "%d file", 5).as_deref(),
+        Some("%d Dateien")
+    );
+    assert_eq!(catalog.get(None, "Close"), None);
+    assert_eq!(catalog.get(None, "Quit"), None);
+    assert_eq!(catalog.get(None, "Old"), None);
+}
+
+#[test]
+fn test_msgfmt_use_fuzzy() {
+    let dir = TempDir::new().unwrap();
+    let mo = dir.path().join("de.mo");
+    msgfmt_test(&["-f", "-o", mo.to_str().unwrap(), "-"], PO, "", 0);
+
+    let catalog = Catalog::open(&mo).unwrap();
+    assert_eq!(catalog.get(None, "Close").as_deref(), Some("Schließen"));
+}
+
+#[test]
+fn test_msgfmt_check() {
+    let dir = TempDir::new().unwrap();
+    let mo = dir.path().join("de.mo");
+    let mo = mo.to_str().unwrap();
+    msgfmt_test(&["-c", "-o", mo, "-"], PO, "", 0);
+
+    let po = "msgid \"\"\n\
+              msgstr \"Content-Type: text/plain; charset=UTF-8\\n\"\n\
+              \n\
+              #, c-format\n\
+              msgid \"%d of %s\"\n\
+              msgstr \"%s von %d\"\n\
+              \n\
+              msgid \"line\\n\"\n\
+              msgstr \"Zeile\"\n\
+              \n\
+              msgid \"%d file\"\n\
+              msgid_plural \"%d files\"\n\
+              msgstr[0] \"%d Datei\"\n\
+              msgstr[1] \"%d Dateien\"\n";
+    msgfmt_test(
+        &["-c", "-o", mo, "-"],
+        po,
+        "msgfmt: -:1: message catalog has plural form translations, but lacks a header entry with \"Plural-Forms: nplurals=INTEGER; plural=EXPRESSION;\"\n\
+         msgfmt: -:5: format specifications in 'msgid' and 'msgstr' for argument 1 are not the same\n\
+         msgfmt: -:8: 'msgid' and 'msgstr' entries do not both end with '\\n'\n\
+         msgfmt: found 3 fatal errors\n",
+        1,
+    );
+    // Without -c, the same file compiles.
+    msgfmt_test(&["-o", mo, "-"], po, "", 0);
+}
+
+#[test]
+fn test_msgfmt_errors() {
+    msgfmt_test(
+        &["-o", "/dev/null", "-"],
+        "msgid \"a\"\nmsgstr \"b\"\n\nmsgid \"a\"\nmsgstr \"c\"\n",
+        "msgfmt: -:4: duplicate message definition\n\
+         msgfmt: -:1: ...this is the location of the first definition\n\
+         msgfmt: found 1 fatal error\n",
+        1,
+    );
+    msgfmt_test(
+        &["-o", "/dev/null", "-"],
+        "msgid \"a\"\nmsgstr[0] \"b\"\n",
+        "msgfmt: -:2: missing 'msgid_plural' section\nmsgfmt: found 1 fatal error\n",
+        1,
+    );
+}
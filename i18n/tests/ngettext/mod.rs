@@ -0,0 +1,71 @@
+//
+// Copyright (c) 2024 Hemi Labs, Inc.
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+use std::path::Path;
+
+use crate::gettext::{install_catalog, run_localized};
+use plib::testing::{run_test, TestPlan};
+use tempfile::TempDir;
+
+fn ngettext_test(args: &[&str], dir: &Path, expected_out: &str) {
+    let output = run_localized("ngettext", args, dir, true);
+    assert_eq!(String::from_utf8_lossy(&output.stdout), expected_out);
+    assert_eq!(String::from_utf8_lossy(&output.stderr), "");
+    assert_eq!(output.status.code(), Some(0));
+}
+
+#[test]
+fn test_ngettext_plural_forms() {
+    let dir = TempDir::new().unwrap();
+    install_catalog(dir.path());
+
+    // The three Polish forms, chosen by the catalog's Plural-Forms.
+    for (n, expected) in [
+        ("1", "%d plik"),
+        ("3", "%d pliki"),
+        ("22", "%d pliki"),
+        ("5", "%d plików"),
+        ("12", "%d plików"),
+        ("0", "%d plików"),
+        ("10000000004", "%d pliki"),
+    ] {
+        ngettext_test(&["%d file", "%d files", n], dir.path(), expected);
+    }
+    ngettext_test(
+        &["test", "%d file", "%d files", "2"],
+        dir.path(),
+        "%d pliki",
+    );
+}
+
+#[test]
+fn test_ngettext_untranslated() {
+    let dir = TempDir::new().unwrap();
+    install_catalog(dir.path());
+
+    ngettext_test(&["-d", "other", "one", "many", "1"], dir.path(), "one");
+    ngettext_test(&["-d", "other", "one", "many", "2"], dir.path(), "many");
+    ngettext_test(
+        &["-e", "-d", "other", r"a\tb", r"c\td", "1"],
+        dir.path(),
+        "a\tb",
+    );
+}
+
+#[test]
+fn test_ngettext_invalid_count() {
+    run_test(TestPlan {
+        cmd: String::from("ngettext"),
+        args: vec![String::from("one"), String::from("many"), String::from("x")],
+        stdin_data: String::new(),
+        expected_out: String::new(),
+        expected_err: String::from("ngettext: invalid count: x\n"),
+        expected_exit_code: 1,
+    });
+}
@@ -0,0 +1,194 @@
+//
+// Copyright (c) 2024 Hemi Labs, Inc.
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+use std::fs;
+
+use plib::testing::{run_test, TestPlan};
+use tempfile::TempDir;
+
+fn xgettext_test(args: &[&str], stdin: &str, expected_out: &str) {
+    run_test(TestPlan {
+        cmd: String::from("xgettext"),
+        args: args.iter().map(|s| String::from(*s)).collect(),
+        stdin_data: String::from(stdin),
+        expected_out: String::from(expected_out),
+        expected_err: String::new(),
+        expected_exit_code: 0,
+    });
+}
+
+const RUST_SOURCE: &str = r###"use gettextrs::{gettext, ngettext};
+
+fn main() {
+    // TRANSLATORS: the program's greeting
+    println!("{}", gettext("Hello, world!"));
+    eprintln!("{}", gettext!("cannot open '{}'", name));
+    let s = ngettext("one file", "{} files", n);
+    let menu = pgettext("menu", r#"Open "file""#);
+    let long = gettext(concat!("first part, ", "second part"));
+    let escaped = gettext("tab\there\n");
+    let not_literal = gettext(name);
+    let again = gettext("Hello, world!");
+    let lifetime: &'static str = "not extracted";
+}
+"###;
+
+#[test]
+fn test_xgettext_rust() {
+    xgettext_test(
+        &[
+            "--omit-header",
+            "-c",
+            "TRANSLATORS",
+            "-L",
+            "Rust",
+            "-o",
+            "-",
+            "-",
+        ],
+        RUST_SOURCE,
+        r#"#. TRANSLATORS: the program's greeting
+#: -:5 -:12
+msgid "Hello, world!"
+msgstr ""
+
+#: -:6
+#, rust-format
+msgid "cannot open '{}'"
+msgstr ""
+
+#: -:7
+msgid "one file"
+msgid_plural "{} files"
+msgstr[0] ""
+msgstr[1] ""
+
+#: -:8
+msgctxt "menu"
+msgid "Open \"file\""
+msgstr ""
+
+#: -:9
+msgid "first part, second part"
+msgstr ""
+
+#: -:10
+msgid "tab\there\n"
+msgstr ""
+"#,
+    );
+}
+
+#[test]
+fn test_xgettext_c() {
+    xgettext_test(
+        &[
+            "--omit-header",
+            "--no-location",
+            "-k",
+            "_",
+            "-k",
+            "N_:2",
+            "-s",
+            "-o",
+            "-",
+            "-",
+        ],
+        "printf(_(\"%d items\\n\"), n);\n\
+         puts(gettext(\"split \" \"literal\"));\n\
+         x = N_(1, \"second\");\n\
+         y = N_(\"not\", 2);\n",
+        r#"#, c-format
+msgid "%d items\n"
+msgstr ""
+
+msgid "second"
+msgstr ""
+
+msgid "split literal"
+msgstr ""
+"#,
+    );
+}
+
+#[test]
+fn test_xgettext_options() {
+    // -a extracts every string, and -m and -M fill in the msgstr.
+    xgettext_test(
+        &["--omit-header", "-a", "-m", "[", "-M", "]", "-o", "-", "-"],
+        "char *s = \"plain\";\n",
+        "#: -:1\nmsgid \"plain\"\nmsgstr \"[plain]\"\n",
+    );
+
+    // An empty keyword disables the defaults.
+    xgettext_test(
+        &["--omit-header", "-k", "", "-k", "tr", "-o", "-", "-"],
+        "gettext(\"no\"); tr(\"yes\");\n",
+        "#: -:1\nmsgid \"yes\"\nmsgstr \"\"\n",
+    );
+}
+
+#[test]
+fn test_xgettext_join_and_exclude() {
+    let dir = TempDir::new().unwrap();
+    let pot = dir.path().join("test.pot");
+    let exclude = dir.path().join("exclude.po");
+    fs::write(
+        &pot,
+        "#: old.c:1\nmsgid \"kept\"\nmsgstr \"\"\n\n#: old.c:2\nmsgid \"shared\"\nmsgstr \"\"\n",
+    )
+    .unwrap();
+    fs::write(&exclude, "msgid \"skipped\"\nmsgstr \"\"\n").unwrap();
+
+    let pot_name = pot.to_str().unwrap();
+    xgettext_test(
+        &[
+            "--omit-header",
+            "-j",
+            "-x",
+            exclude.to_str().unwrap(),
+            "-o",
+            pot_name,
+            "-",
+        ],
+        "gettext(\"shared\"); gettext(\"skipped\");\ngettext(\"new\");\n",
+        "",
+    );
+    assert_eq!(
+        fs::read_to_string(&pot).unwrap(),
+        "#: old.c:1\nmsgid \"kept\"\nmsgstr \"\"\n\n\
+         #: old.c:2 -:1\nmsgid \"shared\"\nmsgstr \"\"\n\n\
+         #: -:2\nmsgid \"new\"\nmsgstr \"\"\n"
+    );
+}
+
+#[test]
+fn test_xgettext_header() {
+    let dir = TempDir::new().unwrap();
+    let source = dir.path().join("prog.rs");
+    fs::write(&source, "gettext(\"caf\u{e9}\");\n").unwrap();
+    xgettext_test(
+        &[
+            "-p",
+            dir.path().to_str().unwrap(),
+            "-d",
+            "prog",
+            source.to_str().unwrap(),
+        ],
+        "",
+        "",
+    );
+    let po = fs::read_to_string(dir.path().join("prog.po")).unwrap();
+    assert!(po.starts_with("# SOME DESCRIPTIVE TITLE.\n"));
+    assert!(po.contains(
+        "#, fuzzy\nmsgid \"\"\nmsgstr \"\"\n\"Project-Id-Version: PACKAGE VERSION\\n\"\n"
+    ));
+    assert!(po.contains("\"Content-Type: text/plain; charset=UTF-8\\n\"\n"));
+    assert!(po.ends_with("msgid \"café\"\nmsgstr \"\"\n"));
+}
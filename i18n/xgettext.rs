@@ -0,0 +1,691 @@
+//
+// Copyright (c) 2024 Hemi Labs, Inc.
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+//! xgettext - extract gettext call strings from C and Rust programs
+//!
+//! The arguments of calls to the keywords (`gettext`, `ngettext`,
+//! `pgettext` and the rest, as functions or as Rust macros) are written
+//! as a portable object template.
+
+mod xgettext_lib;
+
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+
+use chrono::Local;
+use clap::Parser;
+use gettextrs::{bind_textdomain_codeset, gettext, setlocale, textdomain, LocaleCategory};
+use plib::po::{self, format, Message};
+use xgettext_lib::{c, rust, Comment, Lexed, Token, TokenKind};
+
+const DEFAULT_DOMAIN: &str = "messages";
+
+/// The keywords recognized unless `-k ''` is given.
+const DEFAULT_KEYWORDS: &[&str] = &[
+    "gettext",
+    "dgettext:2",
+    "dcgettext:2",
+    "ngettext:1,2",
+    "dngettext:2,3",
+    "dcngettext:2,3",
+    "gettext_noop",
+    "pgettext:1c,2",
+    "npgettext:1c,2,3",
+];
+
+/// xgettext - extract gettext call strings from C and Rust programs
+#[derive(Parser)]
+#[command(version, about = gettext("xgettext - extract gettext call strings from C and Rust programs"))]
+struct Args {
+    #[arg(short = 'a', long = "extract-all", help = gettext("Extract all strings, not just the keyword arguments"))]
+    extract_all: bool,
+
+    #[arg(
+        short = 'c',
+        long = "add-comments",
+        value_name = "TAG",
+        help = gettext("Add the comments that start with TAG and precede keyword lines to the output")
+    )]
+    comment_tag: Option<String>,
+
+    #[arg(
+        short = 'd',
+        long = "default-domain",
+        help = gettext("Write the output to DOMAIN.po instead of messages.po")
+    )]
+    domain: Option<String>,
+
+    #[arg(
+        short = 'D',
+        long = "directory",
+        help = gettext("Add a directory to the search list for input files")
+    )]
+    directories: Vec<PathBuf>,
+
+    #[arg(
+        short = 'f',
+        long = "files-from",
+        help = gettext("Read the names of the input files from this file, or - for standard input")
+    )]
+    files_from: Option<PathBuf>,
+
+    #[arg(short = 'j', long = "join-existing", help = gettext("Join the messages with the existing output file"))]
+    join: bool,
+
+    #[arg(
+        short = 'k',
+        long = "keyword",
+        value_name = "KEYWORDSPEC",
+        help = gettext("Additional keyword, as NAME or NAME:ARGNUM[c],...; an empty one disables the default keywords")
+    )]
+    keywords: Vec<String>,
+
+    #[arg(short = 'L', long = "language", help = gettext("The language of the input files, C or Rust"))]
+    language: Option<String>,
+
+    #[arg(short = 'm', long = "msgstr-prefix", help = gettext("Fill each msgstr with this prefix and the msgid"))]
+    msgstr_prefix: Option<String>,
+
+    #[arg(short = 'M', long = "msgstr-suffix", help = gettext("Fill each msgstr with the msgid and this suffix"))]
+    msgstr_suffix: Option<String>,
+
+    #[arg(short = 'n', long = "add-location", help = gettext("Write source references (the default)"))]
+    add_location: bool,
+
+    #[arg(long = "no-location", help = gettext("Do not write source references"))]
+    no_location: bool,
+
+    #[arg(short = 'o', long = "output", help = gettext("Write the output to this file, or - for standard output"))]
+    output: Option<PathBuf>,
+
+    #[arg(short = 'p', long = "output-dir", help = gettext("Write the output file in this directory"))]
+    output_dir: Option<PathBuf>,
+
+    #[arg(short = 's', long = "sort-output", help = gettext("Sort the messages by msgid"))]
+    sort: bool,
+
+    #[arg(
+        short = 'x',
+        long = "exclude-file",
+        help = gettext("Leave out the messages of this portable object file")
+    )]
+    exclude_files: Vec<PathBuf>,
+
+    #[arg(long = "from-code", help = gettext("The encoding of the input files: UTF-8 or ISO-8859-1"))]
+    from_code: Option<String>,
+
+    #[arg(long = "omit-header", help = gettext("Do not write a header entry"))]
+    omit_header: bool,
+
+    #[arg(help = gettext("Input files, or - for standard input"))]
+    files: Vec<PathBuf>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Language {
+    C,
+    Rust,
+}
+
+impl Language {
+    fn from_name(name: &str) -> Option<Language> {
+        match name.to_ascii_lowercase().as_str() {
+            "c" | "c++" => Some(Language::C),
+            "rust" => Some(Language::Rust),
+            _ => None,
+        }
+    }
+
+    fn from_path(path: &Path) -> Language {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("rs") => Language::Rust,
+            _ => Language::C,
+        }
+    }
+
+    fn lex(self, source: &str) -> Lexed {
+        match self {
+            Language::C => c::lex(source),
+            Language::Rust => rust::lex(source),
+        }
+    }
+}
+
+/// Which arguments of a keyword call hold the message, numbered from 1.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Keyword {
+    singular: usize,
+    plural: Option<usize>,
+    context: Option<usize>,
+}
+
+impl Keyword {
+    /// Parse `NAME[:ARGNUM[c],...]`, where the argument marked `c` is the
+    /// context and the others are the msgid and the plural msgid.
+    fn parse(spec: &str) -> Option<(String, Keyword)> {
+        let (name, argspec) = match spec.split_once(':') {
+            Some((name, argspec)) => (name, Some(argspec)),
+            None => (spec, None),
+        };
+        if name.is_empty() {
+            return None;
+        }
+        let mut keyword = Keyword {
+            singular: 1,
+            plural: None,
+            context: None,
+        };
+        if let Some(argspec) = argspec {
+            let mut numbers = Vec::new();
+            for arg in argspec.split(',') {
+                match arg.strip_suffix('c') {
+                    Some(n) => keyword.context = Some(n.parse().ok().filter(|&n| n > 0)?),
+                    None => numbers.push(arg.parse().ok().filter(|&n| n > 0)?),
+                }
+            }
+            match numbers[..] {
+                [singular] => keyword.singular = singular,
+                [singular, plural] => {
+                    keyword.singular = singular;
+                    keyword.plural = Some(plural);
+                }
+                _ => return None,
+            }
+        }
+        Some((name.to_string(), keyword))
+    }
+}
+
+fn is_punct(tokens: &[Token], i: usize, c: char) -> bool {
+    tokens.get(i).is_some_and(|t| t.kind == TokenKind::Punct(c))
+}
+
+/// Split the arguments of the call whose `(` is at `open`, or return
+/// `None` if the parenthesis is not closed.
+fn arguments(tokens: &[Token], open: usize) -> Option<Vec<&[Token]>> {
+    let mut args = Vec::new();
+    let mut depth = 0;
+    let mut start = open + 1;
+    for (i, token) in tokens.iter().enumerate().skip(open + 1) {
+        match token.kind {
+            TokenKind::Punct('(' | '[' | '{') => depth += 1,
+            TokenKind::Punct(')' | ']' | '}') if depth > 0 => depth -= 1,
+            TokenKind::Punct(')') => {
+                args.push(&tokens[start..i]);
+                return Some(args);
+            }
+            TokenKind::Punct(',') if depth == 0 => {
+                args.push(&tokens[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// The value of an argument that is a string literal: in C, adjacent
+/// literals, and in Rust, one literal or a `concat!` of literals.
+fn literal(arg: &[Token], language: Language) -> Option<Vec<u8>> {
+    let strings = |tokens: &[Token]| -> Option<Vec<u8>> {
+        let mut value = Vec::new();
+        for token in tokens {
+            match &token.kind {
+                TokenKind::Str(s) => value.extend_from_slice(s),
+                _ => return None,
+            }
+        }
+        (!tokens.is_empty()).then_some(value)
+    };
+    match (language, arg) {
+        (Language::C, _) => strings(arg),
+        (Language::Rust, [_]) => strings(arg),
+        (Language::Rust, [concat, ..])
+            if concat.kind == TokenKind::Ident(String::from("concat"))
+                && is_punct(arg, 1, '!')
+                && is_punct(arg, 2, '(')
+                && is_punct(arg, arg.len() - 1, ')') =>
+        {
+            let mut value = Vec::new();
+            for part in arguments(arg, 2)? {
+                if !part.is_empty() {
+                    value.extend(literal(part, language)?);
+                }
+            }
+            Some(value)
+        }
+        _ => None,
+    }
+}
+
+/// Whether a string has a `{}`-style placeholder, as substituted by the
+/// `gettext!` macros.
+fn has_placeholder(s: &[u8]) -> bool {
+    let mut i = 0;
+    while i < s.len() {
+        if s[i] == b'{' {
+            if s.get(i + 1) == Some(&b'{') {
+                i += 2;
+                continue;
+            }
+            let inner = s[i + 1..]
+                .iter()
+                .take_while(|&&b| b.is_ascii_alphanumeric() || b"_:.<>^+-#".contains(&b))
+                .count();
+            if s.get(i + 1 + inner) == Some(&b'}') {
+                return true;
+            }
+        }
+        i += 1;
+    }
+    false
+}
+
+/// Convert a source file to UTF-8 from the `--from-code` encoding.
+fn decode(data: Vec<u8>, from_code: Option<&str>) -> Result<String, String> {
+    match from_code.map(|c| c.to_ascii_uppercase()).as_deref() {
+        None | Some("UTF-8" | "UTF8" | "ASCII" | "US-ASCII") => {
+            String::from_utf8(data).map_err(|_| {
+                gettext("invalid UTF-8 in input; specify the source encoding with --from-code")
+            })
+        }
+        Some("ISO-8859-1" | "ISO8859-1" | "LATIN1") => {
+            Ok(data.into_iter().map(char::from).collect())
+        }
+        Some(_) => Err(gettext("unsupported source encoding")),
+    }
+}
+
+struct Extractor {
+    keywords: HashMap<String, Keyword>,
+    extract_all: bool,
+    comment_tag: Option<String>,
+    locations: bool,
+    excluded: HashSet<Vec<u8>>,
+    messages: Vec<Message>,
+    index: HashMap<Vec<u8>, usize>,
+}
+
+impl Extractor {
+    /// Add a message, or merge it into an earlier one with the same
+    /// context and msgid.
+    fn add(&mut self, mut message: Message) {
+        let key = message.key();
+        if message.id.is_empty() || self.excluded.contains(&key) {
+            return;
+        }
+        if !self.locations {
+            message.references.clear();
+        }
+        match self.index.get(&key) {
+            Some(&i) => {
+                let existing = &mut self.messages[i];
+                for reference in message.references {
+                    if !existing.references.contains(&reference) {
+                        existing.references.push(reference);
+                    }
+                }
+                for comment in message.extracted_comments {
+                    if !existing.extracted_comments.contains(&comment) {
+                        existing.extracted_comments.push(comment);
+                    }
+                }
+                for flag in message.flags {
+                    if !existing.flags.contains(&flag) {
+                        existing.flags.push(flag);
+                    }
+                }
+                if existing.id_plural.is_none() && message.id_plural.is_some() {
+                    existing.id_plural = message.id_plural;
+                    existing.strs.resize(2, Vec::new());
+                }
+            }
+            None => {
+                self.index.insert(key, self.messages.len());
+                self.messages.push(message);
+            }
+        }
+    }
+
+    /// The tagged comment that ends on the line of a keyword or the line
+    /// before it, from the line with the tag onwards.
+    fn comment(&self, comments: &[Comment], line: usize) -> Option<String> {
+        let tag = self.comment_tag.as_deref()?;
+        let comment = comments
+            .iter()
+            .rev()
+            .find(|c| c.end_line <= line)
+            .filter(|c| c.end_line + 1 >= line)?;
+        let lines: Vec<&str> = comment.text.lines().collect();
+        let start = lines.iter().position(|l| l.starts_with(tag))?;
+        Some(lines[start..].join(" "))
+    }
+
+    fn extract(&mut self, name: &str, language: Language, source: &str) {
+        let lexed = language.lex(source);
+        let tokens = &lexed.tokens;
+
+        if self.extract_all {
+            let mut i = 0;
+            while i < tokens.len() {
+                let mut end = i;
+                while matches!(tokens.get(end).map(|t| &t.kind), Some(TokenKind::Str(_))) {
+                    end += 1;
+                    if language == Language::Rust {
+                        break;
+                    }
+                }
+                if end > i {
+                    let id = literal(&tokens[i..end], Language::C).unwrap_or_default();
+                    let line = tokens[i].line;
+                    self.add(Message {
+                        id,
+                        strs: vec![Vec::new()],
+                        references: vec![format!("{name}:{line}")],
+                        extracted_comments: self
+                            .comment(&lexed.comments, line)
+                            .into_iter()
+                            .collect(),
+                        ..Message::default()
+                    });
+                    i = end;
+                } else {
+                    i += 1;
+                }
+            }
+        }
+
+        for (i, token) in tokens.iter().enumerate() {
+            let TokenKind::Ident(ident) = &token.kind else {
+                continue;
+            };
+            let Some(keyword) = self.keywords.get(ident) else {
+                continue;
+            };
+            let is_macro = language == Language::Rust && is_punct(tokens, i + 1, '!');
+            let open = if is_macro { i + 2 } else { i + 1 };
+            if !is_punct(tokens, open, '(') {
+                continue;
+            }
+            let Some(args) = arguments(tokens, open) else {
+                continue;
+            };
+            let string = |n: usize| args.get(n - 1).and_then(|a| literal(a, language));
+            let Some(id) = string(keyword.singular) else {
+                continue;
+            };
+            let context = match keyword.context {
+                Some(n) => match string(n) {
+                    Some(context) => Some(context),
+                    None => continue,
+                },
+                None => None,
+            };
+            let id_plural = keyword.plural.and_then(string);
+
+            let mut flags = Vec::new();
+            let texts = || std::iter::once(&id).chain(id_plural.iter());
+            match language {
+                Language::Rust if is_macro && texts().any(|s| has_placeholder(s)) => {
+                    flags.push(String::from("rust-format"))
+                }
+                Language::C if texts().any(|s| format::has_directives(s)) => {
+                    flags.push(String::from("c-format"))
+                }
+                _ => {}
+            }
+            let forms = if id_plural.is_some() { 2 } else { 1 };
+            self.add(Message {
+                context,
+                id,
+                id_plural,
+                strs: vec![Vec::new(); forms],
+                references: vec![format!("{}:{}", name, token.line)],
+                extracted_comments: self
+                    .comment(&lexed.comments, token.line)
+                    .into_iter()
+                    .collect(),
+                flags,
+                ..Message::default()
+            });
+        }
+    }
+}
+
+/// The header entry of a new template.
+fn header(messages: &[Message]) -> Message {
+    let charset = if messages.iter().any(|m| {
+        m.key()
+            .iter()
+            .chain(m.id_plural.iter().flatten())
+            .any(|b| !b.is_ascii())
+    }) {
+        "UTF-8"
+    } else {
+        "CHARSET"
+    };
+    let mut fields = format!(
+        "Project-Id-Version: PACKAGE VERSION\n\
+         Report-Msgid-Bugs-To: \n\
+         POT-Creation-Date: {}\n\
+         PO-Revision-Date: YEAR-MO-DA HO:MI+ZONE\n\
+         Last-Translator: FULL NAME <EMAIL@ADDRESS>\n\
+         Language-Team: LANGUAGE <LL@li.org>\n\
+         Language: \n\
+         MIME-Version: 1.0\n\
+         Content-Type: text/plain; charset={charset}\n\
+         Content-Transfer-Encoding: 8bit\n",
+        Local::now().format("%Y-%m-%d %H:%M%z")
+    );
+    if messages.iter().any(|m| m.id_plural.is_some()) {
+        fields.push_str("Plural-Forms: nplurals=INTEGER; plural=EXPRESSION;\n");
+    }
+    Message {
+        strs: vec![fields.into_bytes()],
+        comments: vec![
+            String::from("SOME DESCRIPTIVE TITLE."),
+            String::from("Copyright (C) YEAR THE PACKAGE'S COPYRIGHT HOLDER"),
+            String::from("This file is distributed under the same license as the PACKAGE package."),
+            String::from("FIRST AUTHOR <EMAIL@ADDRESS>, YEAR."),
+            String::new(),
+        ],
+        flags: vec![String::from("fuzzy")],
+        ..Message::default()
+    }
+}
+
+fn read_input(path: &Path) -> io::Result<Vec<u8>> {
+    if path.as_os_str() == "-" {
+        let mut data = Vec::new();
+        io::stdin().lock().read_to_end(&mut data)?;
+        Ok(data)
+    } else {
+        fs::read(path)
+    }
+}
+
+/// Find an input file, looking in the `-D` directories for a relative
+/// name that does not exist.
+fn resolve(path: &Path, directories: &[PathBuf]) -> PathBuf {
+    if path.is_absolute() || path.exists() || path.as_os_str() == "-" {
+        return path.to_path_buf();
+    }
+    directories
+        .iter()
+        .map(|dir| dir.join(path))
+        .find(|p| p.exists())
+        .unwrap_or_else(|| path.to_path_buf())
+}
+
+fn read_po(path: &Path) -> Result<Vec<Message>, String> {
+    let data = fs::read(path).map_err(|e| format!("{}: {e}", path.display()))?;
+    po::parse(&data).map_err(|e| format!("{}:{}: {}", path.display(), e.line, gettext(e.message)))
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    setlocale(LocaleCategory::LcAll, "");
+    textdomain("posixutils-rs")?;
+    bind_textdomain_codeset("posixutils-rs", "UTF-8")?;
+
+    let args = Args::parse();
+    let mut exit_code = 0;
+
+    let language = match args.language.as_deref() {
+        Some(name) => match Language::from_name(name) {
+            Some(language) => Some(language),
+            None => {
+                eprintln!("xgettext: {}: {name}", gettext("unknown language"));
+                std::process::exit(1);
+            }
+        },
+        None => None,
+    };
+
+    let mut keywords = HashMap::new();
+    let defaults = DEFAULT_KEYWORDS.iter().map(|s| s.to_string());
+    let disable_defaults = args.keywords.iter().any(|k| k.is_empty());
+    for spec in defaults
+        .filter(|_| !disable_defaults)
+        .chain(args.keywords.iter().filter(|k| !k.is_empty()).cloned())
+    {
+        match Keyword::parse(&spec) {
+            Some((name, keyword)) => {
+                keywords.insert(name, keyword);
+            }
+            None => {
+                eprintln!(
+                    "xgettext: {}: {spec}",
+                    gettext("invalid keyword specification")
+                );
+                std::process::exit(1);
+            }
+        }
+    }
+
+    let mut files = args.files.clone();
+    if let Some(list) = &args.files_from {
+        match read_input(list) {
+            Ok(data) => files.extend(
+                String::from_utf8_lossy(&data)
+                    .lines()
+                    .map(str::trim)
+                    .filter(|l| !l.is_empty() && !l.starts_with('#'))
+                    .map(PathBuf::from),
+            ),
+            Err(e) => {
+                eprintln!("xgettext: {}: {e}", list.display());
+                std::process::exit(1);
+            }
+        }
+    }
+    if files.is_empty() {
+        eprintln!("xgettext: {}", gettext("no input file given"));
+        std::process::exit(1);
+    }
+
+    let output = match &args.output {
+        Some(path) if path.as_os_str() == "-" => path.clone(),
+        Some(path) => args.output_dir.clone().unwrap_or_default().join(path),
+        None => {
+            let domain = args.domain.as_deref().unwrap_or(DEFAULT_DOMAIN);
+            args.output_dir
+                .clone()
+                .unwrap_or_default()
+                .join(format!("{domain}.po"))
+        }
+    };
+
+    let mut extractor = Extractor {
+        keywords,
+        extract_all: args.extract_all,
+        comment_tag: args.comment_tag.clone(),
+        locations: args.add_location || !args.no_location,
+        excluded: HashSet::new(),
+        messages: Vec::new(),
+        index: HashMap::new(),
+    };
+    for path in &args.exclude_files {
+        match read_po(&resolve(path, &args.directories)) {
+            Ok(messages) => extractor.excluded.extend(messages.iter().map(Message::key)),
+            Err(e) => {
+                eprintln!("xgettext: {e}");
+                std::process::exit(1);
+            }
+        }
+    }
+    if args.join && output.as_os_str() != "-" {
+        match read_po(&output) {
+            Ok(messages) => {
+                for message in messages {
+                    extractor
+                        .index
+                        .insert(message.key(), extractor.messages.len());
+                    extractor.messages.push(message);
+                }
+            }
+            Err(e) => {
+                eprintln!("xgettext: {e}");
+                std::process::exit(1);
+            }
+        }
+    }
+
+    for path in &files {
+        let name = path.display().to_string();
+        let source = read_input(&resolve(path, &args.directories))
+            .map_err(|e| e.to_string())
+            .and_then(|data| decode(data, args.from_code.as_deref()));
+        match source {
+            Ok(source) => {
+                let language = language.unwrap_or_else(|| Language::from_path(path));
+                extractor.extract(&name, language, &source);
+            }
+            Err(e) => {
+                eprintln!("xgettext: {name}: {e}");
+                exit_code = 1;
+            }
+        }
+    }
+
+    let mut messages = extractor.messages;
+    if args.sort {
+        messages.sort_by(|a, b| {
+            (!a.is_header(), &a.id, &a.context).cmp(&(!b.is_header(), &b.id, &b.context))
+        });
+    }
+    if args.msgstr_prefix.is_some() || args.msgstr_suffix.is_some() {
+        let prefix = args.msgstr_prefix.as_deref().unwrap_or_default().as_bytes();
+        let suffix = args.msgstr_suffix.as_deref().unwrap_or_default().as_bytes();
+        for message in messages.iter_mut().filter(|m| !m.is_header()) {
+            if message.strs.iter().all(|s| s.is_empty()) {
+                let sources = std::iter::once(&message.id).chain(message.id_plural.iter());
+                message.strs = sources.map(|s| [prefix, s, suffix].concat()).collect();
+            }
+        }
+    }
+    if !args.omit_header && !messages.iter().any(Message::is_header) {
+        messages.insert(0, header(&messages));
+    }
+
+    let mut out = Vec::new();
+    po::write(&mut out, &messages)?;
+    let written = if output.as_os_str() == "-" {
+        io::Write::write_all(&mut io::stdout().lock(), &out)
+    } else {
+        fs::write(&output, &out)
+    };
+    if let Err(e) = written {
+        eprintln!("xgettext: {}: {e}", output.display());
+        exit_code = 1;
+    }
+
+    std::process::exit(exit_code)
+}
@@ -0,0 +1,125 @@
+//
+// Copyright (c) 2024 Hemi Labs, Inc.
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+//! The C lexer.  Adjacent string literals are left as separate tokens;
+//! the extractor joins them.
+
+use super::{push_char, Lexed, Scanner, TokenKind};
+
+/// Read a string or character literal after its opening quote.
+fn literal(s: &mut Scanner, quote: char) -> Vec<u8> {
+    let mut out = Vec::new();
+    while let Some(c) = s.next() {
+        match c {
+            c if c == quote => break,
+            '\n' => break,
+            '\\' => match s.next() {
+                Some('n') => out.push(b'\n'),
+                Some('t') => out.push(b'\t'),
+                Some('r') => out.push(b'\r'),
+                Some('a') => out.push(0x07),
+                Some('b') => out.push(0x08),
+                Some('f') => out.push(0x0c),
+                Some('v') => out.push(0x0b),
+                Some('e') => out.push(0x1b),
+                Some('x') => out.push(s.hex_digits(2).unwrap_or(0) as u8),
+                Some(d @ '0'..='7') => {
+                    let mut value = d.to_digit(8).unwrap();
+                    for _ in 0..2 {
+                        match s.peek().and_then(|c| c.to_digit(8)) {
+                            Some(d) => {
+                                value = value * 8 + d;
+                                s.next();
+                            }
+                            None => break,
+                        }
+                    }
+                    out.push(value as u8);
+                }
+                Some('\n') => {}
+                Some(c) => push_char(&mut out, c),
+                None => break,
+            },
+            c => push_char(&mut out, c),
+        }
+    }
+    out
+}
+
+pub fn lex(source: &str) -> Lexed {
+    let mut s = Scanner::new(source);
+    while let Some(c) = s.peek() {
+        let line = s.line;
+        match c {
+            c if c.is_whitespace() => {
+                s.next();
+            }
+            // A line continuation outside a literal.
+            '\\' if s.peek_at(1) == Some('\n') => {
+                s.next();
+                s.next();
+            }
+            '/' if s.peek_at(1) == Some('/') => s.line_comment(),
+            '/' if s.peek_at(1) == Some('*') => s.block_comment(false),
+            '"' => {
+                s.next();
+                let value = literal(&mut s, '"');
+                s.push(TokenKind::Str(value), line);
+            }
+            '\'' => {
+                s.next();
+                literal(&mut s, '\'');
+                s.push(TokenKind::Literal, line);
+            }
+            c if c.is_ascii_digit() => {
+                s.number();
+                s.push(TokenKind::Literal, line);
+            }
+            c if c == '_' || c.is_alphanumeric() => {
+                let ident = s.ident();
+                s.push(TokenKind::Ident(ident), line);
+            }
+            c => {
+                s.next();
+                s.push(TokenKind::Punct(c), line);
+            }
+        }
+    }
+    s.lexed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lex() {
+        let lexed =
+            lex("/* Translators: a\n * b */\nprintf(gettext(\"x\\101\\x42\\n\"\n \"y\"), 'q');\n");
+        let kinds: Vec<TokenKind> = lexed.tokens.into_iter().map(|t| t.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                TokenKind::Ident("printf".into()),
+                TokenKind::Punct('('),
+                TokenKind::Ident("gettext".into()),
+                TokenKind::Punct('('),
+                TokenKind::Str(b"xAB\n".to_vec()),
+                TokenKind::Str(b"y".to_vec()),
+                TokenKind::Punct(')'),
+                TokenKind::Punct(','),
+                TokenKind::Literal,
+                TokenKind::Punct(')'),
+                TokenKind::Punct(';'),
+            ]
+        );
+        assert_eq!(lexed.comments[0].text, "Translators: a\nb");
+        assert_eq!(lexed.comments[0].end_line, 2);
+    }
+}
@@ -0,0 +1,199 @@
+//
+// Copyright (c) 2024 Hemi Labs, Inc.
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+//! Source scanners for `xgettext`.  Each language's lexer reduces a
+//! source file to identifiers, string literals and punctuation, plus the
+//! comments between them.
+
+pub mod c;
+pub mod rust;
+
+/// A lexical token of a source file.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TokenKind {
+    Ident(String),
+    /// A string literal that can hold a message.
+    Str(Vec<u8>),
+    /// Any other literal: a number, a character or a byte string.
+    Literal,
+    Punct(char),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Token {
+    pub kind: TokenKind,
+    pub line: usize,
+}
+
+/// A comment, or a run of line comments on consecutive lines.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Comment {
+    pub text: String,
+    pub end_line: usize,
+}
+
+/// A source file reduced to tokens and comments.
+#[derive(Debug, Default)]
+pub struct Lexed {
+    pub tokens: Vec<Token>,
+    pub comments: Vec<Comment>,
+}
+
+/// Character-level state shared by the lexers.
+pub struct Scanner {
+    chars: Vec<char>,
+    pos: usize,
+    pub line: usize,
+    pub lexed: Lexed,
+}
+
+impl Scanner {
+    pub fn new(source: &str) -> Scanner {
+        Scanner {
+            chars: source.chars().collect(),
+            pos: 0,
+            line: 1,
+            lexed: Lexed::default(),
+        }
+    }
+
+    pub fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    pub fn peek_at(&self, offset: usize) -> Option<char> {
+        self.chars.get(self.pos + offset).copied()
+    }
+
+    pub fn next(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += 1;
+        if c == '\n' {
+            self.line += 1;
+        }
+        Some(c)
+    }
+
+    pub fn push(&mut self, kind: TokenKind, line: usize) {
+        self.lexed.tokens.push(Token { kind, line });
+    }
+
+    /// Record a comment, joining it to the previous one if that ended on
+    /// the line before, with nothing in between.
+    pub fn push_comment(&mut self, text: &str, start_line: usize) {
+        let text = text.trim();
+        let follows = self.lexed.comments.last().is_some_and(|c| {
+            c.end_line + 1 == start_line
+                && self
+                    .lexed
+                    .tokens
+                    .last()
+                    .map_or(true, |t| t.line < start_line)
+        });
+        match self.lexed.comments.last_mut() {
+            Some(last) if follows => {
+                last.text.push('\n');
+                last.text.push_str(text);
+                last.end_line = self.line;
+            }
+            _ => self.lexed.comments.push(Comment {
+                text: text.to_string(),
+                end_line: self.line,
+            }),
+        }
+    }
+
+    /// Skip a `//` comment, recording its text without the slashes and
+    /// any doc-comment marker.
+    pub fn line_comment(&mut self) {
+        let line = self.line;
+        let mut text = String::new();
+        while let Some(c) = self.peek().filter(|&c| c != '\n') {
+            text.push(c);
+            self.pos += 1;
+        }
+        let text = text.trim_start_matches('/');
+        let text = text.strip_prefix('!').unwrap_or(text);
+        self.push_comment(text, line);
+    }
+
+    /// Skip a `/* */` comment, nested if `nested` is set, recording its
+    /// text without the delimiters and leading asterisks.
+    pub fn block_comment(&mut self, nested: bool) {
+        let line = self.line;
+        self.pos += 2;
+        let mut depth = 1;
+        let mut text = String::new();
+        while let Some(c) = self.next() {
+            if c == '*' && self.peek() == Some('/') {
+                self.pos += 1;
+                depth -= 1;
+                if depth == 0 {
+                    break;
+                }
+            } else if nested && c == '/' && self.peek() == Some('*') {
+                self.pos += 1;
+                depth += 1;
+            } else {
+                text.push(c);
+            }
+        }
+        let text: Vec<&str> = text
+            .lines()
+            .map(|l| {
+                let l = l.trim();
+                l.strip_prefix('*').unwrap_or(l).trim()
+            })
+            .collect();
+        self.push_comment(&text.join("\n"), line);
+    }
+
+    pub fn ident(&mut self) -> String {
+        let mut ident = String::new();
+        while let Some(c) = self.peek().filter(|&c| c == '_' || c.is_alphanumeric()) {
+            ident.push(c);
+            self.pos += 1;
+        }
+        ident
+    }
+
+    /// Skip a number, with any suffix or exponent.
+    pub fn number(&mut self) {
+        while let Some(c) = self.peek() {
+            let exponent_sign = matches!(c, '+' | '-')
+                && self.pos > 0
+                && matches!(self.chars[self.pos - 1], 'e' | 'E' | 'p' | 'P');
+            let fraction = c == '.' && self.peek_at(1).is_some_and(|d| d.is_ascii_digit());
+            if c == '_' || c.is_ascii_alphanumeric() || exponent_sign || fraction {
+                self.pos += 1;
+            } else {
+                break;
+            }
+        }
+    }
+
+    /// Read `count` at most hexadecimal digits.
+    pub fn hex_digits(&mut self, count: usize) -> Option<u32> {
+        let mut value = None;
+        for _ in 0..count {
+            let Some(d) = self.peek().and_then(|c| c.to_digit(16)) else {
+                break;
+            };
+            value = Some(value.unwrap_or(0u32).wrapping_mul(16).wrapping_add(d));
+            self.pos += 1;
+        }
+        value
+    }
+}
+
+/// Append `c` to `out` as UTF-8.
+pub fn push_char(out: &mut Vec<u8>, c: char) {
+    let mut buf = [0; 4];
+    out.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+}
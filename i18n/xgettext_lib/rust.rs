@@ -0,0 +1,216 @@
+//
+// Copyright (c) 2024 Hemi Labs, Inc.
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+//! The Rust lexer: string and raw string literals, nested block comments,
+//! and character literals told apart from lifetimes.
+
+use super::{push_char, Lexed, Scanner, TokenKind};
+
+/// Read a string literal after its opening quote.
+fn string(s: &mut Scanner) -> Vec<u8> {
+    let mut out = Vec::new();
+    while let Some(c) = s.next() {
+        match c {
+            '"' => break,
+            '\\' => match s.next() {
+                Some('n') => out.push(b'\n'),
+                Some('r') => out.push(b'\r'),
+                Some('t') => out.push(b'\t'),
+                Some('0') => out.push(0),
+                Some('x') => out.push(s.hex_digits(2).unwrap_or(0) as u8),
+                Some('u') => {
+                    if s.peek() == Some('{') {
+                        s.next();
+                        let mut value = 0u32;
+                        while let Some(c) = s.next().filter(|&c| c != '}') {
+                            if let Some(d) = c.to_digit(16) {
+                                value = value.wrapping_mul(16).wrapping_add(d);
+                            }
+                        }
+                        push_char(&mut out, char::from_u32(value).unwrap_or('\u{fffd}'));
+                    }
+                }
+                // A line continuation skips the newline and the
+                // indentation after it.
+                Some('\n') => {
+                    while s.peek().is_some_and(char::is_whitespace) {
+                        s.next();
+                    }
+                }
+                Some(c) => push_char(&mut out, c),
+                None => break,
+            },
+            c => push_char(&mut out, c),
+        }
+    }
+    out
+}
+
+/// Read a raw string literal from its `#`s onwards, returning its
+/// contents.
+fn raw_string(s: &mut Scanner) -> Vec<u8> {
+    let mut hashes = 0;
+    while s.peek() == Some('#') {
+        s.next();
+        hashes += 1;
+    }
+    s.next(); // the opening quote
+    let mut out = Vec::new();
+    while let Some(c) = s.next() {
+        if c == '"' && (0..hashes).all(|i| s.peek_at(i) == Some('#')) {
+            for _ in 0..hashes {
+                s.next();
+            }
+            break;
+        }
+        push_char(&mut out, c);
+    }
+    out
+}
+
+/// Whether the scanner is at the start of a raw string: `#`s and a quote.
+fn at_raw_string(s: &Scanner) -> bool {
+    let mut i = 0;
+    while s.peek_at(i) == Some('#') {
+        i += 1;
+    }
+    s.peek_at(i) == Some('"')
+}
+
+pub fn lex(source: &str) -> Lexed {
+    let mut s = Scanner::new(source);
+    while let Some(c) = s.peek() {
+        let line = s.line;
+        match c {
+            c if c.is_whitespace() => {
+                s.next();
+            }
+            '/' if s.peek_at(1) == Some('/') => s.line_comment(),
+            '/' if s.peek_at(1) == Some('*') => s.block_comment(true),
+            '"' => {
+                s.next();
+                let value = string(&mut s);
+                s.push(TokenKind::Str(value), line);
+            }
+            '\'' => {
+                s.next();
+                let is_char = s.peek() == Some('\\') || s.peek_at(1) == Some('\'');
+                if is_char {
+                    while let Some(c) = s.next() {
+                        if c == '\\' {
+                            s.next();
+                        } else if c == '\'' {
+                            break;
+                        }
+                    }
+                    s.push(TokenKind::Literal, line);
+                } else {
+                    s.push(TokenKind::Punct('\''), line);
+                }
+            }
+            c if c.is_ascii_digit() => {
+                s.number();
+                s.push(TokenKind::Literal, line);
+            }
+            c if c == '_' || c.is_alphanumeric() => {
+                let ident = s.ident();
+                match (ident.as_str(), s.peek()) {
+                    ("r", Some('#' | '"')) if at_raw_string(&s) => {
+                        let value = raw_string(&mut s);
+                        s.push(TokenKind::Str(value), line);
+                    }
+                    ("b" | "c", Some('"')) => {
+                        s.next();
+                        string(&mut s);
+                        s.push(TokenKind::Literal, line);
+                    }
+                    ("b" | "c", Some('\'')) => {
+                        s.next();
+                        while let Some(c) = s.next() {
+                            if c == '\\' {
+                                s.next();
+                            } else if c == '\'' {
+                                break;
+                            }
+                        }
+                        s.push(TokenKind::Literal, line);
+                    }
+                    ("br" | "cr", Some('#' | '"')) if at_raw_string(&s) => {
+                        raw_string(&mut s);
+                        s.push(TokenKind::Literal, line);
+                    }
+                    // A raw identifier, `r#name`.
+                    ("r", Some('#')) => {
+                        s.next();
+                        let ident = s.ident();
+                        s.push(TokenKind::Ident(ident), line);
+                    }
+                    _ => s.push(TokenKind::Ident(ident), line),
+                }
+            }
+            c => {
+                s.next();
+                s.push(TokenKind::Punct(c), line);
+            }
+        }
+    }
+    s.lexed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(source: &str) -> Vec<TokenKind> {
+        lex(source).tokens.into_iter().map(|t| t.kind).collect()
+    }
+
+    fn string(s: &str) -> TokenKind {
+        TokenKind::Str(s.as_bytes().to_vec())
+    }
+
+    #[test]
+    fn test_strings() {
+        assert_eq!(
+            kinds(r#"gettext("a\tb\"\u{e9}\x41")"#),
+            vec![
+                TokenKind::Ident("gettext".into()),
+                TokenKind::Punct('('),
+                string("a\tb\"éA"),
+                TokenKind::Punct(')'),
+            ]
+        );
+        assert_eq!(
+            kinds("r#\"raw \"quoted\" \\n\"# r\"x\""),
+            vec![string("raw \"quoted\" \\n"), string("x")]
+        );
+        assert_eq!(kinds("\"one \\\n    two\""), vec![string("one two")]);
+        assert_eq!(
+            kinds("b\"bytes\" 'c' '\\'' r#type"),
+            vec![
+                TokenKind::Literal,
+                TokenKind::Literal,
+                TokenKind::Literal,
+                TokenKind::Ident("type".into())
+            ]
+        );
+    }
+
+    #[test]
+    fn test_lifetimes_and_comments() {
+        let lexed = lex("// first\n// second\nfn f<'a>(x: &'a str) /* a /* nested */ b */ {}\n");
+        assert_eq!(lexed.tokens[3].kind, TokenKind::Punct('\''));
+        assert_eq!(lexed.tokens[4].kind, TokenKind::Ident("a".into()));
+        assert_eq!(lexed.tokens[4].line, 3);
+        assert_eq!(lexed.comments.len(), 2);
+        assert_eq!(lexed.comments[0].text, "first\nsecond");
+        assert_eq!(lexed.comments[0].end_line, 2);
+        assert_eq!(lexed.comments[1].text, "a  nested  b");
+    }
+}
@@ -0,0 +1,87 @@
+//
+// Copyright (c) 2024 Hemi Labs, Inc.
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+//! Members of `ar` archives, which makefiles name as `lib(member)`.
+
+use std::{
+    fs,
+    path::Path,
+    time::{Duration, SystemTime},
+};
+
+const MAGIC: &[u8] = b"!<arch>\n";
+const HEADER_LEN: usize = 60;
+
+/// Splits `lib(member)` into the archive and the member.
+pub fn split_member(name: &str) -> Option<(&str, &str)> {
+    let (archive, member) = name.strip_suffix(')')?.split_once('(')?;
+    if archive.is_empty() || member.is_empty() {
+        return None;
+    }
+    Some((archive, member))
+}
+
+/// The modification time of `member` as recorded in the archive at
+/// `path`, or `None` if the archive cannot be read or has no such member.
+pub fn member_modified(path: impl AsRef<Path>, member: &str) -> Option<SystemTime> {
+    let data = fs::read(path).ok()?;
+    let mut rest = data.strip_prefix(MAGIC)?;
+    // the table of long names of System V archives
+    let mut long_names: &[u8] = &[];
+
+    while rest.len() >= HEADER_LEN {
+        let (header, body) = rest.split_at(HEADER_LEN);
+        let size: usize = field(&header[48..58])?.parse().ok()?;
+        let body = body.get(..size)?;
+        rest = rest.get(HEADER_LEN + size + size % 2..).unwrap_or_default();
+
+        let raw_name = field(&header[..16])?;
+        let name = if raw_name == "//" {
+            long_names = body;
+            continue;
+        } else if let Some(len) = raw_name.strip_prefix("#1/") {
+            // BSD: the name starts the body
+            let len: usize = len.parse().ok()?;
+            let name = body.get(..len)?;
+            std::str::from_utf8(name).ok()?.trim_end_matches('\0')
+        } else if let Some(offset) = raw_name.strip_prefix('/') {
+            // System V: the symbol table, or an offset into the long names
+            let Ok(offset) = offset.parse::<usize>() else {
+                continue;
+            };
+            let name = long_names.get(offset..)?;
+            let end = name.iter().position(|&b| b == b'\n').unwrap_or(name.len());
+            std::str::from_utf8(&name[..end])
+                .ok()?
+                .trim_end_matches('/')
+        } else {
+            raw_name.trim_end_matches('/')
+        };
+
+        if name == member {
+            let seconds = field(&header[16..28])?.parse().ok()?;
+            return Some(SystemTime::UNIX_EPOCH + Duration::from_secs(seconds));
+        }
+    }
+
+    None
+}
+
+/// `time` truncated to whole seconds, as archives record it.
+pub fn whole_seconds(time: SystemTime) -> SystemTime {
+    match time.duration_since(SystemTime::UNIX_EPOCH) {
+        Ok(since) => SystemTime::UNIX_EPOCH + Duration::from_secs(since.as_secs()),
+        Err(_) => time,
+    }
+}
+
+/// A header field, without its padding.
+fn field(bytes: &[u8]) -> Option<&str> {
+    Some(std::str::from_utf8(bytes).ok()?.trim_end())
+}
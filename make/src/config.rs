@@ -9,6 +9,15 @@
 
 use std::collections::{BTreeMap, BTreeSet};
 
+/// The suffixes of the built-in inference rules, in the order in which
+/// they are tried.
+const DEFAULT_SUFFIXES: [&str; 10] = [
+    ".o", ".c", ".y", ".l", ".a", ".sh", ".c~", ".y~", ".l~", ".sh~",
+];
+
+/// Marks the built-in macros and rules that only XSI systems have.
+const XSI: &str = "XSI ";
+
 /// Represents the configuration of the make utility
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Config {
@@ -34,6 +43,12 @@ pub struct Config {
     pub print: bool,
     /// Whether to not delete interrupted files on async events.
     pub precious: bool,
+    /// The number of recipes that may run at the same time
+    pub jobs: usize,
+    /// Whether `.NOTPARALLEL` makes targets be built one at a time
+    pub not_parallel: bool,
+    /// Whether to report why each target is made or not
+    pub debug: bool,
 
     pub rules: BTreeMap<String, BTreeSet<String>>,
 }
@@ -51,16 +66,14 @@ impl Default for Config {
             clear: false,
             print: false,
             precious: false,
+            jobs: 1,
+            not_parallel: false,
+            debug: false,
             terminate: true,
             rules: BTreeMap::from([
                 (
                     ".SUFFIXES".to_string(),
-                    vec![
-                        ".o", ".c", ".y", ".l", ".a", ".sh", ".c~", ".y~", ".l~", ".sh~",
-                    ]
-                    .into_iter()
-                    .map(String::from)
-                    .collect(),
+                    DEFAULT_SUFFIXES.into_iter().map(String::from).collect(),
                 ),
                 (
                     ".SCCS_GET".to_string(),
@@ -92,8 +105,7 @@ impl Default for Config {
                     [
                         // Single-Suffix Rules
                         ".c: $(CC) $(CFLAGS) $(LDFLAGS) -o $@ $<",
-                        ".sh: cp $< $@",
-                        ".sh: chmod a+x $@",
+                        ".sh: cp $< $@; chmod a+x $@",
 
                         // Double-Suffix Rules
                         ".c.o: $(CC) $(CFLAGS) -c $<",
@@ -118,6 +130,95 @@ impl Default for Config {
 }
 
 impl Config {
+    /// The built-in macros, suffixes and inference rules, as a makefile to
+    /// be read before the makefile itself.  Empty once `-r` has cleared
+    /// them.
+    pub fn builtins(&self) -> String {
+        let mut builtins = String::new();
+
+        for definition in self.rules.get(".MACROS").into_iter().flatten() {
+            builtins.push_str(definition.trim_start_matches(XSI));
+            builtins.push('\n');
+        }
+
+        if let Some(suffixes) = self.rules.get(".SUFFIXES") {
+            builtins.push_str(".SUFFIXES:");
+            let ordered = DEFAULT_SUFFIXES
+                .into_iter()
+                .filter(|suffix| suffixes.contains(*suffix));
+            let others = suffixes
+                .iter()
+                .map(String::as_str)
+                .filter(|suffix| !DEFAULT_SUFFIXES.contains(suffix));
+            for suffix in ordered.chain(others) {
+                builtins.push(' ');
+                builtins.push_str(suffix);
+            }
+            builtins.push('\n');
+        }
+
+        let mut rules = BTreeMap::<&str, Vec<&str>>::new();
+        for rule in self.rules.get("SUFFIX RULES").into_iter().flatten() {
+            if let Some((target, recipe)) = rule.trim_start_matches(XSI).split_once(": ") {
+                rules.entry(target).or_default().push(recipe);
+            }
+        }
+        for (target, recipes) in rules {
+            builtins.push_str(target);
+            builtins.push_str(":\n");
+            for recipe in recipes {
+                builtins.push('\t');
+                builtins.push_str(recipe);
+                builtins.push('\n');
+            }
+        }
+
+        builtins
+    }
+
+    /// Sets the options in `makeflags`, the value `MAKEFLAGS` has in the
+    /// environment: their letters, as in `ks`, or as options, as in
+    /// `-k -s`.  Macro definitions and unknown letters are ignored.
+    pub fn apply_makeflags(&mut self, makeflags: &str) {
+        for word in makeflags.split_whitespace() {
+            if word.contains('=') {
+                continue;
+            }
+            for flag in word.trim_start_matches('-').chars() {
+                match flag {
+                    'e' => self.env_macros = true,
+                    'i' => self.ignore = true,
+                    'k' => self.keep_going = true,
+                    'n' => self.dry_run = true,
+                    'q' => self.quit = true,
+                    'r' => self.clear = true,
+                    's' => self.silent = true,
+                    'S' => self.terminate = true,
+                    't' => self.touch = true,
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    /// The value of `MAKEFLAGS` that passes the options on to the makes
+    /// that recipes run: the letters of the options that are set.
+    pub fn makeflags(&self) -> String {
+        [
+            (self.env_macros, 'e'),
+            (self.ignore, 'i'),
+            (self.keep_going, 'k'),
+            (self.dry_run, 'n'),
+            (self.quit, 'q'),
+            (self.clear, 'r'),
+            (self.silent, 's'),
+            (self.touch, 't'),
+        ]
+        .into_iter()
+        .filter_map(|(set, flag)| set.then_some(flag))
+        .collect()
+    }
+
     /// Adds a new suffix to the `.SUFFIXES` rule.
     pub fn add_suffix(&mut self, new_suffix: &str) {
         self.rules
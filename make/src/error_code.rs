@@ -25,6 +25,7 @@ pub enum ErrorCode {
     // Specific
     NoMakefile,
     NotUpToDateError { target: String },
+    NotRemade { target: String },
     NoTarget { target: Option<String> },
     NoRule { rule: String },
     RecursivePrerequisite { origin: String },
@@ -44,7 +45,7 @@ impl From<&ErrorCode> for i32 {
 
         match err {
             NotUpToDateError { .. } => 1,
-            ExecutionError { .. } => 2,
+            ExecutionError { .. } | NotRemade { .. } => 2,
             IoError(_) => 3,
             ParserError { .. } => 4,
             NoMakefile => 5,
@@ -64,6 +65,13 @@ impl fmt::Display for ErrorCode {
             NotUpToDateError { target } => {
                 write!(f, "{}: {}", target, gettext("target is not up to date"))
             }
+            NotRemade { target } => write!(
+                f,
+                "{} {} {}",
+                gettext("Target"),
+                target,
+                gettext("not remade because of errors")
+            ),
             ExecutionError { exit_code } => match exit_code {
                 Some(exit_code) => {
                     write!(f, "{}: {}", gettext("execution error"), exit_code)
@@ -79,7 +87,15 @@ impl fmt::Display for ErrorCode {
             },
             IoError(err) => write!(f, "{}: {}", gettext("io error"), err),
             NoMakefile => write!(f, "{}", gettext("no makefile")),
-            ParserError { constraint } => write!(f, "{}: {}", gettext("parse error"), constraint),
+            // each error on a line of its own, continuing the `make: `
+            // prefix the first line is given
+            ParserError { constraint } => {
+                let errors = constraint
+                    .0
+                    .iter()
+                    .map(|err| format!("{}: {err}", gettext("parse error")));
+                write!(f, "{}", errors.collect::<Vec<_>>().join("\nmake: "))
+            }
             NoTarget { target } => match target {
                 Some(target) => write!(f, "{} '{}'", gettext("no target"), target),
                 None => write!(f, "{}", gettext("no targets to execute")),
@@ -0,0 +1,213 @@
+//
+// Copyright (c) 2024 Hemi Labs, Inc.
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+//! Why targets are made: the reasons `-d` reports for each target, and the
+//! dependency graph `--dot` writes out.
+
+use core::fmt;
+use std::{
+    fmt::Write,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use gettextrs::gettext;
+
+use crate::{is_newer, scheduler::Graph, Make};
+
+/// A reason for a target to be made, or not.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Reason<'a> {
+    /// No rule makes the target, which is an existing file.
+    Exists { modified: Option<SystemTime> },
+    /// An inference rule makes the target from `prerequisite`.
+    Inference {
+        rule: &'a str,
+        prerequisite: &'a str,
+    },
+    /// No rule makes the target, so the `.DEFAULT` rule does.
+    Default,
+    /// The target is phony, so it is always made.
+    Phony,
+    /// The target does not exist.
+    Missing,
+    /// `prerequisite` is newer than the target.
+    Newer {
+        prerequisite: &'a str,
+        modified: Option<SystemTime>,
+        newer: Option<SystemTime>,
+    },
+    /// `prerequisite` is made, so the target is too.
+    Remade { prerequisite: &'a str },
+    /// The target is newer than its prerequisites.
+    UpToDate { modified: Option<SystemTime> },
+}
+
+impl fmt::Display for Reason<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use Reason::*;
+
+        match self {
+            Exists { modified } => write!(
+                f,
+                "{} ({})",
+                gettext("no rule, the file exists"),
+                Time(*modified)
+            ),
+            Inference { rule, prerequisite } => write!(
+                f,
+                "{} '{}' {} '{}'",
+                gettext("inference rule"),
+                rule,
+                gettext("from"),
+                prerequisite
+            ),
+            Default => write!(f, "{} '.DEFAULT'", gettext("no rule, using")),
+            Phony => write!(f, "{}", gettext("target is phony")),
+            Missing => write!(f, "{}", gettext("target does not exist")),
+            Newer {
+                prerequisite,
+                modified,
+                newer,
+            } => write!(
+                f,
+                "{} '{}' {} ({} > {})",
+                gettext("prerequisite"),
+                prerequisite,
+                gettext("is newer"),
+                Time(*newer),
+                Time(*modified)
+            ),
+            Remade { prerequisite } => write!(
+                f,
+                "{} '{}' {}",
+                gettext("prerequisite"),
+                prerequisite,
+                gettext("is made")
+            ),
+            UpToDate { modified } => {
+                write!(
+                    f,
+                    "{} ({})",
+                    gettext("target is up to date"),
+                    Time(*modified)
+                )
+            }
+        }
+    }
+}
+
+/// A modification time, as seconds since the epoch.
+struct Time(Option<SystemTime>);
+
+impl fmt::Display for Time {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0.and_then(|time| time.duration_since(UNIX_EPOCH).ok()) {
+            Some(time) => write!(f, "{}.{:09}", time.as_secs(), time.subsec_nanos()),
+            None => write!(f, "{}", gettext("missing")),
+        }
+    }
+}
+
+impl<'a> Graph<'a> {
+    /// The reasons for the target of `node` to be made or not, once the
+    /// nodes of its prerequisites are in the graph.
+    pub fn reasons(&self, make: &Make, node: usize) -> Vec<Reason<'_>> {
+        let node = &self.nodes[node];
+        let mut reasons = Vec::new();
+
+        match (node.rule, &node.implied) {
+            (None, _) => reasons.push(Reason::Exists {
+                modified: node.modified,
+            }),
+            (Some(rule), Some(implied)) => reasons.push(Reason::Inference {
+                rule: rule.targets().next().map_or("", |target| target.as_ref()),
+                prerequisite: implied,
+            }),
+            (Some(rule), None) => {
+                if make
+                    .default_rule
+                    .as_ref()
+                    .is_some_and(|default| std::ptr::eq(default, rule))
+                {
+                    reasons.push(Reason::Default);
+                }
+            }
+        }
+        if node.rule.is_none() {
+            return reasons;
+        }
+
+        if node.phony {
+            reasons.push(Reason::Phony);
+        }
+        if node.modified.is_none() {
+            reasons.push(Reason::Missing);
+        }
+        for prerequisite in node.prerequisites.iter().map(|&i| &self.nodes[i]) {
+            if prerequisite.stale {
+                reasons.push(Reason::Remade {
+                    prerequisite: &prerequisite.name,
+                });
+            } else if node.modified.is_some()
+                && is_newer(prerequisite.modified, &node.name, node.modified)
+            {
+                reasons.push(Reason::Newer {
+                    prerequisite: &prerequisite.name,
+                    modified: node.modified,
+                    newer: prerequisite.modified,
+                });
+            }
+        }
+        if !node.stale {
+            reasons.push(Reason::UpToDate {
+                modified: node.modified,
+            });
+        }
+
+        reasons
+    }
+
+    /// The graph as Graphviz DOT: an edge from each target to each of its
+    /// prerequisites, labelled with the inference rule for the one it is
+    /// made from, and a dashed one to each target it waits for because of
+    /// `.WAIT`.  The targets that are out of date are red.
+    pub fn to_dot(&self) -> String {
+        let mut dot = format!("digraph {} {{\n", quote(&self.goal().name));
+
+        for node in &self.nodes {
+            let name = quote(&node.name);
+            if node.stale {
+                let _ = writeln!(dot, "    {name} [color=red];");
+            } else {
+                let _ = writeln!(dot, "    {name};");
+            }
+            for prerequisite in node.prerequisites.iter().map(|&i| &self.nodes[i]) {
+                let _ = write!(dot, "    {name} -> {}", quote(&prerequisite.name));
+                match node.rule.and_then(|rule| rule.targets().next()) {
+                    Some(rule) if node.implied.as_ref() == Some(&prerequisite.name) => {
+                        let _ = write!(dot, " [label={}]", quote(rule.as_ref()));
+                    }
+                    _ => {}
+                }
+                dot.push_str(";\n");
+            }
+            for after in node.after.iter().map(|&i| &self.nodes[i]) {
+                let _ = writeln!(dot, "    {name} -> {} [style=dashed];", quote(&after.name));
+            }
+        }
+
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+/// `id` as a quoted DOT identifier.
+fn quote(id: &str) -> String {
+    format!("\"{}\"", id.replace('\\', "\\\\").replace('"', "\\\""))
+}
@@ -0,0 +1,107 @@
+//
+// Copyright (c) 2024 Hemi Labs, Inc.
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+//! Inference rules: making a target that has no recipe of its own from a
+//! file with the same name and another suffix.
+
+use crate::{archive, get_modified_time, rule::Rule, Make};
+
+/// How many inference rules may be chained to make a target, its own
+/// included.
+const MAX_CHAIN: usize = 3;
+
+/// The suffix an inference rule makes archive members with.
+const ARCHIVE_SUFFIX: &str = ".a";
+
+/// How an inference rule makes a target.
+#[derive(Debug)]
+pub struct Inference<'a> {
+    pub rule: &'a Rule,
+    /// The prerequisite the target is made from, which is `$<`.
+    pub prerequisite: String,
+    /// The target without its suffix, which is `$*`.
+    pub stem: String,
+}
+
+/// Whether `target` names an inference rule: `.s1.s2` or `.s1`, for
+/// suffixes in `.SUFFIXES`.
+pub fn is_inference_target(target: &str, suffixes: &[String]) -> bool {
+    suffixes.iter().any(|from| {
+        target
+            .strip_prefix(from.as_str())
+            .is_some_and(|to| to.is_empty() || suffixes.iter().any(|suffix| suffix == to))
+    })
+}
+
+impl Make {
+    /// Finds the inference rule that makes `name`.  A target whose suffix
+    /// is in `.SUFFIXES` is made by a double-suffix rule, and one without
+    /// such a suffix by a single-suffix rule, from the first suffix in
+    /// `.SUFFIXES` order for which there is a rule and a prerequisite that
+    /// exists or can be made.  An archive member `lib(member.o)` is made by
+    /// a `.s1.a` rule from `member.s1`.
+    pub fn infer(&self, name: &str) -> Option<Inference<'_>> {
+        self.infer_chained(name, MAX_CHAIN)
+    }
+
+    fn infer_chained(&self, name: &str, depth: usize) -> Option<Inference<'_>> {
+        if depth == 0 {
+            return None;
+        }
+
+        if let Some((_, member)) = archive::split_member(name) {
+            let stem = member.rsplit_once('.').map_or(member, |(stem, _)| stem);
+            return self.infer_double_suffix(stem, ARCHIVE_SUFFIX, depth);
+        }
+
+        let mut has_suffix = false;
+        for to in &self.suffixes {
+            let Some(stem) = name.strip_suffix(to.as_str()) else {
+                continue;
+            };
+            has_suffix = true;
+            if let Some(inference) = self.infer_double_suffix(stem, to, depth) {
+                return Some(inference);
+            }
+        }
+        if has_suffix {
+            return None;
+        }
+
+        self.suffixes.iter().find_map(|from| {
+            let rule = self.inference_rules.get(from)?;
+            let prerequisite = format!("{name}{from}");
+            self.can_make(&prerequisite, depth).then(|| Inference {
+                rule,
+                prerequisite,
+                stem: name.to_string(),
+            })
+        })
+    }
+
+    fn infer_double_suffix(&self, stem: &str, to: &str, depth: usize) -> Option<Inference<'_>> {
+        self.suffixes.iter().find_map(|from| {
+            let rule = self.inference_rules.get(&format!("{from}{to}"))?;
+            let prerequisite = format!("{stem}{from}");
+            self.can_make(&prerequisite, depth).then(|| Inference {
+                rule,
+                prerequisite,
+                stem: stem.to_string(),
+            })
+        })
+    }
+
+    /// Whether `name` exists, or can be made by a rule or by a chain of
+    /// no more than `depth - 1` inference rules.
+    fn can_make(&self, name: &str, depth: usize) -> bool {
+        get_modified_time(name).is_some()
+            || self.rule_by_target_name(name).is_some()
+            || self.infer_chained(name, depth - 1).is_some()
+    }
+}
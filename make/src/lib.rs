@@ -7,25 +7,30 @@
 // SPDX-License-Identifier: MIT
 //
 
+pub mod archive;
 pub mod config;
 pub mod error_code;
+pub mod explain;
+pub mod inference;
 pub mod parser;
 pub mod rule;
+pub mod scheduler;
 pub mod signal_handler;
 pub mod special_target;
 
 use std::{
-    collections::HashSet,
+    collections::HashMap,
     fs::{self},
     time::SystemTime,
 };
 
 use parser::{Makefile, VariableDefinition};
 
-use crate::special_target::InferenceTarget;
 use config::Config;
 use error_code::ErrorCode::{self, *};
-use rule::{prerequisite::Prerequisite, target::Target, Rule};
+use inference::is_inference_target;
+use rule::{target::Target, Rule};
+use scheduler::Graph;
 use special_target::SpecialTarget;
 
 /// The default shell variable name.
@@ -40,6 +45,10 @@ const DEFAULT_SHELL: &str = "/bin/sh";
 pub struct Make {
     macros: Vec<VariableDefinition>,
     rules: Vec<Rule>,
+    /// The inference rules, by target
+    inference_rules: HashMap<String, Rule>,
+    /// The suffixes of `.SUFFIXES`, in order
+    suffixes: Vec<String>,
     default_rule: Option<Rule>, // .DEFAULT
     pub config: Config,
 }
@@ -57,9 +66,17 @@ impl Make {
             .find(|rule| rule.targets().any(|t| t.as_ref() == target.as_ref()))
     }
 
+    /// Retrieves the first target of the makefile, leaving out the ones
+    /// that start with a period, like special targets and inference rules.
     pub fn first_target(&self) -> Result<&Target, ErrorCode> {
-        let rule = self.rules.first().ok_or(NoTarget { target: None })?;
-        rule.targets().next().ok_or(NoTarget { target: None })
+        self.rules
+            .iter()
+            .filter_map(|rule| rule.targets().next())
+            .find(|target| {
+                let name = target.as_ref();
+                !name.starts_with('.') || name.contains('/')
+            })
+            .ok_or(NoTarget { target: None })
     }
 
     /// Builds the target with the given name.
@@ -69,114 +86,27 @@ impl Make {
     /// - Ok(false) if the target was already up to date.
     /// - Err(_) if any errors occur.
     pub fn build_target(&self, name: impl AsRef<str>) -> Result<bool, ErrorCode> {
-        let rule = match self.rule_by_target_name(&name) {
-            Some(rule) => rule,
-            None => match &self.default_rule {
-                Some(rule) => rule,
-                None => {
-                    return Err(NoTarget {
-                        target: Some(name.as_ref().to_string()),
-                    })
-                }
-            },
-        };
-        let target = Target::new(name.as_ref());
-
-        self.run_rule_with_prerequisites(rule, &target)
+        let graph = Graph::new(self, name.as_ref())?;
+        scheduler::run(self, &graph)
     }
 
-    /// Runs the given rule.
-    ///
-    /// # Returns
-    /// - Ok(true) if the rule was run.
-    /// - Ok(false) if the rule was already up to date.
-    /// - Err(_) if any errors occur.
-    fn run_rule_with_prerequisites(&self, rule: &Rule, target: &Target) -> Result<bool, ErrorCode> {
-        if self.are_prerequisites_recursive(target) {
-            return Err(RecursivePrerequisite {
-                origin: target.to_string(),
-            });
-        }
-
-        let newer_prerequisites = self.get_newer_prerequisites(target);
-        let mut up_to_date = newer_prerequisites.is_empty() && get_modified_time(target).is_some();
-        if rule.config.phony {
-            up_to_date = false;
-        }
-
-        if up_to_date {
-            return Ok(false);
-        }
-
-        for prerequisite in &newer_prerequisites {
-            self.build_target(prerequisite)?;
-        }
-        rule.run(&self.config, &self.macros, target, up_to_date)?;
-
-        Ok(true)
+    /// The dependency graph of the target with the given name, as
+    /// Graphviz DOT.
+    pub fn dependency_graph(&self, name: impl AsRef<str>) -> Result<String, ErrorCode> {
+        Graph::new(self, name.as_ref()).map(|graph| graph.to_dot())
     }
 
-    /// Retrieves the prerequisites of the target that are newer than the target.
-    /// Recursively checks the prerequisites of the prerequisites.
-    /// Returns an empty vector if the target does not exist (or it's a file).
-    fn get_newer_prerequisites(&self, target: impl AsRef<str>) -> Vec<&Prerequisite> {
-        let Some(target_rule) = self.rule_by_target_name(&target) else {
-            return vec![];
-        };
-        let target_modified = get_modified_time(target);
-
-        let prerequisites = target_rule.prerequisites();
-
-        if let Some(target_modified) = target_modified {
-            prerequisites
-                .filter(|prerequisite| {
-                    let Some(pre_modified) = get_modified_time(prerequisite) else {
-                        return true;
-                    };
-
-                    !self.get_newer_prerequisites(prerequisite).is_empty()
-                        || pre_modified > target_modified
-                })
-                .collect()
-        } else {
-            prerequisites.collect()
-        }
-    }
-
-    /// Checks if the target has recursive prerequisites.
-    /// Returns true if the target has recursive prerequisites.
-    fn are_prerequisites_recursive(&self, target: impl AsRef<str>) -> bool {
-        let mut visited = HashSet::from([target.as_ref()]);
-        let mut stack = HashSet::from([target.as_ref()]);
-
-        self._are_prerequisites_recursive(target.as_ref(), &mut visited, &mut stack)
-    }
-
-    /// A helper function to check if the target has recursive prerequisites.
-    /// Uses DFS to check for recursive prerequisites.
-    fn _are_prerequisites_recursive(
-        &self,
-        target: &str,
-        visited: &mut HashSet<&str>,
-        stack: &mut HashSet<&str>,
-    ) -> bool {
-        let Some(rule) = self.rule_by_target_name(target) else {
-            return false;
-        };
-
-        let prerequisites = rule.prerequisites();
-
-        for prerequisite in prerequisites {
-            if (!visited.contains(prerequisite.as_ref())
-                && self._are_prerequisites_recursive(prerequisite.as_ref(), visited, stack))
-                || stack.contains(prerequisite.as_ref())
-            {
-                return true;
-            }
-        }
-
-        stack.remove(target);
-        false
+    /// The values of the macros defined in the makefile, by name.
+    fn macro_values(&self) -> HashMap<String, String> {
+        self.macros
+            .iter()
+            .map(|v| {
+                (
+                    v.name().unwrap_or_default(),
+                    v.raw_value().unwrap_or_default(),
+                )
+            })
+            .collect()
     }
 }
 
@@ -186,7 +116,6 @@ impl TryFrom<(Makefile, Config)> for Make {
     fn try_from((makefile, config): (Makefile, Config)) -> Result<Self, Self::Error> {
         let mut rules = vec![];
         let mut special_rules = vec![];
-        let mut inference_rules = vec![];
 
         for rule in makefile.rules() {
             let rule = Rule::from(rule);
@@ -196,8 +125,6 @@ impl TryFrom<(Makefile, Config)> for Make {
 
             if SpecialTarget::try_from(target.clone()).is_ok() {
                 special_rules.push(rule);
-            } else if InferenceTarget::try_from((target.clone(), config.clone())).is_ok() {
-                inference_rules.push(rule);
             } else {
                 rules.push(rule);
             }
@@ -205,6 +132,8 @@ impl TryFrom<(Makefile, Config)> for Make {
 
         let mut make = Self {
             rules,
+            inference_rules: HashMap::new(),
+            suffixes: Vec::new(),
             macros: makefile.variable_definitions().collect(),
             default_rule: None,
             config,
@@ -214,13 +143,49 @@ impl TryFrom<(Makefile, Config)> for Make {
             special_target::process(rule, &mut make)?;
         }
 
+        // which rules are inference rules depends on all of `.SUFFIXES`;
+        // a later one replaces an earlier one, such as a built-in one
+        let (inference_rules, rules) = make.rules.drain(..).partition::<Vec<_>, _>(|rule| {
+            let mut targets = rule.targets();
+            let is_inference = targets
+                .next()
+                .is_some_and(|target| is_inference_target(target.as_ref(), &make.suffixes));
+            is_inference && targets.next().is_none() && rule.prerequisites().next().is_none()
+        });
+        make.rules = rules;
+        for rule in inference_rules {
+            let target = rule
+                .targets()
+                .next()
+                .expect("rules have targets")
+                .to_string();
+            make.inference_rules.insert(target, rule);
+        }
+
         Ok(make)
     }
 }
 
-/// Retrieves the modified time of the file at the given path.
+/// Retrieves the modified time of the file at the given path, or of the
+/// archive member it names.
 fn get_modified_time(path: impl AsRef<str>) -> Option<SystemTime> {
+    if let Some((archive, member)) = archive::split_member(path.as_ref()) {
+        return archive::member_modified(archive, member);
+    }
     fs::metadata(path.as_ref())
         .ok()
         .and_then(|meta| meta.modified().ok())
 }
+
+/// Whether a prerequisite modified at `prerequisite` is newer than the
+/// target `name`, modified at `modified`.  Archives record the times of
+/// their members in whole seconds, so against a member the prerequisite's
+/// time is truncated to seconds too, or the member would never be up to
+/// date.
+fn is_newer(prerequisite: Option<SystemTime>, name: &str, modified: Option<SystemTime>) -> bool {
+    if archive::split_member(name).is_some() {
+        prerequisite.map(archive::whole_seconds) > modified
+    } else {
+        prerequisite > modified
+    }
+}
@@ -7,17 +7,17 @@
 // SPDX-License-Identifier: MIT
 //
 
-use core::str::FromStr;
 use std::collections::{BTreeMap, BTreeSet};
 use std::ffi::OsString;
 use std::io::Read;
+use std::num::NonZeroUsize;
 use std::path::{Path, PathBuf};
 use std::sync::atomic::Ordering::Relaxed;
 use std::{env, fs, io, process};
 
 use clap::Parser;
 use const_format::formatcp;
-use gettextrs::{bind_textdomain_codeset, gettext, setlocale, textdomain, LocaleCategory};
+use gettextrs::{bind_textdomain_codeset, setlocale, textdomain, LocaleCategory};
 
 use posixutils_make::{
     config::Config,
@@ -103,6 +103,27 @@ struct Args {
     )]
     touch: bool,
 
+    #[arg(
+        short = 'j',
+        long,
+        default_value = "1",
+        help = "Run up to JOBS recipes at the same time"
+    )]
+    jobs: NonZeroUsize,
+
+    #[arg(
+        short = 'd',
+        long,
+        help = "Explain why each target is made or not, on standard error"
+    )]
+    debug: bool,
+
+    #[arg(
+        long,
+        help = "Write the dependency graph of the targets as Graphviz DOT instead of making them"
+    )]
+    dot: bool,
+
     #[arg(help = "Targets to build")]
     targets: Vec<OsString>,
 }
@@ -111,9 +132,10 @@ fn print_rules(rules: &BTreeMap<String, BTreeSet<String>>) {
     print!("{:?}", rules);
 }
 
-/// Parse the makefile at the given path, or the first default makefile found.
+/// Parse the makefile at the given path, or the first default makefile found,
+/// after the built-in macros and rules.
 /// If no makefile is found, print an error message and exit.
-fn parse_makefile(path: Option<impl AsRef<Path>>) -> Result<Makefile, ErrorCode> {
+fn parse_makefile(path: Option<impl AsRef<Path>>, builtins: &str) -> Result<Makefile, ErrorCode> {
     let path = path.as_ref().map(|p| p.as_ref());
 
     let path = match path {
@@ -146,12 +168,24 @@ fn parse_makefile(path: Option<impl AsRef<Path>>) -> Result<Makefile, ErrorCode>
         }
     };
 
-    match Makefile::from_str(&contents) {
+    match Makefile::parse_with_builtins(&contents, builtins) {
         Ok(makefile) => Ok(makefile),
-        Err(err) => Err(ErrorCode::ParserError { constraint: err }),
+        // standard input has no name to give
+        Err(err) if path == Path::new("-") => Err(ErrorCode::ParserError { constraint: err }),
+        Err(err) => Err(ErrorCode::ParserError {
+            constraint: err.in_file(path),
+        }),
     }
 }
 
+/// The value of the `MAKE` macro: the path of this make, so that recipes
+/// can run it from any directory.
+fn make_path() -> String {
+    env::current_exe()
+        .map(|path| path.display().to_string())
+        .unwrap_or_else(|_| String::from("make"))
+}
+
 /// Reads the makefile from `stdin` until EOF (Ctrl + D)
 fn read_stdin() -> Result<String, ErrorCode> {
     let mut buffer = String::new();
@@ -177,6 +211,9 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         print,
         terminate,
         keep_going,
+        jobs,
+        debug,
+        dot,
         mut targets,
     } = Args::parse();
 
@@ -199,20 +236,29 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         print,
         precious: false,
         terminate,
+        jobs: jobs.get(),
+        debug,
         ..Default::default()
     };
 
-    if clear {
+    // the options of the make that runs this one
+    if let Ok(makeflags) = env::var("MAKEFLAGS") {
+        config.apply_makeflags(&makeflags);
+    }
+    env::set_var("MAKEFLAGS", config.makeflags());
+
+    if config.clear {
         config.rules.clear();
     }
 
-    ENV_MACROS.store(env_macros, Relaxed);
+    ENV_MACROS.store(config.env_macros, Relaxed);
 
-    let parsed = match parse_makefile(makefile.as_ref()) {
+    let builtins = format!("MAKE={}\n{}", make_path(), config.builtins());
+    let parsed = match parse_makefile(makefile.as_ref(), &builtins) {
         Ok(parsed) => parsed,
         Err(err) => {
             // -p flag
-            if print {
+            if config.print {
                 // If makefile is not provided or parsing failed, print the default rules
                 print_rules(&config.rules);
                 return Ok(());
@@ -247,9 +293,21 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         targets.push(target);
     }
 
-    let mut had_error = false;
     for target in targets {
         let target = target.into_string().unwrap();
+
+        // --dot flag
+        if dot {
+            match make.dependency_graph(&target) {
+                Ok(graph) => print!("{graph}"),
+                Err(err) => {
+                    eprintln!("make: {}", err);
+                    process::exit(err.into());
+                }
+            }
+            continue;
+        }
+
         match make.build_target(&target) {
             Ok(updated) => {
                 if !updated {
@@ -262,22 +320,10 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             }
         }
 
-        if keep_going {
-            eprintln!(
-                "{}: Target {} not remade because of errors",
-                gettext("make"),
-                target
-            );
-            had_error = true;
-        }
-
         if status_code != 0 {
             break;
         }
     }
 
-    if had_error {
-        status_code = 2;
-    }
     process::exit(status_code);
 }
@@ -5,12 +5,19 @@ use std::str::Chars;
 use std::sync::LazyLock;
 
 use crate::parser::SyntaxKind::{EXPORT, INCLUDE};
-static KEYWORDS: LazyLock<HashMap<&'static str, SyntaxKind>> =
-    LazyLock::new(|| HashMap::from_iter([("include", INCLUDE), ("export", EXPORT)]));
+static KEYWORDS: LazyLock<HashMap<&'static str, SyntaxKind>> = LazyLock::new(|| {
+    HashMap::from_iter([
+        ("include", INCLUDE),
+        ("-include", INCLUDE),
+        ("export", EXPORT),
+    ])
+});
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 enum LineType {
     Recipe,
+    /// The rest of a rule line after a `;`, up to the recipe itself.
+    InlineRecipe,
     Other,
 }
 
@@ -36,7 +43,7 @@ impl<'a> Lexer<'a> {
     }
 
     fn is_valid_identifier_char(c: char) -> bool {
-        c.is_ascii_alphanumeric() || c == '_' || c == '.' || c == '-'
+        c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | '-' | '/' | '~')
     }
 
     fn read_while<F>(&mut self, predicate: F) -> String
@@ -82,7 +89,7 @@ impl<'a> Lexer<'a> {
                     self.line_type = None;
                     return Some((SyntaxKind::NEWLINE, self.input.next()?.to_string()));
                 }
-                '#' => {
+                '#' if self.line_type == Some(LineType::Other) => {
                     return Some((
                         SyntaxKind::COMMENT,
                         self.read_while(|c| !Self::is_newline(c)),
@@ -95,6 +102,13 @@ impl<'a> Lexer<'a> {
                 LineType::Recipe => {
                     Some((SyntaxKind::TEXT, self.read_while(|c| !Self::is_newline(c))))
                 }
+                LineType::InlineRecipe if Self::is_whitespace(c) => {
+                    Some((SyntaxKind::WHITESPACE, self.read_while(Self::is_whitespace)))
+                }
+                LineType::InlineRecipe => {
+                    self.line_type = Some(LineType::Recipe);
+                    Some((SyntaxKind::TEXT, self.read_while(|c| !Self::is_newline(c))))
+                }
                 LineType::Other => match c {
                     c if Self::is_whitespace(c) => {
                         Some((SyntaxKind::WHITESPACE, self.read_while(Self::is_whitespace)))
@@ -176,6 +190,12 @@ impl<'a> Lexer<'a> {
                         self.input.next();
                         Some((SyntaxKind::STAR, "*".to_string()))
                     }
+                    ';' => {
+                        // the rest of the line is a recipe
+                        self.input.next();
+                        self.line_type = Some(LineType::InlineRecipe);
+                        Some((SyntaxKind::SEMICOLON, ";".to_string()))
+                    }
                     '<' => {
                         self.input.next();
                         Some((SyntaxKind::LESS, "<".to_string()))
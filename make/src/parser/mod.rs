@@ -38,6 +38,7 @@ pub enum SyntaxKind {
     COMMA,
     LESS,
     PLUS,
+    SEMICOLON,
     STAR,
     TAB,
 
@@ -8,6 +8,7 @@
 
 use crate::parser::lex::lex;
 use rowan::ast::AstNode;
+use std::path::Path;
 use std::str::FromStr;
 
 use super::SyntaxKind::*;
@@ -38,12 +39,21 @@ impl std::error::Error for Error {}
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct ParseError(pub Vec<String>);
 
+impl ParseError {
+    /// The errors, each naming `file`, the makefile they were found in.
+    pub fn in_file(self, file: &Path) -> ParseError {
+        ParseError(
+            self.0
+                .into_iter()
+                .map(|err| format!("{}: {}", file.display(), err))
+                .collect(),
+        )
+    }
+}
+
 impl std::fmt::Display for ParseError {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        for err in &self.0 {
-            writeln!(f, "{}", err)?;
-        }
-        Ok(())
+        write!(f, "{}", self.0.join("\n"))
     }
 }
 
@@ -75,7 +85,7 @@ impl rowan::Language for Lang {
 use rowan::GreenNode;
 
 use super::SyntaxKind;
-use crate::parser::preprocessor::preprocess;
+use crate::parser::preprocessor::{preprocess_with_builtins, Location};
 /// You can construct GreenNodes by hand, but a builder
 /// is helpful for top-down parsers: it maintains a stack
 /// of currently in-progress nodes
@@ -104,7 +114,14 @@ impl Parsed {
 }
 
 pub fn parse(text: &str) -> Result<Parsed, ParseError> {
-    struct Parser {
+    parse_with_locations(text, &[])
+}
+
+/// Parses `text`, like [`parse`], reporting errors at `locations`: where
+/// each line of `text` comes from.  Lines without a location are reported
+/// by their number in `text`.
+pub fn parse_with_locations(text: &str, locations: &[Location]) -> Result<Parsed, ParseError> {
+    struct Parser<'a> {
         /// input tokens, including whitespace,
         /// in *reverse* order.
         tokens: Vec<(SyntaxKind, String)>,
@@ -113,23 +130,52 @@ pub fn parse(text: &str) -> Result<Parsed, ParseError> {
         /// the list of syntax errors we've accumulated
         /// so far.
         errors: Vec<String>,
+        /// where the lines of the input come from.
+        locations: &'a [Location],
+        /// the number of the current line, counting from 1.
+        line: usize,
+        /// the number of rules parsed so far, not counting the built-in
+        /// ones.
+        rules: usize,
     }
 
-    impl Parser {
+    impl Parser<'_> {
         fn error(&mut self, msg: String) {
             self.builder.start_node(ERROR.into());
+            self.errors.push(format!("{}: {}", self.location(), msg));
             if self.current().is_some() {
                 self.bump();
             }
-            self.errors.push(msg);
             self.builder.finish_node();
         }
 
+        /// Reports an error about the whole of the current line, and skips
+        /// it.
+        fn error_line(&mut self, msg: String) {
+            self.builder.start_node(ERROR.into());
+            self.errors.push(format!("{}: {}", self.location(), msg));
+            while !matches!(self.current(), Some(NEWLINE) | None) {
+                self.bump();
+            }
+            self.builder.finish_node();
+            self.try_expect(NEWLINE);
+        }
+
+        fn location(&self) -> Location {
+            self.locations
+                .get(self.line - 1)
+                .cloned()
+                .unwrap_or(Location {
+                    file: None,
+                    line: self.line,
+                })
+        }
+
         fn parse_expr(&mut self) {
             self.builder.start_node(EXPR.into());
             loop {
                 match self.current() {
-                    Some(NEWLINE) | None => {
+                    Some(NEWLINE) | Some(SEMICOLON) | None => {
                         break;
                     }
                     Some(_t) => {
@@ -148,34 +194,43 @@ pub fn parse(text: &str) -> Result<Parsed, ParseError> {
             self.builder.finish_node();
         }
 
-        fn parse_include(&mut self) {
-            self.builder.start_node(RULE.into());
-            self.expect(IDENTIFIER);
+        /// Parses the recipe that follows a `;` on a rule line.
+        fn parse_inline_recipe(&mut self) {
+            self.builder.start_node(RECIPE.into());
+            self.expect(SEMICOLON);
             self.skip_ws();
-            if self.tokens.last() == Some(&(IDENTIFIER, "include".to_string())) {
-                self.expect(IDENTIFIER);
-                self.skip_ws();
-            }
-            self.expect(NEWLINE);
-            self.builder.token(IDENTIFIER.into(), "variables.mk");
-            dbg!(&self.builder);
+            self.try_expect(TEXT);
             self.builder.finish_node();
         }
 
+        /// `include` lines are replaced with the included makefiles by the
+        /// preprocessor, so one that is left has nothing to parse.
+        fn parse_include(&mut self) {
+            while !matches!(self.current(), Some(NEWLINE) | None) {
+                self.bump();
+            }
+            self.try_expect(NEWLINE);
+        }
+
         fn parse_rule(&mut self) {
             self.builder.start_node(RULE.into());
             self.skip_ws();
             self.try_expect(EXPORT);
             self.skip_ws();
             self.expect(IDENTIFIER);
-            self.skip_ws();
-            if self.tokens.pop() == Some((COLON, ":".to_string())) {
-                self.builder.token(COLON.into(), ":");
+            while !matches!(self.current(), Some(COLON | NEWLINE | SEMICOLON) | None) {
+                self.bump();
+            }
+            if self.current() == Some(COLON) {
+                self.bump();
             } else {
                 self.error("expected ':'".into());
             }
             self.skip_ws();
             self.parse_expr();
+            if self.current() == Some(SEMICOLON) {
+                self.parse_inline_recipe();
+            }
             self.expect(NEWLINE);
             loop {
                 match self.current() {
@@ -196,29 +251,30 @@ pub fn parse(text: &str) -> Result<Parsed, ParseError> {
 
         fn parse(mut self) -> Parse {
             self.builder.start_node(ROOT.into());
-            loop {
-                match self.find(|&&(k, _)| k == COLON || k == NEWLINE || k == INCLUDE) {
-                    Some((COLON, ":")) => {
-                        self.parse_rule();
+            while let Some(current) = self.current() {
+                match current {
+                    NEWLINE | WHITESPACE | COMMENT => {
+                        self.bump();
                     }
-                    Some((INCLUDE, "include")) => {
-                        dbg!(&self.tokens);
+                    INCLUDE => {
                         self.parse_include();
                     }
-                    Some((NEWLINE, _)) => {
-                        self.bump();
+                    INDENT => {
+                        self.error_line("recipe commences before first target".to_string());
                     }
-                    Some(_) | None => {
-                        self.error(" *** No targets. Stop.".to_string());
-                        if self.current().is_some() {
-                            self.bump();
+                    _ if self.line_contains(COLON) => {
+                        if !self.location().is_builtin() {
+                            self.rules += 1;
                         }
+                        self.parse_rule();
+                    }
+                    _ => {
+                        self.error_line("missing separator".to_string());
                     }
                 }
-
-                if self.current().is_none() {
-                    break;
-                }
+            }
+            if self.rules == 0 && self.errors.is_empty() {
+                self.errors.push(" *** No targets. Stop.".to_string());
             }
             // Close the root node.
             self.builder.finish_node();
@@ -232,6 +288,9 @@ pub fn parse(text: &str) -> Result<Parsed, ParseError> {
         /// Advance one token, adding it to the current branch of the tree builder.
         fn bump(&mut self) {
             let (kind, text) = self.tokens.pop().unwrap();
+            if kind == NEWLINE {
+                self.line += 1;
+            }
             self.builder.token(kind.into(), text.as_str());
         }
         /// Peek at the first unprocessed token
@@ -239,15 +298,14 @@ pub fn parse(text: &str) -> Result<Parsed, ParseError> {
             self.tokens.last().map(|(kind, _)| *kind)
         }
 
-        fn find(
-            &self,
-            finder: impl FnMut(&&(SyntaxKind, String)) -> bool,
-        ) -> Option<(SyntaxKind, &str)> {
+        /// Whether the rest of the current line has a token of `kind`.
+        fn line_contains(&self, kind: SyntaxKind) -> bool {
             self.tokens
                 .iter()
                 .rev()
-                .find(finder)
-                .map(|(kind, text)| (*kind, text.as_str()))
+                .map(|(k, _)| *k)
+                .take_while(|&k| k != NEWLINE)
+                .any(|k| k == kind)
         }
 
         fn expect(&mut self, expected: SyntaxKind) {
@@ -281,6 +339,9 @@ pub fn parse(text: &str) -> Result<Parsed, ParseError> {
         tokens,
         builder: GreenNodeBuilder::new(),
         errors: Vec::new(),
+        locations,
+        line: 1,
+        rules: 0,
     }
     .parse();
 
@@ -298,7 +359,6 @@ pub fn parse(text: &str) -> Result<Parsed, ParseError> {
 /// has identity semantics.
 
 type SyntaxNode = rowan::SyntaxNode<Lang>;
-#[allow(unused)]
 type SyntaxToken = rowan::SyntaxToken<Lang>;
 #[allow(unused)]
 type SyntaxElement = rowan::NodeOrToken<SyntaxNode, SyntaxToken>;
@@ -399,6 +459,14 @@ impl Makefile {
         Ok(parsed.root())
     }
 
+    /// Parses `source`, with `builtins`, the built-in macros and rules,
+    /// read before it.
+    pub fn parse_with_builtins(source: &str, builtins: &str) -> Result<Makefile, ParseError> {
+        let (processed, locations) = preprocess_with_builtins(source, builtins)
+            .map_err(|e| ParseError(vec![e.to_string()]))?;
+        parse_with_locations(&processed, &locations).map(|node| node.root())
+    }
+
     pub fn rules(&self) -> impl Iterator<Item = Rule> {
         self.syntax().children().filter_map(Rule::cast)
     }
@@ -432,28 +500,23 @@ impl Makefile {
 
 impl Rule {
     pub fn targets(&self) -> impl Iterator<Item = String> {
-        self.syntax()
-            .children_with_tokens()
-            .take_while(|it| it.as_token().map_or(true, |t| t.kind() != COLON))
-            .filter_map(|it| it.as_token().map(|t| t.text().to_string()))
+        words(
+            self.syntax()
+                .children_with_tokens()
+                .map_while(|it| it.into_token().filter(|t| t.kind() != COLON))
+                .filter(|t| t.kind() != EXPORT),
+        )
     }
 
     pub fn prerequisites(&self) -> impl Iterator<Item = String> {
-        self.syntax()
-            .children()
-            .find(|it| it.kind() == EXPR)
-            .into_iter()
-            .flat_map(|it| {
-                it.children_with_tokens().filter_map(|it| {
-                    it.as_token().and_then(|t| {
-                        if t.kind() == IDENTIFIER {
-                            Some(t.text().to_string())
-                        } else {
-                            None
-                        }
-                    })
-                })
-            })
+        words(
+            self.syntax()
+                .children()
+                .find(|it| it.kind() == EXPR)
+                .into_iter()
+                .flat_map(|it| it.children_with_tokens())
+                .filter_map(|it| it.into_token()),
+        )
     }
 
     pub fn recipes(&self) -> impl Iterator<Item = String> {
@@ -520,6 +583,27 @@ impl Rule {
     }
 }
 
+/// Joins the text of adjacent tokens into words, which whitespace and
+/// comments separate, so that `lib.a(member.o)` is a single word.
+fn words(tokens: impl Iterator<Item = SyntaxToken>) -> impl Iterator<Item = String> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    for token in tokens {
+        match token.kind() {
+            WHITESPACE | COMMENT | NEWLINE => {
+                if !word.is_empty() {
+                    words.push(std::mem::take(&mut word));
+                }
+            }
+            _ => word.push_str(token.text()),
+        }
+    }
+    if !word.is_empty() {
+        words.push(word);
+    }
+    words.into_iter()
+}
+
 impl Default for Makefile {
     fn default() -> Self {
         Self::new()
@@ -530,7 +614,6 @@ impl FromStr for Makefile {
     type Err = ParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let processed = preprocess(s).map_err(|e| ParseError(vec![e.to_string()]))?;
-        parse(&processed).map(|node| node.root())
+        Makefile::parse_with_builtins(s, "")
     }
 }
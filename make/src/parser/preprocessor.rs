@@ -1,11 +1,25 @@
 use std::collections::HashMap;
 use std::fmt::{Debug, Display, Formatter};
 use std::fs;
+use std::io;
 use std::iter::Peekable;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::sync::atomic::AtomicBool;
 use std::sync::atomic::Ordering::Acquire;
 
+use gettextrs::gettext;
+
+/// How deep `include` lines may nest, so that a makefile including
+/// itself is an error rather than a hang.
+const MAX_INCLUDE_DEPTH: usize = 64;
+
+/// How deep macros may expand into macros, so that a macro referring to
+/// itself is an error rather than a hang.
+const MAX_EXPANSION_DEPTH: usize = 256;
+
+/// What the built-in macros and rules are called in error messages.
+const BUILTINS: &str = "<builtin>";
+
 #[derive(Debug)]
 pub enum PreprocError {
     EmptyIdent,
@@ -14,21 +28,92 @@ pub enum PreprocError {
     TooManyColons,
     BadAssignmentOperator(char),
     CommandFailed,
-    UndefinedMacro(String),
     BadMacroName,
+    IncludeFailed(PathBuf, io::Error),
+    TooManyIncludes,
+    RecursiveMacro,
 }
 
 impl Display for PreprocError {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        writeln!(f, "{:?}", self)?;
-        Ok(())
+        use PreprocError::*;
+
+        match self {
+            EmptyIdent => write!(f, "{}", gettext("missing macro name")),
+            UnexpectedEOF => write!(f, "{}", gettext("unexpected end of line")),
+            UnexpectedSymbol(c) => write!(f, "{} '{}'", gettext("unexpected symbol"), c),
+            TooManyColons => write!(f, "{}", gettext("too many colons in assignment")),
+            BadAssignmentOperator(c) => {
+                write!(f, "{} '{}'", gettext("expected '=' after"), c)
+            }
+            CommandFailed => write!(f, "{}", gettext("could not run the command of '!='")),
+            BadMacroName => write!(f, "{}", gettext("bad macro name")),
+            IncludeFailed(path, err) => write!(
+                f,
+                "{} '{}': {}",
+                gettext("cannot include"),
+                path.display(),
+                err,
+            ),
+            TooManyIncludes => write!(f, "{}", gettext("includes nested too deeply")),
+            RecursiveMacro => write!(f, "{}", gettext("recursive macro expansion")),
+        }
     }
 }
 
 impl std::error::Error for PreprocError {}
 
+/// Where a line of the preprocessed makefile comes from.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct Location {
+    /// The included makefile the line is in, or `None` for the makefile
+    /// itself.
+    pub file: Option<PathBuf>,
+    /// The number of the line, counting from 1.
+    pub line: usize,
+}
+
+impl Location {
+    /// Whether the line is one of the built-in macros and rules.
+    pub fn is_builtin(&self) -> bool {
+        self.file.as_deref() == Some(Path::new(BUILTINS))
+    }
+}
+
+impl Display for Location {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match &self.file {
+            Some(file) => write!(f, "{}, {} {}", file.display(), gettext("line"), self.line),
+            None => write!(f, "{} {}", gettext("line"), self.line),
+        }
+    }
+}
+
+/// A [`PreprocError`] and the line it was found on.
+#[derive(Debug)]
+pub struct Error {
+    pub location: Location,
+    pub kind: PreprocError,
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.location, self.kind)
+    }
+}
+
+impl std::error::Error for Error {}
+
 type Result<T> = std::result::Result<T, PreprocError>;
 
+/// Turns a [`PreprocError`] into an [`Error`] found at `location`.
+fn at(location: &Location) -> impl Fn(PreprocError) -> Error + '_ {
+    move |kind| Error {
+        location: location.clone(),
+        kind,
+    }
+}
+
 fn skip_blank(letters: &mut Peekable<impl Iterator<Item = char>>) {
     while let Some(letter) = letters.peek() {
         if !letter.is_whitespace() {
@@ -74,124 +159,167 @@ fn take_till_eol(letters: &mut Peekable<impl Iterator<Item = char>>) -> String {
     content
 }
 
-/// Searches for all the lines in makefile that resemble macro definition
-/// and creates hashtable from macro names and bodies
-fn generate_macro_table(
-    source: &str,
-) -> std::result::Result<HashMap<String, String>, PreprocError> {
-    let macro_defs = source.lines().filter(|line| line.contains('='));
-    let mut macro_table = HashMap::<String, String>::new();
-
-    for def in macro_defs {
-        enum Operator {
-            Equals,
-            Colon,
-            Colon2,
-            Colon3,
-            Bang,
-            QuestionMark,
-            Plus,
-        }
+/// A line of a makefile, with its continuation lines joined to it.
+struct Line {
+    location: Location,
+    text: String,
+}
 
-        let mut text = def.chars().peekable();
+/// Splits `source` into lines.  Outside of recipes, a backslash at the end
+/// of a line joins the next one to it, the backslash, the newline and the
+/// blanks around them becoming a single space.
+fn split_lines(source: &str, file: Option<&Path>) -> Vec<Line> {
+    let mut lines: Vec<Line> = Vec::new();
+    let mut continued = false;
+
+    for (i, text) in source.lines().enumerate() {
+        match lines.last_mut() {
+            Some(last) if continued => {
+                last.text.push(' ');
+                last.text.push_str(text.trim_start());
+            }
+            _ => lines.push(Line {
+                location: Location {
+                    file: file.map(Path::to_path_buf),
+                    line: i + 1,
+                },
+                text: text.to_string(),
+            }),
+        }
 
-        let mut macro_name = get_ident(&mut text)?;
-        if macro_name == "export" {
-            skip_blank(&mut text);
-            macro_name = get_ident(&mut text)?;
+        let last = lines.last_mut().expect("a line was just added");
+        continued = !last.text.starts_with('\t') && last.text.ends_with('\\');
+        if continued {
+            last.text.pop();
+            last.text.truncate(last.text.trim_end().len());
         }
+    }
+
+    lines
+}
+
+enum Operator {
+    Equals,
+    Colon,
+    Colon2,
+    Colon3,
+    Bang,
+    QuestionMark,
+    Plus,
+}
+
+/// Parses `line` as a macro definition, into the name, the assignment
+/// operator and the body of the macro.  Returns `None` if the line is not
+/// a macro definition.
+fn parse_assignment(line: &str) -> Result<Option<(String, Operator, String)>> {
+    if line.starts_with('\t') {
+        return Ok(None);
+    }
+
+    let mut text = line.chars().peekable();
+    skip_blank(&mut text);
+    let Ok(mut macro_name) = get_ident(&mut text) else {
+        return Ok(None);
+    };
+    skip_blank(&mut text);
+    if macro_name == "export" && text.peek().is_some_and(suitable_ident) {
+        macro_name = get_ident(&mut text)?;
         skip_blank(&mut text);
-        let Some(symbol) = text.next() else {
-            Err(PreprocError::UnexpectedEOF)?
-        };
-        let operator = match symbol {
-            '=' => Operator::Equals,
-            ':' => {
-                let mut count = 1;
-                while let Some(':') = text.peek() {
-                    count += 1;
-                    text.next();
-                }
-                let Some('=') = text.next() else {
-                    Err(PreprocError::BadAssignmentOperator(':'))?
-                };
+    }
 
-                match count {
-                    1 => Operator::Colon,
-                    2 => Operator::Colon2,
-                    3 => Operator::Colon3,
-                    _ => Err(PreprocError::TooManyColons)?,
-                }
+    let operator = match text.next() {
+        Some('=') => Operator::Equals,
+        Some(':') => {
+            let mut count = 1;
+            while text.next_if_eq(&':').is_some() {
+                count += 1;
             }
-            '!' => {
-                let Some('=') = text.next() else {
-                    Err(PreprocError::BadAssignmentOperator('!'))?
-                };
-                Operator::Bang
+            // otherwise it is a rule
+            if text.next_if_eq(&'=').is_none() {
+                return Ok(None);
             }
-            '?' => {
-                let Some('=') = text.next() else {
-                    Err(PreprocError::BadAssignmentOperator('?'))?
-                };
-                Operator::QuestionMark
-            }
-            '+' => {
-                let Some('=') = text.next() else {
-                    Err(PreprocError::BadAssignmentOperator('+'))?
-                };
-                Operator::Plus
-            }
-            c => Err(PreprocError::UnexpectedSymbol(c))?,
-        };
-        skip_blank(&mut text);
-        let mut macro_body = take_till_eol(&mut text);
 
-        match operator {
-            Operator::Equals => {}
-            Operator::Colon | Operator::Colon2 => loop {
-                let (result, substitutions) = substitute(&macro_body, &macro_table)?;
-                if substitutions == 0 {
-                    break;
-                } else {
-                    macro_body = result
-                }
-            },
-            Operator::Colon3 => {
-                macro_body = substitute(&macro_body, &macro_table)?.0;
+            match count {
+                1 => Operator::Colon,
+                2 => Operator::Colon2,
+                3 => Operator::Colon3,
+                _ => Err(PreprocError::TooManyColons)?,
             }
-            Operator::Bang => {
-                macro_body = substitute(&macro_body, &macro_table)?.0;
-                let Ok(result) = std::process::Command::new("sh")
-                    .args(["-c", &macro_body])
-                    .output()
-                else {
-                    Err(PreprocError::CommandFailed)?
-                };
-                macro_body = String::from_utf8_lossy(&result.stdout).to_string();
-            }
-            Operator::QuestionMark => {
-                if let Some(body) = macro_table.remove(&macro_name) {
-                    macro_body = body
+        }
+        Some(c @ ('!' | '?' | '+')) => {
+            if text.next_if_eq(&'=').is_none() {
+                if line.contains(':') {
+                    return Ok(None);
                 }
+                Err(PreprocError::BadAssignmentOperator(c))?
             }
-            Operator::Plus => {
-                if let Some(body) = macro_table.remove(&macro_name) {
-                    macro_body = format!("{} {}", body, macro_body);
-                }
+            match c {
+                '!' => Operator::Bang,
+                '?' => Operator::QuestionMark,
+                _ => Operator::Plus,
             }
         }
+        _ => return Ok(None),
+    };
+    skip_blank(&mut text);
+    let macro_body = take_till_eol(&mut text).trim_end().to_string();
 
-        macro_table.insert(macro_name, macro_body);
-    }
+    Ok(Some((macro_name, operator, macro_body)))
+}
 
-    Ok(macro_table)
+/// Parses `line` as an `include` or `-include` line, into whether missing
+/// files are ignored and the list of files.  Returns `None` if the line is
+/// not an include line.
+fn parse_include(line: &str) -> Option<(bool, &str)> {
+    let (optional, rest) = match line.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, line),
+    };
+    let rest = rest.strip_prefix("include")?;
+    if !(rest.is_empty() || rest.starts_with([' ', '\t'])) {
+        return None;
+    }
+    let files = rest.split('#').next().unwrap_or_default();
+    Some((optional, files.trim()))
 }
 
 pub static ENV_MACROS: AtomicBool = AtomicBool::new(false);
 
-fn substitute(source: &str, table: &HashMap<String, String>) -> Result<(String, u32)> {
-    let env_macros = ENV_MACROS.load(Acquire);
+/// Looks up a macro.  Macros from the environment come after the ones
+/// defined in the makefile, or before them with `-e`, and undefined
+/// macros are empty.
+fn lookup(name: &str, table: &HashMap<String, String>) -> String {
+    let env_macro = std::env::var(name).ok();
+    let table_macro = table.get(name).cloned();
+    let value = if ENV_MACROS.load(Acquire) {
+        env_macro.or(table_macro)
+    } else {
+        table_macro.or(env_macro)
+    };
+    value.unwrap_or_default()
+}
+
+/// Replaces `from` with `to` at the end of each word of `value` that ends
+/// with it, for `$(name:from=to)`.
+fn replace_suffixes(value: &str, from: &str, to: &str) -> String {
+    value
+        .split_whitespace()
+        .map(|word| match word.strip_suffix(from) {
+            Some(stem) => format!("{stem}{to}"),
+            None => word.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
 
+/// Substitutes the macros in `source` once, `depth` being how deep in
+/// the expansion of other macros this is.  Returns the result and the
+/// number of macros substituted.
+fn substitute(
+    source: &str,
+    table: &HashMap<String, String>,
+    depth: usize,
+) -> Result<(String, u32)> {
     let mut substitutions = 0;
     let mut result = String::with_capacity(source.len());
 
@@ -216,25 +344,41 @@ fn substitute(source: &str, table: &HashMap<String, String>) -> Result<(String,
                 continue;
             }
             c if suitable_ident(&c) => {
-                let env_macro = if env_macros {
-                    std::env::var(c.to_string()).ok()
-                } else {
-                    None
-                };
-                let table_macro = table.get(&c.to_string()).cloned();
-                let Some(macro_body) = env_macro.or(table_macro) else {
-                    Err(PreprocError::UndefinedMacro(c.to_string()))?
-                };
-                result.push_str(&macro_body);
+                result.push_str(&lookup(&c.to_string(), table));
                 substitutions += 1;
                 continue;
             }
             '(' | '{' => {
+                // internal macros, with their `D` and `F` forms
+                if letters.peek().is_some_and(|c| "@%?<*".contains(*c)) {
+                    result.push('$');
+                    result.push(letter);
+                    for c in letters.by_ref() {
+                        result.push(c);
+                        if matches!(c, ')' | '}') {
+                            break;
+                        }
+                    }
+                    continue;
+                }
+
                 skip_blank(&mut letters);
                 let Ok(macro_name) = get_ident(&mut letters) else {
                     Err(PreprocError::BadMacroName)?
                 };
                 skip_blank(&mut letters);
+                let mut value = lookup(&macro_name, table);
+                // `$(name:from=to)`
+                if letters.next_if_eq(&':').is_some() {
+                    let mut replacement = String::new();
+                    while let Some(c) = letters.next_if(|c| !matches!(c, ')' | '}')) {
+                        replacement.push(c);
+                    }
+                    let Some((from, to)) = replacement.split_once('=') else {
+                        Err(PreprocError::UnexpectedSymbol(':'))?
+                    };
+                    value = replace_suffixes(&expand_at(&value, table, depth + 1)?, from, to);
+                }
                 let Some(finilizer) = letters.next() else {
                     Err(PreprocError::UnexpectedEOF)?
                 };
@@ -242,16 +386,7 @@ fn substitute(source: &str, table: &HashMap<String, String>) -> Result<(String,
                     Err(PreprocError::UnexpectedSymbol(finilizer))?
                 }
 
-                let env_macro = if env_macros {
-                    std::env::var(&macro_name).ok()
-                } else {
-                    None
-                };
-                let table_macro = table.get(&macro_name).cloned();
-                let Some(macro_body) = env_macro.or(table_macro) else {
-                    Err(PreprocError::UndefinedMacro(macro_name.to_string()))?
-                };
-                result.push_str(&macro_body);
+                result.push_str(&value);
                 substitutions += 1;
 
                 continue;
@@ -263,63 +398,154 @@ fn substitute(source: &str, table: &HashMap<String, String>) -> Result<(String,
     Ok((result, substitutions))
 }
 
-/// Copy-pastes included makefiles into single one recursively.
-/// Pretty much the same as C preprocessor and `#include` directive
-fn process_include_lines(source: &str, table: &HashMap<String, String>) -> (String, usize) {
-    let mut counter = 0;
-    let result = source
-        .lines()
-        .map(|x| {
-            if let Some(s) = x.strip_prefix("include") {
-                counter += 1;
-                let s = s.trim();
-                let (source, _) = substitute(s, table).unwrap_or_default();
-                let path = Path::new(&source);
-
-                fs::read_to_string(path).unwrap()
+/// Substitutes macros in `source` until there are none left.
+fn expand(source: &str, table: &HashMap<String, String>) -> Result<String> {
+    expand_at(source, table, 0)
+}
+
+/// Expands `source` like [`expand`], `depth` deep in the expansion of
+/// other macros.
+fn expand_at(source: &str, table: &HashMap<String, String>, depth: usize) -> Result<String> {
+    let mut source = source.to_string();
+    for depth in depth..MAX_EXPANSION_DEPTH {
+        let (result, substitutions) = substitute(&source, table, depth)?;
+        if substitutions == 0 {
+            return Ok(result);
+        }
+        source = result;
+    }
+    Err(PreprocError::RecursiveMacro)
+}
+
+/// Collects the macro definitions of a makefile, and the lines that
+/// remain once they and the `include` lines are taken out.
+#[derive(Default)]
+struct Preprocessor {
+    table: HashMap<String, String>,
+    lines: Vec<Line>,
+}
+
+impl Preprocessor {
+    /// Processes the lines of `source`, in order, so that a macro is
+    /// defined from the line that defines it on.  Included makefiles are
+    /// processed in place of the `include` lines.
+    fn process(
+        &mut self,
+        source: &str,
+        file: Option<&Path>,
+        depth: usize,
+    ) -> std::result::Result<(), Error> {
+        for line in split_lines(source, file) {
+            let location = line.location.clone();
+            let at = at(&location);
+
+            if let Some((macro_name, operator, macro_body)) =
+                parse_assignment(&line.text).map_err(&at)?
+            {
+                self.define(macro_name, operator, macro_body).map_err(&at)?;
+            } else if let Some((optional, files)) = parse_include(&line.text) {
+                let files = expand(files, &self.table).map_err(&at)?;
+                for path in files.split_whitespace() {
+                    let source = match fs::read_to_string(path) {
+                        Ok(source) => source,
+                        Err(_) if optional => continue,
+                        Err(err) => return Err(at(PreprocError::IncludeFailed(path.into(), err))),
+                    };
+                    if depth == MAX_INCLUDE_DEPTH {
+                        return Err(at(PreprocError::TooManyIncludes));
+                    }
+                    self.process(&source, Some(Path::new(path)), depth + 1)?;
+                }
             } else {
-                x.to_string()
+                self.lines.push(line);
             }
-        })
-        .map(|mut x| {
-            x.push('\n');
-            x
-        })
-        .collect::<String>();
-    (result, counter)
-}
+        }
 
-fn remove_variables(source: &str) -> String {
-    source
-        .lines()
-        .filter(|line| !line.contains('='))
-        .map(|x| {
-            let mut x = x.to_string();
-            x.push('\n');
-            x
-        })
-        .collect::<String>()
+        Ok(())
+    }
+
+    fn define(
+        &mut self,
+        macro_name: String,
+        operator: Operator,
+        mut macro_body: String,
+    ) -> Result<()> {
+        match operator {
+            Operator::Equals => {}
+            Operator::Colon | Operator::Colon2 => {
+                macro_body = expand(&macro_body, &self.table)?;
+            }
+            Operator::Colon3 => {
+                macro_body = substitute(&macro_body, &self.table, 0)?.0;
+            }
+            Operator::Bang => {
+                macro_body = expand(&macro_body, &self.table)?;
+                let Ok(result) = std::process::Command::new("sh")
+                    .args(["-c", &macro_body])
+                    .output()
+                else {
+                    Err(PreprocError::CommandFailed)?
+                };
+                // a final newline is removed, and the others become spaces
+                let output = String::from_utf8_lossy(&result.stdout);
+                let output = output.strip_suffix('\n').unwrap_or(&output);
+                macro_body = output.replace('\n', " ");
+            }
+            Operator::QuestionMark => {
+                if self.table.contains_key(&macro_name) || std::env::var_os(&macro_name).is_some() {
+                    return Ok(());
+                }
+            }
+            Operator::Plus => {
+                if let Some(body) = self.table.remove(&macro_name) {
+                    macro_body = format!("{} {}", body, macro_body);
+                }
+            }
+        }
+
+        self.table.insert(macro_name, macro_body);
+        Ok(())
+    }
 }
 
 /// Processes `include`s and macros
-pub fn preprocess(source: &str) -> Result<String> {
-    let mut source = source.to_string();
-    let mut includes = 1;
-    let mut table = generate_macro_table(&source)?;
+pub fn preprocess(source: &str) -> std::result::Result<String, Error> {
+    preprocess_with_locations(source).map(|(result, _)| result)
+}
 
-    while includes > 0 {
-        (source, includes) = process_include_lines(&source, &HashMap::new());
-        table = generate_macro_table(&source)?;
-    }
+/// Processes `include`s and macros, like [`preprocess`], and also returns
+/// where each line of the result comes from.
+pub fn preprocess_with_locations(
+    source: &str,
+) -> std::result::Result<(String, Vec<Location>), Error> {
+    preprocess_with_builtins(source, "")
+}
 
-    source = remove_variables(&source);
+/// Processes `include`s and macros, like [`preprocess_with_locations`],
+/// with `builtins`, the built-in macros and rules, read before `source`.
+pub fn preprocess_with_builtins(
+    source: &str,
+    builtins: &str,
+) -> std::result::Result<(String, Vec<Location>), Error> {
+    let mut preprocessor = Preprocessor::default();
+    preprocessor.process(builtins, Some(Path::new(BUILTINS)), 0)?;
+    preprocessor.process(source, None, 0)?;
 
-    loop {
-        let (result, substitutions) = substitute(&source, &table)?;
-        if substitutions == 0 {
-            break Ok(result);
-        } else {
-            source = result
+    let mut result = String::with_capacity(source.len());
+    let mut locations = Vec::with_capacity(preprocessor.lines.len());
+    for line in preprocessor.lines {
+        let mut text = expand(&line.text, &preprocessor.table).map_err(at(&line.location))?;
+        // recipe lines that run make run even with `-n`, `-q` and `-t`,
+        // which go on to the make they run
+        if line.text.starts_with('\t')
+            && (line.text.contains("$(MAKE)") || line.text.contains("${MAKE}"))
+        {
+            text.insert(1, '+');
         }
+        result.push_str(&text);
+        result.push('\n');
+        locations.push(line.location);
     }
+
+    Ok((result, locations))
 }
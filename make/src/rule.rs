@@ -8,6 +8,8 @@
 //
 
 pub mod config;
+pub mod internal_macros;
+pub mod output;
 pub mod prerequisite;
 pub mod recipe;
 pub mod target;
@@ -15,21 +17,21 @@ pub mod target;
 use crate::{
     config::Config as GlobalConfig,
     error_code::ErrorCode::{self, *},
-    parser::{Rule as ParsedRule, VariableDefinition},
+    parser::Rule as ParsedRule,
     signal_handler, DEFAULT_SHELL, DEFAULT_SHELL_VAR,
 };
 use config::Config;
 use gettextrs::gettext;
+use internal_macros::InternalMacros;
+use output::Output;
 use prerequisite::Prerequisite;
 use recipe::config::Config as RecipeConfig;
 use recipe::Recipe;
-use std::collections::VecDeque;
-use std::io::ErrorKind;
-use std::path::PathBuf;
 use std::{
-    collections::HashMap,
+    collections::{BTreeMap, HashMap},
     env,
     fs::{File, FileTimes},
+    os::unix::process::ExitStatusExt,
     process::{self, Command},
     sync::{Arc, LazyLock, Mutex},
     time::SystemTime,
@@ -38,8 +40,32 @@ use target::Target;
 
 type LazyArcMutex<T> = LazyLock<Arc<Mutex<T>>>;
 
-pub static INTERRUPT_FLAG: LazyArcMutex<Option<(String, bool)>> =
-    LazyLock::new(|| Arc::new(Mutex::new(None)));
+/// The targets whose rules are running, each with whether it is precious,
+/// for the cleanup after a signal to remove the ones that are not.
+pub static INTERRUPT_FLAG: LazyArcMutex<BTreeMap<String, bool>> =
+    LazyLock::new(|| Arc::new(Mutex::new(BTreeMap::new())));
+
+/// Keeps a target in [`INTERRUPT_FLAG`] while its rule runs.
+struct InFlight<'a>(&'a str);
+
+impl<'a> InFlight<'a> {
+    fn new(target: &'a str, precious: bool) -> Self {
+        INTERRUPT_FLAG
+            .lock()
+            .unwrap()
+            .insert(target.to_string(), precious);
+        InFlight(target)
+    }
+}
+
+impl Drop for InFlight<'_> {
+    fn drop(&mut self) {
+        // a rule ended by a signal has not made its target
+        if !signal_handler::interrupted() {
+            INTERRUPT_FLAG.lock().unwrap().remove(self.0);
+        }
+    }
+}
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct Rule {
@@ -66,15 +92,18 @@ impl Rule {
         self.recipes.iter()
     }
 
-    /// Runs the rule with the global config and macros passed in.
+    /// Runs the rule with the global config and macros passed in, writing
+    /// the recipe lines and their output to `output`.
     ///
     /// Returns `Ok` on success and `Err` on any errors while running the rule.
     pub fn run(
         &self,
         global_config: &GlobalConfig,
-        macros: &[VariableDefinition],
+        macros: &HashMap<String, String>,
         target: &Target,
+        internal_macros: &InternalMacros,
         up_to_date: bool,
+        output: &mut Output,
     ) -> Result<(), ErrorCode> {
         let GlobalConfig {
             ignore: global_ignore,
@@ -85,9 +114,12 @@ impl Rule {
             quit: global_quit,
             clear: _,
             print: global_print,
-            keep_going: global_keep_going,
-            terminate: global_terminate,
+            keep_going: _,
+            terminate: _,
             precious: global_precious,
+            jobs: _,
+            not_parallel: _,
+            debug: _,
             rules: _,
         } = *global_config;
         let Config {
@@ -97,186 +129,110 @@ impl Rule {
             phony: _,
         } = self.config;
 
-        let files = match target {
-            Target::Inference { from, to, .. } => find_files_with_extension(from)?
-                .into_iter()
-                .map(|input| {
-                    let mut output = input.clone();
-                    output.set_extension(to);
-                    (input, output)
-                })
-                .collect::<Vec<_>>(),
-            _ => {
-                vec![(PathBuf::from(""), PathBuf::from(""))]
-            }
-        };
+        let _in_flight = InFlight::new(target.as_ref(), global_precious || rule_precious);
 
-        for inout in files {
-            for recipe in self.recipes() {
-                let RecipeConfig {
-                    ignore: recipe_ignore,
-                    silent: recipe_silent,
-                    force_run: recipe_force_run,
-                } = recipe.config;
+        for recipe in self.recipes() {
+            let RecipeConfig {
+                ignore: recipe_ignore,
+                silent: recipe_silent,
+                force_run: recipe_force_run,
+            } = recipe.config;
 
-                let ignore = global_ignore || rule_ignore || recipe_ignore;
-                let dry_run = global_dry_run;
-                let silent = global_silent || rule_silent || recipe_silent;
-                let force_run = recipe_force_run;
-                let touch = global_touch;
-                let env_macros = global_env_macros;
-                let quit = global_quit;
-                let print = global_print;
-                let precious = global_precious || rule_precious;
-                let keep_going = global_keep_going;
-                let terminate = global_terminate;
+            let ignore = global_ignore || rule_ignore || recipe_ignore;
+            let dry_run = global_dry_run;
+            let silent = global_silent || rule_silent || recipe_silent;
+            let force_run = recipe_force_run;
+            let touch = global_touch;
+            let env_macros = global_env_macros;
+            let quit = global_quit;
+            let print = global_print;
 
-                *INTERRUPT_FLAG.lock().unwrap() = Some((target.as_ref().to_string(), precious));
+            if !ignore || print || quit || dry_run {
+                signal_handler::register_signals();
+            }
 
-                if !ignore || print || quit || dry_run {
-                    signal_handler::register_signals();
-                }
+            let line = internal_macros.substitute(recipe.inner());
 
-                if !force_run {
-                    // -n flag
-                    if dry_run {
-                        println!("{}", recipe);
-                        continue;
-                    }
+            if !force_run {
+                // -n flag
+                if dry_run {
+                    output.println(&line);
+                    continue;
+                }
 
-                    // -t flag
-                    if touch {
-                        continue;
-                    }
-                    // -q flag
-                    if quit {
-                        if up_to_date {
-                            process::exit(0);
-                        } else {
-                            process::exit(1);
-                        }
+                // -t flag
+                if touch {
+                    continue;
+                }
+                // -q flag
+                if quit {
+                    if up_to_date {
+                        process::exit(0);
+                    } else {
+                        process::exit(1);
                     }
                 }
+            }
 
-                // -s flag
-                if !silent {
-                    println!("{}", recipe);
-                }
+            // -s flag
+            if !silent {
+                output.println(&line);
+            }
 
-                let mut command = Command::new(
-                    env::var(DEFAULT_SHELL_VAR)
-                        .as_ref()
-                        .map(|s| s.as_str())
-                        .unwrap_or(DEFAULT_SHELL),
-                );
+            let mut command = Command::new(
+                env::var(DEFAULT_SHELL_VAR)
+                    .as_ref()
+                    .map(|s| s.as_str())
+                    .unwrap_or(DEFAULT_SHELL),
+            );
 
-                self.init_env(env_macros, &mut command, macros);
-                let recipe =
-                    self.substitute_internal_macros(target, recipe, &inout, self.prerequisites());
-                command.args(["-c", recipe.as_ref()]);
+            self.init_env(env_macros, &mut command, macros);
+            command.args(["-c", line.as_str()]);
 
-                let status = match command.status() {
-                    Ok(status) => status,
-                    Err(err) => {
-                        if ignore {
-                            continue;
-                        } else {
-                            return Err(IoError(err.kind()));
-                        }
-                    }
-                };
-                if !status.success() && !ignore {
-                    // -S and -k flags
-                    if !terminate && keep_going {
-                        eprintln!(
-                            "make: {}",
-                            ExecutionError {
-                                exit_code: status.code(),
-                            }
-                        );
-                        break;
+            let status = match output.status(&mut command) {
+                Ok(status) => status,
+                Err(err) => {
+                    if ignore {
+                        continue;
                     } else {
-                        return Err(ExecutionError {
-                            exit_code: status.code(),
-                        });
+                        return Err(IoError(err.kind()));
                     }
                 }
+            };
+            if let Some(signal_code) = status.signal() {
+                signal_handler::await_cleanup(signal_code);
             }
-
-            let silent = global_silent || rule_silent;
-            let touch = global_touch;
-
-            // -t flag
-            if touch {
-                if !silent {
-                    println!("{} {target}", gettext("touch"));
-                }
-                let file = File::create(target.as_ref())?;
-                file.set_times(FileTimes::new().set_modified(SystemTime::now()))?;
-                return Ok(());
+            if !status.success() && !ignore {
+                return Err(ExecutionError {
+                    exit_code: status.code(),
+                });
             }
         }
 
-        Ok(())
-    }
-
-    fn substitute_internal_macros<'a>(
-        &self,
-        target: &Target,
-        recipe: &Recipe,
-        files: &(PathBuf, PathBuf),
-        mut prereqs: impl Iterator<Item = &'a Prerequisite>,
-    ) -> Recipe {
-        let recipe = recipe.inner();
-        let mut stream = recipe.chars();
-        let mut result = String::new();
-
-        while let Some(ch) = stream.next() {
-            if ch != '$' {
-                result.push(ch);
-                continue;
-            }
+        let silent = global_silent || rule_silent;
+        let touch = global_touch;
 
-            match stream.next() {
-                Some('@') => {
-                    if let Some(s) = target.as_ref().split('(').next() {
-                        result.push_str(s)
-                    }
-                }
-                Some('%') => {
-                    if let Some(body) = target.as_ref().split('(').nth(1) {
-                        result.push_str(body.strip_suffix(')').unwrap_or(body))
-                    }
-                }
-                Some('?') => {
-                    (&mut prereqs)
-                        .map(|x| x.as_ref())
-                        .for_each(|x| result.push_str(x));
-                }
-                Some('$') => result.push('$'),
-                Some('<') => result.push_str(files.0.to_str().unwrap()),
-                Some('*') => result.push_str(files.1.to_str().unwrap()),
-                Some(_) => break,
-                None => {
-                    eprintln!("Unexpected `$` at the end of the rule!")
-                }
+        // -t flag
+        if touch {
+            if !silent {
+                output.println(format_args!("{} {target}", gettext("touch")));
             }
+            let file = File::create(target.as_ref())?;
+            file.set_times(FileTimes::new().set_modified(SystemTime::now()))?;
+            return Ok(());
         }
 
-        Recipe::new(result)
+        Ok(())
     }
 
     /// A helper function to initialize env vars for shell commands.
-    fn init_env(&self, env_macros: bool, command: &mut Command, variables: &[VariableDefinition]) {
-        let mut macros: HashMap<String, String> = variables
-            .iter()
-            .map(|v| {
-                (
-                    v.name().unwrap_or_default(),
-                    v.raw_value().unwrap_or_default(),
-                )
-            })
-            .collect();
+    fn init_env(
+        &self,
+        env_macros: bool,
+        command: &mut Command,
+        variables: &HashMap<String, String>,
+    ) {
+        let mut macros = variables.clone();
 
         if env_macros {
             let env_vars: HashMap<String, String> = std::env::vars().collect();
@@ -306,33 +262,3 @@ impl From<(ParsedRule, Config)> for Rule {
         }
     }
 }
-
-fn find_files_with_extension(ext: &str) -> Result<Vec<PathBuf>, ErrorCode> {
-    use std::{env, fs};
-
-    let mut result = vec![];
-    let Ok(current) = env::current_dir() else {
-        Err(IoError(ErrorKind::PermissionDenied))?
-    };
-    let mut dirs_to_walk = VecDeque::new();
-    dirs_to_walk.push_back(current);
-
-    while let Some(path) = dirs_to_walk.pop_front() {
-        let files = fs::read_dir(path)?;
-        for file in files.filter_map(Result::ok) {
-            let Ok(metadata) = file.metadata() else {
-                continue;
-            };
-
-            if metadata.is_file() {
-                if let Some(e) = file.path().extension() {
-                    if ext == e {
-                        result.push(file.path());
-                    }
-                }
-            }
-        }
-    }
-
-    Ok(result)
-}
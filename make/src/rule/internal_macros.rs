@@ -0,0 +1,137 @@
+//
+// Copyright (c) 2024 Hemi Labs, Inc.
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+/// The values of the internal macros of a rule run to make a target.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct InternalMacros {
+    /// `$@`: the target, or the archive of an archive member.
+    pub target: String,
+    /// `$%`: the member of an archive member target.
+    pub member: String,
+    /// `$?`: the prerequisites newer than the target.
+    pub newer: Vec<String>,
+    /// `$<`: the prerequisite an inference rule makes the target from.
+    pub implied: String,
+    /// `$*`: the target without its suffix, for an inference rule.
+    pub stem: String,
+}
+
+impl InternalMacros {
+    /// The values of the internal macro `name`: more than one for `$?`.
+    fn values(&self, name: char) -> Option<Vec<&str>> {
+        let value = match name {
+            '@' => &self.target,
+            '%' => &self.member,
+            '<' => &self.implied,
+            '*' => &self.stem,
+            '?' => return Some(self.newer.iter().map(String::as_str).collect()),
+            _ => return None,
+        };
+        Some(vec![value.as_str()])
+    }
+
+    /// Substitutes the internal macros in `recipe`: `$@`, `$%`, `$?`, `$<`
+    /// and `$*`, the same in parentheses or braces, and their `D` and `F`
+    /// forms, such as `$(@D)`, which are the directory part and the file
+    /// part of each word of the value.  `$$` becomes `$`, and anything else
+    /// is left as it is.
+    pub fn substitute(&self, recipe: &str) -> String {
+        let mut stream = recipe.chars().peekable();
+        let mut result = String::new();
+
+        while let Some(ch) = stream.next() {
+            if ch != '$' {
+                result.push(ch);
+                continue;
+            }
+
+            match stream.peek().copied() {
+                Some('$') => {
+                    stream.next();
+                    result.push('$');
+                }
+                Some(name) => {
+                    if let Some(values) = self.values(name) {
+                        stream.next();
+                        result.push_str(&values.join(" "));
+                        continue;
+                    }
+                    let Some(closer) = closer(name) else {
+                        result.push('$');
+                        continue;
+                    };
+                    let mut rest = stream.clone();
+                    rest.next();
+                    let name = rest.next();
+                    let form = rest.next_if(|c| matches!(c, 'D' | 'F'));
+                    match (name.and_then(|name| self.values(name)), rest.next()) {
+                        (Some(values), Some(c)) if c == closer => {
+                            let values: Vec<&str> = values
+                                .into_iter()
+                                .map(|value| match form {
+                                    Some('D') => directory_part(value),
+                                    Some(_) => file_part(value),
+                                    None => value,
+                                })
+                                .collect();
+                            result.push_str(&values.join(" "));
+                            stream = rest;
+                        }
+                        _ => result.push('$'),
+                    }
+                }
+                None => {
+                    eprintln!("Unexpected `$` at the end of the rule!")
+                }
+            }
+        }
+
+        result
+    }
+}
+
+/// The closing bracket of `opener`, if it is one.
+fn closer(opener: char) -> Option<char> {
+    match opener {
+        '(' => Some(')'),
+        '{' => Some('}'),
+        _ => None,
+    }
+}
+
+/// The directory part of a path, for the `D` forms: `.` if there is none,
+/// and nothing for an empty value.
+fn directory_part(path: &str) -> &str {
+    if path.is_empty() {
+        return "";
+    }
+    match path.rsplit_once('/') {
+        Some(("", _)) => "/",
+        Some((directory, _)) => directory,
+        None => ".",
+    }
+}
+
+/// The file part of a path, for the `F` forms.
+fn file_part(path: &str) -> &str {
+    path.rsplit_once('/').map_or(path, |(_, file)| file)
+}
+
+/// Substitutes `$$@`, and its `D` and `F` forms, in a prerequisite of
+/// `target`.
+pub fn substitute_prerequisite(prerequisite: &str, target: &str) -> String {
+    if !prerequisite.contains("$$") {
+        return prerequisite.to_string();
+    }
+    let macros = InternalMacros {
+        target: target.to_string(),
+        ..Default::default()
+    };
+    macros.substitute(&prerequisite.replace("$$", "$"))
+}
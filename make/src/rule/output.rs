@@ -0,0 +1,163 @@
+//
+// Copyright (c) 2024 Hemi Labs, Inc.
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+use core::fmt;
+use std::io::{self, Read, Write};
+use std::mem::MaybeUninit;
+use std::net::Shutdown;
+use std::os::fd::OwnedFd;
+use std::os::unix::net::UnixStream;
+use std::process::{Command, ExitStatus, Stdio};
+use std::sync::LazyLock;
+use std::thread::{self, JoinHandle};
+
+/// Which of make's streams a piece of output goes to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stream {
+    Stdout,
+    Stderr,
+}
+
+/// Where a rule's echoed recipe lines, messages and command output go.
+#[derive(Debug, Default)]
+pub enum Output {
+    /// Straight to make's own stdout and stderr, as the commands run.
+    #[default]
+    Inherit,
+    /// Collected in the order it was written, to be written out in one
+    /// piece when the job finishes, so that jobs running in parallel do
+    /// not interleave their output.
+    Buffered(Vec<(Stream, Vec<u8>)>),
+}
+
+/// Whether make's stdout and stderr are the same file, as on a terminal,
+/// so that the output of a command has to keep its order across both.
+static SHARED_STREAMS: LazyLock<bool> = LazyLock::new(|| {
+    let file_id = |fd| unsafe {
+        let mut stat = MaybeUninit::<libc::stat>::uninit();
+        (libc::fstat(fd, stat.as_mut_ptr()) == 0).then(|| {
+            let stat = stat.assume_init();
+            (stat.st_dev, stat.st_ino)
+        })
+    };
+    let stdout = file_id(libc::STDOUT_FILENO);
+    stdout.is_some() && stdout == file_id(libc::STDERR_FILENO)
+});
+
+/// What a command writes to one end of a socket, read on a thread of its
+/// own so that the command never blocks on a full socket.
+struct Collector {
+    reader: UnixStream,
+    thread: JoinHandle<io::Result<Vec<u8>>>,
+}
+
+impl Collector {
+    /// Starts reading, returning the end for the command to write to.
+    fn start() -> io::Result<(Self, OwnedFd)> {
+        let (reader, writer) = UnixStream::pair()?;
+        let mut thread_reader = reader.try_clone()?;
+        let thread = thread::spawn(move || {
+            let mut bytes = Vec::new();
+            thread_reader.read_to_end(&mut bytes).map(|_| bytes)
+        });
+        Ok((Collector { reader, thread }, OwnedFd::from(writer)))
+    }
+
+    /// Stops reading once the command is done, returning what it wrote.
+    fn finish(self) -> io::Result<Vec<u8>> {
+        // what is still in the socket is read, but not what processes the
+        // command left running in the background write later on, as they
+        // may hold their end open for as long as they like
+        let _ = self.reader.shutdown(Shutdown::Read);
+        self.thread
+            .join()
+            .unwrap_or_else(|_| Err(io::Error::other("output reader panicked")))
+    }
+}
+
+impl Output {
+    pub fn buffered() -> Self {
+        Output::Buffered(Vec::new())
+    }
+
+    /// Collects `bytes` for `stream`, after what was collected before.
+    fn push(chunks: &mut Vec<(Stream, Vec<u8>)>, stream: Stream, bytes: &[u8]) {
+        if bytes.is_empty() {
+            return;
+        }
+        match chunks.last_mut() {
+            Some((last, chunk)) if *last == stream => chunk.extend_from_slice(bytes),
+            _ => chunks.push((stream, bytes.to_vec())),
+        }
+    }
+
+    /// Writes a line to stdout.
+    pub fn println(&mut self, line: impl fmt::Display) {
+        match self {
+            Output::Inherit => println!("{line}"),
+            Output::Buffered(chunks) => {
+                Self::push(chunks, Stream::Stdout, format!("{line}\n").as_bytes());
+            }
+        }
+    }
+
+    /// Writes a line to stderr.
+    pub fn eprintln(&mut self, line: impl fmt::Display) {
+        match self {
+            Output::Inherit => eprintln!("{line}"),
+            Output::Buffered(chunks) => {
+                Self::push(chunks, Stream::Stderr, format!("{line}\n").as_bytes());
+            }
+        }
+    }
+
+    /// Runs the command to completion, its output going where this
+    /// output goes.
+    pub fn status(&mut self, command: &mut Command) -> io::Result<ExitStatus> {
+        match self {
+            Output::Inherit => command.status(),
+            Output::Buffered(chunks) => {
+                let (stdout, writer) = Collector::start()?;
+                // one socket for both when they are the same file, which
+                // keeps what the command writes to each in order
+                let stderr = if *SHARED_STREAMS {
+                    command.stdout(writer.try_clone()?).stderr(writer);
+                    None
+                } else {
+                    let (stderr, stderr_writer) = Collector::start()?;
+                    command.stdout(writer).stderr(stderr_writer);
+                    Some(stderr)
+                };
+                let status = command.spawn().and_then(|mut child| child.wait());
+                // drop the command's ends
+                command.stdout(Stdio::inherit()).stderr(Stdio::inherit());
+                Self::push(chunks, Stream::Stdout, &stdout.finish()?);
+                if let Some(stderr) = stderr {
+                    Self::push(chunks, Stream::Stderr, &stderr.finish()?);
+                }
+                status
+            }
+        }
+    }
+
+    /// Writes out what has been collected.
+    pub fn flush(self) {
+        if let Output::Buffered(chunks) = self {
+            let mut stdout = io::stdout().lock();
+            let mut stderr = io::stderr().lock();
+            for (stream, bytes) in chunks {
+                let _ = match stream {
+                    Stream::Stdout => stdout.write_all(&bytes),
+                    Stream::Stderr => stdout.flush().and_then(|()| stderr.write_all(&bytes)),
+                };
+            }
+            let _ = stdout.flush();
+        }
+    }
+}
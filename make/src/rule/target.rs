@@ -47,12 +47,14 @@ impl Target {
             Target::Special(target) => match target {
                 SpecialTarget::Default => ".DEFAULT",
                 SpecialTarget::Ignore => ".IGNORE",
+                SpecialTarget::NotParallel => ".NOTPARALLEL",
                 SpecialTarget::Posix => ".POSIX",
                 SpecialTarget::Precious => ".PRECIOUS",
                 SpecialTarget::SccsGet => ".SCCS_GET",
                 SpecialTarget::Silent => ".SILENT",
                 SpecialTarget::Suffixes => ".SUFFIXES",
                 SpecialTarget::Phony => ".PHONY",
+                SpecialTarget::Wait => ".WAIT",
             },
         }
     }
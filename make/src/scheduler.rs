@@ -0,0 +1,366 @@
+//
+// Copyright (c) 2024 Hemi Labs, Inc.
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+//! Building a target: the graph of everything it depends on, and the
+//! scheduler that runs the rules of the out-of-date targets in it, up to
+//! `-j` of them at a time.
+
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap, HashSet},
+    sync::mpsc,
+    thread,
+    time::SystemTime,
+};
+
+use crate::{
+    archive,
+    error_code::ErrorCode::{self, *},
+    get_modified_time, is_newer,
+    rule::{
+        internal_macros::{self, InternalMacros},
+        output::Output,
+        target::Target,
+        Rule,
+    },
+    special_target::SpecialTarget,
+    Make,
+};
+
+/// A target in the dependency graph.
+#[derive(Debug)]
+pub struct Node<'a> {
+    pub name: String,
+    /// The rule that makes the target, or `None` for an existing file
+    /// that no rule makes.
+    pub rule: Option<&'a Rule>,
+    /// The modification time of the target, if it exists.
+    pub modified: Option<SystemTime>,
+    /// The nodes of the prerequisites.
+    pub prerequisites: Vec<usize>,
+    /// The nodes that have to be finished first because they come before
+    /// a `.WAIT` in a prerequisite list that this node comes after.
+    pub after: Vec<usize>,
+    /// Whether the target is out of date, so that its rule has to run.
+    pub stale: bool,
+    /// Whether the target is phony, so that its rule always runs.
+    pub phony: bool,
+    /// The prerequisite an inference rule makes the target from.
+    pub implied: Option<String>,
+    /// The target without its suffix, when an inference rule makes it.
+    pub stem: Option<String>,
+}
+
+impl Node<'_> {
+    /// The nodes that have to be finished before this one.
+    fn dependencies(&self) -> impl Iterator<Item = usize> + '_ {
+        self.prerequisites.iter().chain(&self.after).copied()
+    }
+}
+
+/// The targets a goal depends on.  Each node comes after the nodes of its
+/// prerequisites, so the goal is the last one.
+#[derive(Debug, Default)]
+pub struct Graph<'a> {
+    pub nodes: Vec<Node<'a>>,
+    index: HashMap<String, usize>,
+}
+
+impl<'a> Graph<'a> {
+    /// The graph of `goal` and everything it depends on.
+    pub fn new(make: &'a Make, goal: &str) -> Result<Self, ErrorCode> {
+        let mut graph = Graph::default();
+        graph.add(make, goal, &mut Vec::new())?;
+        Ok(graph)
+    }
+
+    pub fn goal(&self) -> &Node<'a> {
+        self.nodes.last().expect("the graph has its goal")
+    }
+
+    /// The internal macros for the rule of `node`, once its prerequisites
+    /// are made: `$?` is the prerequisites that are newer than the target,
+    /// or that were just made.
+    pub fn internal_macros(&self, node: usize) -> InternalMacros {
+        let node = &self.nodes[node];
+        let (target, member) =
+            archive::split_member(&node.name).unwrap_or((node.name.as_str(), ""));
+        let newer = node
+            .prerequisites
+            .iter()
+            .map(|&prerequisite| &self.nodes[prerequisite])
+            .filter(|prerequisite| {
+                node.modified.is_none()
+                    || prerequisite.stale
+                    || is_newer(
+                        get_modified_time(&prerequisite.name),
+                        &node.name,
+                        node.modified,
+                    )
+            })
+            .map(|prerequisite| prerequisite.name.clone())
+            .collect();
+
+        InternalMacros {
+            target: target.to_string(),
+            member: member.to_string(),
+            newer,
+            implied: node.implied.clone().unwrap_or_default(),
+            stem: node.stem.clone().unwrap_or_default(),
+        }
+    }
+
+    /// Adds the node of `name` and of everything it depends on, `stack`
+    /// holding the targets whose prerequisites are being added.
+    fn add(
+        &mut self,
+        make: &'a Make,
+        name: &str,
+        stack: &mut Vec<String>,
+    ) -> Result<usize, ErrorCode> {
+        if let Some(&node) = self.index.get(name) {
+            return Ok(node);
+        }
+        if stack.iter().any(|target| target == name) {
+            return Err(RecursivePrerequisite {
+                origin: stack[0].clone(),
+            });
+        }
+
+        let modified = get_modified_time(name);
+        let explicit = make.rule_by_target_name(name);
+        // a rule without recipes leaves the target to an inference rule
+        let inference = match explicit {
+            Some(rule) if rule.recipes().next().is_some() => None,
+            _ => make.infer(name),
+        };
+        let rule = match (explicit, &inference) {
+            (_, Some(inference)) => Some(inference.rule),
+            (Some(rule), None) => Some(rule),
+            (None, None) if modified.is_some() => None,
+            (None, None) => Some(make.default_rule.as_ref().ok_or_else(|| NoTarget {
+                target: Some(name.to_string()),
+            })?),
+        };
+
+        stack.push(name.to_string());
+        let mut prerequisites = Vec::new();
+        if let Some(inference) = &inference {
+            prerequisites.push(self.add(make, &inference.prerequisite, stack)?);
+        }
+        // the prerequisites before the last `.WAIT`, and after it
+        let mut waited_for = Vec::new();
+        let mut group = Vec::new();
+        for prerequisite in explicit.iter().flat_map(|rule| rule.prerequisites()) {
+            if prerequisite.as_ref() == SpecialTarget::Wait.as_ref() {
+                waited_for.append(&mut group);
+                continue;
+            }
+            let prerequisite =
+                internal_macros::substitute_prerequisite(prerequisite.as_ref(), name);
+            let node = self.add(make, &prerequisite, stack)?;
+            self.wait(node, &waited_for);
+            prerequisites.push(node);
+            group.push(node);
+        }
+        stack.pop();
+
+        let phony = explicit.or(rule).is_some_and(|rule| rule.config.phony);
+        let stale = rule.is_some() && {
+            phony
+                || modified.is_none()
+                || prerequisites.iter().any(|&node| {
+                    let node = &self.nodes[node];
+                    node.stale || is_newer(node.modified, name, modified)
+                })
+        };
+
+        let node = self.nodes.len();
+        self.nodes.push(Node {
+            name: name.to_string(),
+            rule,
+            modified,
+            prerequisites,
+            after: Vec::new(),
+            stale,
+            phony,
+            implied: inference.as_ref().map(|i| i.prerequisite.clone()),
+            stem: inference.map(|i| i.stem),
+        });
+        self.index.insert(name.to_string(), node);
+
+        // -d flag
+        if make.config.debug {
+            for reason in self.reasons(make, node) {
+                eprintln!("make: {name}: {reason}");
+            }
+        }
+        Ok(node)
+    }
+
+    /// Makes `node` wait for each of `waited_for` that does not itself
+    /// depend on `node`, which would never finish.
+    fn wait(&mut self, node: usize, waited_for: &[usize]) {
+        for &other in waited_for {
+            if other != node && !self.depends_on(other, node) {
+                self.nodes[node].after.push(other);
+            }
+        }
+    }
+
+    /// Whether `from` has to wait for `to`, directly or not.
+    fn depends_on(&self, from: usize, to: usize) -> bool {
+        let mut seen = HashSet::new();
+        let mut pending = vec![from];
+        while let Some(node) = pending.pop() {
+            if node == to {
+                return true;
+            }
+            if seen.insert(node) {
+                pending.extend(self.nodes[node].dependencies());
+            }
+        }
+        false
+    }
+}
+
+/// Runs the rules of the out-of-date nodes of `graph`, each once the nodes
+/// it depends on are finished.  Of the nodes that are ready, the earliest
+/// is started first, so a serial build runs the rules in the order of a
+/// depth-first walk of the prerequisites.
+///
+/// With more than one job, each rule's output is held back until the rule
+/// is finished, and then written out in one piece.  After an error no
+/// more rules are started, and the ones running are waited for; with `-k`,
+/// the error is reported and the rules that do not depend on the failed
+/// one go on, the goal then failing with [`ErrorCode::NotRemade`].
+///
+/// Returns whether the goal was out of date.
+pub fn run(make: &Make, graph: &Graph) -> Result<bool, ErrorCode> {
+    let config = &make.config;
+    let jobs = if config.not_parallel {
+        1
+    } else {
+        config.jobs.max(1)
+    };
+    let keep_going = config.keep_going && !config.terminate;
+    let macros = make.macro_values();
+    let macros = &macros;
+
+    let mut dependents = vec![Vec::new(); graph.nodes.len()];
+    let mut remaining = Vec::with_capacity(graph.nodes.len());
+    let mut ready = BinaryHeap::new();
+    for (i, node) in graph.nodes.iter().enumerate() {
+        let mut dependencies: Vec<usize> = node.dependencies().collect();
+        dependencies.sort_unstable();
+        dependencies.dedup();
+        for &dependency in &dependencies {
+            dependents[dependency].push(i);
+        }
+        remaining.push(dependencies.len());
+        if dependencies.is_empty() {
+            ready.push(Reverse(i));
+        }
+    }
+
+    let mut finish = |node: usize, ready: &mut BinaryHeap<Reverse<usize>>| {
+        for &dependent in &dependents[node] {
+            remaining[dependent] -= 1;
+            if remaining[dependent] == 0 {
+                ready.push(Reverse(dependent));
+            }
+        }
+    };
+
+    thread::scope(|scope| {
+        let (sender, receiver) = mpsc::channel();
+        let mut running = 0;
+        let mut error = None;
+        // -k flag
+        let mut failed = false;
+
+        loop {
+            while running < jobs && error.is_none() {
+                let Some(Reverse(i)) = ready.pop() else {
+                    break;
+                };
+                let node = &graph.nodes[i];
+                let rule = match node.rule {
+                    Some(rule) if node.stale => rule,
+                    _ => {
+                        finish(i, &mut ready);
+                        continue;
+                    }
+                };
+                let target = Target::new(node.name.as_str());
+                let internal_macros = graph.internal_macros(i);
+
+                if jobs == 1 {
+                    let mut output = Output::Inherit;
+                    match rule.run(
+                        config,
+                        macros,
+                        &target,
+                        &internal_macros,
+                        false,
+                        &mut output,
+                    ) {
+                        Ok(()) => finish(i, &mut ready),
+                        Err(err @ ExecutionError { .. }) if keep_going => {
+                            eprintln!("make: {err}");
+                            failed = true;
+                        }
+                        Err(err) => error = Some(err),
+                    }
+                    continue;
+                }
+
+                let sender = sender.clone();
+                scope.spawn(move || {
+                    let mut output = Output::buffered();
+                    let result = rule.run(
+                        config,
+                        macros,
+                        &target,
+                        &internal_macros,
+                        false,
+                        &mut output,
+                    );
+                    let _ = sender.send((i, result, output));
+                });
+                running += 1;
+            }
+
+            if running == 0 {
+                break;
+            }
+            let (i, result, output) = receiver.recv().expect("a running job reports back");
+            running -= 1;
+            output.flush();
+            match result {
+                Ok(()) => finish(i, &mut ready),
+                Err(err @ ExecutionError { .. }) if keep_going => {
+                    eprintln!("make: {err}");
+                    failed = true;
+                }
+                Err(err) => {
+                    error.get_or_insert(err);
+                }
+            }
+        }
+
+        match error {
+            Some(err) => Err(err),
+            None if failed => Err(NotRemade {
+                target: graph.goal().name.clone(),
+            }),
+            None => Ok(graph.goal().stale),
+        }
+    })
+}
@@ -7,28 +7,100 @@
 // SPDX-License-Identifier: MIT
 //
 
-use std::{fs::remove_file, process};
+use std::{
+    fs::remove_file,
+    io::{ErrorKind, Read},
+    os::{fd::IntoRawFd, unix::net::UnixStream},
+    path::Path,
+    process,
+    sync::{
+        atomic::{AtomicBool, AtomicI32, Ordering},
+        Once, PoisonError,
+    },
+    thread,
+    time::Duration,
+};
 
 use crate::rule::INTERRUPT_FLAG;
 use gettextrs::gettext;
 use libc::{signal, SIGHUP, SIGINT, SIGQUIT, SIGTERM};
 
-/// Handles incoming signals by setting the interrupt flag and exiting the process.
-pub fn handle_signals(signal_code: libc::c_int) {
-    let interrupt_flag = INTERRUPT_FLAG.lock().unwrap();
-    if let Some((target, precious)) = interrupt_flag.as_ref() {
+/// The signals after which make cleans up and exits.
+const SIGNALS: [libc::c_int; 4] = [SIGINT, SIGQUIT, SIGTERM, SIGHUP];
+
+/// The socket through which the signal handler passes signals on to the
+/// thread that cleans up, or -1 before the handler is registered.
+static SIGNAL_SOCKET: AtomicI32 = AtomicI32::new(-1);
+
+/// Whether one of the [`SIGNALS`] has been handled.
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+/// Whether one of the [`SIGNALS`] has arrived, or is about to be handled, so
+/// that the targets being made are left for the cleanup to remove.
+pub fn interrupted() -> bool {
+    if INTERRUPTED.load(Ordering::SeqCst) {
+        return true;
+    }
+    // the signal that ended a job's command reaches make at the same time,
+    // but may not have been handled yet by the time the job is done
+    unsafe {
+        let mut pending = std::mem::MaybeUninit::<libc::sigset_t>::uninit();
+        libc::sigemptyset(pending.as_mut_ptr());
+        if libc::sigpending(pending.as_mut_ptr()) != 0 {
+            return false;
+        }
+        SIGNALS
+            .iter()
+            .any(|&signal| libc::sigismember(pending.as_ptr(), signal) == 1)
+    }
+}
+
+/// Gives the cleanup the time to run when a command has been ended by
+/// `signal_code`: a signal sent to make's process group reaches the
+/// commands and make together, and the job is not to go on, nor make to
+/// exit, before make has handled it.
+pub fn await_cleanup(signal_code: libc::c_int) {
+    if SIGNALS.contains(&signal_code) {
+        thread::sleep(Duration::from_millis(500));
+    }
+}
+
+/// Handles incoming signals by passing them on to the cleanup thread, as
+/// nothing but async-signal-safe functions may be called here.
+pub extern "C" fn handle_signals(signal_code: libc::c_int) {
+    INTERRUPTED.store(true, Ordering::SeqCst);
+    let byte = signal_code as u8;
+    unsafe {
+        libc::write(
+            SIGNAL_SOCKET.load(Ordering::Relaxed),
+            (&byte as *const u8).cast(),
+            1,
+        );
+    }
+}
+
+/// Removes the targets being made that are not precious, and exits the
+/// process.
+fn clean_up(signal_code: libc::c_int) -> ! {
+    let interrupt_flag = INTERRUPT_FLAG
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner);
+    if !interrupt_flag.is_empty() {
         eprintln!("{}", gettext("make: Interrupt"));
+    }
+    for (target, &precious) in interrupt_flag.iter() {
         // .PRECIOUS special target
-        if !precious {
-            eprintln!(
-                "{}: {} '{}'",
-                gettext("make"),
-                gettext("Deleting file"),
-                target
-            );
-            if let Err(err) = remove_file(target) {
-                eprintln!("{}: {}", gettext("Error deleting file"), err);
-            }
+        if precious || !Path::new(target).exists() {
+            continue;
+        }
+        eprintln!(
+            "{}: {} '{}'",
+            gettext("make"),
+            gettext("Deleting file"),
+            target
+        );
+        if let Err(err) = remove_file(target) {
+            eprintln!("{}: {}", gettext("Error deleting file"), err);
         }
     }
 
@@ -36,10 +108,28 @@ pub fn handle_signals(signal_code: libc::c_int) {
 }
 
 pub fn register_signals() {
-    unsafe {
-        signal(SIGINT, handle_signals as usize);
-        signal(SIGQUIT, handle_signals as usize);
-        signal(SIGTERM, handle_signals as usize);
-        signal(SIGHUP, handle_signals as usize);
-    }
+    static REGISTERED: Once = Once::new();
+
+    REGISTERED.call_once(|| {
+        let Ok((sender, mut receiver)) = UnixStream::pair() else {
+            return;
+        };
+        SIGNAL_SOCKET.store(sender.into_raw_fd(), Ordering::Relaxed);
+        thread::spawn(move || {
+            let mut byte = [0];
+            loop {
+                match receiver.read(&mut byte) {
+                    Ok(1) => clean_up(libc::c_int::from(byte[0])),
+                    Err(err) if err.kind() == ErrorKind::Interrupted => continue,
+                    _ => return,
+                }
+            }
+        });
+
+        for signal_code in SIGNALS {
+            unsafe {
+                signal(signal_code, handle_signals as usize);
+            }
+        }
+    });
 }
@@ -20,22 +20,32 @@ use crate::{
 pub enum SpecialTarget {
     Default,
     Ignore,
+    NotParallel,
     Posix,
     Phony,
     Precious,
     SccsGet,
     Silent,
     Suffixes,
+    Wait,
 }
-use crate::config::Config;
 use gettextrs::gettext;
 use SpecialTarget::*;
 
 impl SpecialTarget {
     // could be automated with `strum`
-    pub const COUNT: usize = 8;
+    pub const COUNT: usize = 10;
     pub const VARIANTS: [Self; Self::COUNT] = [
-        Default, Ignore, Posix, Precious, SccsGet, Silent, Suffixes, Phony,
+        Default,
+        Ignore,
+        NotParallel,
+        Posix,
+        Precious,
+        SccsGet,
+        Silent,
+        Suffixes,
+        Phony,
+        Wait,
     ];
 }
 
@@ -44,12 +54,14 @@ impl AsRef<str> for SpecialTarget {
         match self {
             Default => ".DEFAULT",
             Ignore => ".IGNORE",
+            NotParallel => ".NOTPARALLEL",
             Posix => ".POSIX",
             Precious => ".PRECIOUS",
             SccsGet => ".SCCS_GET",
             Silent => ".SILENT",
             Suffixes => ".SUFFIXES",
             Phony => ".PHONY",
+            Wait => ".WAIT",
         }
     }
 }
@@ -60,28 +72,10 @@ impl fmt::Display for SpecialTarget {
     }
 }
 
-#[derive(Debug)]
-pub struct InferenceTarget {
-    from: String,
-    to: Option<String>,
-}
-
-impl InferenceTarget {
-    pub fn from(&self) -> &str {
-        self.from.as_ref()
-    }
-
-    pub fn to(&self) -> Option<&str> {
-        self.to.as_deref()
-    }
-}
-
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Error {
     MustNotHavePrerequisites,
     MustNotHaveRecipes,
-
-    NotSupported(SpecialTarget),
 }
 
 impl fmt::Display for Error {
@@ -99,14 +93,6 @@ impl fmt::Display for Error {
             MustNotHaveRecipes => {
                 write!(f, "{}", gettext("the special target must not have recipes"))
             }
-            NotSupported(target) => {
-                write!(
-                    f,
-                    "{}: '{}'",
-                    gettext("the special target is not supported"),
-                    target,
-                )
-            }
         }
     }
 }
@@ -134,30 +120,6 @@ impl TryFrom<Target> for SpecialTarget {
     }
 }
 
-impl TryFrom<(Target, Config)> for InferenceTarget {
-    type Error = ParseError;
-
-    fn try_from((target, config): (Target, Config)) -> Result<Self, Self::Error> {
-        let map = &BTreeSet::new();
-        let suffixes = config.rules.get(".SUFFIXES").unwrap_or(map);
-        let source = target.to_string();
-
-        let from = suffixes
-            .iter()
-            .filter_map(|x| source.strip_prefix(x))
-            .next()
-            .ok_or(ParseError)?
-            .to_string();
-        let to = suffixes
-            .iter()
-            .filter_map(|x| source.strip_prefix(x))
-            .next()
-            .map(|x| x.to_string());
-
-        Ok(Self { from, to })
-    }
-}
-
 pub struct Processor<'make> {
     rule: Rule,
     make: &'make mut Make,
@@ -178,12 +140,14 @@ pub fn process(rule: Rule, make: &mut Make) -> Result<(), ErrorCode> {
     match target {
         Default => this.process_default(),
         Ignore => this.process_ignore(),
+        NotParallel => this.process_not_parallel(),
+        Posix => this.process_posix(),
         Silent => this.process_silent(),
         Suffixes => this.process_suffixes(),
         Phony => this.process_phony(),
         Precious => this.process_precious(),
         SccsGet => this.process_sccs_get(),
-        unsupported => Err(Error::NotSupported(unsupported)),
+        Wait => this.process_wait(),
     }
     .map_err(|err| ErrorCode::SpecialTargetConstraintNotFulfilled {
         target: target.to_string(),
@@ -251,6 +215,21 @@ impl Processor<'_> {
         Ok(())
     }
 
+    fn process_not_parallel(self) -> Result<(), Error> {
+        self.without_recipes()?;
+
+        self.make.config.not_parallel = true;
+
+        Ok(())
+    }
+
+    /// The built-in rules and macros already are the ones POSIX specifies,
+    /// so `.POSIX` only has to be well-formed.
+    fn process_posix(self) -> Result<(), Error> {
+        self.without_prerequisites()?;
+        self.without_recipes()
+    }
+
     fn process_silent(mut self) -> Result<(), Error> {
         self.without_recipes()?;
 
@@ -261,18 +240,27 @@ impl Processor<'_> {
         Ok(())
     }
 
+    /// Without prerequisites, `.SUFFIXES` clears the suffixes; with them,
+    /// it adds them, in order.
     fn process_suffixes(self) -> Result<(), Error> {
         let suffixes_key = Suffixes.as_ref();
         let suffixes_set = self
-            .rule
-            .prerequisites()
-            .map(|suffix| suffix.as_ref().to_string())
-            .collect::<BTreeSet<String>>();
-
-        self.make
+            .make
             .config
             .rules
-            .insert(suffixes_key.to_string(), suffixes_set);
+            .entry(suffixes_key.to_string())
+            .or_default();
+
+        if self.rule.prerequisites().next().is_none() {
+            suffixes_set.clear();
+            self.make.suffixes.clear();
+        }
+        for suffix in self.rule.prerequisites() {
+            suffixes_set.insert(suffix.as_ref().to_string());
+            if !self.make.suffixes.iter().any(|s| s == suffix.as_ref()) {
+                self.make.suffixes.push(suffix.as_ref().to_string());
+            }
+        }
 
         Ok(())
     }
@@ -329,4 +317,10 @@ impl Processor<'_> {
 
         Ok(())
     }
+
+    /// `.WAIT` only has a meaning as a prerequisite.
+    fn process_wait(self) -> Result<(), Error> {
+        self.without_prerequisites()?;
+        self.without_recipes()
+    }
 }
@@ -9,7 +9,10 @@
 
 use std::env;
 use std::fs::{remove_file, File};
-use std::io::Write;
+use std::io::{Read, Write};
+use std::os::fd::OwnedFd;
+use std::os::unix::net::UnixStream;
+use std::path::PathBuf;
 use std::process::{Child, Command, Stdio};
 
 use plib::testing::{run_test, run_test_base, TestPlan};
@@ -95,7 +98,7 @@ fn run_test_helper_with_setup_and_destruct(
     destruct();
 }
 
-fn manual_test_helper(args: &[&str]) -> Child {
+fn make_binary() -> PathBuf {
     // Determine the binary path based on the build profile
     let relpath = if cfg!(debug_assertions) {
         format!("target/debug/{}", "make")
@@ -104,14 +107,16 @@ fn manual_test_helper(args: &[&str]) -> Child {
     };
 
     // Build the full path to the binary
-    let test_bin_path = env::current_dir()
+    env::current_dir()
         .expect("failed to get current directory")
         .parent()
         .expect("failed to get parent directory")
-        .join(relpath);
+        .join(relpath)
+}
 
+fn manual_test_helper(args: &[&str]) -> Child {
     // Create and spawn the command
-    Command::new(test_bin_path)
+    Command::new(make_binary())
         .args(args)
         .stdin(Stdio::piped())
         .stdout(Stdio::piped())
@@ -121,6 +126,7 @@ fn manual_test_helper(args: &[&str]) -> Child {
 }
 
 mod arguments {
+    use std::time::{Duration, SystemTime};
 
     use super::*;
 
@@ -144,6 +150,72 @@ mod arguments {
         )
     }
 
+    #[test]
+    fn dash_d() {
+        set_dash_d_times();
+        run_test_helper(
+            &["-dC", "tests/makefiles/arguments/dash_d"],
+            "making target\nmaking missing\ndefault for unknown\n",
+            "make: source: no rule, the file exists (1073741825.000000000)\n\
+             make: target: prerequisite 'source' is newer (1073741825.000000000 > 1073741824.000000000)\n\
+             make: fresh: target is up to date (1073741826.000000000)\n\
+             make: missing: target does not exist\n\
+             make: unknown: no rule, using '.DEFAULT'\n\
+             make: unknown: target does not exist\n\
+             make: all: target is phony\n\
+             make: all: target does not exist\n\
+             make: all: prerequisite 'target' is made\n\
+             make: all: prerequisite 'missing' is made\n\
+             make: all: prerequisite 'unknown' is made\n",
+            0,
+        )
+    }
+
+    #[test]
+    fn dot() {
+        set_dash_d_times();
+        run_test_helper(
+            &["--dot", "-C", "tests/makefiles/arguments/dash_d"],
+            "digraph \"all\" {\n    \
+             \"source\";\n    \
+             \"target\" [color=red];\n    \
+             \"target\" -> \"source\";\n    \
+             \"fresh\";\n    \
+             \"fresh\" -> \"source\";\n    \
+             \"missing\" [color=red];\n    \
+             \"missing\" -> \"target\" [style=dashed];\n    \
+             \"missing\" -> \"fresh\" [style=dashed];\n    \
+             \"unknown\" [color=red];\n    \
+             \"unknown\" -> \"target\" [style=dashed];\n    \
+             \"unknown\" -> \"fresh\" [style=dashed];\n    \
+             \"all\" [color=red];\n    \
+             \"all\" -> \"target\";\n    \
+             \"all\" -> \"fresh\";\n    \
+             \"all\" -> \"missing\";\n    \
+             \"all\" -> \"unknown\";\n\
+             }\n",
+            "",
+            0,
+        )
+    }
+
+    /// Makes `source` newer than `target` and older than `fresh`, as git
+    /// does not keep modification times.
+    fn set_dash_d_times() {
+        for (file, seconds) in [
+            ("target", 1 << 30),
+            ("source", (1 << 30) + 1),
+            ("fresh", (1 << 30) + 2),
+        ] {
+            File::options()
+                .write(true)
+                .open(format!("tests/makefiles/arguments/dash_d/{file}"))
+                .unwrap()
+                .set_modified(SystemTime::UNIX_EPOCH + Duration::from_secs(seconds))
+                .unwrap();
+        }
+    }
+
     #[test]
     fn dash_f() {
         run_test_helper(
@@ -158,7 +230,7 @@ mod arguments {
     fn dash_p() {
         run_test_helper(
             &["-p"],
-            "{\".MACROS\": {\"AR=ar\", \"ARFLAGS=-rv\", \"CC=c17\", \"CFLAGS=-O 1\", \"GFLAGS=\", \"LDFLAGS=\", \"LEX=lex\", \"LFLAGS=\", \"SCCSFLAGS=\", \"SCCSGETFLAGS=-s\", \"XSI GET=get\", \"YACC=yacc\", \"YFLAGS=\"}, \".SCCS_GET\": {\"sccs $(SCCSFLAGS) get $(SCCSGETFLAGS) $@\"}, \".SUFFIXES\": {\".a\", \".c\", \".c~\", \".l\", \".l~\", \".o\", \".sh\", \".sh~\", \".y\", \".y~\"}, \"SUFFIX RULES\": {\".c.a: $(CC) -c $(CFLAGS) $<; $(AR) $(ARFLAGS) $@ $*.o; rm -f $*.o\", \".c.o: $(CC) $(CFLAGS) -c $<\", \".c: $(CC) $(CFLAGS) $(LDFLAGS) -o $@ $<\", \".l.c: $(LEX) $(LFLAGS) $<; mv lex.yy.c $@\", \".l.o: $(LEX) $(LFLAGS) $<; $(CC) $(CFLAGS) -c lex.yy.c; rm -f lex.yy.c; mv lex.yy.o $@\", \".l~.c: $(GET) $(GFLAGS) -p $< > $*.l; $(LEX) $(LFLAGS) $*.l; mv lex.yy.c $@\", \".l~.o: $(GET) $(GFLAGS) -p $< > $*.l; $(LEX) $(LFLAGS) $*.l; $(CC) $(CFLAGS) -c lex.yy.c; rm -f lex.yy.c; mv lex.yy.o $@\", \".sh: chmod a+x $@\", \".sh: cp $< $@\", \".y.c: $(YACC) $(YFLAGS) $<; mv y.tab.c $@\", \".y.o: $(YACC) $(YFLAGS) $<; $(CC) $(CFLAGS) -c y.tab.c; rm -f y.tab.c; mv y.tab.o $@\", \".y~.c: $(GET) $(GFLAGS) -p $< > $*.y; $(YACC) $(YFLAGS) $*.y; mv y.tab.c $@\", \".y~.o: $(GET) $(GFLAGS) -p $< > $*.y; $(YACC) $(YFLAGS) $*.y; $(CC) $(CFLAGS) -c y.tab.c; rm -f y.tab.c; mv y.tab.o $@\", \"XSI .c~.o: $(GET) $(GFLAGS) -p $< > $*.c; $(CC) $(CFLAGS) -c $*.c\"}}",
+            "{\".MACROS\": {\"AR=ar\", \"ARFLAGS=-rv\", \"CC=c17\", \"CFLAGS=-O 1\", \"GFLAGS=\", \"LDFLAGS=\", \"LEX=lex\", \"LFLAGS=\", \"SCCSFLAGS=\", \"SCCSGETFLAGS=-s\", \"XSI GET=get\", \"YACC=yacc\", \"YFLAGS=\"}, \".SCCS_GET\": {\"sccs $(SCCSFLAGS) get $(SCCSGETFLAGS) $@\"}, \".SUFFIXES\": {\".a\", \".c\", \".c~\", \".l\", \".l~\", \".o\", \".sh\", \".sh~\", \".y\", \".y~\"}, \"SUFFIX RULES\": {\".c.a: $(CC) -c $(CFLAGS) $<; $(AR) $(ARFLAGS) $@ $*.o; rm -f $*.o\", \".c.o: $(CC) $(CFLAGS) -c $<\", \".c: $(CC) $(CFLAGS) $(LDFLAGS) -o $@ $<\", \".l.c: $(LEX) $(LFLAGS) $<; mv lex.yy.c $@\", \".l.o: $(LEX) $(LFLAGS) $<; $(CC) $(CFLAGS) -c lex.yy.c; rm -f lex.yy.c; mv lex.yy.o $@\", \".l~.c: $(GET) $(GFLAGS) -p $< > $*.l; $(LEX) $(LFLAGS) $*.l; mv lex.yy.c $@\", \".l~.o: $(GET) $(GFLAGS) -p $< > $*.l; $(LEX) $(LFLAGS) $*.l; $(CC) $(CFLAGS) -c lex.yy.c; rm -f lex.yy.c; mv lex.yy.o $@\", \".sh: cp $< $@; chmod a+x $@\", \".y.c: $(YACC) $(YFLAGS) $<; mv y.tab.c $@\", \".y.o: $(YACC) $(YFLAGS) $<; $(CC) $(CFLAGS) -c y.tab.c; rm -f y.tab.c; mv y.tab.o $@\", \".y~.c: $(GET) $(GFLAGS) -p $< > $*.y; $(YACC) $(YFLAGS) $*.y; mv y.tab.c $@\", \".y~.o: $(GET) $(GFLAGS) -p $< > $*.y; $(YACC) $(YFLAGS) $*.y; $(CC) $(CFLAGS) -c y.tab.c; rm -f y.tab.c; mv y.tab.o $@\", \"XSI .c~.o: $(GET) $(GFLAGS) -p $< > $*.c; $(CC) $(CFLAGS) -c $*.c\"}}",
             "",
             0,
         )
@@ -167,7 +239,7 @@ mod arguments {
     fn dash_p_with_mk() {
         run_test_helper(
             &["-pf", "tests/makefiles/arguments/dash_p/with_phony.mk"],
-            "{\".MACROS\": {\"AR=ar\", \"ARFLAGS=-rv\", \"CC=c17\", \"CFLAGS=-O 1\", \"GFLAGS=\", \"LDFLAGS=\", \"LEX=lex\", \"LFLAGS=\", \"SCCSFLAGS=\", \"SCCSGETFLAGS=-s\", \"XSI GET=get\", \"YACC=yacc\", \"YFLAGS=\"}, \".PHONY\": {\"clean\"}, \".SCCS_GET\": {\"sccs $(SCCSFLAGS) get $(SCCSGETFLAGS) $@\"}, \".SUFFIXES\": {\".a\", \".c\", \".c~\", \".l\", \".l~\", \".o\", \".sh\", \".sh~\", \".y\", \".y~\"}, \"SUFFIX RULES\": {\".c.a: $(CC) -c $(CFLAGS) $<; $(AR) $(ARFLAGS) $@ $*.o; rm -f $*.o\", \".c.o: $(CC) $(CFLAGS) -c $<\", \".c: $(CC) $(CFLAGS) $(LDFLAGS) -o $@ $<\", \".l.c: $(LEX) $(LFLAGS) $<; mv lex.yy.c $@\", \".l.o: $(LEX) $(LFLAGS) $<; $(CC) $(CFLAGS) -c lex.yy.c; rm -f lex.yy.c; mv lex.yy.o $@\", \".l~.c: $(GET) $(GFLAGS) -p $< > $*.l; $(LEX) $(LFLAGS) $*.l; mv lex.yy.c $@\", \".l~.o: $(GET) $(GFLAGS) -p $< > $*.l; $(LEX) $(LFLAGS) $*.l; $(CC) $(CFLAGS) -c lex.yy.c; rm -f lex.yy.c; mv lex.yy.o $@\", \".sh: chmod a+x $@\", \".sh: cp $< $@\", \".y.c: $(YACC) $(YFLAGS) $<; mv y.tab.c $@\", \".y.o: $(YACC) $(YFLAGS) $<; $(CC) $(CFLAGS) -c y.tab.c; rm -f y.tab.c; mv y.tab.o $@\", \"some\n.y~.c: $(GET) $(GFLAGS) -p $< > $*.y; $(YACC) $(YFLAGS) $*.y; mv y.tab.c $@\", \".y~.o: $(GET) $(GFLAGS) -p $< > $*.y; $(YACC) $(YFLAGS) $*.y; $(CC) $(CFLAGS) -c y.tab.c; rm -f y.tab.c; mv y.tab.o $@\", \"XSI .c~.o: $(GET) $(GFLAGS) -p $< > $*.c; $(CC) $(CFLAGS) -c $*.c\"}}",
+            "{\".MACROS\": {\"AR=ar\", \"ARFLAGS=-rv\", \"CC=c17\", \"CFLAGS=-O 1\", \"GFLAGS=\", \"LDFLAGS=\", \"LEX=lex\", \"LFLAGS=\", \"SCCSFLAGS=\", \"SCCSGETFLAGS=-s\", \"XSI GET=get\", \"YACC=yacc\", \"YFLAGS=\"}, \".PHONY\": {\"clean\"}, \".SCCS_GET\": {\"sccs $(SCCSFLAGS) get $(SCCSGETFLAGS) $@\"}, \".SUFFIXES\": {\".a\", \".c\", \".c~\", \".l\", \".l~\", \".o\", \".sh\", \".sh~\", \".y\", \".y~\"}, \"SUFFIX RULES\": {\".c.a: $(CC) -c $(CFLAGS) $<; $(AR) $(ARFLAGS) $@ $*.o; rm -f $*.o\", \".c.o: $(CC) $(CFLAGS) -c $<\", \".c: $(CC) $(CFLAGS) $(LDFLAGS) -o $@ $<\", \".l.c: $(LEX) $(LFLAGS) $<; mv lex.yy.c $@\", \".l.o: $(LEX) $(LFLAGS) $<; $(CC) $(CFLAGS) -c lex.yy.c; rm -f lex.yy.c; mv lex.yy.o $@\", \".l~.c: $(GET) $(GFLAGS) -p $< > $*.l; $(LEX) $(LFLAGS) $*.l; mv lex.yy.c $@\", \".l~.o: $(GET) $(GFLAGS) -p $< > $*.l; $(LEX) $(LFLAGS) $*.l; $(CC) $(CFLAGS) -c lex.yy.c; rm -f lex.yy.c; mv lex.yy.o $@\", \".sh: cp $< $@; chmod a+x $@\", \".y.c: $(YACC) $(YFLAGS) $<; mv y.tab.c $@\", \".y.o: $(YACC) $(YFLAGS) $<; $(CC) $(CFLAGS) -c y.tab.c; rm -f y.tab.c; mv y.tab.o $@\", \"some\n.y~.c: $(GET) $(GFLAGS) -p $< > $*.y; $(YACC) $(YFLAGS) $*.y; mv y.tab.c $@\", \".y~.o: $(GET) $(GFLAGS) -p $< > $*.y; $(YACC) $(YFLAGS) $*.y; $(CC) $(CFLAGS) -c y.tab.c; rm -f y.tab.c; mv y.tab.o $@\", \"XSI .c~.o: $(GET) $(GFLAGS) -p $< > $*.c; $(CC) $(CFLAGS) -c $*.c\"}}",
             "",
             0,
         )
@@ -267,6 +339,66 @@ mod arguments {
             2,
         );
     }
+
+    #[test]
+    fn dash_j() {
+        run_test_helper(
+            &[
+                "-j2",
+                "-f",
+                "tests/makefiles/arguments/dash_j/interleaving.mk",
+            ],
+            "fast1\nfast2\nslow1\nslow2\nall\n",
+            "",
+            0,
+        );
+    }
+
+    #[test]
+    fn dash_j_output_order() {
+        // stdout and stderr on the same file, as on a terminal
+        let (mut reader, writer) = UnixStream::pair().unwrap();
+        let mut child = Command::new(make_binary())
+            .args(["-j2", "-f", "tests/makefiles/arguments/dash_j/order.mk"])
+            .stdout(OwnedFd::from(writer.try_clone().unwrap()))
+            .stderr(OwnedFd::from(writer))
+            .spawn()
+            .unwrap();
+
+        let mut output = String::new();
+        reader.read_to_string(&mut output).unwrap();
+        assert_eq!(output, "out1\nerr1\nout2\nother\n");
+        assert!(child.wait().unwrap().success());
+    }
+
+    #[test]
+    fn dash_j_background_process() {
+        // the process left running keeps its output open, which is not
+        // waited for
+        let start = SystemTime::now();
+        run_test_helper(
+            &[
+                "-j2",
+                "-f",
+                "tests/makefiles/arguments/dash_j/background.mk",
+            ],
+            "background\nother\n",
+            "",
+            0,
+        );
+        assert!(start.elapsed().unwrap() < Duration::from_secs(2));
+    }
+
+    #[test]
+    fn dash_j_stdin() {
+        run_test_with_stdin_helper(
+            &["-j2", "-f", "tests/makefiles/arguments/dash_j/stdin.mk"],
+            "line\n",
+            "read line\n",
+            "",
+            0,
+        );
+    }
 }
 
 // such tests should be moved directly to the package responsible for parsing makefiles
@@ -279,7 +411,54 @@ mod parsing {
         run_test_helper(
             &["-f", "tests/makefiles/parsing/empty.mk"],
             "",
-            "make: parse error:  *** No targets. Stop.\n\n",
+            "make: parse error: tests/makefiles/parsing/empty.mk:  *** No targets. Stop.\n",
+            4,
+        );
+    }
+
+    #[test]
+    fn posix_2024() {
+        run_test_helper(
+            &["-C", "tests/makefiles/parsing/posix_2024"],
+            "one two two set first second a b one two\n",
+            "",
+            0,
+        );
+        run_test_helper(
+            &["-C", "tests/makefiles/parsing/posix_2024", "second"],
+            "included\n",
+            "",
+            0,
+        );
+    }
+
+    #[test]
+    fn missing_separator() {
+        run_test_helper(
+            &["-f", "tests/makefiles/parsing/missing_separator.mk"],
+            "",
+            "make: parse error: tests/makefiles/parsing/missing_separator.mk: line 4: missing separator\n",
+            4,
+        );
+    }
+
+    #[test]
+    fn several_errors() {
+        run_test_helper(
+            &["-f", "tests/makefiles/parsing/several_errors.mk"],
+            "",
+            "make: parse error: tests/makefiles/parsing/several_errors.mk: line 1: missing separator\n\
+             make: parse error: tests/makefiles/parsing/several_errors.mk: line 4: missing separator\n",
+            4,
+        );
+    }
+
+    #[test]
+    fn missing_include() {
+        run_test_helper(
+            &["-f", "tests/makefiles/parsing/missing_include.mk"],
+            "",
+            "make: parse error: tests/makefiles/parsing/missing_include.mk: line 2: cannot include 'missing.mk': No such file or directory (os error 2)\n",
             4,
         );
     }
@@ -367,20 +546,59 @@ mod macros {
             env::remove_var("MACRO");
         }
     }
+
+    #[test]
+    fn internal_macros() {
+        run_test_helper(
+            &["-f", "tests/makefiles/macros/internal_macros.mk"],
+            "made target.dep\n\
+             out/dir/target out/dir target target\n\
+             target.dep . target.dep\n\
+             [] []\n\
+             a.c dir/b.c a.o c.o\n\
+             $x\n",
+            "",
+            0,
+        );
+    }
+
+    #[test]
+    fn recursive_macro() {
+        run_test_helper(
+            &["-f", "tests/makefiles/macros/recursive_macro.mk"],
+            "",
+            "make: parse error: tests/makefiles/macros/recursive_macro.mk: line 3: recursive macro expansion\n",
+            4,
+        );
+    }
+
+    #[test]
+    fn recursive_make() {
+        const DIR: &str = "tests/makefiles/macros/recursive";
+
+        run_test_helper(&["-C", DIR], "sub\n", "", 0);
+        run_test_helper(&["-C", DIR, "-s"], "sub s\n", "", 0);
+        run_test_helper(&["-C", DIR, "-k"], "sub k\n", "", 0);
+        run_test_helper(&["-C", DIR, "-n"], "echo sub n\n", "", 0);
+    }
 }
 
 mod target_behavior {
     use super::*;
-    use libc::{kill, SIGINT};
+    use libc::{kill, killpg, SIGINT};
     use posixutils_make::parser::parse::ParseError;
-    use std::{thread, time::Duration};
+    use std::os::unix::process::CommandExt;
+    use std::{
+        fs, thread,
+        time::{Duration, SystemTime},
+    };
 
     #[test]
     fn no_targets() {
         run_test_helper(
             &["-f", "tests/makefiles/target_behavior/no_targets.mk"],
             "",
-            "make: parse error:  *** No targets. Stop.\n\n",
+            "make: parse error: tests/makefiles/target_behavior/no_targets.mk:  *** No targets. Stop.\n",
             ErrorCode::ParserError {
                 constraint: ParseError(vec![]),
             }
@@ -459,6 +677,63 @@ mod target_behavior {
         );
     }
 
+    #[test]
+    fn file_prerequisite() {
+        run_test_helper(
+            &["-C", "tests/makefiles/target_behavior/file_prerequisite"],
+            "built from input.txt\n",
+            "",
+            0,
+        );
+    }
+
+    #[test]
+    fn inference_rules() {
+        run_test_helper(
+            &["-C", "tests/makefiles/target_behavior/inference"],
+            "chain.in -> chain.mid (chain), newer: chain.in\n\
+             chain.mid -> chain.out (chain)\n\
+             single.in -> single\n",
+            "",
+            0,
+        );
+    }
+
+    #[test]
+    fn archive_member() {
+        const DIR: &str = "tests/makefiles/target_behavior/archive_member";
+
+        let _ = remove_file(format!("{DIR}/lib.a"));
+        File::options()
+            .write(true)
+            .open(format!("{DIR}/member.txt"))
+            .unwrap()
+            .set_modified(SystemTime::UNIX_EPOCH + Duration::from_secs(1 << 30))
+            .unwrap();
+
+        run_test_helper(&["-C", DIR], "member.o of lib.a from member.txt\n", "", 0);
+        run_test_helper(&["-C", DIR], "make: `lib.a` is up to date.\n", "", 0);
+        remove_file(format!("{DIR}/lib.a")).unwrap();
+    }
+
+    #[test]
+    fn archive_member_prerequisite() {
+        const DIR: &str = "tests/makefiles/target_behavior/archive_prerequisite";
+
+        let _ = remove_file(format!("{DIR}/lib.a"));
+        // the archive keeps only the whole seconds of this time
+        File::options()
+            .write(true)
+            .open(format!("{DIR}/mem.o"))
+            .unwrap()
+            .set_modified(SystemTime::UNIX_EPOCH + Duration::new(1 << 30, 500_000_000))
+            .unwrap();
+
+        run_test_helper(&["-C", DIR], "mem.o of lib.a from mem.o\n", "", 0);
+        run_test_helper(&["-C", DIR], "make: `lib.a` is up to date.\n", "", 0);
+        remove_file(format!("{DIR}/lib.a")).unwrap();
+    }
+
     #[test]
     fn async_events() {
         let args = [
@@ -485,6 +760,72 @@ mod target_behavior {
 
         assert_eq!(output.status.code(), Some(130));
     }
+
+    #[test]
+    fn async_events_in_parallel() {
+        let args = [
+            "-j2",
+            "-f",
+            "tests/makefiles/target_behavior/async_events/parallel_signal.mk",
+        ];
+        let child = manual_test_helper(&args);
+        let pid = child.id() as i32;
+
+        thread::spawn(move || {
+            thread::sleep(Duration::from_millis(500));
+            unsafe {
+                kill(pid, SIGINT);
+            }
+        });
+
+        let output = child.wait_with_output().expect("failed to wait for child");
+
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert_eq!(
+            stderr,
+            "make: Interrupt\nmake: Deleting file 'parallel_one'\nmake: Deleting file 'parallel_two'\n"
+        );
+        assert!(!fs::exists("parallel_one").unwrap());
+        assert!(!fs::exists("parallel_two").unwrap());
+
+        assert_eq!(output.status.code(), Some(130));
+    }
+
+    #[test]
+    fn async_events_to_process_group_in_parallel() {
+        // the jobs' commands die of the signal too, and their jobs end as
+        // make handles it
+        for _ in 0..5 {
+            let child = Command::new(make_binary())
+                .args([
+                    "-j2",
+                    "-f",
+                    "tests/makefiles/target_behavior/async_events/group_signal.mk",
+                ])
+                .stdout(Stdio::piped())
+                .stderr(Stdio::piped())
+                .process_group(0)
+                .spawn()
+                .expect("failed to spawn command");
+            let pid = child.id() as i32;
+
+            thread::spawn(move || {
+                thread::sleep(Duration::from_millis(500));
+                unsafe {
+                    killpg(pid, SIGINT);
+                }
+            });
+
+            let output = child.wait_with_output().expect("failed to wait for child");
+
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            assert!(stderr.starts_with("make: Interrupt\n"), "{stderr}");
+            assert!(!fs::exists("group_one").unwrap());
+            assert!(!fs::exists("group_two").unwrap());
+
+            assert_eq!(output.status.code(), Some(130));
+        }
+    }
 }
 
 mod recipes {
@@ -624,11 +965,35 @@ mod special_targets {
         );
     }
 
+    #[test]
+    fn not_parallel() {
+        run_test_helper(
+            &[
+                "-j2",
+                "-f",
+                "tests/makefiles/special_targets/not_parallel.mk",
+            ],
+            "slow1\nslow2\nfast1\nfast2\nall\n",
+            "",
+            0,
+        );
+    }
+
+    #[test]
+    fn wait() {
+        run_test_helper(
+            &["-j2", "-f", "tests/makefiles/special_targets/wait.mk"],
+            "fast\nslow\nall\n",
+            "",
+            0,
+        );
+    }
+
     #[test]
     fn sccs_get() {
         run_test_helper(
             &["-pf", "tests/makefiles/special_targets/sccs/basic_sccs.mk"],
-            "{\".MACROS\": {\"AR=ar\", \"ARFLAGS=-rv\", \"CC=c17\", \"CFLAGS=-O 1\", \"GFLAGS=\", \"LDFLAGS=\", \"LEX=lex\", \"LFLAGS=\", \"SCCSFLAGS=\", \"SCCSGETFLAGS=-s\", \"XSI GET=get\", \"YACC=yacc\", \"YFLAGS=\"}, \".SCCS_GET\": {\"echo \\\"executing command\\\"\"}, \".SUFFIXES\": {\".a\", \".c\", \".c~\", \".l\", \".l~\", \".o\", \".sh\", \".sh~\", \".y\", \".y~\"}, \"SUFFIX RULES\": {\".c.a: $(CC) -c $(CFLAGS) $<; $(AR) $(ARFLAGS) $@ $*.o; rm -f $*.o\", \".c.o: $(CC) $(CFLAGS) -c $<\", \".c: $(CC) $(CFLAGS) $(LDFLAGS) -o $@ $<\", \".l.c: $(LEX) $(LFLAGS) $<; mv lex.yy.c $@\", \".l.o: $(LEX) $(LFLAGS) $<; $(CC) $(CFLAGS) -c lex.yy.c; rm -f lex.yy.c; mv lex.yy.o $@\", \".l~.c: $(GET) $(GFLAGS) -p $< > $*.l; $(LEX) $(LFLAGS) $*.l; mv lex.yy.c $@\", \".l~.o: $(GET) $(GFLAGS) -p $< > $*.l; $(LEX) $(LFLAGS) $*.l; $(CC) $(CFLAGS) -c lex.yy.c; rm -f lex.yy.c; mv lex.yy.o $@\", \".sh: chmod a+x $@\", \".sh: cp $< $@\", \".y.c: $(YACC) $(YFLAGS) $<; mv y.tab.c $@\", \".y.o: $(YACC) $(YFLAGS) $<; $(CC) $(CFLAGS) -c y.tab.c; rm -f y.tab.c; mv y.tab.o $@\", \"something\n.y~.c: $(GET) $(GFLAGS) -p $< > $*.y; $(YACC) $(YFLAGS) $*.y; mv y.tab.c $@\", \".y~.o: $(GET) $(GFLAGS) -p $< > $*.y; $(YACC) $(YFLAGS) $*.y; $(CC) $(CFLAGS) -c y.tab.c; rm -f y.tab.c; mv y.tab.o $@\", \"XSI .c~.o: $(GET) $(GFLAGS) -p $< > $*.c; $(CC) $(CFLAGS) -c $*.c\"}}",
+            "{\".MACROS\": {\"AR=ar\", \"ARFLAGS=-rv\", \"CC=c17\", \"CFLAGS=-O 1\", \"GFLAGS=\", \"LDFLAGS=\", \"LEX=lex\", \"LFLAGS=\", \"SCCSFLAGS=\", \"SCCSGETFLAGS=-s\", \"XSI GET=get\", \"YACC=yacc\", \"YFLAGS=\"}, \".SCCS_GET\": {\"echo \\\"executing command\\\"\"}, \".SUFFIXES\": {\".a\", \".c\", \".c~\", \".l\", \".l~\", \".o\", \".sh\", \".sh~\", \".y\", \".y~\"}, \"SUFFIX RULES\": {\".c.a: $(CC) -c $(CFLAGS) $<; $(AR) $(ARFLAGS) $@ $*.o; rm -f $*.o\", \".c.o: $(CC) $(CFLAGS) -c $<\", \".c: $(CC) $(CFLAGS) $(LDFLAGS) -o $@ $<\", \".l.c: $(LEX) $(LFLAGS) $<; mv lex.yy.c $@\", \".l.o: $(LEX) $(LFLAGS) $<; $(CC) $(CFLAGS) -c lex.yy.c; rm -f lex.yy.c; mv lex.yy.o $@\", \".l~.c: $(GET) $(GFLAGS) -p $< > $*.l; $(LEX) $(LFLAGS) $*.l; mv lex.yy.c $@\", \".l~.o: $(GET) $(GFLAGS) -p $< > $*.l; $(LEX) $(LFLAGS) $*.l; $(CC) $(CFLAGS) -c lex.yy.c; rm -f lex.yy.c; mv lex.yy.o $@\", \".sh: cp $< $@; chmod a+x $@\", \".y.c: $(YACC) $(YFLAGS) $<; mv y.tab.c $@\", \".y.o: $(YACC) $(YFLAGS) $<; $(CC) $(CFLAGS) -c y.tab.c; rm -f y.tab.c; mv y.tab.o $@\", \"something\n.y~.c: $(GET) $(GFLAGS) -p $< > $*.y; $(YACC) $(YFLAGS) $*.y; mv y.tab.c $@\", \".y~.o: $(GET) $(GFLAGS) -p $< > $*.y; $(YACC) $(YFLAGS) $*.y; $(CC) $(CFLAGS) -c y.tab.c; rm -f y.tab.c; mv y.tab.o $@\", \"XSI .c~.o: $(GET) $(GFLAGS) -p $< > $*.c; $(CC) $(CFLAGS) -c $*.c\"}}",
             "",
             0,
         );
@@ -674,6 +1039,7 @@ mod special_targets {
             &[
                 "-f",
                 "tests/makefiles/special_targets/suffixes/suffixes_basic.mk",
+                "copied.out",
             ],
             "Converting copied.txt to copied.out\n",
             "",
@@ -0,0 +1,8 @@
+all: background other
+
+background:
+	@sleep 3 &
+	@echo background
+
+other:
+	@sleep 0.5; echo other
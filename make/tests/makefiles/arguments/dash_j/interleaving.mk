@@ -0,0 +1,8 @@
+all: slow fast
+	@echo all
+
+slow:
+	@echo slow1; sleep 1; echo slow2
+
+fast:
+	@echo fast1; sleep 0.2; echo fast2
@@ -0,0 +1,7 @@
+all: mixed other
+
+mixed:
+	@echo out1; echo err1 >&2; echo out2
+
+other:
+	@sleep 0.2; echo other
@@ -0,0 +1,7 @@
+all: read other
+
+read:
+	@read line; echo "read $$line"
+
+other:
+	@sleep 0.2
@@ -0,0 +1,14 @@
+OBJS = a.o dir/b.o
+SRCS = $(OBJS:.o=.c)
+
+all: out/dir/target
+
+out/dir/target: $$(@F).dep
+	@echo "$@ $(@D) $(@F) ${@F}"
+	@echo "$? $(?D) $(?F)"
+	@echo "[$(%D)] [$(%F)]"
+	@echo "$(SRCS) ${OBJS:dir/b.o=c.o}"
+	@echo '$$x'
+
+target.dep:
+	@echo "made $@"
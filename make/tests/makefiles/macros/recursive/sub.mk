@@ -0,0 +1,2 @@
+sub:
+	@echo sub $(MAKEFLAGS)
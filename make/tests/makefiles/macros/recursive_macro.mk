@@ -0,0 +1,3 @@
+X = a $(X)
+all:
+	@echo $(X)
@@ -0,0 +1,5 @@
+
+include missing.mk
+
+all:
+	@echo all
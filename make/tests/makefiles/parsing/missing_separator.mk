@@ -0,0 +1,4 @@
+all:
+	@echo all
+
+this line is wrong
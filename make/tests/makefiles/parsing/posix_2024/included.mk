@@ -0,0 +1 @@
+MESSAGE = included
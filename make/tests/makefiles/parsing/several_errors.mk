@@ -0,0 +1,4 @@
+this is wrong
+all:
+	@echo all
+so is this
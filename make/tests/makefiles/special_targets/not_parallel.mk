@@ -0,0 +1,10 @@
+.NOTPARALLEL:
+
+all: slow fast
+	@echo all
+
+slow:
+	@echo slow1; sleep 0.5; echo slow2
+
+fast:
+	@echo fast1; echo fast2
@@ -0,0 +1,8 @@
+all: fast .WAIT slow
+	@echo all
+
+slow:
+	@echo slow
+
+fast:
+	@sleep 0.5; echo fast
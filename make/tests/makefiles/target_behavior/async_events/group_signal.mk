@@ -0,0 +1,9 @@
+all: group_one group_two
+
+group_one:
+	touch group_one
+	sleep 3
+
+group_two:
+	touch group_two
+	sleep 3
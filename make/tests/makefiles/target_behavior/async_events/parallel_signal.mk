@@ -0,0 +1,9 @@
+all: parallel_one parallel_two
+
+parallel_one:
+	touch parallel_one
+	sleep 2
+
+parallel_two:
+	touch parallel_two
+	sleep 2
@@ -164,6 +164,26 @@ rule: prerequisite
         );
     }
 
+    #[test]
+    fn test_inline_recipe() {
+        assert_eq!(
+            lex("dir/rule: prerequisite; recipe # not a comment\n")
+                .iter()
+                .map(|(kind, text)| (*kind, text.as_str()))
+                .collect::<Vec<_>>(),
+            vec![
+                (IDENTIFIER, "dir/rule"),
+                (COLON, ":"),
+                (WHITESPACE, " "),
+                (IDENTIFIER, "prerequisite"),
+                (SEMICOLON, ";"),
+                (WHITESPACE, " "),
+                (TEXT, "recipe # not a comment"),
+                (NEWLINE, "\n"),
+            ]
+        );
+    }
+
     #[test]
     fn test_variable_question() {
         assert_eq!(
@@ -256,8 +276,11 @@ endif
 }
 
 mod parse {
-    use posixutils_make::parser::preprocessor::preprocess;
-    use posixutils_make::parser::{parse::parse, Makefile};
+    use posixutils_make::parser::preprocessor::{preprocess, preprocess_with_locations};
+    use posixutils_make::parser::{
+        parse::{parse, parse_with_locations, ParseError},
+        Makefile,
+    };
     use rowan::ast::AstNode;
 
     #[test]
@@ -393,6 +416,46 @@ rule: dependency
         assert_eq!(rule.recipes().collect::<Vec<_>>(), vec!["command"]);
     }
 
+    #[test]
+    fn test_parse_multiple_targets() {
+        let parsed = parse("rule1 rule2: dependency; command1\n\tcommand2\n");
+        assert_eq!(parsed.clone().err(), None);
+        let root = parsed.unwrap().root();
+
+        let rule = root.rules().next().unwrap();
+        assert_eq!(rule.targets().collect::<Vec<_>>(), vec!["rule1", "rule2"]);
+        assert_eq!(rule.prerequisites().collect::<Vec<_>>(), vec!["dependency"]);
+        assert_eq!(
+            rule.recipes().collect::<Vec<_>>(),
+            vec!["command1", "command2"]
+        );
+    }
+
+    #[test]
+    fn test_parse_error_line() {
+        let (processed, locations) =
+            preprocess_with_locations("VARIABLE = value\n\nrule:\n\tcommand\nnot a rule\n")
+                .unwrap();
+        let err = parse_with_locations(&processed, &locations).err().unwrap();
+        assert_eq!(
+            err,
+            ParseError(vec!["line 5: missing separator".to_string()])
+        );
+        assert_eq!(err.to_string(), "line 5: missing separator");
+    }
+
+    #[test]
+    fn test_parse_error_in_file() {
+        let err = ParseError(vec![
+            "line 1: missing separator".to_string(),
+            "line 4: missing separator".to_string(),
+        ]);
+        assert_eq!(
+            err.in_file(std::path::Path::new("Makefile")).to_string(),
+            "Makefile: line 1: missing separator\nMakefile: line 4: missing separator"
+        );
+    }
+
     #[test]
     fn test_add_rule() {
         let mut makefile = Makefile::new();
@@ -51,3 +51,32 @@ pub fn tty() -> String {
 
     panic!("Failed to get tty name from any file descriptor");
 }
+
+/// The login name of the real user ID, from the password database, or the
+/// numeric user ID if it has no entry.
+pub fn real_user_name() -> String {
+    unsafe {
+        let uid = libc::getuid();
+        let pw = libc::getpwuid(uid);
+        if pw.is_null() || (*pw).pw_name.is_null() {
+            return uid.to_string();
+        }
+        CStr::from_ptr((*pw).pw_name).to_string_lossy().into_owned()
+    }
+}
+
+/// The real group ID and the supplementary group IDs of the process.
+pub fn group_ids() -> Vec<u32> {
+    let gid = unsafe { libc::getgid() };
+    let mut gids = vec![gid];
+    let n = unsafe { libc::getgroups(0, std::ptr::null_mut()) };
+    if n > 0 {
+        let mut groups = vec![0 as libc::gid_t; n as usize];
+        let n = unsafe { libc::getgroups(n, groups.as_mut_ptr()) };
+        if n > 0 {
+            groups.truncate(n as usize);
+            gids.extend(groups.into_iter().filter(|&g| g != gid));
+        }
+    }
+    gids
+}
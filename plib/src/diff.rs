@@ -0,0 +1,166 @@
+//
+// Copyright (c) 2024 Hemi Labs, Inc.
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+//! Line-based differencing shared by `diff` and the SCCS utilities.
+
+use std::collections::HashMap;
+use std::hash::Hash;
+
+/// Longest common subsequence of `lines1` and `lines2`, computed by
+/// recursively building a histogram.  The result has one entry per line of
+/// `lines1`: the index of the matching line of `lines2`, or -1 if the line
+/// is not part of the LCS.  Matched indices are strictly increasing.
+pub fn histogram_lcs<T: Eq + Hash>(lines1: &[T], lines2: &[T]) -> Vec<i32> {
+    let mut lcs_indices = vec![-1; lines1.len()];
+    histogram_lcs_range(
+        lines1,
+        lines2,
+        0,
+        lines1.len(),
+        0,
+        lines2.len(),
+        &mut lcs_indices,
+    );
+    lcs_indices
+}
+
+fn histogram_lcs_range<T: Eq + Hash>(
+    lines1: &[T],
+    lines2: &[T],
+    mut x0: usize,
+    mut x1: usize,
+    mut y0: usize,
+    mut y1: usize,
+    lcs_indices: &mut Vec<i32>,
+) {
+    // collect common elements at the beginning
+    while (x0 < x1) && (y0 < y1) && (lines1[x0] == lines2[y0]) {
+        lcs_indices[x0] = y0 as i32;
+        x0 += 1;
+        y0 += 1;
+    }
+
+    if (x0 == x1) || (y0 == y1) {
+        // we can return early
+        return;
+    }
+
+    // collect common elements at the end
+    while (x0 < x1) && (y0 < y1) && (lines1[x1 - 1] == lines2[y1 - 1]) {
+        lcs_indices[x1 - 1] = (y1 - 1) as i32;
+        x1 -= 1;
+        y1 -= 1;
+    }
+
+    // build histogram
+    let mut hist: HashMap<&T, Vec<i32>> = HashMap::new();
+    for (i, line) in lines1.iter().enumerate().take(x1).skip(x0) {
+        if let Some(rec) = hist.get_mut(line) {
+            rec[0] += 1_i32;
+            rec[1] = i as i32;
+        } else {
+            hist.insert(line, vec![1, i as i32, 0, -1]);
+        }
+    }
+    for (i, line) in lines2.iter().enumerate().take(y1).skip(y0) {
+        if let Some(rec) = hist.get_mut(line) {
+            rec[2] += 1_i32;
+            rec[3] = i as i32;
+        } else {
+            hist.insert(line, vec![0, -1, 1, i as i32]);
+        }
+    }
+
+    // find lowest-occurrence item that appears in both files
+    let key = hist
+        .iter()
+        .filter(|(_k, v)| (v[0] > 0) && (v[2] > 0))
+        .min_by(|a, b| {
+            let c = a.1[0] + a.1[2];
+            let d = b.1[0] + b.1[2];
+            c.cmp(&d)
+        })
+        .map(|(k, _v)| *k);
+
+    match key {
+        None => {}
+        Some(k) => {
+            let rec = hist.get(k).unwrap();
+            let x1_new = rec[1] as usize;
+            let y1_new = rec[3] as usize;
+            lcs_indices[x1_new] = y1_new as i32;
+            histogram_lcs_range(lines1, lines2, x0, x1_new, y0, y1_new, lcs_indices);
+            histogram_lcs_range(lines1, lines2, x1_new + 1, x1, y1_new + 1, y1, lcs_indices);
+        }
+    }
+}
+
+/// A step of an edit script turning one sequence of lines into another.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Edit {
+    /// Line `old` of the first sequence is line `new` of the second.
+    Keep { old: usize, new: usize },
+    /// Line `old` of the first sequence is deleted.
+    Delete { old: usize },
+    /// Line `new` of the second sequence is inserted before the next kept
+    /// or deleted line.
+    Insert { new: usize },
+}
+
+/// The edit script turning `lines1` into `lines2`, in order: deletions of
+/// a changed region come before the insertions replacing them.
+pub fn edit_script<T: Eq + Hash>(lines1: &[T], lines2: &[T]) -> Vec<Edit> {
+    let lcs = histogram_lcs(lines1, lines2);
+    let mut edits = Vec::with_capacity(lines1.len().max(lines2.len()));
+    let (mut old, mut new) = (0, 0);
+    while old < lines1.len() || new < lines2.len() {
+        if old < lines1.len() && lcs[old] < 0 {
+            edits.push(Edit::Delete { old });
+            old += 1;
+        } else if new < lines2.len() && (old == lines1.len() || new < lcs[old] as usize) {
+            edits.push(Edit::Insert { new });
+            new += 1;
+        } else {
+            edits.push(Edit::Keep { old, new });
+            old += 1;
+            new += 1;
+        }
+    }
+    edits
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_histogram_lcs() {
+        let a = ["a", "b", "c", "d"];
+        let b = ["a", "c", "x", "d"];
+        assert_eq!(histogram_lcs(&a, &b), vec![0, -1, 1, 3]);
+        assert_eq!(histogram_lcs::<&str>(&[], &b), Vec::<i32>::new());
+        assert_eq!(histogram_lcs(&a, &[]), vec![-1; 4]);
+    }
+
+    #[test]
+    fn test_edit_script() {
+        let a = ["a", "b", "c"];
+        let b = ["a", "x", "c", "d"];
+        assert_eq!(
+            edit_script(&a, &b),
+            vec![
+                Edit::Keep { old: 0, new: 0 },
+                Edit::Delete { old: 1 },
+                Edit::Insert { new: 1 },
+                Edit::Keep { old: 2, new: 2 },
+                Edit::Insert { new: 3 },
+            ]
+        );
+    }
+}
@@ -38,3 +38,161 @@ pub fn input_reader(
     let file = input_stream(pathname, dashed_stdin)?;
     Ok(io::BufReader::new(file))
 }
+
+/// Byte-oriented line reader.
+///
+/// Unlike [`BufRead::lines`](std::io::BufRead::lines), records are returned
+/// as raw bytes borrowed from an internal buffer, so input that is not valid
+/// UTF-8 passes through unchanged.  The record terminator defaults to
+/// `\n`, and may be set to any byte (typically NUL).
+pub struct ByteLines<R> {
+    rdr: R,
+    buf: Vec<u8>,
+    start: usize,
+    end: usize,
+    terminator: u8,
+    eof: bool,
+}
+
+impl<R: Read> ByteLines<R> {
+    pub fn new(rdr: R) -> ByteLines<R> {
+        ByteLines::with_terminator(rdr, b'\n')
+    }
+
+    pub fn with_terminator(rdr: R, terminator: u8) -> ByteLines<R> {
+        ByteLines {
+            rdr,
+            buf: vec![0; crate::BUFSZ],
+            start: 0,
+            end: 0,
+            terminator,
+            eof: false,
+        }
+    }
+
+    pub fn terminator(&self) -> u8 {
+        self.terminator
+    }
+
+    /// Return the next record, including its terminator if one was present.
+    /// The final record of the input may be unterminated.
+    pub fn next_record(&mut self) -> io::Result<Option<&[u8]>> {
+        let mut scanned = self.start;
+        loop {
+            if let Some(pos) = self.buf[scanned..self.end]
+                .iter()
+                .position(|&b| b == self.terminator)
+            {
+                let line_start = self.start;
+                self.start = scanned + pos + 1;
+                return Ok(Some(&self.buf[line_start..self.start]));
+            }
+            scanned = self.end;
+
+            if self.eof {
+                if self.start == self.end {
+                    return Ok(None);
+                }
+                let line_start = self.start;
+                self.start = self.end;
+                return Ok(Some(&self.buf[line_start..self.end]));
+            }
+
+            // Make room for more data: move the partial record to the
+            // front of the buffer, growing it if the record fills it.
+            if self.start > 0 {
+                self.buf.copy_within(self.start..self.end, 0);
+                scanned -= self.start;
+                self.end -= self.start;
+                self.start = 0;
+            }
+            if self.end == self.buf.len() {
+                self.buf.resize(self.buf.len() * 2, 0);
+            }
+
+            match self.rdr.read(&mut self.buf[self.end..]) {
+                Ok(0) => self.eof = true,
+                Ok(n) => self.end += n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// Return the next record with its terminator removed.
+    pub fn next_line(&mut self) -> io::Result<Option<&[u8]>> {
+        let terminator = self.terminator;
+        Ok(self
+            .next_record()?
+            .map(|rec| rec.strip_suffix(&[terminator]).unwrap_or(rec)))
+    }
+}
+
+/// open file, or stdin, as a newline-terminated [`ByteLines`] reader
+pub fn input_byte_lines(
+    pathname: &PathBuf,
+    dashed_stdin: bool,
+) -> io::Result<ByteLines<Box<dyn Read>>> {
+    let file = input_stream(pathname, dashed_stdin)?;
+    Ok(ByteLines::new(file))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn collect(input: &[u8], terminator: u8) -> Vec<Vec<u8>> {
+        let mut lines = ByteLines::with_terminator(input, terminator);
+        let mut v = Vec::new();
+        while let Some(line) = lines.next_line().unwrap() {
+            v.push(line.to_vec());
+        }
+        v
+    }
+
+    #[test]
+    fn test_byte_lines_basic() {
+        assert_eq!(
+            collect(b"a\nbc\n\nd", b'\n'),
+            vec![&b"a"[..], b"bc", b"", b"d"]
+        );
+        assert_eq!(collect(b"a\n", b'\n'), vec![b"a"]);
+        assert!(collect(b"", b'\n').is_empty());
+    }
+
+    #[test]
+    fn test_byte_lines_non_utf8() {
+        let input = b"caf\xe9\n\xff\xfe\x00\n";
+        assert_eq!(
+            collect(input, b'\n'),
+            vec![&b"caf\xe9"[..], b"\xff\xfe\x00"]
+        );
+    }
+
+    #[test]
+    fn test_byte_lines_nul_terminator() {
+        assert_eq!(collect(b"a\nb\0c\0", 0), vec![&b"a\nb"[..], b"c"]);
+    }
+
+    #[test]
+    fn test_byte_lines_record() {
+        let mut lines = ByteLines::new(&b"x\ny"[..]);
+        assert_eq!(lines.next_record().unwrap(), Some(&b"x\n"[..]));
+        assert_eq!(lines.next_record().unwrap(), Some(&b"y"[..]));
+        assert_eq!(lines.next_record().unwrap(), None);
+    }
+
+    #[test]
+    fn test_byte_lines_long_records() {
+        // records spanning several buffer refills
+        let long = vec![b'z'; crate::BUFSZ * 3 + 17];
+        let mut input = long.clone();
+        input.push(b'\n');
+        input.extend_from_slice(b"short\n");
+        input.extend_from_slice(&long);
+        assert_eq!(
+            collect(&input, b'\n'),
+            vec![long.clone(), b"short".to_vec(), long]
+        );
+    }
+}
@@ -8,12 +8,17 @@
 //
 
 pub mod curuser;
+pub mod diff;
 pub mod group;
 pub mod io;
+pub mod locale;
 pub mod lzw;
 pub mod modestr;
+pub mod nlcat;
 pub mod platform;
+pub mod po;
 pub mod priority;
+pub mod regex;
 pub mod sccsfile;
 pub mod testing;
 pub mod utmpx;
@@ -0,0 +1,735 @@
+//
+// Copyright (c) 2024 Hemi Labs, Inc.
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+//! LC_COLLATE: multi-level collation tables built from `order_start`
+//! sections, with `copy`, `collating-element`, `reorder-after` and
+//! `UNDEFINED` support.
+
+use super::source::{str_parts_text, Category, Line, LocaleSource, StrPart, Token};
+use super::{decode, is_posix_locale, symbol_char, SourceLoader};
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+use std::io;
+
+/// Maximum nesting of `copy` directives.
+const MAX_DEPTH: usize = 16;
+
+/// Collation rules of a locale.
+#[derive(Clone, Debug)]
+pub struct Collation {
+    /// `None` for code point (byte) order, as in the POSIX locale.
+    table: Option<Table>,
+}
+
+#[derive(Clone, Debug)]
+struct Table {
+    levels: usize,
+    /// Per section, whether each level is collated backward.
+    backward: Vec<Vec<bool>>,
+    /// Single characters to element index.
+    chars: HashMap<u32, u32>,
+    /// Multi-character collating elements by first character, longest
+    /// first.
+    multi: HashMap<u32, Vec<(Vec<u32>, u32)>>,
+    elements: Vec<Element>,
+    /// Weights of all elements, indexed by `Element::spans`.
+    pool: Vec<u32>,
+    undefined: Option<u32>,
+    max_weight: u32,
+}
+
+#[derive(Clone, Debug)]
+struct Element {
+    /// `(start, len)` into the weight pool, per level.
+    spans: Box<[(u32, u32)]>,
+    section: usize,
+}
+
+#[derive(Clone, Copy)]
+enum Item {
+    Element(u32),
+    Unknown(u32),
+}
+
+impl Collation {
+    /// Collation of the POSIX locale: code point order.
+    pub fn posix() -> Collation {
+        Collation { table: None }
+    }
+
+    /// Build LC_COLLATE from a parsed locale source, loading the sources
+    /// named by `copy` through `loader`.
+    pub fn from_source(source: &LocaleSource, loader: &mut SourceLoader) -> io::Result<Collation> {
+        let category = source
+            .category("LC_COLLATE")
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "no LC_COLLATE category"))?;
+        let mut builder = Builder::new();
+        builder.apply(category, loader, 0)?;
+        Ok(builder.finish())
+    }
+
+    /// Load LC_COLLATE of the locale `name`.
+    pub fn load(name: &str) -> io::Result<Collation> {
+        if is_posix_locale(name) {
+            return Ok(Collation::posix());
+        }
+        let mut loader = SourceLoader::default();
+        let source = loader.load_locale(name)?;
+        Collation::from_source(&source, &mut loader)
+    }
+
+    /// LC_COLLATE of the locale `name`, or of the POSIX locale if it
+    /// cannot be loaded.
+    pub fn for_locale(name: &str) -> Collation {
+        Collation::load(name).unwrap_or_else(|_| Collation::posix())
+    }
+
+    /// Whether strings collate in code point order.
+    pub fn is_codepoint(&self) -> bool {
+        self.table.is_none()
+    }
+
+    /// Transform `s` into a key: comparing keys gives the same result as
+    /// [`Collation::strcoll`] on the strings.
+    pub fn strxfrm(&self, s: &[u8]) -> Vec<u32> {
+        match &self.table {
+            None => s.iter().map(|&b| b as u32 + 1).collect(),
+            Some(table) => table.strxfrm(s),
+        }
+    }
+
+    /// Compare two strings.  Strings that differ only in characters the
+    /// locale ignores compare equal.
+    pub fn strcoll(&self, a: &[u8], b: &[u8]) -> Ordering {
+        match &self.table {
+            None => a.cmp(b),
+            Some(table) => table.strcoll(a, b),
+        }
+    }
+}
+
+impl Table {
+    /// Splits `codes` into collating elements, as far as they are read.
+    fn segment<'a>(&'a self, codes: &'a [u32]) -> impl Iterator<Item = Item> + 'a {
+        let mut i = 0;
+        std::iter::from_fn(move || {
+            let c = *codes.get(i)?;
+            if let Some(candidates) = self.multi.get(&c) {
+                for (seq, idx) in candidates {
+                    if codes[i..].starts_with(seq) {
+                        i += seq.len();
+                        return Some(Item::Element(*idx));
+                    }
+                }
+            }
+            i += 1;
+            Some(match self.chars.get(&c) {
+                Some(&idx) => Item::Element(idx),
+                None => Item::Unknown(c),
+            })
+        })
+    }
+
+    fn is_backward(&self, item: Item, level: usize) -> bool {
+        let section = match item {
+            Item::Element(idx) => self.elements[idx as usize].section,
+            Item::Unknown(_) => match self.undefined {
+                Some(idx) => self.elements[idx as usize].section,
+                None => 0,
+            },
+        };
+        self.backward[section].get(level).copied().unwrap_or(false)
+    }
+
+    /// The weights of `item` at `level`.
+    fn weights(&self, item: Item, level: usize) -> impl Iterator<Item = u32> + '_ {
+        let (weights, unknown) = match item {
+            Item::Element(idx) => (self.spans(idx, level), None),
+            Item::Unknown(c) => match self.undefined {
+                // undefined characters share the UNDEFINED weights but
+                // stay distinct at the last level
+                Some(idx) if level + 1 < self.levels => (self.spans(idx, level), None),
+                _ => (&[][..], Some(self.max_weight + 1 + c)),
+            },
+        };
+        weights.iter().copied().chain(unknown)
+    }
+
+    fn spans(&self, idx: u32, level: usize) -> &[u32] {
+        let (start, len) = self.elements[idx as usize].spans[level];
+        &self.pool[start as usize..(start + len) as usize]
+    }
+
+    /// The weights of `items` at `level`, in the order they compare in:
+    /// the runs of items in backward sections are reversed.
+    fn level_weights<'a>(
+        &'a self,
+        items: impl Iterator<Item = Item> + 'a,
+        level: usize,
+    ) -> impl Iterator<Item = u32> + 'a {
+        let mut items = items.peekable();
+        let mut run = Vec::new();
+        std::iter::from_fn(move || {
+            if let Some(item) = run.pop() {
+                return Some(item);
+            }
+            let item = items.next()?;
+            if self.is_backward(item, level) {
+                run.push(item);
+                while let Some(item) = items.next_if(|&item| self.is_backward(item, level)) {
+                    run.push(item);
+                }
+                return run.pop();
+            }
+            Some(item)
+        })
+        .flat_map(move |item| self.weights(item, level))
+    }
+
+    fn strxfrm(&self, s: &[u8]) -> Vec<u32> {
+        let codes = decode(s);
+        let items: Vec<Item> = self.segment(&codes).collect();
+        let mut key = Vec::with_capacity(codes.len() * (self.levels + 1));
+        for level in 0..self.levels {
+            if level > 0 {
+                key.push(0);
+            }
+            key.extend(self.level_weights(items.iter().copied(), level));
+        }
+        key
+    }
+
+    /// Compares the weights of two strings level by level, as far as they
+    /// differ, rather than building their keys.
+    fn strcoll(&self, a: &[u8], b: &[u8]) -> Ordering {
+        let (a, b) = (decode(a), decode(b));
+        (0..self.levels)
+            .map(|level| {
+                self.level_weights(self.segment(&a), level)
+                    .cmp(self.level_weights(self.segment(&b), level))
+            })
+            .find(|ordering| ordering.is_ne())
+            .unwrap_or(Ordering::Equal)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+enum Key {
+    Char(u32),
+    /// A collating symbol or collating element.
+    Name(String),
+    Undefined,
+}
+
+#[derive(Clone, Debug)]
+enum Weight {
+    /// The element's own position.
+    Same,
+    Ignore,
+    Seq(Vec<Key>),
+}
+
+struct Entry {
+    key: Key,
+    weights: Vec<Weight>,
+    section: usize,
+    prev: usize,
+    next: usize,
+}
+
+/// Collects the collation order as a linked list, so that `reorder-after`
+/// can move and insert entries cheaply.  Entry 0 is the list head.
+struct Builder {
+    entries: Vec<Entry>,
+    index: HashMap<Key, usize>,
+    elements: HashMap<String, Vec<u32>>,
+    symbols: HashSet<String>,
+    equivalents: HashMap<String, String>,
+    backward: Vec<Vec<bool>>,
+    section: usize,
+    /// Insert after this entry instead of at the end (`reorder-after`).
+    cursor: Option<usize>,
+    /// Fields of a pending `..` range line.
+    ellipsis: Option<Vec<Weight>>,
+    codepoint: bool,
+}
+
+impl Builder {
+    fn new() -> Builder {
+        let head = Entry {
+            key: Key::Name(String::new()),
+            weights: Vec::new(),
+            section: 0,
+            prev: 0,
+            next: 0,
+        };
+        Builder {
+            entries: vec![head],
+            index: HashMap::new(),
+            elements: HashMap::new(),
+            symbols: HashSet::new(),
+            equivalents: HashMap::new(),
+            backward: vec![Vec::new()],
+            section: 0,
+            cursor: None,
+            ellipsis: None,
+            codepoint: false,
+        }
+    }
+
+    fn key(&self, name: &str) -> Key {
+        let name = self.equivalents.get(name).map_or(name, String::as_str);
+        if self.elements.contains_key(name) || self.symbols.contains(name) {
+            return Key::Name(name.to_string());
+        }
+        match symbol_char(name) {
+            Some(c) => Key::Char(c as u32),
+            None => Key::Name(name.to_string()),
+        }
+    }
+
+    fn parse_weights(&self, fields: &[Token], line: &Line) -> io::Result<Vec<Weight>> {
+        let mut weights = Vec::new();
+        for field in fields.split(|t| *t == Token::Punct(';')) {
+            weights.push(match field {
+                [] => Weight::Same,
+                [Token::Word(w)] if w == "IGNORE" => Weight::Ignore,
+                [Token::Word(w)] if w == ".." || w == "..." => Weight::Same,
+                [Token::Symbol(name)] => Weight::Seq(vec![self.key(name)]),
+                [Token::Str(parts)] => Weight::Seq(
+                    parts
+                        .iter()
+                        .map(|part| match part {
+                            StrPart::Symbol(name) => self.key(name),
+                            StrPart::Char(c) => Key::Char(*c as u32),
+                        })
+                        .collect(),
+                ),
+                _ => return Err(line.error("bad collation weight")),
+            });
+        }
+        Ok(weights)
+    }
+
+    fn unlink(&mut self, idx: usize) {
+        let (prev, next) = (self.entries[idx].prev, self.entries[idx].next);
+        self.entries[prev].next = next;
+        self.entries[next].prev = prev;
+    }
+
+    fn link_after(&mut self, idx: usize, after: usize) {
+        let next = self.entries[after].next;
+        self.entries[idx].prev = after;
+        self.entries[idx].next = next;
+        self.entries[after].next = idx;
+        self.entries[next].prev = idx;
+    }
+
+    fn insert(&mut self, key: Key, weights: Vec<Weight>) {
+        let after = match self.cursor {
+            Some(cursor) => cursor,
+            None => self.entries[0].prev,
+        };
+        let section = match self.cursor {
+            Some(cursor) => self.entries[cursor].section,
+            None => self.section,
+        };
+        let idx = match self.index.get(&key) {
+            Some(&idx) => {
+                if idx == after {
+                    self.entries[idx].weights = weights;
+                    self.entries[idx].section = section;
+                    return;
+                }
+                self.unlink(idx);
+                self.entries[idx].weights = weights;
+                self.entries[idx].section = section;
+                idx
+            }
+            None => {
+                let idx = self.entries.len();
+                self.entries.push(Entry {
+                    key: key.clone(),
+                    weights,
+                    section,
+                    prev: 0,
+                    next: 0,
+                });
+                self.index.insert(key, idx);
+                idx
+            }
+        };
+        let after = match self.cursor {
+            Some(cursor) => cursor,
+            None => self.entries[0].prev,
+        };
+        self.link_after(idx, after);
+        if self.cursor.is_some() {
+            self.cursor = Some(idx);
+        }
+    }
+
+    /// The character of the entry inserted last, for `..` ranges.
+    fn last_char(&self) -> Option<u32> {
+        let last = match self.cursor {
+            Some(cursor) => cursor,
+            None => self.entries[0].prev,
+        };
+        match self.entries[last].key {
+            Key::Char(c) => Some(c),
+            _ => None,
+        }
+    }
+
+    fn apply(
+        &mut self,
+        category: &Category,
+        loader: &mut SourceLoader,
+        depth: usize,
+    ) -> io::Result<()> {
+        for line in &category.lines {
+            let operands = line.operands();
+            match (&line.tokens[0], line.keyword()) {
+                (_, Some("copy")) => {
+                    let [Token::Str(name)] = operands else {
+                        return Err(line.error("bad copy directive"));
+                    };
+                    if depth >= MAX_DEPTH {
+                        return Err(line.error("copy nested too deeply"));
+                    }
+                    let name = str_parts_text(name);
+                    let source = loader.load(&name)?;
+                    let category = source.category("LC_COLLATE").ok_or_else(|| {
+                        line.error(format!("'{name}' has no LC_COLLATE category"))
+                    })?;
+                    self.apply(category, loader, depth + 1)?;
+                }
+                (_, Some("codepoint_collation")) => self.codepoint = true,
+                (_, Some("collating-symbol")) => match operands {
+                    [Token::Symbol(name)] => {
+                        self.symbols.insert(name.clone());
+                    }
+                    [Token::Symbol(first), Token::Word(dots), Token::Symbol(last)]
+                        if dots == ".." =>
+                    {
+                        let names = symbol_range(first, last)
+                            .ok_or_else(|| line.error("bad collating-symbol range"))?;
+                        self.symbols.extend(names);
+                    }
+                    _ => return Err(line.error("bad collating-symbol")),
+                },
+                (_, Some("collating-element")) => match operands {
+                    [Token::Symbol(name), Token::Word(from), Token::Str(parts)]
+                        if from == "from" =>
+                    {
+                        let mut seq = Vec::new();
+                        for part in parts {
+                            seq.push(match part {
+                                StrPart::Char(c) => *c as u32,
+                                StrPart::Symbol(s) => symbol_char(s)
+                                    .ok_or_else(|| line.error(format!("unknown symbol <{s}>")))?
+                                    as u32,
+                            });
+                        }
+                        self.elements.insert(name.clone(), seq);
+                    }
+                    _ => return Err(line.error("bad collating-element")),
+                },
+                (_, Some("symbol-equivalence")) => match operands {
+                    [Token::Symbol(name), Token::Symbol(target)] => {
+                        self.equivalents.insert(name.clone(), target.clone());
+                    }
+                    _ => return Err(line.error("bad symbol-equivalence")),
+                },
+                (_, Some("order_start")) => {
+                    let mut dirs = Vec::new();
+                    for group in operands.split(|t| *t == Token::Punct(';')) {
+                        match group {
+                            [Token::Symbol(_)] | [] => {}
+                            _ => dirs.push(
+                                group
+                                    .iter()
+                                    .any(|t| *t == Token::Word("backward".to_string())),
+                            ),
+                        }
+                    }
+                    self.backward.push(dirs);
+                    self.section = self.backward.len() - 1;
+                    self.cursor = None;
+                }
+                (_, Some("order_end")) | (_, Some("reorder-end")) => self.cursor = None,
+                (_, Some("reorder-after")) => match operands {
+                    [Token::Symbol(name)] => {
+                        let key = self.key(name);
+                        let idx = *self.index.get(&key).ok_or_else(|| {
+                            line.error(format!("reorder-after unknown symbol <{name}>"))
+                        })?;
+                        self.cursor = Some(idx);
+                    }
+                    _ => return Err(line.error("bad reorder-after")),
+                },
+                (_, Some("UNDEFINED")) => {
+                    let weights = self.parse_weights(operands, line)?;
+                    self.insert(Key::Undefined, weights);
+                }
+                (_, Some(".." | "...")) => {
+                    self.ellipsis = Some(self.parse_weights(operands, line)?);
+                }
+                (Token::Symbol(name), _) => {
+                    let key = self.key(name);
+                    let weights = self.parse_weights(operands, line)?;
+                    if let (Some(fields), Some(lo), Key::Char(hi)) =
+                        (self.ellipsis.take(), self.last_char(), &key)
+                    {
+                        for c in lo + 1..*hi {
+                            self.insert(Key::Char(c), fields.clone());
+                        }
+                    }
+                    self.insert(key, weights);
+                }
+                // script, reorder-sections-after and multi-character
+                // string entries are not used
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn finish(self) -> Collation {
+        if self.codepoint {
+            return Collation::posix();
+        }
+
+        let mut order = Vec::with_capacity(self.entries.len());
+        let mut idx = self.entries[0].next;
+        while idx != 0 {
+            order.push(idx);
+            idx = self.entries[idx].next;
+        }
+        let ordinals: HashMap<&Key, u32> = order
+            .iter()
+            .enumerate()
+            .map(|(n, &idx)| (&self.entries[idx].key, n as u32 + 1))
+            .collect();
+        let max_weight = order.len() as u32;
+        let levels = self.backward.iter().map(Vec::len).max().unwrap_or(0).max(1);
+
+        let mut table = Table {
+            levels,
+            backward: self.backward.clone(),
+            chars: HashMap::new(),
+            multi: HashMap::new(),
+            elements: Vec::new(),
+            pool: Vec::new(),
+            undefined: None,
+            max_weight,
+        };
+        for &idx in &order {
+            let entry = &self.entries[idx];
+            let element_idx = table.elements.len() as u32;
+            match &entry.key {
+                Key::Char(c) => {
+                    table.chars.insert(*c, element_idx);
+                }
+                Key::Name(name) => match self.elements.get(name) {
+                    Some(seq) if seq.len() == 1 => {
+                        table.chars.insert(seq[0], element_idx);
+                    }
+                    Some(seq) if !seq.is_empty() => {
+                        table
+                            .multi
+                            .entry(seq[0])
+                            .or_default()
+                            .push((seq.clone(), element_idx));
+                    }
+                    _ => continue,
+                },
+                Key::Undefined => table.undefined = Some(element_idx),
+            }
+
+            let mut spans = Vec::with_capacity(levels);
+            for level in 0..levels {
+                let start = table.pool.len() as u32;
+                match entry.weights.get(level).unwrap_or(&Weight::Same) {
+                    Weight::Same => table.pool.push(ordinals[&entry.key]),
+                    Weight::Ignore => {}
+                    Weight::Seq(keys) => table
+                        .pool
+                        .extend(keys.iter().filter_map(|k| ordinals.get(k).copied())),
+                }
+                spans.push((start, table.pool.len() as u32 - start));
+            }
+            table.elements.push(Element {
+                spans: spans.into_boxed_slice(),
+                section: entry.section,
+            });
+        }
+        for candidates in table.multi.values_mut() {
+            candidates.sort_by_key(|c| std::cmp::Reverse(c.0.len()));
+        }
+
+        Collation { table: Some(table) }
+    }
+}
+
+/// Expand a range of symbolic names that share a prefix and end in
+/// hexadecimal numbers of the same width, e.g. `<S0009>..<S327F>`.
+fn symbol_range(first: &str, last: &str) -> Option<Vec<String>> {
+    let hex_len = |s: &str| s.bytes().rev().take_while(u8::is_ascii_hexdigit).count();
+    let width = hex_len(first).min(hex_len(last));
+    (1..=width).rev().find_map(|w| {
+        let (prefix, lo) = first.split_at(first.len() - w);
+        let (prefix2, hi) = last.split_at(last.len() - w);
+        if prefix != prefix2 || lo.len() != hi.len() {
+            return None;
+        }
+        let lo = u32::from_str_radix(lo, 16).ok()?;
+        let hi = u32::from_str_radix(hi, 16).ok()?;
+        Some((lo..=hi).map(|n| format!("{prefix}{n:0w$X}")).collect())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn build(text: &str, extra: &[(&str, &str)]) -> Collation {
+        let mut loader = SourceLoader::with_dirs(Vec::new());
+        for (name, src) in extra {
+            loader.insert(name, LocaleSource::parse(src).unwrap());
+        }
+        Collation::from_source(&LocaleSource::parse(text).unwrap(), &mut loader).unwrap()
+    }
+
+    const BASE: &str = "LC_COLLATE\n\
+        collating-symbol <MIN>\n\
+        collating-symbol <CAP>\n\
+        collating-symbol <BASE>\n\
+        collating-symbol <ACUTE>\n\
+        <MIN>\n<CAP>\n<BASE>\n<ACUTE>\n\
+        order_start forward;backward;forward\n\
+        <U0020> IGNORE;IGNORE;IGNORE\n\
+        <U0061> <U0061>;<BASE>;<MIN>\n\
+        <U0041> <U0061>;<BASE>;<CAP>\n\
+        <U00E1> <U0061>;<ACUTE>;<MIN>\n\
+        <U0062> <U0062>;<BASE>;<MIN>\n\
+        <U0042> <U0062>;<BASE>;<CAP>\n\
+        <U0063> <U0063>;<BASE>;<MIN>\n\
+        <U0043> <U0063>;<BASE>;<CAP>\n\
+        order_end\n\
+        END LC_COLLATE\n";
+
+    fn sorted(coll: &Collation, words: &[&str]) -> Vec<String> {
+        let mut words: Vec<String> = words.iter().map(|s| s.to_string()).collect();
+        words.sort_by(|a, b| coll.strcoll(a.as_bytes(), b.as_bytes()).then(a.cmp(b)));
+        words
+    }
+
+    #[test]
+    fn test_posix() {
+        let coll = Collation::posix();
+        assert_eq!(sorted(&coll, &["b", "B", "a", "A"]), ["A", "B", "a", "b"]);
+    }
+
+    #[test]
+    fn test_levels() {
+        let coll = build(BASE, &[]);
+        assert_eq!(
+            sorted(&coll, &["b", "B", "a", "A", "ab", "Ab", "c"]),
+            ["a", "A", "ab", "Ab", "b", "B", "c"]
+        );
+        // spaces are ignored at every level
+        assert_eq!(coll.strcoll(b"a b", b"ab"), Ordering::Equal);
+        // accents collate after the plain letter, before the next letter
+        assert_eq!(sorted(&coll, &["b", "á", "a"]), ["a", "á", "b"]);
+        // characters without an entry sort last
+        assert_eq!(sorted(&coll, &["z", "c"]), ["c", "z"]);
+    }
+
+    #[test]
+    fn test_backward() {
+        let coll = build(BASE, &[]);
+        // the second level is compared from the end of the string, so the
+        // last accent decides, as in French "côte" < "coté"
+        assert_eq!(
+            coll.strcoll("áa".as_bytes(), "aá".as_bytes()),
+            Ordering::Less
+        );
+    }
+
+    #[test]
+    fn test_strcoll_matches_strxfrm() {
+        let coll = build(BASE, &[]);
+        let words = [
+            "", "a", "A", "á", "aá", "áa", "a b", "ab", "Ab", "abc", "b", "z", "az",
+        ];
+        for a in words {
+            for b in words {
+                let (a, b) = (a.as_bytes(), b.as_bytes());
+                assert_eq!(
+                    coll.strcoll(a, b),
+                    coll.strxfrm(a).cmp(&coll.strxfrm(b)),
+                    "{:?} {:?}",
+                    String::from_utf8_lossy(a),
+                    String::from_utf8_lossy(b)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_reorder_and_elements() {
+        let src = "LC_COLLATE\n\
+            copy \"base\"\n\
+            collating-element <c-h> from \"<U0063><U0068>\"\n\
+            reorder-after <U0043>\n\
+            <c-h> <c-h>;<BASE>;<MIN>\n\
+            reorder-after <BASE>\n\
+            <MIN>\n\
+            reorder-end\n\
+            END LC_COLLATE\n";
+        let coll = build(src, &[("base", BASE)]);
+        assert_eq!(
+            sorted(&coll, &["d", "ch", "cz", "c", "C", "a", "A"]),
+            ["A", "a", "C", "c", "cz", "ch", "d"]
+        );
+    }
+
+    #[test]
+    fn test_ellipsis() {
+        let src = "LC_COLLATE\n\
+            order_start forward\n\
+            <U0063>\n\
+            <U0061>\n\
+            ..\n\
+            <U0062>\n\
+            order_end\n\
+            END LC_COLLATE\n";
+        let coll = build(src, &[]);
+        // `..` between <U0061> and <U0062> adds no characters
+        assert_eq!(sorted(&coll, &["a", "b", "c"]), ["c", "a", "b"]);
+
+        let src = "LC_COLLATE\n\
+            order_start forward\n\
+            <U0061>\n\
+            ..\n\
+            <U0065>\n\
+            <U0041>\n\
+            order_end\n\
+            END LC_COLLATE\n";
+        let coll = build(src, &[]);
+        assert_eq!(sorted(&coll, &["A", "c", "a", "e"]), ["a", "c", "e", "A"]);
+    }
+
+    #[test]
+    fn test_codepoint() {
+        let src = "LC_COLLATE\ncodepoint_collation\nEND LC_COLLATE\n";
+        assert!(build(src, &[]).is_codepoint());
+    }
+}
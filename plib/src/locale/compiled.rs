@@ -0,0 +1,203 @@
+//
+// Copyright (c) 2024 Hemi Labs, Inc.
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+//! The compiled locale format written by `localedef`.
+//!
+//! A compiled locale is a directory with one file per category it
+//! defines, named after the category.  Each file holds the category as
+//! locale definition source, behind a header line that tells it apart
+//! from a glibc binary file.  All `copy` and `include` directives are
+//! already resolved, so a compiled locale does not depend on any other
+//! file, and characters outside printable ASCII are written as `<Uxxxx>`
+//! names.  Compiled locales live where glibc's do: in the directories of
+//! `LOCPATH`, else in `/usr/lib/locale`.
+
+use std::fs::{self, File};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+
+use super::info::{compiled_candidates, compiled_dirs};
+use super::{Category, LocaleSource, SourceCategory, StrPart, Token};
+
+/// First line of every category file of a compiled locale.
+pub const COMPILED_HEADER: &str = "# posixutils-rs compiled locale";
+
+fn unicode_name(c: char) -> String {
+    if (c as u32) > 0xffff {
+        format!("<U{:08X}>", c as u32)
+    } else {
+        format!("<U{:04X}>", c as u32)
+    }
+}
+
+fn format_token(token: &Token) -> String {
+    let mut s = String::new();
+    match token {
+        Token::Symbol(name) => {
+            s.push('<');
+            for c in name.chars() {
+                if matches!(c, '>' | '\\') {
+                    s.push('\\');
+                }
+                s.push(c);
+            }
+            s.push('>');
+        }
+        Token::Str(parts) => {
+            s.push('"');
+            for part in parts {
+                match part {
+                    StrPart::Char(c) if c.is_ascii_graphic() && !matches!(c, '"' | '<' | '\\') => {
+                        s.push(*c)
+                    }
+                    StrPart::Char(' ') => s.push(' '),
+                    StrPart::Char(c) => s.push_str(&unicode_name(*c)),
+                    StrPart::Symbol(name) => {
+                        s.push_str(&format_token(&Token::Symbol(name.clone())))
+                    }
+                }
+            }
+            s.push('"');
+        }
+        Token::Word(word) => {
+            for c in word.chars() {
+                if c.is_whitespace() || matches!(c, '\\' | '#' | '<' | '"' | ';' | ',' | '(' | ')')
+                {
+                    s.push('\\');
+                }
+                s.push(c);
+            }
+        }
+        Token::Punct(c) => s.push(*c),
+    }
+    s
+}
+
+/// Write `category` as a category file of a compiled locale.
+pub fn write_category(out: &mut impl Write, category: &SourceCategory) -> io::Result<()> {
+    writeln!(out, "{COMPILED_HEADER}")?;
+    writeln!(out, "{}", category.name)?;
+    for line in &category.lines {
+        let tokens: Vec<String> = line.tokens.iter().map(format_token).collect();
+        writeln!(out, "{}", tokens.join(" "))?;
+    }
+    writeln!(out, "END {}", category.name)
+}
+
+/// Whether the file at `path` starts with [`COMPILED_HEADER`].
+fn has_header(path: &Path) -> bool {
+    let mut header = [0; COMPILED_HEADER.len()];
+    File::open(path)
+        .and_then(|mut file| file.read_exact(&mut header))
+        .is_ok_and(|_| header == COMPILED_HEADER.as_bytes())
+}
+
+/// Whether `dir` holds a compiled locale.
+pub fn is_compiled(dir: &Path) -> bool {
+    Category::ALL
+        .iter()
+        .any(|category| has_header(&dir.join(category.name())))
+}
+
+/// Read the compiled locale in `dir` as a locale definition source
+/// holding the categories it defines.
+pub fn load_compiled(dir: &Path) -> io::Result<LocaleSource> {
+    let mut source = LocaleSource::default();
+    for category in Category::ALL {
+        let path = dir.join(category.name());
+        if !has_header(&path) {
+            continue;
+        }
+        let bytes = fs::read(&path)?;
+        let parsed = LocaleSource::parse(&String::from_utf8_lossy(&bytes))
+            .map_err(|e| io::Error::new(e.kind(), format!("{}: {}", path.display(), e)))?;
+        source.categories.extend(parsed.categories);
+    }
+    Ok(source)
+}
+
+/// The directory of the compiled locale `name`, if there is one.
+pub fn find_compiled(name: &str) -> Option<PathBuf> {
+    let (dirs, _) = compiled_dirs();
+    compiled_candidates(name).iter().find_map(|candidate| {
+        dirs.iter()
+            .map(|dir| dir.join(candidate))
+            .find(|path| is_compiled(path))
+    })
+}
+
+/// The directory the locale `name` is compiled into: a name containing
+/// a slash is used as a path as-is, any other is placed in the first
+/// directory of `LOCPATH`, or else in `/usr/lib/locale`.
+pub fn compiled_path(name: &str) -> PathBuf {
+    if name.contains('/') {
+        return PathBuf::from(name);
+    }
+    let (dirs, _) = compiled_dirs();
+    dirs[0].join(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let text = "escape_char /\n\
+                    LC_CTYPE\n\
+                    upper <U0041>..<U005A>;<U00C0>\n\
+                    toupper (<a>,<A>);(<U00E0>,<U00C0>)\n\
+                    END LC_CTYPE\n\
+                    LC_MONETARY\n\
+                    currency_symbol \"€ /\"/<<U0001F600>\"\n\
+                    weird a/#b/;c\n\
+                    END LC_MONETARY\n";
+        let source = LocaleSource::parse(text).unwrap();
+        let mut compiled = Vec::new();
+        for category in &source.categories {
+            write_category(&mut compiled, category).unwrap();
+        }
+        let compiled = String::from_utf8(compiled).unwrap();
+        assert!(compiled.starts_with(COMPILED_HEADER));
+        assert!(compiled.is_ascii());
+        assert!(compiled.contains("\"<U20AC> <U0022><U003C><U0001F600>\""));
+
+        let parsed = LocaleSource::parse(&compiled).unwrap();
+        assert_eq!(parsed.categories.len(), 2);
+        let tokens = |source: &LocaleSource| -> Vec<Vec<Token>> {
+            let ctype = source.category("LC_CTYPE").unwrap();
+            ctype.lines.iter().map(|l| l.tokens.clone()).collect()
+        };
+        assert_eq!(tokens(&parsed), tokens(&source));
+        let monetary = parsed.category("LC_MONETARY").unwrap();
+        assert_eq!(
+            monetary.lines[1].tokens,
+            vec![Token::Word("weird".into()), Token::Word("a#b;c".into())]
+        );
+    }
+
+    #[test]
+    fn test_load_compiled() {
+        let dir = std::env::temp_dir().join(format!("plib-compiled-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let source = LocaleSource::parse("LC_NUMERIC\ndecimal_point \",\"\nEND LC_NUMERIC\n");
+        let mut file = File::create(dir.join("LC_NUMERIC")).unwrap();
+        write_category(&mut file, &source.unwrap().categories[0]).unwrap();
+        // a file in another format is not part of the compiled locale
+        fs::write(dir.join("LC_TIME"), b"\x15\x11\x03\x20").unwrap();
+
+        assert!(is_compiled(&dir));
+        let loaded = load_compiled(&dir).unwrap();
+        assert_eq!(loaded.categories.len(), 1);
+        assert_eq!(loaded.categories[0].name, "LC_NUMERIC");
+
+        fs::remove_dir_all(&dir).unwrap();
+        assert!(!is_compiled(&dir));
+    }
+}
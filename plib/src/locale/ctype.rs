@@ -0,0 +1,539 @@
+//
+// Copyright (c) 2024 Hemi Labs, Inc.
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+//! LC_CTYPE: character classes, case mappings and transliteration.
+
+use super::source::{Category, LocaleSource, StrPart, Token};
+use super::{is_posix_locale, symbol_char, SourceLoader};
+use std::collections::HashMap;
+use std::io;
+
+/// The character classes every locale defines.
+pub const STANDARD_CLASSES: [&str; 12] = [
+    "alnum", "alpha", "blank", "cntrl", "digit", "graph", "lower", "print", "punct", "space",
+    "upper", "xdigit",
+];
+
+/// Maximum nesting of `copy` and `include` directives.
+const MAX_DEPTH: usize = 16;
+
+/// Character classification and mapping data of a locale.
+#[derive(Clone, Debug)]
+pub struct Ctype {
+    /// Sorted, non-overlapping inclusive code point ranges per class.
+    classes: HashMap<String, Vec<(u32, u32)>>,
+    /// Character mappings, e.g. `toupper`, by name.
+    maps: HashMap<String, HashMap<u32, u32>>,
+    /// Transliteration candidates, in order of preference.
+    translit: HashMap<char, Vec<String>>,
+    default_missing: Option<String>,
+}
+
+impl Ctype {
+    /// The LC_CTYPE category of the POSIX locale.
+    pub fn posix() -> Ctype {
+        let mut builder = Builder::default();
+        let class = |b: &mut Builder, name: &str, pred: fn(&u8) -> bool| {
+            let ranges = (0..=0x7fu8)
+                .filter(pred)
+                .map(|c| (c as u32, c as u32))
+                .collect::<Vec<_>>();
+            b.classes.insert(name.to_string(), ranges);
+        };
+        class(&mut builder, "upper", u8::is_ascii_uppercase);
+        class(&mut builder, "lower", u8::is_ascii_lowercase);
+        class(&mut builder, "digit", u8::is_ascii_digit);
+        class(&mut builder, "xdigit", u8::is_ascii_hexdigit);
+        class(&mut builder, "space", |c| b" \t\n\x0b\x0c\r".contains(c));
+        class(&mut builder, "blank", |c| b" \t".contains(c));
+        class(&mut builder, "cntrl", u8::is_ascii_control);
+        class(&mut builder, "punct", u8::is_ascii_punctuation);
+
+        let upper: HashMap<u32, u32> = (b'a'..=b'z')
+            .map(|c| (c as u32, c.to_ascii_uppercase() as u32))
+            .collect();
+        let lower = upper.iter().map(|(&l, &u)| (u, l)).collect();
+        builder.maps.insert("toupper".to_string(), upper);
+        builder.maps.insert("tolower".to_string(), lower);
+
+        builder.finish()
+    }
+
+    /// Build LC_CTYPE from a parsed locale source, loading the sources
+    /// named by `copy` and `include` through `loader`.
+    pub fn from_source(source: &LocaleSource, loader: &mut SourceLoader) -> io::Result<Ctype> {
+        let category = source
+            .category("LC_CTYPE")
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "no LC_CTYPE category"))?;
+        let mut builder = Builder::default();
+        builder.apply(category, loader, 0)?;
+        Ok(builder.finish())
+    }
+
+    /// Load LC_CTYPE of the locale `name`.
+    pub fn load(name: &str) -> io::Result<Ctype> {
+        if is_posix_locale(name) {
+            return Ok(Ctype::posix());
+        }
+        let mut loader = SourceLoader::default();
+        let source = loader.load_locale(name)?;
+        Ctype::from_source(&source, &mut loader)
+    }
+
+    /// LC_CTYPE of the locale `name`, or of the POSIX locale if it cannot
+    /// be loaded.
+    pub fn for_locale(name: &str) -> Ctype {
+        Ctype::load(name).unwrap_or_else(|_| Ctype::posix())
+    }
+
+    /// Whether the locale defines a class named `class`.
+    pub fn is_class(&self, class: &str) -> bool {
+        self.classes.contains_key(class)
+    }
+
+    /// Names of all classes defined by the locale, sorted.
+    pub fn class_names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self.classes.keys().map(String::as_str).collect();
+        names.sort_unstable();
+        names
+    }
+
+    /// Test `c` for membership in `class`; false for undefined classes.
+    pub fn iswctype(&self, c: char, class: &str) -> bool {
+        let Some(ranges) = self.classes.get(class) else {
+            return false;
+        };
+        let c = c as u32;
+        match ranges.binary_search_by(|&(lo, _)| lo.cmp(&c)) {
+            Ok(_) => true,
+            Err(0) => false,
+            Err(i) => c <= ranges[i - 1].1,
+        }
+    }
+
+    /// All characters of `class` in code point order, or `None` if the
+    /// class is not defined.
+    pub fn class_chars(&self, class: &str) -> Option<Vec<char>> {
+        let ranges = self.classes.get(class)?;
+        Some(
+            ranges
+                .iter()
+                .flat_map(|&(lo, hi)| (lo..=hi).filter_map(char::from_u32))
+                .collect(),
+        )
+    }
+
+    /// Apply the mapping named `map` (e.g. `"toupper"`) to `c`; characters
+    /// without a mapping map to themselves.
+    pub fn towctrans(&self, c: char, map: &str) -> char {
+        self.maps
+            .get(map)
+            .and_then(|m| m.get(&(c as u32)))
+            .and_then(|&m| char::from_u32(m))
+            .unwrap_or(c)
+    }
+
+    pub fn towupper(&self, c: char) -> char {
+        self.towctrans(c, "toupper")
+    }
+
+    pub fn towlower(&self, c: char) -> char {
+        self.towctrans(c, "tolower")
+    }
+
+    /// Transliterations of `c`, most preferred first.
+    pub fn translit(&self, c: char) -> &[String] {
+        self.translit.get(&c).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    /// Replacement for characters without a transliteration.
+    pub fn default_missing(&self) -> Option<&str> {
+        self.default_missing.as_deref()
+    }
+}
+
+#[derive(Default)]
+struct Builder {
+    classes: HashMap<String, Vec<(u32, u32)>>,
+    maps: HashMap<String, HashMap<u32, u32>>,
+    translit: HashMap<char, Vec<String>>,
+    default_missing: Option<String>,
+    in_translit: bool,
+}
+
+fn resolve(name: &str, line: &super::Line) -> io::Result<u32> {
+    symbol_char(name)
+        .map(|c| c as u32)
+        .ok_or_else(|| line.error(format!("unknown symbol <{name}>")))
+}
+
+fn resolve_str(parts: &[StrPart], line: &super::Line) -> io::Result<String> {
+    parts
+        .iter()
+        .map(|part| match part {
+            StrPart::Char(c) => Ok(*c),
+            StrPart::Symbol(name) => {
+                symbol_char(name).ok_or_else(|| line.error(format!("unknown symbol <{name}>")))
+            }
+        })
+        .collect()
+}
+
+/// A single character operand: a symbol or a one-character word.
+fn char_operand(token: &Token, line: &super::Line) -> io::Result<u32> {
+    match token {
+        Token::Symbol(name) => resolve(name, line),
+        Token::Word(w) if w.chars().count() == 1 => Ok(w.chars().next().unwrap() as u32),
+        _ => Err(line.error("expected a character")),
+    }
+}
+
+/// Parse a `;`-separated character list with `..`, `..(n)..` and `...`
+/// ranges into inclusive ranges.
+fn parse_char_list(tokens: &[Token], line: &super::Line) -> io::Result<Vec<(u32, u32)>> {
+    let mut ranges: Vec<(u32, u32)> = Vec::new();
+    let mut pending_ellipsis = false;
+    for group in tokens.split(|t| *t == Token::Punct(';')) {
+        let mut add = |lo: u32, hi: u32, ranges: &mut Vec<(u32, u32)>| {
+            if pending_ellipsis {
+                // `<a>;...;<b>`: the range continues from the previous element
+                if let Some(&(_, prev)) = ranges.last() {
+                    ranges.push((prev, lo));
+                }
+                pending_ellipsis = false;
+            }
+            ranges.push((lo, hi));
+        };
+        match group {
+            [] => {}
+            [Token::Word(w)] if w == "..." || w == ".." => pending_ellipsis = true,
+            [a] => {
+                let c = char_operand(a, line)?;
+                add(c, c, &mut ranges);
+            }
+            [a, Token::Word(dots), b] if dots == ".." || dots == "..." => {
+                let lo = char_operand(a, line)?;
+                let hi = char_operand(b, line)?;
+                add(lo, hi, &mut ranges);
+            }
+            [a, Token::Word(d1), Token::Punct('('), Token::Word(step), Token::Punct(')'), Token::Word(d2), b]
+                if d1 == ".." && d2 == ".." =>
+            {
+                let lo = char_operand(a, line)?;
+                let hi = char_operand(b, line)?;
+                let step: u32 = step
+                    .parse()
+                    .ok()
+                    .filter(|&s| s > 0)
+                    .ok_or_else(|| line.error("bad range step"))?;
+                let mut c = lo;
+                while c <= hi {
+                    add(c, c, &mut ranges);
+                    c += step;
+                }
+            }
+            _ => return Err(line.error("bad character list")),
+        }
+    }
+    Ok(ranges)
+}
+
+/// Parse a list of `(<from>,<to>)` pairs.
+fn parse_map_list(tokens: &[Token], line: &super::Line) -> io::Result<Vec<(u32, u32)>> {
+    let mut pairs = Vec::new();
+    for group in tokens.split(|t| *t == Token::Punct(';')) {
+        match group {
+            [] => {}
+            [Token::Punct('('), a, Token::Punct(','), b, Token::Punct(')')] => {
+                pairs.push((char_operand(a, line)?, char_operand(b, line)?));
+            }
+            _ => return Err(line.error("bad character mapping")),
+        }
+    }
+    Ok(pairs)
+}
+
+/// Sort and coalesce ranges.
+fn normalize(ranges: &mut Vec<(u32, u32)>) {
+    ranges.retain(|&(lo, hi)| lo <= hi);
+    ranges.sort_unstable();
+    let mut out: Vec<(u32, u32)> = Vec::with_capacity(ranges.len());
+    for &(lo, hi) in ranges.iter() {
+        match out.last_mut() {
+            Some(last) if lo <= last.1.saturating_add(1) => last.1 = last.1.max(hi),
+            _ => out.push((lo, hi)),
+        }
+    }
+    *ranges = out;
+}
+
+impl Builder {
+    fn load_ctype(
+        &mut self,
+        name: &str,
+        loader: &mut SourceLoader,
+        depth: usize,
+        line: &super::Line,
+    ) -> io::Result<()> {
+        if depth >= MAX_DEPTH {
+            return Err(line.error("copy nested too deeply"));
+        }
+        let source = loader.load(name)?;
+        let category = source
+            .category("LC_CTYPE")
+            .ok_or_else(|| line.error(format!("'{name}' has no LC_CTYPE category")))?;
+        self.apply(category, loader, depth + 1)
+    }
+
+    fn apply(
+        &mut self,
+        category: &Category,
+        loader: &mut SourceLoader,
+        depth: usize,
+    ) -> io::Result<()> {
+        for line in &category.lines {
+            let operands = line.operands();
+            if self.in_translit {
+                self.apply_translit(line, loader, depth)?;
+                continue;
+            }
+            match line.keyword() {
+                Some("copy") => match operands {
+                    [Token::Str(name)] => {
+                        let name = super::str_parts_text(name);
+                        self.load_ctype(&name, loader, depth, line)?;
+                    }
+                    _ => return Err(line.error("bad copy directive")),
+                },
+                Some("translit_start") => self.in_translit = true,
+                Some("charclass") => {
+                    for token in operands {
+                        if let Token::Word(name) | Token::Symbol(name) = token {
+                            self.classes.entry(name.clone()).or_default();
+                        }
+                    }
+                }
+                Some("class") => match operands {
+                    [Token::Str(name), Token::Punct(';'), rest @ ..] => {
+                        let name = super::str_parts_text(name);
+                        let ranges = parse_char_list(rest, line)?;
+                        self.classes.entry(name).or_default().extend(ranges);
+                    }
+                    _ => return Err(line.error("bad class definition")),
+                },
+                Some("map") => match operands {
+                    [Token::Str(name), Token::Punct(';'), rest @ ..] => {
+                        let name = super::str_parts_text(name);
+                        let pairs = parse_map_list(rest, line)?;
+                        self.maps.entry(name).or_default().extend(pairs);
+                    }
+                    _ => return Err(line.error("bad map definition")),
+                },
+                Some(map @ ("toupper" | "tolower" | "totitle")) => {
+                    let pairs = parse_map_list(operands, line)?;
+                    self.maps.entry(map.to_string()).or_default().extend(pairs);
+                }
+                Some(class) if STANDARD_CLASSES.contains(&class) => {
+                    let ranges = parse_char_list(operands, line)?;
+                    self.classes
+                        .entry(class.to_string())
+                        .or_default()
+                        .extend(ranges);
+                }
+                Some(class) if self.classes.contains_key(class) => {
+                    let ranges = parse_char_list(operands, line)?;
+                    self.classes.get_mut(class).unwrap().extend(ranges);
+                }
+                // charconv, outdigit, width and the like are not used
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn apply_translit(
+        &mut self,
+        line: &super::Line,
+        loader: &mut SourceLoader,
+        depth: usize,
+    ) -> io::Result<()> {
+        let operands = line.operands();
+        match line.keyword() {
+            Some("translit_end") => self.in_translit = false,
+            Some("include") => match operands {
+                [Token::Str(name), ..] => {
+                    let name = super::str_parts_text(name);
+                    let in_translit = std::mem::replace(&mut self.in_translit, false);
+                    self.load_ctype(&name, loader, depth, line)?;
+                    self.in_translit = in_translit;
+                }
+                _ => return Err(line.error("bad include directive")),
+            },
+            Some("default_missing") => {
+                let mut s = String::new();
+                for token in operands {
+                    match token {
+                        Token::Symbol(name) => s.push(resolve_char(name, line)?),
+                        Token::Str(parts) => s.push_str(&resolve_str(parts, line)?),
+                        _ => return Err(line.error("bad default_missing")),
+                    }
+                }
+                self.default_missing = Some(s);
+            }
+            // translit_ignore and other directives are not used
+            Some(_) => {}
+            None => {
+                let (from, targets) = line.tokens.split_first().unwrap();
+                let from = match from {
+                    Token::Symbol(name) => resolve_char(name, line)?,
+                    Token::Str(parts) => {
+                        let s = resolve_str(parts, line)?;
+                        let mut chars = s.chars();
+                        match (chars.next(), chars.next()) {
+                            (Some(c), None) => c,
+                            // multi-character sources are not supported
+                            _ => return Ok(()),
+                        }
+                    }
+                    _ => return Err(line.error("bad transliteration")),
+                };
+                let mut list = Vec::new();
+                for group in targets.split(|t| *t == Token::Punct(';')) {
+                    let mut s = String::new();
+                    for token in group {
+                        match token {
+                            Token::Symbol(name) => s.push(resolve_char(name, line)?),
+                            Token::Str(parts) => s.push_str(&resolve_str(parts, line)?),
+                            _ => return Err(line.error("bad transliteration")),
+                        }
+                    }
+                    list.push(s);
+                }
+                // the first definition of a character wins
+                self.translit.entry(from).or_insert(list);
+            }
+        }
+        Ok(())
+    }
+
+    fn finish(mut self) -> Ctype {
+        for class in STANDARD_CLASSES {
+            self.classes.entry(class.to_string()).or_default();
+        }
+
+        // implied inclusions (XBD 7.3.1)
+        let mut union = |dst: &str, srcs: &[&str]| {
+            let mut extra: Vec<(u32, u32)> = Vec::new();
+            for src in srcs {
+                extra.extend_from_slice(&self.classes[*src]);
+            }
+            self.classes.get_mut(dst).unwrap().extend(extra);
+        };
+        union("xdigit", &["digit"]);
+        union("alpha", &["upper", "lower"]);
+        union("alnum", &["alpha", "digit"]);
+        union("graph", &["alnum", "punct", "xdigit"]);
+        union("space", &["blank"]);
+        for ranges in self.classes.values_mut() {
+            normalize(ranges);
+        }
+        let mut print = self.classes["graph"].clone();
+        print.push((' ' as u32, ' ' as u32));
+        self.classes.get_mut("print").unwrap().extend(print);
+        normalize(self.classes.get_mut("print").unwrap());
+
+        Ctype {
+            classes: self.classes,
+            maps: self.maps,
+            translit: self.translit,
+            default_missing: self.default_missing,
+        }
+    }
+}
+
+fn resolve_char(name: &str, line: &super::Line) -> io::Result<char> {
+    symbol_char(name).ok_or_else(|| line.error(format!("unknown symbol <{name}>")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn build(text: &str, extra: &[(&str, &str)]) -> Ctype {
+        let mut loader = SourceLoader::with_dirs(Vec::new());
+        for (name, src) in extra {
+            loader.insert(name, LocaleSource::parse(src).unwrap());
+        }
+        Ctype::from_source(&LocaleSource::parse(text).unwrap(), &mut loader).unwrap()
+    }
+
+    #[test]
+    fn test_posix() {
+        let ctype = Ctype::posix();
+        assert!(ctype.iswctype('A', "upper"));
+        assert!(ctype.iswctype('a', "alpha"));
+        assert!(ctype.iswctype('5', "alnum"));
+        assert!(ctype.iswctype('!', "graph"));
+        assert!(ctype.iswctype(' ', "print"));
+        assert!(!ctype.iswctype(' ', "graph"));
+        assert!(!ctype.iswctype('é', "alpha"));
+        assert_eq!(ctype.towupper('q'), 'Q');
+        assert_eq!(ctype.towlower('Q'), 'q');
+        assert_eq!(ctype.towupper('é'), 'é');
+        assert_eq!(
+            ctype.class_chars("space").unwrap(),
+            vec!['\t', '\n', '\x0b', '\x0c', '\r', ' ']
+        );
+        assert_eq!(ctype.class_chars("nonexistent"), None);
+    }
+
+    #[test]
+    fn test_from_source() {
+        let base = "LC_CTYPE\n\
+                    upper <U0041>..<U005A>;<U00C0>..<U00D6>\n\
+                    lower <U0061>..<U007A>;<U00E0>..<U00F6>\n\
+                    digit <U0030>..<U0039>\n\
+                    toupper (<U0061>,<U0041>);(<U00E9>,<U00C9>)\n\
+                    END LC_CTYPE\n";
+        let src = "comment_char %\nescape_char /\n\
+                   LC_CTYPE\n\
+                   copy \"base\"\n\
+                   upper <U0100>..(2)..<U0106>\n\
+                   class \"vowel\";<a>;<e>;<i>;<o>;<u>\n\
+                   translit_start\n\
+                   <U00E9> <U0065>\n\
+                   <U00C6> \"<U0041><U0045>\";<U0041>\n\
+                   default_missing <U003F>\n\
+                   translit_end\n\
+                   END LC_CTYPE\n";
+        let ctype = build(src, &[("base", base)]);
+        assert!(ctype.iswctype('Ç', "upper"));
+        assert!(ctype.iswctype('Ç', "alpha"));
+        assert!(ctype.iswctype('\u{102}', "upper"));
+        assert!(!ctype.iswctype('\u{103}', "upper"));
+        assert!(ctype.iswctype('ö', "alnum"));
+        assert!(ctype.iswctype('7', "graph"));
+        assert!(ctype.iswctype('e', "vowel"));
+        assert!(!ctype.iswctype('b', "vowel"));
+        assert_eq!(ctype.towupper('é'), 'É');
+        assert_eq!(ctype.translit('é'), ["e"]);
+        assert_eq!(ctype.translit('Æ'), ["AE", "A"]);
+        assert_eq!(ctype.default_missing(), Some("?"));
+    }
+
+    #[test]
+    fn test_posix_ellipsis() {
+        let src = "LC_CTYPE\nupper <A>;...;<D>;<Z>\nEND LC_CTYPE\n";
+        let ctype = build(src, &[]);
+        assert_eq!(
+            ctype.class_chars("upper").unwrap(),
+            vec!['A', 'B', 'C', 'D', 'Z']
+        );
+    }
+}
@@ -0,0 +1,816 @@
+//
+// Copyright (c) 2024 Hemi Labs, Inc.
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+//! The keywords of the LC_CTYPE, LC_MONETARY, LC_NUMERIC, LC_TIME and
+//! LC_MESSAGES categories, as reported by `locale`.
+//!
+//! Compiled locales are read in the glibc format: one file per category
+//! in `DIR/NAME/LC_*`, where `DIR` is each directory of `LOCPATH` or else
+//! `/usr/lib/locale`, whose `locale-archive` is searched first.  Any other
+//! locale, including one compiled by `localedef`, is read as a definition
+//! source.
+
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+use super::{
+    is_compiled, is_posix_locale, name_candidates, symbol_char, Category, LocaleSource,
+    SourceLoader, StrPart, Token,
+};
+
+/// Directory of compiled locales when `LOCPATH` is not set.
+pub const COMPILED_LOCALE_DIR: &str = "/usr/lib/locale";
+
+const ARCHIVE_NAME: &str = "locale-archive";
+const ARCHIVE_MAGIC: u32 = 0xde02_0109;
+
+/// Maximum nesting of `copy` directives.
+const MAX_DEPTH: usize = 16;
+
+/// The value of a keyword.  Strings are kept as bytes in the codeset of
+/// the locale.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Value {
+    Str(Vec<u8>),
+    Num(i32),
+    /// Strings written as one `;`-separated string, e.g. the day names.
+    Strs(Vec<Vec<u8>>),
+    /// Strings written as separate strings, e.g. the `era` entries.
+    List(Vec<Vec<u8>>),
+    /// Numbers such as `grouping`; empty for no grouping.
+    Nums(Vec<i32>),
+}
+
+impl Value {
+    /// The value as written by `locale` without `-k`, with list items
+    /// separated by `;`.
+    pub fn plain(&self) -> Vec<u8> {
+        match self {
+            Value::Str(s) => s.clone(),
+            Value::Num(n) => n.to_string().into_bytes(),
+            Value::Strs(list) | Value::List(list) => list.join(&b';'),
+            Value::Nums(list) if list.is_empty() => b"-1".to_vec(),
+            Value::Nums(list) => {
+                let list: Vec<String> = list.iter().map(i32::to_string).collect();
+                list.join(";").into_bytes()
+            }
+        }
+    }
+
+    /// The value as written after `keyword=` by `locale -k`: strings are
+    /// quoted and numbers are not.
+    pub fn quoted(&self) -> Vec<u8> {
+        let quote = |s: &[u8]| [b"\"".as_slice(), s, b"\""].concat();
+        match self {
+            Value::Str(s) => quote(s),
+            Value::Strs(list) => quote(&list.join(&b';')),
+            Value::List(list) => {
+                let list: Vec<Vec<u8>> = list.iter().map(|s| quote(s)).collect();
+                list.join(&b';')
+            }
+            Value::Num(_) | Value::Nums(_) => self.plain(),
+        }
+    }
+}
+
+/// How a keyword is stored in a compiled category.
+#[derive(Clone, Copy, Debug)]
+enum Kind {
+    Str,
+    /// A signed byte, `-1` when unspecified.
+    Byte,
+    /// A byte string of numbers.
+    Bytes,
+    /// A fixed number of strings in consecutive items.
+    Strs(usize),
+    /// As many strings as the item that follows `ERA_T_FMT` counts.
+    Era,
+    /// Up to 100 strings in one item.
+    AltDigits,
+}
+
+/// A keyword and where the compiled category holds it.
+#[derive(Debug)]
+pub struct Keyword {
+    pub name: &'static str,
+    pub category: Category,
+    kind: Kind,
+    item: usize,
+}
+
+const fn keyword(name: &'static str, category: Category, kind: Kind, item: usize) -> Keyword {
+    Keyword {
+        name,
+        category,
+        kind,
+        item,
+    }
+}
+
+/// The item holding the number of `era` entries.
+const ERA_NUM_ENTRIES: usize = 50;
+
+/// Every keyword, by category, in the order `locale` lists them.
+pub const KEYWORDS: &[Keyword] = &[
+    keyword("charmap", Category::Ctype, Kind::Str, 14),
+    keyword("decimal_point", Category::Numeric, Kind::Str, 0),
+    keyword("thousands_sep", Category::Numeric, Kind::Str, 1),
+    keyword("grouping", Category::Numeric, Kind::Bytes, 2),
+    keyword("abday", Category::Time, Kind::Strs(7), 0),
+    keyword("day", Category::Time, Kind::Strs(7), 7),
+    keyword("abmon", Category::Time, Kind::Strs(12), 14),
+    keyword("mon", Category::Time, Kind::Strs(12), 26),
+    keyword("am_pm", Category::Time, Kind::Strs(2), 38),
+    keyword("d_t_fmt", Category::Time, Kind::Str, 40),
+    keyword("d_fmt", Category::Time, Kind::Str, 41),
+    keyword("t_fmt", Category::Time, Kind::Str, 42),
+    keyword("t_fmt_ampm", Category::Time, Kind::Str, 43),
+    keyword("era", Category::Time, Kind::Era, 44),
+    keyword("era_year", Category::Time, Kind::Str, 45),
+    keyword("era_d_fmt", Category::Time, Kind::Str, 46),
+    keyword("alt_digits", Category::Time, Kind::AltDigits, 47),
+    keyword("era_d_t_fmt", Category::Time, Kind::Str, 48),
+    keyword("era_t_fmt", Category::Time, Kind::Str, 49),
+    keyword("date_fmt", Category::Time, Kind::Str, 108),
+    keyword("int_curr_symbol", Category::Monetary, Kind::Str, 0),
+    keyword("currency_symbol", Category::Monetary, Kind::Str, 1),
+    keyword("mon_decimal_point", Category::Monetary, Kind::Str, 2),
+    keyword("mon_thousands_sep", Category::Monetary, Kind::Str, 3),
+    keyword("mon_grouping", Category::Monetary, Kind::Bytes, 4),
+    keyword("positive_sign", Category::Monetary, Kind::Str, 5),
+    keyword("negative_sign", Category::Monetary, Kind::Str, 6),
+    keyword("int_frac_digits", Category::Monetary, Kind::Byte, 7),
+    keyword("frac_digits", Category::Monetary, Kind::Byte, 8),
+    keyword("p_cs_precedes", Category::Monetary, Kind::Byte, 9),
+    keyword("p_sep_by_space", Category::Monetary, Kind::Byte, 10),
+    keyword("n_cs_precedes", Category::Monetary, Kind::Byte, 11),
+    keyword("n_sep_by_space", Category::Monetary, Kind::Byte, 12),
+    keyword("p_sign_posn", Category::Monetary, Kind::Byte, 13),
+    keyword("n_sign_posn", Category::Monetary, Kind::Byte, 14),
+    keyword("crncystr", Category::Monetary, Kind::Str, 15),
+    keyword("int_p_cs_precedes", Category::Monetary, Kind::Byte, 16),
+    keyword("int_p_sep_by_space", Category::Monetary, Kind::Byte, 17),
+    keyword("int_n_cs_precedes", Category::Monetary, Kind::Byte, 18),
+    keyword("int_n_sep_by_space", Category::Monetary, Kind::Byte, 19),
+    keyword("int_p_sign_posn", Category::Monetary, Kind::Byte, 20),
+    keyword("int_n_sign_posn", Category::Monetary, Kind::Byte, 21),
+    keyword("yesexpr", Category::Messages, Kind::Str, 0),
+    keyword("noexpr", Category::Messages, Kind::Str, 1),
+    keyword("yesstr", Category::Messages, Kind::Str, 2),
+    keyword("nostr", Category::Messages, Kind::Str, 3),
+];
+
+/// Find a keyword by name.
+pub fn find_keyword(name: &str) -> Option<&'static Keyword> {
+    KEYWORDS.iter().find(|k| k.name == name)
+}
+
+/// The keywords of `category`.
+pub fn category_keywords(category: Category) -> impl Iterator<Item = &'static Keyword> {
+    KEYWORDS.iter().filter(move |k| k.category == category)
+}
+
+impl Category {
+    /// Look up a category by the name of its environment variable.
+    pub fn from_name(name: &str) -> Option<Category> {
+        Category::ALL.into_iter().find(|c| c.name() == name)
+    }
+
+    /// The category's number in glibc's compiled locales.
+    fn glibc_index(self) -> usize {
+        match self {
+            Category::Ctype => 0,
+            Category::Numeric => 1,
+            Category::Time => 2,
+            Category::Collate => 3,
+            Category::Monetary => 4,
+            Category::Messages => 5,
+        }
+    }
+
+    /// The magic number of the category's compiled file.
+    fn glibc_magic(self) -> u32 {
+        let index = self.glibc_index() as u32;
+        match self {
+            Category::Collate => 0x2005_1014 ^ index,
+            Category::Ctype => 0x2009_0720 ^ index,
+            _ => 0x2003_1115 ^ index,
+        }
+    }
+
+    /// The path of the category's file inside a compiled locale directory.
+    fn glibc_file(self) -> &'static str {
+        match self {
+            Category::Messages => "LC_MESSAGES/SYS_LC_MESSAGES",
+            _ => self.name(),
+        }
+    }
+}
+
+fn strings(list: &[&str]) -> Vec<Vec<u8>> {
+    list.iter().map(|s| s.as_bytes().to_vec()).collect()
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Option<u32> {
+    let bytes = data.get(offset..offset + 4)?;
+    Some(u32::from_ne_bytes(bytes.try_into().unwrap()))
+}
+
+/// The NUL-terminated string at `offset`, and the offset after its NUL.
+fn read_cstr(data: &[u8], offset: usize) -> Option<(Vec<u8>, usize)> {
+    let rest = data.get(offset..)?;
+    let len = rest.iter().position(|&b| b == 0)?;
+    Some((rest[..len].to_vec(), offset + len + 1))
+}
+
+fn invalid(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg.to_string())
+}
+
+/// The keyword values of one category of a locale.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LocaleInfo {
+    category: Category,
+    values: HashMap<&'static str, Value>,
+}
+
+impl LocaleInfo {
+    /// The category of the POSIX locale.
+    pub fn posix(category: Category) -> LocaleInfo {
+        let days = [
+            "Sunday",
+            "Monday",
+            "Tuesday",
+            "Wednesday",
+            "Thursday",
+            "Friday",
+            "Saturday",
+        ];
+        let months = [
+            "January",
+            "February",
+            "March",
+            "April",
+            "May",
+            "June",
+            "July",
+            "August",
+            "September",
+            "October",
+            "November",
+            "December",
+        ];
+        let abbreviate =
+            |names: &[&str]| names.iter().map(|n| n.as_bytes()[..3].to_vec()).collect();
+        let mut values = HashMap::new();
+        for keyword in category_keywords(category) {
+            let value = match (keyword.name, keyword.kind) {
+                ("charmap", _) => Value::Str("ANSI_X3.4-1968".as_bytes().to_vec()),
+                ("decimal_point", _) => Value::Str(".".as_bytes().to_vec()),
+                ("crncystr", _) => Value::Str("-".as_bytes().to_vec()),
+                ("abday", _) => Value::Strs(abbreviate(&days)),
+                ("day", _) => Value::Strs(strings(&days)),
+                ("abmon", _) => Value::Strs(abbreviate(&months)),
+                ("mon", _) => Value::Strs(strings(&months)),
+                ("am_pm", _) => Value::Strs(strings(&["AM", "PM"])),
+                ("d_t_fmt", _) => Value::Str("%a %b %e %H:%M:%S %Y".as_bytes().to_vec()),
+                ("d_fmt", _) => Value::Str("%m/%d/%y".as_bytes().to_vec()),
+                ("t_fmt", _) => Value::Str("%H:%M:%S".as_bytes().to_vec()),
+                ("t_fmt_ampm", _) => Value::Str("%I:%M:%S %p".as_bytes().to_vec()),
+                ("date_fmt", _) => Value::Str("%a %b %e %H:%M:%S %Z %Y".as_bytes().to_vec()),
+                ("yesexpr", _) => Value::Str("^[yY]".as_bytes().to_vec()),
+                ("noexpr", _) => Value::Str("^[nN]".as_bytes().to_vec()),
+                (_, Kind::Str) => Value::Str(Vec::new()),
+                (_, Kind::Byte) => Value::Num(-1),
+                (_, Kind::Bytes) => Value::Nums(Vec::new()),
+                (_, Kind::Strs(_)) => Value::Strs(Vec::new()),
+                (_, Kind::Era | Kind::AltDigits) => Value::List(Vec::new()),
+            };
+            values.insert(keyword.name, value);
+        }
+        LocaleInfo { category, values }
+    }
+
+    pub fn category(&self) -> Category {
+        self.category
+    }
+
+    /// The value of the keyword `name`, if it belongs to this category.
+    pub fn get(&self, name: &str) -> Option<&Value> {
+        self.values.get(name)
+    }
+
+    /// Read a category from a glibc compiled category file.
+    pub fn from_compiled(category: Category, data: &[u8]) -> io::Result<LocaleInfo> {
+        if read_u32(data, 0) != Some(category.glibc_magic()) {
+            return Err(invalid("not a compiled locale category"));
+        }
+        let count = read_u32(data, 4).ok_or_else(|| invalid("truncated locale category"))?;
+        let item = |index: usize| -> io::Result<usize> {
+            if index >= count as usize {
+                return Err(invalid("missing locale item"));
+            }
+            read_u32(data, 8 + 4 * index)
+                .map(|offset| offset as usize)
+                .ok_or_else(|| invalid("truncated locale category"))
+        };
+        let string = |offset: usize| -> io::Result<(Vec<u8>, usize)> {
+            read_cstr(data, offset).ok_or_else(|| invalid("unterminated locale string"))
+        };
+
+        let mut values = HashMap::new();
+        for keyword in category_keywords(category) {
+            let value = match keyword.kind {
+                Kind::Str => Value::Str(string(item(keyword.item)?)?.0),
+                Kind::Byte => {
+                    let byte = data.get(item(keyword.item)?).copied().unwrap_or(0);
+                    Value::Num(i32::from(byte as i8))
+                }
+                Kind::Bytes => {
+                    let start = item(keyword.item)?;
+                    let bytes = data.get(start..).unwrap_or_default();
+                    let bytes = bytes.split(|&b| b == 0).next().unwrap_or_default();
+                    Value::Nums(bytes.iter().map(|&b| i32::from(b as i8)).collect())
+                }
+                Kind::Strs(n) => Value::Strs(
+                    (keyword.item..keyword.item + n)
+                        .map(|i| Ok(string(item(i)?)?.0))
+                        .collect::<io::Result<_>>()?,
+                ),
+                Kind::Era => {
+                    let entries = read_u32(data, item(ERA_NUM_ENTRIES)?).unwrap_or(0);
+                    let mut offset = item(keyword.item)?;
+                    let mut list = Vec::new();
+                    for _ in 0..entries {
+                        let (s, next) = string(offset)?;
+                        list.push(s);
+                        offset = next;
+                    }
+                    Value::List(list)
+                }
+                Kind::AltDigits => {
+                    let (mut offset, end) = (item(keyword.item)?, item(keyword.item + 1)?);
+                    let mut list = Vec::new();
+                    while offset < end && list.len() < 100 {
+                        let (s, next) = string(offset)?;
+                        list.push(s);
+                        offset = next;
+                    }
+                    while list.last().is_some_and(Vec::is_empty) {
+                        list.pop();
+                    }
+                    Value::List(list)
+                }
+            };
+            values.insert(keyword.name, value);
+        }
+        Ok(LocaleInfo { category, values })
+    }
+
+    /// Read a category from a parsed locale definition source, loading
+    /// the sources named by `copy` through `loader`.  Keywords the source
+    /// leaves out keep their POSIX values.
+    pub fn from_source(
+        category: Category,
+        source: &LocaleSource,
+        loader: &mut SourceLoader,
+    ) -> io::Result<LocaleInfo> {
+        let mut info = LocaleInfo::posix(category);
+        let section = source
+            .category(category.name())
+            .ok_or_else(|| invalid(&format!("no {} category", category.name())))?;
+        info.apply(section, loader, 0)?;
+        Ok(info)
+    }
+
+    fn apply(
+        &mut self,
+        section: &super::SourceCategory,
+        loader: &mut SourceLoader,
+        depth: usize,
+    ) -> io::Result<()> {
+        for line in &section.lines {
+            let operands = line.operands();
+            let Some(name) = line.keyword() else {
+                continue;
+            };
+            if name == "copy" {
+                let [Token::Str(from)] = operands else {
+                    return Err(line.error("bad copy directive"));
+                };
+                if depth >= MAX_DEPTH {
+                    return Err(line.error("copy nested too deeply"));
+                }
+                let from = super::str_parts_text(from);
+                let source = loader.load(&from)?;
+                let copied = source.category(self.category.name()).ok_or_else(|| {
+                    line.error(format!("'{from}' has no {} category", self.category.name()))
+                })?;
+                self.apply(copied, loader, depth + 1)?;
+                continue;
+            }
+            let Some(keyword) = category_keywords(self.category).find(|k| k.name == name) else {
+                continue;
+            };
+            let mut texts = Vec::new();
+            let mut numbers = Vec::new();
+            for token in operands {
+                match token {
+                    Token::Str(parts) => texts.push(text(parts).into_bytes()),
+                    Token::Word(w) => numbers.push(
+                        w.parse::<i32>()
+                            .map_err(|_| line.error(format!("bad value for {name}")))?,
+                    ),
+                    Token::Punct(';') => {}
+                    _ => return Err(line.error(format!("bad value for {name}"))),
+                }
+            }
+            let value = match keyword.kind {
+                Kind::Str => Value::Str(texts.pop().unwrap_or_default()),
+                Kind::Byte => Value::Num(numbers.pop().unwrap_or(-1)),
+                Kind::Bytes => Value::Nums(numbers),
+                Kind::Strs(_) => Value::Strs(texts),
+                Kind::Era | Kind::AltDigits => Value::List(texts),
+            };
+            self.values.insert(keyword.name, value);
+        }
+        Ok(())
+    }
+
+    /// Load a category of the locale `name`: the POSIX locale, a compiled
+    /// locale, or else the locale's definition source.
+    pub fn load(category: Category, name: &str) -> io::Result<LocaleInfo> {
+        if is_posix_locale(name) {
+            return Ok(LocaleInfo::posix(category));
+        }
+        if let Some(data) = compiled_category(category, name) {
+            return LocaleInfo::from_compiled(category, &data);
+        }
+        let mut loader = SourceLoader::default();
+        let source = loader.load_locale(name)?;
+        let mut info = LocaleInfo::posix(category);
+        match category {
+            // The charmap is not part of a source, but `localedef` records
+            // it in the locales it compiles.  Other sources are read as
+            // UTF-8 unless the locale name gives a codeset.
+            Category::Ctype => {
+                let codeset = match source_charmap(&source) {
+                    Some(charmap) => charmap,
+                    None => name
+                        .split_once('.')
+                        .map_or("UTF-8", |(_, rest)| rest.split('@').next().unwrap_or(rest))
+                        .to_string(),
+                };
+                info.values
+                    .insert("charmap", Value::Str(codeset.into_bytes()));
+            }
+            Category::Collate => {}
+            _ => info = LocaleInfo::from_source(category, &source, &mut loader)?,
+        }
+        Ok(info)
+    }
+
+    /// A category of the locale `name`, or of the POSIX locale if it
+    /// cannot be loaded.
+    pub fn for_locale(category: Category, name: &str) -> LocaleInfo {
+        LocaleInfo::load(category, name).unwrap_or_else(|_| LocaleInfo::posix(category))
+    }
+}
+
+/// Resolve the symbolic names of a string, keeping unknown ones in their
+/// bracketed form.
+fn text(parts: &[StrPart]) -> String {
+    parts
+        .iter()
+        .map(|part| match part {
+            StrPart::Char(c) => c.to_string(),
+            StrPart::Symbol(name) => match symbol_char(name) {
+                Some(c) => c.to_string(),
+                None => format!("<{name}>"),
+            },
+        })
+        .collect()
+}
+
+/// The charmap named by a `charmap` line of the LC_CTYPE category.
+fn source_charmap(source: &LocaleSource) -> Option<String> {
+    let ctype = source.category(Category::Ctype.name())?;
+    ctype.lines.iter().find_map(|line| match line.operands() {
+        [Token::Str(parts)] if line.keyword() == Some("charmap") => Some(text(parts)),
+        _ => None,
+    })
+}
+
+/// glibc's normalized form of a codeset name: letters and digits only,
+/// lowercased, with `iso` in front of an all-digit name.
+fn normalize_codeset(codeset: &str) -> String {
+    let normalized: String = codeset
+        .chars()
+        .filter(char::is_ascii_alphanumeric)
+        .map(|c| c.to_ascii_lowercase())
+        .collect();
+    if normalized.chars().all(|c| c.is_ascii_digit()) {
+        format!("iso{normalized}")
+    } else {
+        normalized
+    }
+}
+
+/// The names a compiled locale may be stored under: the name as given,
+/// with its codeset normalized, then the shorter names.
+pub(super) fn compiled_candidates(name: &str) -> Vec<String> {
+    let mut names = Vec::new();
+    for candidate in name_candidates(name) {
+        let normalized = match candidate.split_once('.') {
+            Some((base, rest)) => {
+                let (codeset, modifier) = match rest.split_once('@') {
+                    Some((codeset, modifier)) => (codeset, format!("@{modifier}")),
+                    None => (rest, String::new()),
+                };
+                Some(format!("{base}.{}{modifier}", normalize_codeset(codeset)))
+            }
+            None => None,
+        };
+        names.push(candidate);
+        names.extend(normalized);
+    }
+    names.dedup();
+    names
+}
+
+/// The directories searched for compiled locales, and whether the
+/// locale archive applies: it is only used when `LOCPATH` is not set.
+pub(super) fn compiled_dirs() -> (Vec<PathBuf>, bool) {
+    match env::var_os("LOCPATH").filter(|p| !p.is_empty()) {
+        Some(paths) => (
+            env::split_paths(&paths)
+                .filter(|p| !p.as_os_str().is_empty())
+                .collect(),
+            false,
+        ),
+        None => (vec![PathBuf::from(COMPILED_LOCALE_DIR)], true),
+    }
+}
+
+/// The compiled data of `category` for the locale `name`, if any.
+fn compiled_category(category: Category, name: &str) -> Option<Vec<u8>> {
+    let candidates = compiled_candidates(name);
+    let (dirs, use_archive) = compiled_dirs();
+    if use_archive {
+        let archive = fs::read(PathBuf::from(COMPILED_LOCALE_DIR).join(ARCHIVE_NAME));
+        if let Ok(archive) = archive {
+            for candidate in &candidates {
+                if let Some(data) = archive_category(&archive, candidate, category) {
+                    return Some(data.to_vec());
+                }
+            }
+        }
+    }
+    candidates.iter().find_map(|candidate| {
+        dirs.iter().find_map(|dir| {
+            fs::read(dir.join(candidate).join(category.glibc_file()))
+                .ok()
+                .filter(|data| read_u32(data, 0) == Some(category.glibc_magic()))
+        })
+    })
+}
+
+/// The names of the locales in a glibc locale archive, and the offset of
+/// each one's record.
+fn archive_names(archive: &[u8]) -> Vec<(String, usize)> {
+    if read_u32(archive, 0) != Some(ARCHIVE_MAGIC) {
+        return Vec::new();
+    }
+    let (Some(table), Some(size)) = (read_u32(archive, 8), read_u32(archive, 16)) else {
+        return Vec::new();
+    };
+    let mut names = Vec::new();
+    for i in 0..size as usize {
+        // Each entry is the name's hash, its offset and its record offset.
+        let entry = table as usize + 12 * i;
+        let (Some(name), Some(record)) =
+            (read_u32(archive, entry + 4), read_u32(archive, entry + 8))
+        else {
+            break;
+        };
+        if name == 0 {
+            continue;
+        }
+        if let Some((name, _)) = read_cstr(archive, name as usize) {
+            names.push((String::from_utf8_lossy(&name).into_owned(), record as usize));
+        }
+    }
+    names
+}
+
+/// The data of `category` for the locale `name` in a glibc locale
+/// archive.
+fn archive_category<'a>(archive: &'a [u8], name: &str, category: Category) -> Option<&'a [u8]> {
+    let (_, record) = archive_names(archive)
+        .into_iter()
+        .find(|(n, _)| n == name)?;
+    // A record is a reference count, then an offset and a length for
+    // each category.
+    let slot = record + 4 + 8 * category.glibc_index();
+    let offset = read_u32(archive, slot)? as usize;
+    let len = read_u32(archive, slot + 4)? as usize;
+    archive.get(offset..offset.checked_add(len)?)
+}
+
+/// The names of the compiled locales, with `C` and `POSIX`, sorted.
+pub fn available_locales() -> Vec<String> {
+    let mut names = vec![String::from("C"), String::from("POSIX")];
+    let (dirs, use_archive) = compiled_dirs();
+    if use_archive {
+        if let Ok(archive) = fs::read(PathBuf::from(COMPILED_LOCALE_DIR).join(ARCHIVE_NAME)) {
+            names.extend(archive_names(&archive).into_iter().map(|(name, _)| name));
+        }
+    }
+    for dir in dirs {
+        let Ok(entries) = fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.join(Category::Ctype.glibc_file()).is_file() || is_compiled(&path) {
+                names.push(entry.file_name().to_string_lossy().into_owned());
+            }
+        }
+    }
+    names.sort();
+    names.dedup();
+    names
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Build a compiled category file holding `items`.
+    fn compiled(category: Category, items: &[&[u8]]) -> Vec<u8> {
+        let mut data = Vec::new();
+        data.extend_from_slice(&category.glibc_magic().to_ne_bytes());
+        data.extend_from_slice(&(items.len() as u32).to_ne_bytes());
+        let mut offset = 8 + 4 * items.len();
+        for item in items {
+            data.extend_from_slice(&(offset as u32).to_ne_bytes());
+            offset += item.len();
+        }
+        for item in items {
+            data.extend_from_slice(item);
+        }
+        data
+    }
+
+    #[test]
+    fn test_value_format() {
+        let days = Value::Strs(strings(&["Mo", "Di"]));
+        assert_eq!(days.quoted(), b"\"Mo;Di\"");
+        assert_eq!(days.plain(), b"Mo;Di");
+        let era = Value::List(strings(&["a", "b"]));
+        assert_eq!(era.quoted(), b"\"a\";\"b\"");
+        assert_eq!(Value::List(Vec::new()).quoted(), b"");
+        assert_eq!(Value::Nums(vec![3, 3]).quoted(), b"3;3");
+        assert_eq!(Value::Nums(Vec::new()).quoted(), b"-1");
+        assert_eq!(Value::Num(-1).quoted(), b"-1");
+        assert_eq!(Value::Str(b",".to_vec()).quoted(), b"\",\"");
+    }
+
+    #[test]
+    fn test_posix() {
+        let info = LocaleInfo::posix(Category::Numeric);
+        assert_eq!(info.get("decimal_point"), Some(&Value::Str(".".into())));
+        assert_eq!(info.get("grouping"), Some(&Value::Nums(Vec::new())));
+        assert_eq!(info.get("yesexpr"), None);
+        let info = LocaleInfo::posix(Category::Time);
+        assert_eq!(
+            info.get("abday").unwrap().plain(),
+            b"Sun;Mon;Tue;Wed;Thu;Fri;Sat"
+        );
+        assert_eq!(info.get("era"), Some(&Value::List(Vec::new())));
+    }
+
+    #[test]
+    fn test_compiled() {
+        let data = compiled(
+            Category::Numeric,
+            &[
+                b",\0",
+                b".\0",
+                b"\x03\x03\0\0",
+                b"\0\0\0\0",
+                b"\0\0\0\0",
+                b"UTF-8\0",
+            ],
+        );
+        let info = LocaleInfo::from_compiled(Category::Numeric, &data).unwrap();
+        assert_eq!(info.get("decimal_point"), Some(&Value::Str(",".into())));
+        assert_eq!(info.get("thousands_sep"), Some(&Value::Str(".".into())));
+        assert_eq!(info.get("grouping"), Some(&Value::Nums(vec![3, 3])));
+
+        assert!(LocaleInfo::from_compiled(Category::Monetary, &data).is_err());
+        assert!(LocaleInfo::from_compiled(Category::Numeric, &data[..12]).is_err());
+    }
+
+    #[test]
+    fn test_archive() {
+        let category = compiled(
+            Category::Messages,
+            &[b"^[jJ]\0", b"^[nN]\0", b"ja\0", b"nein\0"],
+        );
+        // The header, one name entry, the name, then the record.
+        let mut archive = vec![0u8; 56];
+        archive[0..4].copy_from_slice(&ARCHIVE_MAGIC.to_ne_bytes());
+        archive[8..12].copy_from_slice(&56u32.to_ne_bytes());
+        archive[16..20].copy_from_slice(&1u32.to_ne_bytes());
+        let name_offset = 56 + 12;
+        let record_offset = name_offset + 8;
+        let data_offset = record_offset + 4 + 8 * 13;
+        for value in [0, name_offset, record_offset] {
+            archive.extend_from_slice(&(value as u32).to_ne_bytes());
+        }
+        archive.extend_from_slice(b"de_DE\0\0\0");
+        archive.extend_from_slice(&1u32.to_ne_bytes());
+        for i in 0..13 {
+            let (offset, len) = if i == Category::Messages.glibc_index() {
+                (data_offset, category.len())
+            } else {
+                (0, 0)
+            };
+            archive.extend_from_slice(&(offset as u32).to_ne_bytes());
+            archive.extend_from_slice(&(len as u32).to_ne_bytes());
+        }
+        archive.extend_from_slice(&category);
+
+        assert_eq!(
+            archive_names(&archive),
+            vec![(String::from("de_DE"), record_offset)]
+        );
+        let data = archive_category(&archive, "de_DE", Category::Messages).unwrap();
+        let info = LocaleInfo::from_compiled(Category::Messages, data).unwrap();
+        assert_eq!(info.get("yesstr"), Some(&Value::Str("ja".into())));
+        assert_eq!(
+            archive_category(&archive, "fr_FR", Category::Messages),
+            None
+        );
+    }
+
+    #[test]
+    fn test_source() {
+        let mut loader = SourceLoader::with_dirs(Vec::new());
+        loader.insert(
+            "base",
+            LocaleSource::parse(
+                "LC_NUMERIC\ndecimal_point \"<U002C>\"\nthousands_sep \".\"\nEND LC_NUMERIC\n",
+            )
+            .unwrap(),
+        );
+        let source = LocaleSource::parse(
+            "LC_NUMERIC\ncopy \"base\"\ngrouping 3;3\nEND LC_NUMERIC\n\
+             LC_TIME\nabday \"So\";\"Mo\";\"Di\";\"Mi\";\"Do\";\"Fr\";\"Sa\"\n\
+             d_fmt \"%d.%m.%Y\"\nEND LC_TIME\n",
+        )
+        .unwrap();
+
+        let info = LocaleInfo::from_source(Category::Numeric, &source, &mut loader).unwrap();
+        assert_eq!(info.get("decimal_point"), Some(&Value::Str(",".into())));
+        assert_eq!(info.get("thousands_sep"), Some(&Value::Str(".".into())));
+        assert_eq!(info.get("grouping"), Some(&Value::Nums(vec![3, 3])));
+
+        let info = LocaleInfo::from_source(Category::Time, &source, &mut loader).unwrap();
+        assert_eq!(info.get("abday").unwrap().plain(), b"So;Mo;Di;Mi;Do;Fr;Sa");
+        assert_eq!(info.get("d_fmt"), Some(&Value::Str("%d.%m.%Y".into())));
+        assert_eq!(info.get("t_fmt"), Some(&Value::Str("%H:%M:%S".into())));
+
+        assert!(LocaleInfo::from_source(Category::Monetary, &source, &mut loader).is_err());
+    }
+
+    #[test]
+    fn test_compiled_candidates() {
+        assert_eq!(
+            compiled_candidates("de_DE.UTF-8"),
+            vec!["de_DE.UTF-8", "de_DE.utf8", "de_DE"]
+        );
+        assert_eq!(
+            compiled_candidates("fr_FR.ISO-8859-1@euro"),
+            vec![
+                "fr_FR.ISO-8859-1@euro",
+                "fr_FR.iso88591@euro",
+                "fr_FR@euro",
+                "fr_FR.ISO-8859-1",
+                "fr_FR.iso88591",
+                "fr_FR"
+            ]
+        );
+        assert_eq!(normalize_codeset("8859-1"), "iso88591");
+    }
+}
@@ -0,0 +1,391 @@
+//
+// Copyright (c) 2024 Hemi Labs, Inc.
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+//! Locale support: locale name resolution from the environment, the
+//! LC_CTYPE and LC_COLLATE categories loaded from locale definition
+//! sources, and the keywords reported by `locale`.
+//!
+//! The `C` and `POSIX` locales are built in.  Any other locale is looked
+//! up as a locale compiled by `localedef`, then as a definition source
+//! file (the format `localedef` reads) in the directories named by
+//! `I18NPATH` (with `/locales` appended) and then in
+//! `/usr/share/i18n/locales`.  A locale that cannot be loaded behaves as
+//! the `C` locale, the same as a failed `setlocale()`.
+
+mod collate;
+mod compiled;
+mod ctype;
+mod info;
+mod source;
+
+pub use collate::Collation;
+pub use compiled::{
+    compiled_path, find_compiled, is_compiled, load_compiled, write_category, COMPILED_HEADER,
+};
+pub use ctype::Ctype;
+pub use info::{
+    available_locales, category_keywords, find_keyword, Keyword, LocaleInfo, Value,
+    COMPILED_LOCALE_DIR, KEYWORDS,
+};
+pub use source::{str_parts_text, Category as SourceCategory, Line, LocaleSource, StrPart, Token};
+
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::env;
+use std::io;
+use std::path::PathBuf;
+use std::rc::Rc;
+use std::sync::OnceLock;
+
+/// Default directory of locale definition sources.
+pub const LOCALE_SOURCE_DIR: &str = "/usr/share/i18n/locales";
+
+/// Default directory of charmap files.
+pub const CHARMAP_DIR: &str = "/usr/share/i18n/charmaps";
+
+/// Locale categories, as selected by the `LC_*` environment variables.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Category {
+    Ctype,
+    Collate,
+    Messages,
+    Monetary,
+    Numeric,
+    Time,
+}
+
+impl Category {
+    pub const ALL: [Category; 6] = [
+        Category::Ctype,
+        Category::Collate,
+        Category::Messages,
+        Category::Monetary,
+        Category::Numeric,
+        Category::Time,
+    ];
+
+    /// The name of the category and of its environment variable.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Category::Ctype => "LC_CTYPE",
+            Category::Collate => "LC_COLLATE",
+            Category::Messages => "LC_MESSAGES",
+            Category::Monetary => "LC_MONETARY",
+            Category::Numeric => "LC_NUMERIC",
+            Category::Time => "LC_TIME",
+        }
+    }
+}
+
+fn env_nonempty(name: &str) -> Option<String> {
+    env::var(name).ok().filter(|v| !v.is_empty())
+}
+
+/// The locale name in effect for `category`: `LC_ALL`, then the
+/// category's own variable, then `LANG`, then `"C"`.
+pub fn locale_name(category: Category) -> String {
+    env_nonempty("LC_ALL")
+        .or_else(|| env_nonempty(category.name()))
+        .or_else(|| env_nonempty("LANG"))
+        .unwrap_or_else(|| String::from("C"))
+}
+
+/// Whether `name` designates the built-in POSIX locale.
+pub fn is_posix_locale(name: &str) -> bool {
+    name == "C" || name == "POSIX"
+}
+
+/// Directories searched for locale definition sources.
+pub fn source_dirs() -> Vec<PathBuf> {
+    let mut dirs: Vec<PathBuf> = match env::var_os("I18NPATH") {
+        Some(paths) => env::split_paths(&paths)
+            .filter(|p| !p.as_os_str().is_empty())
+            .map(|p| p.join("locales"))
+            .collect(),
+        None => Vec::new(),
+    };
+    dirs.push(PathBuf::from(LOCALE_SOURCE_DIR));
+    dirs
+}
+
+/// Directories searched for charmap files: each directory of
+/// `I18NPATH` with `/charmaps` appended, then [`CHARMAP_DIR`].
+pub fn charmap_dirs() -> Vec<PathBuf> {
+    let mut dirs: Vec<PathBuf> = match env::var_os("I18NPATH") {
+        Some(paths) => env::split_paths(&paths)
+            .filter(|p| !p.as_os_str().is_empty())
+            .map(|p| p.join("charmaps"))
+            .collect(),
+        None => Vec::new(),
+    };
+    dirs.push(PathBuf::from(CHARMAP_DIR));
+    dirs
+}
+
+/// Candidate source names for a locale name of the form
+/// `language[_territory][.codeset][@modifier]`, most specific first.
+fn name_candidates(name: &str) -> Vec<String> {
+    let (base, modifier) = match name.split_once('@') {
+        Some((b, m)) => (b, Some(m)),
+        None => (name, None),
+    };
+    let stripped = base.split_once('.').map(|(b, _)| b);
+
+    let mut names = vec![name.to_string()];
+    if let (Some(stripped), Some(modifier)) = (stripped, modifier) {
+        names.push(format!("{stripped}@{modifier}"));
+    }
+    if modifier.is_some() {
+        names.push(base.to_string());
+    }
+    if let Some(stripped) = stripped {
+        names.push(stripped.to_string());
+    }
+    names
+}
+
+/// Loads locale definition sources by name, for `copy` and `include`
+/// directives, caching each file.
+pub struct SourceLoader {
+    dirs: Vec<PathBuf>,
+    cache: HashMap<String, Rc<LocaleSource>>,
+}
+
+impl Default for SourceLoader {
+    fn default() -> Self {
+        SourceLoader::with_dirs(source_dirs())
+    }
+}
+
+impl SourceLoader {
+    pub fn with_dirs(dirs: Vec<PathBuf>) -> SourceLoader {
+        SourceLoader {
+            dirs,
+            cache: HashMap::new(),
+        }
+    }
+
+    /// Register an in-memory source under `name`.
+    pub fn insert(&mut self, name: &str, source: LocaleSource) {
+        self.cache.insert(name.to_string(), Rc::new(source));
+    }
+
+    /// Path of the source file for `name`: a name containing a slash is
+    /// used as a path as-is.
+    pub fn find(&self, name: &str) -> Option<PathBuf> {
+        if name.contains('/') {
+            let path = PathBuf::from(name);
+            return path.is_file().then_some(path);
+        }
+        self.dirs
+            .iter()
+            .map(|dir| dir.join(name))
+            .find(|path| path.is_file())
+    }
+
+    /// Load the source named `name`.
+    pub fn load(&mut self, name: &str) -> io::Result<Rc<LocaleSource>> {
+        if let Some(source) = self.cache.get(name) {
+            return Ok(source.clone());
+        }
+        let path = self.find(name).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("cannot find locale definition '{name}'"),
+            )
+        })?;
+        let source = Rc::new(LocaleSource::load(&path)?);
+        self.cache.insert(name.to_string(), source.clone());
+        Ok(source)
+    }
+
+    /// Load the source for a locale name: the compiled locale if there is
+    /// one, else the source, trying the name with the codeset and
+    /// modifier removed if the full name is not found.
+    pub fn load_locale(&mut self, name: &str) -> io::Result<Rc<LocaleSource>> {
+        if let Some(dir) = find_compiled(name) {
+            return Ok(Rc::new(load_compiled(&dir)?));
+        }
+        let mut last_err = None;
+        for candidate in name_candidates(name) {
+            match self.load(&candidate) {
+                Ok(source) => return Ok(source),
+                Err(e) if e.kind() == io::ErrorKind::NotFound => last_err = Some(e),
+                Err(e) => return Err(e),
+            }
+        }
+        Err(last_err.unwrap())
+    }
+}
+
+/// Names of the POSIX portable character set (XBD 6.1), usable as
+/// symbolic names such as `<hyphen>` or `[.hyphen.]`.
+pub const PORTABLE_CHARSET_NAMES: &[(&str, char)] = &[
+    ("NUL", '\0'),
+    ("alert", '\x07'),
+    ("backspace", '\x08'),
+    ("tab", '\t'),
+    ("newline", '\n'),
+    ("vertical-tab", '\x0b'),
+    ("form-feed", '\x0c'),
+    ("carriage-return", '\r'),
+    ("space", ' '),
+    ("exclamation-mark", '!'),
+    ("quotation-mark", '"'),
+    ("number-sign", '#'),
+    ("dollar-sign", '$'),
+    ("percent-sign", '%'),
+    ("ampersand", '&'),
+    ("apostrophe", '\''),
+    ("left-parenthesis", '('),
+    ("right-parenthesis", ')'),
+    ("asterisk", '*'),
+    ("plus-sign", '+'),
+    ("comma", ','),
+    ("hyphen", '-'),
+    ("hyphen-minus", '-'),
+    ("period", '.'),
+    ("full-stop", '.'),
+    ("slash", '/'),
+    ("solidus", '/'),
+    ("zero", '0'),
+    ("one", '1'),
+    ("two", '2'),
+    ("three", '3'),
+    ("four", '4'),
+    ("five", '5'),
+    ("six", '6'),
+    ("seven", '7'),
+    ("eight", '8'),
+    ("nine", '9'),
+    ("colon", ':'),
+    ("semicolon", ';'),
+    ("less-than-sign", '<'),
+    ("equals-sign", '='),
+    ("greater-than-sign", '>'),
+    ("question-mark", '?'),
+    ("commercial-at", '@'),
+    ("left-square-bracket", '['),
+    ("backslash", '\\'),
+    ("reverse-solidus", '\\'),
+    ("right-square-bracket", ']'),
+    ("circumflex", '^'),
+    ("circumflex-accent", '^'),
+    ("underscore", '_'),
+    ("low-line", '_'),
+    ("grave-accent", '`'),
+    ("left-brace", '{'),
+    ("left-curly-bracket", '{'),
+    ("vertical-line", '|'),
+    ("right-brace", '}'),
+    ("right-curly-bracket", '}'),
+    ("tilde", '~'),
+    ("DEL", '\x7f'),
+];
+
+/// Resolve a symbolic character name: `Uxxxx`/`Uxxxxxxxx` Unicode code
+/// points, portable character set names, or a single character.
+pub fn symbol_char(name: &str) -> Option<char> {
+    if let Some(hex) = name.strip_prefix('U') {
+        if (hex.len() == 4 || hex.len() == 8) && hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            return char::from_u32(u32::from_str_radix(hex, 16).ok()?);
+        }
+    }
+    let mut chars = name.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        return Some(c);
+    }
+    PORTABLE_CHARSET_NAMES
+        .iter()
+        .find(|(n, _)| *n == name)
+        .map(|&(_, c)| c)
+}
+
+/// Code assigned to a byte that is not part of a valid UTF-8 sequence;
+/// above the Unicode range so it cannot collide with a character.
+const RAW_BYTE_BASE: u32 = 0x11_0000;
+
+/// Decode UTF-8 text into character codes, mapping each invalid byte to
+/// `RAW_BYTE_BASE + byte`.
+fn decode(s: &[u8]) -> Vec<u32> {
+    let mut codes = Vec::with_capacity(s.len());
+    for chunk in s.utf8_chunks() {
+        codes.extend(chunk.valid().chars().map(|c| c as u32));
+        codes.extend(chunk.invalid().iter().map(|&b| RAW_BYTE_BASE + b as u32));
+    }
+    codes
+}
+
+static CTYPE: OnceLock<Ctype> = OnceLock::new();
+static COLLATION: OnceLock<Collation> = OnceLock::new();
+
+/// The LC_CTYPE data of the locale selected by the environment.
+pub fn ctype() -> &'static Ctype {
+    CTYPE.get_or_init(|| Ctype::for_locale(&locale_name(Category::Ctype)))
+}
+
+/// The LC_COLLATE data of the locale selected by the environment.
+pub fn collation() -> &'static Collation {
+    COLLATION.get_or_init(|| Collation::for_locale(&locale_name(Category::Collate)))
+}
+
+/// Compare two strings according to the current LC_COLLATE.
+pub fn strcoll(a: &[u8], b: &[u8]) -> Ordering {
+    collation().strcoll(a, b)
+}
+
+/// Transform a string into a key whose ordering matches [`strcoll`].
+pub fn strxfrm(s: &[u8]) -> Vec<u32> {
+    collation().strxfrm(s)
+}
+
+/// Test `c` for membership in the LC_CTYPE class `class`, e.g. `"alpha"`.
+pub fn iswctype(c: char, class: &str) -> bool {
+    ctype().iswctype(c, class)
+}
+
+/// Map `c` to uppercase according to LC_CTYPE.
+pub fn towupper(c: char) -> char {
+    ctype().towupper(c)
+}
+
+/// Map `c` to lowercase according to LC_CTYPE.
+pub fn towlower(c: char) -> char {
+    ctype().towlower(c)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_name_candidates() {
+        assert_eq!(
+            name_candidates("en_US.UTF-8@euro"),
+            vec!["en_US.UTF-8@euro", "en_US@euro", "en_US.UTF-8", "en_US"]
+        );
+        assert_eq!(name_candidates("de_DE.UTF-8"), vec!["de_DE.UTF-8", "de_DE"]);
+        assert_eq!(name_candidates("fr_FR"), vec!["fr_FR"]);
+    }
+
+    #[test]
+    fn test_symbol_char() {
+        assert_eq!(symbol_char("U0041"), Some('A'));
+        assert_eq!(symbol_char("U0001F600"), Some('\u{1F600}'));
+        assert_eq!(symbol_char("hyphen"), Some('-'));
+        assert_eq!(symbol_char("a"), Some('a'));
+        assert_eq!(symbol_char("BASE"), None);
+    }
+
+    #[test]
+    fn test_decode() {
+        assert_eq!(decode("aé".as_bytes()), vec![0x61, 0xe9]);
+        assert_eq!(decode(b"a\xffb"), vec![0x61, RAW_BYTE_BASE + 0xff, 0x62]);
+    }
+}
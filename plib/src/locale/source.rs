@@ -0,0 +1,501 @@
+//
+// Copyright (c) 2024 Hemi Labs, Inc.
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+//! Reader for the POSIX locale definition source format (see the
+//! "Locale Definition" section of XBD 7.3), as used by `localedef` and
+//! the glibc `i18n/locales` directory.
+//!
+//! The source is split into categories (`LC_CTYPE` ... `END LC_CTYPE`),
+//! each a list of logical lines made of [`Token`]s.  Interpretation of the
+//! keywords is left to the category modules.
+
+use std::collections::HashSet;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// A lexical element of a locale definition line.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Token {
+    /// A symbolic name, e.g. `<U0041>` or `<space>`, without the brackets.
+    Symbol(String),
+    /// A quoted string.
+    Str(Vec<StrPart>),
+    /// Any other run of characters: keywords, numbers, `..`, `IGNORE`.
+    Word(String),
+    /// One of `;`, `,`, `(` or `)`.
+    Punct(char),
+}
+
+/// A piece of a quoted string: symbolic names are kept unresolved.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum StrPart {
+    Symbol(String),
+    Char(char),
+}
+
+/// One logical line, with continuation lines joined and comments removed.
+#[derive(Clone, Debug)]
+pub struct Line {
+    /// Physical line number (1-based) where the logical line starts.
+    pub lineno: usize,
+    pub tokens: Vec<Token>,
+}
+
+impl Line {
+    /// The leading keyword of the line, if it starts with a word.
+    pub fn keyword(&self) -> Option<&str> {
+        match self.tokens.first() {
+            Some(Token::Word(w)) => Some(w),
+            _ => None,
+        }
+    }
+
+    /// The operands of the line: all tokens after the keyword.
+    pub fn operands(&self) -> &[Token] {
+        self.tokens.get(1..).unwrap_or_default()
+    }
+
+    /// Build an `InvalidData` error that points at this line.
+    pub fn error(&self, msg: impl std::fmt::Display) -> io::Error {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("line {}: {}", self.lineno, msg),
+        )
+    }
+}
+
+/// The body of one category section.
+#[derive(Clone, Debug)]
+pub struct Category {
+    pub name: String,
+    pub lines: Vec<Line>,
+}
+
+impl Category {
+    /// If the category consists of (or starts with) `copy "name"`, return
+    /// the name of the locale to copy from.
+    pub fn copy_from(&self) -> Option<String> {
+        let line = self.lines.first()?;
+        match (line.keyword(), line.operands()) {
+            (Some("copy"), [Token::Str(parts)]) => Some(str_parts_text(parts)),
+            _ => None,
+        }
+    }
+}
+
+/// A parsed locale definition source file.
+#[derive(Clone, Debug, Default)]
+pub struct LocaleSource {
+    pub categories: Vec<Category>,
+}
+
+impl LocaleSource {
+    /// Parse locale definition source text.
+    pub fn parse(text: &str) -> io::Result<LocaleSource> {
+        let lines = tokenize(text)?;
+        let lines = preprocess(lines)?;
+
+        let mut categories = Vec::new();
+        let mut current: Option<Category> = None;
+        for line in lines {
+            let keyword = line.keyword().unwrap_or_default().to_string();
+            match current.as_mut() {
+                None => {
+                    if keyword.starts_with("LC_") && line.tokens.len() == 1 {
+                        current = Some(Category {
+                            name: keyword,
+                            lines: Vec::new(),
+                        });
+                    }
+                    // anything else outside a category is ignored
+                }
+                Some(cat) => {
+                    if keyword == "END" {
+                        match line.operands() {
+                            [Token::Word(name)] if *name == cat.name => {
+                                categories.push(current.take().unwrap());
+                            }
+                            _ => return Err(line.error(format!("expected END {}", cat.name))),
+                        }
+                    } else {
+                        cat.lines.push(line);
+                    }
+                }
+            }
+        }
+        if let Some(cat) = current {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("missing END {}", cat.name),
+            ));
+        }
+
+        Ok(LocaleSource { categories })
+    }
+
+    /// Read and parse a locale definition source file.
+    pub fn load(path: &Path) -> io::Result<LocaleSource> {
+        let bytes = fs::read(path)?;
+        LocaleSource::parse(&String::from_utf8_lossy(&bytes))
+            .map_err(|e| io::Error::new(e.kind(), format!("{}: {}", path.display(), e)))
+    }
+
+    /// Find a category by name, e.g. `"LC_CTYPE"`.
+    pub fn category(&self, name: &str) -> Option<&Category> {
+        self.categories.iter().find(|c| c.name == name)
+    }
+}
+
+/// Concatenate the literal characters of a string, keeping symbolic names
+/// in their bracketed form.  Used for names such as `copy "i18n"`.
+pub fn str_parts_text(parts: &[StrPart]) -> String {
+    let mut s = String::new();
+    for part in parts {
+        match part {
+            StrPart::Char(c) => s.push(*c),
+            StrPart::Symbol(name) => {
+                s.push('<');
+                s.push_str(name);
+                s.push('>');
+            }
+        }
+    }
+    s
+}
+
+/// Split the source into logical lines of tokens.
+fn tokenize(text: &str) -> io::Result<Vec<Line>> {
+    let mut comment_char = '#';
+    let mut escape_char = '\\';
+
+    let mut lines = Vec::new();
+    let mut tokens: Vec<Token> = Vec::new();
+    let mut start_lineno = 0;
+    let mut continued = false;
+    // a string continued on the next line
+    let mut string: Option<Vec<StrPart>> = None;
+
+    for (idx, phys) in text.lines().enumerate() {
+        let lineno = idx + 1;
+        let trimmed = phys.trim_start();
+
+        if !continued {
+            // the comment and escape characters may only be redefined
+            // before they are used
+            let mut words = trimmed.split_whitespace();
+            match (words.next(), words.next(), words.next()) {
+                (Some("comment_char"), Some(c), None) if c.chars().count() == 1 => {
+                    comment_char = c.chars().next().unwrap();
+                    continue;
+                }
+                (Some("escape_char"), Some(c), None) if c.chars().count() == 1 => {
+                    escape_char = c.chars().next().unwrap();
+                    continue;
+                }
+                _ => {}
+            }
+            start_lineno = lineno;
+        }
+        if string.is_none() && trimmed.starts_with(comment_char) {
+            continue;
+        }
+
+        continued = false;
+        let mut word = String::new();
+        let mut chars = phys.chars().peekable();
+        let err = |msg: &str| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("line {}: {}", lineno, msg),
+            )
+        };
+        if let Some(mut parts) = string.take() {
+            if read_string(&mut chars, escape_char, &mut parts).map_err(err)? {
+                tokens.push(Token::Str(parts));
+            } else {
+                string = Some(parts);
+                continued = true;
+                continue;
+            }
+        }
+        let flush = |word: &mut String, tokens: &mut Vec<Token>| {
+            if !word.is_empty() {
+                tokens.push(Token::Word(std::mem::take(word)));
+            }
+        };
+
+        while let Some(c) = chars.next() {
+            if c == escape_char {
+                match chars.next() {
+                    None => {
+                        continued = true;
+                        break;
+                    }
+                    Some(n) if n.is_whitespace() && chars.clone().all(char::is_whitespace) => {
+                        continued = true;
+                        break;
+                    }
+                    Some(n) => word.push(n),
+                }
+            } else if c == comment_char {
+                break;
+            } else if c.is_whitespace() {
+                flush(&mut word, &mut tokens);
+            } else if c == '<' {
+                flush(&mut word, &mut tokens);
+                let mut name = String::new();
+                loop {
+                    match chars.next() {
+                        None => return Err(err("unterminated symbolic name")),
+                        Some('>') => break,
+                        Some(e) if e == escape_char => match chars.next() {
+                            Some(n) => name.push(n),
+                            None => return Err(err("unterminated symbolic name")),
+                        },
+                        Some(n) => name.push(n),
+                    }
+                }
+                tokens.push(Token::Symbol(name));
+            } else if c == '"' {
+                flush(&mut word, &mut tokens);
+                let mut parts = Vec::new();
+                if read_string(&mut chars, escape_char, &mut parts).map_err(err)? {
+                    tokens.push(Token::Str(parts));
+                } else {
+                    string = Some(parts);
+                    continued = true;
+                    break;
+                }
+            } else if matches!(c, ';' | ',' | '(' | ')') {
+                flush(&mut word, &mut tokens);
+                tokens.push(Token::Punct(c));
+            } else {
+                word.push(c);
+            }
+        }
+        flush(&mut word, &mut tokens);
+
+        if !continued && !tokens.is_empty() {
+            lines.push(Line {
+                lineno: start_lineno,
+                tokens: std::mem::take(&mut tokens),
+            });
+        }
+    }
+    if string.is_some() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("line {}: unterminated string", start_lineno),
+        ));
+    }
+    if !tokens.is_empty() {
+        lines.push(Line {
+            lineno: start_lineno,
+            tokens,
+        });
+    }
+
+    Ok(lines)
+}
+
+/// Read the rest of a quoted string into `parts`.  Returns whether the
+/// string ended on this line: an escape character at the end of the
+/// line continues it on the next one.
+fn read_string(
+    chars: &mut std::iter::Peekable<std::str::Chars>,
+    escape_char: char,
+    parts: &mut Vec<StrPart>,
+) -> Result<bool, &'static str> {
+    loop {
+        match chars.next() {
+            None => return Err("unterminated string"),
+            Some('"') => return Ok(true),
+            Some('<') => {
+                let mut name = String::new();
+                loop {
+                    match chars.next() {
+                        None => return Err("unterminated string"),
+                        Some('>') => break,
+                        Some(e) if e == escape_char => match chars.next() {
+                            Some(n) => name.push(n),
+                            None => return Err("unterminated string"),
+                        },
+                        Some(n) => name.push(n),
+                    }
+                }
+                parts.push(StrPart::Symbol(name));
+            }
+            Some(e) if e == escape_char => {
+                if chars.clone().all(char::is_whitespace) {
+                    return Ok(false);
+                }
+                parts.push(StrPart::Char(parse_escape(chars).ok_or("bad escape")?));
+            }
+            Some(n) => parts.push(StrPart::Char(n)),
+        }
+    }
+}
+
+/// Parse the rest of an escape sequence inside a string: `d` decimal, `x`
+/// hexadecimal, an octal digit, or a literal character.
+fn parse_escape(chars: &mut std::iter::Peekable<std::str::Chars>) -> Option<char> {
+    let c = chars.next()?;
+    let (radix, max_len) = match c {
+        'd' => (10, 3),
+        'x' => (16, 2),
+        '0'..='7' => (8, 3),
+        _ => return Some(c),
+    };
+    let mut digits = String::new();
+    if radix == 8 {
+        digits.push(c);
+    }
+    while digits.len() < max_len {
+        match chars.peek() {
+            Some(d) if d.is_digit(radix) => {
+                digits.push(*d);
+                chars.next();
+            }
+            _ => break,
+        }
+    }
+    let value = u32::from_str_radix(&digits, radix).ok()?;
+    char::from_u32(value)
+}
+
+/// Apply the `define`/`ifdef`/`ifndef`/`else`/`endif` directives.
+fn preprocess(lines: Vec<Line>) -> io::Result<Vec<Line>> {
+    let mut defined = HashSet::new();
+    // each entry: (condition of this branch, some branch already taken)
+    let mut stack: Vec<(bool, bool)> = Vec::new();
+    let mut out = Vec::new();
+
+    for line in lines {
+        let active = stack.iter().all(|(cond, _)| *cond);
+        let name = match line.operands() {
+            [Token::Word(w)] => Some(w.clone()),
+            _ => None,
+        };
+        match line.keyword() {
+            Some("ifdef") | Some("ifndef") => {
+                let name = name.ok_or_else(|| line.error("missing macro name"))?;
+                let cond = defined.contains(&name) == (line.keyword() == Some("ifdef"));
+                stack.push((cond, cond));
+            }
+            Some("elifdef") | Some("elifndef") => {
+                let name = name.ok_or_else(|| line.error("missing macro name"))?;
+                let want = line.keyword() == Some("elifdef");
+                let top = stack
+                    .last_mut()
+                    .ok_or_else(|| line.error("elifdef without ifdef"))?;
+                let cond = !top.1 && defined.contains(&name) == want;
+                *top = (cond, top.1 || cond);
+            }
+            Some("else") => {
+                let top = stack
+                    .last_mut()
+                    .ok_or_else(|| line.error("else without ifdef"))?;
+                *top = (!top.1, true);
+            }
+            Some("endif") => {
+                stack
+                    .pop()
+                    .ok_or_else(|| line.error("endif without ifdef"))?;
+            }
+            Some("define") if active => {
+                let name = name.ok_or_else(|| line.error("missing macro name"))?;
+                defined.insert(name);
+            }
+            _ if active => out.push(line),
+            _ => {}
+        }
+    }
+
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tokenize() {
+        let src = "comment_char %\nescape_char /\n\
+                   % a comment\n\
+                   LC_CTYPE\n\
+                   upper <U0041>..<U005A>;/\n   <U00C0> % trailing\n\
+                   toupper (<a>,<A>)\n\
+                   END LC_CTYPE\n";
+        let source = LocaleSource::parse(src).unwrap();
+        let ctype = source.category("LC_CTYPE").unwrap();
+        assert_eq!(ctype.lines.len(), 2);
+        assert_eq!(
+            ctype.lines[0].tokens,
+            vec![
+                Token::Word("upper".into()),
+                Token::Symbol("U0041".into()),
+                Token::Word("..".into()),
+                Token::Symbol("U005A".into()),
+                Token::Punct(';'),
+                Token::Symbol("U00C0".into()),
+            ]
+        );
+        assert_eq!(ctype.lines[1].keyword(), Some("toupper"));
+        assert_eq!(ctype.lines[1].operands().len(), 5);
+    }
+
+    #[test]
+    fn test_strings_and_copy() {
+        let src = "LC_COLLATE\ncopy \"iso14651_t1\"\nEND LC_COLLATE\n\
+                   LC_MESSAGES\nyesexpr \"^[+1yY]\"\nEND LC_MESSAGES\n";
+        let source = LocaleSource::parse(src).unwrap();
+        let collate = source.category("LC_COLLATE").unwrap();
+        assert_eq!(collate.copy_from().as_deref(), Some("iso14651_t1"));
+        let messages = source.category("LC_MESSAGES").unwrap();
+        match &messages.lines[0].operands()[0] {
+            Token::Str(parts) => assert_eq!(str_parts_text(parts), "^[+1yY]"),
+            t => panic!("unexpected token {t:?}"),
+        }
+    }
+
+    #[test]
+    fn test_continued_string() {
+        let src = "escape_char /\nLC_TIME\nd_fmt \"%d./\n%m\" ;/\n  \"x\"\nEND LC_TIME\n";
+        let source = LocaleSource::parse(src).unwrap();
+        let time = source.category("LC_TIME").unwrap();
+        assert_eq!(time.lines.len(), 1);
+        match time.lines[0].operands() {
+            [Token::Str(first), Token::Punct(';'), Token::Str(second)] => {
+                assert_eq!(str_parts_text(first), "%d.%m");
+                assert_eq!(str_parts_text(second), "x");
+            }
+            t => panic!("unexpected tokens {t:?}"),
+        }
+        assert!(LocaleSource::parse("LC_TIME\nd_fmt \"a\\\n").is_err());
+    }
+
+    #[test]
+    fn test_ifdef() {
+        let src = "LC_COLLATE\nifdef FOO\na\nelse\nb\nendif\ndefine FOO\n\
+                   ifdef FOO\nc\nendif\nEND LC_COLLATE\n";
+        let source = LocaleSource::parse(src).unwrap();
+        let words: Vec<_> = source.categories[0]
+            .lines
+            .iter()
+            .map(|l| l.keyword().unwrap().to_string())
+            .collect();
+        assert_eq!(words, vec!["b", "c"]);
+    }
+
+    #[test]
+    fn test_missing_end() {
+        assert!(LocaleSource::parse("LC_CTYPE\nupper <A>\n").is_err());
+    }
+}
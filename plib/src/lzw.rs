@@ -10,11 +10,17 @@
 // adapted from FreeBSD's zopen.c.
 //
 
-use std::io::{self, Error, ErrorKind, Read};
+//! The Unix `compress` LZW format, as streaming adaptors: [`UnixLZWReader`]
+//! decompresses any `Read`, and [`UnixLZWWriter`] compresses into any
+//! `Write`.  Both keep only bounded buffers, so data of any size can be
+//! streamed through them, e.g. with `io::copy`.
+
+use crate::BUFSZ;
+use std::io::{self, Error, ErrorKind, Read, Write};
 
 const INIT_BITS: u32 = 9;
 const HSIZE: usize = 69_001;
-const CHECK_GAP: usize = 10_000;
+const CHECK_GAP: i64 = 10_000;
 const BITS: u32 = 16;
 const MAGIC_HEADER: [u8; 2] = [0x1F, 0x9D];
 const HDR_BIT_MASK: u8 = 0x1f;
@@ -25,57 +31,35 @@ const CLEAR: i32 = 256;
 const LMASK: [i32; 9] = [0xff, 0xfe, 0xfc, 0xf8, 0xf0, 0xe0, 0xc0, 0x80, 0x00];
 const RMASK: [i32; 9] = [0x00, 0x01, 0x03, 0x07, 0x0f, 0x1f, 0x3f, 0x7f, 0xff];
 
-/// A wrapper around the Read trait object used
-/// for reading the compressed file or
-/// file to be compressed
-struct CompReader {
-    inner_rdr: Box<dyn Read>,
+fn max_code(n_bits: u32) -> u32 {
+    (1 << (n_bits)) - 1
 }
 
-impl CompReader {
-    fn new(rdr: Box<dyn Read>) -> Self {
-        Self { inner_rdr: rdr }
-    }
-
-    fn read_exact(&mut self, buf: &mut [u8]) -> io::Result<usize> {
-        let mut total_read = 0;
-
-        while total_read < buf.len() {
-            match self.inner_rdr.read(&mut buf[total_read..]) {
-                Ok(n) => {
-                    if n == 0 {
-                        break;
-                    }
-                    total_read += n;
-                }
-                Err(ref e) if e.kind() == ErrorKind::Interrupted => {}
-                Err(e) => {
-                    return Err(e);
-                }
-            }
-        }
-
-        if total_read == 0 {
-            return Err(io::Error::new(ErrorKind::UnexpectedEof, "Unexpected EOF"));
-        }
-
-        Ok(total_read)
-    }
+fn corrupt() -> Error {
+    Error::new(ErrorKind::InvalidData, "corrupt input")
 }
 
-fn max_code(n_bits: u32) -> u32 {
-    (1 << (n_bits)) - 1
+/// Enum to store the current state of the reader
+#[derive(PartialEq)]
+enum ReaderState {
+    Header,
+    Codes,
+    Eof,
 }
 
-pub struct UnixLZWReader {
-    /// the reader of the compressed file or the file to be compressed
-    rdr: CompReader,
+/// Decompressing reader: reads compressed data from the inner reader and
+/// yields the original bytes.
+pub struct UnixLZWReader<R> {
+    /// the reader of the compressed data
+    rdr: R,
 
-    /// if the compressed file has header or not
-    have_hdr: bool,
+    /// buffered input from `rdr`
+    inbuf: Box<[u8]>,
+    inpos: usize,
+    inlen: usize,
 
-    /// if the eof has been reached or not
-    eof: bool,
+    /// The current state of the reader
+    state: ReaderState,
 
     /// the max no of bits for the code(maxmaxcode is derived from this)
     maxbits: u32,
@@ -89,13 +73,9 @@ pub struct UnixLZWReader {
     /// It indicates if the buffer has to be cleared or not
     clear: bool,
 
-    code: i32,
-
     /// the previously recognized code
     oldcode: i32,
 
-    incode: i32,
-
     /// the max no of codes that can be created of n_bits
     maxcode: i32,
 
@@ -105,66 +85,152 @@ pub struct UnixLZWReader {
     /// the next free entry on the table
     free_ent: i32,
 
-    finchar: i32,
+    finchar: u8,
 
-    /// It's the current read offset
+    /// It's the current read offset, in bits, into `gbuf`
     roffset: i32,
 
     size: i32,
 
-    /// Buffer to fill as we go on read  from the read stream
+    /// The current group of codes, as read from the input
     gbuf: [u8; BITS as usize],
 
-    tab_suffix: [i32; HSIZE],
+    tab_suffix: Vec<u8>,
+
+    tab_prefix: Vec<u16>,
 
-    tab_prefix: [u16; HSIZE],
+    /// Decoded bytes not yet returned, in reverse order
+    stack: Vec<u8>,
 }
 
-impl UnixLZWReader {
-    pub fn new(rdr: Box<dyn Read>) -> UnixLZWReader {
+impl<R: Read> UnixLZWReader<R> {
+    pub fn new(rdr: R) -> UnixLZWReader<R> {
         UnixLZWReader {
-            rdr: CompReader::new(rdr),
-
-            have_hdr: false,
-
-            eof: false,
-
+            rdr,
+            inbuf: vec![0; BUFSZ].into_boxed_slice(),
+            inpos: 0,
+            inlen: 0,
+            state: ReaderState::Header,
             maxbits: 0,
-
             n_bits: 0,
-
             block_compress: false,
-
             clear: false,
-
-            code: 0,
-
             oldcode: 0,
-
-            incode: 0,
-
             maxcode: 0,
-
             maxmaxcode: 0,
-
             free_ent: 0,
-
             finchar: 0,
-
             roffset: 0,
-
             size: 0,
-
             gbuf: [0; BITS as usize],
+            tab_suffix: vec![0; 1 << BITS],
+            tab_prefix: vec![0; 1 << BITS],
+            stack: Vec::new(),
+        }
+    }
 
-            tab_suffix: [0; HSIZE],
+    /// Gets a reference to the underlying reader.
+    pub fn get_ref(&self) -> &R {
+        &self.rdr
+    }
 
-            tab_prefix: [0; HSIZE],
+    /// Unwraps this `UnixLZWReader`, returning the underlying reader.
+    /// Input read ahead into the internal buffer is lost.
+    pub fn into_inner(self) -> R {
+        self.rdr
+    }
+
+    /// Fill `buf` from the input, returning fewer bytes only at end of
+    /// input.
+    fn read_input(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let mut total = 0;
+        while total < buf.len() {
+            if self.inpos == self.inlen {
+                match self.rdr.read(&mut self.inbuf) {
+                    Ok(0) => break,
+                    Ok(n) => {
+                        self.inpos = 0;
+                        self.inlen = n;
+                    }
+                    Err(ref e) if e.kind() == ErrorKind::Interrupted => continue,
+                    Err(e) => return Err(e),
+                }
+            }
+            let n = (self.inlen - self.inpos).min(buf.len() - total);
+            buf[total..total + n].copy_from_slice(&self.inbuf[self.inpos..self.inpos + n]);
+            self.inpos += n;
+            total += n;
         }
+        Ok(total)
     }
 
+    fn read_header(&mut self) -> io::Result<()> {
+        // 3-byte header: 2 byte magic, 1 byte a bitmask of options.
+        let mut header = [0; 3];
+        match self.read_input(&mut header)? {
+            0 => return Err(Error::new(ErrorKind::UnexpectedEof, "Unexpected EOF")),
+            3 if header[0..2] == MAGIC_HEADER => {}
+            _ => {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    "invalid file header: magic number",
+                ))
+            }
+        }
+
+        // the third byte has bitmask of options
+        // (Eg) if it has 10011111
+        // that means the first bit represents the BLOCK_MASK i.e block_compress
+        // has to be enabled or not
+        //
+        // the lower 5 bits hold the max no of bits per code
+        let options = header[2];
+
+        self.maxbits = (options & HDR_BIT_MASK) as u32;
+        self.block_compress = (options & HDR_BLOCK_MASK) != 0;
+
+        if self.maxbits > BITS || self.maxbits < INIT_BITS {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "invalid file header: bits",
+            ));
+        }
+
+        // the max value that self.maxcode can have, which is derived
+        // from the maxbits that codes can have
+        // hence, 2^(self.maxbits)
+        self.maxmaxcode = 1 << self.maxbits;
+
+        // the no of bits of code that we start with
+        // btw, this no of bits also represent the fact that there can be
+        // 2 ^ (self.n_bits) entries in the table initially
+        self.n_bits = INIT_BITS; // 9
+        self.maxcode = max_code(self.n_bits) as i32; // 511
+
+        for code in 0..=255 {
+            self.tab_prefix[code] = 0;
+            self.tab_suffix[code] = code as u8;
+        }
+
+        // code 256 is reserved for CLEAR in block mode
+        self.free_ent = if self.block_compress { FIRST } else { 256 };
+
+        match self.getcode()? {
+            None => self.state = ReaderState::Eof,
+            Some(code) if code > 255 => return Err(corrupt()),
+            Some(code) => {
+                self.oldcode = code;
+                self.finchar = code as u8;
+                self.stack.push(self.finchar);
+                self.state = ReaderState::Codes;
+            }
+        }
+        Ok(())
+    }
+
+    /// Read the next code, or `None` at the end of the input.
     #[allow(clippy::assign_op_pattern)]
-    fn getcode(&mut self) -> i32 {
+    fn getcode(&mut self) -> io::Result<Option<i32>> {
         if self.clear || self.roffset >= self.size || self.free_ent > self.maxcode {
             // as free_ent represents the index of the next available entry that can be made
             // on the table, so if its more than the self.maxcode (i.e max allowed no of codes),
@@ -187,18 +253,19 @@ impl UnixLZWReader {
                 self.clear = false;
             }
 
-            // the buffer for current max n of bits
-            let gbuf = &mut self.gbuf[0..self.n_bits as usize];
-
-            match self.rdr.read_exact(gbuf) {
-                Ok(n) => {
-                    self.size = n as i32;
-                }
-                Err(_) => return -1,
+            // codes are written in groups of n_bits bytes
+            let mut gbuf = [0; BITS as usize];
+            let n = self.read_input(&mut gbuf[..self.n_bits as usize])?;
+            if n == 0 {
+                return Ok(None);
             }
+            self.gbuf = gbuf;
 
             self.roffset = 0;
-            self.size = (self.size << 3) - (self.n_bits - 1) as i32;
+            self.size = ((n as i32) << 3) - (self.n_bits - 1) as i32;
+            if self.size <= 0 {
+                return Ok(None);
+            }
         }
 
         let mut r_off = self.roffset;
@@ -220,155 +287,104 @@ impl UnixLZWReader {
             bits = bits - 8;
         }
 
-        gcode = gcode | (((self.gbuf[bp] as i32) & RMASK[bits as usize]) << r_off);
+        if bits > 0 {
+            gcode = gcode | (((self.gbuf[bp] as i32) & RMASK[bits as usize]) << r_off);
+        }
         self.roffset = self.roffset + self.n_bits as i32;
 
-        gcode
+        Ok(Some(gcode))
     }
 
-    /// Read from the compressed stream of file
-    pub fn read(&mut self) -> io::Result<Vec<u8>> {
-        let mut outbytes: Vec<u8> = Vec::new();
-
-        if !self.have_hdr {
-            // 3-byte header: 2 byte magic, 1 byte a bitmask of options.
-            let mut header = [0; 3];
-            self.rdr.read_exact(&mut header)?;
-
-            let header_magic = &header[0..2];
+    /// Decode the next code onto the stack.  Returns false at the end of
+    /// the input.
+    fn decode_next(&mut self) -> io::Result<bool> {
+        let mut code = match self.getcode()? {
+            Some(code) => code,
+            None => return Ok(false),
+        };
 
-            if &MAGIC_HEADER[..] != header_magic {
-                return Err(Error::new(
-                    ErrorKind::Other,
-                    "invalid file header: magic number",
-                ));
-            }
+        if code == CLEAR && self.block_compress {
+            // clear the table; the dictionary is rebuilt from scratch
+            self.tab_prefix[..256].fill(0);
+            self.clear = true;
+            self.free_ent = FIRST - 1;
 
-            // the third byte has bitmask of options
-            // (Eg) if it has 10011111
-            // that means the first bit represents the BLOCK_MASK i.e block_compress
-            // has to be enabled or not
-            //
-            // the bit that we get onwards represent the bit position of the value we want as
-            // max no bits
-            // if it's at 5th position then it means 2^4 = 16
-            let options = header[2];
-
-            self.maxbits = (options & HDR_BIT_MASK) as u32;
-            self.block_compress = (options & HDR_BLOCK_MASK) != 0;
-
-            if self.maxbits > BITS {
-                return Err(Error::new(ErrorKind::Other, "invalid file header: bits"));
-            }
+            code = match self.getcode()? {
+                Some(code) => code,
+                None => return Ok(false),
+            };
+        }
 
-            // the max value that self.maxcode can have, which is derived
-            // from the maxbits that codes can have
-            // hence, 2^(self.maxbits)
-            self.maxmaxcode = 1 << self.maxbits;
-
-            // the no of bits of code that we start with
-            // btw, this no of bits also represent the fact that there can be
-            // 2 ^ (self.n_bits) entries in the table initially
-            self.n_bits = INIT_BITS; // 9
-            self.maxcode = max_code(self.n_bits) as i32; // 511
-
-            for code in (0..=255).rev() {
-                let idx: usize = code as usize;
-                self.code = code;
-                self.tab_prefix[idx] = 0;
-                self.tab_suffix[idx] = code;
-            }
+        let incode = code;
 
-            if self.block_compress {
-                // TODO: understand why we need to skip one index (i.e 256) (initial guess is that
-                // we need that index reserverd for CLEAR)
-                self.free_ent = FIRST;
-            } else {
-                self.free_ent = 256;
+        if code >= self.free_ent {
+            // the KwKwK case: the code being defined right now
+            if code > self.free_ent {
+                return Err(corrupt());
             }
+            self.stack.push(self.finchar);
+            code = self.oldcode;
+        }
 
-            self.have_hdr = true;
+        while code >= 256 {
+            let idx = code as usize;
+            self.stack.push(self.tab_suffix[idx]);
+            code = self.tab_prefix[idx] as i32;
+        }
+        self.finchar = self.tab_suffix[code as usize];
+        self.stack.push(self.finchar);
+
+        if self.free_ent < self.maxmaxcode {
+            let idx = self.free_ent as usize;
+            self.tab_prefix[idx] = self.oldcode as u16;
+            self.tab_suffix[idx] = self.finchar;
+            self.free_ent += 1;
+        }
 
-            self.oldcode = self.getcode();
-            self.finchar = self.oldcode;
-            if self.oldcode < 0 {
-                self.eof = true;
-                return Ok(Vec::new());
-            }
+        self.oldcode = incode;
+        Ok(true)
+    }
+}
 
-            outbytes.push(self.finchar as u8);
+impl<R: Read> Read for UnixLZWReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.state == ReaderState::Header {
+            self.read_header()?;
         }
 
-        loop {
-            let mut stack: Vec<u8> = Vec::new();
-
-            self.code = self.getcode();
-            if self.code < 0 {
-                self.eof = true;
+        let mut n = 0;
+        while n < buf.len() {
+            if self.stack.is_empty() && (self.state == ReaderState::Eof || !self.decode_next()?) {
+                self.state = ReaderState::Eof;
                 break;
             }
-
-            if (self.code == CLEAR) && self.block_compress {
-                // clear the table and fill it again with value
-                for code in (0..=255).rev() {
-                    let idx: usize = code as usize;
-                    self.code = code;
-                    self.tab_prefix[idx] = 0;
-                }
-                self.clear = true;
-                self.free_ent = FIRST - 1;
-
-                self.code = self.getcode();
-                if self.code < 0 {
-                    self.eof = true;
-                    break;
-                }
+            let take = self.stack.len().min(buf.len() - n);
+            let start = self.stack.len() - take;
+            for (dst, src) in buf[n..n + take]
+                .iter_mut()
+                .zip(self.stack[start..].iter().rev())
+            {
+                *dst = *src;
             }
-
-            self.incode = self.code;
-
-            if self.code >= self.free_ent {
-                stack.push(self.finchar as u8);
-                self.code = self.oldcode;
-            }
-
-            while self.code >= 256 {
-                let code = self.code as usize;
-                stack.push(self.tab_suffix[code] as u8);
-                self.code = self.tab_prefix[code] as i32;
-            }
-            self.finchar = self.tab_suffix[self.code as usize];
-            stack.push(self.finchar as u8);
-
-            for stackchar in stack.iter().rev() {
-                outbytes.push(*stackchar);
-            }
-
-            self.code = self.free_ent;
-            if self.code < self.maxmaxcode {
-                let code = self.code as usize;
-                self.tab_prefix[code] = self.oldcode as u16;
-                self.tab_suffix[code] = self.finchar;
-                self.free_ent = self.code + 1;
-            }
-
-            self.oldcode = self.incode;
+            self.stack.truncate(start);
+            n += take;
         }
-
-        Ok(outbytes)
+        Ok(n)
     }
 }
 
-/// Enum to store the current state of the writer
-#[derive(PartialEq)]
-enum WriterState {
-    Start,
-    Middle,
-}
+/// Compressing writer: bytes written to it are compressed into the inner
+/// writer.  [`UnixLZWWriter::finish`] must be called to write the final
+/// code; dropping the writer finishes it too, ignoring errors.
+pub struct UnixLZWWriter<W: Write> {
+    /// The writer of the compressed data; `None` once finished
+    wtr: Option<W>,
+
+    /// Whether the header has been written
+    started: bool,
 
-pub struct UnixLZWWriter {
-    /// The current state of the writer
-    state: WriterState,
+    /// Whether `ent` holds the prefix of the current string
+    have_ent: bool,
 
     /// Current number of bits per code
     n_bits: u32,
@@ -385,8 +401,6 @@ pub struct UnixLZWWriter {
     /// Hash table for codes
     htab: Vec<i32>,
 
-    /// Size of the hash table
-    hsize: i32,
     /// Code table
     codetab: Vec<u16>,
 
@@ -399,80 +413,114 @@ pub struct UnixLZWWriter {
     /// Flag to clear the dictionary
     clear_flg: bool,
 
-    ratio: i32,
+    ratio: i64,
 
     /// Next checkpoint for compression ratio
-    checkpoint: i32,
+    checkpoint: i64,
 
     /// Offset for current byte
     offset: u32,
 
-    in_count: i32,
+    in_count: i64,
 
-    bytes_out: i32,
+    bytes_out: i64,
 
-    out_count: i32,
-
-    /// Buffer for output bytes
+    /// Buffer for the current group of codes
     buf: [u8; BITS as usize],
 
-    write_params: WriteParams,
-}
-
-struct WriteParams {
-    fcode: i32,
+    /// Compressed bytes not yet written to `wtr`
+    outbuf: Vec<u8>,
 
+    /// The code of the current string
     ent: i32,
 
-    hsize_reg: i32,
-
     hshift: i32,
 }
 
-impl UnixLZWWriter {
-    pub fn new(mbits: Option<u32>) -> Self {
+impl<W: Write> UnixLZWWriter<W> {
+    /// Create a writer using at most `mbits` bits per code (9 to 16,
+    /// default 16).
+    pub fn new(wtr: W, mbits: Option<u32>) -> Self {
         let maxbits = match mbits {
-            Some(m) => m.clamp(9, 16),
+            Some(m) => m.clamp(INIT_BITS, BITS),
             None => BITS,
         };
 
-        let maxmaxcode = (1 << maxbits) as i32;
+        let mut fcode = HSIZE as i32;
+        let mut hshift = 0;
+        while fcode < 65536 {
+            hshift += 1;
+            fcode *= 2;
+        }
 
         Self {
-            state: WriterState::Start,
+            wtr: Some(wtr),
+            started: false,
+            have_ent: false,
             n_bits: INIT_BITS,
             maxbits,
             maxcode: max_code(INIT_BITS) as i32,
-            maxmaxcode,
+            maxmaxcode: 1 << maxbits,
             htab: vec![-1; HSIZE],
             codetab: vec![0; HSIZE],
-            hsize: HSIZE as i32,
-            free_ent: 0,
+            free_ent: FIRST,
             block_compress: true,
             clear_flg: false,
             ratio: 0,
-            checkpoint: CHECK_GAP as i32,
+            checkpoint: CHECK_GAP,
             offset: 0,
             in_count: 0,
             bytes_out: 0,
-            out_count: 0,
             buf: [0; BITS as usize],
-            write_params: WriteParams {
-                fcode: 0,
-                ent: 0,
-                hsize_reg: 0,
-                hshift: 0,
-            },
+            outbuf: Vec::with_capacity(BUFSZ),
+            ent: 0,
+            hshift: 8 - hshift,
+        }
+    }
+
+    /// Number of uncompressed bytes written so far.
+    pub fn bytes_in(&self) -> u64 {
+        self.in_count as u64
+    }
+
+    /// Number of compressed bytes produced so far, including the header.
+    pub fn bytes_out(&self) -> u64 {
+        (self.bytes_out as usize + self.offset.div_ceil(8) as usize) as u64
+    }
+
+    /// Gets a reference to the underlying writer.
+    pub fn get_ref(&self) -> &W {
+        self.wtr.as_ref().unwrap()
+    }
+
+    fn start(&mut self) {
+        self.started = true;
+        self.outbuf.extend_from_slice(&MAGIC_HEADER);
+        let options = (self.maxbits as u8)
+            | if self.block_compress {
+                HDR_BLOCK_MASK
+            } else {
+                0
+            };
+        self.outbuf.push(options);
+        self.bytes_out = 3;
+    }
+
+    fn flush_outbuf(&mut self) -> io::Result<()> {
+        if !self.outbuf.is_empty() {
+            self.wtr.as_mut().unwrap().write_all(&self.outbuf)?;
+            self.outbuf.clear();
         }
+        Ok(())
     }
 
-    fn clear_block(&mut self) -> io::Result<()> {
-        self.checkpoint = self.in_count + CHECK_GAP as i32;
+    fn clear_block(&mut self) {
+        self.checkpoint = self.in_count + CHECK_GAP;
 
         let rat = if self.in_count > 0x007fffff {
             match self.bytes_out >> 8 {
                 0 => 0x7fffffff,
-                _ => self.in_count / (self.bytes_out >> 8),
+                b => self.in_count / b,
             }
         } else {
             (self.in_count << 8) / self.bytes_out
@@ -482,30 +530,26 @@ impl UnixLZWWriter {
             self.ratio = rat;
         } else {
             self.ratio = 0;
-            self.clear_hash();
+            self.htab.fill(-1);
             self.free_ent = FIRST;
             self.clear_flg = true;
-            self.output(CLEAR)?;
+            self.output(CLEAR);
         }
-        Ok(())
-    }
-
-    fn clear_hash(&mut self) {
-        self.htab.fill(-1)
     }
 
-    fn output(&mut self, mut ocode: i32) -> io::Result<Vec<u8>> {
+    /// Append a code to the output, or flush the partial group of codes if
+    /// `ocode` is negative.
+    fn output(&mut self, mut ocode: i32) {
         let mut r_off = self.offset;
         let mut bits = self.n_bits;
         let mut bp: usize = 0;
-        let mut outbytes: Vec<u8> = Vec::new();
 
         if ocode >= 0 {
             bp += (r_off >> 3) as usize;
             r_off &= 7;
 
-            self.buf[bp] = ((self.buf[bp] as i32 & RMASK[r_off as usize] as i32) as u8)
-                | ((ocode << r_off) & LMASK[r_off as usize] as i32) as u8;
+            self.buf[bp] = ((self.buf[bp] as i32 & RMASK[r_off as usize]) as u8)
+                | ((ocode << r_off) & LMASK[r_off as usize]) as u8;
             bp += 1;
             bits -= 8 - r_off;
             ocode >>= 8 - r_off;
@@ -524,22 +568,23 @@ impl UnixLZWWriter {
             self.offset += self.n_bits;
 
             if self.offset == (self.n_bits << 3) {
-                bits = self.n_bits;
-                self.bytes_out += bits as i32;
-                let temp_buf = &self.buf[..bits as usize];
-                outbytes.extend(temp_buf);
+                let n = self.n_bits as usize;
+                self.outbuf.extend_from_slice(&self.buf[..n]);
+                self.bytes_out += n as i64;
                 self.offset = 0;
             }
 
+            // a change of code size starts a new group
             if self.free_ent > self.maxcode || self.clear_flg {
                 if self.offset > 0 {
-                    let temp_buf = &self.buf[..self.n_bits as usize];
-                    outbytes.extend(temp_buf);
-                    self.bytes_out += self.n_bits as i32;
+                    let n = self.n_bits as usize;
+                    self.outbuf.extend_from_slice(&self.buf[..n]);
+                    self.bytes_out += n as i64;
                 }
                 self.offset = 0;
 
                 if self.clear_flg {
+                    self.n_bits = INIT_BITS;
                     self.maxcode = max_code(INIT_BITS) as i32;
                     self.clear_flg = false;
                 } else {
@@ -553,109 +598,208 @@ impl UnixLZWWriter {
             }
         } else {
             if self.offset > 0 {
-                self.offset = (self.offset + 7) / 8;
-                let temp_buf = &self.buf[..self.offset as usize];
-                outbytes.extend(temp_buf);
-                self.bytes_out += self.offset as i32;
+                let n = self.offset.div_ceil(8) as usize;
+                self.outbuf.extend_from_slice(&self.buf[..n]);
+                self.bytes_out += n as i64;
             }
             self.offset = 0;
         }
-
-        Ok(outbytes)
     }
 
-    pub fn write(&mut self, buffer: &[u8]) -> io::Result<Vec<u8>> {
-        let mut outbytes: Vec<u8> = Vec::new();
+    fn compress_byte(&mut self, c: i32) {
+        self.in_count += 1;
+        if !self.have_ent {
+            self.ent = c;
+            self.have_ent = true;
+            return;
+        }
 
-        if self.state == WriterState::Start {
-            self.state = WriterState::Middle;
-            self.maxmaxcode = 1 << self.maxbits;
-            outbytes.extend(&[0x1F, 0x9D]);
-            let tmp = (self.maxbits as u8)
-                | if self.block_compress {
-                    HDR_BLOCK_MASK
-                } else {
-                    0
-                };
-            outbytes.push(tmp);
-
-            self.bytes_out = 3;
-            self.clear_flg = false;
-            self.in_count = 1;
-            self.free_ent = if self.block_compress { FIRST } else { 256 };
-
-            let mut fcode = self.hsize;
-            let mut hshift = 0;
-            while fcode < 65536 {
-                hshift += 1;
-                fcode *= 2;
+        let fcode = (c << self.maxbits) + self.ent;
+        let mut i = (c << self.hshift) ^ self.ent;
+
+        if self.htab[i as usize] == fcode {
+            self.ent = self.codetab[i as usize] as i32;
+            return;
+        } else if self.htab[i as usize] >= 0 {
+            // secondary hash (after G. Knott)
+            let disp = if i == 0 { 1 } else { HSIZE as i32 - i };
+            loop {
+                i -= disp;
+                if i < 0 {
+                    i += HSIZE as i32;
+                }
+                if self.htab[i as usize] == fcode {
+                    self.ent = self.codetab[i as usize] as i32;
+                    return;
+                }
+                if self.htab[i as usize] < 0 {
+                    break;
+                }
             }
-            self.write_params = WriteParams {
-                fcode,
-                ent: buffer[0] as i32,
-                hsize_reg: self.hsize,
-                hshift: 8 - hshift,
-            };
+        }
 
-            self.clear_hash();
+        self.output(self.ent);
+        self.ent = c;
+
+        if self.free_ent < self.maxmaxcode {
+            self.codetab[i as usize] = self.free_ent as u16;
+            self.free_ent += 1;
+            self.htab[i as usize] = fcode;
+        } else if self.in_count >= self.checkpoint && self.block_compress {
+            self.clear_block();
         }
+    }
 
-        for &item in &buffer[1..] {
-            let c = item as i32;
-            self.in_count += 1;
+    fn do_finish(&mut self) -> io::Result<()> {
+        if !self.started {
+            self.start();
+        }
+        if self.have_ent {
+            self.output(self.ent);
+            self.have_ent = false;
+        }
+        self.output(-1);
+        self.flush_outbuf()?;
+        self.wtr.as_mut().unwrap().flush()
+    }
 
-            self.write_params.fcode = ((c) << self.maxbits) + (self.write_params.ent);
-            let mut i = (c << self.write_params.hshift) ^ self.write_params.ent;
+    /// Write the remaining compressed data and return the inner writer.
+    pub fn finish(mut self) -> io::Result<W> {
+        self.do_finish()?;
+        Ok(self.wtr.take().unwrap())
+    }
+}
 
-            let mut skip_flag = false;
-            if self.htab[i as usize] as i32 == self.write_params.fcode {
-                self.write_params.ent = self.codetab[i as usize] as i32;
-                continue;
-            } else if self.htab[i as usize] as i32 >= 0 {
-                let mut disp = self.write_params.hsize_reg - i;
-                if i == 0 {
-                    disp = 1;
-                }
-                loop {
-                    i -= disp;
-                    if i < 0 {
-                        i += self.write_params.hsize_reg;
-                    }
-                    if self.htab[i as usize] as i32 == self.write_params.fcode {
-                        self.write_params.ent = self.codetab[i as usize] as i32;
-                        skip_flag = true;
-                        break;
-                    }
-                    if self.htab[i as usize] < 0 {
-                        break;
-                    }
-                }
-            }
-            if skip_flag {
-                continue;
+impl<W: Write> Write for UnixLZWWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if !self.started {
+            self.start();
+        }
+        for &c in buf {
+            self.compress_byte(c as i32);
+            if self.outbuf.len() >= BUFSZ {
+                self.flush_outbuf()?;
             }
+        }
+        Ok(buf.len())
+    }
+
+    /// Writes out whole groups of codes; the last partial group is only
+    /// written by `finish`.
+    fn flush(&mut self) -> io::Result<()> {
+        self.flush_outbuf()?;
+        self.wtr.as_mut().unwrap().flush()
+    }
+}
 
-            outbytes.extend(self.output(self.write_params.ent)?);
-            self.out_count += 1;
-            self.write_params.ent = c;
+impl<W: Write> Drop for UnixLZWWriter<W> {
+    fn drop(&mut self) {
+        if self.wtr.is_some() {
+            let _ = self.do_finish();
+        }
+    }
+}
 
-            if self.free_ent < self.maxmaxcode {
-                self.codetab[i as usize] = self.free_ent as u16;
-                self.free_ent += 1;
-                self.htab[i as usize] = self.write_params.fcode as i32;
-            } else if self.in_count >= self.checkpoint && self.block_compress {
-                self.clear_block()?;
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn compress(data: &[u8], bits: Option<u32>, chunk: usize) -> Vec<u8> {
+        let mut encoder = UnixLZWWriter::new(Vec::new(), bits);
+        for piece in data.chunks(chunk.max(1)) {
+            encoder.write_all(piece).unwrap();
+        }
+        encoder.finish().unwrap()
+    }
+
+    fn uncompress(data: &[u8], chunk: usize) -> Vec<u8> {
+        let mut decoder = UnixLZWReader::new(data);
+        let mut out = Vec::new();
+        let mut buf = vec![0; chunk];
+        loop {
+            let n = decoder.read(&mut buf).unwrap();
+            if n == 0 {
+                break;
             }
+            out.extend_from_slice(&buf[..n]);
+        }
+        out
+    }
+
+    /// Pseudo-random text with enough repetition to fill the code table.
+    fn sample(len: usize) -> Vec<u8> {
+        let words: [&[u8]; 8] = [
+            b"lorem ",
+            b"ipsum ",
+            b"dolor ",
+            b"sit ",
+            b"amet\n",
+            b"\x00\xff",
+            b"zz",
+            b"q",
+        ];
+        let mut state: u32 = 12345;
+        let mut out = Vec::with_capacity(len);
+        while out.len() < len {
+            state = state.wrapping_mul(1_103_515_245).wrapping_add(12345);
+            out.extend_from_slice(words[(state >> 16) as usize % words.len()]);
+            out.push((state >> 8) as u8);
+        }
+        out.truncate(len);
+        out
+    }
+
+    #[test]
+    fn test_header() {
+        let out = compress(b"hello", Some(12), 100);
+        assert_eq!(&out[..3], &[0x1f, 0x9d, 0x80 | 12]);
+        assert_eq!(compress(b"", None, 1), vec![0x1f, 0x9d, 0x90]);
+    }
+
+    #[test]
+    fn test_roundtrip_small() {
+        for data in [&b""[..], b"a", b"abababababababab", b"hello, world\n"] {
+            assert_eq!(uncompress(&compress(data, None, 3), 2), data);
         }
-        Ok(outbytes)
     }
 
-    /// Return the remaining bytes which could not be written from close()
-    pub fn close(&mut self) -> io::Result<Vec<u8>> {
-        let mut outbytes: Vec<u8> = Vec::new();
-        outbytes.extend(self.output(self.write_params.ent)?);
-        self.out_count += 1;
-        outbytes.extend(self.output(-1)?);
-        Ok(outbytes)
+    #[test]
+    fn test_roundtrip_large() {
+        // large enough for the table to fill up and be cleared
+        let data = sample(600_000);
+        for bits in [9, 12, 16] {
+            let z = compress(&data, Some(bits), 7919);
+            assert!(z.len() < data.len());
+            assert_eq!(uncompress(&z, 4096), data);
+            // the encoding does not depend on how the input is split
+            assert_eq!(compress(&data, Some(bits), data.len()), z);
+        }
+    }
+
+    #[test]
+    fn test_io_copy() {
+        let data = sample(100_000);
+        let mut encoder = UnixLZWWriter::new(Vec::new(), None);
+        io::copy(&mut &data[..], &mut encoder).unwrap();
+        assert_eq!(encoder.bytes_in(), data.len() as u64);
+        let z = encoder.finish().unwrap();
+
+        let mut out = Vec::new();
+        io::copy(&mut UnixLZWReader::new(&z[..]), &mut out).unwrap();
+        assert_eq!(out, data);
+    }
+
+    #[test]
+    fn test_bad_input() {
+        let mut out = Vec::new();
+        let err = UnixLZWReader::new(&b"not compressed"[..])
+            .read_to_end(&mut out)
+            .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+
+        let err = UnixLZWReader::new(&[0x1f, 0x9d, 0x90, 0xff, 0xff][..])
+            .read_to_end(&mut out)
+            .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
     }
 }
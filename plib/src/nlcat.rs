@@ -0,0 +1,439 @@
+//
+// Copyright (c) 2024 Hemi Labs, Inc.
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+//! Message catalogs written by `gencat`, and `catopen()`'s search for
+//! them along `NLSPATH`.
+//!
+//! Two binary layouts are read.  The GNU layout starts with the magic
+//! number 0x960408de in the byte order of the machine that wrote it,
+//! then the size and depth of a hash table of (set + 1, message, offset)
+//! triples, stored once little-endian and once big-endian, then a pool
+//! of NUL-terminated strings.  The BSD layout used by macOS starts with
+//! `*nazgul*` and is a big-endian linked list of sets, each pointing at
+//! its message index and string data.
+//!
+//! Messages are kept as bytes, in the character set of the catalog.
+
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use crate::locale::{self, Category};
+
+/// The set of messages not in any `$set`.
+pub const NL_SETD: u32 = 1;
+
+/// `catopen()` flag selecting the locale from `LC_MESSAGES` rather than
+/// from `LANG` alone.
+pub const NL_CAT_LOCALE: i32 = 1;
+
+const GNU_MAGIC: u32 = 0x960408de;
+const BSD_MAGIC: &[u8; 8] = b"*nazgul*";
+const BSD_HEADER_LEN: usize = 32;
+const BSD_SET_LEN: usize = 40;
+const BSD_MSG_LEN: usize = 16;
+
+/// Where catalogs are looked for after the directories of `NLSPATH`.
+#[cfg(target_os = "macos")]
+pub const DEFAULT_NLSPATH: &str = "/usr/share/nls/%L/%N.cat:/usr/share/nls/%N/%L:\
+                                   /usr/local/share/nls/%L/%N.cat:/usr/local/share/nls/%N/%L";
+
+/// Where catalogs are looked for after the directories of `NLSPATH`.
+#[cfg(not(target_os = "macos"))]
+pub const DEFAULT_NLSPATH: &str = "/usr/share/locale/%L/%N:/usr/share/locale/%L/LC_MESSAGES/%N:\
+                                   /usr/share/locale/%l/%N:/usr/share/locale/%l/LC_MESSAGES/%N";
+
+fn invalid(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+/// `len` bytes of `data` at `at`.
+fn field(data: &[u8], at: usize, len: usize) -> io::Result<&[u8]> {
+    at.checked_add(len)
+        .and_then(|end| data.get(at..end))
+        .ok_or_else(|| invalid("truncated message catalog"))
+}
+
+fn read_u32(data: &[u8], at: usize, big_endian: bool) -> io::Result<u32> {
+    let bytes: [u8; 4] = field(data, at, 4)?.try_into().unwrap();
+    Ok(if big_endian {
+        u32::from_be_bytes(bytes)
+    } else {
+        u32::from_le_bytes(bytes)
+    })
+}
+
+fn read_offset(data: &[u8], at: usize) -> io::Result<usize> {
+    let bytes: [u8; 8] = field(data, at, 8)?.try_into().unwrap();
+    usize::try_from(i64::from_be_bytes(bytes)).map_err(|_| invalid("invalid catalog offset"))
+}
+
+/// The NUL-terminated string at the start of `data`.
+fn c_string(data: &[u8]) -> &[u8] {
+    let end = data.iter().position(|&b| b == 0).unwrap_or(data.len());
+    &data[..end]
+}
+
+/// A message catalog: messages by set number and message number.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Catalog {
+    sets: BTreeMap<u32, BTreeMap<u32, Vec<u8>>>,
+}
+
+impl Catalog {
+    /// Read a catalog in either binary layout.
+    pub fn parse(data: &[u8]) -> io::Result<Catalog> {
+        if data.starts_with(BSD_MAGIC) {
+            Catalog::parse_bsd(data)
+        } else if read_u32(data, 0, false)? == GNU_MAGIC {
+            Catalog::parse_gnu(data, false)
+        } else if read_u32(data, 0, true)? == GNU_MAGIC {
+            Catalog::parse_gnu(data, true)
+        } else {
+            Err(invalid("not a message catalog"))
+        }
+    }
+
+    fn parse_gnu(data: &[u8], big_endian: bool) -> io::Result<Catalog> {
+        let plane_size = read_u32(data, 4, big_endian)? as usize;
+        let plane_depth = read_u32(data, 8, big_endian)? as usize;
+        let entries = plane_size
+            .checked_mul(plane_depth)
+            .and_then(|n| n.checked_mul(3))
+            .ok_or_else(|| invalid("invalid catalog table size"))?;
+
+        // both copies of the table are the same; use the little-endian one
+        let table = 12;
+        let pool = entries
+            .checked_mul(8)
+            .and_then(|len| len.checked_add(table))
+            .and_then(|pool_at| data.get(pool_at..))
+            .ok_or_else(|| invalid("truncated message catalog"))?;
+
+        let mut catalog = Catalog::default();
+        for i in (0..entries).step_by(3) {
+            let set_id = read_u32(data, table + i * 4, false)?;
+            if set_id == 0 {
+                continue;
+            }
+            let msg_id = read_u32(data, table + (i + 1) * 4, false)?;
+            let offset = read_u32(data, table + (i + 2) * 4, false)? as usize;
+            let msg = pool
+                .get(offset..)
+                .ok_or_else(|| invalid("invalid catalog offset"))?;
+            catalog.insert(set_id - 1, msg_id, c_string(msg).to_vec());
+        }
+        Ok(catalog)
+    }
+
+    fn parse_bsd(data: &[u8]) -> io::Result<Catalog> {
+        field(data, 0, BSD_HEADER_LEN)?;
+        let num_sets = read_u32(data, 20, true)?;
+        let mut set_at = read_offset(data, 24)?;
+
+        let mut catalog = Catalog::default();
+        for _ in 0..num_sets {
+            field(data, set_at, BSD_SET_LEN)?;
+            let set_id = read_u32(data, set_at, true)?;
+            let next_set = read_offset(data, set_at + 4)?;
+            let index = read_offset(data, set_at + 12)?;
+            let data_at = read_offset(data, set_at + 20)?;
+            let data_len = read_u32(data, set_at + 28, true)? as usize;
+            let num_msgs = read_u32(data, set_at + 32, true)? as usize;
+            let set_invalid = read_u32(data, set_at + 36, true)? != 0;
+
+            if !set_invalid {
+                let strings = field(data, data_at, data_len)?;
+                for i in 0..num_msgs {
+                    let msg_at = index + i * BSD_MSG_LEN;
+                    field(data, msg_at, BSD_MSG_LEN)?;
+                    if read_u32(data, msg_at + 12, true)? != 0 {
+                        continue;
+                    }
+                    let msg_id = read_u32(data, msg_at, true)?;
+                    let offset = read_offset(data, msg_at + 4)?;
+                    let msg = strings
+                        .get(offset..)
+                        .ok_or_else(|| invalid("invalid catalog offset"))?;
+                    catalog.insert(set_id, msg_id, c_string(msg).to_vec());
+                }
+            }
+
+            if next_set == 0 {
+                break;
+            }
+            set_at = next_set;
+        }
+        Ok(catalog)
+    }
+
+    /// Read the catalog file at `path`.
+    pub fn open(path: &Path) -> io::Result<Catalog> {
+        Catalog::parse(&fs::read(path)?)
+    }
+
+    pub fn insert(&mut self, set_id: u32, msg_id: u32, msg: Vec<u8>) {
+        self.sets.entry(set_id).or_default().insert(msg_id, msg);
+    }
+
+    /// Message `msg_id` of set `set_id`.
+    pub fn catgets(&self, set_id: u32, msg_id: u32) -> Option<&[u8]> {
+        self.sets.get(&set_id)?.get(&msg_id).map(Vec::as_slice)
+    }
+
+    /// The sets in ascending order, each with its messages in ascending
+    /// order.
+    pub fn sets(&self) -> impl Iterator<Item = (u32, &BTreeMap<u32, Vec<u8>>)> {
+        self.sets.iter().map(|(&set_id, msgs)| (set_id, msgs))
+    }
+
+    /// Write the catalog as `gencat` source, every message quoted with
+    /// `"` and escaped so that it reads back the same.
+    pub fn write_msg<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        writeln!(out, "$quote \"")?;
+        for (set_id, msgs) in self.sets() {
+            writeln!(out, "$set {set_id}")?;
+            for (msg_id, msg) in msgs {
+                write!(out, "{msg_id} \"")?;
+                out.write_all(&escape(msg))?;
+                writeln!(out, "\"")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// `msg` with the characters that cannot appear as themselves in a
+/// quoted `gencat` message replaced by escape sequences.
+pub fn escape(msg: &[u8]) -> Vec<u8> {
+    let mut escaped = Vec::with_capacity(msg.len());
+    for &b in msg {
+        match b {
+            b'\n' => escaped.extend_from_slice(b"\\n"),
+            b'\t' => escaped.extend_from_slice(b"\\t"),
+            b'\r' => escaped.extend_from_slice(b"\\r"),
+            0x0b => escaped.extend_from_slice(b"\\v"),
+            0x08 => escaped.extend_from_slice(b"\\b"),
+            0x0c => escaped.extend_from_slice(b"\\f"),
+            b'\\' | b'"' => escaped.extend_from_slice(&[b'\\', b]),
+            _ if b < 0x20 || b == 0x7f => escaped.extend(format!("\\{b:03o}").bytes()),
+            _ => escaped.push(b),
+        }
+    }
+    escaped
+}
+
+/// Expand the `NLSPATH` template `template` for the catalog `name` in
+/// the locale `locale`: `%N` is the name, `%L` the locale, and `%l`,
+/// `%t` and `%c` the language, territory and codeset parts of a locale
+/// name of the form `language[_territory][.codeset][@modifier]`.
+pub fn expand_nlspath(template: &str, name: &str, locale: &str) -> PathBuf {
+    let base = locale.split_once('@').map_or(locale, |(base, _)| base);
+    let (base, codeset) = base.split_once('.').unwrap_or((base, ""));
+    let (language, territory) = base.split_once('_').unwrap_or((base, ""));
+
+    let mut path = String::new();
+    let mut chars = template.chars();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            path.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('N') => path.push_str(name),
+            Some('L') => path.push_str(locale),
+            Some('l') => path.push_str(language),
+            Some('t') => path.push_str(territory),
+            Some('c') => path.push_str(codeset),
+            Some('%') => path.push('%'),
+            Some(other) => {
+                path.push('%');
+                path.push(other);
+            }
+            None => path.push('%'),
+        }
+    }
+    PathBuf::from(path)
+}
+
+/// The paths `catopen()` tries for the catalog `name` in the locale
+/// `locale`, in order: `name` itself if it contains a slash, otherwise
+/// each template of `NLSPATH` and then of [`DEFAULT_NLSPATH`].  An empty
+/// template stands for `%N`.
+pub fn catalog_paths(name: &str, locale: &str) -> Vec<PathBuf> {
+    if name.contains('/') {
+        return vec![PathBuf::from(name)];
+    }
+    let nlspath = env::var("NLSPATH").unwrap_or_default();
+    let templates = if nlspath.is_empty() {
+        DEFAULT_NLSPATH.to_string()
+    } else {
+        format!("{nlspath}:{DEFAULT_NLSPATH}")
+    };
+    templates
+        .split(':')
+        .map(|template| {
+            let template = if template.is_empty() { "%N" } else { template };
+            expand_nlspath(template, name, locale)
+        })
+        .collect()
+}
+
+/// Open the catalog `name` as `catopen()` does.  With [`NL_CAT_LOCALE`]
+/// in `oflag` the locale comes from `LC_ALL`, `LC_MESSAGES` and `LANG`,
+/// otherwise from `LANG` alone.
+pub fn catopen(name: &str, oflag: i32) -> io::Result<Catalog> {
+    let locale = if oflag & NL_CAT_LOCALE != 0 {
+        locale::locale_name(Category::Messages)
+    } else {
+        env::var("LANG")
+            .ok()
+            .filter(|lang| !lang.is_empty())
+            .unwrap_or_else(|| String::from("C"))
+    };
+
+    for path in catalog_paths(name, &locale) {
+        if path.is_file() {
+            return Catalog::open(&path);
+        }
+    }
+    Err(io::Error::new(
+        io::ErrorKind::NotFound,
+        format!("{name}: message catalog not found"),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gnu_catalog(big_endian: bool) -> Vec<u8> {
+        let u32_bytes = |v: u32, big: bool| {
+            if big {
+                v.to_be_bytes()
+            } else {
+                v.to_le_bytes()
+            }
+        };
+        // one row of two entries: set 1 message 2, set 3 message 1
+        let table = [2, 2, 0, 4, 1, 6];
+        let mut data = Vec::new();
+        for v in [GNU_MAGIC, 2, 1] {
+            data.extend(u32_bytes(v, big_endian));
+        }
+        for big in [false, true] {
+            for v in table {
+                data.extend(u32_bytes(v, big));
+            }
+        }
+        data.extend(b"hello\0world\0");
+        data
+    }
+
+    #[test]
+    fn test_parse_gnu() {
+        for big_endian in [false, true] {
+            let catalog = Catalog::parse(&gnu_catalog(big_endian)).unwrap();
+            assert_eq!(catalog.catgets(1, 2), Some(&b"hello"[..]));
+            assert_eq!(catalog.catgets(3, 1), Some(&b"world"[..]));
+            assert_eq!(catalog.catgets(1, 1), None);
+        }
+
+        let mut truncated = gnu_catalog(false);
+        truncated.truncate(30);
+        assert!(Catalog::parse(&truncated).is_err());
+        assert!(Catalog::parse(b"not a catalog").is_err());
+    }
+
+    #[test]
+    fn test_parse_bsd() {
+        let mut data = Vec::new();
+        data.extend(BSD_MAGIC);
+        for v in [1, 0, 0, 1] {
+            data.extend(u32::to_be_bytes(v));
+        }
+        data.extend(32i64.to_be_bytes());
+        // set 2 at 32, its strings at 72, its index at 80
+        data.extend(2u32.to_be_bytes());
+        data.extend(0i64.to_be_bytes());
+        data.extend(80i64.to_be_bytes());
+        data.extend(72i64.to_be_bytes());
+        for v in [8, 2, 0] {
+            data.extend(u32::to_be_bytes(v));
+        }
+        data.extend(b"one\0two\0");
+        for (msg_id, offset, invalid) in [(1u32, 0i64, 0u32), (5, 4, 0)] {
+            data.extend(msg_id.to_be_bytes());
+            data.extend(offset.to_be_bytes());
+            data.extend(invalid.to_be_bytes());
+        }
+
+        let catalog = Catalog::parse(&data).unwrap();
+        assert_eq!(catalog.catgets(2, 1), Some(&b"one"[..]));
+        assert_eq!(catalog.catgets(2, 5), Some(&b"two"[..]));
+        assert_eq!(catalog.sets().count(), 1);
+    }
+
+    #[test]
+    fn test_write_msg() {
+        let mut catalog = Catalog::default();
+        catalog.insert(2, 1, b"say \"hi\"\\\n\x01".to_vec());
+        catalog.insert(1, 3, b" padded ".to_vec());
+        let mut out = Vec::new();
+        catalog.write_msg(&mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "$quote \"\n$set 1\n3 \" padded \"\n$set 2\n1 \"say \\\"hi\\\"\\\\\\n\\001\"\n"
+        );
+    }
+
+    #[test]
+    fn test_expand_nlspath() {
+        let locale = "de_AT.UTF-8@euro";
+        assert_eq!(
+            expand_nlspath("/nls/%L/%N.cat", "app", locale),
+            PathBuf::from("/nls/de_AT.UTF-8@euro/app.cat")
+        );
+        assert_eq!(
+            expand_nlspath("/nls/%l_%t/%c/%N%%%x", "app", locale),
+            PathBuf::from("/nls/de_AT/UTF-8/app%%x")
+        );
+        assert_eq!(
+            expand_nlspath("/nls/%l/%t/%N", "app", "C"),
+            PathBuf::from("/nls/C//app")
+        );
+    }
+
+    #[test]
+    fn test_catopen() {
+        let dir = env::temp_dir().join(format!("nlcat-test-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("app.cat"), gnu_catalog(false)).unwrap();
+
+        env::set_var("NLSPATH", format!("{}/%N.cat", dir.display()));
+        let catalog = catopen("app", NL_CAT_LOCALE).unwrap();
+        assert_eq!(catalog.catgets(3, 1), Some(&b"world"[..]));
+        assert_eq!(
+            catopen("missing", 0).unwrap_err().kind(),
+            io::ErrorKind::NotFound
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_catalog_paths() {
+        assert_eq!(
+            catalog_paths("./app.cat", "fr_FR"),
+            vec![PathBuf::from("./app.cat")]
+        );
+    }
+}
@@ -0,0 +1,208 @@
+//
+// Copyright (c) 2024 Hemi Labs, Inc.
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+//! `printf` format strings of `c-format` messages.
+
+/// The arguments consumed by the directives of a format string: the
+/// type each argument is converted as, such as `d`, `ld` or `s`, by
+/// argument number.  A `*` width or precision consumes an `int`.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Arguments(pub Vec<String>);
+
+/// Record that argument `index` has type `ty`, returning false if it
+/// already has another.
+fn set(args: &mut Vec<Option<String>>, index: usize, ty: String) -> bool {
+    if args.len() <= index {
+        args.resize(index + 1, None);
+    }
+    match &args[index] {
+        Some(existing) if *existing != ty => false,
+        _ => {
+            args[index] = Some(ty);
+            true
+        }
+    }
+}
+
+/// Parse the directives of a format string, or return `None` if a
+/// directive is malformed or an argument is used with two types.
+pub fn parse(fmt: &[u8]) -> Option<Arguments> {
+    let mut args: Vec<Option<String>> = Vec::new();
+    let mut next = 0;
+
+    let mut i = 0;
+    while i < fmt.len() {
+        if fmt[i] != b'%' {
+            i += 1;
+            continue;
+        }
+        i += 1;
+        if fmt.get(i) == Some(&b'%') {
+            i += 1;
+            continue;
+        }
+        // An explicit argument number, `%N$`.
+        let digits = fmt[i..].iter().take_while(|b| b.is_ascii_digit()).count();
+        let mut position = None;
+        if digits > 0 && fmt.get(i + digits) == Some(&b'$') {
+            let n: usize = std::str::from_utf8(&fmt[i..i + digits])
+                .ok()?
+                .parse()
+                .ok()?;
+            position = Some(n.checked_sub(1)?);
+            i += digits + 1;
+        }
+        while matches!(fmt.get(i), Some(b'-' | b'+' | b' ' | b'#' | b'0' | b'\'')) {
+            i += 1;
+        }
+        // Width and precision.
+        for prefix in [None, Some(b'.')] {
+            if let Some(p) = prefix {
+                if fmt.get(i) != Some(&p) {
+                    continue;
+                }
+                i += 1;
+            }
+            if fmt.get(i) == Some(&b'*') {
+                i += 1;
+                let digits = fmt[i..].iter().take_while(|b| b.is_ascii_digit()).count();
+                let index = if digits > 0 && fmt.get(i + digits) == Some(&b'$') {
+                    let n: usize = std::str::from_utf8(&fmt[i..i + digits])
+                        .ok()?
+                        .parse()
+                        .ok()?;
+                    i += digits + 1;
+                    n.checked_sub(1)?
+                } else {
+                    next += 1;
+                    next - 1
+                };
+                if !set(&mut args, index, String::from("d")) {
+                    return None;
+                }
+            } else {
+                while fmt.get(i).is_some_and(u8::is_ascii_digit) {
+                    i += 1;
+                }
+            }
+        }
+        let start = i;
+        while matches!(
+            fmt.get(i),
+            Some(b'h' | b'l' | b'j' | b'z' | b't' | b'L' | b'q')
+        ) {
+            i += 1;
+        }
+        let conversion = *fmt.get(i)?;
+        if !b"diouxXeEfFgGaAcspnCS".contains(&conversion) {
+            return None;
+        }
+        i += 1;
+        let length = std::str::from_utf8(&fmt[start..i - 1]).ok()?;
+        let class = match conversion {
+            b'i' => 'd',
+            b'o' | b'x' | b'X' => 'u',
+            b'E' | b'f' | b'F' | b'g' | b'G' | b'a' | b'A' => 'e',
+            c => char::from(c),
+        };
+        let index = position.unwrap_or_else(|| {
+            next += 1;
+            next - 1
+        });
+        if !set(&mut args, index, format!("{length}{class}")) {
+            return None;
+        }
+    }
+    Some(Arguments(
+        args.into_iter().map(|a| a.unwrap_or_default()).collect(),
+    ))
+}
+
+/// Whether `s` contains a `printf` directive, as `xgettext` uses to
+/// decide a message is in C format.
+pub fn has_directives(s: &[u8]) -> bool {
+    parse(s).is_some_and(|args| !args.0.is_empty())
+}
+
+/// Compare the directives of a translation with those of its msgid.  A
+/// translation of a plural message may leave out arguments, such as the
+/// count in a form used only for one value; otherwise every argument must
+/// be used with the same type.  Returns a description of the first
+/// difference.
+pub fn check(msgid: &[u8], msgstr: &[u8], allow_missing: bool) -> Result<(), String> {
+    let Some(expected) = parse(msgid) else {
+        return Ok(());
+    };
+    let Some(actual) = parse(msgstr) else {
+        return Err(String::from("'msgstr' is not a valid C format string"));
+    };
+    if actual.0.len() > expected.0.len() {
+        return Err(String::from(
+            "a format specification for argument in 'msgstr' doesn't exist in 'msgid'",
+        ));
+    }
+    for (n, ty) in expected.0.iter().enumerate() {
+        match actual.0.get(n).filter(|t| !t.is_empty()) {
+            Some(t) if t == ty => {}
+            Some(_) => {
+                return Err(format!(
+                "format specifications in 'msgid' and 'msgstr' for argument {} are not the same",
+                n + 1
+            ))
+            }
+            None if allow_missing || ty.is_empty() => {}
+            None => {
+                return Err(format!(
+                    "a format specification for argument {} doesn't exist in 'msgstr'",
+                    n + 1
+                ))
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(fmt: &str) -> Option<Vec<String>> {
+        parse(fmt.as_bytes()).map(|a| a.0)
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(args("no directives 100%%"), Some(vec![]));
+        assert_eq!(
+            args("%s: %5.2f %ld %x%c"),
+            Some(vec![
+                "s".into(),
+                "e".into(),
+                "ld".into(),
+                "u".into(),
+                "c".into()
+            ])
+        );
+        assert_eq!(args("%2$s %1$d"), Some(vec!["d".into(), "s".into()]));
+        assert_eq!(args("%*d"), Some(vec!["d".into(), "d".into()]));
+        assert_eq!(args("%1$d %1$s"), None);
+        assert_eq!(args("%y"), None);
+        assert!(has_directives(b"%d files"));
+        assert!(!has_directives(b"50%% off"));
+    }
+
+    #[test]
+    fn test_check() {
+        assert!(check(b"%d of %s", b"%2$s: %1$d", false).is_ok());
+        assert!(check(b"%d of %s", b"%s of %d", false).is_err());
+        assert!(check(b"%d file", b"one file", false).is_err());
+        assert!(check(b"%d file", b"one file", true).is_ok());
+        assert!(check(b"file", b"%d", false).is_err());
+    }
+}
@@ -0,0 +1,604 @@
+//
+// Copyright (c) 2024 Hemi Labs, Inc.
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+//! Portable object (`.po`) files.
+//!
+//! A PO file is a sequence of entries, each optionally preceded by
+//! comments:
+//!
+//! ```text
+//! #, c-format
+//! msgctxt "menu"
+//! msgid "%d file"
+//! msgid_plural "%d files"
+//! msgstr[0] "%d Datei"
+//! msgstr[1] "%d Dateien"
+//! ```
+//!
+//! Strings may be continued on following lines that hold only a quoted
+//! string, entries commented out with `#~` are obsolete, and a
+//! `domain "name"` line places the entries after it in another domain.
+//! Strings are kept as bytes, in the character set of the file.
+
+pub mod format;
+
+use std::fmt;
+use std::io::{self, Write};
+
+/// One entry of a PO file.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Message {
+    pub domain: Option<String>,
+    pub context: Option<Vec<u8>>,
+    pub id: Vec<u8>,
+    pub id_plural: Option<Vec<u8>>,
+    /// The `msgstr`, or each `msgstr[N]` of a plural message.
+    pub strs: Vec<Vec<u8>>,
+    /// Translator comments, `# text`.
+    pub comments: Vec<String>,
+    /// Comments for translators extracted from the sources, `#. text`.
+    pub extracted_comments: Vec<String>,
+    /// Source references, `#: file:line`.
+    pub references: Vec<String>,
+    /// The flags of `#,` comments, such as `fuzzy` and `c-format`.
+    pub flags: Vec<String>,
+    pub obsolete: bool,
+    /// The line of the entry's first keyword.
+    pub line: usize,
+}
+
+impl Message {
+    /// Whether this is the header entry, the one with an empty msgid.
+    pub fn is_header(&self) -> bool {
+        self.context.is_none() && self.id.is_empty()
+    }
+
+    pub fn has_flag(&self, flag: &str) -> bool {
+        self.flags.iter().any(|f| f == flag)
+    }
+
+    pub fn is_fuzzy(&self) -> bool {
+        self.has_flag("fuzzy")
+    }
+
+    /// Whether every form of the translation is non-empty.
+    pub fn is_translated(&self) -> bool {
+        !self.strs.is_empty() && self.strs.iter().all(|s| !s.is_empty())
+    }
+
+    /// The string a catalog is keyed by: the msgid, prefixed with the
+    /// context and an EOT character when there is one.
+    pub fn key(&self) -> Vec<u8> {
+        let mut key = Vec::new();
+        if let Some(context) = &self.context {
+            key.extend_from_slice(context);
+            key.push(0x04);
+        }
+        key.extend_from_slice(&self.id);
+        key
+    }
+}
+
+/// A syntax error, at a line of the file.
+#[derive(Debug, PartialEq, Eq)]
+pub struct ParseError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.line, self.message)
+    }
+}
+
+/// The part of an entry a continuation string is appended to.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Section {
+    None,
+    Context,
+    Id,
+    IdPlural,
+    Str,
+}
+
+struct Parser {
+    messages: Vec<Message>,
+    current: Message,
+    section: Section,
+    domain: Option<String>,
+    line: usize,
+}
+
+fn error(line: usize, message: &str) -> ParseError {
+    ParseError {
+        line,
+        message: message.to_string(),
+    }
+}
+
+/// Parse a C-style quoted string, returning its value.
+pub fn parse_string(s: &[u8], line: usize) -> Result<Vec<u8>, ParseError> {
+    let inner = s
+        .trim_ascii()
+        .strip_prefix(b"\"")
+        .ok_or_else(|| error(line, "keyword must be followed by a string"))?;
+    let mut out = Vec::new();
+    let mut bytes = inner.iter().copied();
+    loop {
+        match bytes.next() {
+            None => return Err(error(line, "end-of-line within string")),
+            Some(b'"') => break,
+            Some(b'\\') => {
+                let c = bytes
+                    .next()
+                    .ok_or_else(|| error(line, "end-of-line within string"))?;
+                let value = match c {
+                    b'n' => b'\n',
+                    b't' => b'\t',
+                    b'r' => b'\r',
+                    b'a' => 0x07,
+                    b'b' => 0x08,
+                    b'f' => 0x0c,
+                    b'v' => 0x0b,
+                    b'\\' | b'"' | b'\'' | b'?' => c,
+                    b'0'..=b'7' => {
+                        let mut value = u32::from(c - b'0');
+                        for _ in 0..2 {
+                            match bytes.clone().next() {
+                                Some(d @ b'0'..=b'7') => {
+                                    value = value * 8 + u32::from(d - b'0');
+                                    bytes.next();
+                                }
+                                _ => break,
+                            }
+                        }
+                        u8::try_from(value).map_err(|_| error(line, "invalid control sequence"))?
+                    }
+                    b'x' => {
+                        let mut value = 0u32;
+                        let mut digits = 0;
+                        while let Some(d) = bytes
+                            .clone()
+                            .next()
+                            .and_then(|d| char::from(d).to_digit(16).filter(|_| digits < 2))
+                        {
+                            value = value * 16 + d;
+                            digits += 1;
+                            bytes.next();
+                        }
+                        if digits == 0 {
+                            return Err(error(line, "invalid control sequence"));
+                        }
+                        value as u8
+                    }
+                    _ => return Err(error(line, "invalid control sequence")),
+                };
+                out.push(value);
+            }
+            Some(b) => out.push(b),
+        }
+    }
+    if !bytes.all(|b| b.is_ascii_whitespace()) {
+        return Err(error(line, "syntax error"));
+    }
+    Ok(out)
+}
+
+impl Parser {
+    /// Finish the current entry, if it has begun.
+    fn finish(&mut self) -> Result<(), ParseError> {
+        if self.section == Section::None {
+            // Comments without an entry are dropped.
+            self.current = Message::default();
+            return Ok(());
+        }
+        if self.section != Section::Str {
+            return Err(error(self.current.line, "missing 'msgstr' section"));
+        }
+        let mut message = std::mem::take(&mut self.current);
+        message.domain = self.domain.clone();
+        self.messages.push(message);
+        self.section = Section::None;
+        Ok(())
+    }
+
+    fn start_keyword(&mut self, obsolete: bool) {
+        if self.section == Section::None {
+            self.current.line = self.line;
+            self.current.obsolete = obsolete;
+        }
+    }
+
+    fn keyword(&mut self, keyword: &str, rest: &[u8], obsolete: bool) -> Result<(), ParseError> {
+        let line = self.line;
+        if matches!(keyword, "msgctxt" | "msgid") && self.section == Section::Str {
+            self.finish()?;
+        }
+        self.start_keyword(obsolete);
+        let value = parse_string(rest, line)?;
+        match keyword {
+            "msgctxt" if self.section == Section::None => {
+                self.current.context = Some(value);
+                self.section = Section::Context;
+            }
+            "msgid" if matches!(self.section, Section::None | Section::Context) => {
+                self.current.id = value;
+                self.section = Section::Id;
+            }
+            "msgid_plural" if self.section == Section::Id => {
+                self.current.id_plural = Some(value);
+                self.section = Section::IdPlural;
+            }
+            "msgstr" if self.section == Section::Id => {
+                self.current.strs = vec![value];
+                self.section = Section::Str;
+            }
+            "msgstr" if self.section == Section::IdPlural => {
+                return Err(error(line, "missing 'msgstr[]' section"));
+            }
+            _ => {
+                if let Some(index) = keyword
+                    .strip_prefix("msgstr[")
+                    .and_then(|k| k.strip_suffix(']'))
+                {
+                    let expected = match self.section {
+                        Section::IdPlural => 0,
+                        Section::Str if self.current.id_plural.is_some() => self.current.strs.len(),
+                        Section::Id => return Err(error(line, "missing 'msgid_plural' section")),
+                        _ => return Err(error(line, "syntax error")),
+                    };
+                    if index.parse::<usize>().ok() != Some(expected) {
+                        return Err(error(line, "plural form has wrong index"));
+                    }
+                    self.current.strs.push(value);
+                    self.section = Section::Str;
+                } else {
+                    return Err(error(line, "syntax error"));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn continuation(&mut self, rest: &[u8]) -> Result<(), ParseError> {
+        let value = parse_string(rest, self.line)?;
+        let target = match self.section {
+            Section::None => return Err(error(self.line, "syntax error")),
+            Section::Context => self.current.context.as_mut().unwrap(),
+            Section::Id => &mut self.current.id,
+            Section::IdPlural => self.current.id_plural.as_mut().unwrap(),
+            Section::Str => self.current.strs.last_mut().unwrap(),
+        };
+        target.extend_from_slice(&value);
+        Ok(())
+    }
+
+    fn line(&mut self, text: &[u8]) -> Result<(), ParseError> {
+        let text = text.trim_ascii();
+        let (text, obsolete) = match text.strip_prefix(b"#~") {
+            Some(rest) => (rest.trim_ascii_start(), true),
+            None => (text, false),
+        };
+        if text.is_empty() {
+            return Ok(());
+        }
+        if let Some(comment) = text.strip_prefix(b"#") {
+            if self.section == Section::Str {
+                self.finish()?;
+            }
+            let comment = String::from_utf8_lossy(comment);
+            let current = &mut self.current;
+            match comment.chars().next() {
+                Some(',') => current
+                    .flags
+                    .extend(comment[1..].split(',').map(|f| f.trim().to_string())),
+                Some('.') => current
+                    .extracted_comments
+                    .push(comment[1..].trim().to_string()),
+                Some(':') => current
+                    .references
+                    .extend(comment[1..].split_whitespace().map(String::from)),
+                // The msgid of the previous translation of a fuzzy entry.
+                Some('|') => {}
+                _ => current
+                    .comments
+                    .push(comment.strip_prefix(' ').unwrap_or(&comment).to_string()),
+            }
+            return Ok(());
+        }
+        if text.starts_with(b"\"") {
+            return self.continuation(text);
+        }
+        let split = text
+            .iter()
+            .position(u8::is_ascii_whitespace)
+            .ok_or_else(|| error(self.line, "syntax error"))?;
+        let (keyword, rest) = text.split_at(split);
+        let keyword = std::str::from_utf8(keyword).unwrap_or_default();
+        if keyword == "domain" && !obsolete {
+            self.finish()?;
+            let name = parse_string(rest, self.line)?;
+            self.domain = Some(String::from_utf8_lossy(&name).into_owned());
+            return Ok(());
+        }
+        self.keyword(keyword, rest, obsolete)
+    }
+}
+
+/// Parse the contents of a PO file.
+pub fn parse(data: &[u8]) -> Result<Vec<Message>, ParseError> {
+    let mut parser = Parser {
+        messages: Vec::new(),
+        current: Message::default(),
+        section: Section::None,
+        domain: None,
+        line: 0,
+    };
+    for (index, line) in data.split(|&b| b == b'\n').enumerate() {
+        parser.line = index + 1;
+        parser.line(line)?;
+    }
+    parser.finish()?;
+    Ok(parser.messages)
+}
+
+/// Escape a string for a PO file, without the quotes.
+pub fn escape(s: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(s.len());
+    for &b in s {
+        match b {
+            b'\\' => out.extend_from_slice(b"\\\\"),
+            b'"' => out.extend_from_slice(b"\\\""),
+            b'\n' => out.extend_from_slice(b"\\n"),
+            b'\t' => out.extend_from_slice(b"\\t"),
+            b'\r' => out.extend_from_slice(b"\\r"),
+            0x07 => out.extend_from_slice(b"\\a"),
+            0x08 => out.extend_from_slice(b"\\b"),
+            0x0b => out.extend_from_slice(b"\\v"),
+            0x0c => out.extend_from_slice(b"\\f"),
+            0..=0x1f | 0x7f => out.extend_from_slice(format!("\\{b:03o}").as_bytes()),
+            _ => out.push(b),
+        }
+    }
+    out
+}
+
+/// Output lines are kept within this width where possible.
+const WIDTH: usize = 79;
+
+/// Write `keyword "value"`, split after each newline of the value and
+/// wrapped at spaces when it does not fit on one line.
+fn write_string<W: Write>(
+    out: &mut W,
+    prefix: &str,
+    keyword: &str,
+    value: &[u8],
+) -> io::Result<()> {
+    let escaped = escape(value);
+    let inner_newline = value
+        .iter()
+        .take(value.len().saturating_sub(1))
+        .any(|&b| b == b'\n');
+    if !inner_newline && prefix.len() + keyword.len() + escaped.len() + 3 <= WIDTH {
+        write!(out, "{prefix}{keyword} \"")?;
+        out.write_all(&escaped)?;
+        return writeln!(out, "\"");
+    }
+    writeln!(out, "{prefix}{keyword} \"\"")?;
+    let max = WIDTH - prefix.len() - 2;
+    for segment in value.split_inclusive(|&b| b == b'\n') {
+        let mut line: Vec<u8> = Vec::new();
+        for word in escape(segment).split_inclusive(|&b| b == b' ') {
+            if !line.is_empty() && line.len() + word.len() > max {
+                write!(out, "{prefix}\"")?;
+                out.write_all(&line)?;
+                writeln!(out, "\"")?;
+                line.clear();
+            }
+            line.extend_from_slice(word);
+        }
+        write!(out, "{prefix}\"")?;
+        out.write_all(&line)?;
+        writeln!(out, "\"")?;
+    }
+    Ok(())
+}
+
+/// Write the comments, references and flags of a message.
+fn write_comments<W: Write>(out: &mut W, message: &Message) -> io::Result<()> {
+    for comment in &message.comments {
+        if comment.is_empty() {
+            writeln!(out, "#")?;
+        } else {
+            writeln!(out, "# {comment}")?;
+        }
+    }
+    for comment in &message.extracted_comments {
+        writeln!(out, "#. {comment}")?;
+    }
+    let mut line = String::new();
+    for reference in &message.references {
+        if !line.is_empty() && line.len() + reference.len() + 1 > WIDTH {
+            writeln!(out, "{line}")?;
+            line.clear();
+        }
+        if line.is_empty() {
+            line.push_str("#:");
+        }
+        line.push(' ');
+        line.push_str(reference);
+    }
+    if !line.is_empty() {
+        writeln!(out, "{line}")?;
+    }
+    if !message.flags.is_empty() {
+        writeln!(out, "#, {}", message.flags.join(", "))?;
+    }
+    Ok(())
+}
+
+/// Write messages in PO format, separated by blank lines.  A `domain`
+/// line is written wherever the domain changes.
+pub fn write<W: Write>(out: &mut W, messages: &[Message]) -> io::Result<()> {
+    let mut domain = None;
+    for (i, message) in messages.iter().enumerate() {
+        if i > 0 {
+            writeln!(out)?;
+        }
+        if message.domain.is_some() && message.domain != domain {
+            domain = message.domain.clone();
+            writeln!(
+                out,
+                "domain \"{}\"\n",
+                domain.as_deref().unwrap_or_default()
+            )?;
+        }
+        write_comments(out, message)?;
+        let prefix = if message.obsolete { "#~ " } else { "" };
+        if let Some(context) = &message.context {
+            write_string(out, prefix, "msgctxt", context)?;
+        }
+        write_string(out, prefix, "msgid", &message.id)?;
+        match &message.id_plural {
+            Some(plural) => {
+                write_string(out, prefix, "msgid_plural", plural)?;
+                for (n, msgstr) in message.strs.iter().enumerate() {
+                    write_string(out, prefix, &format!("msgstr[{n}]"), msgstr)?;
+                }
+            }
+            None => {
+                let msgstr = message.strs.first().map_or(&[][..], |s| &s[..]);
+                write_string(out, prefix, "msgstr", msgstr)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// The value of the header field `name`, such as `Plural-Forms`.
+pub fn header_field<'a>(header: &'a str, name: &str) -> Option<&'a str> {
+    header.lines().find_map(|line| {
+        let (field, value) = line.split_once(':')?;
+        (field.trim() == name).then(|| value.trim())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let po = "# comment\n\
+                  msgid \"\"\n\
+                  msgstr \"\"\n\
+                  \"Content-Type: text/plain; charset=UTF-8\\n\"\n\
+                  \n\
+                  #, fuzzy, c-format\n\
+                  msgctxt \"menu\"\n\
+                  msgid \"%d file\"\n\
+                  msgid_plural \"%d files\"\n\
+                  msgstr[0] \"%d Datei\"\n\
+                  msgstr[1] \"%d \\\"Dateien\\\"\\t\\101\\x42\"\n\
+                  domain \"other\"\n\
+                  msgid \"caf\u{e9}\"\n\
+                  msgstr \"\"\n\
+                  #~ msgid \"old\"\n\
+                  #~ msgstr \"alt\"\n";
+        let messages = parse(po.as_bytes()).unwrap();
+        assert_eq!(messages.len(), 4);
+        assert!(messages[0].is_header());
+        assert_eq!(
+            messages[0].strs,
+            vec![b"Content-Type: text/plain; charset=UTF-8\n".to_vec()]
+        );
+
+        let plural = &messages[1];
+        assert_eq!(plural.line, 7);
+        assert_eq!(plural.context.as_deref(), Some(&b"menu"[..]));
+        assert_eq!(plural.key(), b"menu\x04%d file");
+        assert_eq!(plural.id_plural.as_deref(), Some(&b"%d files"[..]));
+        assert_eq!(plural.strs[1], b"%d \"Dateien\"\tAB");
+        assert!(plural.is_fuzzy() && plural.has_flag("c-format"));
+        assert_eq!(plural.domain, None);
+
+        assert_eq!(messages[2].id, "café".as_bytes());
+        assert_eq!(messages[2].domain.as_deref(), Some("other"));
+        assert!(!messages[2].is_translated());
+        assert!(messages[3].obsolete);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let err = |po: &str| parse(po.as_bytes()).unwrap_err();
+        assert_eq!(err("msgid \"a\"\n").message, "missing 'msgstr' section");
+        assert_eq!(err("msgid \"a\nmsgstr \"\"\n").line, 1);
+        assert_eq!(
+            err("msgid \"a\"\nmsgstr[0] \"b\"\n").message,
+            "missing 'msgid_plural' section"
+        );
+        assert_eq!(
+            err("msgid \"a\"\nmsgid_plural \"b\"\nmsgstr[1] \"c\"\n").message,
+            "plural form has wrong index"
+        );
+        assert_eq!(err("msgid \"\\q\"\n").message, "invalid control sequence");
+        assert_eq!(err("bogus\n").line, 1);
+    }
+
+    #[test]
+    fn test_header_field() {
+        let header = "Language: de\nPlural-Forms: nplurals=2; plural=n != 1;\n";
+        assert_eq!(
+            header_field(header, "Plural-Forms"),
+            Some("nplurals=2; plural=n != 1;")
+        );
+        assert_eq!(header_field(header, "Content-Type"), None);
+    }
+
+    #[test]
+    fn test_write() {
+        let po = "# translator\n\
+                  #. extracted\n\
+                  #: a.rs:1 b.rs:2\n\
+                  #, c-format\n\
+                  msgid \"%d \\\"file\\\"\"\n\
+                  msgid_plural \"%d files\"\n\
+                  msgstr[0] \"\"\n\
+                  msgstr[1] \"\"\n\
+                  \n\
+                  msgid \"\"\n\
+                  \"first line\\n\"\n\
+                  \"second line\\n\"\n\
+                  msgstr \"\"\n\
+                  \n\
+                  #~ msgid \"old\"\n\
+                  #~ msgstr \"alt\"\n";
+        let messages = parse(po.as_bytes()).unwrap();
+        assert_eq!(messages[0].references, vec!["a.rs:1", "b.rs:2"]);
+        let mut out = Vec::new();
+        write(&mut out, &messages).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), po);
+
+        let long = Message {
+            id: b"word ".repeat(20),
+            ..Message::default()
+        };
+        let mut out = Vec::new();
+        write(&mut out, &[long]).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            format!(
+                "msgid \"\"\n\"{}\"\n\"{}\"\nmsgstr \"\"\n",
+                "word ".repeat(15),
+                "word ".repeat(5)
+            )
+        );
+    }
+}
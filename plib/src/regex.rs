@@ -0,0 +1,1946 @@
+//
+// Copyright (c) 2024 Hemi Labs, Inc.
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+//! POSIX basic (BRE) and extended (ERE) regular expressions.
+//!
+//! This is a self-contained replacement for the libc `regcomp`/`regexec`
+//! pair, so that every utility matches the same way regardless of the
+//! C library it was linked against.  Matching operates on `&[u8]`:
+//! well-formed UTF-8 sequences are treated as single characters, and any
+//! other byte is treated as a character of its own, so NUL bytes and
+//! non-UTF-8 input never cause a failure.
+//!
+//! Matching follows the POSIX leftmost-longest rule.  Patterns without
+//! back-references are matched with a Thompson NFA simulation, and
+//! sub-match offsets are recovered afterwards by splitting the overall
+//! match top-down, giving each subexpression the longest possible span
+//! from left to right (the approach of Henry Spencer's "dissect" phase).
+//! Patterns with back-references fall back to a backtracking search, as
+//! the problem is not regular any more.  It runs on a stack of its own and
+//! never explores a state twice, and an NFA for a regular superset of the
+//! pattern, with each back-reference replaced by a copy of its
+//! subexpression, rules out most of the text that cannot match first.
+
+use crate::locale;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::ops::Range;
+
+/// Maximum repetition count accepted in an interval expression.
+pub const RE_DUP_MAX: u32 = 255;
+
+/// Upper bound on the size of a compiled program.
+const MAX_PROGRAM_SIZE: usize = 1 << 20;
+
+/// How many states the backtracking matcher remembers before it forgets
+/// them all, which bounds its memory at the cost of exploring some states
+/// again.
+const MAX_VISITED_STATES: usize = 1 << 20;
+
+/// Bytes that are not part of a well-formed UTF-8 sequence are mapped into
+/// the low surrogate range, which can never be produced by decoding UTF-8.
+const RAW_BYTE_BASE: u32 = 0xDC00;
+
+/// Compilation flags, equivalent to the `cflags` argument of `regcomp`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RegexFlags {
+    /// Use extended regular expressions (`REG_EXTENDED`).
+    pub extended: bool,
+    /// Ignore case when matching (`REG_ICASE`).
+    pub icase: bool,
+    /// Treat newline as a line separator (`REG_NEWLINE`).
+    pub newline: bool,
+    /// Only report success or failure (`REG_NOSUB`).
+    pub nosub: bool,
+}
+
+impl RegexFlags {
+    /// Flags for a basic regular expression.
+    pub fn bre() -> Self {
+        Self::default()
+    }
+
+    /// Flags for an extended regular expression.
+    pub fn ere() -> Self {
+        Self {
+            extended: true,
+            ..Self::default()
+        }
+    }
+
+    pub fn ignore_case(mut self, icase: bool) -> Self {
+        self.icase = icase;
+        self
+    }
+
+    pub fn newline(mut self, newline: bool) -> Self {
+        self.newline = newline;
+        self
+    }
+
+    pub fn no_sub(mut self, nosub: bool) -> Self {
+        self.nosub = nosub;
+        self
+    }
+}
+
+/// Execution flags, equivalent to the `eflags` argument of `regexec`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MatchFlags {
+    /// The beginning of the input is not the beginning of a line (`REG_NOTBOL`).
+    pub not_bol: bool,
+    /// The end of the input is not the end of a line (`REG_NOTEOL`).
+    pub not_eol: bool,
+}
+
+/// Errors reported while compiling a regular expression.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RegexError {
+    /// Invalid collating element referenced (`REG_ECOLLATE`).
+    Collate,
+    /// Invalid character class type referenced (`REG_ECTYPE`).
+    CharClass,
+    /// Trailing backslash (`REG_EESCAPE`).
+    Escape,
+    /// Number in `\digit` invalid or in error (`REG_ESUBREG`).
+    SubReg,
+    /// Bracket imbalance (`REG_EBRACK`).
+    Bracket,
+    /// Parenthesis imbalance (`REG_EPAREN`).
+    Paren,
+    /// Brace imbalance (`REG_EBRACE`).
+    Brace,
+    /// Content of an interval expression is invalid (`REG_BADBR`).
+    BadBrace,
+    /// Invalid endpoint in a range expression (`REG_ERANGE`).
+    Range,
+    /// The compiled expression would be too large (`REG_ESPACE`).
+    Space,
+    /// A repetition operator is not preceded by a valid expression (`REG_BADRPT`).
+    BadRepeat,
+}
+
+impl fmt::Display for RegexError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let msg = match self {
+            RegexError::Collate => "Invalid collation character",
+            RegexError::CharClass => "Invalid character class name",
+            RegexError::Escape => "Trailing backslash",
+            RegexError::SubReg => "Invalid back reference",
+            RegexError::Bracket => "Unmatched [, [^, [:, [., or [=",
+            RegexError::Paren => "Unmatched ( or \\(",
+            RegexError::Brace => "Unmatched \\{",
+            RegexError::BadBrace => "Invalid content of \\{\\}",
+            RegexError::Range => "Invalid range end",
+            RegexError::Space => "Regular expression too big",
+            RegexError::BadRepeat => "Invalid preceding regular expression",
+        };
+        f.write_str(msg)
+    }
+}
+
+impl std::error::Error for RegexError {}
+
+/// Sub-match offsets of a successful match.  Index 0 holds the whole match,
+/// index `n` the `n`th parenthesized subexpression, or `None` if it did not
+/// participate in the match.
+pub type Captures = Vec<Option<Range<usize>>>;
+
+/// Character classes usable as `[:name:]` inside a bracket expression.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CharClass {
+    Alnum,
+    Alpha,
+    Blank,
+    Cntrl,
+    Digit,
+    Graph,
+    Lower,
+    Print,
+    Punct,
+    Space,
+    Upper,
+    Xdigit,
+}
+
+impl CharClass {
+    pub fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "alnum" => CharClass::Alnum,
+            "alpha" => CharClass::Alpha,
+            "blank" => CharClass::Blank,
+            "cntrl" => CharClass::Cntrl,
+            "digit" => CharClass::Digit,
+            "graph" => CharClass::Graph,
+            "lower" => CharClass::Lower,
+            "print" => CharClass::Print,
+            "punct" => CharClass::Punct,
+            "space" => CharClass::Space,
+            "upper" => CharClass::Upper,
+            "xdigit" => CharClass::Xdigit,
+            _ => return None,
+        })
+    }
+
+    pub fn matches(&self, c: char) -> bool {
+        match self {
+            CharClass::Alnum => c.is_alphanumeric(),
+            CharClass::Alpha => c.is_alphabetic(),
+            CharClass::Blank => c == ' ' || c == '\t',
+            CharClass::Cntrl => c.is_control(),
+            CharClass::Digit => c.is_ascii_digit(),
+            CharClass::Graph => !c.is_control() && !c.is_whitespace(),
+            CharClass::Lower => c.is_lowercase(),
+            CharClass::Print => !c.is_control(),
+            CharClass::Punct => {
+                if c.is_ascii() {
+                    c.is_ascii_punctuation()
+                } else {
+                    !c.is_control() && !c.is_whitespace() && !c.is_alphanumeric()
+                }
+            }
+            CharClass::Space => c.is_whitespace(),
+            CharClass::Upper => c.is_uppercase(),
+            CharClass::Xdigit => c.is_ascii_hexdigit(),
+        }
+    }
+}
+
+/// Decodes the character starting at the beginning of `s`, returning its
+/// code and its length in bytes.
+fn decode(s: &[u8]) -> (u32, usize) {
+    let b0 = s[0];
+    if b0 < 0x80 {
+        return (b0 as u32, 1);
+    }
+    let width = match b0 {
+        0xC2..=0xDF => 2,
+        0xE0..=0xEF => 3,
+        0xF0..=0xF4 => 4,
+        _ => 0,
+    };
+    if width != 0 && s.len() >= width {
+        if let Ok(st) = std::str::from_utf8(&s[..width]) {
+            return (st.chars().next().unwrap() as u32, width);
+        }
+    }
+    (RAW_BYTE_BASE + b0 as u32, 1)
+}
+
+fn simple_lower(c: u32) -> u32 {
+    match char::from_u32(c) {
+        Some(ch) => {
+            let mut lower = ch.to_lowercase();
+            match (lower.next(), lower.next()) {
+                (Some(l), None) => l as u32,
+                _ => c,
+            }
+        }
+        None => c,
+    }
+}
+
+fn simple_upper(c: u32) -> u32 {
+    match char::from_u32(c) {
+        Some(ch) => {
+            let mut upper = ch.to_uppercase();
+            match (upper.next(), upper.next()) {
+                (Some(u), None) => u as u32,
+                _ => c,
+            }
+        }
+        None => c,
+    }
+}
+
+/// A bracket expression, e.g. `[^a-z[:digit:]]`.
+#[derive(Clone, Debug, Default)]
+struct Bracket {
+    negated: bool,
+    chars: Vec<u32>,
+    ranges: Vec<(u32, u32)>,
+    classes: Vec<CharClass>,
+}
+
+impl Bracket {
+    fn contains_exact(&self, c: u32) -> bool {
+        if self.chars.contains(&c) {
+            return true;
+        }
+        if self.ranges.iter().any(|&(lo, hi)| lo <= c && c <= hi) {
+            return true;
+        }
+        match char::from_u32(c) {
+            Some(ch) => self.classes.iter().any(|class| class.matches(ch)),
+            None => false,
+        }
+    }
+
+    fn matches(&self, c: u32, flags: &RegexFlags) -> bool {
+        if self.negated && flags.newline && c == '\n' as u32 {
+            return false;
+        }
+        let found = self.contains_exact(c)
+            || (flags.icase
+                && (self.contains_exact(simple_lower(c)) || self.contains_exact(simple_upper(c))));
+        found != self.negated
+    }
+}
+
+#[derive(Clone, Debug)]
+enum NodeKind {
+    Empty,
+    Char(u32),
+    Any,
+    Set(usize),
+    Bol,
+    Eol,
+    Backref(usize),
+    Group(usize, Box<Node>),
+    Concat(Box<Node>, Box<Node>),
+    Alternate(Vec<Node>),
+    /// `node*`; the flag is set when it continues earlier copies of `node`,
+    /// as in the expansion of `node+`.
+    Star(Box<Node>, bool),
+    /// `node?`; the flag has the same meaning as for `Star`.
+    Opt(Box<Node>, bool),
+}
+
+#[derive(Clone, Debug)]
+struct Node {
+    /// Index into `Regex::spans`, assigned once parsing is complete.
+    id: usize,
+    /// Whether a parenthesized subexpression occurs inside this node.
+    captures: bool,
+    kind: NodeKind,
+}
+
+impl Node {
+    fn new(kind: NodeKind) -> Self {
+        Node {
+            id: 0,
+            captures: false,
+            kind,
+        }
+    }
+
+    fn concat(mut nodes: Vec<Node>) -> Self {
+        let mut node = match nodes.pop() {
+            Some(node) => node,
+            None => return Node::new(NodeKind::Empty),
+        };
+        while let Some(prev) = nodes.pop() {
+            node = Node::new(NodeKind::Concat(Box::new(prev), Box::new(node)));
+        }
+        node
+    }
+
+    /// The number of nodes in this one, itself included.
+    fn size(&self) -> usize {
+        1 + match &self.kind {
+            NodeKind::Group(_, inner) | NodeKind::Star(inner, _) | NodeKind::Opt(inner, _) => {
+                inner.size()
+            }
+            NodeKind::Concat(a, b) => a.size() + b.size(),
+            NodeKind::Alternate(alts) => alts.iter().map(Node::size).sum(),
+            _ => 0,
+        }
+    }
+
+    /// Expands `node{min,max}` into concatenations of copies, stars and
+    /// optional nodes, so that later stages only deal with `*` and `?`.
+    ///
+    /// The size of the expansion is checked before any copy is made, as
+    /// nested intervals multiply it.
+    fn repeat(node: Node, min: u32, max: Option<u32>) -> Result<Self, RegexError> {
+        let copies = max.unwrap_or(min).max(min) as usize + 1;
+        if (node.size() + 2).saturating_mul(copies) >= MAX_PROGRAM_SIZE {
+            return Err(RegexError::Space);
+        }
+        let mut parts: Vec<Node> = (0..min).map(|_| node.clone()).collect();
+        match max {
+            None => parts.push(Node::new(NodeKind::Star(Box::new(node), min > 0))),
+            Some(max) if max > min => {
+                let mut tail = Node::new(NodeKind::Opt(Box::new(node.clone()), true));
+                for _ in min + 1..max {
+                    let inner = Node::concat(vec![node.clone(), tail]);
+                    tail = Node::new(NodeKind::Opt(Box::new(inner), true));
+                }
+                if let NodeKind::Opt(_, continuation) = &mut tail.kind {
+                    *continuation = min > 0;
+                }
+                parts.push(tail);
+            }
+            Some(_) => {}
+        }
+        Ok(Node::concat(parts))
+    }
+
+    /// Assigns unique ids and computes the `captures` flag, returning the
+    /// next free id.
+    fn number(&mut self, mut next_id: usize) -> usize {
+        self.id = next_id;
+        next_id += 1;
+        self.captures = match &mut self.kind {
+            NodeKind::Group(_, inner) => {
+                next_id = inner.number(next_id);
+                true
+            }
+            NodeKind::Concat(a, b) => {
+                next_id = a.number(next_id);
+                next_id = b.number(next_id);
+                a.captures || b.captures
+            }
+            NodeKind::Alternate(alts) => {
+                let mut captures = false;
+                for alt in alts.iter_mut() {
+                    next_id = alt.number(next_id);
+                    captures |= alt.captures;
+                }
+                captures
+            }
+            NodeKind::Star(inner, _) | NodeKind::Opt(inner, _) => {
+                next_id = inner.number(next_id);
+                inner.captures
+            }
+            _ => false,
+        };
+        next_id
+    }
+
+    /// Collects the subexpressions, indexed by their number.
+    fn groups<'a>(&'a self, groups: &mut Vec<Option<&'a Node>>) {
+        match &self.kind {
+            NodeKind::Group(n, inner) => {
+                if groups.len() <= *n {
+                    groups.resize(*n + 1, None);
+                }
+                groups[*n] = Some(inner);
+                inner.groups(groups);
+            }
+            NodeKind::Concat(a, b) => {
+                a.groups(groups);
+                b.groups(groups);
+            }
+            NodeKind::Alternate(alts) => alts.iter().for_each(|alt| alt.groups(groups)),
+            NodeKind::Star(inner, _) | NodeKind::Opt(inner, _) => inner.groups(groups),
+            _ => {}
+        }
+    }
+
+    /// A node without back-references that matches whatever this one
+    /// matches: each back-reference becomes a copy of its subexpression,
+    /// without anchors, as the text it repeats can occur anywhere.  `None`
+    /// if a back-reference has no subexpression to copy.
+    fn approximate(&self, groups: &[Option<&Node>], copies: usize) -> Option<Node> {
+        let kind = match &self.kind {
+            NodeKind::Bol | NodeKind::Eol if copies > 0 => NodeKind::Empty,
+            NodeKind::Backref(n) => {
+                if copies >= groups.len() {
+                    return None;
+                }
+                return groups
+                    .get(*n)
+                    .copied()
+                    .flatten()?
+                    .approximate(groups, copies + 1);
+            }
+            NodeKind::Group(n, inner) => {
+                NodeKind::Group(*n, Box::new(inner.approximate(groups, copies)?))
+            }
+            NodeKind::Concat(a, b) => NodeKind::Concat(
+                Box::new(a.approximate(groups, copies)?),
+                Box::new(b.approximate(groups, copies)?),
+            ),
+            NodeKind::Alternate(alts) => NodeKind::Alternate(
+                alts.iter()
+                    .map(|alt| alt.approximate(groups, copies))
+                    .collect::<Option<_>>()?,
+            ),
+            NodeKind::Star(inner, continuation) => {
+                NodeKind::Star(Box::new(inner.approximate(groups, copies)?), *continuation)
+            }
+            NodeKind::Opt(inner, continuation) => {
+                NodeKind::Opt(Box::new(inner.approximate(groups, copies)?), *continuation)
+            }
+            kind => kind.clone(),
+        };
+        Some(Node::new(kind))
+    }
+
+    fn clear_captures(&self, caps: &mut Captures) {
+        match &self.kind {
+            NodeKind::Group(n, inner) => {
+                caps[*n] = None;
+                inner.clear_captures(caps);
+            }
+            NodeKind::Concat(a, b) => {
+                a.clear_captures(caps);
+                b.clear_captures(caps);
+            }
+            NodeKind::Alternate(alts) => alts.iter().for_each(|alt| alt.clear_captures(caps)),
+            NodeKind::Star(inner, _) | NodeKind::Opt(inner, _) => inner.clear_captures(caps),
+            _ => {}
+        }
+    }
+}
+
+struct Parser<'a> {
+    pattern: &'a [u32],
+    pos: usize,
+    flags: RegexFlags,
+    nsub: usize,
+    closed_groups: Vec<bool>,
+    sets: Vec<Bracket>,
+    has_backrefs: bool,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<u32> {
+        self.pattern.get(self.pos).copied()
+    }
+
+    fn peek_at(&self, offset: usize) -> Option<u32> {
+        self.pattern.get(self.pos + offset).copied()
+    }
+
+    fn at(&self, offset: usize, c: char) -> bool {
+        self.peek_at(offset) == Some(c as u32)
+    }
+
+    fn literal(&self, c: u32) -> Node {
+        if self.flags.icase {
+            Node::new(NodeKind::Char(simple_lower(c)))
+        } else {
+            Node::new(NodeKind::Char(c))
+        }
+    }
+
+    fn backref(&mut self, n: usize) -> Result<Node, RegexError> {
+        if n == 0 || n > self.nsub || !self.closed_groups[n - 1] {
+            return Err(RegexError::SubReg);
+        }
+        self.has_backrefs = true;
+        Ok(Node::new(NodeKind::Backref(n)))
+    }
+
+    fn open_group(&mut self) -> usize {
+        self.nsub += 1;
+        self.closed_groups.push(false);
+        self.nsub
+    }
+
+    fn close_group(&mut self, n: usize, inner: Node) -> Node {
+        self.closed_groups[n - 1] = true;
+        Node::new(NodeKind::Group(n, Box::new(inner)))
+    }
+
+    /// Parses `m`, `m,` or `m,n` followed by the closing brace, with the
+    /// opening brace already consumed.
+    fn parse_interval(&mut self) -> Result<(u32, Option<u32>), RegexError> {
+        let min = self.parse_number()?.ok_or(RegexError::BadBrace)?;
+        let max = if self.at(0, ',') {
+            self.pos += 1;
+            self.parse_number()?
+        } else {
+            Some(min)
+        };
+        let closed = if self.flags.extended {
+            self.at(0, '}')
+        } else {
+            self.at(0, '\\') && self.at(1, '}')
+        };
+        if !closed {
+            return if self.peek().is_none() || (self.at(0, '\\') && self.peek_at(1).is_none()) {
+                Err(RegexError::Brace)
+            } else {
+                Err(RegexError::BadBrace)
+            };
+        }
+        self.pos += if self.flags.extended { 1 } else { 2 };
+        if let Some(max) = max {
+            if max < min {
+                return Err(RegexError::BadBrace);
+            }
+        }
+        Ok((min, max))
+    }
+
+    fn parse_number(&mut self) -> Result<Option<u32>, RegexError> {
+        let mut value: Option<u32> = None;
+        while let Some(c) = self.peek().and_then(char::from_u32) {
+            let Some(digit) = c.to_digit(10) else {
+                break;
+            };
+            let v = value.unwrap_or(0) * 10 + digit;
+            if v > RE_DUP_MAX {
+                return Err(RegexError::BadBrace);
+            }
+            value = Some(v);
+            self.pos += 1;
+        }
+        Ok(value)
+    }
+
+    /// Parses a bracket expression, with the opening `[` already consumed.
+    fn parse_bracket(&mut self) -> Result<Node, RegexError> {
+        let mut bracket = Bracket::default();
+        if self.at(0, '^') {
+            bracket.negated = true;
+            self.pos += 1;
+        }
+        let mut first = true;
+        loop {
+            let c = self.peek().ok_or(RegexError::Bracket)?;
+            if c == ']' as u32 && !first {
+                self.pos += 1;
+                break;
+            }
+            first = false;
+
+            let start = if c == '[' as u32 && self.at(1, ':') {
+                self.pos += 2;
+                let name = self.parse_bracket_term(':')?;
+                let class = CharClass::from_name(&name).ok_or(RegexError::CharClass)?;
+                bracket.classes.push(class);
+                continue;
+            } else if c == '[' as u32 && self.at(1, '=') {
+                self.pos += 2;
+                let name = self.parse_bracket_term('=')?;
+                let c = collating_element(&name)?;
+                bracket.chars.push(c);
+                continue;
+            } else if c == '[' as u32 && self.at(1, '.') {
+                self.pos += 2;
+                let name = self.parse_bracket_term('.')?;
+                collating_element(&name)?
+            } else {
+                self.pos += 1;
+                c
+            };
+
+            // a '-' is a range operator unless it is the last character
+            if self.at(0, '-') && !self.at(1, ']') && self.peek_at(1).is_some() {
+                self.pos += 1;
+                let end = if self.at(0, '[') && self.at(1, '.') {
+                    self.pos += 2;
+                    let name = self.parse_bracket_term('.')?;
+                    collating_element(&name)?
+                } else if self.at(0, '[') && (self.at(1, '=') || self.at(1, ':')) {
+                    return Err(RegexError::Range);
+                } else {
+                    let end = self.peek().ok_or(RegexError::Bracket)?;
+                    self.pos += 1;
+                    end
+                };
+                if end < start {
+                    return Err(RegexError::Range);
+                }
+                bracket.ranges.push((start, end));
+            } else {
+                bracket.chars.push(start);
+            }
+        }
+
+        self.sets.push(bracket);
+        Ok(Node::new(NodeKind::Set(self.sets.len() - 1)))
+    }
+
+    /// Reads the body of `[:name:]`, `[=c=]` or `[.c.]` up to the closing
+    /// `delim]` pair.
+    fn parse_bracket_term(&mut self, delim: char) -> Result<String, RegexError> {
+        let mut name = String::new();
+        loop {
+            let c = self.peek().ok_or(RegexError::Bracket)?;
+            if c == delim as u32 && self.at(1, ']') {
+                self.pos += 2;
+                return Ok(name);
+            }
+            name.push(char::from_u32(c).unwrap_or(char::REPLACEMENT_CHARACTER));
+            self.pos += 1;
+        }
+    }
+
+    fn parse_bre(&mut self, depth: usize) -> Result<Node, RegexError> {
+        let mut items: Vec<Node> = Vec::new();
+        // number of leading items that a `*` may not follow
+        let mut anchored = 0;
+        if self.at(0, '^') {
+            items.push(Node::new(NodeKind::Bol));
+            anchored = 1;
+            self.pos += 1;
+        }
+
+        while let Some(c) = self.peek() {
+            let ch = char::from_u32(c).unwrap_or(char::REPLACEMENT_CHARACTER);
+            if ch == '\\' && self.at(1, ')') {
+                if depth == 0 {
+                    return Err(RegexError::Paren);
+                }
+                break;
+            }
+
+            let atom = match ch {
+                '$' if self.peek_at(1).is_none()
+                    || (depth > 0 && self.at(1, '\\') && self.at(2, ')')) =>
+                {
+                    self.pos += 1;
+                    items.push(Node::new(NodeKind::Eol));
+                    continue;
+                }
+                '*' if items.len() == anchored => {
+                    self.pos += 1;
+                    self.literal(c)
+                }
+                '*' => {
+                    self.pos += 1;
+                    let prev = items.pop().unwrap();
+                    items.push(Node::repeat(prev, 0, None)?);
+                    continue;
+                }
+                '.' => {
+                    self.pos += 1;
+                    Node::new(NodeKind::Any)
+                }
+                '[' => {
+                    self.pos += 1;
+                    self.parse_bracket()?
+                }
+                '\\' => {
+                    let next = self.peek_at(1).ok_or(RegexError::Escape)?;
+                    self.pos += 2;
+                    match char::from_u32(next) {
+                        Some('(') => {
+                            let n = self.open_group();
+                            let inner = self.parse_bre(depth + 1)?;
+                            if !(self.at(0, '\\') && self.at(1, ')')) {
+                                return Err(RegexError::Paren);
+                            }
+                            self.pos += 2;
+                            self.close_group(n, inner)
+                        }
+                        Some('{') => {
+                            if items.len() == anchored {
+                                return Err(RegexError::BadRepeat);
+                            }
+                            let (min, max) = self.parse_interval()?;
+                            let prev = items.pop().unwrap();
+                            items.push(Node::repeat(prev, min, max)?);
+                            continue;
+                        }
+                        Some(d @ '1'..='9') => self.backref(d as usize - '0' as usize)?,
+                        _ => self.literal(next),
+                    }
+                }
+                _ => {
+                    self.pos += 1;
+                    self.literal(c)
+                }
+            };
+            items.push(atom);
+        }
+
+        if depth > 0 && self.peek().is_none() {
+            return Err(RegexError::Paren);
+        }
+        Ok(Node::concat(items))
+    }
+
+    fn parse_ere(&mut self, depth: usize) -> Result<Node, RegexError> {
+        let mut alternatives = vec![self.parse_ere_branch(depth)?];
+        while self.at(0, '|') {
+            self.pos += 1;
+            alternatives.push(self.parse_ere_branch(depth)?);
+        }
+        if alternatives.len() == 1 {
+            Ok(alternatives.pop().unwrap())
+        } else {
+            Ok(Node::new(NodeKind::Alternate(alternatives)))
+        }
+    }
+
+    fn parse_ere_branch(&mut self, depth: usize) -> Result<Node, RegexError> {
+        let mut items: Vec<Node> = Vec::new();
+        while let Some(c) = self.peek() {
+            let ch = char::from_u32(c).unwrap_or(char::REPLACEMENT_CHARACTER);
+            let is_interval = ch == '{'
+                && self
+                    .peek_at(1)
+                    .and_then(char::from_u32)
+                    .is_some_and(|c| c.is_ascii_digit());
+            let atom = match ch {
+                '|' => break,
+                ')' if depth > 0 => break,
+                '*' | '+' | '?' if !items.is_empty() => {
+                    self.pos += 1;
+                    let prev = items.pop().unwrap();
+                    let (min, max) = match ch {
+                        '*' => (0, None),
+                        '+' => (1, None),
+                        _ => (0, Some(1)),
+                    };
+                    items.push(Node::repeat(prev, min, max)?);
+                    continue;
+                }
+                '{' if is_interval => {
+                    if items.is_empty() {
+                        return Err(RegexError::BadRepeat);
+                    }
+                    self.pos += 1;
+                    let (min, max) = self.parse_interval()?;
+                    let prev = items.pop().unwrap();
+                    items.push(Node::repeat(prev, min, max)?);
+                    continue;
+                }
+                '(' => {
+                    self.pos += 1;
+                    let n = self.open_group();
+                    let inner = self.parse_ere(depth + 1)?;
+                    if !self.at(0, ')') {
+                        return Err(RegexError::Paren);
+                    }
+                    self.pos += 1;
+                    self.close_group(n, inner)
+                }
+                '^' => {
+                    self.pos += 1;
+                    Node::new(NodeKind::Bol)
+                }
+                '$' => {
+                    self.pos += 1;
+                    Node::new(NodeKind::Eol)
+                }
+                '.' => {
+                    self.pos += 1;
+                    Node::new(NodeKind::Any)
+                }
+                '[' => {
+                    self.pos += 1;
+                    self.parse_bracket()?
+                }
+                '\\' => {
+                    let next = self.peek_at(1).ok_or(RegexError::Escape)?;
+                    self.pos += 2;
+                    match char::from_u32(next) {
+                        Some(d @ '1'..='9') => self.backref(d as usize - '0' as usize)?,
+                        _ => self.literal(next),
+                    }
+                }
+                _ => {
+                    self.pos += 1;
+                    self.literal(c)
+                }
+            };
+            items.push(atom);
+        }
+        Ok(Node::concat(items))
+    }
+}
+
+/// Resolves the contents of `[.name.]` or `[=name=]` to a single character.
+fn collating_element(name: &str) -> Result<u32, RegexError> {
+    let mut chars = name.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        return Ok(c as u32);
+    }
+    locale::PORTABLE_CHARSET_NAMES
+        .iter()
+        .find(|(n, _)| *n == name)
+        .map(|&(_, c)| c as u32)
+        .ok_or(RegexError::Collate)
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Inst {
+    Char(u32),
+    Any,
+    Set(usize),
+    Bol,
+    Eol,
+    Split(usize, usize),
+    Jmp(usize),
+}
+
+struct Compiler {
+    program: Vec<Inst>,
+    /// `[start, end)` program range of each node, indexed by node id.
+    spans: Vec<(usize, usize)>,
+}
+
+impl Compiler {
+    fn push(&mut self, inst: Inst) -> Result<usize, RegexError> {
+        if self.program.len() >= MAX_PROGRAM_SIZE {
+            return Err(RegexError::Space);
+        }
+        self.program.push(inst);
+        Ok(self.program.len() - 1)
+    }
+
+    /// Emits code for `node`.  Every exit from the code of a node jumps to
+    /// the instruction immediately following it, which lets the matcher run
+    /// any node on its own.
+    fn emit(&mut self, node: &Node) -> Result<(), RegexError> {
+        let start = self.program.len();
+        match &node.kind {
+            NodeKind::Empty => {}
+            NodeKind::Char(c) => {
+                self.push(Inst::Char(*c))?;
+            }
+            NodeKind::Any => {
+                self.push(Inst::Any)?;
+            }
+            NodeKind::Set(idx) => {
+                self.push(Inst::Set(*idx))?;
+            }
+            NodeKind::Bol => {
+                self.push(Inst::Bol)?;
+            }
+            NodeKind::Eol => {
+                self.push(Inst::Eol)?;
+            }
+            NodeKind::Backref(_) => unreachable!("back-references are never compiled"),
+            NodeKind::Group(_, inner) => self.emit(inner)?,
+            NodeKind::Concat(a, b) => {
+                self.emit(a)?;
+                self.emit(b)?;
+            }
+            NodeKind::Alternate(alts) => {
+                let mut jumps = Vec::new();
+                for (i, alt) in alts.iter().enumerate() {
+                    if i + 1 < alts.len() {
+                        let split = self.push(Inst::Split(0, 0))?;
+                        self.emit(alt)?;
+                        jumps.push(self.push(Inst::Jmp(0))?);
+                        self.program[split] = Inst::Split(split + 1, self.program.len());
+                    } else {
+                        self.emit(alt)?;
+                    }
+                }
+                let end = self.program.len();
+                for jump in jumps {
+                    self.program[jump] = Inst::Jmp(end);
+                }
+            }
+            NodeKind::Star(inner, _) => {
+                let split = self.push(Inst::Split(0, 0))?;
+                self.emit(inner)?;
+                self.push(Inst::Jmp(split))?;
+                self.program[split] = Inst::Split(split + 1, self.program.len());
+            }
+            NodeKind::Opt(inner, _) => {
+                let split = self.push(Inst::Split(0, 0))?;
+                self.emit(inner)?;
+                self.program[split] = Inst::Split(split + 1, self.program.len());
+            }
+        }
+        self.spans[node.id] = (start, self.program.len());
+        Ok(())
+    }
+}
+
+/// Sparse set of program counters, each with the match start position of
+/// the thread occupying it.
+struct ThreadList {
+    dense: Vec<(usize, usize)>,
+    sparse: Vec<usize>,
+}
+
+impl ThreadList {
+    fn new(size: usize) -> Self {
+        ThreadList {
+            dense: Vec::with_capacity(size),
+            sparse: vec![0; size],
+        }
+    }
+
+    fn contains(&self, pc: usize) -> bool {
+        let idx = self.sparse[pc];
+        idx < self.dense.len() && self.dense[idx].0 == pc
+    }
+
+    fn insert(&mut self, pc: usize, start: usize) {
+        self.sparse[pc] = self.dense.len();
+        self.dense.push((pc, start));
+    }
+
+    fn clear(&mut self) {
+        self.dense.clear();
+    }
+}
+
+/// Per-call matching context.
+struct Exec<'r, 'i> {
+    regex: &'r Regex,
+    input: &'i [u8],
+    eflags: MatchFlags,
+}
+
+impl Exec<'_, '_> {
+    fn at_bol(&self, pos: usize) -> bool {
+        (pos == 0 && !self.eflags.not_bol)
+            || (self.regex.flags.newline && pos > 0 && self.input[pos - 1] == b'\n')
+    }
+
+    fn at_eol(&self, pos: usize) -> bool {
+        (pos == self.input.len() && !self.eflags.not_eol)
+            || (self.regex.flags.newline && pos < self.input.len() && self.input[pos] == b'\n')
+    }
+
+    fn char_matches(&self, inst: &Inst, c: u32) -> bool {
+        let flags = &self.regex.flags;
+        match inst {
+            Inst::Char(expected) => {
+                if flags.icase {
+                    simple_lower(c) == *expected
+                } else {
+                    c == *expected
+                }
+            }
+            Inst::Any => !(flags.newline && c == '\n' as u32),
+            Inst::Set(idx) => self.regex.sets[*idx].matches(c, flags),
+            _ => false,
+        }
+    }
+
+    /// Adds the thread at `pc` and everything reachable from it without
+    /// consuming input.  Returns true if `accept` was reached.
+    fn add_thread(
+        &self,
+        list: &mut ThreadList,
+        stack: &mut Vec<usize>,
+        pc: usize,
+        start: usize,
+        pos: usize,
+        accept: usize,
+    ) -> bool {
+        let program = &self.regex.program;
+        let mut accepted = false;
+        stack.push(pc);
+        while let Some(pc) = stack.pop() {
+            if pc == accept {
+                accepted = true;
+                continue;
+            }
+            if list.contains(pc) {
+                continue;
+            }
+            list.insert(pc, start);
+            match program[pc] {
+                Inst::Jmp(target) => stack.push(target),
+                Inst::Split(a, b) => {
+                    stack.push(b);
+                    stack.push(a);
+                }
+                Inst::Bol if self.at_bol(pos) => stack.push(pc + 1),
+                Inst::Eol if self.at_eol(pos) => stack.push(pc + 1),
+                _ => {}
+            }
+        }
+        accepted
+    }
+
+    /// Finds the leftmost-longest match of the whole program, starting the
+    /// search at byte offset `from`.
+    fn search(&self, from: usize) -> Option<Range<usize>> {
+        let size = self.regex.program.len();
+        let accept = size;
+        let mut clist = ThreadList::new(size);
+        let mut nlist = ThreadList::new(size);
+        let mut stack = Vec::new();
+        let mut best: Option<Range<usize>> = None;
+        let mut pos = from;
+
+        // all threads in a list are ordered by their start position, and a
+        // program counter is always claimed by the earliest start first
+        loop {
+            // accepting threads from the current step are checked first, as
+            // they started no later than a thread seeded at `pos`
+            if best.is_none() && self.add_thread(&mut clist, &mut stack, 0, pos, pos, accept) {
+                best = Some(pos..pos);
+            }
+            if pos >= self.input.len() || (clist.dense.is_empty() && best.is_some()) {
+                break;
+            }
+
+            let (c, width) = decode(&self.input[pos..]);
+            let next = pos + width;
+            for i in 0..clist.dense.len() {
+                let (pc, start) = clist.dense[i];
+                if best.as_ref().is_some_and(|b| start > b.start) {
+                    continue;
+                }
+                let inst = &self.regex.program[pc];
+                if self.char_matches(inst, c)
+                    && self.add_thread(&mut nlist, &mut stack, pc + 1, start, next, accept)
+                {
+                    let better = match &best {
+                        None => true,
+                        Some(b) => start < b.start || (start == b.start && next > b.end),
+                    };
+                    if better {
+                        best = Some(start..next);
+                    }
+                }
+            }
+            std::mem::swap(&mut clist, &mut nlist);
+            nlist.clear();
+            if let Some(b) = &best {
+                clist.dense.retain(|&(_, start)| start <= b.start);
+                for (idx, &(pc, _)) in clist.dense.iter().enumerate() {
+                    clist.sparse[pc] = idx;
+                }
+            }
+            pos = next;
+        }
+        best
+    }
+
+    /// Returns every end position `e <= limit` such that the node with
+    /// program span `span` matches exactly `input[start..e]`, in ascending
+    /// order.
+    fn ends(&self, span: (usize, usize), start: usize, limit: usize) -> Vec<usize> {
+        let (entry, accept) = span;
+        let size = self.regex.program.len();
+        let mut clist = ThreadList::new(size);
+        let mut nlist = ThreadList::new(size);
+        let mut stack = Vec::new();
+        let mut ends = Vec::new();
+        let mut pos = start;
+
+        if self.add_thread(&mut clist, &mut stack, entry, start, pos, accept) {
+            ends.push(pos);
+        }
+        while !clist.dense.is_empty() && pos < limit {
+            let (c, width) = decode(&self.input[pos..]);
+            let next = pos + width;
+            let mut accepted = false;
+            for i in 0..clist.dense.len() {
+                let (pc, _) = clist.dense[i];
+                let inst = &self.regex.program[pc];
+                if self.char_matches(inst, c) {
+                    accepted |=
+                        self.add_thread(&mut nlist, &mut stack, pc + 1, start, next, accept);
+                }
+            }
+            if accepted {
+                ends.push(next);
+            }
+            std::mem::swap(&mut clist, &mut nlist);
+            nlist.clear();
+            pos = next;
+        }
+        ends
+    }
+}
+
+/// Recovers sub-match offsets once the extent of the overall match is known.
+struct Dissector<'e, 'r, 'i> {
+    exec: &'e Exec<'r, 'i>,
+    memo: HashMap<(usize, usize), Vec<usize>>,
+    limit: usize,
+}
+
+impl Dissector<'_, '_, '_> {
+    fn matches(&mut self, node: &Node, start: usize, end: usize) -> bool {
+        self.ends(node, start).binary_search(&end).is_ok()
+    }
+
+    fn ends(&mut self, node: &Node, start: usize) -> &Vec<usize> {
+        let span = self.exec.regex.spans[node.id];
+        let exec = self.exec;
+        let limit = self.limit;
+        self.memo
+            .entry((node.id, start))
+            .or_insert_with(|| exec.ends(span, start, limit))
+    }
+
+    /// Assigns sub-matches inside `node`, which must match exactly
+    /// `input[start..end]`.
+    fn dissect(&mut self, node: &Node, start: usize, end: usize, caps: &mut Captures) {
+        if !node.captures {
+            return;
+        }
+        match &node.kind {
+            NodeKind::Group(n, inner) => {
+                caps[*n] = Some(start..end);
+                self.dissect(inner, start, end, caps);
+            }
+            NodeKind::Concat(a, b) => {
+                // the leftmost part takes the longest possible span
+                let candidates: Vec<usize> = self.ends(a, start).clone();
+                for &mid in candidates.iter().rev() {
+                    if mid <= end && self.matches(b, mid, end) {
+                        self.dissect(a, start, mid, caps);
+                        self.dissect(b, mid, end, caps);
+                        return;
+                    }
+                }
+            }
+            NodeKind::Alternate(alts) => {
+                for alt in alts {
+                    if self.matches(alt, start, end) {
+                        self.dissect(alt, start, end, caps);
+                        return;
+                    }
+                }
+            }
+            // an empty match never adds an iteration to earlier copies
+            NodeKind::Opt(inner, continuation)
+                if (start != end || !continuation) && self.matches(inner, start, end) =>
+            {
+                self.dissect(inner, start, end, caps);
+            }
+            NodeKind::Star(inner, continuation) => {
+                if start == end {
+                    if !continuation && self.matches(inner, start, end) {
+                        self.dissect(inner, start, end, caps);
+                    }
+                    return;
+                }
+                // each iteration takes the longest span that still lets the
+                // remaining iterations reach `end`; the last one is reported
+                let mut pos = start;
+                while pos < end {
+                    let candidates: Vec<usize> = self.ends(inner, pos).clone();
+                    let next = candidates
+                        .iter()
+                        .rev()
+                        .copied()
+                        .find(|&mid| mid > pos && mid <= end && self.matches(node, mid, end));
+                    let Some(next) = next else {
+                        break;
+                    };
+                    inner.clear_captures(caps);
+                    self.dissect(inner, pos, next, caps);
+                    pos = next;
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Instruction of the program run by the backtracking matcher, which
+/// unlike the NFA program keeps track of subexpressions.
+#[derive(Clone, Copy, Debug)]
+enum Op {
+    /// Matches one character, as the NFA instruction does.
+    Char(Inst),
+    Bol,
+    Eol,
+    Backref(usize),
+    /// Records the start of subexpression `n`.
+    Open(usize),
+    /// Records the end of subexpression `n`, setting its capture.
+    Close(usize),
+    /// Tries the first target, and on failure the second one.
+    Split(usize, usize),
+    Jmp(usize),
+    /// Records the position in a register.
+    Mark(usize),
+    /// Fails unless the position moved since the register was marked, so
+    /// an empty iteration of `*` can never lead anywhere new.
+    Progress(usize),
+    Match,
+}
+
+/// Compiles a tree into the program of the backtracking matcher.
+struct BacktrackCompiler {
+    program: Vec<Op>,
+    /// The number of registers: the start of each subexpression, then the
+    /// marks.
+    registers: usize,
+}
+
+impl BacktrackCompiler {
+    fn push(&mut self, op: Op) -> Result<usize, RegexError> {
+        if self.program.len() >= MAX_PROGRAM_SIZE {
+            return Err(RegexError::Space);
+        }
+        self.program.push(op);
+        Ok(self.program.len() - 1)
+    }
+
+    fn emit(&mut self, node: &Node) -> Result<(), RegexError> {
+        match &node.kind {
+            NodeKind::Empty => {}
+            NodeKind::Char(c) => {
+                self.push(Op::Char(Inst::Char(*c)))?;
+            }
+            NodeKind::Any => {
+                self.push(Op::Char(Inst::Any))?;
+            }
+            NodeKind::Set(idx) => {
+                self.push(Op::Char(Inst::Set(*idx)))?;
+            }
+            NodeKind::Bol => {
+                self.push(Op::Bol)?;
+            }
+            NodeKind::Eol => {
+                self.push(Op::Eol)?;
+            }
+            NodeKind::Backref(n) => {
+                self.push(Op::Backref(*n))?;
+            }
+            NodeKind::Group(n, inner) => {
+                self.push(Op::Open(*n))?;
+                self.emit(inner)?;
+                self.push(Op::Close(*n))?;
+            }
+            NodeKind::Concat(a, b) => {
+                self.emit(a)?;
+                self.emit(b)?;
+            }
+            NodeKind::Alternate(alts) => {
+                let mut jumps = Vec::new();
+                for (i, alt) in alts.iter().enumerate() {
+                    if i + 1 < alts.len() {
+                        let split = self.push(Op::Split(0, 0))?;
+                        self.emit(alt)?;
+                        jumps.push(self.push(Op::Jmp(0))?);
+                        self.program[split] = Op::Split(split + 1, self.program.len());
+                    } else {
+                        self.emit(alt)?;
+                    }
+                }
+                let end = self.program.len();
+                for jump in jumps {
+                    self.program[jump] = Op::Jmp(end);
+                }
+            }
+            NodeKind::Star(inner, _) => {
+                let register = self.registers;
+                self.registers += 1;
+                let split = self.push(Op::Split(0, 0))?;
+                self.push(Op::Mark(register))?;
+                self.emit(inner)?;
+                self.push(Op::Progress(register))?;
+                self.push(Op::Jmp(split))?;
+                self.program[split] = Op::Split(split + 1, self.program.len());
+            }
+            NodeKind::Opt(inner, _) => {
+                let split = self.push(Op::Split(0, 0))?;
+                self.emit(inner)?;
+                self.program[split] = Op::Split(split + 1, self.program.len());
+            }
+        }
+        Ok(())
+    }
+}
+
+/// What the backtracking matcher does when a thread fails.
+enum Frame {
+    /// Resumes at an untried alternative.
+    Try { pc: usize, pos: usize },
+    /// Undoes the change of a capture.
+    Capture(usize, Option<Range<usize>>),
+    /// Undoes the change of a register.
+    Register(usize, usize),
+}
+
+/// State of the backtracking matcher used for patterns with back-references.
+///
+/// The matcher keeps its alternatives on a stack of its own rather than
+/// recursing, so long inputs cannot overflow the call stack, and remembers
+/// every state it has been in at an alternative: as a state reached again
+/// can only lead where it did the first time, it is not explored twice,
+/// which bounds the search by the number of distinct states.
+struct Backtrack<'e, 'r, 'i> {
+    exec: &'e Exec<'r, 'i>,
+    caps: Captures,
+    registers: Vec<usize>,
+    stack: Vec<Frame>,
+    /// States seen at `Split` instructions.  Only searches that found no
+    /// match go on to the next start position, so the states stay dead
+    /// from one start position to the next.
+    visited: HashSet<(usize, usize, Captures, Vec<usize>)>,
+}
+
+impl<'e, 'r, 'i> Backtrack<'e, 'r, 'i> {
+    fn new(exec: &'e Exec<'r, 'i>) -> Self {
+        let regex = exec.regex;
+        Backtrack {
+            exec,
+            caps: vec![None; regex.nsub + 1],
+            registers: vec![0; regex.registers],
+            stack: Vec::new(),
+            visited: HashSet::new(),
+        }
+    }
+
+    /// Finds the longest match starting at `start`, with the captures of
+    /// the first way of matching it in order of preference.
+    fn longest(&mut self, start: usize) -> Option<(usize, Captures)> {
+        let exec = self.exec;
+        let input = exec.input;
+        let program = &exec.regex.backtrack_program;
+        self.caps.iter_mut().for_each(|cap| *cap = None);
+        self.stack.clear();
+        self.stack.push(Frame::Try { pc: 0, pos: start });
+
+        let mut best: Option<(usize, Captures)> = None;
+        while let Some(frame) = self.stack.pop() {
+            let (mut pc, mut pos) = match frame {
+                Frame::Try { pc, pos } => (pc, pos),
+                Frame::Capture(n, cap) => {
+                    self.caps[n] = cap;
+                    continue;
+                }
+                Frame::Register(r, value) => {
+                    self.registers[r] = value;
+                    continue;
+                }
+            };
+
+            // run the thread until it fails
+            loop {
+                match program[pc] {
+                    Op::Char(inst) => {
+                        if pos >= input.len() {
+                            break;
+                        }
+                        let (c, width) = decode(&input[pos..]);
+                        if !exec.char_matches(&inst, c) {
+                            break;
+                        }
+                        pos += width;
+                    }
+                    Op::Bol => {
+                        if !exec.at_bol(pos) {
+                            break;
+                        }
+                    }
+                    Op::Eol => {
+                        if !exec.at_eol(pos) {
+                            break;
+                        }
+                    }
+                    Op::Backref(n) => {
+                        let Some(range) = self.caps[n].clone() else {
+                            break;
+                        };
+                        match self.backref_len(range, pos) {
+                            Some(len) => pos += len,
+                            None => break,
+                        }
+                    }
+                    // the start of subexpression `n` is register `n`
+                    Op::Open(r) | Op::Mark(r) => {
+                        self.stack.push(Frame::Register(r, self.registers[r]));
+                        self.registers[r] = pos;
+                    }
+                    Op::Close(n) => {
+                        let open = self.registers[n];
+                        let cap = self.caps[n].replace(open..pos);
+                        self.stack.push(Frame::Capture(n, cap));
+                    }
+                    Op::Progress(r) => {
+                        if self.registers[r] == pos {
+                            break;
+                        }
+                    }
+                    Op::Split(first, second) => {
+                        if self.visited.len() >= MAX_VISITED_STATES {
+                            self.visited.clear();
+                        }
+                        let state = (pc, pos, self.caps.clone(), self.registers.clone());
+                        if !self.visited.insert(state) {
+                            break;
+                        }
+                        self.stack.push(Frame::Try { pc: second, pos });
+                        pc = first;
+                        continue;
+                    }
+                    Op::Jmp(target) => {
+                        pc = target;
+                        continue;
+                    }
+                    Op::Match => {
+                        if best.as_ref().map_or(true, |(end, _)| pos > *end) {
+                            best = Some((pos, self.caps.clone()));
+                        }
+                        // nothing can be longer than the rest of the input
+                        if pos == input.len() {
+                            return best;
+                        }
+                        break;
+                    }
+                }
+                pc += 1;
+            }
+        }
+        best
+    }
+
+    /// Length of the text at `pos` repeating the text at `range`, if any.
+    fn backref_len(&self, range: Range<usize>, pos: usize) -> Option<usize> {
+        let input = self.exec.input;
+        let len = range.len();
+        if !self.exec.regex.flags.icase {
+            return (input.len() - pos >= len && input[range] == input[pos..pos + len])
+                .then_some(len);
+        }
+
+        let (mut a, mut b) = (range.start, pos);
+        while a < range.end {
+            if b >= input.len() {
+                return None;
+            }
+            let (ca, wa) = decode(&input[a..]);
+            let (cb, wb) = decode(&input[b..]);
+            if simple_lower(ca) != simple_lower(cb) {
+                return None;
+            }
+            a += wa;
+            b += wb;
+        }
+        Some(b - pos)
+    }
+}
+
+/// A compiled POSIX regular expression.
+#[derive(Clone)]
+pub struct Regex {
+    pattern: Vec<u8>,
+    flags: RegexFlags,
+    nsub: usize,
+    root: Node,
+    sets: Vec<Bracket>,
+    program: Vec<Inst>,
+    spans: Vec<(usize, usize)>,
+    has_backrefs: bool,
+    /// The program of the backtracking matcher, for patterns with
+    /// back-references.
+    backtrack_program: Vec<Op>,
+    /// The number of registers the backtracking program uses.
+    registers: usize,
+    /// Whether `program` matches a superset of a pattern with
+    /// back-references, which no match can start before.
+    prefilter: bool,
+}
+
+impl Regex {
+    /// Compiles `pattern`, the equivalent of `regcomp`.
+    pub fn new(pattern: &[u8], flags: RegexFlags) -> Result<Self, RegexError> {
+        let mut chars = Vec::with_capacity(pattern.len());
+        let mut pos = 0;
+        while pos < pattern.len() {
+            let (c, width) = decode(&pattern[pos..]);
+            chars.push(c);
+            pos += width;
+        }
+
+        let mut parser = Parser {
+            pattern: &chars,
+            pos: 0,
+            flags,
+            nsub: 0,
+            closed_groups: Vec::new(),
+            sets: Vec::new(),
+            has_backrefs: false,
+        };
+        let mut root = if flags.extended {
+            parser.parse_ere(0)?
+        } else {
+            parser.parse_bre(0)?
+        };
+        let node_count = root.number(0);
+
+        let mut compiler = Compiler {
+            program: Vec::new(),
+            spans: vec![(0, 0); node_count],
+        };
+        let mut backtrack_compiler = BacktrackCompiler {
+            program: Vec::new(),
+            registers: parser.nsub + 1,
+        };
+        let mut prefilter = false;
+        if parser.has_backrefs {
+            backtrack_compiler.emit(&root)?;
+            backtrack_compiler.push(Op::Match)?;
+
+            // the NFA program matches a regular superset of the pattern,
+            // to rule out the text that cannot match quickly
+            let mut groups = Vec::new();
+            root.groups(&mut groups);
+            if let Some(mut approximation) = root.approximate(&groups, 0) {
+                compiler.spans = vec![(0, 0); approximation.number(0)];
+                compiler.emit(&approximation)?;
+                prefilter = true;
+            }
+        } else {
+            compiler.emit(&root)?;
+        }
+
+        Ok(Regex {
+            pattern: pattern.to_vec(),
+            flags,
+            nsub: parser.nsub,
+            root,
+            sets: parser.sets,
+            program: compiler.program,
+            spans: compiler.spans,
+            has_backrefs: parser.has_backrefs,
+            registers: backtrack_compiler.registers,
+            prefilter,
+            backtrack_program: backtrack_compiler.program,
+        })
+    }
+
+    /// The source text of the expression.
+    pub fn pattern(&self) -> &[u8] {
+        &self.pattern
+    }
+
+    pub fn flags(&self) -> RegexFlags {
+        self.flags
+    }
+
+    /// Number of parenthesized subexpressions (`re_nsub`).
+    pub fn subexpression_count(&self) -> usize {
+        self.nsub
+    }
+
+    /// Returns true if the expression matches anywhere in `input`.
+    pub fn is_match(&self, input: &[u8]) -> bool {
+        self.find(input).is_some()
+    }
+
+    /// Returns the extent of the leftmost-longest match in `input`.
+    pub fn find(&self, input: &[u8]) -> Option<Range<usize>> {
+        self.find_at(input, 0, MatchFlags::default())
+    }
+
+    /// Returns the extent of the leftmost-longest match starting at or
+    /// after byte offset `start`.  The text before `start` still counts as
+    /// context for `^` and `$`.
+    pub fn find_at(&self, input: &[u8], start: usize, eflags: MatchFlags) -> Option<Range<usize>> {
+        let exec = Exec {
+            regex: self,
+            input,
+            eflags,
+        };
+        if self.has_backrefs {
+            self.backtrack(&exec, start)
+                .map(|caps| caps[0].clone().unwrap())
+        } else {
+            exec.search(start)
+        }
+    }
+
+    /// Matches `input` and reports sub-match offsets, the equivalent of
+    /// `regexec`.
+    pub fn exec(&self, input: &[u8], eflags: MatchFlags) -> Option<Captures> {
+        self.exec_at(input, 0, eflags)
+    }
+
+    /// Like [`Regex::exec`], but starts the search at byte offset `start`.
+    pub fn exec_at(&self, input: &[u8], start: usize, eflags: MatchFlags) -> Option<Captures> {
+        let exec = Exec {
+            regex: self,
+            input,
+            eflags,
+        };
+        if self.has_backrefs {
+            let mut caps = self.backtrack(&exec, start)?;
+            if self.flags.nosub {
+                caps.truncate(1);
+            }
+            return Some(caps);
+        }
+
+        let range = exec.search(start)?;
+        if self.flags.nosub {
+            return Some(vec![Some(range)]);
+        }
+        let mut caps: Captures = vec![None; self.nsub + 1];
+        if self.root.captures {
+            let mut dissector = Dissector {
+                exec: &exec,
+                memo: HashMap::new(),
+                limit: range.end,
+            };
+            dissector.dissect(&self.root, range.start, range.end, &mut caps);
+        }
+        caps[0] = Some(range);
+        Some(caps)
+    }
+
+    /// Iterates over successive non-overlapping matches in `input`.
+    pub fn find_iter<'r, 'i>(&'r self, input: &'i [u8]) -> Matches<'r, 'i> {
+        Matches {
+            regex: self,
+            input,
+            next_start: 0,
+            last_end: None,
+        }
+    }
+
+    fn backtrack(&self, exec: &Exec, from: usize) -> Option<Captures> {
+        let input = exec.input;
+        let mut bt = Backtrack::new(exec);
+        let mut start = from;
+        loop {
+            if self.prefilter {
+                start = exec.search(start)?.start;
+            }
+            if let Some((end, mut caps)) = bt.longest(start) {
+                caps[0] = Some(start..end);
+                return Some(caps);
+            }
+            if start >= input.len() {
+                return None;
+            }
+            start += decode(&input[start..]).1;
+        }
+    }
+}
+
+impl fmt::Debug for Regex {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Regex({:?})", String::from_utf8_lossy(&self.pattern))
+    }
+}
+
+/// Iterator over the matches of a [`Regex`], created by [`Regex::find_iter`].
+pub struct Matches<'r, 'i> {
+    regex: &'r Regex,
+    input: &'i [u8],
+    next_start: usize,
+    last_end: Option<usize>,
+}
+
+impl Iterator for Matches<'_, '_> {
+    type Item = Range<usize>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if self.next_start > self.input.len() {
+                return None;
+            }
+            let m = self
+                .regex
+                .find_at(self.input, self.next_start, MatchFlags::default())?;
+            if m.is_empty() && Some(m.start) == self.last_end {
+                // an empty match right after the previous match is skipped
+                if m.start >= self.input.len() {
+                    return None;
+                }
+                self.next_start = m.start + decode(&self.input[m.start..]).1;
+                continue;
+            }
+            self.next_start = if m.is_empty() {
+                if m.end >= self.input.len() {
+                    self.input.len() + 1
+                } else {
+                    m.end + decode(&self.input[m.end..]).1
+                }
+            } else {
+                m.end
+            };
+            self.last_end = Some(m.end);
+            return Some(m);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bre(pattern: &str) -> Regex {
+        Regex::new(pattern.as_bytes(), RegexFlags::bre()).unwrap()
+    }
+
+    fn ere(pattern: &str) -> Regex {
+        Regex::new(pattern.as_bytes(), RegexFlags::ere()).unwrap()
+    }
+
+    fn caps(re: &Regex, input: &str) -> Vec<Option<(usize, usize)>> {
+        re.exec(input.as_bytes(), MatchFlags::default())
+            .unwrap()
+            .into_iter()
+            .map(|r| r.map(|r| (r.start, r.end)))
+            .collect()
+    }
+
+    #[test]
+    fn test_leftmost_longest() {
+        assert_eq!(ere("a|ab|abc").find(b"xabcd"), Some(1..4));
+        assert_eq!(ere("(a|ab)(c|bcd)").find(b"abcd"), Some(0..4));
+        assert_eq!(bre("a*").find(b"baaa"), Some(0..0));
+        assert_eq!(ere("x*y+").find(b"aaxxyyyb"), Some(2..7));
+    }
+
+    #[test]
+    fn test_submatches() {
+        let re = ere("(a|ab)(c|bcd)(d*)");
+        assert_eq!(
+            caps(&re, "abcd"),
+            vec![Some((0, 4)), Some((0, 2)), Some((2, 3)), Some((3, 4))]
+        );
+        let re = ere("(a*)*");
+        assert_eq!(caps(&re, "b"), vec![Some((0, 0)), Some((0, 0))]);
+        let re = ere("(a|(b))+");
+        assert_eq!(
+            caps(&re, "ab"),
+            vec![Some((0, 2)), Some((1, 2)), Some((1, 2))]
+        );
+        let re = ere("(a)|b");
+        assert_eq!(caps(&re, "b"), vec![Some((0, 1)), None]);
+    }
+
+    #[test]
+    fn test_bre_syntax() {
+        assert!(bre("a\\{2,3\\}").is_match(b"caab"));
+        assert!(!bre("a\\{2,3\\}").is_match(b"cab"));
+        assert!(bre("*a").is_match(b"x*a"));
+        assert!(bre("a|b").is_match(b"a|b"));
+        assert!(!bre("a|b").is_match(b"a"));
+        assert!(bre("a+").is_match(b"a+"));
+        assert!(bre("x^").is_match(b"x^"));
+        assert!(bre("$x").is_match(b"$x"));
+        assert!(bre("\\(^a\\)").is_match(b"ab"));
+        assert!(!bre("\\(^a\\)").is_match(b"ba"));
+        assert_eq!(bre("\\(ab\\)*c").find(b"xababc"), Some(1..6));
+    }
+
+    #[test]
+    fn test_ere_syntax() {
+        assert!(ere("^(ab|cd)+$").is_match(b"abcdab"));
+        assert!(!ere("^(ab|cd)+$").is_match(b"abc"));
+        assert!(ere("colou?r").is_match(b"color"));
+        assert!(ere("a{2}").is_match(b"aa"));
+        assert!(ere("\\{").is_match(b"{"));
+        assert!(ere("a{,").is_match(b"a{,"));
+        assert!(ere("a)").is_match(b"a)"));
+    }
+
+    #[test]
+    fn test_backreferences() {
+        let re = bre("\\(a*\\)b\\1");
+        assert_eq!(caps(&re, "xaabaa"), vec![Some((1, 6)), Some((1, 3))]);
+        assert!(!bre("^\\(.*\\)\\1$").is_match(b"abcab"));
+        assert!(bre("^\\(.*\\)\\1$").is_match(b"abcabc"));
+        assert!(ere("(a)\\1").is_match(b"aa"));
+        let re = Regex::new(b"\\(a\\)\\1", RegexFlags::bre().ignore_case(true)).unwrap();
+        assert!(re.is_match(b"aA"));
+        // anchors in a subexpression do not anchor its back-references
+        assert!(bre("\\(^a\\)x\\1").is_match(b"axa"));
+    }
+
+    #[test]
+    fn test_backreferences_long_input() {
+        let input = vec![b'a'; 200_000];
+        let re = bre("\\(a\\)\\1*");
+        assert_eq!(re.find(&input), Some(0..input.len()));
+        assert!(!bre("\\(a\\)\\1*b").is_match(&input));
+    }
+
+    #[test]
+    fn test_backreferences_pathological() {
+        let input = "a".repeat(40);
+        let re = bre("\\(a*\\)*\\1c");
+        assert!(!re.is_match(input.as_bytes()));
+        assert_eq!(
+            caps(&re, &format!("{input}c")),
+            vec![Some((0, 41)), Some((38, 39))]
+        );
+    }
+
+    #[test]
+    fn test_brackets() {
+        assert!(bre("[[:digit:]]x").is_match(b"a5x"));
+        assert!(!bre("[^[:alpha:]]").is_match(b"abc"));
+        assert!(bre("[]a]").is_match(b"]"));
+        assert!(bre("[a-]").is_match(b"-"));
+        assert!(bre("[[.hyphen.]]").is_match(b"-"));
+        assert!(bre("[[=e=]]").is_match(b"e"));
+        assert!(bre("[\\]").is_match(b"\\"));
+        assert!(bre("[[.a.]-c]").is_match(b"b"));
+    }
+
+    #[test]
+    fn test_flags() {
+        let re = Regex::new(b"ABC", RegexFlags::bre().ignore_case(true)).unwrap();
+        assert!(re.is_match(b"xabcx"));
+        let re = Regex::new(b"[a-c]+", RegexFlags::ere().ignore_case(true)).unwrap();
+        assert_eq!(re.find(b"xBCa"), Some(1..4));
+
+        let re = Regex::new(b"^b$", RegexFlags::bre().newline(true)).unwrap();
+        assert_eq!(re.find(b"a\nb\nc"), Some(2..3));
+        assert!(!bre("^b$").is_match(b"a\nb\nc"));
+        let re = Regex::new(b"a.b", RegexFlags::bre().newline(true)).unwrap();
+        assert!(!re.is_match(b"a\nb"));
+        assert!(bre("a.b").is_match(b"a\nb"));
+
+        let re = Regex::new(b"\\(a\\)", RegexFlags::bre().no_sub(true)).unwrap();
+        assert_eq!(re.exec(b"a", MatchFlags::default()), Some(vec![Some(0..1)]));
+
+        let not_bol = MatchFlags {
+            not_bol: true,
+            ..MatchFlags::default()
+        };
+        assert_eq!(bre("^a").exec(b"a", not_bol), None);
+    }
+
+    #[test]
+    fn test_bytes() {
+        assert!(bre("a.c").is_match(b"a\0c"));
+        assert!(bre("a.c").is_match(b"a\xffc"));
+        assert!(bre("a.c").is_match("aéc".as_bytes()));
+        assert!(!bre("a..c").is_match("aéc".as_bytes()));
+        let re = Regex::new(b"\xe9", RegexFlags::bre()).unwrap();
+        assert!(re.is_match(b"caf\xe9"));
+        assert!(!bre("[[:alpha:]]").is_match(b"\xe9"));
+    }
+
+    #[test]
+    fn test_find_iter() {
+        let re = ere("x*");
+        let matches: Vec<_> = re.find_iter(b"axb").collect();
+        assert_eq!(matches, vec![0..0, 1..2, 3..3]);
+        let re = bre("^a");
+        let matches: Vec<_> = re.find_iter(b"aaa").collect();
+        assert_eq!(matches, vec![0..1]);
+    }
+
+    #[test]
+    fn test_errors() {
+        let err = |p: &str, flags| Regex::new(p.as_bytes(), flags).unwrap_err();
+        assert_eq!(err("\\{1,3\\}", RegexFlags::bre()), RegexError::BadRepeat);
+        assert_eq!(err("{1,3}", RegexFlags::ere()), RegexError::BadRepeat);
+        assert_eq!(err("a\\{1", RegexFlags::bre()), RegexError::Brace);
+        assert_eq!(err("a\\{3,1\\}", RegexFlags::bre()), RegexError::BadBrace);
+        assert_eq!(err("\\(a", RegexFlags::bre()), RegexError::Paren);
+        assert_eq!(err("a\\)", RegexFlags::bre()), RegexError::Paren);
+        assert_eq!(err("(a", RegexFlags::ere()), RegexError::Paren);
+        assert_eq!(err("[a", RegexFlags::bre()), RegexError::Bracket);
+        assert_eq!(err("[[:foo:]]", RegexFlags::bre()), RegexError::CharClass);
+        assert_eq!(err("[z-a]", RegexFlags::bre()), RegexError::Range);
+        assert_eq!(err("\\1", RegexFlags::bre()), RegexError::SubReg);
+        assert_eq!(err("a\\", RegexFlags::bre()), RegexError::Escape);
+        assert_eq!(err("[[.foo.]]", RegexFlags::bre()), RegexError::Collate);
+    }
+
+    #[test]
+    fn test_nested_intervals_too_big() {
+        let start = std::time::Instant::now();
+        let err = Regex::new(b"((a{255}){255}){255}", RegexFlags::ere()).unwrap_err();
+        assert_eq!(err, RegexError::Space);
+        let err = Regex::new(
+            b"\\(\\(a\\{255\\}\\)\\{255\\}\\)\\{255\\}",
+            RegexFlags::bre(),
+        )
+        .unwrap_err();
+        assert_eq!(err, RegexError::Space);
+        assert!(start.elapsed() < std::time::Duration::from_secs(1));
+        assert!(ere("^(a{255}){2}$").is_match(&[b'a'; 510]));
+    }
+}
@@ -7,256 +7,1392 @@
 // SPDX-License-Identifier: MIT
 //
 
-use std::collections::HashMap;
+//! SCCS history files ("s-files").
+//!
+//! An s-file is a text file whose structural lines start with the control
+//! character `^A` (0x01):
+//!
+//! ```text
+//! ^Ahsssss                          checksum
+//! ^As iiiii/ddddd/uuuuu             delta table entry, newest first:
+//! ^Ad D 1.2 yy/mm/dd hh:mm:ss user serial pred
+//! ^Ai ... / ^Ax ... / ^Ag ...       included, excluded, ignored serials
+//! ^Am mr                            modification requests
+//! ^Ac comment                       comments
+//! ^Ae
+//! ^Au                               user list
+//! ^AU
+//! ^Af x value                       flags
+//! ^At                               descriptive text
+//! ^AT
+//! ^AI serial / ^AD serial / ^AE serial   interleaved body (the weave)
+//! ```
+//!
+//! The checksum is the sum of every byte after the first line, modulo
+//! 65536.  The body holds the text of every delta at once: each line sits
+//! inside the `^AI` block of the delta that inserted it and the `^AD`
+//! blocks of the deltas that deleted it.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::io::{self, Write};
+use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
 
-#[derive(Debug)]
-pub struct SccsFile {
-    pub header: SccsHeader,
-    pub deltas: Vec<SccsDelta>,
-    pub edits: Vec<SccsEdit>,
-    pub user_info: SccsUserInfo,
-    pub stats: SccsStats,
-}
+use crate::diff::{self, Edit};
 
-#[derive(Debug)]
-pub struct SccsHeader {
-    pub format_version: String,
-    pub creation_date: String,
-    pub author: String,
-    pub description: String,
+/// The control character that starts every structural line.
+pub const CTLCHAR: u8 = 0x01;
+
+/// Largest value of a SID component and of a delta serial number.
+pub const MAX_SID_COMPONENT: u16 = 9999;
+
+/// An SCCS identification string: `release.level` on the trunk,
+/// `release.level.branch.sequence` on a branch.  A component of zero is
+/// absent, so a partial SID such as `1` or `1.2.1` (as accepted by
+/// `get -r`) can be represented too.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Sid {
+    pub rel: u16,
+    pub lev: u16,
+    pub br: u16,
+    pub seq: u16,
 }
 
-#[derive(Debug)]
-pub struct SccsDelta {
-    pub sid: String,
-    pub date: String,
-    pub time: String,
-    pub author: String,
-    pub added_lines: usize,
-    pub deleted_lines: usize,
-    pub comments: String,
+impl Sid {
+    /// A trunk SID.
+    pub fn new(rel: u16, lev: u16) -> Sid {
+        Sid {
+            rel,
+            lev,
+            br: 0,
+            seq: 0,
+        }
+    }
+
+    /// A branch SID.
+    pub fn branch(rel: u16, lev: u16, br: u16, seq: u16) -> Sid {
+        Sid { rel, lev, br, seq }
+    }
+
+    /// Number of components present (1 to 4).
+    pub fn components(&self) -> usize {
+        if self.seq != 0 {
+            4
+        } else if self.br != 0 {
+            3
+        } else if self.lev != 0 {
+            2
+        } else {
+            1
+        }
+    }
+
+    /// Whether this SID names a delta on a branch.
+    pub fn is_branch(&self) -> bool {
+        self.br != 0
+    }
+
+    /// The trunk delta this SID is, or branches from.
+    pub fn trunk(&self) -> Sid {
+        Sid::new(self.rel, self.lev)
+    }
+
+    /// Whether this is a complete SID that can name a delta.
+    pub fn is_complete(&self) -> bool {
+        matches!(self.components(), 2 | 4)
+    }
 }
 
-#[derive(Debug)]
-pub enum SccsEdit {
-    Insert(String),
-    Delete(usize),
+impl fmt::Display for Sid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.rel)?;
+        if self.components() >= 2 {
+            write!(f, ".{}", self.lev)?;
+        }
+        if self.components() >= 3 {
+            write!(f, ".{}", self.br)?;
+        }
+        if self.components() >= 4 {
+            write!(f, ".{}", self.seq)?;
+        }
+        Ok(())
+    }
 }
 
-#[derive(Debug)]
-pub struct SccsUserInfo {
-    pub users: HashMap<String, String>,
+impl FromStr for Sid {
+    type Err = String;
+
+    /// Parse a SID of one to four non-zero components.
+    fn from_str(s: &str) -> Result<Sid, String> {
+        let err = || format!("invalid SID '{s}'");
+        let mut parts = [0u16; 4];
+        for (n, part) in s.split('.').enumerate() {
+            if n == 4 || part.is_empty() || !part.bytes().all(|b| b.is_ascii_digit()) {
+                return Err(err());
+            }
+            let value: u16 = part.parse().map_err(|_| err())?;
+            if value == 0 || value > MAX_SID_COMPONENT {
+                return Err(err());
+            }
+            parts[n] = value;
+        }
+        Ok(Sid::branch(parts[0], parts[1], parts[2], parts[3]))
+    }
 }
 
-#[derive(Debug)]
-pub struct SccsStats {
-    pub total_deltas: usize,
-    pub total_lines: usize,
+/// A delta's date and time, as recorded in the delta table.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SccsDate {
+    pub year: u16,
+    pub month: u8,
+    pub day: u8,
+    pub hour: u8,
+    pub minute: u8,
+    pub second: u8,
 }
 
-impl SccsFile {
-    pub fn from_string(s: &str) -> Result<Self, &'static str> {
-        let lines: Vec<&str> = s.lines().collect();
-
-        let header = parse_header(&lines)?;
-        let deltas = parse_deltas(&lines)?;
-        let edits = parse_edits(&lines)?;
-        let user_info = parse_user_info(&lines)?;
-        let stats = parse_stats(&deltas)?;
-
-        Ok(SccsFile {
-            header,
-            deltas,
-            edits,
-            user_info,
-            stats,
+impl SccsDate {
+    /// Expand a two-digit year: 69-99 are 1969-1999, 00-68 are 2000-2068.
+    pub fn full_year(yy: u16) -> u16 {
+        match yy {
+            0..=68 => 2000 + yy,
+            69..=99 => 1900 + yy,
+            _ => yy,
+        }
+    }
+
+    /// Parse the `yy/mm/dd` and `hh:mm:ss` fields of a delta entry.  Four
+    /// digit years are accepted as well.
+    pub fn parse(date: &str, time: &str) -> Option<SccsDate> {
+        fn fields(s: &str, sep: char) -> Option<[u16; 3]> {
+            let mut out = [0u16; 3];
+            let mut parts = s.split(sep);
+            for slot in out.iter_mut() {
+                let part = parts.next()?;
+                if part.is_empty() || !part.bytes().all(|b| b.is_ascii_digit()) {
+                    return None;
+                }
+                *slot = part.parse().ok()?;
+            }
+            parts.next().is_none().then_some(out)
+        }
+
+        let [year, month, day] = fields(date, '/')?;
+        let [hour, minute, second] = fields(time, ':')?;
+        let year = if date.len() > 8 {
+            year
+        } else {
+            SccsDate::full_year(year)
+        };
+        if !(1..=12).contains(&month) || !(1..=31).contains(&day) || hour > 23 || minute > 59 {
+            return None;
+        }
+        if second > 60 {
+            return None;
+        }
+        Some(SccsDate {
+            year,
+            month: month as u8,
+            day: day as u8,
+            hour: hour as u8,
+            minute: minute as u8,
+            second: second as u8,
+        })
+    }
+
+    /// The date as `yy/mm/dd`.
+    pub fn date_str(&self) -> String {
+        format!("{:02}/{:02}/{:02}", self.year % 100, self.month, self.day)
+    }
+
+    /// The time as `hh:mm:ss`.
+    pub fn time_str(&self) -> String {
+        format!("{:02}:{:02}:{:02}", self.hour, self.minute, self.second)
+    }
+
+    /// The current local date and time.
+    pub fn now() -> SccsDate {
+        let mut tm: libc::tm = unsafe { std::mem::zeroed() };
+        unsafe {
+            let t = libc::time(std::ptr::null_mut());
+            libc::localtime_r(&t, &mut tm);
+        }
+        SccsDate {
+            year: (tm.tm_year + 1900) as u16,
+            month: (tm.tm_mon + 1) as u8,
+            day: tm.tm_mday as u8,
+            hour: tm.tm_hour as u8,
+            minute: tm.tm_min as u8,
+            second: tm.tm_sec as u8,
+        }
+    }
+
+    /// Parse a cutoff date-time `[YY[MM[DD[HH[MM[SS]]]]]]` as taken by
+    /// `get -c` and `prs -c`.  Non-digit characters may separate the
+    /// fields; omitted fields take their largest value.
+    pub fn from_cutoff(s: &str) -> Option<SccsDate> {
+        let digits: Vec<u16> = s
+            .chars()
+            .filter_map(|c| c.to_digit(10))
+            .map(|d| d as u16)
+            .collect();
+        if digits.len() % 2 != 0 || digits.len() > 12 || digits.is_empty() {
+            return None;
+        }
+        let field = |i: usize, default: u16| {
+            digits
+                .get(2 * i..2 * i + 2)
+                .map_or(default, |d| d[0] * 10 + d[1])
+        };
+        let year = SccsDate::full_year(field(0, 0));
+        let month = field(1, 12);
+        let last_day = match month {
+            2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+            2 => 28,
+            4 | 6 | 9 | 11 => 30,
+            _ => 31,
+        };
+        let day = field(2, last_day);
+        let (hour, minute, second) = (field(3, 23), field(4, 59), field(5, 59));
+        if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+            return None;
+        }
+        if hour > 23 || minute > 59 || second > 59 {
+            return None;
+        }
+        Some(SccsDate {
+            year,
+            month: month as u8,
+            day: day as u8,
+            hour: hour as u8,
+            minute: minute as u8,
+            second: second as u8,
         })
     }
+}
 
-    pub fn serialize(&self) -> String {
-        serialize_sccs_file(self)
+impl fmt::Display for SccsDate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.date_str(), self.time_str())
     }
 }
 
-fn parse_header(lines: &[&str]) -> Result<SccsHeader, &'static str> {
-    let mut format_version = String::new();
-    let mut creation_date = String::new();
-    let author = String::new();
-    let description = String::new();
+/// The type of a delta table entry.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DeltaType {
+    /// An ordinary delta (`D`).
+    #[default]
+    Normal,
+    /// A delta removed by `rmdel` (`R`).
+    Removed,
+}
 
-    for line in lines {
-        if line.starts_with('h') {
-            format_version = line.to_string();
-        } else if line.starts_with('s') && creation_date.is_empty() {
-            creation_date = line.to_string();
-        } else if line.starts_with('d') {
-            break; // End of header section
+impl DeltaType {
+    pub fn as_char(&self) -> char {
+        match self {
+            DeltaType::Normal => 'D',
+            DeltaType::Removed => 'R',
         }
     }
+}
+
+/// Line counts of the `^As` line: lines inserted, deleted and unchanged
+/// by a delta.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DeltaStats {
+    pub inserted: u32,
+    pub deleted: u32,
+    pub unchanged: u32,
+}
+
+/// An entry of the delta table.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Delta {
+    pub stats: DeltaStats,
+    pub kind: DeltaType,
+    pub sid: Sid,
+    pub date: SccsDate,
+    pub user: String,
+    pub serial: u16,
+    /// Serial number of the predecessor delta, 0 for the first delta.
+    pub pred: u16,
+    pub included: Vec<u16>,
+    pub excluded: Vec<u16>,
+    pub ignored: Vec<u16>,
+    /// Modification request numbers.
+    pub mrs: Vec<String>,
+    pub comments: Vec<String>,
+}
+
+/// A line of the weave.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum WeaveLine {
+    /// `^AI serial`: start of lines inserted by a delta.
+    Insert(u16),
+    /// `^AD serial`: start of lines deleted by a delta.
+    Delete(u16),
+    /// `^AE serial`: end of an insert or delete block.
+    End(u16),
+    /// A line of text, without its newline.
+    Text(Vec<u8>),
+}
+
+/// A line of text retrieved from the weave.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BodyLine<'a> {
+    /// Serial number of the delta that inserted the line.
+    pub serial: u16,
+    /// Index of the line in [`SccsFile::weave`].
+    pub index: usize,
+    pub text: &'a [u8],
+}
+
+/// A parsed s-file.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SccsFile {
+    /// The delta table, newest (highest serial) first.
+    pub deltas: Vec<Delta>,
+    /// Users and group IDs allowed to make deltas; empty allows anyone.
+    pub users: Vec<String>,
+    /// Flags by letter; flags without a value map to an empty string.
+    pub flags: BTreeMap<char, String>,
+    /// Descriptive text.
+    pub text: Vec<String>,
+    pub weave: Vec<WeaveLine>,
+    stored_checksum: u16,
+    checksum_ok: bool,
+}
+
+fn format_error(lineno: usize, msg: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("format error at line {lineno}: {msg}"),
+    )
+}
 
-    if format_version.is_empty() || creation_date.is_empty() {
-        return Err("Missing header");
+/// Sum of `data` as SCCS computes it, modulo 65536.
+pub fn checksum(data: &[u8]) -> u16 {
+    data.iter()
+        .fold(0u16, |sum, &b| sum.wrapping_add(u16::from(b)))
+}
+
+/// The checksum of historical implementations that summed signed chars.
+fn signed_checksum(data: &[u8]) -> u16 {
+    data.iter()
+        .fold(0u16, |sum, &b| sum.wrapping_add(b as i8 as i16 as u16))
+}
+
+fn parse_number<T: FromStr>(s: &str, lineno: usize, what: &str) -> io::Result<T> {
+    if s.is_empty() || !s.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(format_error(lineno, &format!("invalid {what}")));
+    }
+    s.parse()
+        .map_err(|_| format_error(lineno, &format!("invalid {what}")))
+}
+
+fn parse_serial(s: &str, lineno: usize) -> io::Result<u16> {
+    let serial: u16 = parse_number(s, lineno, "serial number")?;
+    if serial == 0 {
+        return Err(format_error(lineno, "invalid serial number"));
+    }
+    Ok(serial)
+}
+
+fn parse_serial_list(s: &str, lineno: usize) -> io::Result<Vec<u16>> {
+    s.split_whitespace()
+        .map(|n| parse_serial(n, lineno))
+        .collect()
+}
+
+/// The s-file as a sequence of lines, with the line number of each and
+/// the operands of the control lines.
+struct Lines<'a> {
+    lines: Vec<&'a [u8]>,
+    pos: usize,
+}
+
+impl<'a> Lines<'a> {
+    fn lineno(&self) -> usize {
+        self.pos
     }
 
-    Ok(SccsHeader {
-        format_version,
-        creation_date,
-        author,
-        description,
+    fn peek(&self) -> Option<&'a [u8]> {
+        self.lines.get(self.pos).copied()
+    }
+
+    /// The next line, if it is a control line with keyword `key`; returns
+    /// its operand text with the separating space removed.
+    fn control(&mut self, key: u8) -> io::Result<Option<String>> {
+        let Some(line) = self.peek() else {
+            return Ok(None);
+        };
+        if line.len() < 2 || line[0] != CTLCHAR || line[1] != key {
+            return Ok(None);
+        }
+        self.pos += 1;
+        let rest = &line[2..];
+        let rest = rest.strip_prefix(b" ").unwrap_or(rest);
+        match std::str::from_utf8(rest) {
+            Ok(s) => Ok(Some(s.to_string())),
+            Err(_) => Err(format_error(self.pos, "invalid character in control line")),
+        }
+    }
+
+    fn expect(&mut self, key: u8) -> io::Result<String> {
+        self.control(key)?.ok_or_else(|| {
+            format_error(
+                self.pos + 1,
+                &format!("expected control line '^A{}'", key as char),
+            )
+        })
+    }
+
+    /// Plain text lines up to the control line `end`.
+    fn text_until(&mut self, end: u8) -> io::Result<Vec<String>> {
+        let mut text = Vec::new();
+        while let Some(line) = self.peek() {
+            if line.first() == Some(&CTLCHAR) {
+                break;
+            }
+            self.pos += 1;
+            text.push(String::from_utf8_lossy(line).into_owned());
+        }
+        self.expect(end)?;
+        Ok(text)
+    }
+}
+
+fn parse_stats(s: &str, lineno: usize) -> io::Result<DeltaStats> {
+    let fields: Vec<&str> = s.split('/').collect();
+    if fields.len() != 3 {
+        return Err(format_error(lineno, "invalid delta statistics"));
+    }
+    Ok(DeltaStats {
+        inserted: parse_number(fields[0], lineno, "delta statistics")?,
+        deleted: parse_number(fields[1], lineno, "delta statistics")?,
+        unchanged: parse_number(fields[2], lineno, "delta statistics")?,
     })
 }
 
-fn parse_edits(lines: &[&str]) -> Result<Vec<SccsEdit>, &'static str> {
-    let mut edits = Vec::new();
-    let mut current_insert = String::new();
-    let mut in_insert_section = false;
-
-    for line in lines.iter() {
-        if line.starts_with("I ") {
-            in_insert_section = true;
-            current_insert.push_str(line);
-            current_insert.push('\n');
-        } else if line.starts_with("E ") && in_insert_section {
-            current_insert.push_str(line);
-            current_insert.push('\n');
-            edits.push(SccsEdit::Insert(current_insert.clone()));
-            current_insert.clear();
-            in_insert_section = false;
-        } else if in_insert_section {
-            current_insert.push_str(line);
-            current_insert.push('\n');
-        }
-    }
-
-    Ok(edits)
-}
-
-fn parse_user_info(lines: &[&str]) -> Result<SccsUserInfo, &'static str> {
-    // Simplified user info parsing
-    let mut users = HashMap::new();
-    for line in lines.iter().skip_while(|l| !l.starts_with("users")).skip(1) {
-        let parts: Vec<&str> = line.split_whitespace().collect();
-        if parts.len() < 2 {
-            return Err("Invalid user info format");
-        }
-        users.insert(parts[0].to_string(), parts[1].to_string());
-    }
-    Ok(SccsUserInfo { users })
-}
-
-fn parse_deltas(lines: &[&str]) -> Result<Vec<SccsDelta>, &'static str> {
-    let mut deltas = Vec::new();
-    let mut current_comments = String::new();
-    let mut in_delta_section = false;
-
-    for line in lines.iter() {
-        if line.starts_with("d D") {
-            in_delta_section = true;
-            let parts: Vec<&str> = line.split_whitespace().collect();
-            if parts.len() < 8 {
-                return Err("Invalid delta format");
-            }
-            deltas.push(SccsDelta {
-                sid: parts[2].to_string(),
-                date: parts[3].to_string(),
-                time: parts[4].to_string(),
-                author: parts[5].to_string(),
-                added_lines: usize::from_str(parts[6]).map_err(|_| "Invalid number format")?,
-                deleted_lines: usize::from_str(parts[7]).map_err(|_| "Invalid number format")?,
-                comments: String::new(),
-            });
-        } else if in_delta_section {
-            if let Some(st) = line.strip_prefix("c ") {
-                current_comments.push_str(st);
-                current_comments.push('\n');
-            } else if line.starts_with("e") {
-                if let Some(last_delta) = deltas.last_mut() {
-                    last_delta.comments = current_comments.clone();
+fn parse_delta(lines: &mut Lines, stats_line: &str) -> io::Result<Delta> {
+    let stats = parse_stats(stats_line, lines.lineno())?;
+    let d_line = lines.expect(b'd')?;
+    let lineno = lines.lineno();
+    let fields: Vec<&str> = d_line.split(' ').collect();
+    if fields.len() != 7 {
+        return Err(format_error(lineno, "invalid delta entry"));
+    }
+    let kind = match fields[0] {
+        "D" => DeltaType::Normal,
+        "R" => DeltaType::Removed,
+        _ => return Err(format_error(lineno, "invalid delta type")),
+    };
+    let sid: Sid = fields[1]
+        .parse()
+        .map_err(|e: String| format_error(lineno, &e))?;
+    if !sid.is_complete() {
+        return Err(format_error(lineno, "invalid SID"));
+    }
+    let date = SccsDate::parse(fields[2], fields[3])
+        .ok_or_else(|| format_error(lineno, "invalid date"))?;
+    let serial = parse_serial(fields[5], lineno)?;
+    let pred = parse_number(fields[6], lineno, "predecessor serial number")?;
+
+    let mut delta = Delta {
+        stats,
+        kind,
+        sid,
+        date,
+        user: fields[4].to_string(),
+        serial,
+        pred,
+        ..Default::default()
+    };
+
+    if let Some(list) = lines.control(b'i')? {
+        delta.included = parse_serial_list(&list, lines.lineno())?;
+    }
+    if let Some(list) = lines.control(b'x')? {
+        delta.excluded = parse_serial_list(&list, lines.lineno())?;
+    }
+    if let Some(list) = lines.control(b'g')? {
+        delta.ignored = parse_serial_list(&list, lines.lineno())?;
+    }
+    while let Some(mr) = lines.control(b'm')? {
+        delta.mrs.push(mr);
+    }
+    while let Some(comment) = lines.control(b'c')? {
+        delta.comments.push(comment);
+    }
+    lines.expect(b'e')?;
+    Ok(delta)
+}
+
+fn parse_weave(lines: &mut Lines) -> io::Result<Vec<WeaveLine>> {
+    let mut weave = Vec::new();
+    let mut open: Vec<u16> = Vec::new();
+    while let Some(line) = lines.peek() {
+        lines.pos += 1;
+        let lineno = lines.lineno();
+        if line.first() != Some(&CTLCHAR) {
+            weave.push(WeaveLine::Text(line.to_vec()));
+            continue;
+        }
+        let operand = std::str::from_utf8(line.get(2..).unwrap_or_default())
+            .map_err(|_| format_error(lineno, "invalid control line"))?;
+        let serial = parse_serial(operand.strip_prefix(' ').unwrap_or(""), lineno)?;
+        match line.get(1) {
+            Some(b'I') | Some(b'D') => {
+                if open.contains(&serial) {
+                    return Err(format_error(lineno, "block opened twice"));
+                }
+                open.push(serial);
+                weave.push(if line[1] == b'I' {
+                    WeaveLine::Insert(serial)
+                } else {
+                    WeaveLine::Delete(serial)
+                });
+            }
+            Some(b'E') => {
+                let pos = open
+                    .iter()
+                    .rposition(|&s| s == serial)
+                    .ok_or_else(|| format_error(lineno, "end of block that is not open"))?;
+                open.remove(pos);
+                weave.push(WeaveLine::End(serial));
+            }
+            _ => return Err(format_error(lineno, "invalid control line in body")),
+        }
+    }
+    if !open.is_empty() {
+        return Err(format_error(lines.lineno(), "premature end of file"));
+    }
+    Ok(weave)
+}
+
+impl SccsFile {
+    /// Parse the contents of an s-file.  A checksum mismatch is not an
+    /// error here; see [`SccsFile::checksum_valid`].
+    pub fn from_bytes(data: &[u8]) -> io::Result<SccsFile> {
+        let mut split: Vec<&[u8]> = data.split(|&b| b == b'\n').collect();
+        if data.ends_with(b"\n") {
+            split.pop();
+        }
+        let mut lines = Lines {
+            lines: split,
+            pos: 0,
+        };
+
+        let header = lines.expect(b'h')?;
+        let header = header.trim_start();
+        if header.len() != 5 {
+            return Err(format_error(1, "invalid checksum line"));
+        }
+        let stored_checksum: u16 = parse_number(header, 1, "checksum")?;
+        let rest = data
+            .iter()
+            .position(|&b| b == b'\n')
+            .map_or(&data[data.len()..], |pos| &data[pos + 1..]);
+        let checksum_ok =
+            checksum(rest) == stored_checksum || signed_checksum(rest) == stored_checksum;
+
+        let mut file = SccsFile {
+            stored_checksum,
+            checksum_ok,
+            ..Default::default()
+        };
+
+        while let Some(stats) = lines.control(b's')? {
+            file.deltas.push(parse_delta(&mut lines, &stats)?);
+        }
+
+        lines.expect(b'u')?;
+        file.users = lines.text_until(b'U')?;
+
+        while let Some(flag) = lines.control(b'f')? {
+            let mut chars = flag.chars();
+            let letter = chars
+                .next()
+                .filter(|c| c.is_ascii_lowercase())
+                .ok_or_else(|| format_error(lines.lineno(), "invalid flag"))?;
+            let value = chars.as_str();
+            let value = value.strip_prefix(' ').unwrap_or(value);
+            file.flags.insert(letter, value.to_string());
+        }
+
+        lines.expect(b't')?;
+        file.text = lines.text_until(b'T')?;
+
+        file.weave = parse_weave(&mut lines)?;
+        file.check_delta_table()?;
+        Ok(file)
+    }
+
+    /// Parse an s-file given as a string.
+    pub fn from_string(s: &str) -> io::Result<SccsFile> {
+        SccsFile::from_bytes(s.as_bytes())
+    }
+
+    /// Read and parse the s-file at `path`.
+    pub fn read(path: &Path) -> io::Result<SccsFile> {
+        SccsFile::from_bytes(&fs::read(path)?)
+    }
+
+    fn check_delta_table(&self) -> io::Result<()> {
+        let invalid = |msg: String| {
+            io::Error::new(io::ErrorKind::InvalidData, format!("corrupted file: {msg}"))
+        };
+        let max = self.max_serial();
+        for delta in &self.deltas {
+            if self
+                .deltas
+                .iter()
+                .filter(|d| d.serial == delta.serial)
+                .count()
+                > 1
+            {
+                return Err(invalid(format!("duplicate serial number {}", delta.serial)));
+            }
+            if delta.pred >= delta.serial {
+                return Err(invalid(format!(
+                    "delta {} has an invalid predecessor",
+                    delta.sid
+                )));
+            }
+            let lists = [&delta.included, &delta.excluded, &delta.ignored];
+            if lists.iter().any(|list| list.iter().any(|&s| s > max)) {
+                return Err(invalid(format!(
+                    "delta {} refers to an unknown serial number",
+                    delta.sid
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether the checksum stored in the file matches its contents.
+    pub fn checksum_valid(&self) -> bool {
+        self.checksum_ok
+    }
+
+    /// The checksum recorded in the file as read.
+    pub fn stored_checksum(&self) -> u16 {
+        self.stored_checksum
+    }
+
+    /// Serialize everything after the checksum line.
+    fn serialize_body(&self) -> Vec<u8> {
+        fn control(out: &mut Vec<u8>, key: u8, operand: Option<&str>) {
+            out.push(CTLCHAR);
+            out.push(key);
+            if let Some(operand) = operand {
+                out.push(b' ');
+                out.extend_from_slice(operand.as_bytes());
+            }
+            out.push(b'\n');
+        }
+        fn serials(list: &[u16]) -> String {
+            let list: Vec<String> = list.iter().map(|s| s.to_string()).collect();
+            list.join(" ")
+        }
+
+        let mut out = Vec::new();
+        for d in &self.deltas {
+            let stats = format!(
+                "{:05}/{:05}/{:05}",
+                d.stats.inserted.min(99999),
+                d.stats.deleted.min(99999),
+                d.stats.unchanged.min(99999)
+            );
+            control(&mut out, b's', Some(&stats));
+            let entry = format!(
+                "{} {} {} {} {} {}",
+                d.kind.as_char(),
+                d.sid,
+                d.date,
+                d.user,
+                d.serial,
+                d.pred
+            );
+            control(&mut out, b'd', Some(&entry));
+            if !d.included.is_empty() {
+                control(&mut out, b'i', Some(&serials(&d.included)));
+            }
+            if !d.excluded.is_empty() {
+                control(&mut out, b'x', Some(&serials(&d.excluded)));
+            }
+            if !d.ignored.is_empty() {
+                control(&mut out, b'g', Some(&serials(&d.ignored)));
+            }
+            for mr in &d.mrs {
+                control(&mut out, b'm', Some(mr));
+            }
+            for comment in &d.comments {
+                control(&mut out, b'c', Some(comment));
+            }
+            control(&mut out, b'e', None);
+        }
+
+        control(&mut out, b'u', None);
+        for user in &self.users {
+            out.extend_from_slice(user.as_bytes());
+            out.push(b'\n');
+        }
+        control(&mut out, b'U', None);
+
+        for (letter, value) in &self.flags {
+            let flag = if value.is_empty() {
+                letter.to_string()
+            } else {
+                format!("{letter} {value}")
+            };
+            control(&mut out, b'f', Some(&flag));
+        }
+
+        control(&mut out, b't', None);
+        for line in &self.text {
+            out.extend_from_slice(line.as_bytes());
+            out.push(b'\n');
+        }
+        control(&mut out, b'T', None);
+
+        for line in &self.weave {
+            match line {
+                WeaveLine::Insert(s) => control(&mut out, b'I', Some(&s.to_string())),
+                WeaveLine::Delete(s) => control(&mut out, b'D', Some(&s.to_string())),
+                WeaveLine::End(s) => control(&mut out, b'E', Some(&s.to_string())),
+                WeaveLine::Text(text) => {
+                    out.extend_from_slice(text);
+                    out.push(b'\n');
+                }
+            }
+        }
+        out
+    }
+
+    /// Serialize the s-file with a freshly computed checksum.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let body = self.serialize_body();
+        let mut out = format!("\x01h{:05}\n", checksum(&body)).into_bytes();
+        out.extend_from_slice(&body);
+        out
+    }
+
+    /// The delta with SID `sid`.
+    pub fn delta(&self, sid: &Sid) -> Option<&Delta> {
+        self.deltas.iter().find(|d| d.sid == *sid)
+    }
+
+    /// The delta with SID `sid`, unless it was removed: the SIDs of removed
+    /// deltas may be used again.
+    pub fn live_delta(&self, sid: &Sid) -> Option<&Delta> {
+        self.deltas
+            .iter()
+            .find(|d| d.sid == *sid && d.kind == DeltaType::Normal)
+    }
+
+    /// The delta with serial number `serial`.
+    pub fn delta_by_serial(&self, serial: u16) -> Option<&Delta> {
+        self.deltas.iter().find(|d| d.serial == serial)
+    }
+
+    /// The highest serial number in the delta table.
+    pub fn max_serial(&self) -> u16 {
+        self.deltas.iter().map(|d| d.serial).max().unwrap_or(0)
+    }
+
+    /// Deltas whose predecessor is `serial`: the next trunk delta and the
+    /// first delta of each branch started from it.
+    pub fn successors(&self, serial: u16) -> impl Iterator<Item = &Delta> {
+        self.deltas
+            .iter()
+            .filter(move |d| d.pred == serial && d.kind == DeltaType::Normal)
+    }
+
+    /// The serial numbers of the delta `serial` and its predecessors.
+    pub fn ancestors(&self, serial: u16) -> Vec<u16> {
+        let mut chain = Vec::new();
+        let mut current = serial;
+        while current != 0 && !chain.contains(&current) {
+            chain.push(current);
+            current = self.delta_by_serial(current).map_or(0, |d| d.pred);
+        }
+        chain
+    }
+
+    /// The value of flag `letter`, if set.
+    pub fn flag(&self, letter: char) -> Option<&str> {
+        self.flags.get(&letter).map(|s| s.as_str())
+    }
+
+    /// Which deltas apply when retrieving delta `serial`, indexed by
+    /// serial number.  The delta and its predecessors apply, adjusted by
+    /// the include, exclude and ignore lists recorded in the applied
+    /// deltas; the explicit `include` and `exclude` lists take precedence.
+    pub fn applied_serials(&self, serial: u16, include: &[u16], exclude: &[u16]) -> Vec<bool> {
+        let max = usize::from(self.max_serial().max(serial));
+        let mut state: Vec<Option<bool>> = vec![None; max + 1];
+        for &s in include {
+            if let Some(slot) = state.get_mut(usize::from(s)) {
+                *slot = Some(true);
+            }
+        }
+        for &s in exclude {
+            if let Some(slot) = state.get_mut(usize::from(s)) {
+                *slot = Some(false);
+            }
+        }
+
+        let mut on_chain = vec![false; max + 1];
+        for s in self.ancestors(serial) {
+            on_chain[usize::from(s)] = true;
+        }
+
+        for s in (1..=max).rev() {
+            if state[s].is_none() && on_chain[s] {
+                state[s] = Some(true);
+            }
+            if state[s] != Some(true) {
+                continue;
+            }
+            let Some(delta) = self.delta_by_serial(s as u16) else {
+                continue;
+            };
+            for &i in &delta.included {
+                let slot = &mut state[usize::from(i)];
+                if slot.is_none() {
+                    *slot = Some(true);
+                }
+            }
+            for &x in delta.excluded.iter().chain(&delta.ignored) {
+                let slot = &mut state[usize::from(x)];
+                if slot.is_none() {
+                    *slot = Some(false);
+                }
+            }
+        }
+
+        state.into_iter().map(|s| s == Some(true)).collect()
+    }
+
+    /// The text of the version made up of the `applied` deltas (see
+    /// [`SccsFile::applied_serials`]).
+    ///
+    /// Of the blocks enclosing a line, the one with the highest serial
+    /// number that is either an insertion or an applied deletion decides:
+    /// the line is present if that block inserts it and its delta applies.
+    pub fn body(&self, applied: &[bool]) -> Vec<BodyLine<'_>> {
+        let is_applied = |s: u16| applied.get(usize::from(s)).copied().unwrap_or(false);
+        let mut open: Vec<(u16, bool)> = Vec::new();
+        let mut visible: Option<u16> = None;
+        let mut lines = Vec::new();
+
+        for (index, line) in self.weave.iter().enumerate() {
+            match line {
+                WeaveLine::Insert(s) => open.push((*s, true)),
+                WeaveLine::Delete(s) => open.push((*s, false)),
+                WeaveLine::End(s) => {
+                    if let Some(pos) = open.iter().rposition(|(o, _)| o == s) {
+                        open.remove(pos);
+                    }
+                }
+                WeaveLine::Text(text) => {
+                    if let Some(serial) = visible {
+                        lines.push(BodyLine {
+                            serial,
+                            index,
+                            text,
+                        });
+                    }
+                    continue;
+                }
+            }
+
+            visible = open
+                .iter()
+                .filter(|&&(s, insert)| insert || is_applied(s))
+                .max_by_key(|(s, _)| *s)
+                .and_then(|&(s, insert)| (insert && is_applied(s)).then_some(s));
+        }
+        lines
+    }
+
+    /// The text of delta `sid`, with the deltas in `include` added and
+    /// those in `exclude` left out.
+    pub fn body_for_sid(
+        &self,
+        sid: &Sid,
+        include: &[Sid],
+        exclude: &[Sid],
+    ) -> io::Result<Vec<BodyLine<'_>>> {
+        let serial_of = |sid: &Sid| {
+            self.delta(sid)
+                .filter(|d| d.kind == DeltaType::Normal)
+                .map(|d| d.serial)
+                .ok_or_else(|| {
+                    io::Error::new(io::ErrorKind::NotFound, format!("nonexistent SID {sid}"))
+                })
+        };
+        let serial = serial_of(sid)?;
+        let include = include
+            .iter()
+            .map(serial_of)
+            .collect::<io::Result<Vec<_>>>()?;
+        let exclude = exclude
+            .iter()
+            .map(serial_of)
+            .collect::<io::Result<Vec<_>>>()?;
+        let applied = self.applied_serials(serial, &include, &exclude);
+        Ok(self.body(&applied))
+    }
+
+    /// Add `delta` as the newest delta, turning the version made up of the
+    /// `applied` deltas into `new_text`.  The delta gets the next serial
+    /// number and the line counts of the change, which are returned.
+    pub fn add_delta(
+        &mut self,
+        mut delta: Delta,
+        applied: &[bool],
+        new_text: &[&[u8]],
+    ) -> DeltaStats {
+        let serial = self.max_serial() + 1;
+        let old: Vec<(usize, &[u8])> = self
+            .body(applied)
+            .iter()
+            .map(|l| (l.index, l.text))
+            .collect();
+        let old_text: Vec<&[u8]> = old.iter().map(|&(_, text)| text).collect();
+
+        // Control lines to place before and after each line of the weave.
+        let mut before: Vec<Vec<WeaveLine>> = vec![Vec::new(); self.weave.len()];
+        let mut after: Vec<Vec<WeaveLine>> = vec![Vec::new(); self.weave.len()];
+        let mut at_start = Vec::new();
+        let mut stats = DeltaStats::default();
+
+        // Weave indices of the first and last line of the deletion being
+        // built, the last old line passed, and the lines to insert after it.
+        let mut deletion: Option<(usize, usize)> = None;
+        let mut anchor: Option<usize> = None;
+        let mut inserted: Vec<WeaveLine> = Vec::new();
+
+        let close_deletion = |deletion: &mut Option<(usize, usize)>,
+                              before: &mut Vec<Vec<WeaveLine>>,
+                              after: &mut Vec<Vec<WeaveLine>>| {
+            if let Some((first, last)) = deletion.take() {
+                before[first].push(WeaveLine::Delete(serial));
+                after[last].push(WeaveLine::End(serial));
+            }
+        };
+        let flush_insertion = |inserted: &mut Vec<WeaveLine>,
+                               anchor: Option<usize>,
+                               after: &mut Vec<Vec<WeaveLine>>,
+                               at_start: &mut Vec<WeaveLine>| {
+            if inserted.is_empty() {
+                return;
+            }
+            let target = match anchor {
+                Some(index) => &mut after[index],
+                None => at_start,
+            };
+            target.push(WeaveLine::Insert(serial));
+            target.append(inserted);
+            target.push(WeaveLine::End(serial));
+        };
+
+        for edit in diff::edit_script(&old_text, new_text) {
+            match edit {
+                Edit::Keep { old: i, .. } => {
+                    close_deletion(&mut deletion, &mut before, &mut after);
+                    flush_insertion(&mut inserted, anchor, &mut after, &mut at_start);
+                    anchor = Some(old[i].0);
+                    stats.unchanged += 1;
+                }
+                Edit::Delete { old: i } => {
+                    flush_insertion(&mut inserted, anchor, &mut after, &mut at_start);
+                    let index = old[i].0;
+                    // Extend the deletion unless other text lies between.
+                    deletion = match deletion {
+                        Some((first, last))
+                            if !self.weave[last + 1..index]
+                                .iter()
+                                .any(|l| matches!(l, WeaveLine::Text(_))) =>
+                        {
+                            Some((first, index))
+                        }
+                        _ => {
+                            close_deletion(&mut deletion, &mut before, &mut after);
+                            Some((index, index))
+                        }
+                    };
+                    anchor = Some(index);
+                    stats.deleted += 1;
+                }
+                Edit::Insert { new } => {
+                    inserted.push(WeaveLine::Text(new_text[new].to_vec()));
+                    stats.inserted += 1;
+                }
+            }
+        }
+        close_deletion(&mut deletion, &mut before, &mut after);
+        flush_insertion(&mut inserted, anchor, &mut after, &mut at_start);
+        if self.weave.is_empty() && at_start.is_empty() {
+            at_start = vec![WeaveLine::Insert(serial), WeaveLine::End(serial)];
+        }
+
+        let old_weave = std::mem::take(&mut self.weave);
+        let mut weave = at_start;
+        for ((line, before), after) in old_weave.into_iter().zip(before).zip(after) {
+            weave.extend(before);
+            weave.push(line);
+            weave.extend(after);
+        }
+        self.weave = weave;
+
+        delta.serial = serial;
+        delta.stats = stats;
+        self.deltas.insert(0, delta);
+        stats
+    }
+
+    /// Remove delta `serial`: the lines it inserted and its control lines
+    /// leave the weave, and it is marked removed in the delta table.  The
+    /// caller checks that no other delta depends on it.
+    pub fn remove_delta(&mut self, serial: u16) {
+        let mut open: Vec<(u16, bool)> = Vec::new();
+        let old_weave = std::mem::take(&mut self.weave);
+        for line in old_weave {
+            match line {
+                WeaveLine::Insert(s) => open.push((s, true)),
+                WeaveLine::Delete(s) => open.push((s, false)),
+                WeaveLine::End(s) => {
+                    if let Some(pos) = open.iter().rposition(|&(o, _)| o == s) {
+                        open.remove(pos);
+                    }
+                }
+                WeaveLine::Text(_) => {
+                    let inserter = open.iter().rev().find(|&&(_, insert)| insert);
+                    if inserter.is_some_and(|&(s, _)| s == serial) {
+                        continue;
+                    }
                 }
-                current_comments.clear();
-                in_delta_section = false;
+            }
+            if !matches!(line, WeaveLine::Insert(s) | WeaveLine::Delete(s) | WeaveLine::End(s) if s == serial)
+            {
+                self.weave.push(line);
             }
         }
+        if let Some(delta) = self.deltas.iter_mut().find(|d| d.serial == serial) {
+            delta.kind = DeltaType::Removed;
+        }
+    }
+
+    /// Write the s-file to `path`; see [`write_sfile`].
+    pub fn write(&self, path: &Path) -> io::Result<()> {
+        write_sfile(path, &self.to_bytes())
     }
 
-    Ok(deltas)
+    /// Serial numbers of the deltas named by a `get -i`/`-x` list: SIDs
+    /// and `SID1-SID2` ranges separated by commas.  A range covers the
+    /// deltas between its ends on the trunk, or on the branch of `SID1`.
+    pub fn sid_list(&self, list: &str) -> io::Result<Vec<u16>> {
+        let invalid = |msg: String| io::Error::new(io::ErrorKind::InvalidInput, msg);
+        let exists = |sid: &str| -> io::Result<Sid> {
+            let sid: Sid = sid.parse().map_err(invalid)?;
+            match self.delta(&sid) {
+                Some(d) if d.kind == DeltaType::Normal => Ok(sid),
+                _ => Err(invalid(format!("nonexistent SID {sid}"))),
+            }
+        };
+
+        let mut serials = Vec::new();
+        for item in list.split(',') {
+            let (first, last) = match item.split_once('-') {
+                Some((a, b)) => (exists(a)?, exists(b)?),
+                None => {
+                    let sid = exists(item)?;
+                    (sid, sid)
+                }
+            };
+            let same_line = |sid: &Sid| {
+                if first.is_branch() {
+                    (sid.rel, sid.lev, sid.br) == (first.rel, first.lev, first.br)
+                } else {
+                    !sid.is_branch()
+                }
+            };
+            if first > last || !same_line(&last) {
+                return Err(invalid(format!("invalid SID range {item}")));
+            }
+            for d in self.deltas.iter().rev() {
+                let in_range = first <= d.sid && d.sid <= last && same_line(&d.sid);
+                if in_range && d.kind == DeltaType::Normal && !serials.contains(&d.serial) {
+                    serials.push(d.serial);
+                }
+            }
+        }
+        Ok(serials)
+    }
+
+    /// Whether the user list allows `user`, a member of the groups `gids`,
+    /// to make deltas.  Entries are login names or numeric group IDs; an
+    /// entry starting with `!` denies instead.
+    pub fn user_allowed(&self, user: &str, gids: &[u32]) -> bool {
+        let matches = |entry: &str| {
+            entry == user || entry.parse::<u32>().is_ok_and(|gid| gids.contains(&gid))
+        };
+        let entries: Vec<&str> = self
+            .users
+            .iter()
+            .flat_map(|l| l.split_whitespace())
+            .collect();
+        if entries
+            .iter()
+            .any(|e| e.strip_prefix('!').is_some_and(matches))
+        {
+            return false;
+        }
+        let allow: Vec<&&str> = entries.iter().filter(|e| !e.starts_with('!')).collect();
+        allow.is_empty() || allow.iter().any(|e| matches(e))
+    }
+
+    /// Whether release `rel` is locked against editing by the `l` flag.
+    pub fn release_locked(&self, rel: u16) -> bool {
+        self.flag('l').is_some_and(|list| {
+            list.split(',')
+                .any(|item| match item.trim().split_once('-') {
+                    _ if item.trim() == "a" => true,
+                    Some((a, b)) => match (a.parse::<u16>(), b.parse::<u16>()) {
+                        (Ok(a), Ok(b)) => (a..=b).contains(&rel),
+                        _ => false,
+                    },
+                    None => item.trim().parse::<u16>() == Ok(rel),
+                })
+        })
+    }
 }
 
-fn parse_stats(deltas: &[SccsDelta]) -> Result<SccsStats, &'static str> {
-    let total_deltas = deltas.len();
-    let total_lines = deltas.iter().map(|d| d.added_lines + d.deleted_lines).sum();
+/// The name of an auxiliary file of the s-file `sfile`: the file in the
+/// same directory with the `s.` prefix replaced by `prefix` and a dot.
+/// Returns `None` if `sfile` is not named `s.something`.
+pub fn aux_file(sfile: &Path, prefix: char) -> Option<PathBuf> {
+    let name = sfile.file_name()?.to_str()?;
+    let module = name.strip_prefix("s.").filter(|m| !m.is_empty())?;
+    Some(sfile.with_file_name(format!("{prefix}.{module}")))
+}
 
-    Ok(SccsStats {
-        total_deltas,
-        total_lines,
-    })
+/// The name of the g-file (the retrieved text) for `sfile`: the s-file
+/// name without its `s.` prefix.  It is created in the current directory.
+pub fn gfile_name(sfile: &Path) -> Option<PathBuf> {
+    let name = sfile.file_name()?.to_str()?;
+    name.strip_prefix("s.")
+        .filter(|m| !m.is_empty())
+        .map(PathBuf::from)
+}
+
+/// Expand the file operands of an SCCS utility: a directory stands for
+/// every `s.` file in it, and `-` for the names read from standard input.
+pub fn sfile_operands(operands: &[PathBuf]) -> io::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for operand in operands {
+        if operand.as_os_str() == "-" {
+            for line in io::stdin().lines() {
+                let line = line?;
+                if !line.is_empty() {
+                    files.push(PathBuf::from(line));
+                }
+            }
+        } else if operand.is_dir() {
+            let mut entries = Vec::new();
+            for entry in fs::read_dir(operand)? {
+                let path = entry?.path();
+                if gfile_name(&path).is_some() && path.is_file() {
+                    entries.push(path);
+                }
+            }
+            entries.sort();
+            files.extend(entries);
+        } else {
+            files.push(operand.clone());
+        }
+    }
+    Ok(files)
 }
 
-fn serialize_sccs_file(sccs_file: &SccsFile) -> String {
-    let mut result = String::new();
-    result.push_str(&sccs_file.header.format_version);
-    result.push('\n');
-    result.push_str(&sccs_file.header.creation_date);
-    result.push('\n');
-    result.push_str(&sccs_file.header.author);
-    result.push('\n');
-    result.push_str(&sccs_file.header.description);
-    result.push('\n');
+/// The letters of the ID keywords `%X%` that `get` expands.
+pub const ID_KEYWORDS: &[u8] = b"ABCDEFGHILMPQRSTUWYZ";
 
-    for delta in &sccs_file.deltas {
-        result.push_str(&format!(
-            "d D {} {} {} {} {} {}\n",
-            delta.sid, delta.date, delta.time, delta.author, delta.added_lines, delta.deleted_lines
-        ));
-        if !delta.comments.is_empty() {
-            result.push_str(&format!("c {}\n", delta.comments.trim_end()));
+/// Whether `line` contains an ID keyword.
+pub fn has_id_keywords(line: &[u8]) -> bool {
+    line.windows(3)
+        .any(|w| w[0] == b'%' && w[2] == b'%' && ID_KEYWORDS.contains(&w[1]))
+}
+
+/// Replace the s-file `path` with `data`.  The data is written to the
+/// `x.` file next to it, which is then renamed over the s-file; the mode
+/// of an existing s-file is kept, and a new one is read-only.
+pub fn write_sfile(path: &Path, data: &[u8]) -> io::Result<()> {
+    let xfile = aux_file(path, 'x')
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "not an SCCS file"))?;
+    let mode = fs::metadata(path).map_or(0o444, |m| m.permissions().mode() & 0o7777);
+    let _ = fs::remove_file(&xfile);
+    let mut file = fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .mode(mode)
+        .open(&xfile)?;
+    if let Err(e) = file.write_all(data).and_then(|_| file.sync_all()) {
+        let _ = fs::remove_file(&xfile);
+        return Err(e);
+    }
+    fs::set_permissions(&xfile, fs::Permissions::from_mode(mode))?;
+    fs::rename(&xfile, path)
+}
+
+/// A line of a p-file, recording a delta being edited.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PFileEntry {
+    /// The SID retrieved for editing.
+    pub old: Sid,
+    /// The SID the new delta will get.
+    pub new: Sid,
+    pub user: String,
+    pub date: SccsDate,
+    /// The `-i` list given to `get`.
+    pub include: Option<String>,
+    /// The `-x` list given to `get`.
+    pub exclude: Option<String>,
+}
+
+impl PFileEntry {
+    /// Parse a p-file line: `old new user yy/mm/dd hh:mm:ss [-ilist] [-xlist]`.
+    pub fn parse(line: &str) -> Option<PFileEntry> {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        if fields.len() < 5 {
+            return None;
+        }
+        let mut entry = PFileEntry {
+            old: fields[0].parse().ok()?,
+            new: fields[1].parse().ok()?,
+            user: fields[2].to_string(),
+            date: SccsDate::parse(fields[3], fields[4])?,
+            include: None,
+            exclude: None,
+        };
+        for field in &fields[5..] {
+            if let Some(list) = field.strip_prefix("-i") {
+                entry.include = Some(list.to_string());
+            } else if let Some(list) = field.strip_prefix("-x") {
+                entry.exclude = Some(list.to_string());
+            } else {
+                return None;
+            }
         }
+        Some(entry)
     }
+}
 
-    result.push_str("edits\n");
-    for edit in &sccs_file.edits {
-        match edit {
-            SccsEdit::Insert(line) => result.push_str(&format!("insert {}\n", line)),
-            SccsEdit::Delete(line_no) => result.push_str(&format!("delete {}\n", line_no)),
+impl fmt::Display for PFileEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {} {} {}", self.old, self.new, self.user, self.date)?;
+        if let Some(list) = &self.include {
+            write!(f, " -i{list}")?;
+        }
+        if let Some(list) = &self.exclude {
+            write!(f, " -x{list}")?;
         }
+        Ok(())
     }
+}
+
+/// Read the p-file `path`; a missing p-file has no entries.
+pub fn read_pfile(path: &Path) -> io::Result<Vec<PFileEntry>> {
+    let data = match fs::read_to_string(path) {
+        Ok(data) => data,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+    data.lines()
+        .filter(|line| !line.is_empty())
+        .map(|line| {
+            PFileEntry::parse(line).ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("{}: invalid p-file entry", path.display()),
+                )
+            })
+        })
+        .collect()
+}
 
-    result.push_str("users\n");
-    for (user, info) in &sccs_file.user_info.users {
-        result.push_str(&format!("{} {}\n", user, info));
+/// Write the p-file `path`, removing it when there are no entries left.
+pub fn write_pfile(path: &Path, entries: &[PFileEntry]) -> io::Result<()> {
+    if entries.is_empty() {
+        return match fs::remove_file(path) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        };
     }
+    let data: String = entries.iter().map(|e| format!("{e}\n")).collect();
+    fs::write(path, data)
+}
+
+/// The `z.` lock file that serializes updates of an s-file and its
+/// p-file.  It holds the process ID and is removed when dropped.
+pub struct LockFile {
+    path: PathBuf,
+}
 
-    result.push_str(&format!(
-        "stats total_deltas:{} total_lines:{}\n",
-        sccs_file.stats.total_deltas, sccs_file.stats.total_lines
-    ));
+impl LockFile {
+    /// Take the lock for the s-file `sfile`.
+    pub fn acquire(sfile: &Path) -> io::Result<LockFile> {
+        let path = aux_file(sfile, 'z')
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "not an SCCS file"))?;
+        let mut file = fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .mode(0o444)
+            .open(&path)
+            .map_err(|e| match e.kind() {
+                io::ErrorKind::AlreadyExists => io::Error::new(
+                    io::ErrorKind::AlreadyExists,
+                    format!("lock file {} busy", path.display()),
+                ),
+                _ => e,
+            })?;
+        writeln!(file, "{}", std::process::id())?;
+        Ok(LockFile { path })
+    }
+}
 
-    result
+impl Drop for LockFile {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
 }
 
 #[cfg(test)]
 mod sccstest {
     use super::*;
 
-    #[test]
-    fn basic_sccs_file_parse() {
-        let simple = r#"
-h23005
-s 00003/00000/00013
-d D 1.2 24/07/09 19:42:04 jgarzik 2 1
-c added more data
-e
-s 00013/00000/00000
-d D 1.1 24/07/09 19:38:28 jgarzik 1 0
-c date and time created 24/07/09 19:38:28 by jgarzik
-e
-u
-U
-f e 0
-t
-T
-I 1
+    const SIMPLE: &str = "\
+\x01h23005
+\x01s 00003/00000/00013
+\x01d D 1.2 24/07/09 19:42:04 jgarzik 2 1
+\x01c added more data
+\x01e
+\x01s 00013/00000/00000
+\x01d D 1.1 24/07/09 19:38:28 jgarzik 1 0
+\x01c date and time created 24/07/09 19:38:28 by jgarzik
+\x01e
+\x01u
+\x01U
+\x01f e 0
+\x01t
+\x01T
+\x01I 1
 apple
 banana
 charlie
@@ -270,66 +1406,358 @@ juliet
 kilo
 lima
 mike
-E 1
-I 2
+\x01E 1
+\x01I 2
 november
 october
 pauly
-E 2
-"#;
+\x01E 2
+";
 
-        let sccs_file = SccsFile::from_string(simple).expect("Failed to parse SCCS file");
+    fn text(lines: &[BodyLine]) -> String {
+        lines
+            .iter()
+            .map(|l| format!("{}\n", String::from_utf8_lossy(l.text)))
+            .collect()
+    }
+
+    #[test]
+    fn basic_sccs_file_parse() {
+        let sccs_file = SccsFile::from_string(SIMPLE).expect("Failed to parse SCCS file");
 
-        // Verify header
-        assert_eq!(sccs_file.header.format_version, "h23005");
-        assert_eq!(sccs_file.header.creation_date, "s 00003/00000/00013");
-        assert_eq!(sccs_file.header.author, "");
-        assert_eq!(sccs_file.header.description, "");
+        assert_eq!(sccs_file.stored_checksum(), 23005);
+        assert!(sccs_file.checksum_valid());
 
-        // Verify deltas
         assert_eq!(sccs_file.deltas.len(), 2);
+        let d = &sccs_file.deltas[0];
+        assert_eq!(d.sid, Sid::new(1, 2));
+        assert_eq!(d.kind, DeltaType::Normal);
+        assert_eq!(d.date.date_str(), "24/07/09");
+        assert_eq!(d.date.time_str(), "19:42:04");
+        assert_eq!(d.date.year, 2024);
+        assert_eq!(d.user, "jgarzik");
+        assert_eq!((d.serial, d.pred), (2, 1));
+        assert_eq!(
+            d.stats,
+            DeltaStats {
+                inserted: 3,
+                deleted: 0,
+                unchanged: 13
+            }
+        );
+        assert_eq!(d.comments, vec!["added more data"]);
 
-        assert_eq!(sccs_file.deltas[0].sid, "1.2");
-        assert_eq!(sccs_file.deltas[0].date, "24/07/09");
-        assert_eq!(sccs_file.deltas[0].time, "19:42:04");
-        assert_eq!(sccs_file.deltas[0].author, "jgarzik");
-        assert_eq!(sccs_file.deltas[0].added_lines, 2);
-        assert_eq!(sccs_file.deltas[0].deleted_lines, 1);
-        assert_eq!(sccs_file.deltas[0].comments, "added more data\n");
-
-        assert_eq!(sccs_file.deltas[1].sid, "1.1");
-        assert_eq!(sccs_file.deltas[1].date, "24/07/09");
-        assert_eq!(sccs_file.deltas[1].time, "19:38:28");
-        assert_eq!(sccs_file.deltas[1].author, "jgarzik");
-        assert_eq!(sccs_file.deltas[1].added_lines, 1);
-        assert_eq!(sccs_file.deltas[1].deleted_lines, 0);
+        let d = &sccs_file.deltas[1];
+        assert_eq!(d.sid, Sid::new(1, 1));
+        assert_eq!((d.serial, d.pred), (1, 0));
         assert_eq!(
-            sccs_file.deltas[1].comments,
-            "date and time created 24/07/09 19:38:28 by jgarzik\n"
+            d.comments,
+            vec!["date and time created 24/07/09 19:38:28 by jgarzik"]
         );
 
-        // Verify edits
-        assert_eq!(sccs_file.edits.len(), 2);
+        assert!(sccs_file.users.is_empty());
+        assert_eq!(sccs_file.flag('e'), Some("0"));
+        assert!(sccs_file.text.is_empty());
+        assert_eq!(sccs_file.weave.len(), 20);
+        assert_eq!(sccs_file.weave[0], WeaveLine::Insert(1));
+        assert_eq!(sccs_file.weave[1], WeaveLine::Text(b"apple".to_vec()));
 
-        match &sccs_file.edits[0] {
-            SccsEdit::Insert(line) => {
-                assert_eq!(line, "I 1\napple\nbanana\ncharlie\ndelta\necho\nfoxtrot\ngolf\nhotel\nindia\njuliet\nkilo\nlima\nmike\nE 1\n");
-            }
-            _ => panic!("Unexpected edit type"),
+        let v1 = sccs_file.body_for_sid(&Sid::new(1, 1), &[], &[]).unwrap();
+        assert_eq!(v1.len(), 13);
+        assert!(v1.iter().all(|l| l.serial == 1));
+        let v2 = sccs_file.body_for_sid(&Sid::new(1, 2), &[], &[]).unwrap();
+        assert_eq!(v2.len(), 16);
+        assert_eq!(text(&v2[13..]), "november\noctober\npauly\n");
+        assert_eq!(v2[15].serial, 2);
+    }
+
+    #[test]
+    fn roundtrip_and_checksum() {
+        let sccs_file = SccsFile::from_string(SIMPLE).unwrap();
+        assert_eq!(sccs_file.to_bytes(), SIMPLE.as_bytes());
+
+        let corrupt = SIMPLE.replace("pauly", "paulx");
+        let sccs_file = SccsFile::from_string(&corrupt).unwrap();
+        assert!(!sccs_file.checksum_valid());
+        let fixed = SccsFile::from_bytes(&sccs_file.to_bytes()).unwrap();
+        assert!(fixed.checksum_valid());
+    }
+
+    #[test]
+    fn sid_parse_and_format() {
+        assert_eq!("1.2".parse::<Sid>(), Ok(Sid::new(1, 2)));
+        assert_eq!("1.2.1.3".parse::<Sid>(), Ok(Sid::branch(1, 2, 1, 3)));
+        assert_eq!("3".parse::<Sid>().unwrap().components(), 1);
+        assert!("1.0".parse::<Sid>().is_err());
+        assert!("1..2".parse::<Sid>().is_err());
+        assert!("1.2.3.4.5".parse::<Sid>().is_err());
+        assert_eq!(Sid::branch(1, 2, 1, 3).to_string(), "1.2.1.3");
+        assert_eq!(Sid::branch(1, 2, 1, 0).to_string(), "1.2.1");
+        assert!(Sid::new(1, 2) < Sid::branch(1, 2, 1, 1));
+    }
+
+    #[test]
+    fn dates() {
+        let d = SccsDate::parse("99/12/31", "23:59:59").unwrap();
+        assert_eq!(d.year, 1999);
+        assert_eq!(d.to_string(), "99/12/31 23:59:59");
+        assert_eq!(SccsDate::parse("68/01/01", "00:00:00").unwrap().year, 2068);
+        assert!(SccsDate::parse("24/13/01", "00:00:00").is_none());
+        assert!(SccsDate::parse("24/01/01", "00:00").is_none());
+    }
+
+    /// 1.1: a b c; 1.2 deletes b; 1.1.1.1 (a branch from 1.1) appends d;
+    /// 1.3 inserts x after a and includes 1.1.1.1.
+    const BRANCHED: &str = "\
+\x01h00000
+\x01s 00001/00000/00002
+\x01d D 1.3 24/07/10 10:00:00 dev 4 2
+\x01i 3
+\x01c include the branch
+\x01e
+\x01s 00001/00000/00003
+\x01d D 1.1.1.1 24/07/09 12:00:00 dev 3 1
+\x01m MR1
+\x01m MR2
+\x01c branch
+\x01e
+\x01s 00000/00001/00002
+\x01d D 1.2 24/07/09 11:00:00 dev 2 1
+\x01c drop b
+\x01e
+\x01s 00003/00000/00000
+\x01d D 1.1 24/07/09 10:00:00 dev 1 0
+\x01c initial
+\x01e
+\x01u
+dev
+\x01U
+\x01f b
+\x01f q text with  spaces
+\x01t
+description
+\x01T
+\x01I 1
+a
+\x01I 4
+x
+\x01E 4
+\x01D 2
+b
+\x01E 2
+c
+\x01I 3
+d
+\x01E 3
+\x01E 1
+";
+
+    fn branched() -> SccsFile {
+        SccsFile::from_string(BRANCHED).unwrap()
+    }
+
+    #[test]
+    fn branches_and_lists() {
+        let f = branched();
+        assert!(!f.checksum_valid());
+        assert_eq!(f.users, vec!["dev"]);
+        assert_eq!(f.flag('b'), Some(""));
+        assert_eq!(f.flag('q'), Some("text with  spaces"));
+        assert_eq!(f.text, vec!["description"]);
+
+        let branch = f.delta(&Sid::branch(1, 1, 1, 1)).unwrap();
+        assert_eq!(branch.mrs, vec!["MR1", "MR2"]);
+        assert_eq!(f.delta(&Sid::new(1, 3)).unwrap().included, vec![3]);
+        let succ: Vec<Sid> = f.successors(1).map(|d| d.sid).collect();
+        assert_eq!(succ, vec![Sid::branch(1, 1, 1, 1), Sid::new(1, 2)]);
+        assert_eq!(f.ancestors(4), vec![4, 2, 1]);
+
+        let get =
+            |sid: Sid, inc: &[Sid], exc: &[Sid]| text(&f.body_for_sid(&sid, inc, exc).unwrap());
+        assert_eq!(get(Sid::new(1, 1), &[], &[]), "a\nb\nc\n");
+        assert_eq!(get(Sid::new(1, 2), &[], &[]), "a\nc\n");
+        assert_eq!(get(Sid::branch(1, 1, 1, 1), &[], &[]), "a\nb\nc\nd\n");
+        assert_eq!(get(Sid::new(1, 3), &[], &[]), "a\nx\nc\nd\n");
+        assert_eq!(
+            get(Sid::new(1, 3), &[], &[Sid::branch(1, 1, 1, 1)]),
+            "a\nx\nc\n"
+        );
+        assert_eq!(
+            get(Sid::new(1, 3), &[], &[Sid::new(1, 2)]),
+            "a\nx\nb\nc\nd\n"
+        );
+        assert_eq!(
+            get(Sid::new(1, 2), &[Sid::branch(1, 1, 1, 1)], &[]),
+            "a\nc\nd\n"
+        );
+        assert!(f.body_for_sid(&Sid::new(2, 1), &[], &[]).is_err());
+    }
+
+    #[test]
+    fn branched_roundtrip() {
+        let f = branched();
+        let bytes = f.to_bytes();
+        let reparsed = SccsFile::from_bytes(&bytes).unwrap();
+        assert!(reparsed.checksum_valid());
+        assert_eq!(reparsed.deltas, f.deltas);
+        assert_eq!(reparsed.weave, f.weave);
+        assert_eq!(&bytes[8..], &BRANCHED.as_bytes()[8..]);
+    }
+
+    #[test]
+    fn format_errors() {
+        assert!(SccsFile::from_string("").is_err());
+        assert!(SccsFile::from_string("h12345\n").is_err());
+        let unterminated = SIMPLE.replace("\x01E 2\n", "");
+        assert!(SccsFile::from_string(&unterminated).is_err());
+        let bad_delta = SIMPLE.replace("jgarzik 2 1", "jgarzik 2");
+        assert!(SccsFile::from_string(&bad_delta).is_err());
+        let bad_ctl = SIMPLE.replace("\x01T\n", "\x01T\n\x01X 1\n");
+        assert!(SccsFile::from_string(&bad_ctl).is_err());
+    }
+
+    #[test]
+    fn cutoff_dates() {
+        let c = SccsDate::from_cutoff("24").unwrap();
+        assert_eq!(c.to_string(), "24/12/31 23:59:59");
+        let c = SccsDate::from_cutoff("24/02").unwrap();
+        assert_eq!(c.to_string(), "24/02/29 23:59:59");
+        let c = SccsDate::from_cutoff("23/02/10 10:30").unwrap();
+        assert_eq!(c.to_string(), "23/02/10 10:30:59");
+        assert!(SccsDate::from_cutoff("245").is_none());
+        assert!(SccsDate::from_cutoff("241301").is_none());
+    }
+
+    #[test]
+    fn sid_lists_and_users() {
+        let mut f = branched();
+        assert_eq!(f.sid_list("1.1-1.3").unwrap(), vec![1, 2, 4]);
+        assert_eq!(f.sid_list("1.1.1.1,1.2").unwrap(), vec![3, 2]);
+        assert!(f.sid_list("1.9").is_err());
+        assert!(f.sid_list("1.3-1.1").is_err());
+
+        assert!(f.user_allowed("dev", &[]));
+        assert!(!f.user_allowed("other", &[]));
+        f.users = vec!["100".into(), "!dev".into()];
+        assert!(!f.user_allowed("dev", &[100]));
+        assert!(f.user_allowed("other", &[100]));
+
+        f.flags.insert('l', "2,4-6".into());
+        assert!(f.release_locked(5));
+        assert!(!f.release_locked(1));
+        f.flags.insert('l', "a".into());
+        assert!(f.release_locked(1));
+    }
+
+    #[test]
+    fn pfile_entries() {
+        let line = "1.3 1.4 dev 24/07/11 09:08:07 -i1.1.1.1 -x1.2";
+        let entry = PFileEntry::parse(line).unwrap();
+        assert_eq!(entry.old, Sid::new(1, 3));
+        assert_eq!(entry.new, Sid::new(1, 4));
+        assert_eq!(entry.include.as_deref(), Some("1.1.1.1"));
+        assert_eq!(entry.exclude.as_deref(), Some("1.2"));
+        assert_eq!(entry.to_string(), line);
+        assert!(PFileEntry::parse("1.3 1.4 dev").is_none());
+
+        assert_eq!(
+            aux_file(Path::new("dir/s.foo.c"), 'p'),
+            Some(PathBuf::from("dir/p.foo.c"))
+        );
+        assert_eq!(
+            gfile_name(Path::new("dir/s.foo.c")),
+            Some(PathBuf::from("foo.c"))
+        );
+        assert_eq!(gfile_name(Path::new("dir/foo.c")), None);
+    }
+
+    fn new_delta(sid: Sid, pred: u16) -> Delta {
+        Delta {
+            sid,
+            pred,
+            user: String::from("dev"),
+            date: SccsDate::parse("24/07/11", "09:00:00").unwrap(),
+            ..Default::default()
         }
+    }
+
+    #[test]
+    fn add_delta_weave() {
+        let mut f = branched();
+        let before: Vec<String> = f
+            .deltas
+            .iter()
+            .map(|d| text(&f.body_for_sid(&d.sid, &[], &[]).unwrap()))
+            .collect();
 
-        match &sccs_file.edits[1] {
-            SccsEdit::Insert(line) => {
-                assert_eq!(line, "I 2\nnovember\noctober\npauly\nE 2\n");
+        // 1.3 is "a x c d": change x to y, drop c and append e.
+        let applied = f.applied_serials(4, &[], &[]);
+        let new_text: Vec<&[u8]> = vec![b"a", b"y", b"d", b"e"];
+        let stats = f.add_delta(new_delta(Sid::new(1, 4), 4), &applied, &new_text);
+        assert_eq!(
+            stats,
+            DeltaStats {
+                inserted: 2,
+                deleted: 2,
+                unchanged: 2
             }
-            _ => panic!("Unexpected edit type"),
+        );
+        assert_eq!(f.deltas[0].serial, 5);
+        assert_eq!(f.deltas[0].stats, stats);
+
+        let reparsed = SccsFile::from_bytes(&f.to_bytes()).unwrap();
+        assert!(reparsed.checksum_valid());
+        assert_eq!(
+            text(&reparsed.body_for_sid(&Sid::new(1, 4), &[], &[]).unwrap()),
+            "a\ny\nd\ne\n"
+        );
+        for (d, expected) in f.deltas[1..].iter().zip(&before) {
+            assert_eq!(
+                &text(&reparsed.body_for_sid(&d.sid, &[], &[]).unwrap()),
+                expected
+            );
         }
 
-        // Verify user info
-        assert_eq!(sccs_file.user_info.users.len(), 0);
+        // A branch delta from 1.2 ("a c").
+        let applied = f.applied_serials(2, &[], &[]);
+        let new_text: Vec<&[u8]> = vec![b"z", b"a", b"c"];
+        f.add_delta(new_delta(Sid::branch(1, 2, 1, 1), 2), &applied, &new_text);
+        assert_eq!(
+            text(&f.body_for_sid(&Sid::branch(1, 2, 1, 1), &[], &[]).unwrap()),
+            "z\na\nc\n"
+        );
+        assert_eq!(
+            text(&f.body_for_sid(&Sid::new(1, 4), &[], &[]).unwrap()),
+            "a\ny\nd\ne\n"
+        );
+    }
+
+    #[test]
+    fn remove_leaf_delta() {
+        let mut f = branched();
+        let before = f.clone();
+        let applied = f.applied_serials(4, &[], &[]);
+        let new_text: Vec<&[u8]> = vec![b"a", b"y", b"d", b"e"];
+        f.add_delta(new_delta(Sid::new(1, 4), 4), &applied, &new_text);
+        f.remove_delta(5);
+        assert_eq!(f.weave, before.weave);
+        assert_eq!(f.deltas[0].kind, DeltaType::Removed);
+        assert_eq!(f.deltas[1..], before.deltas[..]);
+    }
+
+    #[test]
+    fn add_initial_delta() {
+        let mut f = SccsFile::default();
+        f.add_delta(new_delta(Sid::new(1, 1), 0), &[], &[]);
+        assert_eq!(f.weave, vec![WeaveLine::Insert(1), WeaveLine::End(1)]);
 
-        // Verify stats
-        assert_eq!(sccs_file.stats.total_deltas, 2);
-        assert_eq!(sccs_file.stats.total_lines, 4);
+        let mut f = SccsFile::default();
+        let stats = f.add_delta(new_delta(Sid::new(1, 1), 0), &[], &[b"one", b"two"]);
+        assert_eq!(stats.inserted, 2);
+        assert_eq!(f.weave.len(), 4);
+        assert_eq!(
+            text(&f.body_for_sid(&Sid::new(1, 1), &[], &[]).unwrap()),
+            "one\ntwo\n"
+        );
     }
 }
@@ -0,0 +1,363 @@
+//
+// Copyright (c) 2024 Hemi Labs, Inc.
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+use std::fs;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+
+use clap::Parser;
+use gettextrs::{bind_textdomain_codeset, gettext, setlocale, textdomain, LocaleCategory};
+use plib::curuser;
+use plib::sccsfile::{self, Delta, LockFile, SccsDate, SccsFile, Sid, CTLCHAR, MAX_SID_COMPONENT};
+
+#[derive(Parser)]
+#[command(
+    version,
+    disable_help_flag = true,
+    about = gettext("admin - create and administer SCCS files")
+)]
+struct Args {
+    #[arg(long, action = clap::ArgAction::HelpLong)]
+    help: Option<bool>,
+
+    #[arg(short = 'n', help = gettext("Create a new SCCS file"))]
+    new: bool,
+
+    #[arg(short = 'i', help = gettext("Create a new SCCS file with the initial text read from the named file, or standard input"))]
+    init: Option<String>,
+
+    #[arg(short = 'r', help = gettext("Release of the initial delta"))]
+    release: Option<String>,
+
+    #[arg(short = 't', help = gettext("Descriptive text read from the named file; with no name, remove the text"))]
+    text: Option<String>,
+
+    #[arg(short = 'f', help = gettext("Set a flag, and its value"))]
+    set_flags: Vec<String>,
+
+    #[arg(short = 'd', help = gettext("Delete a flag"))]
+    delete_flags: Vec<String>,
+
+    #[arg(short = 'a', help = gettext("Add a login name or numeric group ID to the list of users who may make deltas"))]
+    add_users: Vec<String>,
+
+    #[arg(short = 'e', help = gettext("Erase a login name or group ID from the list of users who may make deltas"))]
+    erase_users: Vec<String>,
+
+    #[arg(short = 'y', help = gettext("Comment for the initial delta"))]
+    comment: Option<String>,
+
+    #[arg(short = 'm', help = gettext("Modification request numbers for the initial delta"))]
+    mrs: Option<String>,
+
+    #[arg(short = 'h', help = gettext("Check the structure and checksum of the SCCS file"))]
+    check: bool,
+
+    #[arg(short = 'z', help = gettext("Recompute the checksum of the SCCS file"))]
+    fix_checksum: bool,
+
+    #[arg(required = true, help = gettext("SCCS files"))]
+    files: Vec<PathBuf>,
+}
+
+impl Args {
+    /// Parse the command line.  `-i`, `-t` and `-y` take an optional
+    /// argument that must be attached, so given alone they are rewritten
+    /// with an empty one before `clap` sees them.
+    fn parse_custom() -> Args {
+        let args = std::env::args().map(|arg| match arg.as_str() {
+            "-i" | "-t" | "-y" => format!("{arg}="),
+            _ => arg,
+        });
+        Args::parse_from(args)
+    }
+}
+
+fn error(msg: String) -> io::Error {
+    io::Error::other(msg)
+}
+
+/// Split text into lines, rejecting lines that start with the SCCS
+/// control character.
+fn text_lines(data: &[u8]) -> io::Result<Vec<Vec<u8>>> {
+    let mut lines: Vec<Vec<u8>> = data.split(|&b| b == b'\n').map(|l| l.to_vec()).collect();
+    if data.is_empty() || data.ends_with(b"\n") {
+        lines.pop();
+    }
+    if let Some(n) = lines.iter().position(|l| l.first() == Some(&CTLCHAR)) {
+        return Err(error(format!(
+            "{} {}",
+            gettext("illegal data on line"),
+            n + 1
+        )));
+    }
+    Ok(lines)
+}
+
+/// Check a `-f` flag and split it into its letter and value.
+fn parse_flag(flag: &str) -> io::Result<(char, String)> {
+    let mut chars = flag.chars();
+    let letter = chars.next().unwrap_or(' ');
+    let value = chars.as_str().to_string();
+    let invalid = || error(format!("{} '{flag}'", gettext("invalid flag")));
+    match letter {
+        'b' | 'j' | 'n' if value.is_empty() => {}
+        'i' | 'q' | 'v' => {}
+        'm' | 't' if !value.is_empty() => {}
+        'c' | 'f' => match value.parse::<u16>() {
+            Ok(rel) if (1..=MAX_SID_COMPONENT).contains(&rel) => {}
+            _ => return Err(invalid()),
+        },
+        'd' => {
+            value.parse::<Sid>().map_err(error)?;
+        }
+        'l' => {
+            let valid = value == "a"
+                || (!value.is_empty()
+                    && value.split(',').all(|item| {
+                        item.split('-').count() <= 2
+                            && item.split('-').all(|r| r.parse::<u16>().is_ok())
+                    }));
+            if !valid {
+                return Err(invalid());
+            }
+        }
+        _ => return Err(invalid()),
+    }
+    Ok((letter, value))
+}
+
+/// Set the `-f` flags and remove the `-d` flags.  `-dl` with a list
+/// unlocks only the listed releases.
+fn update_flags(file: &mut SccsFile, args: &Args) -> io::Result<()> {
+    for flag in &args.set_flags {
+        let (letter, value) = parse_flag(flag)?;
+        file.flags.insert(letter, value);
+    }
+    for flag in &args.delete_flags {
+        let mut chars = flag.chars();
+        let letter = chars.next().unwrap_or(' ');
+        let list = chars.as_str();
+        if letter == 'l' && !list.is_empty() && list != "a" {
+            if let Some(locked) = file.flags.get_mut(&'l') {
+                let unlock: Vec<&str> = list.split(',').collect();
+                let kept: Vec<&str> = locked.split(',').filter(|r| !unlock.contains(r)).collect();
+                *locked = kept.join(",");
+                if locked.is_empty() {
+                    file.flags.remove(&'l');
+                }
+            }
+        } else if letter.is_ascii_lowercase() {
+            file.flags.remove(&letter);
+        } else {
+            return Err(error(format!("{} '{flag}'", gettext("invalid flag"))));
+        }
+    }
+    Ok(())
+}
+
+fn update_users(file: &mut SccsFile, args: &Args) {
+    for user in &args.add_users {
+        if !file.users.contains(user) {
+            file.users.push(user.clone());
+        }
+    }
+    file.users.retain(|u| !args.erase_users.contains(u));
+}
+
+/// The descriptive text named by `-t`, read once for all files.
+fn read_text(name: &str) -> io::Result<Vec<String>> {
+    let data = fs::read(name)?;
+    let lines = text_lines(&data)?;
+    Ok(lines
+        .iter()
+        .map(|l| String::from_utf8_lossy(l).into_owned())
+        .collect())
+}
+
+fn create_file(
+    args: &Args,
+    path: &Path,
+    initial: &[Vec<u8>],
+    text: Option<&[String]>,
+) -> io::Result<()> {
+    if sccsfile::gfile_name(path).is_none() {
+        return Err(error(gettext("not an SCCS file")));
+    }
+    if fs::symlink_metadata(path).is_ok() {
+        return Err(error(gettext("file exists")));
+    }
+    let rel = match &args.release {
+        Some(r) => match r.parse::<u16>() {
+            Ok(rel) if (1..=MAX_SID_COMPONENT).contains(&rel) => rel,
+            _ => return Err(error(format!("{} '{r}'", gettext("invalid release")))),
+        },
+        None => 1,
+    };
+
+    let mut file = SccsFile::default();
+    update_flags(&mut file, args)?;
+    update_users(&mut file, args);
+    if let Some(text) = text {
+        file.text = text.to_vec();
+    }
+
+    let mrs: Vec<String> = match &args.mrs {
+        Some(_) if file.flag('v').is_none() => {
+            return Err(error(gettext("MRs not allowed without the v flag")))
+        }
+        Some(list) => list
+            .split([' ', ','])
+            .filter(|m| !m.is_empty())
+            .map(String::from)
+            .collect(),
+        None if file.flag('v').is_some() => return Err(error(gettext("MRs required"))),
+        None => Vec::new(),
+    };
+
+    let user = curuser::real_user_name();
+    let date = SccsDate::now();
+    let comments = match &args.comment {
+        Some(comment) if comment.is_empty() => Vec::new(),
+        Some(comment) => comment.lines().map(String::from).collect(),
+        None => vec![format!(
+            "date and time created {} {} by {user}",
+            date.date_str(),
+            date.time_str()
+        )],
+    };
+
+    if !initial.iter().any(|l| sccsfile::has_id_keywords(l)) {
+        if file.flag('i').is_some() {
+            return Err(error(gettext("No id keywords (cm6)")));
+        }
+        eprintln!(
+            "admin: {}: {}",
+            path.display(),
+            gettext("No id keywords (cm7)")
+        );
+    }
+
+    let delta = Delta {
+        sid: Sid::new(rel, 1),
+        date,
+        user,
+        mrs,
+        comments,
+        ..Default::default()
+    };
+    let lines: Vec<&[u8]> = initial.iter().map(|l| l.as_slice()).collect();
+    file.add_delta(delta, &[], &lines);
+    file.write(path)
+}
+
+fn update_file(args: &Args, path: &Path, text: Option<&[String]>) -> io::Result<()> {
+    if sccsfile::gfile_name(path).is_none() {
+        return Err(error(gettext("not an SCCS file")));
+    }
+    let _lock = LockFile::acquire(path)?;
+
+    if args.fix_checksum {
+        let data = fs::read(path)?;
+        SccsFile::from_bytes(&data)?;
+        let rest = match data.iter().position(|&b| b == b'\n') {
+            Some(pos) => &data[pos + 1..],
+            None => &[],
+        };
+        let mut out = format!("\x01h{:05}\n", sccsfile::checksum(rest)).into_bytes();
+        out.extend_from_slice(rest);
+        return sccsfile::write_sfile(path, &out);
+    }
+
+    let mut file = SccsFile::read(path)?;
+    if !file.checksum_valid() {
+        return Err(error(gettext("corrupted file")));
+    }
+    update_flags(&mut file, args)?;
+    update_users(&mut file, args);
+    match text {
+        Some(text) => file.text = text.to_vec(),
+        None if args.text.is_some() => file.text.clear(),
+        None => {}
+    }
+    file.write(path)
+}
+
+fn check_file(path: &Path) -> io::Result<()> {
+    let file = SccsFile::read(path)?;
+    if !file.checksum_valid() {
+        return Err(error(gettext("corrupted file")));
+    }
+    Ok(())
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    setlocale(LocaleCategory::LcAll, "");
+    textdomain("posixutils-rs")?;
+    bind_textdomain_codeset("posixutils-rs", "UTF-8")?;
+
+    let args = Args::parse_custom();
+
+    let creating = args.new || args.init.is_some();
+    if args.init.is_some() && args.files.len() > 1 {
+        eprintln!("admin: {}", gettext("-i allows only one SCCS file"));
+        std::process::exit(1);
+    }
+    if !creating && (args.release.is_some() || args.comment.is_some() || args.mrs.is_some()) {
+        eprintln!(
+            "admin: {}",
+            gettext("-r, -y and -m are only allowed with -i or -n")
+        );
+        std::process::exit(1);
+    }
+
+    let initial = match args.init.as_deref() {
+        Some("") => {
+            let mut data = Vec::new();
+            io::stdin().lock().read_to_end(&mut data)?;
+            text_lines(&data)
+        }
+        Some(name) => fs::read(name).and_then(|data| text_lines(&data)),
+        None => Ok(Vec::new()),
+    };
+    let text = match args.text.as_deref() {
+        Some("") | None => Ok(None),
+        Some(name) => read_text(name).map(Some),
+    };
+    let (initial, text) = match (initial, text) {
+        (Ok(initial), Ok(text)) => (initial, text),
+        (Err(e), _) | (_, Err(e)) => {
+            eprintln!("admin: {e}");
+            std::process::exit(1);
+        }
+    };
+
+    let mut exit_code = 0;
+
+    let files = if creating {
+        args.files.clone()
+    } else {
+        sccsfile::sfile_operands(&args.files)?
+    };
+    for path in &files {
+        let result = if args.check {
+            check_file(path)
+        } else if creating {
+            create_file(&args, path, &initial, text.as_deref())
+        } else {
+            update_file(&args, path, text.as_deref())
+        };
+        if let Err(e) = result {
+            exit_code = 1;
+            eprintln!("admin: {}: {}", path.display(), e);
+        }
+    }
+
+    std::process::exit(exit_code)
+}
@@ -0,0 +1,339 @@
+//
+// Copyright (c) 2024 Hemi Labs, Inc.
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+use std::fs;
+use std::io::{self, BufRead, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use clap::Parser;
+use gettextrs::{bind_textdomain_codeset, gettext, setlocale, textdomain, LocaleCategory};
+use plib::curuser;
+use plib::diff::{self, Edit};
+use plib::sccsfile::{self, Delta, DeltaType, LockFile, SccsDate, SccsFile, Sid, CTLCHAR};
+
+#[derive(Parser)]
+#[command(version, about = gettext("delta - make a delta (change) to an SCCS file"))]
+struct Args {
+    #[arg(short = 'r', help = gettext("SID of the edited version, when the user has several"))]
+    sid: Option<String>,
+
+    #[arg(short = 's', help = gettext("Suppress informative messages"))]
+    silent: bool,
+
+    #[arg(short = 'n', help = gettext("Keep the edited g-file"))]
+    keep: bool,
+
+    #[arg(short = 'g', help = gettext("List of deltas to ignore"))]
+    ignore: Option<String>,
+
+    #[arg(short = 'p', help = gettext("Write the differences to standard output"))]
+    print_diff: bool,
+
+    #[arg(short = 'm', help = gettext("Modification request numbers"))]
+    mrs: Option<String>,
+
+    #[arg(short = 'y', help = gettext("Comment describing the change"))]
+    comment: Option<String>,
+
+    #[arg(required = true, help = gettext("SCCS files"))]
+    files: Vec<PathBuf>,
+}
+
+impl Args {
+    /// Parse the command line.  `-y` takes an optional argument that must
+    /// be attached, so given alone it is rewritten with an empty one.
+    fn parse_custom() -> Args {
+        let args = std::env::args().map(|arg| match arg.as_str() {
+            "-y" => String::from("-y="),
+            _ => arg,
+        });
+        Args::parse_from(args)
+    }
+}
+
+fn error(msg: String) -> io::Error {
+    io::Error::other(msg)
+}
+
+/// Read a response from standard input, prompting with `prompt` when it is
+/// a terminal.  A line ending in a backslash continues on the next line.
+fn prompt_lines(prompt: &str) -> io::Result<Vec<String>> {
+    let stdin = io::stdin();
+    if stdin.is_terminal() {
+        print!("{prompt} ");
+        io::stdout().flush()?;
+    }
+    let mut lines = Vec::new();
+    for line in stdin.lock().lines() {
+        let line = line?;
+        match line.strip_suffix('\\') {
+            Some(partial) => lines.push(partial.to_string()),
+            None => {
+                lines.push(line);
+                break;
+            }
+        }
+    }
+    Ok(lines)
+}
+
+/// The comments and MRs for the delta, from the command line or asked
+/// for once for all files.
+struct Commentary {
+    comments: Option<Vec<String>>,
+    mrs: Option<Vec<String>>,
+}
+
+fn split_mrs(lines: &[String]) -> Vec<String> {
+    lines
+        .iter()
+        .flat_map(|l| l.split([' ', '\t', ',']))
+        .filter(|m| !m.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+impl Commentary {
+    fn comments(&mut self) -> io::Result<Vec<String>> {
+        if self.comments.is_none() {
+            let lines = prompt_lines("comments?")?;
+            self.comments = Some(lines.into_iter().filter(|l| !l.is_empty()).collect());
+        }
+        Ok(self.comments.clone().unwrap())
+    }
+
+    fn mrs(&mut self) -> io::Result<Vec<String>> {
+        if self.mrs.is_none() {
+            self.mrs = Some(split_mrs(&prompt_lines("MRs?")?));
+        }
+        Ok(self.mrs.clone().unwrap())
+    }
+}
+
+/// Split the g-file into lines, rejecting lines that start with the SCCS
+/// control character.
+fn gfile_lines(data: &[u8]) -> io::Result<Vec<&[u8]>> {
+    let mut lines: Vec<&[u8]> = data.split(|&b| b == b'\n').collect();
+    if data.is_empty() || data.ends_with(b"\n") {
+        lines.pop();
+    }
+    if let Some(n) = lines.iter().position(|l| l.first() == Some(&CTLCHAR)) {
+        return Err(error(format!(
+            "{} {}",
+            gettext("illegal data on line"),
+            n + 1
+        )));
+    }
+    Ok(lines)
+}
+
+/// Write the changes from `old` to `new` in the format of `diff`.
+fn print_differences(old: &[&[u8]], new: &[&[u8]]) -> io::Result<()> {
+    fn range(start: usize, end: usize) -> String {
+        if end - start > 1 {
+            format!("{},{}", start + 1, end)
+        } else {
+            format!("{end}")
+        }
+    }
+
+    let mut out = io::stdout().lock();
+    let edits = diff::edit_script(old, new);
+    let (mut old_pos, mut new_pos) = (0, 0);
+    let mut i = 0;
+    while i < edits.len() {
+        if let Edit::Keep { .. } = edits[i] {
+            old_pos += 1;
+            new_pos += 1;
+            i += 1;
+            continue;
+        }
+        let (old_start, new_start) = (old_pos, new_pos);
+        while let Some(edit) = edits.get(i) {
+            match edit {
+                Edit::Delete { .. } => old_pos += 1,
+                Edit::Insert { .. } => new_pos += 1,
+                Edit::Keep { .. } => break,
+            }
+            i += 1;
+        }
+        let deleted = &old[old_start..old_pos];
+        let inserted = &new[new_start..new_pos];
+        match (deleted.is_empty(), inserted.is_empty()) {
+            (true, _) => writeln!(out, "{}a{}", old_start, range(new_start, new_pos))?,
+            (_, true) => writeln!(out, "{}d{}", range(old_start, old_pos), new_start)?,
+            _ => writeln!(
+                out,
+                "{}c{}",
+                range(old_start, old_pos),
+                range(new_start, new_pos)
+            )?,
+        }
+        for line in deleted {
+            out.write_all(b"< ")?;
+            out.write_all(line)?;
+            out.write_all(b"\n")?;
+        }
+        if !deleted.is_empty() && !inserted.is_empty() {
+            writeln!(out, "---")?;
+        }
+        for line in inserted {
+            out.write_all(b"> ")?;
+            out.write_all(line)?;
+            out.write_all(b"\n")?;
+        }
+    }
+    Ok(())
+}
+
+/// Run the MR validation program named by the `v` flag.
+fn validate_mrs(program: &str, module: &str, mrs: &[String]) -> io::Result<()> {
+    let status = Command::new(program).arg(module).args(mrs).status()?;
+    if !status.success() {
+        return Err(error(gettext("invalid MRs")));
+    }
+    Ok(())
+}
+
+fn delta_file(args: &Args, commentary: &mut Commentary, path: &Path) -> io::Result<()> {
+    let gfile = sccsfile::gfile_name(path).ok_or_else(|| error(gettext("not an SCCS file")))?;
+    let pfile = sccsfile::aux_file(path, 'p').unwrap();
+    let _lock = LockFile::acquire(path)?;
+
+    let user = curuser::real_user_name();
+    let mut entries = sccsfile::read_pfile(&pfile)?;
+    let requested = match &args.sid {
+        Some(sid) => Some(sid.parse::<Sid>().map_err(error)?),
+        None => None,
+    };
+    let candidates: Vec<usize> = (0..entries.len())
+        .filter(|&i| entries[i].user == user)
+        .filter(|&i| requested.map_or(true, |r| entries[i].old == r || entries[i].new == r))
+        .collect();
+    let pos = match candidates[..] {
+        [] => return Err(error(gettext("no outstanding deltas"))),
+        [pos] => pos,
+        _ => return Err(error(gettext("missing -r argument"))),
+    };
+    let entry = entries[pos].clone();
+
+    let mut file = SccsFile::read(path)?;
+    if !file.checksum_valid() {
+        return Err(error(gettext("corrupted file")));
+    }
+    if !file.user_allowed(&user, &curuser::group_ids()) {
+        return Err(error(format!(
+            "{} {user} {}",
+            gettext("user"),
+            gettext("not authorized to make deltas")
+        )));
+    }
+    if file.live_delta(&entry.new).is_some() {
+        return Err(error(format!(
+            "{} {}",
+            gettext("SID already exists:"),
+            entry.new
+        )));
+    }
+    let old_serial = file
+        .delta(&entry.old)
+        .filter(|d| d.kind == DeltaType::Normal)
+        .map(|d| d.serial)
+        .ok_or_else(|| error(format!("{} {}", gettext("nonexistent SID"), entry.old)))?;
+    let list = |list: &Option<String>| match list {
+        Some(list) => file.sid_list(list),
+        None => Ok(Vec::new()),
+    };
+    let included = list(&entry.include)?;
+    let excluded = list(&entry.exclude)?;
+    let ignored = list(&args.ignore)?;
+
+    let data = fs::read(&gfile)?;
+    let new_text = gfile_lines(&data)?;
+
+    let mrs = match (file.flag('v'), &args.mrs) {
+        (None, Some(_)) => return Err(error(gettext("MRs not allowed without the v flag"))),
+        (None, None) => Vec::new(),
+        (Some(_), Some(list)) => split_mrs(std::slice::from_ref(list)),
+        (Some(_), None) => commentary.mrs()?,
+    };
+    if let Some(program) = file.flag('v').filter(|p| !p.is_empty()) {
+        validate_mrs(program, &gfile.to_string_lossy(), &mrs)?;
+    }
+    let comments = match &args.comment {
+        Some(comment) => comment.lines().map(String::from).collect(),
+        None => commentary.comments()?,
+    };
+
+    let applied = file.applied_serials(old_serial, &included, &excluded);
+    if args.print_diff {
+        let old_text: Vec<&[u8]> = file.body(&applied).iter().map(|l| l.text).collect();
+        print_differences(&old_text, &new_text)?;
+    }
+
+    let delta = Delta {
+        sid: entry.new,
+        date: SccsDate::now(),
+        user,
+        pred: old_serial,
+        included,
+        excluded,
+        ignored,
+        mrs,
+        comments,
+        ..Default::default()
+    };
+    let stats = file.add_delta(delta, &applied, &new_text);
+    file.write(path)?;
+
+    entries.remove(pos);
+    sccsfile::write_pfile(&pfile, &entries)?;
+    if !args.keep {
+        fs::remove_file(&gfile)?;
+    }
+
+    if !args.silent {
+        println!("{}", entry.new);
+        println!("{} {}", stats.inserted, gettext("inserted"));
+        println!("{} {}", stats.deleted, gettext("deleted"));
+        println!("{} {}", stats.unchanged, gettext("unchanged"));
+    }
+    Ok(())
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    setlocale(LocaleCategory::LcAll, "");
+    textdomain("posixutils-rs")?;
+    bind_textdomain_codeset("posixutils-rs", "UTF-8")?;
+
+    let args = Args::parse_custom();
+
+    let mut commentary = Commentary {
+        comments: args
+            .comment
+            .as_ref()
+            .map(|c| c.lines().map(String::from).collect()),
+        mrs: args
+            .mrs
+            .as_ref()
+            .map(|m| split_mrs(std::slice::from_ref(m))),
+    };
+
+    let mut exit_code = 0;
+
+    for path in sccsfile::sfile_operands(&args.files)? {
+        if let Err(e) = delta_file(&args, &mut commentary, &path) {
+            exit_code = 1;
+            eprintln!("delta: {}: {}", path.display(), e);
+        }
+    }
+
+    std::process::exit(exit_code)
+}
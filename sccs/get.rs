@@ -0,0 +1,528 @@
+//
+// Copyright (c) 2024 Hemi Labs, Inc.
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+use std::fs::{self, OpenOptions};
+use std::io::{self, BufWriter, Write};
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::{Path, PathBuf};
+
+use clap::Parser;
+use gettextrs::{bind_textdomain_codeset, gettext, setlocale, textdomain, LocaleCategory};
+use plib::curuser;
+use plib::sccsfile::{
+    self, Delta, DeltaType, LockFile, PFileEntry, SccsDate, SccsFile, Sid, MAX_SID_COMPONENT,
+};
+
+#[derive(Parser)]
+#[command(version, about = gettext("get - get a version of an SCCS file"))]
+struct Args {
+    #[arg(short = 'r', help = gettext("SCCS identification string (SID) of the version to retrieve"))]
+    sid: Option<String>,
+
+    #[arg(short = 'c', help = gettext("Ignore deltas created after the cutoff date-time [YY[MM[DD[HH[MM[SS]]]]]]"))]
+    cutoff: Option<String>,
+
+    #[arg(short = 'e', help = gettext("Retrieve the version for editing by a later delta"))]
+    edit: bool,
+
+    #[arg(short = 'b', help = gettext("With -e, create the new delta on a new branch"))]
+    branch: bool,
+
+    #[arg(short = 'i', help = gettext("List of deltas to include"))]
+    include: Option<String>,
+
+    #[arg(short = 'x', help = gettext("List of deltas to exclude"))]
+    exclude: Option<String>,
+
+    #[arg(short = 'k', help = gettext("Do not expand ID keywords"))]
+    no_keywords: bool,
+
+    #[arg(short = 'l', help = gettext("Write a delta summary to the l-file"))]
+    lfile: bool,
+
+    #[arg(short = 'L', help = gettext("Write a delta summary to standard output"))]
+    summary: bool,
+
+    #[arg(short = 'm', help = gettext("Precede each line with the SID of the delta that inserted it"))]
+    sid_prefix: bool,
+
+    #[arg(short = 'n', help = gettext("Precede each line with the %M% keyword value"))]
+    module_prefix: bool,
+
+    #[arg(short = 'p', help = gettext("Write the text to standard output"))]
+    stdout: bool,
+
+    #[arg(short = 's', help = gettext("Suppress informative messages"))]
+    silent: bool,
+
+    #[arg(short = 'g', help = gettext("Do not retrieve the text"))]
+    no_text: bool,
+
+    #[arg(short = 't', help = gettext("Retrieve the most recently created delta of the release"))]
+    top: bool,
+
+    #[arg(required = true, help = gettext("SCCS files"))]
+    files: Vec<PathBuf>,
+}
+
+fn error(msg: String) -> io::Error {
+    io::Error::other(msg)
+}
+
+/// The delta to retrieve for the SID `requested`, which may name only a
+/// release, a release and level, or a branch.
+fn select_delta(file: &SccsFile, requested: Option<Sid>, top: bool) -> io::Result<&Delta> {
+    let deltas = || file.deltas.iter().filter(|d| d.kind == DeltaType::Normal);
+    let highest_trunk = |pred: &dyn Fn(&Delta) -> bool| {
+        deltas()
+            .filter(|d| !d.sid.is_branch() && pred(d))
+            .max_by_key(|d| d.sid)
+    };
+    let newest =
+        |pred: &dyn Fn(&Delta) -> bool| deltas().filter(|d| pred(d)).max_by_key(|d| d.serial);
+
+    let found = match requested {
+        None if top => newest(&|_| true),
+        None => highest_trunk(&|_| true),
+        Some(r) => match r.components() {
+            1 if top => newest(&|d| d.sid.rel == r.rel),
+            // A release that does not exist selects the highest delta of
+            // the nearest lower release.
+            1 => highest_trunk(&|d| d.sid.rel == r.rel)
+                .or_else(|| highest_trunk(&|d| d.sid.rel < r.rel)),
+            2 if top => newest(&|d| d.sid.trunk() == r),
+            3 => deltas()
+                .filter(|d| d.sid.is_branch() && Sid { seq: 0, ..d.sid } == r)
+                .max_by_key(|d| d.sid.seq),
+            _ => deltas().find(|d| d.sid == r),
+        },
+    };
+    found.ok_or_else(|| match requested {
+        Some(r) => error(format!("{} {r}", gettext("nonexistent SID"))),
+        None => error(gettext("no deltas")),
+    })
+}
+
+/// The SID of the delta that will be made from the retrieved delta `got`.
+fn new_delta_sid(
+    file: &SccsFile,
+    got: &Delta,
+    requested: Option<Sid>,
+    branch: bool,
+    pending: &[PFileEntry],
+) -> Sid {
+    let taken = |sid: Sid| file.live_delta(&sid).is_some() || pending.iter().any(|p| p.new == sid);
+    let trunk = || {
+        file.deltas
+            .iter()
+            .filter(|d| !d.sid.is_branch() && d.kind == DeltaType::Normal)
+    };
+    let max_rel = trunk().map(|d| d.sid.rel).max().unwrap_or(0);
+    let sid = got.sid;
+
+    if let Some(r) = requested {
+        if r.components() == 1 && r.rel > max_rel {
+            return Sid::new(r.rel, 1);
+        }
+    }
+
+    if !(branch && file.flag('b').is_some()) {
+        let (next, at_tip) = if sid.is_branch() {
+            let next = Sid {
+                seq: sid.seq + 1,
+                ..sid
+            };
+            let later = file.deltas.iter().any(|d| {
+                d.kind == DeltaType::Normal
+                    && d.sid.is_branch()
+                    && Sid { seq: 0, ..d.sid } == Sid { seq: 0, ..sid }
+                    && d.sid.seq > sid.seq
+            });
+            (next, !later)
+        } else {
+            let next = Sid::new(sid.rel, sid.lev + 1);
+            (next, !trunk().any(|d| d.sid > sid))
+        };
+        if at_tip && !taken(next) {
+            return next;
+        }
+    }
+
+    let max_br = file
+        .deltas
+        .iter()
+        .map(|d| d.sid)
+        .chain(pending.iter().map(|p| p.new))
+        .filter(|s| s.trunk() == sid.trunk())
+        .map(|s| s.br)
+        .max()
+        .unwrap_or(0);
+    Sid::branch(sid.rel, sid.lev, max_br + 1, 1)
+}
+
+/// Check that the user may make a delta `new` from `got`.
+fn check_edit(file: &SccsFile, got: &Delta, new: Sid, pending: &[PFileEntry]) -> io::Result<()> {
+    let user = curuser::real_user_name();
+    if !file.user_allowed(&user, &curuser::group_ids()) {
+        return Err(error(format!(
+            "{} {user} {}",
+            gettext("user"),
+            gettext("not authorized to make deltas")
+        )));
+    }
+    if file.release_locked(new.rel) {
+        return Err(error(format!(
+            "{} {} {}",
+            gettext("release"),
+            new.rel,
+            gettext("locked against editing")
+        )));
+    }
+    let limit = |flag: char, default: u16| {
+        file.flag(flag)
+            .and_then(|v| v.trim().parse::<u16>().ok())
+            .unwrap_or(default)
+    };
+    if new.rel < limit('f', 1) || new.rel > limit('c', MAX_SID_COMPONENT) {
+        return Err(error(format!(
+            "{} {} {}",
+            gettext("release"),
+            new.rel,
+            gettext("is outside the floor and ceiling")
+        )));
+    }
+    if file.flag('j').is_none() {
+        if let Some(entry) = pending.iter().find(|p| p.old == got.sid) {
+            return Err(error(format!("{}: `{entry}'", gettext("being edited"))));
+        }
+    }
+    Ok(())
+}
+
+/// Values of the ID keywords for the retrieved version.
+struct Keywords {
+    module: String,
+    sid: Sid,
+    sfile: String,
+    full_path: String,
+    now: SccsDate,
+    newest: SccsDate,
+    module_type: String,
+    q_flag: String,
+}
+
+fn month_day_year(date: &SccsDate) -> String {
+    format!("{:02}/{:02}/{:02}", date.month, date.day, date.year % 100)
+}
+
+impl Keywords {
+    fn value(&self, key: u8, lineno: usize) -> Option<String> {
+        let sid = &self.sid;
+        Some(match key {
+            b'M' => self.module.clone(),
+            b'I' => sid.to_string(),
+            b'R' => sid.rel.to_string(),
+            b'L' => sid.lev.to_string(),
+            b'B' => sid.br.to_string(),
+            b'S' => sid.seq.to_string(),
+            b'D' => self.now.date_str(),
+            b'H' => month_day_year(&self.now),
+            b'T' => self.now.time_str(),
+            b'E' => self.newest.date_str(),
+            b'G' => month_day_year(&self.newest),
+            b'U' => self.newest.time_str(),
+            b'Y' => self.module_type.clone(),
+            b'F' => self.sfile.clone(),
+            b'P' => self.full_path.clone(),
+            b'Q' => self.q_flag.clone(),
+            b'C' => lineno.to_string(),
+            b'Z' => String::from("@(#)"),
+            b'W' => format!("@(#){}\t{}", self.module, sid),
+            b'A' => format!("@(#){} {} {}@(#)", self.module_type, self.module, sid),
+            _ => return None,
+        })
+    }
+
+    /// Append `line` to `out` with its keywords expanded; returns whether
+    /// any keyword was found.
+    fn expand(&self, line: &[u8], lineno: usize, out: &mut Vec<u8>) -> bool {
+        let mut found = false;
+        let mut i = 0;
+        while i < line.len() {
+            if line[i] == b'%' && line.get(i + 2) == Some(&b'%') {
+                if let Some(value) = self.value(line[i + 1], lineno) {
+                    out.extend_from_slice(value.as_bytes());
+                    found = true;
+                    i += 3;
+                    continue;
+                }
+            }
+            out.push(line[i]);
+            i += 1;
+        }
+        found
+    }
+}
+
+/// The delta summary written by `-l` and `-L`.
+fn delta_summary(
+    file: &SccsFile,
+    applied: &[bool],
+    include: &[u16],
+    exclude: &[u16],
+    cutoff: Option<SccsDate>,
+) -> String {
+    let is_applied = |s: u16| applied.get(usize::from(s)).copied().unwrap_or(false);
+    let mut out = String::new();
+    for d in file.deltas.iter().filter(|d| d.kind == DeltaType::Normal) {
+        let ignored = file
+            .deltas
+            .iter()
+            .any(|o| is_applied(o.serial) && o.ignored.contains(&d.serial));
+        let reason = if include.contains(&d.serial) {
+            'I'
+        } else if exclude.contains(&d.serial) {
+            'X'
+        } else if cutoff.is_some_and(|c| d.date > c) {
+            'C'
+        } else {
+            ' '
+        };
+        out.push(if is_applied(d.serial) { ' ' } else { '*' });
+        out.push(if is_applied(d.serial) || ignored {
+            ' '
+        } else {
+            '*'
+        });
+        out.push(reason);
+        out.push_str(&format!(" {}\t{} {}\n", d.sid, d.date, d.user));
+        for line in d.mrs.iter().chain(&d.comments) {
+            out.push_str(&format!("\t{line}\n"));
+        }
+        out.push('\n');
+    }
+    out
+}
+
+/// Create the g-file, refusing to overwrite a writable one.
+fn create_gfile(path: &Path, edit: bool) -> io::Result<Box<dyn Write>> {
+    if let Ok(meta) = fs::symlink_metadata(path) {
+        if !meta.permissions().readonly() {
+            return Err(error(format!(
+                "{} `{}' {}",
+                gettext("writable"),
+                path.display(),
+                gettext("exists")
+            )));
+        }
+        fs::remove_file(path)?;
+    }
+    let file = OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .mode(if edit { 0o644 } else { 0o444 })
+        .open(path)?;
+    Ok(Box::new(BufWriter::new(file)))
+}
+
+fn get_file(args: &Args, path: &Path) -> io::Result<()> {
+    let gfile = sccsfile::gfile_name(path).ok_or_else(|| error(gettext("not an SCCS file")))?;
+    let pfile = sccsfile::aux_file(path, 'p').unwrap();
+    let file = SccsFile::read(path)?;
+    if !file.checksum_valid() {
+        return Err(error(gettext("corrupted file")));
+    }
+
+    let requested = match args.sid.as_deref().or(file.flag('d')) {
+        Some(sid) => Some(sid.trim().parse::<Sid>().map_err(error)?),
+        None => None,
+    };
+    let cutoff = match &args.cutoff {
+        Some(c) => Some(
+            SccsDate::from_cutoff(c)
+                .ok_or_else(|| error(format!("{} {c}", gettext("invalid cutoff date"))))?,
+        ),
+        None => None,
+    };
+
+    let mut got = select_delta(&file, requested, args.top)?;
+    if let Some(cutoff) = cutoff {
+        while got.date > cutoff {
+            got = file
+                .delta_by_serial(got.pred)
+                .ok_or_else(|| error(gettext("no deltas before the cutoff date")))?;
+        }
+    }
+
+    let lock = if args.edit {
+        Some(LockFile::acquire(path)?)
+    } else {
+        None
+    };
+    let pending = sccsfile::read_pfile(&pfile)?;
+    let new_sid = if args.edit {
+        let new = new_delta_sid(&file, got, requested, args.branch, &pending);
+        check_edit(&file, got, new, &pending)?;
+        Some(new)
+    } else {
+        None
+    };
+
+    let include = match &args.include {
+        Some(list) => file.sid_list(list)?,
+        None => Vec::new(),
+    };
+    let exclude = match &args.exclude {
+        Some(list) => file.sid_list(list)?,
+        None => Vec::new(),
+    };
+    if include.iter().any(|s| exclude.contains(s)) {
+        return Err(error(gettext("a delta is both included and excluded")));
+    }
+    let applied = file.applied_serials(got.serial, &include, &exclude);
+
+    let module = file
+        .flag('m')
+        .map(String::from)
+        .unwrap_or_else(|| gfile.to_string_lossy().into_owned());
+    let mut nlines = 0;
+    if !args.no_text {
+        let keywords = Keywords {
+            module: module.clone(),
+            sid: got.sid,
+            sfile: path.to_string_lossy().into_owned(),
+            full_path: std::path::absolute(path)
+                .unwrap_or_else(|_| path.to_path_buf())
+                .to_string_lossy()
+                .into_owned(),
+            now: SccsDate::now(),
+            newest: file
+                .deltas
+                .iter()
+                .filter(|d| applied.get(usize::from(d.serial)) == Some(&true))
+                .map(|d| d.date)
+                .max()
+                .unwrap_or(got.date),
+            module_type: file.flag('t').unwrap_or_default().to_string(),
+            q_flag: file.flag('q').unwrap_or_default().to_string(),
+        };
+        let expand = !args.edit && !args.no_keywords;
+
+        let mut text = Vec::new();
+        let mut found = false;
+        for line in file.body(&applied) {
+            nlines += 1;
+            if args.module_prefix {
+                text.extend_from_slice(module.as_bytes());
+                text.push(b'\t');
+            }
+            if args.sid_prefix {
+                if let Some(d) = file.delta_by_serial(line.serial) {
+                    text.extend_from_slice(format!("{}\t", d.sid).as_bytes());
+                }
+            }
+            if expand {
+                found |= keywords.expand(line.text, nlines, &mut text);
+            } else {
+                text.extend_from_slice(line.text);
+            }
+            text.push(b'\n');
+        }
+        if expand && !found {
+            if file.flag('i').is_some() {
+                return Err(error(gettext("No id keywords (cm6)")));
+            }
+            if !args.silent {
+                eprintln!(
+                    "get: {}: {}",
+                    path.display(),
+                    gettext("No id keywords (cm7)")
+                );
+            }
+        }
+
+        let mut out: Box<dyn Write> = if args.stdout {
+            Box::new(io::stdout().lock())
+        } else {
+            create_gfile(&gfile, args.edit)?
+        };
+        out.write_all(&text)?;
+        out.flush()?;
+    }
+
+    if let Some(new) = new_sid {
+        let mut entries = pending;
+        entries.push(PFileEntry {
+            old: got.sid,
+            new,
+            user: curuser::real_user_name(),
+            date: SccsDate::now(),
+            include: args.include.clone(),
+            exclude: args.exclude.clone(),
+        });
+        sccsfile::write_pfile(&pfile, &entries)?;
+    }
+    drop(lock);
+
+    if args.lfile || args.summary {
+        let summary = delta_summary(&file, &applied, &include, &exclude, cutoff);
+        if args.summary {
+            print!("{summary}");
+        } else {
+            fs::write(format!("l.{}", gfile.display()), summary)?;
+        }
+    }
+
+    if !args.silent {
+        let sid_name = |s: &u16| file.delta_by_serial(*s).map(|d| d.sid.to_string());
+        let mut msg = format!("{}\n", got.sid);
+        for (title, list) in [
+            (gettext("Included:"), &include),
+            (gettext("Excluded:"), &exclude),
+        ] {
+            if !list.is_empty() {
+                msg.push_str(&format!("{title}\n"));
+                for sid in list.iter().filter_map(sid_name) {
+                    msg.push_str(&format!("{sid}\n"));
+                }
+            }
+        }
+        if let Some(new) = new_sid {
+            msg.push_str(&format!("{} {new}\n", gettext("new delta")));
+        }
+        if !args.no_text {
+            msg.push_str(&format!("{nlines} {}\n", gettext("lines")));
+        }
+        if args.stdout {
+            eprint!("{msg}");
+        } else {
+            print!("{msg}");
+        }
+    }
+    Ok(())
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    setlocale(LocaleCategory::LcAll, "");
+    textdomain("posixutils-rs")?;
+    bind_textdomain_codeset("posixutils-rs", "UTF-8")?;
+
+    let args = Args::parse();
+
+    let mut exit_code = 0;
+
+    for path in sccsfile::sfile_operands(&args.files)? {
+        if let Err(e) = get_file(&args, &path) {
+            exit_code = 1;
+            eprintln!("get: {}: {}", path.display(), e);
+        }
+    }
+
+    std::process::exit(exit_code)
+}
@@ -0,0 +1,374 @@
+//
+// Copyright (c) 2024 Hemi Labs, Inc.
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use clap::Parser;
+use gettextrs::{bind_textdomain_codeset, gettext, setlocale, textdomain, LocaleCategory};
+use plib::sccsfile::{self, Delta, DeltaType, SccsDate, SccsFile, Sid, WeaveLine};
+
+#[derive(Parser)]
+#[command(version, about = gettext("prs - print an SCCS file"))]
+struct Args {
+    #[arg(short = 'd', help = gettext("Output format, with data keywords such as :I: and :C:"))]
+    dataspec: Option<String>,
+
+    #[arg(short = 'r', help = gettext("SID of the delta to describe; the newest delta if empty"))]
+    sid: Option<String>,
+
+    #[arg(short = 'e', conflicts_with = "later", help = gettext("Describe the deltas created earlier than and including the selected one"))]
+    earlier: bool,
+
+    #[arg(short = 'l', help = gettext("Describe the deltas created later than and including the selected one"))]
+    later: bool,
+
+    #[arg(short = 'c', help = gettext("Cutoff date-time [YY[MM[DD[HH[MM[SS]]]]]] selecting the deltas"))]
+    cutoff: Option<String>,
+
+    #[arg(short = 'a', help = gettext("Describe removed deltas as well"))]
+    all: bool,
+
+    #[arg(required = true, help = gettext("SCCS files"))]
+    files: Vec<PathBuf>,
+}
+
+impl Args {
+    /// Parse the command line.  `-r` takes an optional argument that must
+    /// be attached, so given alone it is rewritten with an empty one.
+    fn parse_custom() -> Args {
+        let args = std::env::args().map(|arg| match arg.as_str() {
+            "-r" => String::from("-r="),
+            _ => arg,
+        });
+        Args::parse_from(args)
+    }
+}
+
+/// The output format used without `-d`.
+const DEFAULT_DATASPEC: &str = ":Dt:\t:DL:\nMRs:\n:MR:COMMENTS:\n:C:";
+
+fn error(msg: String) -> io::Error {
+    io::Error::other(msg)
+}
+
+/// Replace the `\n`, `\t` and `\\` escapes of a dataspec.
+fn unescape(spec: &str) -> String {
+    let mut out = String::with_capacity(spec.len());
+    let mut chars = spec.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => out.push('\n'),
+            Some('t') => out.push('\t'),
+            Some(other) => out.push(other),
+            None => out.push('\\'),
+        }
+    }
+    out
+}
+
+fn yes_no(set: bool) -> String {
+    String::from(if set { "yes" } else { "no" })
+}
+
+/// Lines of text, each followed by a newline, as the text keywords
+/// produce them.
+fn text_block<S: AsRef<str>>(lines: &[S]) -> String {
+    lines.iter().fold(String::new(), |mut out, line| {
+        out.push_str(line.as_ref());
+        out.push('\n');
+        out
+    })
+}
+
+fn serial_list(serials: &[u16]) -> String {
+    let list: Vec<String> = serials.iter().map(|s| s.to_string()).collect();
+    list.join(" ")
+}
+
+/// The description of each flag written by `:FL:`.
+fn flag_lines(file: &SccsFile) -> String {
+    let mut out = String::new();
+    for (letter, value) in &file.flags {
+        let desc = match letter {
+            'b' => "branch",
+            'c' => "ceiling",
+            'd' => "default SID",
+            'f' => "floor",
+            'i' => "id keywd err/warn",
+            'j' => "joint edit",
+            'l' => "locked releases",
+            'm' => "module",
+            'n' => "null delta",
+            'q' => "csect name",
+            't' => "type",
+            'v' => "validate MRs",
+            _ => continue,
+        };
+        out.push('\t');
+        out.push_str(desc);
+        if !value.is_empty() {
+            out.push('\t');
+            out.push_str(value);
+        }
+        out.push('\n');
+    }
+    out
+}
+
+/// The values of the data keywords for one delta of a file.
+struct DataKeywords<'a> {
+    file: &'a SccsFile,
+    delta: &'a Delta,
+    path: &'a Path,
+    module: String,
+}
+
+impl DataKeywords<'_> {
+    fn flag_or_none(&self, letter: char) -> String {
+        match self.file.flag(letter) {
+            Some(value) if !value.is_empty() => value.to_string(),
+            _ => String::from("none"),
+        }
+    }
+
+    fn body(&self) -> String {
+        let mut out = String::new();
+        for line in &self.file.weave {
+            match line {
+                WeaveLine::Insert(s) => out.push_str(&format!("\x01I {s}")),
+                WeaveLine::Delete(s) => out.push_str(&format!("\x01D {s}")),
+                WeaveLine::End(s) => out.push_str(&format!("\x01E {s}")),
+                WeaveLine::Text(text) => out.push_str(&String::from_utf8_lossy(text)),
+            }
+            out.push('\n');
+        }
+        out
+    }
+
+    fn gotten_body(&self) -> String {
+        let applied = self.file.applied_serials(self.delta.serial, &[], &[]);
+        let mut out = String::new();
+        for line in self.file.body(&applied) {
+            out.push_str(&String::from_utf8_lossy(line.text));
+            out.push('\n');
+        }
+        out
+    }
+
+    /// The value of the keyword `key`, or `None` if it is not a keyword.
+    fn value(&self, key: &str) -> Option<String> {
+        let delta = self.delta;
+        let sid = &delta.sid;
+        let date = &delta.date;
+        Some(match key {
+            "Dt" => self.expand(":DT: :I: :D: :T: :P: :DS: :DP:"),
+            "DL" => self.expand(":Li:/:Ld:/:Lu:"),
+            "Li" => format!("{:05}", delta.stats.inserted),
+            "Ld" => format!("{:05}", delta.stats.deleted),
+            "Lu" => format!("{:05}", delta.stats.unchanged),
+            "DT" => delta.kind.as_char().to_string(),
+            "I" => sid.to_string(),
+            "R" => sid.rel.to_string(),
+            "L" => sid.lev.to_string(),
+            "B" => sid.br.to_string(),
+            "S" => sid.seq.to_string(),
+            "D" => date.date_str(),
+            "Dy" => format!("{:02}", date.year % 100),
+            "Dm" => format!("{:02}", date.month),
+            "Dd" => format!("{:02}", date.day),
+            "T" => date.time_str(),
+            "Th" => format!("{:02}", date.hour),
+            "Tm" => format!("{:02}", date.minute),
+            "Ts" => format!("{:02}", date.second),
+            "P" => delta.user.clone(),
+            "DS" => delta.serial.to_string(),
+            "DP" => delta.pred.to_string(),
+            "DI" => self.expand(":Dn:/:Dx:/:Dg:"),
+            "Dn" => serial_list(&delta.included),
+            "Dx" => serial_list(&delta.excluded),
+            "Dg" => serial_list(&delta.ignored),
+            "MR" => text_block(&delta.mrs),
+            "C" => text_block(&delta.comments),
+            "UN" => text_block(&self.file.users),
+            "FL" => flag_lines(self.file),
+            "Y" => self.file.flag('t').unwrap_or_default().to_string(),
+            "MF" => yes_no(self.file.flag('v').is_some()),
+            "MP" => self.flag_or_none('v'),
+            "KF" => yes_no(self.file.flag('i').is_some()),
+            "KV" => self.flag_or_none('i'),
+            "BF" => yes_no(self.file.flag('b').is_some()),
+            "J" => yes_no(self.file.flag('j').is_some()),
+            "LK" => self.flag_or_none('l'),
+            "Q" => self.file.flag('q').unwrap_or_default().to_string(),
+            "M" => self.module.clone(),
+            "FB" => self.flag_or_none('f'),
+            "CB" => self.flag_or_none('c'),
+            "Ds" => self.flag_or_none('d'),
+            "ND" => yes_no(self.file.flag('n').is_some()),
+            "FD" => text_block(&self.file.text),
+            "BD" => self.body(),
+            "GB" => self.gotten_body(),
+            "W" => self.expand(":Z::M:\t:I:"),
+            "A" => self.expand(":Z::Y: :M: :I::Z:"),
+            "Z" => String::from("@(#)"),
+            "F" => self
+                .path
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+            "PN" => self.path.display().to_string(),
+            _ => return None,
+        })
+    }
+
+    /// Expand the data keywords of `spec`; anything that is not a keyword
+    /// is copied unchanged.
+    fn expand(&self, spec: &str) -> String {
+        let mut out = String::new();
+        let mut rest = spec;
+        while let Some(start) = rest.find(':') {
+            out.push_str(&rest[..start]);
+            let after = &rest[start + 1..];
+            let value = after
+                .find(':')
+                .and_then(|end| self.value(&after[..end]).map(|v| (end, v)));
+            match value {
+                Some((end, value)) => {
+                    out.push_str(&value);
+                    rest = &after[end + 1..];
+                }
+                None => {
+                    out.push(':');
+                    rest = after;
+                }
+            }
+        }
+        out.push_str(rest);
+        out
+    }
+}
+
+/// The deltas to describe, newest first.
+fn select_deltas<'a>(args: &Args, file: &'a SccsFile) -> io::Result<Vec<&'a Delta>> {
+    let visible = |d: &&Delta| args.all || d.kind == DeltaType::Normal;
+    let cutoff = match &args.cutoff {
+        Some(c) => Some(
+            SccsDate::from_cutoff(c)
+                .ok_or_else(|| error(format!("{} {c}", gettext("invalid cutoff date"))))?,
+        ),
+        None => None,
+    };
+
+    // Without -e or -l, describe a single delta, except that plain prs
+    // describes them all.
+    let range = args.earlier || args.later || cutoff.is_some();
+    if !range && args.sid.is_none() && args.dataspec.is_none() {
+        return Ok(file.deltas.iter().filter(visible).collect());
+    }
+
+    let selected = match cutoff {
+        Some(cutoff) if args.later => file
+            .deltas
+            .iter()
+            .filter(visible)
+            .filter(|d| d.date >= cutoff)
+            .collect(),
+        Some(cutoff) => file
+            .deltas
+            .iter()
+            .filter(visible)
+            .filter(|d| d.date <= cutoff)
+            .collect(),
+        None => {
+            let chosen = match args.sid.as_deref() {
+                Some(s) if !s.is_empty() => {
+                    let sid = s.parse::<Sid>().map_err(error)?;
+                    file.delta(&sid)
+                        .filter(visible)
+                        .ok_or_else(|| error(format!("{} {sid}", gettext("nonexistent SID"))))?
+                }
+                _ => match file.deltas.iter().find(visible) {
+                    Some(delta) => delta,
+                    None => return Ok(Vec::new()),
+                },
+            };
+            file.deltas
+                .iter()
+                .filter(visible)
+                .filter(|d| {
+                    if args.earlier {
+                        d.serial <= chosen.serial
+                    } else if args.later {
+                        d.serial >= chosen.serial
+                    } else {
+                        d.serial == chosen.serial
+                    }
+                })
+                .collect()
+        }
+    };
+    Ok(selected)
+}
+
+fn prs_file(args: &Args, path: &Path) -> io::Result<()> {
+    let module = sccsfile::gfile_name(path)
+        .ok_or_else(|| error(gettext("not an SCCS file")))?
+        .to_string_lossy()
+        .into_owned();
+    let file = SccsFile::read(path)?;
+    if !file.checksum_valid() {
+        return Err(error(gettext("corrupted file")));
+    }
+    let module = file.flag('m').map(String::from).unwrap_or(module);
+
+    let dataspec = match &args.dataspec {
+        Some(spec) => unescape(spec),
+        None => String::from(DEFAULT_DATASPEC),
+    };
+
+    let deltas = select_deltas(args, &file)?;
+    let mut out = io::stdout().lock();
+    if args.dataspec.is_none() {
+        write!(out, "{}:\n\n", path.display())?;
+    }
+    for delta in deltas {
+        let keywords = DataKeywords {
+            file: &file,
+            delta,
+            path,
+            module: module.clone(),
+        };
+        writeln!(out, "{}", keywords.expand(&dataspec))?;
+    }
+    Ok(())
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    setlocale(LocaleCategory::LcAll, "");
+    textdomain("posixutils-rs")?;
+    bind_textdomain_codeset("posixutils-rs", "UTF-8")?;
+
+    let args = Args::parse_custom();
+
+    let mut exit_code = 0;
+
+    for path in sccsfile::sfile_operands(&args.files)? {
+        if let Err(e) = prs_file(&args, &path) {
+            exit_code = 1;
+            eprintln!("prs: {}: {}", path.display(), e);
+        }
+    }
+
+    std::process::exit(exit_code)
+}
@@ -0,0 +1,111 @@
+//
+// Copyright (c) 2024 Hemi Labs, Inc.
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+use std::fs;
+use std::io;
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+
+use clap::Parser;
+use gettextrs::{bind_textdomain_codeset, gettext, setlocale, textdomain, LocaleCategory};
+use plib::curuser;
+use plib::sccsfile::{self, DeltaType, LockFile, SccsFile, Sid};
+
+#[derive(Parser)]
+#[command(version, about = gettext("rmdel - remove a delta from an SCCS file"))]
+struct Args {
+    #[arg(short = 'r', required = true, help = gettext("SID of the delta to remove"))]
+    sid: String,
+
+    #[arg(required = true, help = gettext("SCCS files"))]
+    files: Vec<PathBuf>,
+}
+
+fn error(msg: String) -> io::Error {
+    io::Error::other(msg)
+}
+
+fn rmdel_file(sid: Sid, path: &Path) -> io::Result<()> {
+    let pfile = sccsfile::aux_file(path, 'p')
+        .filter(|_| sccsfile::gfile_name(path).is_some())
+        .ok_or_else(|| error(gettext("not an SCCS file")))?;
+    let _lock = LockFile::acquire(path)?;
+
+    let mut file = SccsFile::read(path)?;
+    if !file.checksum_valid() {
+        return Err(error(gettext("corrupted file")));
+    }
+    let delta = file
+        .delta(&sid)
+        .filter(|d| d.kind == DeltaType::Normal)
+        .ok_or_else(|| error(format!("{} {sid}", gettext("nonexistent SID"))))?;
+    let serial = delta.serial;
+
+    // Only the person who made the delta, or the owner of the file, may
+    // remove it.
+    let user = curuser::real_user_name();
+    let owner = fs::metadata(path)?.uid() == unsafe { libc::getuid() };
+    if delta.user != user && !owner {
+        return Err(error(format!(
+            "{} {user} {}",
+            gettext("user"),
+            gettext("not authorized to remove the delta")
+        )));
+    }
+
+    let depended_on = file.successors(serial).next().is_some()
+        || file.deltas.iter().any(|d| {
+            d.kind == DeltaType::Normal
+                && (d.included.contains(&serial)
+                    || d.excluded.contains(&serial)
+                    || d.ignored.contains(&serial))
+        });
+    if depended_on {
+        return Err(error(format!(
+            "{} {sid} {}",
+            gettext("delta"),
+            gettext("is not the newest on its branch")
+        )));
+    }
+    if let Some(entry) = sccsfile::read_pfile(&pfile)?
+        .into_iter()
+        .find(|e| e.old == sid)
+    {
+        return Err(error(format!("{}: `{entry}'", gettext("being edited"))));
+    }
+
+    file.remove_delta(serial);
+    file.write(path)
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    setlocale(LocaleCategory::LcAll, "");
+    textdomain("posixutils-rs")?;
+    bind_textdomain_codeset("posixutils-rs", "UTF-8")?;
+
+    let args = Args::parse();
+    let sid = match args.sid.parse::<Sid>() {
+        Ok(sid) if sid.is_complete() => sid,
+        _ => {
+            eprintln!("rmdel: {} '{}'", gettext("invalid SID"), args.sid);
+            std::process::exit(1);
+        }
+    };
+
+    let mut exit_code = 0;
+
+    for path in sccsfile::sfile_operands(&args.files)? {
+        if let Err(e) = rmdel_file(sid, &path) {
+            exit_code = 1;
+            eprintln!("rmdel: {}: {}", path.display(), e);
+        }
+    }
+
+    std::process::exit(exit_code)
+}
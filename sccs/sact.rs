@@ -0,0 +1,62 @@
+//
+// Copyright (c) 2024 Hemi Labs, Inc.
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+use std::io;
+use std::path::{Path, PathBuf};
+
+use clap::Parser;
+use gettextrs::{bind_textdomain_codeset, gettext, setlocale, textdomain, LocaleCategory};
+use plib::sccsfile;
+
+#[derive(Parser)]
+#[command(version, about = gettext("sact - print current SCCS file-editing activity"))]
+struct Args {
+    #[arg(required = true, help = gettext("SCCS files"))]
+    files: Vec<PathBuf>,
+}
+
+fn sact_file(path: &Path, show_name: bool) -> io::Result<()> {
+    let pfile = sccsfile::aux_file(path, 'p')
+        .filter(|_| sccsfile::gfile_name(path).is_some())
+        .ok_or_else(|| io::Error::other(gettext("not an SCCS file")))?;
+    if !path.exists() {
+        return Err(io::Error::from(io::ErrorKind::NotFound));
+    }
+    let entries = sccsfile::read_pfile(&pfile)?;
+    if entries.is_empty() {
+        return Ok(());
+    }
+    if show_name {
+        println!("\n{}:", path.display());
+    }
+    for entry in entries {
+        println!("{} {} {} {}", entry.old, entry.new, entry.user, entry.date);
+    }
+    Ok(())
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    setlocale(LocaleCategory::LcAll, "");
+    textdomain("posixutils-rs")?;
+    bind_textdomain_codeset("posixutils-rs", "UTF-8")?;
+
+    let args = Args::parse();
+
+    let mut exit_code = 0;
+
+    let files = sccsfile::sfile_operands(&args.files)?;
+    for path in &files {
+        if let Err(e) = sact_file(path, files.len() > 1) {
+            exit_code = 1;
+            eprintln!("sact: {}: {}", path.display(), e);
+        }
+    }
+
+    std::process::exit(exit_code)
+}
@@ -0,0 +1,568 @@
+//
+// Copyright (c) 2024 Hemi Labs, Inc.
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+use std::env;
+use std::ffi::{CStr, CString};
+use std::fs;
+use std::io;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use clap::{Parser, Subcommand};
+use gettextrs::{bind_textdomain_codeset, gettext, setlocale, textdomain, LocaleCategory};
+use plib::curuser;
+use plib::sccsfile::{self, PFileEntry};
+
+#[derive(Parser)]
+#[command(version, about = gettext("sccs - front end for the SCCS subsystem"))]
+struct Args {
+    #[arg(short = 'r', help = gettext("Run the command with the real user ID"))]
+    real_user: bool,
+
+    #[arg(short = 'd', help = gettext("Root directory of the SCCS files, overriding PROJECTDIR"))]
+    root: Option<PathBuf>,
+
+    #[arg(short = 'p', help = gettext("Directory holding the SCCS files, instead of SCCS"))]
+    sccs_dir: Option<PathBuf>,
+
+    #[command(subcommand)]
+    command: SccsCommand,
+}
+
+/// The command to run, with its options and operands left for the
+/// command itself to interpret.
+#[derive(Subcommand)]
+enum SccsCommand {
+    #[command(external_subcommand)]
+    Command(Vec<String>),
+}
+
+/// The options of a command that take a value: `value` lists those whose
+/// value may be the next argument, `attached` those whose value, possibly
+/// empty, is the rest of the argument.
+struct OptionSpec {
+    value: &'static str,
+    attached: &'static str,
+}
+
+fn option_spec(command: &str) -> OptionSpec {
+    let (value, attached) = match command {
+        "admin" | "create" => ("rfdaem", "ity"),
+        "delta" => ("rgm", "y"),
+        "get" | "edit" => ("rcix", ""),
+        "prs" => ("dc", "r"),
+        "rmdel" | "unget" | "fix" => ("r", ""),
+        "val" => ("rmy", ""),
+        "delget" | "deledit" => ("mrcix", "y"),
+        "diffs" => ("rcix", ""),
+        "info" | "check" | "tell" | "clean" => ("", "u"),
+        _ => ("", ""),
+    };
+    OptionSpec { value, attached }
+}
+
+/// A command-line argument of a command: an option with its value, or an
+/// operand.
+enum CommandArg {
+    Opt(char, Option<String>),
+    Operand(String),
+}
+
+fn split_args(args: &[String], spec: &OptionSpec) -> Vec<CommandArg> {
+    let mut split = Vec::new();
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        if arg == "--" {
+            split.extend(iter.by_ref().cloned().map(CommandArg::Operand));
+            break;
+        }
+        let Some(opts) = arg.strip_prefix('-').filter(|o| !o.is_empty()) else {
+            split.push(CommandArg::Operand(arg.clone()));
+            continue;
+        };
+        for (pos, letter) in opts.char_indices() {
+            let rest = &opts[pos + letter.len_utf8()..];
+            if spec.value.contains(letter) {
+                let value = match rest {
+                    "" => iter.next().cloned().unwrap_or_default(),
+                    _ => rest.to_string(),
+                };
+                split.push(CommandArg::Opt(letter, Some(value)));
+                break;
+            } else if spec.attached.contains(letter) {
+                split.push(CommandArg::Opt(letter, Some(rest.to_string())));
+                break;
+            }
+            split.push(CommandArg::Opt(letter, None));
+        }
+    }
+    split
+}
+
+fn option_string(letter: char, value: &Option<String>) -> String {
+    match value {
+        Some(value) => format!("-{letter}{value}"),
+        None => format!("-{letter}"),
+    }
+}
+
+/// The options of `args` whose letter is in `letters`.
+fn options(args: &[CommandArg], letters: &str) -> Vec<String> {
+    args.iter()
+        .filter_map(|arg| match arg {
+            CommandArg::Opt(letter, value) if letters.contains(*letter) => {
+                Some(option_string(*letter, value))
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+fn option_value(args: &[CommandArg], wanted: char) -> Option<Option<String>> {
+    args.iter().find_map(|arg| match arg {
+        CommandArg::Opt(letter, value) if *letter == wanted => Some(value.clone()),
+        _ => None,
+    })
+}
+
+fn operands(args: &[CommandArg]) -> Vec<String> {
+    args.iter()
+        .filter_map(|arg| match arg {
+            CommandArg::Operand(operand) => Some(operand.clone()),
+            _ => None,
+        })
+        .collect()
+}
+
+/// The user named by `-u`, the current user if the option has no value.
+fn user_option(args: &[CommandArg]) -> Option<String> {
+    match option_value(args, 'u')? {
+        Some(user) if !user.is_empty() => Some(user),
+        _ => Some(curuser::real_user_name()),
+    }
+}
+
+/// The home directory of `user`.
+fn home_dir(user: &str) -> Option<PathBuf> {
+    let name = CString::new(user).ok()?;
+    unsafe {
+        let pw = libc::getpwnam(name.as_ptr());
+        if pw.is_null() {
+            return None;
+        }
+        let dir = CStr::from_ptr((*pw).pw_dir);
+        Some(PathBuf::from(dir.to_string_lossy().into_owned()))
+    }
+}
+
+/// The root directory named by `PROJECTDIR`: a path, or the name of a user
+/// whose `src` or `source` directory holds the project.
+fn project_dir(value: &str) -> io::Result<PathBuf> {
+    if value.starts_with('/') {
+        return Ok(PathBuf::from(value));
+    }
+    let home = home_dir(value).ok_or_else(|| {
+        io::Error::other(format!("{}: {value}", gettext("PROJECTDIR: unknown user")))
+    })?;
+    ["src", "source"]
+        .iter()
+        .map(|dir| home.join(dir))
+        .find(|dir| dir.is_dir())
+        .ok_or_else(|| {
+            io::Error::other(format!(
+                "{}: {value}",
+                gettext("PROJECTDIR: no src or source directory for user")
+            ))
+        })
+}
+
+/// The utility `name` when it was installed next to this program.
+fn bundled_utility(name: &str) -> Option<PathBuf> {
+    env::current_exe()
+        .ok()
+        .and_then(|exe| exe.parent().map(|dir| dir.join(name)))
+        .filter(|path| path.is_file())
+}
+
+/// Where the SCCS utilities are: next to this program when they were
+/// installed together, otherwise found through `PATH`.
+fn utility_path(name: &str) -> PathBuf {
+    bundled_utility(name).unwrap_or_else(|| PathBuf::from(name))
+}
+
+/// Run the utility `name`, returning its exit status.
+fn run(name: &str, args: &[String]) -> io::Result<i32> {
+    let status = Command::new(utility_path(name))
+        .args(args)
+        .status()
+        .map_err(|e| io::Error::other(format!("{name}: {e}")))?;
+    Ok(status.code().unwrap_or(1))
+}
+
+struct Sccs {
+    root: Option<PathBuf>,
+    sccs_dir: PathBuf,
+}
+
+impl Sccs {
+    /// The directory holding the SCCS files of the current directory.
+    fn directory(&self) -> PathBuf {
+        match &self.root {
+            Some(root) => root.join(&self.sccs_dir),
+            None => self.sccs_dir.clone(),
+        }
+    }
+
+    /// The s-file for the operand `name`: `dir/file` becomes
+    /// `dir/SCCS/s.file`, under the root directory if `name` is relative.
+    /// Names of s-files and directories are used as they are.
+    fn sfile(&self, name: &str) -> String {
+        let path = Path::new(name);
+        let base = path.file_name().map(|n| n.to_string_lossy().into_owned());
+        let Some(base) = base.filter(|b| !b.starts_with("s.") && !path.is_dir()) else {
+            return name.to_string();
+        };
+        let mut sfile = match &self.root {
+            Some(root) if path.is_relative() => root.clone(),
+            _ => PathBuf::new(),
+        };
+        if let Some(parent) = path.parent() {
+            sfile.push(parent);
+        }
+        sfile.push(&self.sccs_dir);
+        sfile.push(format!("s.{base}"));
+        sfile.to_string_lossy().into_owned()
+    }
+
+    fn sfiles(&self, names: &[String]) -> Vec<String> {
+        names.iter().map(|name| self.sfile(name)).collect()
+    }
+
+    /// Run an SCCS utility with its file operands mapped to s-files.
+    fn utility(&self, name: &str, args: &[String]) -> io::Result<i32> {
+        let spec = option_spec(name);
+        let mut mapped = Vec::new();
+        for arg in split_args(args, &spec) {
+            match arg {
+                CommandArg::Opt(letter, value) => mapped.push(option_string(letter, &value)),
+                CommandArg::Operand(name) => mapped.push(self.sfile(&name)),
+            }
+        }
+        run(name, &mapped)
+    }
+
+    /// `delget` and `deledit`: make deltas, then get the new versions.
+    fn delta_then_get(&self, args: &[CommandArg], edit: bool) -> io::Result<i32> {
+        let sfiles = self.sfiles(&operands(args));
+        let mut delta_args = options(args, "mprsy");
+        delta_args.extend(sfiles.iter().cloned());
+        let status = run("delta", &delta_args)?;
+        if status != 0 {
+            return Ok(status);
+        }
+        let mut get_args = options(args, "bceiklsx");
+        if edit {
+            get_args.push(String::from("-e"));
+        }
+        get_args.extend(sfiles);
+        run("get", &get_args)
+    }
+
+    /// `create`: make s-files from the named files, keep the originals
+    /// as `,file`, and get read-only versions.
+    fn create(&self, args: &[CommandArg]) -> io::Result<i32> {
+        let admin_args = options(args, "nrtfdaeym");
+        let mut exit_code = 0;
+        for name in operands(args) {
+            let sfile = self.sfile(&name);
+            let mut cmd_args = admin_args.clone();
+            cmd_args.push(format!("-i{name}"));
+            cmd_args.push(sfile.clone());
+            let status = run("admin", &cmd_args)?;
+            if status != 0 {
+                exit_code = status;
+                continue;
+            }
+            let path = Path::new(&name);
+            let base = path.file_name().unwrap().to_string_lossy();
+            fs::rename(path, path.with_file_name(format!(",{base}")))?;
+            let status = run("get", &[sfile])?;
+            if status != 0 {
+                exit_code = status;
+            }
+        }
+        Ok(exit_code)
+    }
+
+    /// `fix`: remove a delta, leaving its text as an edited g-file.
+    fn fix(&self, args: &[CommandArg]) -> io::Result<i32> {
+        let Some(Some(sid)) = option_value(args, 'r') else {
+            return Err(io::Error::other(gettext("fix: -r option required")));
+        };
+        let names = operands(args);
+        let sfiles = self.sfiles(&names);
+        let sid_arg = format!("-r{sid}");
+        let steps: [(&str, Vec<String>); 3] = [
+            ("get", vec![String::from("-k"), sid_arg.clone()]),
+            ("rmdel", vec![sid_arg]),
+            ("get", vec![String::from("-e"), String::from("-g")]),
+        ];
+        for (name, mut cmd_args) in steps {
+            cmd_args.extend(sfiles.iter().cloned());
+            let status = run(name, &cmd_args)?;
+            if status != 0 {
+                return Ok(status);
+            }
+        }
+        for sfile in &sfiles {
+            if let Some(gfile) = sccsfile::gfile_name(Path::new(sfile)) {
+                let mut perms = fs::metadata(&gfile)?.permissions();
+                perms.set_mode(perms.mode() | 0o200);
+                fs::set_permissions(&gfile, perms)?;
+            }
+        }
+        Ok(0)
+    }
+
+    /// The files being edited, with their p-file entries: all edits, only
+    /// those on the trunk with `-b`, and only those of one user with `-u`.
+    fn edited(&self, args: &[CommandArg]) -> io::Result<Vec<(String, Vec<PFileEntry>)>> {
+        let trunk_only = option_value(args, 'b').is_some();
+        let user = user_option(args);
+        let dir = self.directory();
+        if !dir.is_dir() {
+            return Ok(Vec::new());
+        }
+        let mut edited = Vec::new();
+        for sfile in sccsfile::sfile_operands(&[dir])? {
+            let (Some(gfile), Some(pfile)) = (
+                sccsfile::gfile_name(&sfile),
+                sccsfile::aux_file(&sfile, 'p'),
+            ) else {
+                continue;
+            };
+            let entries: Vec<PFileEntry> = sccsfile::read_pfile(&pfile)?
+                .into_iter()
+                .filter(|e| !trunk_only || !e.new.is_branch())
+                .filter(|e| user.as_ref().map_or(true, |u| e.user == *u))
+                .collect();
+            if !entries.is_empty() {
+                edited.push((gfile.to_string_lossy().into_owned(), entries));
+            }
+        }
+        Ok(edited)
+    }
+
+    /// `info` and `check`: list the files being edited.  `check` is silent
+    /// when nothing is being edited, and fails otherwise.
+    fn info(&self, args: &[CommandArg], check: bool) -> io::Result<i32> {
+        let edited = self.edited(args)?;
+        for (gfile, entries) in &edited {
+            for entry in entries {
+                println!(
+                    "{gfile:>12}: {} {} {} {} {}",
+                    gettext("being edited:"),
+                    entry.old,
+                    entry.new,
+                    entry.user,
+                    entry.date
+                );
+            }
+        }
+        if edited.is_empty() && !check {
+            let mut msg = gettext("Nothing being edited");
+            if option_value(args, 'b').is_some() {
+                msg.push_str(&format!(" {}", gettext("(on trunk)")));
+            }
+            if let Some(user) = user_option(args) {
+                msg.push_str(&format!(" {} {user}", gettext("by")));
+            }
+            println!("{msg}");
+        }
+        Ok(i32::from(check && !edited.is_empty()))
+    }
+
+    /// `tell`: the names of the files being edited.
+    fn tell(&self, args: &[CommandArg]) -> io::Result<i32> {
+        for (gfile, _) in self.edited(args)? {
+            println!("{gfile}");
+        }
+        Ok(0)
+    }
+
+    /// `clean`: remove the g-files that can be retrieved again, leaving
+    /// those being edited.
+    fn clean(&self, args: &[CommandArg]) -> io::Result<i32> {
+        let edited: Vec<String> = self.edited(args)?.into_iter().map(|(g, _)| g).collect();
+        let dir = self.directory();
+        if !dir.is_dir() {
+            return Ok(0);
+        }
+        for sfile in sccsfile::sfile_operands(&[dir])? {
+            let Some(gfile) = sccsfile::gfile_name(&sfile) else {
+                continue;
+            };
+            if edited.contains(&gfile.to_string_lossy().into_owned()) || !gfile.is_file() {
+                continue;
+            }
+            fs::remove_file(&gfile)?;
+        }
+        Ok(0)
+    }
+
+    /// `unedit`: drop an edit, losing its changes, and get the file again.
+    fn unedit(&self, args: &[CommandArg]) -> io::Result<i32> {
+        let sfiles = self.sfiles(&operands(args));
+        let status = run("unget", &sfiles)?;
+        if status != 0 {
+            return Ok(status);
+        }
+        run("get", &sfiles)
+    }
+
+    /// `diffs`: compare the files being edited with the versions they were
+    /// retrieved from, using `diff`.  The headers of context and unified
+    /// output name the s-file only with the `diff` of these utilities, as
+    /// `--label` is an extension; any other `diff` names a temporary file.
+    fn diffs(&self, args: &[CommandArg]) -> io::Result<i32> {
+        let get_args = options(args, "rcixt");
+        let diff_args: Vec<String> = args
+            .iter()
+            .filter_map(|arg| match arg {
+                CommandArg::Opt('C', _) => Some(String::from("-c")),
+                CommandArg::Opt(letter, value) if "befu".contains(*letter) => {
+                    Some(option_string(*letter, value))
+                }
+                _ => None,
+            })
+            .collect();
+
+        let mut exit_code = 0;
+        for name in operands(args) {
+            let sfile = self.sfile(&name);
+            let gfile = sccsfile::gfile_name(Path::new(&sfile)).ok_or_else(|| {
+                io::Error::other(format!("{name}: {}", gettext("not an SCCS file")))
+            })?;
+
+            let mut cmd_args = vec![String::from("-p"), String::from("-k"), String::from("-s")];
+            cmd_args.extend(get_args.iter().cloned());
+            cmd_args.push(sfile.clone());
+            let output = Command::new(utility_path("get"))
+                .args(&cmd_args)
+                .stderr(Stdio::inherit())
+                .output()?;
+            if !output.status.success() {
+                exit_code = output.status.code().unwrap_or(1);
+                continue;
+            }
+            let old = env::temp_dir().join(format!(
+                "sccs-diffs-{}-{}",
+                std::process::id(),
+                gfile.file_name().unwrap_or_default().to_string_lossy()
+            ));
+            fs::write(&old, &output.stdout)?;
+
+            println!("\n------- {} -------", gfile.display());
+            let mut cmd_args = diff_args.clone();
+            if bundled_utility("diff").is_some() {
+                cmd_args.push(format!("--label={sfile}"));
+            }
+            cmd_args.push(old.to_string_lossy().into_owned());
+            cmd_args.push(gfile.to_string_lossy().into_owned());
+            let status = run("diff", &cmd_args);
+            fs::remove_file(&old)?;
+            // diff exits with 1 when the files differ.
+            if status? > 1 {
+                exit_code = 1;
+            }
+        }
+        Ok(exit_code)
+    }
+
+    fn dispatch(&self, command: &str, args: &[String]) -> io::Result<i32> {
+        let split = || split_args(args, &option_spec(command));
+        match command {
+            "admin" | "delta" | "get" | "prs" | "rmdel" | "sact" | "unget" | "val" => {
+                self.utility(command, args)
+            }
+            "what" => run("what", args),
+            "edit" => {
+                let mut get_args = vec![String::from("-e")];
+                get_args.extend(args.iter().cloned());
+                self.utility("get", &get_args)
+            }
+            "delget" => self.delta_then_get(&split(), false),
+            "deledit" => self.delta_then_get(&split(), true),
+            "create" => self.create(&split()),
+            "fix" => self.fix(&split()),
+            "clean" => self.clean(&split()),
+            "info" => self.info(&split(), false),
+            "check" => self.info(&split(), true),
+            "tell" => self.tell(&split()),
+            "unedit" => self.unedit(&split()),
+            "diffs" => self.diffs(&split()),
+            _ => Err(io::Error::other(format!(
+                "{} `{command}'",
+                gettext("unknown command")
+            ))),
+        }
+    }
+}
+
+/// Run with the real user and group IDs rather than the effective ones, as
+/// when this program is installed set-user-ID.
+fn drop_privileges() -> io::Result<()> {
+    unsafe {
+        if libc::setgid(libc::getgid()) != 0 || libc::setuid(libc::getuid()) != 0 {
+            return Err(io::Error::last_os_error());
+        }
+    }
+    Ok(())
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    setlocale(LocaleCategory::LcAll, "");
+    textdomain("posixutils-rs")?;
+    bind_textdomain_codeset("posixutils-rs", "UTF-8")?;
+
+    let args = Args::parse();
+
+    let privileges = if args.real_user {
+        drop_privileges()
+    } else {
+        Ok(())
+    };
+    let root = privileges.and_then(|()| match (&args.root, env::var("PROJECTDIR")) {
+        (Some(root), _) => Ok(Some(root.clone())),
+        (None, Ok(dir)) if !dir.is_empty() => project_dir(&dir).map(Some),
+        _ => Ok(None),
+    });
+    let result = root.and_then(|root| {
+        let sccs = Sccs {
+            root,
+            sccs_dir: args
+                .sccs_dir
+                .clone()
+                .unwrap_or_else(|| PathBuf::from("SCCS")),
+        };
+        let SccsCommand::Command(command) = &args.command;
+        sccs.dispatch(&command[0], &command[1..])
+    });
+
+    let exit_code = match result {
+        Ok(code) => code,
+        Err(e) => {
+            eprintln!("sccs: {e}");
+            1
+        }
+    };
+
+    std::process::exit(exit_code)
+}
@@ -0,0 +1,98 @@
+//
+// Copyright (c) 2024 Hemi Labs, Inc.
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+use plib::sccsfile::{SccsFile, Sid};
+use plib::testing::{run_test, TestPlan};
+use std::fs;
+use tempfile::tempdir;
+
+fn admin_test(args: &[&str], stdin_data: &str, expected_err: &str, expected_exit_code: i32) {
+    run_test(TestPlan {
+        cmd: String::from("admin"),
+        args: args.iter().map(|s| s.to_string()).collect(),
+        stdin_data: String::from(stdin_data),
+        expected_out: String::new(),
+        expected_err: String::from(expected_err),
+        expected_exit_code,
+    });
+}
+
+#[test]
+fn admin_create_from_stdin() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("s.prog.c");
+    let sfile = path.to_str().unwrap();
+
+    admin_test(
+        &["-i", "-r2", "-yfirst", "-fqQQ", "-adev", sfile],
+        "%W%\nmain\n",
+        "",
+        0,
+    );
+    let file = SccsFile::read(&path).unwrap();
+    assert!(file.checksum_valid());
+    assert_eq!(file.deltas.len(), 1);
+    assert_eq!(file.deltas[0].sid, Sid::new(2, 1));
+    assert_eq!(file.deltas[0].comments, vec!["first"]);
+    assert_eq!(file.flag('q'), Some("QQ"));
+    assert_eq!(file.users, vec!["dev"]);
+    let body: Vec<&[u8]> = file
+        .body_for_sid(&Sid::new(2, 1), &[], &[])
+        .unwrap()
+        .iter()
+        .map(|l| l.text)
+        .collect();
+    assert_eq!(body, vec![&b"%W%"[..], b"main"]);
+
+    admin_test(
+        &["-n", sfile],
+        "",
+        &format!("admin: {sfile}: file exists\n"),
+        1,
+    );
+}
+
+#[test]
+fn admin_flags_and_checksum() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("s.empty");
+    let sfile = path.to_str().unwrap();
+
+    admin_test(
+        &["-n", sfile],
+        "",
+        &format!("admin: {sfile}: No id keywords (cm7)\n"),
+        0,
+    );
+    admin_test(&["-fb", "-fl1,2", "-fttype", sfile], "", "", 0);
+    admin_test(&["-dl1", "-dt", sfile], "", "", 0);
+    let file = SccsFile::read(&path).unwrap();
+    assert_eq!(file.flag('b'), Some(""));
+    assert_eq!(file.flag('l'), Some("2"));
+    assert_eq!(file.flag('t'), None);
+
+    admin_test(
+        &["-fx", sfile],
+        "",
+        &format!("admin: {sfile}: invalid flag 'x'\n"),
+        1,
+    );
+
+    // Damage the file by hand, then repair its checksum.
+    let data = fs::read_to_string(&path).unwrap();
+    fs::write(&path, data.replace("\x01f b", "\x01f n")).unwrap();
+    admin_test(
+        &["-h", sfile],
+        "",
+        &format!("admin: {sfile}: corrupted file\n"),
+        1,
+    );
+    admin_test(&["-z", sfile], "", "", 0);
+    admin_test(&["-h", sfile], "", "", 0);
+}
@@ -0,0 +1,76 @@
+//
+// Copyright (c) 2024 Hemi Labs, Inc.
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+use crate::{check, run_in};
+use plib::sccsfile::{SccsFile, Sid};
+use std::fs;
+use tempfile::tempdir;
+
+#[test]
+fn delta_round_trip() {
+    let dir = tempdir().unwrap();
+    let dir = dir.path();
+    check(
+        run_in(dir, "admin", &["-i", "-yinitial", "s.notes"], "%I%\nb\nc\n"),
+        "",
+        "",
+        0,
+    );
+    check(
+        run_in(dir, "get", &["-e", "s.notes"], ""),
+        "1.1\nnew delta 1.2\n3 lines\n",
+        "",
+        0,
+    );
+    fs::write(dir.join("notes"), "%I%\nB\nc\nd\n").unwrap();
+    check(
+        run_in(dir, "delta", &["-p", "s.notes"], "fix b\\\nadd d\n"),
+        "2c2\n< b\n---\n> B\n3a4\n> d\n1.2\n2 inserted\n1 deleted\n2 unchanged\n",
+        "",
+        0,
+    );
+    assert!(!dir.join("notes").exists());
+    assert!(!dir.join("p.notes").exists());
+
+    let file = SccsFile::read(&dir.join("s.notes")).unwrap();
+    assert!(file.checksum_valid());
+    assert_eq!(file.deltas[0].sid, Sid::new(1, 2));
+    assert_eq!(file.deltas[0].comments, vec!["fix b", "add d"]);
+
+    check(
+        run_in(dir, "get", &["-p", "-s", "-r1.1", "s.notes"], ""),
+        "1.1\nb\nc\n",
+        "",
+        0,
+    );
+    check(
+        run_in(dir, "get", &["-p", "-s", "s.notes"], ""),
+        "1.2\nB\nc\nd\n",
+        "",
+        0,
+    );
+}
+
+#[test]
+fn delta_without_edit() {
+    let dir = tempdir().unwrap();
+    let dir = dir.path();
+    check(
+        run_in(dir, "admin", &["-n", "s.empty"], ""),
+        "",
+        "admin: s.empty: No id keywords (cm7)\n",
+        0,
+    );
+    check(
+        run_in(dir, "delta", &["-ynothing", "s.empty"], ""),
+        "",
+        "delta: s.empty: no outstanding deltas\n",
+        1,
+    );
+}
@@ -0,0 +1,143 @@
+//
+// Copyright (c) 2024 Hemi Labs, Inc.
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+use plib::testing::{run_test, TestPlan};
+use std::fs;
+use std::path::PathBuf;
+use tempfile::tempdir;
+
+// s.branched.c: 1.1 "a b c", 1.2 deletes b, 1.1.1.1 appends d, and 1.3
+// inserts x and includes 1.1.1.1.
+fn sfile() -> String {
+    let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    path.push("tests");
+    path.push("s.branched.c");
+    path.to_str().unwrap().to_string()
+}
+
+fn get_test(args: &[&str], expected_out: &str, expected_err: &str, expected_exit_code: i32) {
+    run_test(TestPlan {
+        cmd: String::from("get"),
+        args: args.iter().map(|s| s.to_string()).collect(),
+        stdin_data: String::new(),
+        expected_out: String::from(expected_out),
+        expected_err: String::from(expected_err),
+        expected_exit_code,
+    });
+}
+
+#[test]
+fn get_latest_with_keywords() {
+    let sfile = sfile();
+    get_test(
+        &["-p", &sfile],
+        &format!(
+            "a 1.3 @(#)branched.c\t1.3 @(#)prog branched.c 1.3@(#) 1\n\
+             x branched.c {sfile} prog QQ 24/07/10 07/10/24 10:00:00\n\
+             c\n\
+             d\n"
+        ),
+        "1.3\n4 lines\n",
+        0,
+    );
+}
+
+#[test]
+fn get_sid_prefixes() {
+    get_test(
+        &["-p", "-s", "-k", "-m", "-n", "-r1.1", &sfile()],
+        "branched.c\t1.1\ta %I% %W% %A% %C%\n\
+         branched.c\t1.1\tb\n\
+         branched.c\t1.1\tc\n",
+        "",
+        0,
+    );
+}
+
+#[test]
+fn get_branch_and_summary() {
+    get_test(
+        &["-p", "-k", "-r1.1.1", "-L", &sfile()],
+        "a %I% %W% %A% %C%\nb\nc\nd\n\
+         **  1.3\t24/07/10 10:00:00 dev\n\tinclude the branch\n\n\
+         \x20   1.1.1.1\t24/07/09 12:00:00 dev\n\tMR1\n\tbranch\n\n\
+         **  1.2\t24/07/09 11:00:00 dev\n\tdrop b\n\n\
+         \x20   1.1\t24/07/09 10:00:00 dev\n\tinitial\n\n",
+        "1.1.1.1\n4 lines\n",
+        0,
+    );
+}
+
+#[test]
+fn get_include_exclude() {
+    get_test(
+        &["-p", "-k", "-x1.1.1.1", &sfile()],
+        "a %I% %W% %A% %C%\nx %M% %F% %Y% %Q% %E% %G% %U%\nc\n",
+        "1.3\nExcluded:\n1.1.1.1\n3 lines\n",
+        0,
+    );
+    get_test(
+        &["-p", "-k", "-s", "-r1.2", "-i1.1.1.1", &sfile()],
+        "a %I% %W% %A% %C%\nc\nd\n",
+        "",
+        0,
+    );
+}
+
+#[test]
+fn get_cutoff() {
+    get_test(
+        &["-p", "-k", "-c", "24/07/09 11:30", &sfile()],
+        "a %I% %W% %A% %C%\nc\n",
+        "1.2\n2 lines\n",
+        0,
+    );
+}
+
+#[test]
+fn get_nonexistent_sid() {
+    let sfile = sfile();
+    get_test(
+        &["-p", "-r1.7", &sfile],
+        "",
+        &format!("get: {sfile}: nonexistent SID 1.7\n"),
+        1,
+    );
+}
+
+#[test]
+fn get_edit_writes_pfile() {
+    let dir = tempdir().unwrap();
+    let sfile_path = dir.path().join("s.branched.c");
+    fs::copy(sfile(), &sfile_path).unwrap();
+    let sfile = sfile_path.to_str().unwrap();
+    let pfile = dir.path().join("p.branched.c");
+
+    get_test(&["-e", "-g", sfile], "1.3\nnew delta 1.4\n", "", 0);
+    get_test(
+        &["-e", "-g", "-r1.1", sfile],
+        "1.1\nnew delta 1.1.2.1\n",
+        "",
+        0,
+    );
+    let entries = fs::read_to_string(&pfile).unwrap();
+    let lines: Vec<&str> = entries.lines().collect();
+    assert_eq!(lines.len(), 2);
+    assert!(lines[0].starts_with("1.3 1.4 "));
+    assert!(lines[1].starts_with("1.1 1.1.2.1 "));
+
+    run_test(TestPlan {
+        cmd: String::from("get"),
+        args: vec![String::from("-e"), String::from("-g"), sfile.to_string()],
+        stdin_data: String::new(),
+        expected_out: String::new(),
+        expected_err: format!("get: {sfile}: being edited: `{}'\n", lines[0]),
+        expected_exit_code: 1,
+    });
+}
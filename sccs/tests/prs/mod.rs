@@ -0,0 +1,93 @@
+//
+// Copyright (c) 2024 Hemi Labs, Inc.
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+use plib::testing::{run_test, TestPlan};
+use std::path::PathBuf;
+
+fn sfile() -> String {
+    let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    path.push("tests");
+    path.push("s.branched.c");
+    path.to_str().unwrap().to_string()
+}
+
+fn prs_test(args: &[&str], expected_out: &str, expected_err: &str, expected_exit_code: i32) {
+    run_test(TestPlan {
+        cmd: String::from("prs"),
+        args: args.iter().map(|s| s.to_string()).collect(),
+        stdin_data: String::new(),
+        expected_out: String::from(expected_out),
+        expected_err: String::from(expected_err),
+        expected_exit_code,
+    });
+}
+
+#[test]
+fn prs_default_format() {
+    let sfile = sfile();
+    prs_test(
+        &["-r1.1.1.1", &sfile],
+        &format!(
+            "{sfile}:\n\n\
+             D 1.1.1.1 24/07/09 12:00:00 dev 3 1\t00001/00000/00003\n\
+             MRs:\nMR1\nCOMMENTS:\nbranch\n\n"
+        ),
+        "",
+        0,
+    );
+}
+
+#[test]
+fn prs_dataspec_keywords() {
+    prs_test(
+        &[
+            "-d:R:-:L:-:B:-:S: :Dy::Dm::Dd: :Th::Tm: :DI: :Y: :Q: :M:",
+            &sfile(),
+        ],
+        "1-3-0-0 240710 1000 3// prog QQ branched.c\n",
+        "",
+        0,
+    );
+    prs_test(
+        &["-d:W:\\t:BF: :MP: :FB:\\n:FL::F:", "-r1.1", &sfile()],
+        "@(#)branched.c\t1.1\tno none none\n\
+         \tcsect name\tQQ\n\ttype\tprog\n\
+         s.branched.c\n",
+        "",
+        0,
+    );
+}
+
+#[test]
+fn prs_earlier_and_later() {
+    prs_test(&["-d:I:", "-e", "-r1.2", &sfile()], "1.2\n1.1\n", "", 0);
+    prs_test(
+        &["-d:I:", "-l", "-r1.2", &sfile()],
+        "1.3\n1.1.1.1\n1.2\n",
+        "",
+        0,
+    );
+    prs_test(
+        &["-d:I: :C:", "-e", "-c240709113000", &sfile()],
+        "1.2 drop b\n\n1.1 initial\n\n",
+        "",
+        0,
+    );
+}
+
+#[test]
+fn prs_nonexistent_sid() {
+    let sfile = sfile();
+    prs_test(
+        &["-r2.1", &sfile],
+        "",
+        &format!("prs: {sfile}: nonexistent SID 2.1\n"),
+        1,
+    );
+}
@@ -0,0 +1,120 @@
+//
+// Copyright (c) 2024 Hemi Labs, Inc.
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+use crate::{check, run_in};
+use plib::sccsfile::{DeltaType, SccsFile, Sid};
+use plib::testing::{run_test, TestPlan};
+use std::fs;
+use std::path::PathBuf;
+use tempfile::tempdir;
+
+fn rmdel_test(args: &[&str], expected_err: &str, expected_exit_code: i32) {
+    run_test(TestPlan {
+        cmd: String::from("rmdel"),
+        args: args.iter().map(|s| s.to_string()).collect(),
+        stdin_data: String::new(),
+        expected_out: String::new(),
+        expected_err: String::from(expected_err),
+        expected_exit_code,
+    });
+}
+
+#[test]
+fn rmdel_removes_leaf_deltas() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("s.branched.c");
+    let mut fixture = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    fixture.push("tests/s.branched.c");
+    fs::copy(fixture, &path).unwrap();
+    let sfile = path.to_str().unwrap();
+
+    // 1.3 includes 1.1.1.1, and 1.2 has a successor.
+    rmdel_test(
+        &["-r1.1.1.1", sfile],
+        &format!("rmdel: {sfile}: delta 1.1.1.1 is not the newest on its branch\n"),
+        1,
+    );
+    rmdel_test(
+        &["-r1.2", sfile],
+        &format!("rmdel: {sfile}: delta 1.2 is not the newest on its branch\n"),
+        1,
+    );
+    rmdel_test(&["-r1.3", sfile], "", 0);
+    rmdel_test(&["-r1.1.1.1", sfile], "", 0);
+    rmdel_test(
+        &["-r1.3", sfile],
+        &format!("rmdel: {sfile}: nonexistent SID 1.3\n"),
+        1,
+    );
+
+    let file = SccsFile::read(&path).unwrap();
+    assert!(file.checksum_valid());
+    assert_eq!(
+        file.delta(&Sid::new(1, 3)).unwrap().kind,
+        DeltaType::Removed
+    );
+    let body: Vec<&[u8]> = file
+        .body_for_sid(&Sid::new(1, 2), &[], &[])
+        .unwrap()
+        .iter()
+        .map(|l| l.text)
+        .collect();
+    assert_eq!(body, vec![&b"a %I% %W% %A% %C%"[..], b"c"]);
+}
+
+#[test]
+fn rmdel_frees_sid() {
+    let dir = tempdir().unwrap();
+    let dir = dir.path();
+    check(
+        run_in(dir, "admin", &["-i", "-yinitial", "s.notes"], "%I%\n"),
+        "",
+        "",
+        0,
+    );
+    check(
+        run_in(dir, "get", &["-e", "s.notes"], ""),
+        "1.1\nnew delta 1.2\n1 lines\n",
+        "",
+        0,
+    );
+    fs::write(dir.join("notes"), "%I%\nb\n").unwrap();
+    check(
+        run_in(dir, "delta", &["-yfirst", "s.notes"], ""),
+        "1.2\n1 inserted\n0 deleted\n1 unchanged\n",
+        "",
+        0,
+    );
+    check(run_in(dir, "rmdel", &["-r1.2", "s.notes"], ""), "", "", 0);
+
+    // The SID of the removed delta is given to the next one.
+    check(
+        run_in(dir, "get", &["-e", "s.notes"], ""),
+        "1.1\nnew delta 1.2\n1 lines\n",
+        "",
+        0,
+    );
+    fs::write(dir.join("notes"), "%I%\nc\n").unwrap();
+    check(
+        run_in(dir, "delta", &["-ysecond", "s.notes"], ""),
+        "1.2\n1 inserted\n0 deleted\n1 unchanged\n",
+        "",
+        0,
+    );
+
+    let file = SccsFile::read(&dir.join("s.notes")).unwrap();
+    let delta = file.live_delta(&Sid::new(1, 2)).unwrap();
+    assert_eq!(delta.comments, vec!["second"]);
+    check(
+        run_in(dir, "get", &["-p", "-s", "s.notes"], ""),
+        "1.2\nc\n",
+        "",
+        0,
+    );
+}
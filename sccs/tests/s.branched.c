@@ -0,0 +1,39 @@
+h23148
+s 00001/00000/00002
+d D 1.3 24/07/10 10:00:00 dev 4 2
+i 3
+c include the branch
+e
+s 00001/00000/00003
+d D 1.1.1.1 24/07/09 12:00:00 dev 3 1
+m MR1
+c branch
+e
+s 00000/00001/00002
+d D 1.2 24/07/09 11:00:00 dev 2 1
+c drop b
+e
+s 00003/00000/00000
+d D 1.1 24/07/09 10:00:00 dev 1 0
+c initial
+e
+u
+U
+f q QQ
+f t prog
+t
+description
+T
+I 1
+a %I% %W% %A% %C%
+I 4
+x %M% %F% %Y% %Q% %E% %G% %U%
+E 4
+D 2
+b
+E 2
+c
+I 3
+d
+E 3
+E 1
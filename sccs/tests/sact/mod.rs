@@ -0,0 +1,51 @@
+//
+// Copyright (c) 2024 Hemi Labs, Inc.
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+use plib::testing::{run_test, TestPlan};
+use std::fs;
+use tempfile::tempdir;
+
+#[test]
+fn sact_lists_pending_edits() {
+    let dir = tempdir().unwrap();
+    let sfile = dir.path().join("s.prog.c");
+    fs::write(&sfile, "").unwrap();
+    fs::write(
+        dir.path().join("p.prog.c"),
+        "1.3 1.4 dev 24/07/11 09:00:00\n1.1 1.1.2.1 ops 24/07/11 09:30:00 -i1.2\n",
+    )
+    .unwrap();
+    let empty = dir.path().join("s.idle.c");
+    fs::write(&empty, "").unwrap();
+
+    run_test(TestPlan {
+        cmd: String::from("sact"),
+        args: vec![sfile.to_str().unwrap().to_string()],
+        stdin_data: String::new(),
+        expected_out: String::from(
+            "1.3 1.4 dev 24/07/11 09:00:00\n1.1 1.1.2.1 ops 24/07/11 09:30:00\n",
+        ),
+        expected_err: String::new(),
+        expected_exit_code: 0,
+    });
+    run_test(TestPlan {
+        cmd: String::from("sact"),
+        args: vec![
+            empty.to_str().unwrap().to_string(),
+            sfile.to_str().unwrap().to_string(),
+        ],
+        stdin_data: String::new(),
+        expected_out: format!(
+            "\n{}:\n1.3 1.4 dev 24/07/11 09:00:00\n1.1 1.1.2.1 ops 24/07/11 09:30:00\n",
+            sfile.display()
+        ),
+        expected_err: String::new(),
+        expected_exit_code: 0,
+    });
+}
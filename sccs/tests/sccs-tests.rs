@@ -1 +1,56 @@
+//
+// Copyright (c) 2024 Hemi Labs, Inc.
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+use std::env;
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Output, Stdio};
+
+mod admin;
+mod delta;
+mod get;
+mod prs;
+mod rmdel;
+mod sact;
+mod sccs;
+mod unget;
+mod val;
 mod what;
+
+// The SCCS utilities work on g-files in the current directory, so some
+// tests run them from the test's directory rather than through `run_test`.
+pub fn run_in(dir: &Path, cmd: &str, args: &[&str], stdin_data: &str) -> Output {
+    let relpath = if cfg!(debug_assertions) {
+        format!("target/debug/{cmd}")
+    } else {
+        format!("target/release/{cmd}")
+    };
+    let test_bin_path = env::current_dir().unwrap().parent().unwrap().join(relpath);
+    let mut child = Command::new(test_bin_path)
+        .args(args)
+        .current_dir(dir)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(stdin_data.as_bytes())
+        .unwrap();
+    child.wait_with_output().unwrap()
+}
+
+pub fn check(output: Output, expected_out: &str, expected_err: &str, expected_exit_code: i32) {
+    assert_eq!(String::from_utf8_lossy(&output.stdout), expected_out);
+    assert_eq!(String::from_utf8_lossy(&output.stderr), expected_err);
+    assert_eq!(output.status.code(), Some(expected_exit_code));
+}
@@ -0,0 +1,165 @@
+//
+// Copyright (c) 2024 Hemi Labs, Inc.
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+use crate::{check, run_in};
+use std::fs;
+use tempfile::tempdir;
+
+#[test]
+fn sccs_edit_cycle() {
+    let dir = tempdir().unwrap();
+    let dir = dir.path();
+    fs::create_dir(dir.join("SCCS")).unwrap();
+    fs::write(dir.join("prog.c"), "%I%\nmain\n").unwrap();
+
+    check(
+        run_in(dir, "sccs", &["create", "prog.c"], ""),
+        "1.1\n2 lines\n",
+        "",
+        0,
+    );
+    assert!(dir.join("SCCS/s.prog.c").exists());
+    assert!(dir.join(",prog.c").exists());
+    check(
+        run_in(dir, "sccs", &["info"], ""),
+        "Nothing being edited\n",
+        "",
+        0,
+    );
+
+    check(
+        run_in(dir, "sccs", &["edit", "prog.c"], ""),
+        "1.1\nnew delta 1.2\n2 lines\n",
+        "",
+        0,
+    );
+    fs::write(dir.join("prog.c"), "%I%\nmain\nexit\n").unwrap();
+    check(run_in(dir, "sccs", &["tell"], ""), "prog.c\n", "", 0);
+    let output = run_in(dir, "sccs", &["check"], "");
+    assert_eq!(output.status.code(), Some(1));
+    assert!(
+        String::from_utf8_lossy(&output.stdout).starts_with("      prog.c: being edited: 1.1 1.2 ")
+    );
+
+    check(
+        run_in(dir, "sccs", &["delget", "-yadd exit", "prog.c"], ""),
+        "1.2\n1 inserted\n0 deleted\n2 unchanged\n1.2\n3 lines\n",
+        "",
+        0,
+    );
+    check(
+        run_in(dir, "sccs", &["prs", "-d:I: :C:", "prog.c"], ""),
+        "1.2 add exit\n\n",
+        "",
+        0,
+    );
+    check(run_in(dir, "sccs", &["check"], ""), "", "", 0);
+
+    check(run_in(dir, "sccs", &["clean"], ""), "", "", 0);
+    assert!(!dir.join("prog.c").exists());
+}
+
+#[test]
+fn sccs_unedit_and_fix() {
+    let dir = tempdir().unwrap();
+    let dir = dir.path();
+    fs::create_dir(dir.join("SCCS")).unwrap();
+    check(
+        run_in(dir, "admin", &["-i", "SCCS/s.notes"], "%I%\none\n"),
+        "",
+        "",
+        0,
+    );
+
+    run_in(dir, "sccs", &["edit", "notes"], "");
+    fs::write(dir.join("notes"), "%I%\none\ntwo\n").unwrap();
+    check(
+        run_in(dir, "sccs", &["unedit", "notes"], ""),
+        "1.2\n1.1\n2 lines\n",
+        "",
+        0,
+    );
+    assert_eq!(fs::read_to_string(dir.join("notes")).unwrap(), "1.1\none\n");
+
+    run_in(dir, "sccs", &["edit", "notes"], "");
+    fs::write(dir.join("notes"), "%I%\none\ntwo\n").unwrap();
+    run_in(dir, "sccs", &["delta", "-ytwo", "notes"], "");
+    check(
+        run_in(dir, "sccs", &["fix", "-r", "1.2", "notes"], ""),
+        "1.2\n3 lines\n1.1\nnew delta 1.2\n",
+        "",
+        0,
+    );
+    assert_eq!(
+        fs::read_to_string(dir.join("notes")).unwrap(),
+        "%I%\none\ntwo\n"
+    );
+    check(
+        run_in(dir, "sccs", &["prs", "-a", "-d:DT::I:", "-e", "notes"], ""),
+        "R1.2\nD1.1\n",
+        "",
+        0,
+    );
+}
+
+#[test]
+fn sccs_path_options() {
+    let dir = tempdir().unwrap();
+    let root = dir.path().join("project");
+    fs::create_dir_all(root.join("src/RCS")).unwrap();
+    let root_str = root.to_str().unwrap();
+    check(
+        run_in(
+            dir.path(),
+            "sccs",
+            &["-d", root_str, "-p", "RCS", "admin", "-n", "src/lib.c"],
+            "",
+        ),
+        "",
+        &format!("admin: {root_str}/src/RCS/s.lib.c: No id keywords (cm7)\n"),
+        0,
+    );
+    assert!(root.join("src/RCS/s.lib.c").exists());
+
+    check(
+        run_in(dir.path(), "sccs", &["frobnicate"], ""),
+        "",
+        "sccs: unknown command `frobnicate'\n",
+        1,
+    );
+}
+
+#[test]
+fn sccs_diffs() {
+    let dir = tempdir().unwrap();
+    let dir = dir.path();
+    fs::create_dir(dir.join("SCCS")).unwrap();
+    check(
+        run_in(dir, "admin", &["-i", "SCCS/s.notes"], "%I%\none\n"),
+        "",
+        "",
+        0,
+    );
+    run_in(dir, "sccs", &["edit", "notes"], "");
+    fs::write(dir.join("notes"), "%I%\none\ntwo\n").unwrap();
+
+    check(
+        run_in(dir, "sccs", &["diffs", "notes"], ""),
+        "\n------- notes -------\n2a3\n> two\n",
+        "",
+        0,
+    );
+    // The file is unchanged from the delta being edited with -r.
+    check(
+        run_in(dir, "sccs", &["-r", "diffs", "-r1.1", "notes"], ""),
+        "\n------- notes -------\n2a3\n> two\n",
+        "",
+        0,
+    );
+}
@@ -0,0 +1,58 @@
+//
+// Copyright (c) 2024 Hemi Labs, Inc.
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+use plib::testing::{run_test, TestPlan};
+use std::fs;
+use std::path::PathBuf;
+use tempfile::tempdir;
+
+fn run(cmd: &str, args: &[&str], expected_out: &str, expected_err: &str, code: i32) {
+    run_test(TestPlan {
+        cmd: String::from(cmd),
+        args: args.iter().map(|s| s.to_string()).collect(),
+        stdin_data: String::new(),
+        expected_out: String::from(expected_out),
+        expected_err: String::from(expected_err),
+        expected_exit_code: code,
+    });
+}
+
+#[test]
+fn unget_cancels_edits() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("s.branched.c");
+    let mut fixture = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    fixture.push("tests/s.branched.c");
+    fs::copy(fixture, &path).unwrap();
+    let sfile = path.to_str().unwrap();
+    let pfile = dir.path().join("p.branched.c");
+
+    run("get", &["-e", "-g", "-s", sfile], "", "", 0);
+    run("get", &["-e", "-g", "-s", "-r1.1", sfile], "", "", 0);
+    run(
+        "unget",
+        &["-n", sfile],
+        "",
+        &format!("unget: {sfile}: missing -r argument\n"),
+        1,
+    );
+    run("unget", &["-n", "-r1.4", sfile], "1.4\n", "", 0);
+    assert!(fs::read_to_string(&pfile)
+        .unwrap()
+        .starts_with("1.1 1.1.2.1 "));
+    run("unget", &["-n", "-s", sfile], "", "", 0);
+    assert!(!pfile.exists());
+    run(
+        "unget",
+        &["-n", sfile],
+        "",
+        &format!("unget: {sfile}: no outstanding deltas\n"),
+        1,
+    );
+}
@@ -0,0 +1,78 @@
+//
+// Copyright (c) 2024 Hemi Labs, Inc.
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+use plib::testing::{run_test, TestPlan};
+use std::path::PathBuf;
+
+fn sfile() -> String {
+    let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    path.push("tests");
+    path.push("s.branched.c");
+    path.to_str().unwrap().to_string()
+}
+
+fn val_test(args: &[&str], stdin_data: &str, expected_out: &str, expected_exit_code: i32) {
+    run_test(TestPlan {
+        cmd: String::from("val"),
+        args: args.iter().map(|s| s.to_string()).collect(),
+        stdin_data: String::from(stdin_data),
+        expected_out: String::from(expected_out),
+        expected_err: String::new(),
+        expected_exit_code,
+    });
+}
+
+#[test]
+fn val_valid_file() {
+    val_test(
+        &["-r1.1.1.1", "-yprog", "-mbranched.c", &sfile()],
+        "",
+        "",
+        0,
+    );
+}
+
+#[test]
+fn val_mismatches() {
+    let sfile = sfile();
+    val_test(
+        &["-r", "1.9", "-m", "other.c", "-y", "lib", &sfile],
+        "",
+        &format!(
+            "{sfile}: nonexistent SID 1.9\n\
+             {sfile}: %Y%, -y mismatch\n\
+             {sfile}: %M%, -m mismatch\n"
+        ),
+        0x07,
+    );
+    val_test(&["-s", "-r1.2.1", &sfile], "", "", 0x08);
+}
+
+#[test]
+fn val_usage_errors() {
+    val_test(&[], "", "val: missing file argument\n", 0x80);
+    val_test(
+        &["-r1.1", "-r1.2", &sfile()],
+        "",
+        "-r1.2: unknown or duplicate option\n",
+        0x40,
+    );
+    val_test(&["-s", "s.missing"], "", "", 0x10);
+}
+
+#[test]
+fn val_stdin_command_lines() {
+    let sfile = sfile();
+    val_test(
+        &["-"],
+        &format!("-r1.3 {sfile}\n-s -r2.1 {sfile}\n-s -ylib {sfile}\n"),
+        "",
+        0x06,
+    );
+}
@@ -0,0 +1,97 @@
+//
+// Copyright (c) 2024 Hemi Labs, Inc.
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use clap::Parser;
+use gettextrs::{bind_textdomain_codeset, gettext, setlocale, textdomain, LocaleCategory};
+use plib::curuser;
+use plib::sccsfile::{self, LockFile, Sid};
+
+#[derive(Parser)]
+#[command(version, about = gettext("unget - undo a previous get of an SCCS file"))]
+struct Args {
+    #[arg(short = 'r', help = gettext("SID of the new delta that is no longer intended"))]
+    sid: Option<String>,
+
+    #[arg(short = 's', help = gettext("Suppress writing the intended SID"))]
+    silent: bool,
+
+    #[arg(short = 'n', help = gettext("Keep the retrieved g-file"))]
+    keep: bool,
+
+    #[arg(required = true, help = gettext("SCCS files"))]
+    files: Vec<PathBuf>,
+}
+
+fn error(msg: String) -> io::Error {
+    io::Error::other(msg)
+}
+
+fn unget_file(args: &Args, requested: Option<Sid>, path: &Path) -> io::Result<()> {
+    let gfile = sccsfile::gfile_name(path).ok_or_else(|| error(gettext("not an SCCS file")))?;
+    let pfile = sccsfile::aux_file(path, 'p').unwrap();
+    let _lock = LockFile::acquire(path)?;
+
+    let user = curuser::real_user_name();
+    let mut entries = sccsfile::read_pfile(&pfile)?;
+    let candidates: Vec<usize> = (0..entries.len())
+        .filter(|&i| entries[i].user == user)
+        .filter(|&i| requested.map_or(true, |r| entries[i].new == r))
+        .collect();
+    let pos = match candidates[..] {
+        [] => return Err(error(gettext("no outstanding deltas"))),
+        [pos] => pos,
+        _ => return Err(error(gettext("missing -r argument"))),
+    };
+    let entry = entries.remove(pos);
+    sccsfile::write_pfile(&pfile, &entries)?;
+
+    if !args.keep {
+        match fs::remove_file(&gfile) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e),
+            _ => {}
+        }
+    }
+    if !args.silent {
+        println!("{}", entry.new);
+    }
+    Ok(())
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    setlocale(LocaleCategory::LcAll, "");
+    textdomain("posixutils-rs")?;
+    bind_textdomain_codeset("posixutils-rs", "UTF-8")?;
+
+    let args = Args::parse();
+    let requested = match &args.sid {
+        Some(sid) => match sid.parse::<Sid>() {
+            Ok(sid) => Some(sid),
+            Err(e) => {
+                eprintln!("unget: {e}");
+                std::process::exit(1);
+            }
+        },
+        None => None,
+    };
+
+    let mut exit_code = 0;
+
+    for path in sccsfile::sfile_operands(&args.files)? {
+        if let Err(e) = unget_file(&args, requested, &path) {
+            exit_code = 1;
+            eprintln!("unget: {}: {}", path.display(), e);
+        }
+    }
+
+    std::process::exit(exit_code)
+}
@@ -0,0 +1,200 @@
+//
+// Copyright (c) 2024 Hemi Labs, Inc.
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+//! val - validate SCCS files
+//!
+//! The options are parsed by hand rather than with `clap`: an unknown or
+//! repeated option is reported through the exit status like any other
+//! finding, and with the `-` operand each line of standard input is a
+//! separate command line.
+
+use std::io::{self, BufRead};
+use std::path::Path;
+
+use gettextrs::{bind_textdomain_codeset, gettext, setlocale, textdomain, LocaleCategory};
+use plib::sccsfile::{self, DeltaType, SccsFile, Sid};
+
+// Bits of the exit status, most significant first.
+const MISSING_FILE: u8 = 0x80;
+const BAD_OPTION: u8 = 0x40;
+const CORRUPTED: u8 = 0x20;
+const CANNOT_OPEN: u8 = 0x10;
+const INVALID_SID: u8 = 0x08;
+const NONEXISTENT_SID: u8 = 0x04;
+const TYPE_MISMATCH: u8 = 0x02;
+const MODULE_MISMATCH: u8 = 0x01;
+
+#[derive(Default)]
+struct Options {
+    silent: bool,
+    sid: Option<String>,
+    module: Option<String>,
+    module_type: Option<String>,
+    files: Vec<String>,
+}
+
+/// Parse one command line.  On an unknown or repeated option, the
+/// offending argument is returned as the error.
+fn parse_options(args: &[String]) -> Result<Options, String> {
+    let mut opts = Options::default();
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        let Some(opt) = arg.strip_prefix('-').filter(|o| !o.is_empty()) else {
+            opts.files.push(arg.clone());
+            continue;
+        };
+        let letter = opt.chars().next().unwrap();
+        let attached = &opt[letter.len_utf8()..];
+        let value = match letter {
+            's' if attached.is_empty() && !opts.silent => {
+                opts.silent = true;
+                continue;
+            }
+            'r' | 'm' | 'y' if !attached.is_empty() => attached.to_string(),
+            'r' | 'm' | 'y' => iter.next().ok_or_else(|| arg.clone())?.clone(),
+            _ => return Err(arg.clone()),
+        };
+        let slot = match letter {
+            'r' => &mut opts.sid,
+            'm' => &mut opts.module,
+            _ => &mut opts.module_type,
+        };
+        if slot.replace(value).is_some() {
+            return Err(arg.clone());
+        }
+    }
+    Ok(opts)
+}
+
+fn report(opts: &Options, name: &str, msg: &str) {
+    if !opts.silent {
+        println!("{name}: {msg}");
+    }
+}
+
+/// Check that the SID `requested` is well formed and names a delta.
+fn check_sid(file: &SccsFile, requested: &str) -> Result<(), u8> {
+    let sid = match requested.parse::<Sid>() {
+        Ok(sid) if sid.components() != 3 => sid,
+        _ => return Err(INVALID_SID),
+    };
+    let exists = file.deltas.iter().any(|d| {
+        d.kind == DeltaType::Normal
+            && match sid.components() {
+                1 => d.sid.rel == sid.rel,
+                _ => d.sid == sid,
+            }
+    });
+    if exists {
+        Ok(())
+    } else {
+        Err(NONEXISTENT_SID)
+    }
+}
+
+fn validate_file(opts: &Options, name: &str) -> u8 {
+    let path = Path::new(name);
+    let file = match sccsfile::gfile_name(path).map(|_| SccsFile::read(path)) {
+        Some(Ok(file)) => file,
+        Some(Err(e)) if e.kind() == io::ErrorKind::InvalidData => {
+            report(opts, name, &gettext("corrupted SCCS file"));
+            return CORRUPTED;
+        }
+        _ => {
+            report(opts, name, &gettext("cannot open file or file not SCCS"));
+            return CANNOT_OPEN;
+        }
+    };
+    if !file.checksum_valid() {
+        report(opts, name, &gettext("corrupted SCCS file"));
+        return CORRUPTED;
+    }
+
+    let mut status = 0;
+    if let Some(requested) = &opts.sid {
+        match check_sid(&file, requested) {
+            Ok(()) => {}
+            Err(INVALID_SID) => {
+                report(
+                    opts,
+                    name,
+                    &format!("{} {requested}", gettext("invalid or ambiguous SID")),
+                );
+                status |= INVALID_SID;
+            }
+            Err(bit) => {
+                report(
+                    opts,
+                    name,
+                    &format!("{} {requested}", gettext("nonexistent SID")),
+                );
+                status |= bit;
+            }
+        }
+    }
+    if let Some(module_type) = &opts.module_type {
+        if file.flag('t') != Some(module_type.as_str()) {
+            report(opts, name, &gettext("%Y%, -y mismatch"));
+            status |= TYPE_MISMATCH;
+        }
+    }
+    if let Some(module) = &opts.module {
+        let actual = match file.flag('m') {
+            Some(m) => m.to_string(),
+            None => sccsfile::gfile_name(path)
+                .unwrap()
+                .to_string_lossy()
+                .into_owned(),
+        };
+        if actual != *module {
+            report(opts, name, &gettext("%M%, -m mismatch"));
+            status |= MODULE_MISMATCH;
+        }
+    }
+    status
+}
+
+/// Validate the files of one command line, returning the exit status bits.
+fn val_command(args: &[String]) -> u8 {
+    let opts = match parse_options(args) {
+        Ok(opts) => opts,
+        Err(arg) => {
+            println!("{arg}: {}", gettext("unknown or duplicate option"));
+            return BAD_OPTION;
+        }
+    };
+    if opts.files.is_empty() {
+        report(&opts, "val", &gettext("missing file argument"));
+        return MISSING_FILE;
+    }
+    opts.files
+        .iter()
+        .fold(0, |status, name| status | validate_file(&opts, name))
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    setlocale(LocaleCategory::LcAll, "");
+    textdomain("posixutils-rs")?;
+    bind_textdomain_codeset("posixutils-rs", "UTF-8")?;
+
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    let status = if args.len() == 1 && args[0] == "-" {
+        let mut status = 0;
+        for line in io::stdin().lock().lines() {
+            let words: Vec<String> = line?.split_whitespace().map(String::from).collect();
+            status |= val_command(&words);
+        }
+        status
+    } else {
+        val_command(&args)
+    };
+
+    std::process::exit(i32::from(status))
+}
@@ -11,12 +11,12 @@
 // - add tests
 //
 
-use std::io::{self, BufRead};
+use std::io::{self, Write};
 use std::path::PathBuf;
 
 use clap::Parser;
 use gettextrs::{bind_textdomain_codeset, gettext, setlocale, textdomain, LocaleCategory};
-use plib::io::input_reader;
+use plib::io::input_byte_lines;
 
 /// asa - interpret carriage-control characters
 #[derive(Parser)]
@@ -28,7 +28,7 @@ struct Args {
 
 struct AsaState {
     first_line: bool,
-    lines: Vec<String>,
+    lines: Vec<Vec<u8>>,
 }
 
 impl Default for AsaState {
@@ -41,8 +41,8 @@ impl Default for AsaState {
 }
 
 impl AsaState {
-    fn push(&mut self, line: &str) {
-        self.lines.push(line.to_string());
+    fn push(&mut self, line: &[u8]) {
+        self.lines.push(line.to_vec());
         if self.first_line {
             self.first_line = false;
         }
@@ -54,71 +54,64 @@ impl AsaState {
         }
     }
 
-    fn flush(&mut self) {
-        let mut nl = String::new();
-        for line in &self.lines {
-            print!("{}{}", nl, line);
-
+    fn flush(&mut self) -> io::Result<()> {
+        let mut stdout = io::stdout().lock();
+        for (i, line) in self.lines.iter().enumerate() {
             // do not prefix with newline on first line
-            if nl.is_empty() {
-                nl = "\n".to_string();
+            if i > 0 {
+                stdout.write_all(b"\n")?;
             }
+            stdout.write_all(line)?;
         }
 
         self.lines.clear();
+        Ok(())
     }
 }
 
 fn asa_file(pathname: &PathBuf) -> io::Result<()> {
-    let mut reader = input_reader(pathname, false)?;
+    let mut reader = input_byte_lines(pathname, false)?;
     let mut line_no: usize = 0;
     let mut state = AsaState::default();
 
     loop {
         line_no += 1;
 
-        let mut raw_line = String::new();
-        let n_read = reader.read_line(&mut raw_line)?;
-        if n_read == 0 {
+        let Some(raw_line) = reader.next_line()? else {
             break;
-        }
+        };
 
-        if raw_line.len() < 2 {
+        if raw_line.is_empty() {
             eprintln!("{} {}", gettext("malformed line"), line_no);
             continue;
         }
 
-        let ch = raw_line.chars().next().unwrap();
-
-        // exclude first char, and trailing newline
-        let mut line_len = raw_line.len() - 1;
-        if raw_line.ends_with('\n') {
-            line_len -= 1;
-        }
-        let line = &raw_line[1..line_len];
+        // exclude first char; the trailing newline is already removed
+        let ch = raw_line[0];
+        let line = &raw_line[1..];
 
         match ch {
-            '+' => {
+            b'+' => {
                 state.push(line);
             }
-            '0' => {
-                state.flush();
+            b'0' => {
+                state.flush()?;
                 println!();
                 state.push(line);
             }
-            '-' => {
-                state.flush();
+            b'-' => {
+                state.flush()?;
                 println!();
                 println!();
                 state.push(line);
             }
-            '1' => {
-                state.flush();
+            b'1' => {
+                state.flush()?;
                 state.formfeed();
                 state.push(line);
             }
             _ => {
-                state.flush();
+                state.flush()?;
                 state.push(line);
             }
         };
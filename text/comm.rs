@@ -9,8 +9,10 @@
 
 use clap::Parser;
 use gettextrs::{bind_textdomain_codeset, setlocale, textdomain, LocaleCategory};
-use std::fs;
-use std::io::{self, BufRead, Write};
+use plib::io::{input_byte_lines, ByteLines};
+use plib::locale;
+use std::cmp::Ordering;
+use std::io::{self, Read, Write};
 use std::path::PathBuf;
 
 const NO1: u32 = 1 << 0;
@@ -40,40 +42,42 @@ struct Args {
     file2: PathBuf,
 }
 
-fn line_out(lead_dup: &'static str, outmask: u32, curtype: u32, s: &str) -> io::Result<()> {
+fn line_out(lead_dup: &'static str, outmask: u32, curtype: u32, s: &[u8]) -> io::Result<()> {
     if (outmask & curtype) != 0 {
         return Ok(());
     }
 
-    match curtype {
-        NO1 => {
-            io::stdout().write_all(s.as_bytes())?;
-        }
+    let lead = match curtype {
+        NO1 => "",
         NO2 => {
-            let lead_f2 = {
-                if (outmask & NO1) != 0 {
-                    ""
-                } else {
-                    "\t"
-                }
-            };
-            let newstr = format!("{}{}", lead_f2, s);
-            io::stdout().write_all(newstr.as_bytes())?;
-        }
-        NODUP => {
-            let newstr = format!("{}{}", lead_dup, s);
-            io::stdout().write_all(newstr.as_bytes())?;
+            if (outmask & NO1) != 0 {
+                ""
+            } else {
+                "\t"
+            }
         }
+        NODUP => lead_dup,
         _ => {
             panic!("should never occur");
         }
-    }
+    };
+
+    let mut stdout = io::stdout().lock();
+    stdout.write_all(lead.as_bytes())?;
+    stdout.write_all(s)?;
+    stdout.write_all(b"\n")?;
 
     Ok(())
 }
 
-fn open_file(pathname: &PathBuf) -> io::Result<io::BufReader<fs::File>> {
-    Ok(io::BufReader::new(fs::File::open(pathname)?))
+fn read_next(rdr: &mut ByteLines<Box<dyn Read>>, buf: &mut Vec<u8>) -> io::Result<bool> {
+    match rdr.next_line()? {
+        Some(line) => {
+            buf.extend_from_slice(line);
+            Ok(true)
+        }
+        None => Ok(false),
+    }
 }
 
 fn comm_file(
@@ -83,46 +87,51 @@ fn comm_file(
     file2name: &PathBuf,
 ) -> io::Result<()> {
     // open files, or stdin
-    let mut rdr1 = open_file(file1name)?;
-    let mut rdr2 = open_file(file2name)?;
+    let mut rdr1 = input_byte_lines(file1name, true)?;
+    let mut rdr2 = input_byte_lines(file2name, true)?;
 
-    let mut buf1 = String::new();
-    let mut buf2 = String::new();
+    let mut buf1 = Vec::new();
+    let mut buf2 = Vec::new();
+    let mut have1 = false;
+    let mut have2 = false;
     let mut want1 = true;
     let mut want2 = true;
 
     loop {
-        if want1 && buf1.is_empty() {
-            if rdr1.read_line(&mut buf1)? == 0 {
-                want1 = false;
-            }
+        if want1 && !have1 {
+            have1 = read_next(&mut rdr1, &mut buf1)?;
+            want1 = have1;
         }
-        if want2 && buf2.is_empty() {
-            if rdr2.read_line(&mut buf2)? == 0 {
-                want2 = false;
-            }
+        if want2 && !have2 {
+            have2 = read_next(&mut rdr2, &mut buf2)?;
+            want2 = have2;
         }
 
-        if buf1.is_empty() && buf2.is_empty() {
+        if !have1 && !have2 {
             break;
         }
 
-        if buf1.is_empty() {
+        // lines are ordered by the collating sequence, as sort(1) leaves them
+        let order = if have1 && have2 {
+            locale::strcoll(&buf1, &buf2).then_with(|| buf1.cmp(&buf2))
+        } else {
+            Ordering::Equal
+        };
+
+        if !have1 || (have2 && order == Ordering::Greater) {
             line_out(lead_dup, mask, NO2, &buf2)?;
             buf2.clear();
-        } else if buf2.is_empty() {
+            have2 = false;
+        } else if !have2 || order == Ordering::Less {
             line_out(lead_dup, mask, NO1, &buf1)?;
             buf1.clear();
-        } else if buf1 < buf2 {
-            line_out(lead_dup, mask, NO1, &buf1)?;
-            buf1.clear();
-        } else if buf2 < buf1 {
-            line_out(lead_dup, mask, NO2, &buf2)?;
-            buf2.clear();
+            have1 = false;
         } else {
             line_out(lead_dup, mask, NODUP, &buf1)?;
             buf1.clear();
             buf2.clear();
+            have1 = false;
+            have2 = false;
         }
     }
 
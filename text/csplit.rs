@@ -12,9 +12,10 @@
 
 use clap::Parser;
 use gettextrs::{bind_textdomain_codeset, setlocale, textdomain, LocaleCategory};
-use regex::Regex;
+use plib::io::ByteLines;
+use plib::regex::{MatchFlags, Regex, RegexFlags};
 use std::fs::{self, File, OpenOptions};
-use std::io::{self, BufRead, Error, ErrorKind, Read, Write};
+use std::io::{self, Error, ErrorKind, Read, Write};
 use std::path::PathBuf;
 
 /// csplit - split files based on context
@@ -191,32 +192,33 @@ fn csplit_file(args: &Args, ctx: SplitOps, new_files: &mut Vec<String>) -> io::R
         }
     };
     let mut state = OutputState::new(&args.prefix, args.num);
-    let mut reader = io::BufReader::new(file);
+    let mut reader = ByteLines::new(file);
 
-    let mut lines = String::new();
+    let mut lines = Vec::new();
 
     loop {
-        let mut line = String::new();
-        let n_read = reader.read_line(&mut line)?;
-        if n_read == 0 {
-            process_lines(&mut lines, &mut state, new_files, args.suppress)?;
-            break;
-        }
+        let mut line = match reader.next_record()? {
+            Some(line) => line.to_vec(),
+            None => {
+                process_lines(&lines, &mut state, new_files, args.suppress)?;
+                break;
+            }
+        };
 
         if split_options.is_empty() {
-            lines.push_str(&line);
+            lines.extend_from_slice(&line);
             continue;
         }
         match split_options.first().unwrap() {
             Operand::LineNum(num) => {
                 if *num == state.in_line_no {
-                    if lines.ends_with('\n') && lines != "\n" {
+                    if lines.ends_with(b"\n") && lines != b"\n" {
                         lines.pop();
                     }
-                    process_lines(&mut lines, &mut state, new_files, args.suppress)?;
+                    process_lines(&lines, &mut state, new_files, args.suppress)?;
                     state.in_line_no = 1;
-                    lines = String::new();
-                    lines.push_str(&line);
+                    lines = Vec::new();
+                    lines.extend_from_slice(&line);
 
                     if split_options.len() > 1 {
                         if let Operand::Repeat(repeat) = &mut split_options[1] {
@@ -228,68 +230,72 @@ fn csplit_file(args: &Args, ctx: SplitOps, new_files: &mut Vec<String>) -> io::R
                         }
                     }
                 } else {
-                    lines.push_str(&line);
+                    lines.extend_from_slice(&line);
                 }
             }
             Operand::Rx(regex, offset, skip) => {
-                if line == "\n" {
-                    line = String::new();
+                if line == b"\n" {
+                    line = Vec::new();
                 }
-                if regex.is_match(&line) {
+                let text = line.strip_suffix(b"\n").unwrap_or(&line);
+                if regex.is_match(text) {
                     match offset.cmp(&0) {
                         std::cmp::Ordering::Less => {
-                            let mut lines_vec: Vec<&str> = lines.lines().collect();
-
-                            let mut removed_lines_string = String::new();
+                            let mut lines_vec: Vec<&[u8]> = if lines.is_empty() {
+                                Vec::new()
+                            } else {
+                                lines
+                                    .strip_suffix(b"\n")
+                                    .unwrap_or(&lines)
+                                    .split(|&b| b == b'\n')
+                                    .collect()
+                            };
+
+                            let mut removed_lines_string = Vec::new();
                             let length = lines_vec.len();
                             if length >= offset.unsigned_abs() {
                                 let removed_lines =
                                     lines_vec.split_off(length - offset.unsigned_abs());
-                                removed_lines_string = removed_lines.join("\n");
+                                removed_lines_string = removed_lines.join(&b'\n');
 
-                                removed_lines_string.push('\n');
+                                removed_lines_string.push(b'\n');
                             }
 
-                            lines = lines_vec.join("\n");
+                            lines = lines_vec.join(&b'\n');
                             if !lines.is_empty() {
                                 if *skip {
                                     lines.clear();
                                 } else {
-                                    if lines.ends_with('\n') {
+                                    if lines.ends_with(b"\n") {
                                         lines.pop();
                                     }
-                                    process_lines(
-                                        &mut lines,
-                                        &mut state,
-                                        new_files,
-                                        args.suppress,
-                                    )?;
+                                    process_lines(&lines, &mut state, new_files, args.suppress)?;
                                 }
                             }
 
                             lines = removed_lines_string;
 
                             if line.is_empty() {
-                                lines.push('\n');
+                                lines.push(b'\n');
                             } else {
-                                lines.push_str(&line);
+                                lines.extend_from_slice(&line);
                             }
                         }
                         std::cmp::Ordering::Equal => {
                             if *skip {
                                 lines.clear();
                                 if line.is_empty() {
-                                    line = "\n".to_string();
+                                    line = b"\n".to_vec();
                                 }
-                                lines.push_str(&line);
+                                lines.extend_from_slice(&line);
                             } else {
-                                if lines.ends_with('\n') {
+                                if lines.ends_with(b"\n") {
                                     lines.pop();
                                 }
-                                process_lines(&mut lines, &mut state, new_files, args.suppress)?;
+                                process_lines(&lines, &mut state, new_files, args.suppress)?;
 
                                 if line.is_empty() {
-                                    lines = "\n".to_string();
+                                    lines = b"\n".to_vec();
                                 } else {
                                     lines = line;
                                 }
@@ -297,33 +303,26 @@ fn csplit_file(args: &Args, ctx: SplitOps, new_files: &mut Vec<String>) -> io::R
                         }
                         std::cmp::Ordering::Greater => {
                             if line.is_empty() {
-                                lines.push('\n');
+                                lines.push(b'\n');
                             } else {
-                                lines.push_str(&line);
+                                lines.extend_from_slice(&line);
                             }
                             for _ in 0..*offset - 1 {
-                                let mut new_line = String::new();
-                                let n_read = reader.read_line(&mut new_line)?;
-                                if n_read == 0 {
-                                    process_lines(
-                                        &mut lines,
-                                        &mut state,
-                                        new_files,
-                                        args.suppress,
-                                    )?;
+                                let Some(new_line) = reader.next_record()? else {
+                                    process_lines(&lines, &mut state, new_files, args.suppress)?;
                                     break;
-                                }
-                                lines.push_str(&new_line);
+                                };
+                                lines.extend_from_slice(new_line);
                             }
 
                             if *skip {
                                 lines.clear();
                             } else {
-                                if lines.ends_with('\n') {
+                                if lines.ends_with(b"\n") {
                                     lines.pop();
                                 }
-                                process_lines(&mut lines, &mut state, new_files, args.suppress)?;
-                                lines = String::new();
+                                process_lines(&lines, &mut state, new_files, args.suppress)?;
+                                lines = Vec::new();
                             }
                         }
                     }
@@ -347,9 +346,9 @@ fn csplit_file(args: &Args, ctx: SplitOps, new_files: &mut Vec<String>) -> io::R
                     }
                 } else {
                     if line.is_empty() {
-                        line = "\n".to_string();
+                        line = b"\n".to_vec();
                     }
-                    lines.push_str(&line);
+                    lines.extend_from_slice(&line);
                 }
             }
             _ => {}
@@ -379,13 +378,13 @@ fn csplit_file(args: &Args, ctx: SplitOps, new_files: &mut Vec<String>) -> io::R
 ///   or an `io` error.
 ///
 fn process_lines(
-    lines: &mut String,
+    lines: &[u8],
     state: &mut OutputState,
     new_files: &mut Vec<String>,
     suppress: bool,
 ) -> io::Result<()> {
     let file_name = state.open_output()?;
-    state.outf.as_mut().unwrap().write_all(lines.as_bytes())?;
+    state.outf.as_mut().unwrap().write_all(lines)?;
     new_files.push(file_name);
     if !suppress {
         println!("{}\n", lines.len());
@@ -451,7 +450,7 @@ fn parse_op_rx(opstr: &str, delim: char) -> io::Result<Operand> {
     // parse string sandwiched between two delimiter chars
     let end_pos = res.unwrap();
     let re_str = &opstr[1..end_pos];
-    let res = Regex::new(re_str);
+    let res = Regex::new(re_str.as_bytes(), RegexFlags::bre().no_sub(true));
     if res.is_err() {
         return Err(Error::new(ErrorKind::Other, "invalid regex"));
     }
@@ -498,13 +497,13 @@ fn parse_op_rx(opstr: &str, delim: char) -> io::Result<Operand> {
 ///
 fn parse_op_repeat(opstr: &str) -> io::Result<Operand> {
     // a regex fully describes what must be parsed
-    let re = Regex::new(r"^\{(\d*|[*])}$").unwrap();
+    let re = Regex::new(br"^\{([0-9]*|[*])\}$", RegexFlags::ere()).unwrap();
 
     // grab and parse capture #1, if matched
-    match re.captures(opstr) {
+    match re.exec(opstr.as_bytes(), MatchFlags::default()) {
         None => {}
         Some(caps) => {
-            let numstr = caps.get(1).unwrap().as_str();
+            let numstr = &opstr[caps[1].clone().unwrap()];
             if numstr == "*" {
                 return Ok(Operand::Repeat(usize::MAX));
             }
@@ -663,7 +662,7 @@ mod tests {
         let delim = '/';
         match parse_op_rx(opstr, delim) {
             Ok(Operand::Rx(regex, offset, is_skip)) => {
-                assert_eq!(regex.as_str(), "pattern");
+                assert_eq!(regex.pattern(), b"pattern");
                 assert_eq!(offset, 0);
                 assert!(!is_skip);
             }
@@ -677,7 +676,7 @@ mod tests {
         let delim = '/';
         match parse_op_rx(opstr, delim) {
             Ok(Operand::Rx(regex, offset, is_skip)) => {
-                assert_eq!(regex.as_str(), "pattern");
+                assert_eq!(regex.pattern(), b"pattern");
                 assert_eq!(offset, 3);
                 assert!(!is_skip);
             }
@@ -691,7 +690,7 @@ mod tests {
         let delim = '/';
         match parse_op_rx(opstr, delim) {
             Ok(Operand::Rx(regex, offset, is_skip)) => {
-                assert_eq!(regex.as_str(), "pattern");
+                assert_eq!(regex.pattern(), b"pattern");
                 assert_eq!(offset, -2);
                 assert!(!is_skip);
             }
@@ -705,7 +704,7 @@ mod tests {
         let delim = '/';
         match parse_op_rx(opstr, delim) {
             Ok(Operand::Rx(regex, offset, is_skip)) => {
-                assert_eq!(regex.as_str(), "pattern");
+                assert_eq!(regex.pattern(), b"pattern");
                 assert_eq!(offset, 5);
                 assert!(!is_skip);
             }
@@ -719,7 +718,7 @@ mod tests {
         let delim = '%';
         match parse_op_rx(opstr, delim) {
             Ok(Operand::Rx(regex, offset, is_skip)) => {
-                assert_eq!(regex.as_str(), "pattern");
+                assert_eq!(regex.pattern(), b"pattern");
                 assert_eq!(offset, 0);
                 assert!(is_skip);
             }
@@ -816,14 +815,14 @@ mod tests {
                 assert_eq!(ops.ops.len(), 4);
                 match &ops.ops[0] {
                     Operand::Rx(re, offset, _) => {
-                        assert_eq!(re.as_str(), "pattern");
+                        assert_eq!(re.pattern(), b"pattern");
                         assert_eq!(*offset, 1);
                     }
                     _ => panic!("Expected Operand::Rx"),
                 }
                 match &ops.ops[1] {
                     Operand::Rx(re, offset, _) => {
-                        assert_eq!(re.as_str(), "skip");
+                        assert_eq!(re.pattern(), b"skip");
                         assert_eq!(*offset, 10);
                     }
                     _ => panic!("Expected Operand::Rx"),
@@ -901,7 +900,7 @@ mod tests {
             suppress: false,
             filename: PathBuf::from("tests/assets/test_file_c"),
             operands: vec![
-                String::from(r"%main\(%"),
+                String::from(r"%main(%"),
                 String::from("/^}/+1"),
                 String::from("{3}"),
             ],
@@ -958,7 +957,7 @@ mod tests {
             suppress: false,
             filename: PathBuf::from("tests/assets/test_file_c"),
             operands: vec![
-                String::from(r"%main\(%+1"),
+                String::from(r"%main(%+1"),
                 String::from("/^}/+1"),
                 String::from("{3}"),
             ],
@@ -1014,7 +1013,7 @@ mod tests {
             suppress: false,
             filename: PathBuf::from("tests/assets/test_file_c"),
             operands: vec![
-                String::from(r"%main\(%-1"),
+                String::from(r"%main(%-1"),
                 String::from("/^}/+1"),
                 String::from("{3}"),
             ],
@@ -1071,7 +1070,7 @@ mod tests {
             suppress: false,
             filename: PathBuf::from("tests/assets/test_file_c"),
             operands: vec![
-                String::from(r"%main\(%"),
+                String::from(r"%main(%"),
                 String::from("/^}/"),
                 String::from("{3}"),
             ],
@@ -1128,7 +1127,7 @@ mod tests {
             suppress: false,
             filename: PathBuf::from("tests/assets/test_file_c"),
             operands: vec![
-                String::from(r"%main\(%"),
+                String::from(r"%main(%"),
                 String::from("/^}/-1"),
                 String::from("{3}"),
             ],
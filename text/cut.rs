@@ -7,10 +7,11 @@
 // SPDX-License-Identifier: MIT
 //
 
-use std::io::{self, BufRead, Error, ErrorKind, Read};
+use std::io::{self, Error, ErrorKind, Read, Write};
 
 use clap::Parser;
 use gettextrs::{bind_textdomain_codeset, setlocale, textdomain, LocaleCategory};
+use plib::io::ByteLines;
 use std::path::PathBuf;
 
 /// Cut - cut out selected fields of each line of a file
@@ -223,25 +224,32 @@ fn cut_characters(line: &str, delim: Option<char>, ranges: &Vec<(i32, i32)>) ->
 ///
 /// # Arguments
 ///
-/// * `line` - A string representing the input line.
+/// * `line` - A slice of bytes representing the input line.
 /// * `delim` - A character delimiter used to split the line into fields.
 /// * `ranges` - A vector of tuples representing the start and end indices of the fields to cut.
 /// * `suppress` - A boolean flag indicating whether to suppress output if no fields are found.
 ///
 /// # Returns
 ///
-/// A tuple containing the resulting string with the selected fields and a boolean indicating
+/// A tuple containing the resulting bytes with the selected fields and a boolean indicating
 /// whether the output was suppressed.
 ///
-fn cut_fields(line: &str, delim: char, ranges: &Vec<(i32, i32)>, suppress: bool) -> (String, bool) {
-    let mut result = String::new();
+fn cut_fields(
+    line: &[u8],
+    delim: char,
+    ranges: &Vec<(i32, i32)>,
+    suppress: bool,
+) -> (Vec<u8>, bool) {
+    let mut result = Vec::new();
     let mut skip = false;
+    let mut delim_buf = [0; 4];
+    let delim_bytes = delim.encode_utf8(&mut delim_buf).as_bytes();
     let delim_escaped = delim.escape_debug().to_string();
-    let mut fields: Vec<&str>;
+    let mut fields: Vec<&[u8]>;
     if delim_escaped.len() > 1 {
-        fields = line.split(&delim_escaped).collect();
+        fields = split_bytes(line, delim_escaped.as_bytes());
     } else {
-        fields = line.split(delim).collect();
+        fields = split_bytes(line, delim_bytes);
     }
 
     if fields.len() == 1 {
@@ -254,19 +262,21 @@ fn cut_fields(line: &str, delim: char, ranges: &Vec<(i32, i32)>, suppress: bool)
 
         if fields.get(start).is_some() {
             if start == end {
-                result.push_str(fields[start]);
-                result.push(delim);
+                result.extend_from_slice(fields[start]);
+                result.extend_from_slice(delim_bytes);
             } else {
                 for i in fields.iter().take(end + 1).skip(start) {
-                    result.push_str(i);
-                    result.push(delim);
+                    result.extend_from_slice(i);
+                    result.extend_from_slice(delim_bytes);
                 }
             }
         }
     }
-    result.pop();
+    if !result.is_empty() {
+        result.truncate(result.len() - delim_bytes.len());
+    }
     if result.is_empty() && fields.is_empty() && !suppress {
-        result.push_str(line);
+        result.extend_from_slice(line);
     }
     if result.is_empty() && fields.is_empty() && suppress {
         skip = true;
@@ -274,6 +284,24 @@ fn cut_fields(line: &str, delim: char, ranges: &Vec<(i32, i32)>, suppress: bool)
     (result, skip)
 }
 
+/// Splits `line` on every occurrence of the byte string `sep`.
+fn split_bytes<'a>(line: &'a [u8], sep: &[u8]) -> Vec<&'a [u8]> {
+    let mut fields = Vec::new();
+    let mut start = 0;
+    let mut i = 0;
+    while i + sep.len() <= line.len() {
+        if line[i..].starts_with(sep) {
+            fields.push(&line[start..i]);
+            i += sep.len();
+            start = i;
+        } else {
+            i += 1;
+        }
+    }
+    fields.push(&line[start..]);
+    fields
+}
+
 /// Processes files according to the provided arguments, cutting out selected fields, characters, or bytes.
 ///
 /// # Arguments
@@ -302,9 +330,11 @@ fn cut_files(args: Args) -> Result<(), Box<dyn std::error::Error>> {
             bufs
         };
 
+    let mut stdout = io::stdout().lock();
+
     // Process each file
     for file in readers {
-        let reader = io::BufReader::new(file);
+        let mut reader = ByteLines::new(file);
 
         let parse_option;
 
@@ -326,30 +356,30 @@ fn cut_files(args: Args) -> Result<(), Box<dyn std::error::Error>> {
             return Err(Box::new(Error::new(ErrorKind::Other, "Invalid arguments")));
         }
 
-        for line in reader.lines() {
-            let line = line?;
-            match parse_option.clone() {
+        while let Some(line) = reader.next_line()? {
+            let output = match &parse_option {
                 ParseVariat::Bytes(ranges) => {
-                    let bytes = cut_bytes(line.as_bytes(), args.delimiter, &ranges, args.no_split);
-                    match String::from_utf8(bytes) {
-                        Ok(string) => println!("{}", string),
-                        Err(e) => eprintln!("Conversion error to string: {}", e),
-                    }
-                }
-                ParseVariat::Characters(ranges) => {
-                    println!("{}", cut_characters(&line, args.delimiter, &ranges))
+                    cut_bytes(line, args.delimiter, ranges, args.no_split)
                 }
+                ParseVariat::Characters(ranges) => match std::str::from_utf8(line) {
+                    Ok(line) => cut_characters(line, args.delimiter, ranges).into_bytes(),
+                    // Not a valid character string: treat each byte as a character
+                    Err(_) => cut_bytes(line, args.delimiter, ranges, false),
+                },
                 ParseVariat::Fields(ranges) => {
                     if let Some(delim) = args.delimiter {
-                        let result = cut_fields(&line, delim, &ranges, args.suppress);
-                        if !result.1 {
-                            println!("{}", result.0)
+                        let (result, skip) = cut_fields(line, delim, ranges, args.suppress);
+                        if skip {
+                            continue;
                         }
+                        result
                     } else {
-                        println!("{}", line);
+                        line.to_vec()
                     }
                 }
-            }
+            };
+            stdout.write_all(&output)?;
+            stdout.write_all(b"\n")?;
         }
     }
     Ok(())
@@ -11,7 +11,6 @@ use crate::diff_util::constants::NO_NEW_LINE_AT_END_OF_FILE;
 
 use std::{
     cmp::Reverse,
-    collections::HashMap,
     fmt::Write,
     fs::{read_to_string, File},
     io::{self, BufReader, Read},
@@ -80,18 +79,9 @@ impl<'a> FileDiff<'a> {
             let mut diff = FileDiff::new(&mut file1, &mut file2, format_options);
 
             // histogram diff
-            let mut lcs_indices: Vec<i32> = vec![-1; diff.file1.lines().len()];
             let num_lines1 = diff.file1.lines().len();
             let num_lines2 = diff.file2.lines().len();
-            FileDiff::histogram_lcs(
-                diff.file1,
-                diff.file2,
-                0,
-                num_lines1,
-                0,
-                num_lines2,
-                &mut lcs_indices,
-            );
+            let lcs_indices = plib::diff::histogram_lcs(diff.file1.lines(), diff.file2.lines());
 
             diff.hunks
                 .create_hunks_from_lcs(&lcs_indices, num_lines1, num_lines2);
@@ -215,81 +205,6 @@ impl<'a> FileDiff<'a> {
         }
     }
 
-    /// Longest common subsequence (LCS) algorithm by recursively building a histogram.
-    /// Indices in lcs_indices will be the line number in file1 while values will
-    /// be the corresponding line number in file2. If the value is -1, then the
-    /// line is not part of the LCS.
-    pub fn histogram_lcs(
-        file1: &FileData,
-        file2: &FileData,
-        mut x0: usize,
-        mut x1: usize,
-        mut y0: usize,
-        mut y1: usize,
-        lcs_indices: &mut Vec<i32>,
-    ) {
-        // collect common elements at the beginning
-        while (x0 < x1) && (y0 < y1) && (file1.line(x0) == file2.line(y0)) {
-            lcs_indices[x0] = y0 as i32;
-            x0 += 1;
-            y0 += 1;
-        }
-
-        if (x0 == x1) || (y0 == y1) {
-            // we can return early
-            return;
-        }
-
-        // collect common elements at the end
-        while (x0 < x1) && (y0 < y1) && (file1.line(x1 - 1) == file2.line(y1 - 1)) {
-            lcs_indices[x1 - 1] = (y1 - 1) as i32;
-            x1 -= 1;
-            y1 -= 1;
-        }
-
-        // build histogram
-        let mut hist: HashMap<&str, Vec<i32>> = HashMap::new();
-        for i in x0..x1 {
-            if let Some(rec) = hist.get_mut(file1.line(i)) {
-                rec[0] += 1_i32;
-                rec[1] = i as i32;
-            } else {
-                hist.insert(file1.line(i), vec![1, i as i32, 0, -1]);
-            }
-        }
-        for i in y0..y1 {
-            if let Some(rec) = hist.get_mut(file2.line(i)) {
-                rec[2] += 1_i32;
-                rec[3] = i as i32;
-            } else {
-                hist.insert(file2.line(i), vec![0, -1, 1, i as i32]);
-            }
-        }
-
-        // find lowest-occurrence item that appears in both files
-        let key = hist
-            .iter()
-            .filter(|(_k, v)| (v[0] > 0) && (v[2] > 0))
-            .min_by(|a, b| {
-                let c = a.1[0] + a.1[2];
-                let d = b.1[0] + b.1[2];
-                c.cmp(&d)
-            })
-            .map(|(k, _v)| *k);
-
-        match key {
-            None => {}
-            Some(k) => {
-                let rec = hist.get(k).unwrap();
-                let x1_new = rec[1] as usize;
-                let y1_new = rec[3] as usize;
-                lcs_indices[x1_new] = y1_new as i32;
-                FileDiff::histogram_lcs(file1, file2, x0, x1_new, y0, y1_new, lcs_indices);
-                FileDiff::histogram_lcs(file1, file2, x1_new + 1, x1, y1_new + 1, y1, lcs_indices);
-            }
-        }
-    }
-
     fn order_hunks_by_output_format(&mut self) {
         match self.format_options.output_format {
             OutputFormat::Debug => self.order_hunks_ascending(),
@@ -260,8 +260,8 @@ impl Hunks {
             self.add_hunk(hunk_start1, hunk_end1, hunk_start2, hunk_end2);
         } else if lcs_indices[lcs_indices.len() - 1] < ((num_lines2 - 1) as i32) {
             // there might be some insertions after the last lcs block
-            hunk_start1 = num_lines1 - 1;
-            hunk_end1 = num_lines1 - 1;
+            hunk_start1 = num_lines1;
+            hunk_end1 = num_lines1;
             hunk_start2 = (lcs_indices[lcs_indices.len() - 1] + 1) as usize;
             hunk_end2 = num_lines2;
             self.add_hunk(hunk_start1, hunk_end1, hunk_start2, hunk_end2);
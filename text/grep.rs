@@ -9,13 +9,12 @@
 
 use clap::Parser;
 use gettextrs::{bind_textdomain_codeset, setlocale, textdomain, LocaleCategory};
-use libc::{regcomp, regex_t, regexec, regfree, REG_EXTENDED, REG_ICASE, REG_NOMATCH};
+use plib::io::ByteLines;
+use plib::regex::{Regex, RegexFlags};
 use std::{
-    ffi::CString,
     fs::File,
-    io::{self, BufRead, BufReader},
+    io::{self, Read, Write},
     path::{Path, PathBuf},
-    ptr,
 };
 
 /// grep - search a file for a pattern.
@@ -81,6 +80,10 @@ struct Args {
     #[arg(name = "FILE")]
     input_files: Vec<String>,
 
+    /// The patterns of `-e`, `-f` and the operand, one per line.
+    #[arg(skip)]
+    patterns: Vec<Vec<u8>>,
+
     #[arg(skip)]
     any_errors: bool,
 }
@@ -112,9 +115,14 @@ impl Args {
 
     /// Resolves input patterns and input files. Reads patterns from pattern files and merges them with specified as argument. Handles input files if empty.
     fn resolve(&mut self) {
+        let mut patterns: Vec<Vec<u8>> = self
+            .regexp
+            .iter()
+            .map(|pattern| pattern.clone().into_bytes())
+            .collect();
         for path_buf in &self.file {
             match Self::get_file_patterns(path_buf) {
-                Ok(patterns) => self.regexp.extend(patterns),
+                Ok(file_patterns) => patterns.extend(file_patterns),
                 Err(err) => {
                     self.any_errors = true;
                     if !self.no_messages {
@@ -127,21 +135,20 @@ impl Args {
         match &self.single_pattern {
             None => {}
             Some(pattern) => {
-                if !self.regexp.is_empty() {
+                if !patterns.is_empty() {
                     self.input_files.insert(0, pattern.clone());
                 } else {
-                    self.regexp = vec![pattern.clone()];
+                    patterns = vec![pattern.clone().into_bytes()];
                 }
             }
         }
 
-        self.regexp = self
-            .regexp
+        self.patterns = patterns
             .iter()
-            .flat_map(|pattern| pattern.split('\n').map(String::from))
+            .flat_map(|pattern| pattern.split(|&b| b == b'\n').map(<[u8]>::to_vec))
             .collect();
-        self.regexp.sort_by_key(|r| r.len());
-        self.regexp.dedup();
+        self.patterns.sort_by_key(|r| r.len());
+        self.patterns.dedup();
 
         if self.input_files.is_empty() {
             self.input_files.push(String::from("-"))
@@ -157,10 +164,13 @@ impl Args {
     /// # Errors
     ///
     /// Returns an error if there is an issue reading the file.
-    fn get_file_patterns<P: AsRef<Path>>(path: P) -> Result<Vec<String>, io::Error> {
-        BufReader::new(File::open(&path)?)
-            .lines()
-            .collect::<Result<Vec<_>, _>>()
+    fn get_file_patterns<P: AsRef<Path>>(path: P) -> Result<Vec<Vec<u8>>, io::Error> {
+        let mut reader = ByteLines::new(File::open(&path)?);
+        let mut patterns = Vec::new();
+        while let Some(line) = reader.next_line()? {
+            patterns.push(line.to_vec());
+        }
+        Ok(patterns)
     }
 
     /// Maps [Args](Args) object into [GrepModel](GrepModel).
@@ -180,7 +190,7 @@ impl Args {
         };
 
         let patterns = Patterns::new(
-            self.regexp,
+            self.patterns,
             self.extended_regexp,
             self.fixed_strings,
             self.ignore_case,
@@ -201,10 +211,10 @@ impl Args {
     }
 }
 
-/// Newtype over `Vec[libc::regex_t]`. Provides functionality for matching input data.
+/// Newtype over `Vec[plib::regex::Regex]`. Provides functionality for matching input data.
 enum Patterns {
-    Fixed(Vec<String>, bool, bool),
-    Regex(Vec<regex_t>),
+    Fixed(Vec<Vec<u8>>, bool, bool),
+    Regex(Vec<Regex>, bool),
 }
 
 impl Patterns {
@@ -212,7 +222,7 @@ impl Patterns {
     ///
     /// # Arguments
     ///
-    /// * `patterns` - `Vec<String>` containing the patterns.
+    /// * `patterns` - `Vec<Vec<u8>>` containing the patterns.
     /// * `extended_regexp` - `bool` indicating whether to use extended regular expressions.
     /// * `fixed_string` - `bool` indicating whether pattern is fixed string or regex.
     /// * `ignore_case` - `bool` indicating whether to ignore case.
@@ -226,7 +236,7 @@ impl Patterns {
     ///
     /// Returns [Patterns](Patterns).
     fn new(
-        patterns: Vec<String>,
+        patterns: Vec<Vec<u8>>,
         extended_regexp: bool,
         fixed_string: bool,
         ignore_case: bool,
@@ -236,7 +246,13 @@ impl Patterns {
             Ok(Self::Fixed(
                 patterns
                     .into_iter()
-                    .map(|p| if ignore_case { p.to_lowercase() } else { p })
+                    .map(|p| {
+                        if ignore_case {
+                            String::from_utf8_lossy(&p).to_lowercase().into_bytes()
+                        } else {
+                            p
+                        }
+                    })
                     .collect(),
                 ignore_case,
                 line_regexp,
@@ -244,47 +260,23 @@ impl Patterns {
         } else {
             let mut ps = vec![];
 
-            let mut cflags = 0;
-            if extended_regexp {
-                cflags |= REG_EXTENDED;
-            }
-            if ignore_case {
-                cflags |= REG_ICASE;
+            let flags = if extended_regexp {
+                RegexFlags::ere()
+            } else {
+                RegexFlags::bre()
             }
-            for mut pattern in patterns {
-                // macOS version of [regcomp](regcomp) from `libc`
-                // provides additional check for empty regex. In this case,
-                // an error [REG_EMPTY](https://developer.apple.com/library/archive/documentation/System/Conceptual/ManPages_iPhoneOS/man3/regcomp.3.html)
-                // will be returned.
-
-                // Therefore, an empty pattern is replaced with ".*".
-                #[cfg(target_os = "macos")]
-                {
-                    pattern = if pattern.is_empty() {
-                        String::from(".*")
-                    } else {
-                        pattern
-                    };
-                }
-                pattern = if line_regexp {
-                    format!("^{pattern}$")
-                } else {
-                    pattern
-                };
-
-                let c_pattern = CString::new(pattern).map_err(|err| err.to_string())?;
-                let mut regex = unsafe { std::mem::zeroed::<regex_t>() };
-
-                let result = unsafe { regcomp(&mut regex, c_pattern.as_ptr(), cflags) };
-                if result != 0 {
-                    return Err(format!(
+            .ignore_case(ignore_case)
+            .no_sub(true);
+            for pattern in patterns {
+                let regex = Regex::new(&pattern, flags).map_err(|_| {
+                    format!(
                         "Error compiling regex '{}'",
-                        c_pattern.to_string_lossy()
-                    ));
-                }
+                        String::from_utf8_lossy(&pattern)
+                    )
+                })?;
                 ps.push(regex);
             }
-            Ok(Self::Regex(ps))
+            Ok(Self::Regex(ps, line_regexp))
         }
     }
 
@@ -292,47 +284,38 @@ impl Patterns {
     ///
     /// # Arguments
     ///
-    /// * `input` - object that implements [AsRef](AsRef) for [str](str) and describes line.
+    /// * `input` - [u8](u8) slice that describes line.
     ///
     /// # Returns
     ///
     /// Returns [bool](bool) - `true` if input matches present patterns, else `false`.
-    fn matches(&self, input: impl AsRef<str>) -> bool {
-        let input = input.as_ref();
+    fn matches(&self, input: &[u8]) -> bool {
         match self {
             Patterns::Fixed(patterns, ignore_case, line_regexp) => {
+                let lowercase;
                 let input = if *ignore_case {
-                    input.to_lowercase()
+                    lowercase = String::from_utf8_lossy(input).to_lowercase();
+                    lowercase.as_bytes()
                 } else {
-                    input.to_string()
+                    input
                 };
                 patterns.iter().any(|p| {
+                    let p = p.as_slice();
                     if *line_regexp {
-                        input == *p
+                        input == p
                     } else {
-                        input.contains(p)
+                        p.is_empty() || input.windows(p.len()).any(|w| w == p)
                     }
                 })
             }
-            Patterns::Regex(patterns) => {
-                let c_input = CString::new(input).unwrap();
-                patterns.iter().any(|p| unsafe {
-                    regexec(p, c_input.as_ptr(), 0, ptr::null_mut(), 0) != REG_NOMATCH
-                })
-            }
-        }
-    }
-}
-
-impl Drop for Patterns {
-    fn drop(&mut self) {
-        match &self {
-            Patterns::Fixed(_, _, _) => {}
-            Patterns::Regex(regexes) => {
-                for regex in regexes {
-                    unsafe { regfree(regex as *const regex_t as *mut regex_t) }
+            Patterns::Regex(patterns, line_regexp) => patterns.iter().any(|p| {
+                if *line_regexp {
+                    // the longest match starting at 0 covers the whole line if any does
+                    p.find(input) == Some(0..input.len())
+                } else {
+                    p.is_match(input)
                 }
-            }
+            }),
         }
     }
 }
@@ -368,12 +351,12 @@ impl GrepModel {
     fn grep(&mut self) -> i32 {
         for input_name in self.input_files.drain(..).collect::<Vec<_>>() {
             if input_name == "-" {
-                let reader = Box::new(BufReader::new(io::stdin()));
+                let reader = ByteLines::new(Box::new(io::stdin().lock()) as Box<dyn Read>);
                 self.process_input("(standard input)", reader);
             } else {
                 match File::open(&input_name) {
                     Ok(file) => {
-                        let reader = Box::new(BufReader::new(file));
+                        let reader = ByteLines::new(Box::new(file) as Box<dyn Read>);
                         self.process_input(&input_name, reader)
                     }
                     Err(err) => {
@@ -403,23 +386,14 @@ impl GrepModel {
     /// # Arguments
     ///
     /// * `input_name` - [str](str) that represents content source name.
-    /// * `reader` - [Box](Box) that contains object that implements [BufRead] and reads lines.
-    fn process_input(&mut self, input_name: &str, mut reader: Box<dyn BufRead>) {
+    /// * `reader` - [ByteLines] that reads lines from the input.
+    fn process_input(&mut self, input_name: &str, mut reader: ByteLines<Box<dyn Read>>) {
         let mut line_number: u64 = 0;
         loop {
-            let mut line = String::new();
             line_number += 1;
-            match reader.read_line(&mut line) {
-                Ok(n_read) => {
-                    if n_read == 0 {
-                        break;
-                    }
-                    let trimmed = if line.ends_with('\n') {
-                        &line[..line.len() - 1]
-                    } else {
-                        &line
-                    };
-
+            match reader.next_line() {
+                Ok(None) => break,
+                Ok(Some(trimmed)) => {
                     let init_matches = self.patterns.matches(trimmed);
                     let matches = if self.invert_match {
                         !init_matches
@@ -440,8 +414,8 @@ impl GrepModel {
                                 return;
                             }
                             OutputMode::Default => {
-                                let result = format!(
-                                    "{}{}{}",
+                                let mut result = format!(
+                                    "{}{}",
                                     if self.multiple_inputs {
                                         format!("{input_name}:")
                                     } else {
@@ -452,13 +426,18 @@ impl GrepModel {
                                     } else {
                                         String::new()
                                     },
-                                    trimmed
-                                );
-                                println!("{result}");
+                                )
+                                .into_bytes();
+                                result.extend_from_slice(trimmed);
+                                result.push(b'\n');
+                                if let Err(err) = io::stdout().write_all(&result) {
+                                    eprintln!("{}", err);
+                                    self.any_errors = true;
+                                    return;
+                                }
                             }
                         }
                     }
-                    line.clear();
                 }
                 Err(err) => {
                     self.any_errors = true;
@@ -468,6 +447,7 @@ impl GrepModel {
                             input_name, line_number, err
                         );
                     }
+                    break;
                 }
             }
         }
@@ -9,9 +9,11 @@
 
 use clap::Parser;
 use gettextrs::{bind_textdomain_codeset, setlocale, textdomain, LocaleCategory};
-use std::collections::HashMap;
+use plib::io::{input_byte_lines, ByteLines};
+use plib::locale;
+use std::collections::HashSet;
 use std::fs::File;
-use std::io::{self, BufRead, BufReader};
+use std::io::{self, Write};
 use std::path::PathBuf;
 
 /// join - relational database operator
@@ -53,8 +55,27 @@ struct Args {
     file2: PathBuf,
 }
 
-fn parse_fields(line: &str, sep: char) -> Vec<String> {
-    line.split(sep).map(|s| s.to_string()).collect()
+fn parse_fields(line: &[u8], sep: &[u8]) -> Vec<Vec<u8>> {
+    let mut fields = Vec::new();
+    let mut start = 0;
+    let mut i = 0;
+    while i + sep.len() <= line.len() {
+        if line[i..].starts_with(sep) {
+            fields.push(line[start..i].to_vec());
+            i += sep.len();
+            start = i;
+        } else {
+            i += 1;
+        }
+    }
+    fields.push(line[start..].to_vec());
+    fields
+}
+
+fn print_line(line: &[u8]) -> io::Result<()> {
+    let mut stdout = io::stdout().lock();
+    stdout.write_all(line)?;
+    stdout.write_all(b"\n")
 }
 
 fn process_files2(
@@ -68,38 +89,30 @@ fn process_files2(
     o: Option<Vec<String>>,
     v: u8,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    // code to support stdin
-    let stdin = io::stdin();
-    let file1: Box<dyn BufRead> = if file1_path.to_str() == Some("-") {
-        Box::new(stdin.lock())
-    } else {
-        Box::new(BufReader::new(File::open(file1_path)?))
-    };
-
-    let mut matched_keys = HashMap::new();
-    for line1 in file1.lines() {
-        let line1 = line1?;
-        let fields1 = parse_fields(&line1, sep);
-        let key1 = &fields1[field1 - 1];
+    let mut sep_buf = [0; 4];
+    let sep = sep.encode_utf8(&mut sep_buf).as_bytes();
+
+    let mut file1 = input_byte_lines(file1_path, true)?;
+
+    // keys are matched by the collating sequence of the current locale
+    let mut matched_keys = HashSet::new();
+    while let Some(line1) = file1.next_line()? {
+        let fields1 = parse_fields(line1, sep);
+        let key1 = locale::strxfrm(&fields1[field1 - 1]);
 
         let mut found_match = false;
 
-        let file2: Box<dyn BufRead> = if file2_path.to_str() == Some("-") {
-            Box::new(stdin.lock())
-        } else {
-            Box::new(BufReader::new(File::open(file2_path)?))
-        };
-        for line2 in file2.lines() {
-            let line = line2?;
-            let fields2 = parse_fields(&line, sep);
-            let key2 = &fields2[field2 - 1];
+        let mut file2 = input_byte_lines(file2_path, true)?;
+        while let Some(line2) = file2.next_line()? {
+            let fields2 = parse_fields(line2, sep);
+            let key2 = locale::strxfrm(&fields2[field2 - 1]);
 
             if key1 == key2 {
                 found_match = true;
-                matched_keys.insert(key2.clone(), true);
+                matched_keys.insert(key2);
 
                 if let Some(order) = &o {
-                    let mut res: Vec<String> = Vec::new();
+                    let mut res: Vec<Vec<u8>> = Vec::new();
                     for num in order {
                         let f_num: Vec<&str> = num.split('.').collect();
                         assert_eq!(f_num.len(), 2);
@@ -108,7 +121,7 @@ fn process_files2(
                         if f_num[0] == "1" {
                             if fields1.len() <= n {
                                 if let Some(e) = &e {
-                                    res.push(e.to_string());
+                                    res.push(e.as_bytes().to_vec());
                                 }
                             } else {
                                 res.push(fields1[n].clone());
@@ -116,7 +129,7 @@ fn process_files2(
                         } else if f_num[0] == "2" {
                             if fields2.len() <= n {
                                 if let Some(e) = &e {
-                                    res.push(e.to_string());
+                                    res.push(e.as_bytes().to_vec());
                                 }
                             } else {
                                 res.push(fields2[n].clone());
@@ -127,39 +140,40 @@ fn process_files2(
                         }
                     }
                     if v == 0 {
-                        println!("{}", res.join(" "));
+                        print_line(&res.join(&b' '))?;
                     }
                 } else {
                     if v == 0 {
-                        println!("{} {}", fields1.join(" "), fields2[1..].join(" "));
+                        let mut out = fields1.join(&b' ');
+                        out.push(b' ');
+                        out.extend_from_slice(&fields2[1..].join(&b' '));
+                        print_line(&out)?;
                     }
                 }
             }
         }
 
         if !found_match && a == 1 {
-            println!("{}", fields1.join(" "));
+            print_line(&fields1.join(&b' '))?;
         }
     }
 
-    let file1 = BufReader::new(File::open(file1_path)?);
-    let file2 = BufReader::new(File::open(file2_path)?);
     if v == 1 {
-        for line1 in file1.lines() {
-            let line1 = line1?;
-            let fields1 = parse_fields(&line1, sep);
-            let key1 = &fields1[field1 - 1];
-            if !matched_keys.contains_key(key1) {
-                println!("{}", fields1.join(" "));
+        let mut file1 = ByteLines::new(File::open(file1_path)?);
+        while let Some(line1) = file1.next_line()? {
+            let fields1 = parse_fields(line1, sep);
+            let key1 = locale::strxfrm(&fields1[field1 - 1]);
+            if !matched_keys.contains(&key1) {
+                print_line(&fields1.join(&b' '))?;
             }
         }
     } else if v == 2 {
-        for line2 in file2.lines() {
-            let line2 = line2?;
-            let fields2 = parse_fields(&line2, sep);
-            let key2 = &fields2[field2 - 1];
-            if !matched_keys.contains_key(key2) {
-                println!("{}", fields2.join(" "));
+        let mut file2 = ByteLines::new(File::open(file2_path)?);
+        while let Some(line2) = file2.next_line()? {
+            let fields2 = parse_fields(line2, sep);
+            let key2 = locale::strxfrm(&fields2[field2 - 1]);
+            if !matched_keys.contains(&key2) {
+                print_line(&fields2.join(&b' '))?;
             }
         }
     }
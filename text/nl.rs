@@ -9,9 +9,10 @@
 
 use clap::{Parser, ValueEnum};
 use gettextrs::{bind_textdomain_codeset, setlocale, textdomain, LocaleCategory};
-use regex::Regex;
+use plib::io::ByteLines;
+use plib::regex::{Regex, RegexFlags};
 use std::fs;
-use std::io::{self, BufRead, Read};
+use std::io::{self, Read, Write};
 use std::path::PathBuf;
 use std::process::ExitCode;
 use std::str::FromStr;
@@ -110,7 +111,7 @@ impl FromStr for LineNumberingStyle {
             "n" => Ok(LineNumberingStyle::None),
             s => {
                 if let Some(re) = s.strip_prefix('p') {
-                    if let Ok(regexp) = Regex::new(re) {
+                    if let Ok(regexp) = Regex::new(re.as_bytes(), RegexFlags::bre().no_sub(true)) {
                         Ok(LineNumberingStyle::Regex(regexp))
                     } else {
                         Err(format!("invalid regular expression: {re}"))
@@ -130,7 +131,7 @@ impl std::fmt::Display for LineNumberingStyle {
             LineNumberingStyle::NonEmpty => write!(f, "t"),
             LineNumberingStyle::None => write!(f, "n"),
             LineNumberingStyle::Regex(re) => {
-                write!(f, "p{}", re)
+                write!(f, "p{}", String::from_utf8_lossy(re.pattern()))
             }
         }
     }
@@ -163,13 +164,12 @@ fn nl_main(args: &Args) -> io::Result<()> {
     } else {
         Box::new(io::stdin().lock())
     };
-    let mut reader = io::BufReader::new(readable);
+    let mut reader = ByteLines::new(readable);
 
     let delimiter_header: String = (0..3).map(|_| args.section_delimiter.as_str()).collect();
     let delimiter_body: String = (0..2).map(|_| args.section_delimiter.as_str()).collect();
     let delimiter_footer: String = args.section_delimiter.clone();
 
-    let mut line_buffer = String::new();
     let mut line_number = args.starting_line_number;
     let mut line_number_overflowed = false;
     let mut current_numbering_style = &args.body_numbering;
@@ -220,88 +220,71 @@ fn nl_main(args: &Args) -> io::Result<()> {
         );
     };
 
-    loop {
-        line_buffer.clear();
-
-        if let Ok(read_bytes) = reader.read_line(&mut line_buffer) {
-            // EOF
-            if read_bytes == 0 {
-                break;
-            }
-
-            // Removing the newline makes for easier checks but it has to be
-            // added back later
-            if line_buffer.ends_with('\n') {
-                line_buffer.pop();
-            }
-
-            // If we are in a header, body or footer
-            let mut non_text = false;
-
-            if line_buffer == delimiter_header {
-                non_text = true;
-                current_numbering_style = &args.header_numbering;
-            } else if line_buffer == delimiter_body {
-                non_text = true;
-                current_numbering_style = &args.body_numbering;
-            } else if line_buffer == delimiter_footer {
-                non_text = true;
-                current_numbering_style = &args.footer_numbering;
-            } else {
-                match current_numbering_style {
-                    LineNumberingStyle::All => {
-                        if args.join_blank_lines > 1 {
-                            if !line_buffer.is_empty() {
+    // The newline is removed for easier checks but it has to be added back
+    // later
+    while let Ok(Some(line_buffer)) = reader.next_line() {
+        // If we are in a header, body or footer
+        let mut non_text = false;
+
+        if line_buffer == delimiter_header.as_bytes() {
+            non_text = true;
+            current_numbering_style = &args.header_numbering;
+        } else if line_buffer == delimiter_body.as_bytes() {
+            non_text = true;
+            current_numbering_style = &args.body_numbering;
+        } else if line_buffer == delimiter_footer.as_bytes() {
+            non_text = true;
+            current_numbering_style = &args.footer_numbering;
+        } else {
+            match current_numbering_style {
+                LineNumberingStyle::All => {
+                    if args.join_blank_lines > 1 {
+                        if !line_buffer.is_empty() {
+                            print_line_number(&mut line_number, &mut line_number_overflowed)?;
+                            consecutive_blank_lines = 0;
+                        } else {
+                            consecutive_blank_lines += 1;
+                            if consecutive_blank_lines == args.join_blank_lines {
                                 print_line_number(&mut line_number, &mut line_number_overflowed)?;
                                 consecutive_blank_lines = 0;
                             } else {
-                                consecutive_blank_lines += 1;
-                                if consecutive_blank_lines == args.join_blank_lines {
-                                    print_line_number(
-                                        &mut line_number,
-                                        &mut line_number_overflowed,
-                                    )?;
-                                    consecutive_blank_lines = 0;
-                                } else {
-                                    print_spacer();
-                                }
+                                print_spacer();
                             }
-                        } else {
-                            print_line_number(&mut line_number, &mut line_number_overflowed)?;
                         }
+                    } else {
+                        print_line_number(&mut line_number, &mut line_number_overflowed)?;
                     }
-                    LineNumberingStyle::NonEmpty => {
-                        if !line_buffer.is_empty() {
-                            print_line_number(&mut line_number, &mut line_number_overflowed)?;
-                        } else {
-                            print_spacer();
-                        }
+                }
+                LineNumberingStyle::NonEmpty => {
+                    if !line_buffer.is_empty() {
+                        print_line_number(&mut line_number, &mut line_number_overflowed)?;
+                    } else {
+                        print_spacer();
                     }
-                    LineNumberingStyle::None => print_spacer(),
-                    LineNumberingStyle::Regex(regexp) => {
-                        if regexp.is_match(&line_buffer) {
-                            print_line_number(&mut line_number, &mut line_number_overflowed)?;
-                        } else {
-                            print_spacer();
-                        }
+                }
+                LineNumberingStyle::None => print_spacer(),
+                LineNumberingStyle::Regex(regexp) => {
+                    if regexp.is_match(line_buffer) {
+                        print_line_number(&mut line_number, &mut line_number_overflowed)?;
+                    } else {
+                        print_spacer();
                     }
                 }
-
-                // Reference `nl` unconditionally adds a newline even on files
-                // not ending on a newline
-                line_buffer.push('\n');
-                print!("{}", &line_buffer);
             }
 
-            if non_text {
-                if !args.no_renumber {
-                    line_number = args.starting_line_number;
-                    line_number_overflowed = false;
-                }
-                println!();
+            // Reference `nl` unconditionally adds a newline even on files
+            // not ending on a newline
+            let mut stdout = io::stdout().lock();
+            stdout.write_all(line_buffer)?;
+            stdout.write_all(b"\n")?;
+        }
+
+        if non_text {
+            if !args.no_renumber {
+                line_number = args.starting_line_number;
+                line_number_overflowed = false;
             }
-        } else {
-            break;
+            println!();
         }
     }
 
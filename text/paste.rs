@@ -12,10 +12,11 @@
 
 use clap::Parser;
 use gettextrs::{bind_textdomain_codeset, setlocale, textdomain, LocaleCategory};
+use plib::io::ByteLines;
 use std::cell::{OnceCell, RefCell};
 use std::error::Error;
 use std::fs::File;
-use std::io::{self, BufRead, BufReader, Stdin, Write};
+use std::io::{self, Stdin, Write};
 use std::iter::Cycle;
 use std::rc::Rc;
 use std::slice::Iter;
@@ -39,27 +40,34 @@ struct Args {
 
 enum Source {
     File {
-        buf_reader: BufReader<File>,
+        lines: ByteLines<File>,
         file_description: String,
     },
-    StandardInput(Rc<RefCell<Stdin>>),
+    StandardInput(Rc<RefCell<ByteLines<Stdin>>>),
 }
 
 impl Source {
-    fn read_until_new_line(&mut self, vec: &mut Vec<u8>) -> Result<usize, Box<dyn Error>> {
-        const READ_UNTIL_BYTE: u8 = b'\n';
+    /// Append the next line, sans trailing newline, to `vec`.  Returns
+    /// false at end of input.
+    fn read_line(&mut self, vec: &mut Vec<u8>) -> Result<bool, Box<dyn Error>> {
+        fn append(line: io::Result<Option<&[u8]>>, vec: &mut Vec<u8>) -> io::Result<bool> {
+            Ok(match line? {
+                Some(line) => {
+                    vec.extend_from_slice(line);
+                    true
+                }
+                None => false,
+            })
+        }
 
         let (source_description, result) = match self {
             Self::File {
-                buf_reader,
+                lines,
                 file_description,
-            } => (
-                file_description.as_str(),
-                buf_reader.read_until(READ_UNTIL_BYTE, vec),
-            ),
+            } => (file_description.as_str(), append(lines.next_line(), vec)),
             Self::StandardInput(st) => (
                 "Pipe: standard input",
-                st.try_borrow()?.lock().read_until(READ_UNTIL_BYTE, vec),
+                append(st.try_borrow_mut()?.next_line(), vec),
             ),
         };
 
@@ -232,7 +240,7 @@ fn parse_delimiters_argument(delimiters: Option<String>) -> Result<Box<[Box<[u8]
 }
 
 fn open_inputs(files: Vec<String>) -> Result<PasteInfo, Box<dyn Error>> {
-    let stdin_once_cell = OnceCell::<Rc<RefCell<Stdin>>>::new();
+    let stdin_once_cell = OnceCell::<Rc<RefCell<ByteLines<Stdin>>>>::new();
 
     let mut paste_file_vec = Vec::<PasteFile>::with_capacity(files.len());
 
@@ -245,7 +253,7 @@ fn open_inputs(files: Vec<String>) -> Result<PasteInfo, Box<dyn Error>> {
             "-" => {
                 paste_file_vec.push(PasteFile::new(Source::StandardInput(
                     stdin_once_cell
-                        .get_or_init(|| Rc::new(RefCell::new(io::stdin())))
+                        .get_or_init(|| Rc::new(RefCell::new(ByteLines::new(io::stdin()))))
                         .clone(),
                 )));
             }
@@ -255,17 +263,17 @@ fn open_inputs(files: Vec<String>) -> Result<PasteInfo, Box<dyn Error>> {
             st => {
                 let open_result = File::open(st);
 
-                let buf_reader = match open_result {
+                let lines = match open_result {
                     Err(er) => {
                         return Err(Box::from(format!("{st}: {er}")));
                     }
-                    Ok(fi) => BufReader::new(fi),
+                    Ok(fi) => ByteLines::new(fi),
                 };
 
                 let filename = format!("File: {st}");
 
                 paste_file_vec.push(PasteFile::new(Source::File {
-                    buf_reader,
+                    lines,
                     file_description: filename,
                 }));
             }
@@ -306,10 +314,8 @@ fn paste_files_serial(
             // Equivalent to allocating a new Vec here
             buffer.clear();
 
-            let read_line_result = paste_file.source.read_until_new_line(&mut buffer)?;
-
             // if EOF, output line terminator and end inner loop
-            if read_line_result == 0 {
+            if !paste_file.source.read_line(&mut buffer)? {
                 stdout_lock.write_all(b"\n")?;
 
                 break;
@@ -319,21 +325,7 @@ fn paste_files_serial(
                 }
 
                 // output line segment
-                let mut iter = buffer.iter();
-
-                // TODO
-                // Check that the removed character is a newline?
-                // Update: checking if it was a newline in this manner fixes "paste_multiple_stdin_serial_test_two"
-                // But this seems hacky
-                let slice = match iter.next_back() {
-                    // `iter` is correct, since the byte that was removed was a newline character
-                    Some(b'\n') => iter.as_slice(),
-                    // `iter` is wrong, since it is now missing the final character (which was not a newline
-                    // character), so use `buffer`, unmodified
-                    _ => buffer.as_slice(),
-                };
-
-                stdout_lock.write_all(slice)?;
+                stdout_lock.write_all(&buffer)?;
             }
 
             if first_line {
@@ -358,8 +350,7 @@ fn paste_files(
 ) -> Result<(), Box<dyn Error>> {
     // for each input line, across N files
 
-    // Re-use buffers to avoid repeated allocations
-    let mut buffer = Vec::new();
+    // Re-use buffer to avoid repeated allocations
     let mut output = Vec::new();
 
     loop {
@@ -372,27 +363,12 @@ fn paste_files(
         for paste_file in &mut paste_info.inputs {
             // if not already at EOF, read and process a line
             if !paste_file.eof {
-                // Equivalent to allocating a new Vec here
-                buffer.clear();
-
-                let read_line_result = paste_file.source.read_until_new_line(&mut buffer)?;
-
-                if read_line_result == 0 {
+                // add to output line, sans trailing NL
+                if paste_file.source.read_line(&mut output)? {
+                    have_data = true;
+                } else {
                     // if at EOF, note and continue
                     paste_file.eof = true;
-                } else {
-                    // otherwise add to output line, sans trailing NL
-                    have_data = true;
-
-                    let mut iter = buffer.iter();
-
-                    // See note above
-                    let slice = match iter.next_back() {
-                        Some(b'\n') => iter.as_slice(),
-                        _ => buffer.as_slice(),
-                    };
-
-                    output.extend_from_slice(slice);
                 }
             }
 
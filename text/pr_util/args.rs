@@ -12,7 +12,7 @@ use crate::{
     PAGE_WIDTH_IF_HAS_SEPARATOR, TAB,
 };
 use clap::Parser;
-use regex::Regex;
+use plib::regex::{MatchFlags, Regex, RegexFlags};
 use std::path::PathBuf;
 use std::str::FromStr;
 
@@ -184,13 +184,14 @@ impl Args {
     }
 
     pub fn parse_custom() -> Self {
-        let page_column_regex = Regex::new(r"^\+\d+(?::\d+)?|-\d+\b").unwrap();
+        let page_column_regex =
+            Regex::new(br"^\+[0-9]+(:[0-9]+)?|^-[0-9]+$", RegexFlags::ere()).unwrap();
 
         let mut env_args: Vec<_> = std::env::args()
             .filter_map(|s| {
                 // Map the arguments +FIRST_PAGE[:LAST_PAGE] and -COLUMN
                 // to something that `clap` can parse
-                if page_column_regex.is_match(&s) {
+                if page_column_regex.is_match(s.as_bytes()) {
                     if s.starts_with('+') {
                         Some(format!("--pages={}", s.strip_prefix('+').unwrap()))
                     } else {
@@ -231,10 +232,10 @@ impl Args {
 }
 
 fn parse_pages(s: &str) -> Result<(usize, Option<usize>), String> {
-    let page_option_regex = Regex::new(r"^(\d+)(?::(\d+))?\b").unwrap();
-    if let Some(caps) = page_option_regex.captures(s) {
-        let first_page = caps.get(1).unwrap().as_str().parse().unwrap();
-        let last_page = caps.get(2).map(|x| x.as_str().parse().unwrap());
+    let page_option_regex = Regex::new(br"^([0-9]+)(:([0-9]+))?$", RegexFlags::ere()).unwrap();
+    if let Some(caps) = page_option_regex.exec(s.as_bytes(), MatchFlags::default()) {
+        let first_page = s[caps[1].clone().unwrap()].parse().unwrap();
+        let last_page = caps[3].clone().map(|x| s[x].parse().unwrap());
         return Ok((first_page, last_page));
     }
 
@@ -12,12 +12,14 @@ use std::cmp::Ordering;
 use std::io::{ErrorKind, Read};
 use std::{
     fs::File,
-    io::{self, BufRead, BufWriter, Error, Write},
+    io::{self, BufWriter, Error, Write},
     path::PathBuf,
 };
 
 use clap::Parser;
 use gettextrs::{bind_textdomain_codeset, setlocale, textdomain, LocaleCategory};
+use plib::io::ByteLines;
+use plib::locale;
 
 /// Sort, merge, or sequence check text files
 #[derive(Parser)]
@@ -223,17 +225,17 @@ fn compare_range_fields(field1: &RangeField, field2: &RangeField) -> bool {
 ///
 /// # Arguments
 ///
-/// * `line` - A vector of string slices representing the fields to be processed.
+/// * `line` - A vector of byte slices representing the fields to be processed.
 /// * `key_range` - A tuple containing two elements:
 ///     * The first `RangeField` specifies the starting field and character position.
 ///     * An optional `RangeField` that specifies the ending field and character position.
 ///
 /// # Returns
 ///
-/// A `String` that contains the concatenated result of the specified range of substrings.
+/// A `Vec<u8>` that contains the concatenated result of the specified range of substrings.
 ///
-fn cut_line_by_range(line: Vec<&str>, key_range: &(RangeField, Option<RangeField>)) -> String {
-    let mut result = String::new();
+fn cut_line_by_range(line: Vec<&[u8]>, key_range: &(RangeField, Option<RangeField>)) -> Vec<u8> {
+    let mut result = Vec::new();
 
     let start_field = key_range.0.field_number;
     let start_char = key_range.0.first_character;
@@ -249,7 +251,7 @@ fn cut_line_by_range(line: Vec<&str>, key_range: &(RangeField, Option<RangeField
         if i >= start_field && i <= end_field {
             let start = if i == start_field {
                 if key_range.0.ignore_leading_blanks {
-                    start_char + (field.len() - field.trim_start().len())
+                    start_char + (field.len() - field.trim_ascii_start().len())
                 } else {
                     start_char
                 }
@@ -261,7 +263,7 @@ fn cut_line_by_range(line: Vec<&str>, key_range: &(RangeField, Option<RangeField
                     if char == usize::MAX - 1 {
                         field.len() - 1
                     } else if key_range.clone().1.unwrap().ignore_leading_blanks {
-                        char + (field.len() - field.trim_start().len())
+                        char + (field.len() - field.trim_ascii_start().len())
                     } else {
                         char
                     }
@@ -275,7 +277,7 @@ fn cut_line_by_range(line: Vec<&str>, key_range: &(RangeField, Option<RangeField
                 end = field.len() - 1;
             }
 
-            result.push_str(&field[start..=end]);
+            result.extend_from_slice(&field[start..=end]);
         }
     }
 
@@ -325,8 +327,8 @@ fn numeric_sort_filter(input: &str) -> Option<String> {
 ///
 /// # Arguments
 ///
-/// * `line1` - A string slice (`&str`) representing the first input string.
-/// * `line2` - A string slice (`&str`) representing the second input string.
+/// * `line1` - A byte slice (`&[u8]`) representing the first input string.
+/// * `line2` - A byte slice (`&[u8]`) representing the second input string.
 ///
 /// # Returns
 ///
@@ -337,52 +339,84 @@ fn numeric_sort_filter(input: &str) -> Option<String> {
 /// * `Ordering::Equal` if the numbers are equal or if both strings lack numeric sequences and are
 ///   equal lexicographically.
 ///
-fn compare_numeric(line1: &str, line2: &str) -> Ordering {
-    let line1 = numeric_sort_filter(line1).unwrap_or("0".to_string());
-    let line2 = numeric_sort_filter(line2).unwrap_or("0".to_string());
+fn compare_numeric(line1: &[u8], line2: &[u8]) -> Ordering {
+    let line1 = numeric_sort_filter(&String::from_utf8_lossy(line1)).unwrap_or("0".to_string());
+    let line2 = numeric_sort_filter(&String::from_utf8_lossy(line2)).unwrap_or("0".to_string());
     let a_num = line1.parse::<f64>().ok();
     let b_num = line2.parse::<f64>().ok();
 
     a_num.partial_cmp(&b_num).unwrap_or(Ordering::Equal)
 }
 
-/// Filters a string to include only alphanumeric characters and whitespace.
+/// Filters a string to include only alphanumeric characters and blanks.
 ///
-/// This function processes an input string and retains only the alphanumeric characters
-/// (`a-z`, `A-Z`, `0-9`) and whitespace characters, effectively removing all other characters.
-/// It is useful for preparing strings for dictionary-order comparisons.
+/// This function processes an input string and retains only the characters of the
+/// `alnum` and `blank` classes of the current locale, effectively removing all other
+/// characters. It is useful for preparing strings for dictionary-order comparisons.
 ///
 /// # Arguments
 ///
-/// * `line` - A string slice (`&str`) representing the input string to be filtered.
+/// * `line` - A byte slice (`&[u8]`) representing the input string to be filtered.
 ///
 /// # Returns
 ///
-/// A `String` containing only the alphanumeric and whitespace characters from the input string.
+/// A `Vec<u8>` containing only the alphanumeric and blank characters from the input string;
+/// bytes that are not valid characters belong to no class and are removed.
 ///
-fn dictionary_order_filter(line: &str) -> String {
-    line.chars()
-        .filter(|c| c.is_alphanumeric() || c.is_whitespace())
-        .collect::<String>()
+fn dictionary_order_filter(line: &[u8]) -> Vec<u8> {
+    let ctype = locale::ctype();
+    chars_filter(line, |c| {
+        ctype.iswctype(c, "alnum") || ctype.iswctype(c, "blank")
+    })
 }
 
-/// Filters a string to include only printable ASCII characters.
+/// Filters a string to include only printable characters.
 ///
-/// This function processes an input string and retains only the ASCII characters that are
-/// considered printable and graphic (i.e., not control characters or whitespace).
+/// This function processes an input string and retains only the characters of the
+/// `print` class of the current locale.
 ///
 /// # Arguments
 ///
-/// * `line` - A string slice (`&str`) representing the input string to be filtered.
+/// * `line` - A byte slice (`&[u8]`) representing the input string to be filtered.
 ///
 /// # Returns
 ///
-/// A `String` containing only the printable ASCII characters from the input string.
+/// A `Vec<u8>` containing only the printable characters from the input string.
 ///
-fn ignore_nonprintable_filter(line: &str) -> String {
-    line.chars()
-        .filter(|&c| c.is_ascii() && c.is_ascii_graphic())
-        .collect()
+fn ignore_nonprintable_filter(line: &[u8]) -> Vec<u8> {
+    let ctype = locale::ctype();
+    chars_filter(line, |c| ctype.iswctype(c, "print"))
+}
+
+/// Keeps the characters of a string for which `keep` holds, dropping the
+/// bytes that are not valid characters.
+fn chars_filter(line: &[u8], mut keep: impl FnMut(char) -> bool) -> Vec<u8> {
+    let mut result = String::new();
+    for chunk in line.utf8_chunks() {
+        result.extend(chunk.valid().chars().filter(|&c| keep(c)));
+    }
+    result.into_bytes()
+}
+
+/// Maps every character of a string to uppercase according to the current locale,
+/// leaving the bytes that are not valid characters as they are.
+fn fold_case_filter(line: &[u8]) -> Vec<u8> {
+    let ctype = locale::ctype();
+    let mut result = Vec::with_capacity(line.len());
+    for chunk in line.utf8_chunks() {
+        for c in chunk.valid().chars() {
+            let mut buf = [0; 4];
+            result.extend_from_slice(ctype.towupper(c).encode_utf8(&mut buf).as_bytes());
+        }
+        result.extend_from_slice(chunk.invalid());
+    }
+    result
+}
+
+/// Compares two strings according to the collating sequence of the current locale,
+/// falling back to a byte comparison for strings that collate equally.
+fn collate(line1: &[u8], line2: &[u8]) -> Ordering {
+    locale::strcoll(line1, line2).then_with(|| line1.cmp(line2))
 }
 
 /// Generates a `RangeField` struct based on the specified key range and arguments.
@@ -510,100 +544,141 @@ fn generate_range(
 ///
 /// # Returns
 ///
-/// A string containing the selected fields based on the key range(s).
+/// The bytes of the selected fields based on the key range(s).
 ///
 fn cut_line(
-    line: &str,
+    line: &[u8],
     key_range: &(RangeField, Option<RangeField>),
     field_separator: Option<char>,
-) -> String {
+) -> Vec<u8> {
     if let Some(separator) = field_separator {
-        let split = line.split(separator);
-        cut_line_by_range(split.collect(), key_range)
+        let mut buf = [0; 4];
+        let split = split_fields(line, separator.encode_utf8(&mut buf).as_bytes());
+        cut_line_by_range(split, key_range)
     } else {
-        let split: Vec<&str> = line.split(' ').collect();
+        let split = split_fields(line, b" ");
 
         cut_line_by_range(
             merge_empty_lines(split)
                 .iter()
-                .map(|s| s.as_str())
+                .map(|s| s.as_slice())
                 .collect(),
             key_range,
         )
     }
 }
 
-/// Compares two strings based on a specified key range and optional field separator.
-///
-/// This function compares two strings (`line1` and `line2`) based on a specified key range,
-/// taking into account optional field separation if provided. It applies sorting and comparison
-/// rules defined in the `RangeField` struct associated with the key range.
+/// Splits a line into the fields between the occurrences of `separator`.
+fn split_fields<'a>(line: &'a [u8], separator: &[u8]) -> Vec<&'a [u8]> {
+    let mut fields = Vec::new();
+    let mut rest = line;
+    while let Some(pos) = rest
+        .windows(separator.len())
+        .position(|window| window == separator)
+    {
+        fields.push(&rest[..pos]);
+        rest = &rest[pos + separator.len()..];
+    }
+    fields.push(rest);
+    fields
+}
+
+/// A key field cut from a line, prepared once per line so that comparisons
+/// do not repeat the cutting, filtering and collation for every pair of lines.
+struct KeyField {
+    text: Vec<u8>,
+    collated: Vec<u32>,
+    folded: Option<(Vec<u32>, Vec<u8>)>,
+}
+
+/// Cuts the key field described by `key_range` out of a line and prepares it
+/// for comparison.
 ///
 /// # Arguments
 ///
-/// * `line1` - A string slice (`&str`) representing the first input string to be compared.
-/// * `line2` - A string slice (`&str`) representing the second input string to be compared.
+/// * `line` - A byte slice (`&[u8]`) representing the line to cut the key from.
 /// * `key_range` - A tuple containing two elements:
-///     * The first `RangeField` specifies the key range configuration for both strings.
+///     * The first `RangeField` specifies the key range configuration.
 ///     * An optional `RangeField` specifies the end of the key range if different from the start.
 /// * `field_separator` - An optional character specifying the field separator for splitting
 ///   strings into fields before comparison. If `None`, whitespace is used as the separator.
 ///
 /// # Returns
 ///
-/// An `Ordering` value (`Ordering::Less`, `Ordering::Greater`, or `Ordering::Equal`) indicating
-/// the result of the comparison:
-/// * `Ordering::Less` if `line1` is less than `line2` according to the specified key range.
-/// * `Ordering::Greater` if `line1` is greater than `line2` according to the specified key range.
-/// * `Ordering::Equal` if `line1` and `line2` are equal within the specified key range.
+/// The `KeyField` holding the filtered key text and its collation keys.
 ///
-fn compare_key(
-    line1: &str,
-    line2: &str,
+fn key_field(
+    line: &[u8],
     key_range: &(RangeField, Option<RangeField>),
     field_separator: Option<char>,
-) -> Ordering {
-    let mut line1 = cut_line(line1, key_range, field_separator);
-    let mut line2 = cut_line(line2, key_range, field_separator);
+) -> KeyField {
+    let mut text = cut_line(line, key_range, field_separator);
 
-    // Compare keys
     if key_range.0.numeric_sort {
-        // If the keys are represented by numbers, compare them as numbers
-        let mut result = compare_numeric(&line1, &line2);
-        if key_range.0.reverse {
-            match result {
-                Ordering::Less => result = Ordering::Greater,
-                Ordering::Greater => result = Ordering::Less,
-                _ => (),
-            }
-        }
-        return result;
+        // Numeric keys compare by value and need no collation keys
+        return KeyField {
+            text,
+            collated: Vec::new(),
+            folded: None,
+        };
     } else if key_range.0.dictionary_order {
-        line1 = dictionary_order_filter(&line1);
-        line2 = dictionary_order_filter(&line2);
+        text = dictionary_order_filter(&text);
     } else if key_range.0.ignore_nonprintable {
-        line1 = ignore_nonprintable_filter(&line1);
-        line2 = ignore_nonprintable_filter(&line2);
+        text = ignore_nonprintable_filter(&text);
     }
 
-    let result;
+    let folded = key_range.0.fold_case.then(|| {
+        let folded = fold_case_filter(&text);
+        (locale::strxfrm(&folded), folded)
+    });
+    KeyField {
+        collated: locale::strxfrm(&text),
+        text,
+        folded,
+    }
+}
 
-    if key_range.0.fold_case {
-        let cmp = line1.to_uppercase().cmp(&line2.to_uppercase());
-        if cmp == std::cmp::Ordering::Equal {
-            result = line1.cmp(&line2);
-        } else {
-            result = cmp;
-        }
+/// Compares two key fields cut from lines with the same key range.
+///
+/// Keys compare by the collating sequence of the current locale, with a byte
+/// comparison deciding between keys that collate equally.
+///
+/// # Arguments
+///
+/// * `key1` - The `KeyField` of the first line.
+/// * `key2` - The `KeyField` of the second line.
+/// * `key_range` - The key range both fields were cut with.
+///
+/// # Returns
+///
+/// An `Ordering` value (`Ordering::Less`, `Ordering::Greater`, or `Ordering::Equal`) indicating
+/// the result of the comparison:
+/// * `Ordering::Less` if `key1` is less than `key2` according to the specified key range.
+/// * `Ordering::Greater` if `key1` is greater than `key2` according to the specified key range.
+/// * `Ordering::Equal` if `key1` and `key2` are equal within the specified key range.
+///
+fn compare_key(
+    key1: &KeyField,
+    key2: &KeyField,
+    key_range: &(RangeField, Option<RangeField>),
+) -> Ordering {
+    let result = if key_range.0.numeric_sort {
+        // If the keys are represented by numbers, compare them as numbers
+        compare_numeric(&key1.text, &key2.text)
     } else {
-        result = line1.cmp(&line2);
-    }
-    if key_range.0.reverse {
-        match result {
-            Ordering::Less => Ordering::Greater,
-            Ordering::Greater => Ordering::Less,
+        let folded = match (&key1.folded, &key2.folded) {
+            (Some((collated1, text1)), Some((collated2, text2))) => {
+                collated1.cmp(collated2).then_with(|| text1.cmp(text2))
+            }
             _ => Ordering::Equal,
-        }
+        };
+        folded
+            .then_with(|| key1.collated.cmp(&key2.collated))
+            .then_with(|| key1.text.cmp(&key2.text))
+    };
+
+    if key_range.0.reverse {
+        result.reverse()
     } else {
         result
     }
@@ -617,8 +692,8 @@ fn compare_key(
 ///
 /// # Arguments
 ///
-/// * `line1` - A string slice (`&str`) representing the first line of text to be compared.
-/// * `line2` - A string slice (`&str`) representing the second line of text to be compared.
+/// * `line1` - A byte slice (`&[u8]`) representing the first line of text to be compared.
+/// * `line2` - A byte slice (`&[u8]`) representing the second line of text to be compared.
 /// * `dictionary_order` - A boolean indicating whether to use dictionary ordering.
 /// * `fold_case` - A boolean indicating whether to fold case during comparison.
 /// * `ignore_nonprintable` - A boolean indicating whether to ignore non-printable characters.
@@ -633,15 +708,15 @@ fn compare_key(
 /// * `Ordering::Equal` if `line1` and `line2` are equal within the specified options.
 ///
 fn compare_lines(
-    line1: &str,
-    line2: &str,
+    line1: &[u8],
+    line2: &[u8],
     dictionary_order: bool,
     fold_case: bool,
     ignore_nonprintable: bool,
     numeric_sort: bool,
 ) -> Ordering {
-    let mut line1 = line1.to_string();
-    let mut line2 = line2.to_string();
+    let mut line1 = line1.to_vec();
+    let mut line2 = line2.to_vec();
 
     if numeric_sort {
         return compare_numeric(&line1, &line2);
@@ -654,14 +729,14 @@ fn compare_lines(
     }
 
     if fold_case {
-        let cmp = line1.to_uppercase().cmp(&line2.to_uppercase());
+        let cmp = collate(&fold_case_filter(&line1), &fold_case_filter(&line2));
         if cmp == std::cmp::Ordering::Equal {
-            line1.cmp(&line2)
+            collate(&line1, &line2)
         } else {
             cmp
         }
     } else {
-        line1.cmp(&line2)
+        collate(&line1, &line2)
     }
 }
 
@@ -675,8 +750,8 @@ fn compare_lines(
 ///
 /// # Arguments
 ///
-/// * `lines_1` - A slice of strings (`&[String]`) representing the first set of lines.
-/// * `lines_2` - A slice of strings (`&[String]`) representing the second set of lines.
+/// * `lines_1` - A slice of byte strings (`&[Vec<u8>]`) representing the first set of lines.
+/// * `lines_2` - A slice of byte strings (`&[Vec<u8>]`) representing the second set of lines.
 ///
 /// # Returns
 ///
@@ -686,7 +761,7 @@ fn compare_lines(
 ///   additional line from `lines_1` compared to `lines_2`.
 /// - If the slices are identical, it returns `None`.
 ///
-fn find_first_difference(lines_1: &[String], lines_2: &[String]) -> Option<(usize, String)> {
+fn find_first_difference(lines_1: &[Vec<u8>], lines_2: &[Vec<u8>]) -> Option<(usize, Vec<u8>)> {
     let min_length = std::cmp::min(lines_1.len(), lines_2.len());
 
     for i in 0..min_length {
@@ -696,7 +771,7 @@ fn find_first_difference(lines_1: &[String], lines_2: &[String]) -> Option<(usiz
     }
 
     if lines_1.len() != lines_2.len() {
-        return Some((min_length, lines_1.get(min_length).unwrap().to_string()));
+        return Some((min_length, lines_1.get(min_length).unwrap().clone()));
     }
 
     None
@@ -780,7 +855,7 @@ fn create_ranges(
 /// * `Ok(())` if the sorting and writing process completes successfully.
 /// * `Err(Box<dyn Error>)` if an error occurs during sorting, reading, or writing.
 ///
-fn sort_lines(args: &Args, lines: Vec<String>) -> Result<(), Box<dyn std::error::Error>> {
+fn sort_lines(args: &Args, lines: Vec<Vec<u8>>) -> Result<(), Box<dyn std::error::Error>> {
     let mut result_lines = lines.clone();
     let mut duplicates = vec![];
 
@@ -800,38 +875,78 @@ fn sort_lines(args: &Args, lines: Vec<String>) -> Result<(), Box<dyn std::error:
             None => None,
         };
 
-        // Sort strings by keys
-        result_lines.sort_by(|a, b| {
-            let mut ordering = compare_key(a, b, &ranges, args.field_separator);
+        // Cut the keys out of each line once, then sort strings by keys
+        let mut keyed: Vec<(KeyField, Option<KeyField>, Vec<u8>)> = result_lines
+            .into_iter()
+            .map(|line| {
+                let key = key_field(&line, &ranges, args.field_separator);
+                let key_2 = ranges_2
+                    .as_ref()
+                    .map(|ranges_2| key_field(&line, ranges_2, args.field_separator));
+                (key, key_2, line)
+            })
+            .collect();
+        keyed.sort_by(|(key_a, key_2_a, a), (key_b, key_2_b, _)| {
+            let mut ordering = compare_key(key_a, key_b, &ranges);
             if let Ordering::Equal = ordering {
-                if let Some(ranges_2) = &ranges_2 {
-                    let ordering_2 = compare_key(a, b, ranges_2, args.field_separator);
+                if let (Some(ranges_2), Some(key_2_a), Some(key_2_b)) =
+                    (&ranges_2, key_2_a, key_2_b)
+                {
+                    let ordering_2 = compare_key(key_2_a, key_2_b, ranges_2);
                     if let Ordering::Equal = ordering_2 {
-                        duplicates.push(a.to_string());
+                        duplicates.push(a.clone());
                     }
                     ordering = ordering_2
                 }
             }
             ordering
         });
+        result_lines = keyed.into_iter().map(|(_, _, line)| line).collect();
         if args.unique {
             result_lines.retain(|line| !duplicates.contains(line));
         }
     } else {
-        result_lines.sort_by(|a, b| {
-            let ord = compare_lines(
-                a,
-                b,
-                args.dictionary_order,
-                args.fold_case,
-                args.ignore_nonprintable,
-                args.numeric_sort,
-            );
-            if let Ordering::Equal = ord {
-                duplicates.push(a.to_string());
-            }
-            ord
-        });
+        let plain = !(args.dictionary_order
+            || args.fold_case
+            || args.ignore_nonprintable
+            || args.numeric_sort);
+        if plain && !locale::collation().is_codepoint() {
+            // Without any ordering options, lines compare by the collating
+            // sequence of the current locale, through keys transformed once
+            // per line rather than on every comparison
+            let mut keyed: Vec<(Vec<u32>, Vec<u8>)> = result_lines
+                .into_iter()
+                .map(|line| (locale::strxfrm(&line), line))
+                .collect();
+            keyed.sort_by(|(key_a, a), (key_b, b)| {
+                let ord = key_a.cmp(key_b).then_with(|| a.cmp(b));
+                if let Ordering::Equal = ord {
+                    duplicates.push(a.clone());
+                }
+                ord
+            });
+            result_lines = keyed.into_iter().map(|(_, line)| line).collect();
+        } else {
+            result_lines.sort_by(|a, b| {
+                // In the POSIX locale, the collating sequence is byte order
+                let ord = if plain {
+                    a.cmp(b)
+                } else {
+                    compare_lines(
+                        a,
+                        b,
+                        args.dictionary_order,
+                        args.fold_case,
+                        args.ignore_nonprintable,
+                        args.numeric_sort,
+                    )
+                };
+                if let Ordering::Equal = ord {
+                    duplicates.push(a.clone());
+                }
+                ord
+            });
+        }
 
         if args.unique {
             result_lines.retain(|line| !duplicates.contains(line));
@@ -853,14 +968,17 @@ fn sort_lines(args: &Args, lines: Vec<String>) -> Result<(), Box<dyn std::error:
         }
     } else if args.check_order {
         if args.unique && !duplicates.is_empty() {
-            let message = format!("Duplicate key was found! `{}`", duplicates.first().unwrap());
+            let message = format!(
+                "Duplicate key was found! `{}`",
+                String::from_utf8_lossy(duplicates.first().unwrap())
+            );
             return Err(Box::new(Error::new(ErrorKind::Other, message)));
         }
         if let Some((index, line)) = find_first_difference(&lines, &result_lines) {
             let message = format!(
                 "The order of the lines is not correct on line {}:`{}`",
                 index + 1,
-                line
+                String::from_utf8_lossy(&line)
             );
             return Err(Box::new(Error::new(ErrorKind::Other, message)));
         }
@@ -872,11 +990,17 @@ fn sort_lines(args: &Args, lines: Vec<String>) -> Result<(), Box<dyn std::error:
 
         // Write the sorted strings to a file
         for line in result_lines {
-            writeln!(writer, "{}", line)?;
+            writer.write_all(&line)?;
+            writer.write_all(b"\n")?;
         }
+        writer.flush()?;
     } else {
-        let result = result_lines.join("\n");
-        println!("{result}");
+        let mut writer = BufWriter::new(io::stdout().lock());
+        for line in result_lines {
+            writer.write_all(&line)?;
+            writer.write_all(b"\n")?;
+        }
+        writer.flush()?;
     }
 
     Ok(())
@@ -923,20 +1047,20 @@ fn merge_files(paths: &mut Vec<Box<dyn Read>>, output_path: &Option<PathBuf>) ->
 ///
 /// # Arguments
 ///
-/// * `vec` - A vector of string references (`&str`).
+/// * `vec` - A vector of byte string references (`&[u8]`).
 ///
 /// # Returns
 ///
-/// A vector of strings (`Vec<String>`) where consecutive empty strings are merged with the nearest non-empty string.
+/// A vector of byte strings (`Vec<Vec<u8>>`) where consecutive empty strings are merged with the nearest non-empty string.
 ///
 /// # Examples
 ///
 /// ```
-/// let result = merge_empty_lines(vec!["line1", "line2", "", "", "", "lineN"]);
-/// assert_eq!(result, vec!["line1", "line2", "   lineN"]);
+/// let result = merge_empty_lines(vec![&b"line1"[..], b"line2", b"", b"", b"", b"lineN"]);
+/// assert_eq!(result, vec![b"line1".to_vec(), b"line2".to_vec(), b"   lineN".to_vec()]);
 /// ```
 ///
-fn merge_empty_lines(vec: Vec<&str>) -> Vec<String> {
+fn merge_empty_lines(vec: Vec<&[u8]>) -> Vec<Vec<u8>> {
     let mut empty_count = 0;
     let mut result = vec![];
 
@@ -944,11 +1068,12 @@ fn merge_empty_lines(vec: Vec<&str>) -> Vec<String> {
         if i.is_empty() {
             empty_count += 1;
         } else if empty_count > 0 {
-            let spaces = " ".repeat(empty_count);
-            result.push(format!("{}{}", spaces, i));
+            let mut merged = b" ".repeat(empty_count);
+            merged.extend_from_slice(i);
+            result.push(merged);
             empty_count = 0;
         } else {
-            result.push(i.to_string());
+            result.push(i.to_vec());
         }
     }
 
@@ -990,11 +1115,12 @@ fn sort(args: &Args) -> Result<(), Box<dyn std::error::Error>> {
         merge_files(&mut readers, &args.output_file)?;
         return Ok(());
     }
-    let mut all_lines: Vec<String> = Vec::new();
+    let mut all_lines: Vec<Vec<u8>> = Vec::new();
     for reader in readers {
-        let reader = io::BufReader::new(reader);
-        let lines: Vec<String> = reader.lines().map(|l| l.unwrap()).collect();
-        all_lines.extend(lines);
+        let mut lines = ByteLines::new(reader);
+        while let Some(line) = lines.next_line()? {
+            all_lines.push(line.to_vec());
+        }
     }
     sort_lines(args, all_lines)?;
 
@@ -1026,7 +1152,10 @@ mod tests {
 
     #[test]
     fn test_merge_empty_lines() {
-        let result = merge_empty_lines(vec!["line1", "line2", "", "", "", "lineN"]);
-        assert_eq!(result, vec!["line1", "line2", "   lineN"]);
+        let result = merge_empty_lines(vec![&b"line1"[..], b"line2", b"", b"", b"", b"lineN"]);
+        assert_eq!(
+            result,
+            vec![b"line1".to_vec(), b"line2".to_vec(), b"   lineN".to_vec()]
+        );
     }
 }
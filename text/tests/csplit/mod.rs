@@ -8,7 +8,7 @@
 // SPDX-License-Identifier: MIT
 //
 
-use plib::testing::{run_test, TestPlan};
+use plib::testing::{run_test, run_test_u8, TestPlan, TestPlanU8};
 
 fn csplit_test(args: &[&str], test_data: &str, expected_output: &str) {
     let str_args: Vec<String> = args.iter().map(|s| String::from(*s)).collect();
@@ -72,7 +72,7 @@ fn test_csplit_c_code_by_regex() {
             "-f",
             "code_c",
             "tests/assets/test_file_c",
-            r"%main\(%",
+            r"%main(%",
             "/^}/+1",
             "{3}",
         ],
@@ -92,7 +92,7 @@ fn test_csplit_c_code_by_regex_negative_offset() {
             "-f",
             "code_c_neg",
             "tests/assets/test_file_c",
-            r"%main\(%",
+            r"%main(%",
             "/^}/-2",
             "{3}",
         ],
@@ -113,7 +113,7 @@ fn test_csplit_c_code_by_regex_suppress() {
             "-f",
             "code_c_s",
             "tests/assets/test_file_c",
-            r"%main\(%",
+            r"%main(%",
             "/^}/+1",
             "{3}",
         ],
@@ -135,7 +135,7 @@ fn test_csplit_c_code_by_regex_with_number() {
             "-n",
             "3",
             "tests/assets/test_file_c",
-            r"%main\(%",
+            r"%main(%",
             "/^}/+1",
             "{3}",
         ],
@@ -226,3 +226,24 @@ fn test_csplit_regex_in_seq() {
     std::fs::remove_file("in_seq02").unwrap();
     std::fs::remove_file("in_seq03").unwrap();
 }
+
+#[test]
+fn test_csplit_invalid_utf8() {
+    run_test_u8(TestPlanU8 {
+        cmd: String::from("csplit"),
+        args: vec![
+            String::from("-f"),
+            String::from("invalid_utf8"),
+            String::from("-"),
+            String::from("/^sep/"),
+        ],
+        stdin_data: b"a\xff\nsep\xfe\nb\n".to_vec(),
+        expected_out: b"2\n\n7\n\n".to_vec(),
+        expected_err: Vec::new(),
+        expected_exit_code: 0,
+    });
+    assert_eq!(std::fs::read("invalid_utf800").unwrap(), b"a\xff");
+    assert_eq!(std::fs::read("invalid_utf801").unwrap(), b"sep\xfe\nb\n");
+    std::fs::remove_file("invalid_utf800").unwrap();
+    std::fs::remove_file("invalid_utf801").unwrap();
+}
@@ -433,3 +433,23 @@ fn test_diff_unified_two_labels() {
         EXIT_STATUS_DIFFERENCE,
     );
 }
+
+#[test]
+fn test_diff_insertion_at_end() {
+    let appended = diff_base_path()
+        .join("f1_appended.txt")
+        .to_str()
+        .expect("Could not unwrap f1_appended_path")
+        .to_string();
+
+    diff_test(
+        &[&f1_txt_path(), &appended],
+        "16a17\n> This line is appended.\n",
+        EXIT_STATUS_DIFFERENCE,
+    );
+    diff_test(
+        &["-e", &f1_txt_path(), &appended],
+        "16a\nThis line is appended.\n.\n",
+        EXIT_STATUS_DIFFERENCE,
+    );
+}
@@ -8,7 +8,7 @@
 // SPDX-License-Identifier: MIT
 //
 
-use plib::testing::{run_test, TestPlan};
+use plib::testing::{run_test, run_test_u8, TestPlan, TestPlanU8};
 
 const LINES_INPUT: &str =
     "line_{1}\np_line_{2}_s\n  line_{3}  \nLINE_{4}\np_LINE_{5}_s\nl_{6}\nline_{70}\n";
@@ -30,6 +30,7 @@ const INVALID_ERE: &str = r#"{1,3}"#;
 const BRE_FILE_1: &str = "tests/grep/bre/p_1";
 const BRE_FILE_2: &str = "tests/grep/bre/p_2";
 const EMPTY_PATTERN_FILE: &str = "tests/grep/empty_pattern";
+const INVALID_PATTERN_FILE: &str = "tests/grep/invalid_pattern";
 
 fn grep_test(
     args: &[&str],
@@ -130,11 +131,31 @@ fn test_basic_regexp_03() {
         &[BRE, INVALID_LINE_INPUT_FILE],
         "",
         "line_{1}\np_line_{2}_s\n",
-        "tests/grep/invalid_line: Error reading line 2 (stream did not contain valid UTF-8)\n",
-        2,
+        "",
+        0,
     );
 }
 
+#[test]
+fn test_invalid_pattern_file() {
+    for options in [&[][..], &["-E"], &["-F"]] {
+        let mut args: Vec<String> = options.iter().map(|s| String::from(*s)).collect();
+        args.extend([
+            String::from("-f"),
+            String::from(INVALID_PATTERN_FILE),
+            String::from(INVALID_LINE_INPUT_FILE),
+        ]);
+        run_test_u8(TestPlanU8 {
+            cmd: String::from("grep"),
+            args,
+            stdin_data: Vec::new(),
+            expected_out: b"\x80\x81\x82\x83\x84\x85\n".to_vec(),
+            expected_err: Vec::new(),
+            expected_exit_code: 0,
+        });
+    }
+}
+
 #[test]
 fn test_basic_regexp_count_01() {
     grep_test(&["-c", BRE], LINES_INPUT, "4\n", "", 0);
@@ -230,8 +251,8 @@ fn test_basic_regexp_line_number_03() {
         &["-n", BRE, INVALID_LINE_INPUT_FILE],
         "",
         "1:line_{1}\n3:p_line_{2}_s\n",
-        "tests/grep/invalid_line: Error reading line 2 (stream did not contain valid UTF-8)\n",
-        2,
+        "",
+        0,
     );
 }
 
@@ -290,7 +311,7 @@ fn test_basic_regexp_no_messages_with_error_05() {
         "",
         "line_{1}\np_line_{2}_s\n",
         "",
-        2,
+        0,
     );
 }
 
@@ -353,8 +374,8 @@ fn test_extended_regexp_03() {
         &["-E", ERE, INVALID_LINE_INPUT_FILE],
         "",
         "line_{1}\np_line_{2}_s\n",
-        "tests/grep/invalid_line: Error reading line 2 (stream did not contain valid UTF-8)\n",
-        2,
+        "",
+        0,
     );
 }
 
@@ -459,8 +480,8 @@ fn test_extended_regexp_line_number_03() {
         &["-E", "-n", ERE, INVALID_LINE_INPUT_FILE],
         "",
         "1:line_{1}\n3:p_line_{2}_s\n",
-        "tests/grep/invalid_line: Error reading line 2 (stream did not contain valid UTF-8)\n",
-        2,
+        "",
+        0,
     );
 }
 
@@ -525,7 +546,7 @@ fn test_extended_regexp_no_messages_with_error_05() {
         "",
         "line_{1}\np_line_{2}_s\n",
         "",
-        2,
+        0,
     );
 }
 
@@ -594,8 +615,8 @@ fn test_fixed_strings_03() {
         &["-F", FIXED, INVALID_LINE_INPUT_FILE],
         "",
         "line_{1}\np_line_{2}_s\n",
-        "tests/grep/invalid_line: Error reading line 2 (stream did not contain valid UTF-8)\n",
-        2,
+        "",
+        0,
     );
 }
 
@@ -706,8 +727,8 @@ fn test_fixed_strings_line_number_03() {
         &["-F", "-n", FIXED, INVALID_LINE_INPUT_FILE],
         "",
         "1:line_{1}\n3:p_line_{2}_s\n",
-        "tests/grep/invalid_line: Error reading line 2 (stream did not contain valid UTF-8)\n",
-        2,
+        "",
+        0,
     );
 }
 
@@ -772,7 +793,7 @@ fn test_fixed_strings_no_messages_with_error_05() {
         "",
         "line_{1}\np_line_{2}_s\n",
         "",
-        2,
+        0,
     );
 }
 
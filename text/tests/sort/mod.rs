@@ -8,7 +8,7 @@
 // SPDX-License-Identifier: MIT
 //
 
-use plib::testing::{run_test, TestPlan};
+use plib::testing::{run_test, run_test_u8, TestPlan, TestPlanU8};
 
 fn sort_test(
     args: &[&str],
@@ -613,3 +613,19 @@ fn test_files_sort_2() {
             "",
         );
 }
+
+#[test]
+fn test_invalid_bytes_unique() {
+    // lines that differ only in bytes that are not valid characters are
+    // not duplicates
+    for args in [&["-u", "-k1,1", "-k2,2"][..], &["-u", "-f"], &["-u", "-k1"]] {
+        run_test_u8(TestPlanU8 {
+            cmd: String::from("sort"),
+            args: args.iter().map(|s| String::from(*s)).collect(),
+            stdin_data: b"a\x81 x\na\x80 x\n".to_vec(),
+            expected_out: b"a\x80 x\na\x81 x\n".to_vec(),
+            expected_err: Vec::new(),
+            expected_exit_code: 0,
+        });
+    }
+}
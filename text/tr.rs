@@ -86,10 +86,10 @@ Only one string may be given when deleting without squeezing repeats."
 ///   if there is an error reading from standard input or processing the input string.
 ///
 fn tr(args: &Args) -> Result<(), Box<dyn std::error::Error>> {
-    let string1_operands = parsing::parse_string1_or_string2(&args.string1)?;
+    let string1_operands = parsing::parse_string1_or_string2(&args.string1, false)?;
 
     let string2_operands = match &args.string2 {
-        Some(st) => Some(parsing::parse_string1_or_string2(st)?),
+        Some(st) => Some(parsing::parse_string1_or_string2(st, true)?),
         None => None,
     };
 
@@ -310,7 +310,8 @@ fn main() -> Result<(), Box<dyn Error>> {
 }
 
 mod parsing {
-    use std::iter::{self, Peekable};
+    use plib::locale;
+    use std::iter::Peekable;
     use std::str::Chars;
 
     use crate::setup::FullChar;
@@ -401,7 +402,10 @@ mod parsing {
         }
     }
 
-    pub fn parse_string1_or_string2(string1_or_string2: &str) -> Result<Vec<Operand>, String> {
+    pub fn parse_string1_or_string2(
+        string1_or_string2: &str,
+        is_string2: bool,
+    ) -> Result<Vec<Operand>, String> {
         // The longest valid "[:class:]", "[=equiv=]", or "[x*n]" construct is a "[x*n]" construct
         // These are (seemingly) the shortest invalid "[x*n]" constructs (octal and decimal):
         // [a*010000000000000000000000]
@@ -456,6 +460,7 @@ mod parsing {
                         {
                             expand_character_class(
                                 &square_bracket_constructs_buffer,
+                                is_string2,
                                 &mut operand_vec,
                             )?;
 
@@ -930,6 +935,7 @@ mod parsing {
 
     fn expand_character_class(
         square_bracket_constructs_buffer: &[char],
+        is_string2: bool,
         operand_vec: &mut Vec<Operand>,
     ) -> Result<(), String> {
         // "[:class:]" construct
@@ -945,41 +951,42 @@ mod parsing {
         // Performance
         let class = into_iter.collect::<String>();
 
-        let char_vec = match class.as_str() {
-            "alnum" => ('0'..='9')
-                .chain('A'..='Z')
-                .chain('a'..='z')
-                .collect::<Vec<_>>(),
-            "alpha" => ('A'..='Z').chain('a'..='z').collect::<Vec<_>>(),
-            "digit" => ('0'..='9').collect::<Vec<_>>(),
-            "lower" => ('a'..='z').collect::<Vec<_>>(),
-            "upper" => ('A'..='Z').collect::<Vec<_>>(),
-            "space" => vec![' ', '\t', '\n', '\r', '\x0b', '\x0c'],
-            "blank" => vec![' ', '\t'],
-            "cntrl" => (0_u8..=31_u8)
-                .chain(iter::once(127_u8))
-                .map(char::from)
-                .collect::<Vec<_>>(),
-            "graph" => (33_u8..=126_u8).map(char::from).collect::<Vec<_>>(),
-            "print" => (32_u8..=126_u8).map(char::from).collect::<Vec<_>>(),
-            "punct" => (33_u8..=47_u8)
-                .chain(58_u8..=64_u8)
-                .chain(91_u8..=96_u8)
-                .chain(123_u8..=126_u8)
-                .map(char::from)
-                .collect::<Vec<_>>(),
-            "xdigit" => ('0'..='9')
-                .chain('A'..='F')
-                .chain('a'..='f')
-                .collect::<Vec<_>>(),
-            "" => {
+        let ctype = locale::ctype();
+
+        // In string2, "[:upper:]" and "[:lower:]" start with the case mappings of the
+        // other class, in its order, so that they pair up with it in string1
+        let case_pair = match class.as_str() {
+            "upper" if is_string2 => Some(("lower", "toupper")),
+            "lower" if is_string2 => Some(("upper", "tolower")),
+            _ => None,
+        };
+
+        let char_vec = match (class.as_str(), ctype.class_chars(&class)) {
+            ("", _) => {
                 return Err("input '[::]' is invalid: missing character class name".to_string());
             }
-            st => {
+            (st, None) => {
                 return Err(format!(
                     "input '[:{st}:]' is invalid: invalid character class '{st}'"
                 ))
             }
+            (_, Some(chars)) => match case_pair {
+                Some((other, map)) => {
+                    let mut mapped = ctype
+                        .class_chars(other)
+                        .unwrap_or_default()
+                        .into_iter()
+                        .map(|ch| ctype.towctrans(ch, map))
+                        .collect::<Vec<_>>();
+                    let rest = chars
+                        .into_iter()
+                        .filter(|ch| !mapped.contains(ch))
+                        .collect::<Vec<_>>();
+                    mapped.extend(rest);
+                    mapped
+                }
+                None => chars,
+            },
         };
 
         operand_vec.extend(char_vec.into_iter().map(|ch| {
@@ -7,12 +7,12 @@
 // SPDX-License-Identifier: MIT
 //
 
-use std::io::{self, BufRead};
+use std::io::{self, Write};
 use std::path::PathBuf;
 
 use clap::Parser;
 use gettextrs::{bind_textdomain_codeset, setlocale, textdomain, LocaleCategory};
-use plib::io::input_stream_opt;
+use plib::io::{input_stream_opt, ByteLines};
 use topological_sort::TopologicalSort;
 
 /// tsort - topological sort
@@ -25,34 +25,33 @@ struct Args {
 
 fn tsort_file(pathname: &Option<PathBuf>) -> io::Result<()> {
     let file = input_stream_opt(pathname)?;
-    let mut reader = io::BufReader::new(file);
+    let mut reader = ByteLines::new(file);
 
-    let mut ts = TopologicalSort::<String>::new();
-    let mut sv: Vec<String> = Vec::new();
+    let mut ts = TopologicalSort::<Vec<u8>>::new();
+    let mut sv: Vec<Vec<u8>> = Vec::new();
 
-    loop {
-        let mut buffer = String::new();
-        let n_read = reader.read_line(&mut buffer)?;
-        if n_read == 0 {
-            break;
-        }
-
-        for token in buffer.split_whitespace() {
-            sv.push(String::from(token));
+    while let Some(buffer) = reader.next_line()? {
+        for token in buffer
+            .split(|b| b.is_ascii_whitespace())
+            .filter(|t| !t.is_empty())
+        {
+            sv.push(token.to_vec());
 
             if sv.len() == 2 {
                 if sv[0] == sv[1] {
-                    ts.insert(String::from(&sv[0]));
+                    ts.insert(sv[0].clone());
                 } else {
-                    ts.add_dependency(String::from(&sv[0]), String::from(&sv[1]));
+                    ts.add_dependency(sv[0].clone(), sv[1].clone());
                 }
                 sv.clear();
             }
         }
     }
 
+    let mut stdout = io::stdout().lock();
     for s in ts {
-        println!("{}", s);
+        stdout.write_all(&s)?;
+        stdout.write_all(b"\n")?;
     }
 
     Ok(())
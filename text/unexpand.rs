@@ -1,6 +1,7 @@
 use clap::Parser;
 use gettextrs::{bind_textdomain_codeset, setlocale, textdomain, LocaleCategory};
-use std::io::{self, BufRead, Write};
+use plib::io::ByteLines;
+use std::io::{self, Read, Write};
 use std::path::PathBuf;
 
 #[derive(Parser)]
@@ -27,58 +28,48 @@ fn unexpand(args: &Args) -> Result<(), Box<dyn std::error::Error>> {
         Some(s) => parse_tablist(s)?,
         None => vec![8],
     };
-    let mut stdout = io::stdout();
-
-    if (args.files.len() == 1 && args.files[0] == PathBuf::from("-")) || args.files.is_empty() {
-        let reader = io::stdin();
-        let lines = io::BufReader::new(reader).lines();
-        for line in lines {
-            let line = line?;
-            let converted_line = if args.all_spaces && args.tablist.is_none() {
-                convert_all_blanks(&line, &tablist)
-            } else {
-                convert_leading_blanks(&line, &tablist)
-            };
-            writeln!(stdout, "{}", converted_line)?;
-        }
+    let mut stdout = io::stdout().lock();
+
+    let readers: Vec<Box<dyn Read>> = if (args.files.len() == 1
+        && args.files[0] == PathBuf::from("-"))
+        || args.files.is_empty()
+    {
+        vec![Box::new(io::stdin().lock())]
     } else {
+        let mut readers: Vec<Box<dyn Read>> = Vec::new();
         for file in &args.files {
-            let reader = io::BufReader::new(std::fs::File::open(file)?);
-            for line in reader.lines() {
-                let line = line?;
-                let converted_line = if args.all_spaces && args.tablist.is_none() {
-                    convert_all_blanks(&line, &tablist)
-                } else {
-                    convert_leading_blanks(&line, &tablist)
-                };
-                writeln!(stdout, "{}", converted_line)?;
-            }
+            readers.push(Box::new(std::fs::File::open(file)?));
         }
+        readers
     };
 
+    for reader in readers {
+        let mut lines = ByteLines::new(reader);
+        while let Some(line) = lines.next_line()? {
+            let mut converted_line = if args.all_spaces && args.tablist.is_none() {
+                convert_all_blanks(line, &tablist)
+            } else {
+                convert_leading_blanks(line, &tablist)
+            };
+            converted_line.push(b'\n');
+            stdout.write_all(&converted_line)?;
+        }
+    }
+
     Ok(())
 }
 
-fn convert_leading_blanks(line: &str, tablist: &[usize]) -> String {
-    let mut result = String::new();
-    let mut space_count = 0;
-    let mut chars = line.chars().peekable();
-
-    while let Some(&ch) = chars.peek() {
-        if ch == ' ' {
-            space_count += 1;
-            chars.next();
-        } else {
-            break;
-        }
-    }
+fn convert_leading_blanks(line: &[u8], tablist: &[usize]) -> Vec<u8> {
+    let mut result = Vec::new();
+    let mut space_count = line.iter().take_while(|&&ch| ch == b' ').count();
+    let rest = &line[space_count..];
 
     let mut col = 0;
     for &tabstop in tablist {
         while space_count > 0 && col < tabstop {
             let spaces_to_next_tabstop = tabstop - col;
             if space_count >= spaces_to_next_tabstop {
-                result.push('\t');
+                result.push(b'\t');
                 space_count -= spaces_to_next_tabstop;
                 col = tabstop;
             } else {
@@ -88,79 +79,60 @@ fn convert_leading_blanks(line: &str, tablist: &[usize]) -> String {
         }
     }
 
-    for _ in 0..space_count {
-        result.push(' ');
-    }
-
-    result.push_str(&chars.collect::<String>());
+    result.resize(result.len() + space_count, b' ');
+    result.extend_from_slice(rest);
     result
 }
 
-fn split_whitespaces(line: &str) -> Vec<String> {
+fn split_whitespaces(line: &[u8]) -> Vec<&[u8]> {
     let mut parts = Vec::new();
-    let mut current_part = String::new();
+    let mut part_start = 0;
     let mut in_word = false;
 
-    for c in line.chars() {
-        if c.is_whitespace() {
+    for (i, c) in line.iter().enumerate() {
+        if c.is_ascii_whitespace() {
             if in_word {
-                parts.push(current_part.clone());
-                current_part.clear();
+                parts.push(&line[part_start..i]);
+                part_start = i;
                 in_word = false;
             }
         } else if !in_word {
             in_word = true;
         }
-
-        current_part.push(c);
     }
 
-    if !current_part.is_empty() {
-        parts.push(current_part);
+    if part_start < line.len() {
+        parts.push(&line[part_start..]);
     }
     parts
 }
 
-fn convert_all_blanks(line: &str, tablist: &[usize]) -> String {
-    let mut result = String::new();
-
-    let split_parts: Vec<String> = split_whitespaces(line);
+fn convert_all_blanks(line: &[u8], tablist: &[usize]) -> Vec<u8> {
+    let mut result = Vec::new();
 
-    for part in &split_parts {
-        result.push_str(&convert_spaces_to_tabs(part, tablist[0]));
+    for part in split_whitespaces(line) {
+        result.extend_from_slice(&convert_spaces_to_tabs(part, tablist[0]));
     }
 
     result
 }
 
-fn convert_spaces_to_tabs(line: &str, tabstop: usize) -> String {
-    let mut result = String::new();
-    let mut space_count = 0;
-    let mut chars = line.chars().peekable();
-
-    while let Some(&ch) = chars.peek() {
-        if ch == ' ' {
-            space_count += 1;
-            chars.next();
-        } else {
-            break;
-        }
-    }
+fn convert_spaces_to_tabs(line: &[u8], tabstop: usize) -> Vec<u8> {
+    let mut result = Vec::new();
+    let mut space_count = line.iter().take_while(|&&ch| ch == b' ').count();
+    let rest = &line[space_count..];
 
     while space_count > 0 {
         if space_count >= tabstop {
-            result.push('\t');
+            result.push(b'\t');
             space_count -= tabstop;
         } else {
             break;
         }
     }
 
-    for _ in 0..space_count {
-        result.push(' ');
-    }
-
-    result.push_str(&chars.collect::<String>());
+    result.resize(result.len() + space_count, b' ');
+    result.extend_from_slice(rest);
     result
 }
 
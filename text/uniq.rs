@@ -1,7 +1,8 @@
 use clap::Parser;
 use gettextrs::{bind_textdomain_codeset, setlocale, textdomain, LocaleCategory};
+use plib::io::ByteLines;
 use std::fs::File;
-use std::io::{self, BufRead, BufReader, Write};
+use std::io::{self, BufWriter, Read, Write};
 use std::path::PathBuf;
 
 /// The uniq utility - filters out duplicate lines in a file
@@ -66,31 +67,30 @@ impl Args {
 ///
 /// Returns an error if there is an issue reading the input or writing the output.
 fn uniq(args: &Args) -> Result<(), Box<dyn std::error::Error>> {
-    let input: Box<dyn BufRead> = match &args.input_file {
+    let input: Box<dyn Read> = match &args.input_file {
         Some(file) => {
             if *file == PathBuf::from("-") {
-                Box::new(BufReader::new(io::stdin()))
+                Box::new(io::stdin().lock())
             } else {
-                Box::new(BufReader::new(File::open(file)?))
+                Box::new(File::open(file)?)
             }
         }
-        None => Box::new(BufReader::new(io::stdin())),
+        None => Box::new(io::stdin().lock()),
     };
+    let mut lines = ByteLines::new(input);
 
     let mut output: Box<dyn Write> = match &args.output_file {
-        Some(file) => Box::new(File::create(file)?),
-        None => Box::new(io::stdout()),
+        Some(file) => Box::new(BufWriter::new(File::create(file)?)),
+        None => Box::new(BufWriter::new(io::stdout().lock())),
     };
 
-    let lines: Vec<String> = input.lines().collect::<Result<_, _>>()?;
-
-    let mut last_line: Option<String> = None;
+    let mut last_line: Option<Vec<u8>> = None;
     let mut current_count = 0;
 
-    for line in &lines {
+    while let Some(line) = lines.next_line()? {
         let processed_line = process_line(line, args.fields, args.chars);
 
-        if let Some(last_line) = &last_line {
+        if let Some(last_line) = &mut last_line {
             let processed_last_line = process_line(last_line, args.fields, args.chars);
             if processed_line == processed_last_line {
                 current_count += 1;
@@ -98,14 +98,18 @@ fn uniq(args: &Args) -> Result<(), Box<dyn std::error::Error>> {
             } else {
                 output_result(&mut output, last_line, current_count, args)?;
             }
+            last_line.clear();
+            last_line.extend_from_slice(line);
+        } else {
+            last_line = Some(line.to_vec());
         }
-        last_line = Some(line.to_string());
         current_count = 1;
     }
 
     if let Some(last) = last_line {
         output_result(&mut output, &last, current_count, args)?;
     }
+    output.flush()?;
     Ok(())
 }
 
@@ -119,41 +123,38 @@ fn uniq(args: &Args) -> Result<(), Box<dyn std::error::Error>> {
 ///
 /// # Returns
 ///
-/// Returns the processed line as a `String`.
-fn process_line(line: &str, fields: Option<usize>, chars: Option<usize>) -> String {
-    let mut processed_line = line.to_string();
+/// Returns the part of the line used for comparison.
+fn process_line(line: &[u8], fields: Option<usize>, chars: Option<usize>) -> &[u8] {
+    let mut processed_line = line;
     if line.is_empty() {
-        return line.to_string();
+        return line;
     }
     if let Some(f) = fields {
-        if f == 0 {
-            processed_line = line.to_string();
-        } else {
+        if f != 0 {
             let mut field_count = 0;
 
-            let chars = line.chars().skip_while(|c| {
-                if c.is_whitespace() {
-                    if field_count >= f - 1 {
-                        return false;
+            let start = line
+                .iter()
+                .position(|c| {
+                    if c.is_ascii_whitespace() {
+                        if field_count >= f - 1 {
+                            return true;
+                        }
+                        field_count += 1;
                     }
-                    field_count += 1;
-                }
-                true
-            });
-            processed_line = chars.collect::<String>();
+                    false
+                })
+                .unwrap_or(line.len());
+            processed_line = &line[start..];
         }
     }
 
     if let Some(c) = chars {
-        if c < processed_line.len() {
-            processed_line = processed_line[c..].to_string();
-        } else {
-            processed_line.clear();
-        }
+        processed_line = processed_line.get(c..).unwrap_or_default();
     }
 
     if processed_line.is_empty() {
-        line.to_string()
+        line
     } else {
         processed_line
     }
@@ -173,18 +174,23 @@ fn process_line(line: &str, fields: Option<usize>, chars: Option<usize>) -> Stri
 /// Returns an error if there is an issue writing to the output.
 fn output_result<W: Write>(
     output: &mut W,
-    line: &str,
+    line: &[u8],
     count: usize,
     args: &Args,
 ) -> Result<(), io::Error> {
-    if args.count {
-        writeln!(output, "{} {}", count, line)?;
-    } else if args.repeated && count > 1 {
-        writeln!(output, "{}", line)?;
-    } else if args.unique && count == 1 {
-        writeln!(output, "{}", line)?;
-    } else if !args.repeated && !args.unique {
-        writeln!(output, "{}", line)?;
+    let print = if args.count {
+        write!(output, "{} ", count)?;
+        true
+    } else if args.repeated {
+        count > 1
+    } else if args.unique {
+        count == 1
+    } else {
+        true
+    };
+    if print {
+        output.write_all(line)?;
+        output.write_all(b"\n")?;
     }
     Ok(())
 }
@@ -13,6 +13,7 @@ use crate::{
     DATE_TIME_FORMAT_OLD_OR_FUTURE, DATE_TIME_FORMAT_RECENT,
 };
 use chrono::{DateTime, Local};
+use plib::locale;
 use std::{
     cmp::Ordering,
     ffi::{CStr, OsStr, OsString},
@@ -351,9 +352,10 @@ impl Entry {
         );
     }
 
-    /// Comparison key for sorting based on just the file name.
+    /// Comparison key for sorting based on just the file name, in the
+    /// collating sequence of the current locale.
     pub fn sorting_cmp_lexicographic(&self, other: &Self) -> Ordering {
-        self.file_name_raw.cmp(&other.file_name_raw)
+        collate_names(&self.file_name_raw, &other.file_name_raw)
     }
 
     // Returns (is_device, size, file_name). The `bool` is to have devices
@@ -377,7 +379,7 @@ impl Entry {
         match self_sorting_key.0.cmp(&other_sorting_key.0) {
             Ordering::Equal => {
                 match self_sorting_key.1.cmp(&other_sorting_key.1) {
-                    Ordering::Equal => self_sorting_key.2.cmp(other_sorting_key.2),
+                    Ordering::Equal => collate_names(self_sorting_key.2, other_sorting_key.2),
                     r => r.reverse(), // Default is from largest file size to smallest
                 }
             }
@@ -391,7 +393,7 @@ impl Entry {
     /// The kind of time is dependent on the flags -t, -c, -u.
     pub fn sorting_cmp_time(&self, other: &Self) -> Ordering {
         match self.time.cmp(&other.time) {
-            Ordering::Equal => self.file_name_raw.cmp(&other.file_name_raw),
+            Ordering::Equal => self.sorting_cmp_lexicographic(other),
             r => r.reverse(), // Default is newest to oldest
         }
     }
@@ -464,6 +466,12 @@ impl Entry {
     }
 }
 
+/// Compare file names in the collating sequence of the current locale, using
+/// the bytes of the names to order names that collate equally.
+fn collate_names(a: &OsStr, b: &OsStr) -> Ordering {
+    locale::strcoll(a.as_bytes(), b.as_bytes()).then_with(|| a.cmp(b))
+}
+
 /// Used for padding in long format
 #[derive(Default)]
 pub struct LongFormatPadding {
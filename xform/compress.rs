@@ -12,8 +12,8 @@ use gettextrs::{bind_textdomain_codeset, gettext, setlocale, textdomain, LocaleC
 use plib::io::input_stream;
 use plib::lzw::UnixLZWWriter;
 use std::fs::{self, File};
-use std::io::{self, Write};
-use std::path::{Path, PathBuf};
+use std::io::{self, BufWriter, Read, Write};
+use std::path::PathBuf;
 
 const NAME_MAX: usize = 255;
 
@@ -41,6 +41,24 @@ struct Args {
     files: Vec<PathBuf>,
 }
 
+/// A writer that counts the bytes written through it.
+struct CountingWriter<W: Write> {
+    inner: W,
+    count: u64,
+}
+
+impl<W: Write> Write for CountingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = self.inner.write(buf)?;
+        self.count += n as u64;
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
 fn prompt_user(prompt: &str) -> bool {
     eprint!("compress: {} ", prompt);
     let mut response = String::new();
@@ -51,66 +69,84 @@ fn prompt_user(prompt: &str) -> bool {
 fn compress_file(args: &Args, pathname: &PathBuf) -> io::Result<i32> {
     let mut file = input_stream(pathname, false)?;
 
-    let mut encoder = UnixLZWWriter::new(args.bits);
+    let fname = match pathname.file_name() {
+        Some(name) if !args.stdout => format!("{}.Z", name.to_string_lossy()),
+        // standard input, or -c
+        _ => String::new(),
+    };
+
+    // If "on adding .Z" the name exceeds the limit of NAME_MAX, then we output on the stdout
+    if fname.is_empty() || fname.len() > NAME_MAX {
+        let stdout = CountingWriter {
+            inner: io::stdout().lock(),
+            count: 0,
+        };
+        let mut encoder = UnixLZWWriter::new(stdout, args.bits);
+        io::copy(&mut file, &mut encoder)?;
+        let inp_size = encoder.bytes_in();
+        let mut stdout = encoder.finish()?;
+        stdout.flush()?;
+
+        // the output is written all the same, but as for files, it not
+        // being smaller than the input is reported without '-f'
+        if stdout.count >= inp_size && !args.force {
+            return Ok(2);
+        }
+        return Ok(0);
+    }
 
-    let mut inp_buf = Vec::new();
-    file.read_to_end(&mut inp_buf)?;
-    let inp_buf_size = inp_buf.len();
+    let mut new_file = pathname.clone();
+    new_file.set_file_name(&fname);
+    if new_file.exists() && !args.force {
+        let is_affirm = prompt_user(&gettext!(
+            "Do you want to overwrite {} (y)es or (n)o?",
+            new_file.display()
+        ));
+
+        if !is_affirm {
+            println!("{} not overwritten", new_file.display());
+            return Ok(1);
+        }
+    }
 
-    let mut out_buf = encoder.write(&inp_buf)?;
-    out_buf.extend_from_slice(&encoder.close()?);
-    let out_buf_size = out_buf.len();
+    let compress = |file: &mut dyn Read| -> io::Result<u64> {
+        let mut encoder = UnixLZWWriter::new(BufWriter::new(File::create(&new_file)?), args.bits);
+        io::copy(file, &mut encoder)?;
+        let inp_size = encoder.bytes_in();
+        encoder.finish()?.flush()?;
+        Ok(inp_size)
+    };
+    let inp_size = match compress(&mut file) {
+        Ok(n) => n,
+        Err(e) => {
+            let _ = fs::remove_file(&new_file);
+            return Err(e);
+        }
+    };
+    let out_size = fs::metadata(&new_file)?.len();
 
-    // check if the output buffer size is greater than input buffer size
-    // this can indicate that the compressed file is greater than the input file
+    // check if the compressed file is larger than the input file
     //
     // in such case using '-f' works, i.e "force", which means we don't care if size
     // isn't reduced, just compress it
-    if (out_buf_size < inp_buf_size) || args.force {
-        if args.stdout {
-            io::stdout().write_all(&out_buf)?;
-        } else {
-            let fname = format!("{}.Z", pathname.file_name().unwrap().to_str().unwrap());
-            // If "on adding .Z" the name exceeds the limit of NAME_MAX, then we output on the stdout
-            if fname.len() > NAME_MAX {
-                io::stdout().write_all(&out_buf)?;
-            } else {
-                let path = Path::new(&fname);
-                if path.exists() && !args.force {
-                    let is_affirm = prompt_user(&gettext!(
-                        "Do you want to overwrite {} (y)es or (n)o?",
-                        fname
-                    ));
-
-                    if !is_affirm {
-                        println!("{fname} not overwritten");
-                        return Ok(1);
-                    }
-                }
-
-                let mut new_file = pathname.clone();
-                new_file.set_file_name(&fname);
-                let mut f = File::create(&new_file)?;
-
-                fs::remove_file(pathname)?;
-                f.write_all(&out_buf)?;
-
-                if args.verbose {
-                    println!(
-                        "{}: -- replaced with {} Compression: {:.1}%",
-                        pathname.display(),
-                        new_file.display(),
-                        100_f32 - (out_buf_size as f32 / inp_buf_size as f32) * 100_f32
-                    );
-                }
-            }
-        }
-    } else {
+    if out_size >= inp_size && !args.force {
         // error status code when file was not compressed as they would have increased in size and (-f
         // was not provided)
+        fs::remove_file(&new_file)?;
         return Ok(2);
     }
 
+    fs::remove_file(pathname)?;
+
+    if args.verbose {
+        println!(
+            "{}: -- replaced with {} Compression: {:.1}%",
+            pathname.display(),
+            new_file.display(),
+            100_f32 - (out_size as f32 / inp_size as f32) * 100_f32
+        );
+    }
+
     Ok(0)
 }
 
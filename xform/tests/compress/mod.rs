@@ -7,7 +7,7 @@
 // SPDX-License-Identifier: MIT
 //
 
-use plib::testing::{run_test, TestPlan};
+use plib::testing::{run_test, run_test_base, run_test_u8, TestPlan, TestPlanU8};
 use std::{
     fs::{remove_file, File},
     io::Read,
@@ -119,3 +119,67 @@ fn compression_compress_file() {
         remove_file(&compressed_file_path).unwrap();
     }
 }
+
+#[test]
+fn compression_stdout_stdin_roundtrip() {
+    use std::env;
+    use std::path::PathBuf;
+
+    let cargo_manifest_dir = PathBuf::from(env::var("CARGO_MANIFEST_DIR").unwrap());
+    let source_file = cargo_manifest_dir.join("tests/compress/lorem_ipsum.txt");
+
+    let mut original = Vec::new();
+    File::open(&source_file)
+        .unwrap()
+        .read_to_end(&mut original)
+        .unwrap();
+
+    // compress -c leaves the input file alone and writes to standard output
+    let output = run_test_base(
+        "compress",
+        &vec![
+            String::from("-c"),
+            source_file.to_str().unwrap().to_string(),
+        ],
+        b"",
+    );
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(&output.stdout[..2], &[0x1F, 0x9D]);
+    assert!(output.stdout.len() < original.len());
+    assert!(source_file.exists());
+
+    run_test_u8(TestPlanU8 {
+        cmd: String::from("uncompress"),
+        args: Vec::new(),
+        stdin_data: output.stdout,
+        expected_out: original,
+        expected_err: Vec::new(),
+        expected_exit_code: 0,
+    });
+}
+
+#[test]
+fn compression_stdout_not_smaller() {
+    // too short to shrink: written all the same, with status 2 unless forced
+    let output = run_test_base("compress", &vec![String::from("-c")], b"abc");
+    assert_eq!(output.status.code(), Some(2));
+    assert_eq!(&output.stdout[..2], &[0x1F, 0x9D]);
+    assert!(output.stdout.len() >= 3);
+
+    let forced = run_test_base(
+        "compress",
+        &vec![String::from("-c"), String::from("-f")],
+        b"abc",
+    );
+    assert_eq!(forced.status.code(), Some(0));
+    assert_eq!(forced.stdout, output.stdout);
+
+    run_test_u8(TestPlanU8 {
+        cmd: String::from("uncompress"),
+        args: Vec::new(),
+        stdin_data: output.stdout,
+        expected_out: b"abc".to_vec(),
+        expected_err: Vec::new(),
+        expected_exit_code: 0,
+    });
+}
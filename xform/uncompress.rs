@@ -42,16 +42,9 @@ fn uncompress_file(pathname: &PathBuf) -> io::Result<()> {
     let file = input_stream(pathname, false)?;
     let mut decoder = UnixLZWReader::new(file);
 
-    loop {
-        let buf = decoder.read()?;
-        if buf.is_empty() {
-            break;
-        }
-
-        io::stdout().write_all(&buf)?;
-    }
-
-    Ok(())
+    let mut stdout = io::stdout().lock();
+    io::copy(&mut decoder, &mut stdout)?;
+    stdout.flush()
 }
 
 fn prog_is_zcat() -> bool {